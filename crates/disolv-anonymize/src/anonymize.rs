@@ -0,0 +1,301 @@
+use crate::remap::{CoordTransform, IdRemap};
+use arrow::array::{ArrayRef, AsArray, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Float64Type, SchemaRef, UInt64Type};
+use disolv_input::columns::{COORD_X, COORD_Y};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn read_batches(file_path: &Path) -> (SchemaRef, Vec<RecordBatch>) {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Error reading file {}: {}", file_path.display(), e),
+    };
+    let builder = match ParquetRecordBatchReaderBuilder::try_new(file) {
+        Ok(builder) => builder,
+        Err(e) => panic!("Error building parquet reader: {}", e),
+    };
+    let schema = builder.schema().clone();
+    let reader = match builder.build() {
+        Ok(reader) => reader,
+        Err(e) => panic!("Error building reader: {}", e),
+    };
+    let batches = reader
+        .map(|batch| batch.unwrap_or_else(|e| panic!("Error reading record batch: {}", e)))
+        .collect();
+    (schema, batches)
+}
+
+fn has_coordinates(schema: &SchemaRef) -> bool {
+    [COORD_X, COORD_Y].iter().all(|name| {
+        schema
+            .field_with_name(name)
+            .is_ok_and(|field| field.data_type() == &DataType::Float64)
+    })
+}
+
+fn coordinates(record_batch: &RecordBatch, name: &str) -> Float64Array {
+    match record_batch.column_by_name(name) {
+        Some(column) => column.as_primitive::<Float64Type>().clone(),
+        None => panic!("Failed to read column {}", name),
+    }
+}
+
+/// Smallest x and y coordinates in the files that have coordinates, which become the origin
+/// when no offset is configured.
+pub fn smallest_coordinates(file_paths: &[&Path]) -> Option<[f64; 2]> {
+    let mut smallest: Option<[f64; 2]> = None;
+    for file_path in file_paths {
+        let (schema, batches) = read_batches(file_path);
+        if !has_coordinates(&schema) {
+            continue;
+        }
+        for record_batch in batches.iter() {
+            let x_min = arrow::compute::min(&coordinates(record_batch, COORD_X));
+            let y_min = arrow::compute::min(&coordinates(record_batch, COORD_Y));
+            if let (Some(x_min), Some(y_min)) = (x_min, y_min) {
+                smallest = Some(match smallest {
+                    Some([x, y]) => [x.min(x_min), y.min(y_min)],
+                    None => [x_min, y_min],
+                });
+            }
+        }
+    }
+    smallest
+}
+
+fn anonymize_batch(
+    record_batch: &RecordBatch,
+    id_columns: &[String],
+    remap: &mut IdRemap,
+    transform: &CoordTransform,
+) -> RecordBatch {
+    let schema = record_batch.schema();
+    let transformed: Option<(ArrayRef, ArrayRef)> = match has_coordinates(&schema) {
+        true => {
+            let (x, y): (Vec<Option<f64>>, Vec<Option<f64>>) = coordinates(record_batch, COORD_X)
+                .iter()
+                .zip(coordinates(record_batch, COORD_Y).iter())
+                .map(|pair| match pair {
+                    (Some(x), Some(y)) => {
+                        let (x, y) = transform.apply(x, y);
+                        (Some(x), Some(y))
+                    }
+                    _ => (None, None),
+                })
+                .unzip();
+            Some((
+                Arc::new(Float64Array::from(x)),
+                Arc::new(Float64Array::from(y)),
+            ))
+        }
+        false => None,
+    };
+
+    let columns: Vec<ArrayRef> = schema
+        .fields()
+        .iter()
+        .zip(record_batch.columns())
+        .map(|(field, column)| match field.name().as_str() {
+            name if id_columns.iter().any(|id_column| id_column == name) => {
+                if field.data_type() != &DataType::UInt64 {
+                    panic!("Id column {} must hold unsigned 64-bit integers.", name);
+                }
+                let ids: UInt64Array = column
+                    .as_primitive::<UInt64Type>()
+                    .iter()
+                    .map(|id| id.map(|id| remap.remap(id)))
+                    .collect();
+                Arc::new(ids) as ArrayRef
+            }
+            COORD_X if transformed.is_some() => transformed.as_ref().unwrap().0.clone(),
+            COORD_Y if transformed.is_some() => transformed.as_ref().unwrap().1.clone(),
+            _ => column.clone(),
+        })
+        .collect();
+    match RecordBatch::try_new(schema.clone(), columns) {
+        Ok(record_batch) => record_batch,
+        Err(e) => panic!("Failed to rebuild the record batch: {}", e),
+    }
+}
+
+/// Whether the column looks like it holds agent ids, so that it is not copied unchanged by
+/// mistake.
+fn holds_ids(name: &str, data_type: &DataType) -> bool {
+    data_type == &DataType::UInt64 && (name.ends_with("_id") || name.ends_with("_agent"))
+}
+
+/// Rewrites the file with the remapped ids and the transformed coordinates. All the other
+/// columns are copied unchanged. Returns the number of rows written. Files with a column that
+/// looks like it holds agent ids but is not one of the `id_columns` are rejected, so that the
+/// original ids do not leak.
+pub fn anonymize_file(
+    input_file: &Path,
+    output_file: &Path,
+    id_columns: &[String],
+    remap: &mut IdRemap,
+    transform: &CoordTransform,
+) -> usize {
+    let (schema, batches) = read_batches(input_file);
+    if let Some(field) = schema.fields().iter().find(|field| {
+        holds_ids(field.name(), field.data_type())
+            && !id_columns.iter().any(|id_column| id_column == field.name())
+    }) {
+        panic!(
+            "Column {} of {} looks like it holds agent ids but is not an id column.",
+            field.name(),
+            input_file.display()
+        );
+    }
+    if let Some(parent) = output_file.parent() {
+        std::fs::create_dir_all(parent)
+            .unwrap_or_else(|e| panic!("Failed to create {}: {}", parent.display(), e));
+    }
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = match File::create(output_file) {
+        Ok(file) => file,
+        Err(e) => panic!("Failed to create file {}: {}", output_file.display(), e),
+    };
+    let mut writer = match ArrowWriter::try_new(file, schema, Some(props)) {
+        Ok(writer) => writer,
+        Err(e) => panic!("Failed to create parquet writer: {}", e),
+    };
+    let mut rows = 0;
+    for record_batch in batches.iter() {
+        let record_batch = anonymize_batch(record_batch, id_columns, remap, transform);
+        rows += record_batch.num_rows();
+        writer
+            .write(&record_batch)
+            .expect("Failed to write anonymized batch to file");
+    }
+    writer.close().expect("Failed to close anonymized file");
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AGENT_ID_COLUMNS;
+    use arrow::array::{Float32Array, UInt32Array};
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("disolv_anonymize_{}_{}", std::process::id(), name))
+    }
+
+    fn id_columns() -> Vec<String> {
+        AGENT_ID_COLUMNS
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// A table with the columns of the transfers written by the simulator.
+    fn write_tx_data(tx_file: &Path) {
+        let record_batch = RecordBatch::try_from_iter(vec![
+            (
+                "time_step",
+                Arc::new(UInt64Array::from(vec![100, 100, 200])) as ArrayRef,
+            ),
+            (
+                "agent_id",
+                Arc::new(UInt64Array::from(vec![1, 2, 1])) as ArrayRef,
+            ),
+            (
+                "selected_agent",
+                Arc::new(UInt64Array::from(vec![100, 100, 2])) as ArrayRef,
+            ),
+            (
+                "distance",
+                Arc::new(Float32Array::from(vec![10.0, 20.0, 5.0])) as ArrayRef,
+            ),
+            (
+                "tx_status",
+                Arc::new(UInt32Array::from(vec![0, 0, 1])) as ArrayRef,
+            ),
+            (
+                "payload_size",
+                Arc::new(UInt64Array::from(vec![300, 300, 600])) as ArrayRef,
+            ),
+        ])
+        .expect("Failed to build the transfers");
+        let file = File::create(tx_file).expect("Failed to create the transfers file");
+        let mut writer =
+            ArrowWriter::try_new(file, record_batch.schema(), None).expect("Writer is created");
+        writer.write(&record_batch).expect("Transfers are written");
+        writer.close().expect("Transfers file is closed");
+    }
+
+    fn column(record_batch: &RecordBatch, name: &str) -> Vec<u64> {
+        record_batch
+            .column_by_name(name)
+            .expect("Column is missing")
+            .as_primitive::<UInt64Type>()
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_tx_data_ids_are_remapped_consistently() {
+        let (input_file, output_file) = (temp_file("tx_in.parquet"), temp_file("tx_out.parquet"));
+        write_tx_data(&input_file);
+        let mut remap = IdRemap::new("secret");
+        let transform = CoordTransform::new([0.0, 0.0], 0.0);
+        let rows = anonymize_file(
+            &input_file,
+            &output_file,
+            &id_columns(),
+            &mut remap,
+            &transform,
+        );
+        let (_, batches) = read_batches(&output_file);
+        std::fs::remove_file(&input_file).expect("Input file is removed");
+        std::fs::remove_file(&output_file).expect("Output file is removed");
+
+        assert_eq!(rows, 3);
+        let record_batch = &batches[0];
+        let (agents, selected) = (
+            column(record_batch, "agent_id"),
+            column(record_batch, "selected_agent"),
+        );
+        let mut check = IdRemap::new("secret");
+        assert_eq!(agents, vec![check.remap(1), check.remap(2), check.remap(1)]);
+        assert_eq!(
+            selected,
+            vec![check.remap(100), check.remap(100), check.remap(2)]
+        );
+        // The agent 2 has the same id as a sender and as a target.
+        assert_eq!(agents[1], selected[2]);
+        assert_eq!(column(record_batch, "time_step"), vec![100, 100, 200]);
+        assert_eq!(column(record_batch, "payload_size"), vec![300, 300, 600]);
+        assert_eq!(remap.agent_count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "looks like it holds agent ids")]
+    fn test_unmapped_id_columns_are_rejected() {
+        let (input_file, output_file) =
+            (temp_file("tx_leak.parquet"), temp_file("tx_none.parquet"));
+        write_tx_data(&input_file);
+        let id_columns = vec!["agent_id".to_string()];
+        let result = std::panic::catch_unwind(|| {
+            anonymize_file(
+                &input_file,
+                &output_file,
+                &id_columns,
+                &mut IdRemap::new("secret"),
+                &CoordTransform::new([0.0, 0.0], 0.0),
+            )
+        });
+        std::fs::remove_file(&input_file).expect("Input file is removed");
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
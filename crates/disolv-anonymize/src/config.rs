@@ -0,0 +1,53 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// Columns of the traces and the results of the simulator that hold agent ids.
+pub const AGENT_ID_COLUMNS: [&str; 6] = [
+    "agent_id",
+    "target_id",
+    "selected_agent",
+    "source_id",
+    "station_id",
+    "vehicle_id",
+];
+
+/// Configuration of the anonymization of traces and results before they are shared.
+///
+/// Every parquet file in `files` is rewritten to the same relative path under `output_path`.
+/// The agent ids in the `id_columns`, all the agent id columns the simulator writes by default,
+/// are remapped with a hash keyed by the secret `key`, so that an agent gets the same id in all
+/// the files. The
+/// coordinates are shifted by the `offset`, the smallest coordinates in the files by default,
+/// and rotated counter-clockwise by `rotation` degrees around the origin.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Config {
+    pub key: String,
+    pub output_path: String,
+    pub files: Vec<String>,
+    pub id_columns: Option<Vec<String>>,
+    pub offset: Option<[f64; 2]>,
+    pub rotation: Option<f64>,
+}
+
+impl Config {
+    pub fn id_columns(&self) -> Vec<String> {
+        match self.id_columns {
+            Some(ref id_columns) => id_columns.clone(),
+            None => AGENT_ID_COLUMNS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+pub fn read_config(file_path: &Path) -> Config {
+    let input_toml = match std::fs::read_to_string(file_path) {
+        Ok(parsed_string) => parsed_string,
+        Err(e) => panic!("Failed to read {}: {}", file_path.display(), e),
+    };
+    match toml::from_str(&input_toml) {
+        Ok(config) => config,
+        Err(e) => panic!("Invalid anonymization configuration: {}", e),
+    }
+}
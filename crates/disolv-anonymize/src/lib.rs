@@ -0,0 +1,3 @@
+pub mod anonymize;
+pub mod config;
+pub mod remap;
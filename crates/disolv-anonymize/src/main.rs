@@ -0,0 +1,56 @@
+use clap::Parser;
+use disolv_anonymize::anonymize::{anonymize_file, smallest_coordinates};
+use disolv_anonymize::config::read_config;
+use disolv_anonymize::remap::{CoordTransform, IdRemap};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 'c', long, value_name = "Anonymization Configuration File")]
+    config: String,
+}
+
+fn main() {
+    let config_file = PathBuf::from(CliArgs::parse().config);
+    let start = std::time::Instant::now();
+    let config = read_config(&config_file);
+    let config_path = config_file.parent().map(PathBuf::from).unwrap_or_default();
+
+    let input_files: Vec<PathBuf> = config
+        .files
+        .iter()
+        .map(|file| config_path.join(file))
+        .collect();
+    let offset = match config.offset {
+        Some(offset) => offset,
+        None => {
+            let files: Vec<&Path> = input_files.iter().map(PathBuf::as_path).collect();
+            smallest_coordinates(&files).unwrap_or([0.0, 0.0])
+        }
+    };
+    let transform = CoordTransform::new(offset, config.rotation.unwrap_or(0.0));
+    let id_columns = config.id_columns();
+    let mut remap = IdRemap::new(&config.key);
+
+    let output_path = config_path.join(&config.output_path);
+    for (file, input_file) in config.files.iter().zip(input_files.iter()) {
+        let output_file = output_path.join(file);
+        let rows = anonymize_file(
+            input_file,
+            &output_file,
+            &id_columns,
+            &mut remap,
+            &transform,
+        );
+        println!("Anonymized {} rows into {}.", rows, output_file.display());
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "Anonymized {} agents in {} files in {} ms.",
+        remap.agent_count(),
+        config.files.len(),
+        elapsed.as_millis()
+    );
+}
@@ -0,0 +1,94 @@
+use disolv_core::hashbrown::HashMap;
+use siphasher::sip::SipHasher24;
+use siphasher::sip128::SipHasher24 as KeyHasher;
+
+/// Ids are kept below 2^53 so that tools reading them as floats do not round them.
+const ID_MASK: u64 = (1 << 53) - 1;
+
+/// Remaps agent ids with SipHash keyed by a secret. The same key gives the same ids in every
+/// file and every run, while the original ids cannot be recovered without it.
+#[derive(Clone, Debug)]
+pub struct IdRemap {
+    hasher: SipHasher24,
+    seen: HashMap<u64, u64>,
+}
+
+impl IdRemap {
+    pub fn new(key: &str) -> Self {
+        let key = KeyHasher::new().hash(key.as_bytes()).as_bytes();
+        Self {
+            hasher: SipHasher24::new_with_key(&key),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Remaps the id and fails if it collides with the remapped id of another agent, which
+    /// would merge the two agents in the shared files.
+    pub fn remap(&mut self, id: u64) -> u64 {
+        let remapped = self.hasher.hash(&id.to_le_bytes()) & ID_MASK;
+        match self.seen.insert(remapped, id) {
+            Some(other) if other != id => panic!(
+                "Agents {} and {} are remapped to the same id, use another key.",
+                other, id
+            ),
+            _ => remapped,
+        }
+    }
+
+    pub fn agent_count(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+/// Shifts the coordinates by an offset and rotates them around the origin.
+#[derive(Clone, Copy, Debug)]
+pub struct CoordTransform {
+    offset: [f64; 2],
+    cos: f64,
+    sin: f64,
+}
+
+impl CoordTransform {
+    pub fn new(offset: [f64; 2], rotation: f64) -> Self {
+        let radians = rotation.to_radians();
+        Self {
+            offset,
+            cos: radians.cos(),
+            sin: radians.sin(),
+        }
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = (x - self.offset[0], y - self.offset[1]);
+        (x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_is_keyed_and_stable() {
+        let mut remap = IdRemap::new("secret");
+        let first = remap.remap(42);
+        assert_eq!(remap.remap(42), first);
+        assert_ne!(remap.remap(43), first);
+        assert_eq!(IdRemap::new("secret").remap(42), first);
+        assert_ne!(IdRemap::new("another").remap(42), first);
+        assert!(first <= ID_MASK);
+        assert_eq!(remap.agent_count(), 2);
+    }
+
+    #[test]
+    fn test_transform_keeps_distances() {
+        let transform = CoordTransform::new([100.0, 50.0], 90.0);
+        let (x, y) = transform.apply(110.0, 50.0);
+        assert!(x.abs() < 1e-9);
+        assert!((y - 10.0).abs() < 1e-9);
+
+        let a = transform.apply(130.0, 90.0);
+        let b = transform.apply(100.0, 50.0);
+        assert!(((a.0 - b.0).hypot(a.1 - b.1) - 50.0).abs() < 1e-9);
+    }
+}
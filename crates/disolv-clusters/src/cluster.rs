@@ -0,0 +1,201 @@
+use crate::features::Features;
+
+/// Cluster of every agent, in the order of the features, and the centroid of every cluster in
+/// the units of the features. Clusters are numbered by the speed of their centroid, from the
+/// slowest to the fastest.
+#[derive(Clone, Debug)]
+pub struct Clustering {
+    pub labels: Vec<usize>,
+    pub centroids: Vec<[f64; 3]>,
+}
+
+/// Features scaled to zero mean and unit deviation, so that all of them weigh the same.
+fn standardize(features: &[Features]) -> Vec<[f64; 3]> {
+    let count = features.len().max(1) as f64;
+    let mut mean = [0.0; 3];
+    features.iter().for_each(|agent| {
+        for (sum, value) in mean.iter_mut().zip(agent.values()) {
+            *sum += value / count;
+        }
+    });
+    let mut deviation = [0.0; 3];
+    features.iter().for_each(|agent| {
+        for (idx, value) in agent.values().iter().enumerate() {
+            deviation[idx] += (value - mean[idx]).powi(2) / count;
+        }
+    });
+    let deviation = deviation.map(|variance: f64| match variance > 0.0 {
+        true => variance.sqrt(),
+        false => 1.0,
+    });
+    features
+        .iter()
+        .map(|agent| {
+            let values = agent.values();
+            [0, 1, 2].map(|idx| (values[idx] - mean[idx]) / deviation[idx])
+        })
+        .collect()
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| (a - b).powi(2)).sum()
+}
+
+fn nearest(point: &[f64; 3], centroids: &[[f64; 3]]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(idx, centroid)| (idx, distance(point, centroid)))
+        .fold((0, f64::INFINITY), |best, next| match next.1 < best.1 {
+            true => next,
+            false => best,
+        })
+        .0
+}
+
+/// Seeds the clusters at the slowest agent and then at the agents farthest from the seeds
+/// chosen so far, which keeps the clustering deterministic.
+fn seed(points: &[[f64; 3]], features: &[Features], count: usize) -> Vec<[f64; 3]> {
+    let slowest = features.iter().enumerate().fold(0, |best, (idx, agent)| {
+        match agent.speed < features[best].speed {
+            true => idx,
+            false => best,
+        }
+    });
+    let mut centroids = vec![points[slowest]];
+    while centroids.len() < count {
+        let farthest = points
+            .iter()
+            .enumerate()
+            .map(|(idx, point)| (idx, distance(point, &centroids[nearest(point, &centroids)])))
+            .fold((0, -1.0), |best, next| match next.1 > best.1 {
+                true => next,
+                false => best,
+            })
+            .0;
+        centroids.push(points[farthest]);
+    }
+    centroids
+}
+
+/// Groups the agents into the clusters with k-means on their standardized features, for at most
+/// the given number of passes.
+pub fn cluster(features: &[Features], count: usize, iterations: u32) -> Clustering {
+    if count == 0 || count > features.len() {
+        panic!(
+            "{} clusters are requested for {} agents.",
+            count,
+            features.len()
+        );
+    }
+    let points = standardize(features);
+    let mut centroids = seed(&points, features, count);
+    let mut labels: Vec<usize> = points
+        .iter()
+        .map(|point| nearest(point, &centroids))
+        .collect();
+    for _ in 0..iterations {
+        centroids = (0..count)
+            .map(|cluster| {
+                let members: Vec<&[f64; 3]> = points
+                    .iter()
+                    .zip(labels.iter())
+                    .filter(|(_, label)| **label == cluster)
+                    .map(|(point, _)| point)
+                    .collect();
+                match members.is_empty() {
+                    true => centroids[cluster],
+                    false => [0, 1, 2].map(|idx| {
+                        members.iter().map(|point| point[idx]).sum::<f64>() / members.len() as f64
+                    }),
+                }
+            })
+            .collect();
+        let next: Vec<usize> = points
+            .iter()
+            .map(|point| nearest(point, &centroids))
+            .collect();
+        if next == labels {
+            break;
+        }
+        labels = next;
+    }
+    order_by_speed(features, labels, count)
+}
+
+/// Numbers the clusters by the mean speed of their agents and computes their centroids in the
+/// units of the features.
+fn order_by_speed(features: &[Features], labels: Vec<usize>, count: usize) -> Clustering {
+    let mut centroids = vec![[0.0; 3]; count];
+    let mut sizes = vec![0.0; count];
+    for (agent, label) in features.iter().zip(labels.iter()) {
+        for (sum, value) in centroids[*label].iter_mut().zip(agent.values()) {
+            *sum += value;
+        }
+        sizes[*label] += 1.0;
+    }
+    for (centroid, size) in centroids.iter_mut().zip(sizes.iter()) {
+        if *size > 0.0 {
+            centroid.iter_mut().for_each(|value| *value /= size);
+        }
+    }
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|a, b| centroids[*a][0].total_cmp(&centroids[*b][0]));
+    let mut rank = vec![0; count];
+    order
+        .iter()
+        .enumerate()
+        .for_each(|(new, old)| rank[*old] = new);
+    Clustering {
+        labels: labels.iter().map(|label| rank[*label]).collect(),
+        centroids: order.iter().map(|old| centroids[*old]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::features_of;
+    use disolv_core::agent::AgentId;
+
+    /// Positions every second of an agent driving along x at the speed, or parked when it is
+    /// zero.
+    fn trace(start: f64, speed: f64) -> Vec<(u64, [f64; 2])> {
+        (0..10u64)
+            .map(|second| (second * 1000, [start + speed * second as f64, 0.0]))
+            .collect()
+    }
+
+    #[test]
+    fn test_features_of_a_parked_agent() {
+        let features = features_of(AgentId::from(1), &trace(120.0, 0.0), 0.5, 50.0);
+        assert_eq!(features.speed, 0.0);
+        assert_eq!(features.gyration, 0.0);
+        assert_eq!(features.dwell_areas, 1.0);
+
+        let features = features_of(AgentId::from(2), &trace(0.0, 10.0), 0.5, 50.0);
+        assert!((features.speed - 10.0).abs() < 1e-9);
+        assert_eq!(features.dwell_areas, 0.0);
+    }
+
+    #[test]
+    fn test_clusters_are_ordered_by_speed() {
+        let speeds = [30.0, 0.0, 29.0, 1.0, 0.0, 31.0];
+        let features: Vec<Features> = speeds
+            .iter()
+            .enumerate()
+            .map(|(idx, speed)| {
+                features_of(
+                    AgentId::from(idx as u64),
+                    &trace(100.0 * idx as f64, *speed),
+                    0.5,
+                    50.0,
+                )
+            })
+            .collect();
+        let clustering = cluster(&features, 2, 20);
+        assert_eq!(clustering.labels, vec![1, 0, 1, 0, 0, 1]);
+        assert!(clustering.centroids[0][0] < clustering.centroids[1][0]);
+        assert!((clustering.centroids[1][0] - 30.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,35 @@
+use disolv_models::device::types::DeviceClass;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Configuration of the clustering of the mobility patterns of the agents.
+///
+/// The agents in the `trace_file`, which uses the position schema of the simulator, are grouped
+/// into `count` clusters with at most `iterations` passes of k-means. An agent dwells while it
+/// moves slower than `dwell_speed` m/s, and its dwell areas are the cells of `dwell_cell` meters
+/// it dwells in. The labels are written to the `output_file`. When a `population_file` exported
+/// by the simulator is given, the agents in it get the class of their cluster from `classes` and
+/// the population is written to `population_output`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Config {
+    pub trace_file: String,
+    pub output_file: String,
+    pub count: usize,
+    pub dwell_speed: Option<f64>,
+    pub dwell_cell: Option<f64>,
+    pub iterations: Option<u32>,
+    pub population_file: Option<String>,
+    pub population_output: Option<String>,
+    pub classes: Option<Vec<DeviceClass>>,
+}
+
+pub fn read_config(file_path: &Path) -> Config {
+    let input_toml = match std::fs::read_to_string(file_path) {
+        Ok(parsed_string) => parsed_string,
+        Err(e) => panic!("Failed to read {}: {}", file_path.display(), e),
+    };
+    match toml::from_str(&input_toml) {
+        Ok(config) => config,
+        Err(e) => panic!("Invalid clustering configuration: {}", e),
+    }
+}
@@ -0,0 +1,117 @@
+use arrow::array::RecordBatch;
+use disolv_core::agent::AgentId;
+use disolv_core::hashbrown::{HashMap, HashSet};
+use disolv_input::batch::{read_f64_column, read_u64_column};
+use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, TIME_STEP};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+/// Features of the mobility of an agent over its positions in the trace.
+///
+/// * `speed` is the length of its path over the time between its first and last position, in
+///   m/s.
+/// * `gyration` is the radius of gyration of its positions around their centroid, in m.
+/// * `dwell_areas` is the number of cells in which it moved slower than the dwell speed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Features {
+    pub agent_id: AgentId,
+    pub speed: f64,
+    pub gyration: f64,
+    pub dwell_areas: f64,
+}
+
+impl Features {
+    pub fn values(&self) -> [f64; 3] {
+        [self.speed, self.gyration, self.dwell_areas]
+    }
+}
+
+/// Computes the features of an agent from its positions ordered by time step.
+pub fn features_of(
+    agent_id: AgentId,
+    positions: &[(u64, [f64; 2])],
+    dwell_speed: f64,
+    dwell_cell: f64,
+) -> Features {
+    let count = positions.len().max(1) as f64;
+    let centroid = positions.iter().fold([0.0, 0.0], |sum, (_, pos)| {
+        [sum[0] + pos[0] / count, sum[1] + pos[1] / count]
+    });
+    let gyration = (positions
+        .iter()
+        .map(|(_, pos)| (pos[0] - centroid[0]).powi(2) + (pos[1] - centroid[1]).powi(2))
+        .sum::<f64>()
+        / count)
+        .sqrt();
+
+    let mut path = 0.0;
+    let mut dwell_cells: HashSet<(i64, i64)> = HashSet::new();
+    for pair in positions.windows(2) {
+        let ((from_time, from), (to_time, to)) = (pair[0], pair[1]);
+        let distance = ((to[0] - from[0]).powi(2) + (to[1] - from[1]).powi(2)).sqrt();
+        path += distance;
+        let seconds = (to_time - from_time) as f64 / 1000.0;
+        if seconds > 0.0 && distance / seconds < dwell_speed {
+            dwell_cells.insert((
+                (from[0] / dwell_cell).floor() as i64,
+                (from[1] / dwell_cell).floor() as i64,
+            ));
+        }
+    }
+    let speed = match (positions.first(), positions.last()) {
+        (Some((first, _)), Some((last, _))) if last > first => {
+            path / ((last - first) as f64 / 1000.0)
+        }
+        _ => 0.0,
+    };
+    Features {
+        agent_id,
+        speed,
+        gyration,
+        dwell_areas: dwell_cells.len() as f64,
+    }
+}
+
+/// Reads the positions of the agents in the trace and computes their features, in the order of
+/// the agent ids.
+pub fn read_features(file_path: &Path, dwell_speed: f64, dwell_cell: f64) -> Vec<Features> {
+    let mut positions: HashMap<AgentId, Vec<(u64, [f64; 2])>> = HashMap::new();
+    for record_batch in read_batches(file_path) {
+        let time_steps = read_u64_column(TIME_STEP, &record_batch);
+        let agent_ids = read_u64_column(AGENT_ID, &record_batch);
+        let x_positions = read_f64_column(COORD_X, &record_batch);
+        let y_positions = read_f64_column(COORD_Y, &record_batch);
+        for row in 0..record_batch.num_rows() {
+            positions
+                .entry(AgentId::from(agent_ids[row]))
+                .or_default()
+                .push((time_steps[row], [x_positions[row], y_positions[row]]));
+        }
+    }
+    let mut features: Vec<Features> = positions
+        .into_iter()
+        .map(|(agent_id, mut positions)| {
+            positions.sort_by_key(|(time_step, _)| *time_step);
+            features_of(agent_id, &positions, dwell_speed, dwell_cell)
+        })
+        .collect();
+    features.sort_by_key(|features| features.agent_id);
+    features
+}
+
+fn read_batches(file_path: &Path) -> impl Iterator<Item = RecordBatch> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Error reading file {}: {}", file_path.display(), e),
+    };
+    let reader = match ParquetRecordBatchReaderBuilder::try_new(file) {
+        Ok(builder) => builder.build(),
+        Err(e) => panic!("Error building parquet reader: {}", e),
+    };
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(e) => panic!("Error building reader: {}", e),
+    };
+    reader.map(|batch| batch.unwrap_or_else(|e| panic!("Error reading record batch: {}", e)))
+}
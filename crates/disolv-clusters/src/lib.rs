@@ -0,0 +1,5 @@
+pub mod cluster;
+pub mod config;
+pub mod features;
+pub mod population;
+pub mod writer;
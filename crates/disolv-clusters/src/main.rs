@@ -0,0 +1,86 @@
+use clap::Parser;
+use disolv_clusters::cluster::cluster;
+use disolv_clusters::config::read_config;
+use disolv_clusters::features::read_features;
+use disolv_clusters::population::assign_classes;
+use disolv_clusters::writer::write_clusters;
+use disolv_core::hashbrown::HashMap;
+use std::path::PathBuf;
+
+const DWELL_SPEED: f64 = 0.5;
+const DWELL_CELL: f64 = 50.0;
+const ITERATIONS: u32 = 50;
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 'c', long, value_name = "Clustering Configuration File")]
+    config: String,
+}
+
+fn main() {
+    let config_file = PathBuf::from(CliArgs::parse().config);
+    let start = std::time::Instant::now();
+    let config = read_config(&config_file);
+    let config_path = config_file.parent().map(PathBuf::from).unwrap_or_default();
+
+    let features = read_features(
+        &config_path.join(&config.trace_file),
+        config.dwell_speed.unwrap_or(DWELL_SPEED),
+        config.dwell_cell.unwrap_or(DWELL_CELL),
+    );
+    println!(
+        "Clustering {} agents into {} clusters.",
+        features.len(),
+        config.count
+    );
+    let clustering = cluster(
+        &features,
+        config.count,
+        config.iterations.unwrap_or(ITERATIONS),
+    );
+    write_clusters(
+        &config_path.join(&config.output_file),
+        &features,
+        &clustering,
+    );
+    for (label, centroid) in clustering.centroids.iter().enumerate() {
+        let size = clustering.labels.iter().filter(|l| **l == label).count();
+        println!(
+            "Cluster {}: {} agents, {:.1} m/s, {:.1} m gyration, {:.1} dwell areas.",
+            label, size, centroid[0], centroid[1], centroid[2]
+        );
+    }
+
+    if let Some(ref population_file) = config.population_file {
+        let classes = match config.classes {
+            Some(ref classes) if classes.len() == config.count => classes,
+            _ => panic!(
+                "A class must be given for each of the {} clusters.",
+                config.count
+            ),
+        };
+        let population_output = match config.population_output {
+            Some(ref population_output) => config_path.join(population_output),
+            None => panic!("An output file must be given for the population."),
+        };
+        let assignments: HashMap<_, _> = features
+            .iter()
+            .zip(clustering.labels.iter())
+            .map(|(agent, label)| (agent.agent_id, classes[*label]))
+            .collect();
+        let assigned = assign_classes(
+            &config_path.join(population_file),
+            &population_output,
+            &assignments,
+        );
+        println!(
+            "Assigned the classes of {} agents in {}.",
+            assigned,
+            population_output.display()
+        );
+    }
+
+    let elapsed = start.elapsed();
+    println!("Clustering finished in {} ms.", elapsed.as_millis());
+}
@@ -0,0 +1,46 @@
+use disolv_core::agent::AgentId;
+use disolv_core::hashbrown::HashMap;
+use disolv_models::device::types::DeviceClass;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Sets the class of the agents in a population file exported by the simulator to the class
+/// of their cluster. The other agents and the rest of the records, e.g. the seeds, are kept so
+/// that a run importing the population only differs in the classes. Returns the number of
+/// agents whose class was set.
+pub fn assign_classes(
+    population_file: &Path,
+    population_output: &Path,
+    classes: &HashMap<AgentId, DeviceClass>,
+) -> usize {
+    let content = match std::fs::read_to_string(population_file) {
+        Ok(content) => content,
+        Err(e) => panic!("Failed to read {}: {}", population_file.display(), e),
+    };
+    let mut population: Table = match toml::from_str(&content) {
+        Ok(population) => population,
+        Err(e) => panic!(
+            "Invalid population file {}: {}",
+            population_file.display(),
+            e
+        ),
+    };
+    let mut assigned = 0;
+    if let Some(Value::Array(agents)) = population.get_mut("agents") {
+        for record in agents.iter_mut().filter_map(Value::as_table_mut) {
+            let agent_id = match record.get("agent_id").and_then(Value::as_integer) {
+                Some(agent_id) => AgentId::from(agent_id as u64),
+                None => continue,
+            };
+            if let Some(class) = classes.get(&agent_id) {
+                record.insert("agent_class".to_string(), Value::from(class.to_string()));
+                assigned += 1;
+            }
+        }
+    }
+    let content = toml::to_string(&population).expect("Failed to serialize the population");
+    if let Err(e) = std::fs::write(population_output, content) {
+        panic!("Failed to write {}: {}", population_output.display(), e);
+    }
+    assigned
+}
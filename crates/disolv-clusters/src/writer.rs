@@ -0,0 +1,70 @@
+use crate::cluster::Clustering;
+use crate::features::Features;
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use disolv_input::columns::AGENT_ID;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Writes the cluster of every agent with the features it was clustered by.
+pub fn write_clusters(output_file: &Path, features: &[Features], clustering: &Clustering) {
+    let schema = Schema::new(vec![
+        Field::new(AGENT_ID, DataType::UInt64, false),
+        Field::new("cluster", DataType::UInt32, false),
+        Field::new("speed", DataType::Float64, false),
+        Field::new("gyration", DataType::Float64, false),
+        Field::new("dwell_areas", DataType::Float64, false),
+    ]);
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = match File::create(output_file) {
+        Ok(file) => file,
+        Err(e) => panic!("Failed to create file {}: {}", output_file.display(), e),
+    };
+    let mut writer = match ArrowWriter::try_new(file, SchemaRef::from(schema), Some(props)) {
+        Ok(writer) => writer,
+        Err(e) => panic!("Failed to create parquet writer: {}", e),
+    };
+    let record_batch = RecordBatch::try_from_iter(vec![
+        (
+            AGENT_ID,
+            Arc::new(UInt64Array::from_iter_values(
+                features.iter().map(|agent| agent.agent_id.as_u64()),
+            )) as ArrayRef,
+        ),
+        (
+            "cluster",
+            Arc::new(UInt32Array::from_iter_values(
+                clustering.labels.iter().map(|label| *label as u32),
+            )) as ArrayRef,
+        ),
+        (
+            "speed",
+            Arc::new(Float64Array::from_iter_values(
+                features.iter().map(|agent| agent.speed),
+            )) as ArrayRef,
+        ),
+        (
+            "gyration",
+            Arc::new(Float64Array::from_iter_values(
+                features.iter().map(|agent| agent.gyration),
+            )) as ArrayRef,
+        ),
+        (
+            "dwell_areas",
+            Arc::new(Float64Array::from_iter_values(
+                features.iter().map(|agent| agent.dwell_areas),
+            )) as ArrayRef,
+        ),
+    ])
+    .expect("Failed to convert clusters to record batch");
+    writer
+        .write(&record_batch)
+        .expect("Failed to write clusters to file");
+    writer.close().expect("Failed to close clusters file");
+}
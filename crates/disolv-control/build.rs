@@ -0,0 +1,8 @@
+fn main() {
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc binary is not available");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/control.proto"], &["proto"])
+        .expect("failed to compile control protocol");
+}
@@ -0,0 +1,98 @@
+use disolv_core::agent::AgentId;
+use disolv_core::control::{ControlCommand, ControlHandle};
+use log::{error, info};
+use std::net::SocketAddr;
+use std::thread;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("disolv.control");
+}
+
+use proto::control_server::{Control, ControlServer};
+use proto::{CommandReply, Empty, Kpi, Kpis, Progress, SnapshotRequest};
+
+/// Serves the control requests by forwarding them to the simulation through the handle.
+#[derive(Debug)]
+pub struct ControlService {
+    handle: ControlHandle,
+}
+
+impl ControlService {
+    pub fn new(handle: ControlHandle) -> Self {
+        Self { handle }
+    }
+
+    fn command(&self, command: ControlCommand) -> Response<CommandReply> {
+        let accepted = self.handle.send(command);
+        Response::new(CommandReply { accepted })
+    }
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    async fn get_progress(&self, _request: Request<Empty>) -> Result<Response<Progress>, Status> {
+        let status = self.handle.status();
+        Ok(Response::new(Progress {
+            now: status.now,
+            duration: status.duration,
+            paused: status.paused,
+            finished: status.finished,
+        }))
+    }
+
+    async fn get_kpis(&self, _request: Request<Empty>) -> Result<Response<Kpis>, Status> {
+        let status = self.handle.status();
+        let kpis = status
+            .kpis
+            .into_iter()
+            .map(|(name, value)| Kpi { name, value })
+            .collect();
+        Ok(Response::new(Kpis {
+            now: status.now,
+            kpis,
+        }))
+    }
+
+    async fn pause(&self, _request: Request<Empty>) -> Result<Response<CommandReply>, Status> {
+        Ok(self.command(ControlCommand::Pause))
+    }
+
+    async fn resume(&self, _request: Request<Empty>) -> Result<Response<CommandReply>, Status> {
+        Ok(self.command(ControlCommand::Resume))
+    }
+
+    async fn checkpoint(&self, _request: Request<Empty>) -> Result<Response<CommandReply>, Status> {
+        Ok(self.command(ControlCommand::Checkpoint))
+    }
+
+    async fn snapshot(
+        &self,
+        request: Request<SnapshotRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let agent_id = request.into_inner().agent_id.map(AgentId::from);
+        Ok(self.command(ControlCommand::Snapshot(agent_id)))
+    }
+}
+
+/// Starts the control server on the given address in a background thread. The server lives as
+/// long as the process. The service is not authenticated, so it should only be bound to the
+/// interfaces of trusted networks.
+pub fn start_control_server(handle: ControlHandle, address: SocketAddr) {
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(e) => panic!("Failed to start the control server runtime: {}", e),
+        };
+        info!("Starting control server on {}", address);
+        let served = runtime.block_on(
+            Server::builder()
+                .add_service(ControlServer::new(ControlService::new(handle)))
+                .serve(address),
+        );
+        if let Err(e) = served {
+            error!("Control server stopped: {}", e);
+        }
+    });
+}
@@ -1,7 +1,7 @@
 use super::bucket::TimeMS;
 use crate::bucket::Bucket;
 use crate::core::Core;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -9,7 +9,7 @@ use std::str::FromStr;
 use typed_builder::TypedBuilder;
 
 /// A unique ID that is a property of all the agents in the simulation.
-#[derive(Deserialize, Default, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Serialize, Default, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct AgentId(u64);
 
 impl fmt::Display for AgentId {
@@ -81,7 +81,9 @@ pub trait AgentClass:
 /// agents. At each time step, the agents are sorted by their tier. The agents with the
 /// lowest tier are called first and gradually proceeding to the agents with the highest tier.
 /// This allows the agents to be simulated in a tiered fashion.
-#[derive(Deserialize, Debug, Copy, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(
+    Deserialize, Serialize, Debug, Copy, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
 pub struct AgentOrder(pub u32);
 
 impl From<u32> for AgentOrder {
@@ -128,7 +130,8 @@ pub trait Activatable {
 /// you want to simulate. Only types with this trait can be added to a bucket and hence
 /// scheduled for simulation.
 ///
-pub trait Agent<B>: Activatable + Orderable + Movable<B> + Clone + Send + Sync
+pub trait Agent<B>:
+    Activatable + Orderable + Movable<B> + DebugView<B> + Clone + Send + Sync
 where
     B: Bucket,
 {
@@ -144,6 +147,12 @@ where
 
 pub trait AgentStats: Copy + Clone + Send + Sync {}
 
+/// A trait to dump the state of an agent for offline inspection. The view should include the
+/// state held for the agent in the bucket, e.g. the messages waiting to be received by it.
+pub trait DebugView<B> {
+    fn debug_view(&self, bucket: &B) -> serde_json::Value;
+}
+
 /// A struct that represents a generic agent. This is a wrapper around the agent type that
 /// implements the [Agent] trait. This is required to store the agents in the [scheduler].
 #[derive(Clone, Debug, Default, TypedBuilder)]
@@ -171,8 +180,8 @@ where
 #[cfg(test)]
 pub(crate) mod tests {
     use super::{
-        Activatable, Agent, AgentId, AgentKind, AgentOrder, AgentStats, MobilityInfo, Movable,
-        Orderable,
+        Activatable, Agent, AgentId, AgentKind, AgentOrder, AgentStats, DebugView, MobilityInfo,
+        Movable, Orderable,
     };
     use crate::bucket::tests::MyBucket;
     use crate::bucket::TimeMS;
@@ -260,6 +269,12 @@ pub(crate) mod tests {
         }
     }
 
+    impl DebugView<MyBucket> for TDevice {
+        fn debug_view(&self, _bucket: &MyBucket) -> serde_json::Value {
+            serde_json::json!({ "id": self.id, "size": self.stats.size })
+        }
+    }
+
     impl Agent<MyBucket> for TDevice {
         type AS = DeviceStats;
 
@@ -0,0 +1,322 @@
+use crate::bucket::TimeMS;
+use hashbrown::HashMap;
+use log::{debug, info};
+use serde::Deserialize;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Index of a region of the map in the distributed mode.
+pub type RegionId = u32;
+
+/// Largest frame accepted from a peer. A longer frame means a corrupt stream or a process that
+/// is not a peer, and allocating its length would exhaust the memory.
+const MAX_FRAME_SIZE: u64 = 1 << 30;
+
+/// Settings of a process in the distributed mode. Every region of the map is simulated by one
+/// process, which listens at the address of its region. The processes connect to each other
+/// when the simulation starts and wait up to `connect_timeout` seconds for the others.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BoundarySettings {
+    pub region: RegionId,
+    pub addresses: Vec<String>,
+    pub connect_timeout: Option<u64>,
+}
+
+struct Peer {
+    region: RegionId,
+    stream: TcpStream,
+}
+
+/// Socket connections to the processes of the other regions. In every step, each process sends
+/// one frame to every other process and waits for one frame from each of them, which keeps the
+/// processes in lockstep. A frame is the step, the length of the content and the content.
+pub struct Boundary {
+    region: RegionId,
+    regions: u32,
+    peers: Vec<Peer>,
+}
+
+impl Boundary {
+    /// Connects to the processes of the other regions. Processes connect to the regions with a
+    /// lower index and accept the connections from the regions with a higher index. Panics if a
+    /// region does not connect in time or a connection claims a region that is not expected.
+    pub fn connect(settings: &BoundarySettings) -> Self {
+        let regions = settings.addresses.len() as u32;
+        if settings.region >= regions {
+            panic!(
+                "Region {} has no address, only {} addresses are given.",
+                settings.region, regions
+            );
+        }
+        let timeout = Duration::from_secs(settings.connect_timeout.unwrap_or(60));
+        let own_address = &settings.addresses[settings.region as usize];
+        let listener = match TcpListener::bind(own_address) {
+            Ok(listener) => listener,
+            Err(e) => panic!("Failed to listen at {}: {}", own_address, e),
+        };
+        info!(
+            "Region {} listening at {} for {} regions",
+            settings.region, own_address, regions
+        );
+
+        let mut peers = Vec::with_capacity(regions as usize - 1);
+        for region in 0..settings.region {
+            let mut stream = connect_with_retry(&settings.addresses[region as usize], timeout);
+            stream
+                .write_all(&settings.region.to_le_bytes())
+                .expect("Failed to send the region to the peer");
+            peers.push(Peer { region, stream });
+        }
+        listener
+            .set_nonblocking(true)
+            .expect("Failed to configure the listener");
+        let start = Instant::now();
+        for _ in settings.region + 1..regions {
+            let (mut stream, address) = accept_with_timeout(&listener, start, timeout);
+            stream
+                .set_read_timeout((!timeout.is_zero()).then_some(timeout))
+                .expect("Failed to configure the peer connection");
+            let mut region = [0u8; 4];
+            stream
+                .read_exact(&mut region)
+                .expect("Failed to read the region of the peer");
+            let region = RegionId::from_le_bytes(region);
+            if region <= settings.region || region >= regions {
+                panic!(
+                    "Region {} accepted a connection from {} with invalid region {}",
+                    settings.region, address, region
+                );
+            }
+            if peers.iter().any(|peer| peer.region == region) {
+                panic!(
+                    "Region {} is connected twice, again from {}",
+                    region, address
+                );
+            }
+            stream
+                .set_read_timeout(None)
+                .expect("Failed to configure the peer connection");
+            debug!("Region {} connected from {}", region, address);
+            peers.push(Peer { region, stream });
+        }
+        for peer in peers.iter() {
+            peer.stream
+                .set_nodelay(true)
+                .expect("Failed to configure the peer connection");
+        }
+        peers.sort_by_key(|peer| peer.region);
+        Self {
+            region: settings.region,
+            regions,
+            peers,
+        }
+    }
+
+    pub fn region(&self) -> RegionId {
+        self.region
+    }
+
+    pub fn regions(&self) -> u32 {
+        self.regions
+    }
+
+    /// Sends the content for each region and returns the content received from every region.
+    /// Regions without content are sent an empty frame. Panics if a peer is at another step.
+    pub fn exchange(
+        &mut self,
+        step: TimeMS,
+        mut outgoing: HashMap<RegionId, Vec<u8>>,
+    ) -> Vec<(RegionId, Vec<u8>)> {
+        thread::scope(|scope| {
+            for peer in self.peers.iter() {
+                let content = outgoing.remove(&peer.region).unwrap_or_default();
+                let mut writer = peer
+                    .stream
+                    .try_clone()
+                    .expect("Failed to clone the peer connection");
+                scope.spawn(move || write_frame(&mut writer, step, &content));
+            }
+            self.peers
+                .iter_mut()
+                .map(|peer| {
+                    let (peer_step, content) = read_frame(&mut peer.stream);
+                    if peer_step != step {
+                        panic!(
+                            "Region {} is at step {} while region {} is at step {}",
+                            peer.region, peer_step, self.region, step
+                        );
+                    }
+                    (peer.region, content)
+                })
+                .collect()
+        })
+    }
+}
+
+fn connect_with_retry(address: &str, timeout: Duration) -> TcpStream {
+    let start = Instant::now();
+    loop {
+        match TcpStream::connect(address) {
+            Ok(stream) => return stream,
+            Err(e) if start.elapsed() < timeout => {
+                debug!("Waiting for the region at {}: {}", address, e);
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => panic!("Failed to connect to the region at {}: {}", address, e),
+        }
+    }
+}
+
+/// Accepts the next peer on the nonblocking listener, waiting until `timeout` has passed since
+/// `start`. The accepted stream is blocking.
+fn accept_with_timeout(
+    listener: &TcpListener,
+    start: Instant,
+    timeout: Duration,
+) -> (TcpStream, SocketAddr) {
+    loop {
+        match listener.accept() {
+            Ok((stream, address)) => {
+                stream
+                    .set_nonblocking(false)
+                    .expect("Failed to configure the peer connection");
+                return (stream, address);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock && start.elapsed() < timeout => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                panic!("No peer connected within {} seconds", timeout.as_secs())
+            }
+            Err(e) => panic!("Failed to accept a peer: {}", e),
+        }
+    }
+}
+
+fn write_frame(stream: &mut TcpStream, step: TimeMS, content: &[u8]) {
+    let mut frame = Vec::with_capacity(16 + content.len());
+    frame.extend_from_slice(&step.as_u64().to_le_bytes());
+    frame.extend_from_slice(&(content.len() as u64).to_le_bytes());
+    frame.extend_from_slice(content);
+    stream
+        .write_all(&frame)
+        .expect("Failed to send the frame to the peer");
+}
+
+fn read_frame(stream: &mut TcpStream) -> (TimeMS, Vec<u8>) {
+    let mut header = [0u8; 16];
+    stream
+        .read_exact(&mut header)
+        .expect("Failed to read the frame from the peer");
+    let step = u64::from_le_bytes(header[..8].try_into().expect("Invalid frame header"));
+    let length = u64::from_le_bytes(header[8..].try_into().expect("Invalid frame header"));
+    if length > MAX_FRAME_SIZE {
+        panic!(
+            "Frame of {} bytes exceeds the limit of {} bytes",
+            length, MAX_FRAME_SIZE
+        );
+    }
+    let mut content = vec![0u8; length as usize];
+    stream
+        .read_exact(&mut content)
+        .expect("Failed to read the frame from the peer");
+    (TimeMS::from(step), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn free_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        listener
+            .local_addr()
+            .expect("Failed to read the address")
+            .to_string()
+    }
+
+    #[test]
+    fn test_exchange_in_lockstep() {
+        let addresses: Vec<String> = (0..3).map(|_| free_address()).collect();
+        let handles: Vec<_> = (0..3u32)
+            .map(|region| {
+                let settings = BoundarySettings {
+                    region,
+                    addresses: addresses.clone(),
+                    connect_timeout: Some(10),
+                };
+                thread::spawn(move || {
+                    let mut boundary = Boundary::connect(&settings);
+                    let mut received = Vec::new();
+                    for step in [0u64, 100] {
+                        let outgoing = (0..3u32)
+                            .filter(|other| *other != region)
+                            .map(|other| (other, format!("{}>{}@{}", region, other, step)))
+                            .map(|(other, text)| (other, text.into_bytes()))
+                            .collect();
+                        received.push(boundary.exchange(TimeMS::from(step), outgoing));
+                    }
+                    (boundary.regions(), received)
+                })
+            })
+            .collect();
+        for (region, handle) in handles.into_iter().enumerate() {
+            let (regions, received) = handle.join().expect("Region failed");
+            assert_eq!(regions, 3);
+            let last: Vec<(RegionId, String)> = received[1]
+                .iter()
+                .map(|(from, content)| (*from, String::from_utf8(content.clone()).unwrap()))
+                .collect();
+            let expected: Vec<(RegionId, String)> = (0..3u32)
+                .filter(|other| *other != region as u32)
+                .map(|other| (other, format!("{}>{}@100", other, region)))
+                .collect();
+            assert_eq!(last, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "No peer connected")]
+    fn test_accept_times_out() {
+        let settings = BoundarySettings {
+            region: 0,
+            addresses: vec![free_address(), free_address()],
+            connect_timeout: Some(0),
+        };
+        Boundary::connect(&settings);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid region")]
+    fn test_unknown_peer_region_is_rejected() {
+        let addresses = vec![free_address(), free_address()];
+        let own_address = addresses[0].clone();
+        thread::spawn(move || {
+            let mut stream = connect_with_retry(&own_address, Duration::from_secs(10));
+            stream.write_all(&7u32.to_le_bytes()).unwrap();
+            thread::sleep(Duration::from_secs(1));
+        });
+        let settings = BoundarySettings {
+            region: 0,
+            addresses,
+            connect_timeout: Some(10),
+        };
+        Boundary::connect(&settings);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the limit")]
+    fn test_oversized_frame_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let address = listener.local_addr().expect("Failed to read the address");
+        let mut sender = TcpStream::connect(address).expect("Failed to connect");
+        let (mut receiver, _) = listener.accept().expect("Failed to accept");
+        let mut header = Vec::new();
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&u64::MAX.to_le_bytes());
+        sender.write_all(&header).unwrap();
+        read_frame(&mut receiver);
+    }
+}
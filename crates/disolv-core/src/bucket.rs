@@ -1,3 +1,7 @@
+use crate::agent::AgentId;
+use crate::heatmap::HeatmapData;
+use crate::memory::MemoryUsage;
+use crate::timing::StageTimes;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
 use std::ops::{Add, AddAssign, Div, Mul};
@@ -95,12 +99,54 @@ pub trait Bucket: Send {
     fn stream_input(&mut self, step: TimeMS);
     fn stream_output(&mut self, step: TimeMS);
     fn terminate(self, step: TimeMS);
+    /// Key performance indicators of the simulation so far, reported to external controllers.
+    fn kpis(&self) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+    /// Persists the state of the simulation so far when requested by an external controller.
+    fn checkpoint(&mut self, _step: TimeMS) {}
+    /// Writes the debug views of the agents requested by the user, of the given agent or of all
+    /// the active agents.
+    fn write_snapshot(
+        &mut self,
+        _step: TimeMS,
+        _agent_id: Option<AgentId>,
+        _views: Vec<serde_json::Value>,
+    ) {
+    }
+    /// Receives the agents in the order they run in the step, when the execution order is
+    /// audited.
+    fn record_execution_order(&mut self, _step: TimeMS, _agent_ids: &[AgentId]) {}
+    /// Receives the time spent in each stage of the simulation, just before it terminates.
+    fn record_performance(&mut self, _summary: &StageTimes) {}
+    /// Heatmap of the last output interval to be shown in the user interface, returned once.
+    fn heatmap(&mut self) -> Option<HeatmapData> {
+        None
+    }
+    /// Memory held by the subsystems of the bucket when it was last sampled, returned once.
+    fn memory_usage(&mut self) -> Option<MemoryUsage> {
+        None
+    }
+    /// Average distance moved by the agents since the previous call, used to adapt the
+    /// streaming interval. There is no displacement when no agent was seen in both calls.
+    fn displacement(&mut self) -> Option<f64> {
+        None
+    }
+    /// Receives the streaming interval chosen at the step, before the input is streamed.
+    fn set_streaming_interval(
+        &mut self,
+        _step: TimeMS,
+        _interval: TimeMS,
+        _displacement: Option<f64>,
+    ) {
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use super::Bucket;
     use super::TimeMS;
+    use crate::agent::AgentId;
 
     pub(crate) struct BucketModels {
         pub(crate) models: i32,
@@ -109,12 +155,14 @@ pub(crate) mod tests {
     #[derive(Default, Clone)]
     pub(crate) struct MyBucket {
         pub(crate) step: TimeMS,
+        pub(crate) snapshots: Vec<(Option<AgentId>, Vec<serde_json::Value>)>,
     }
 
     impl MyBucket {
         pub(crate) fn new() -> Self {
             Self {
                 step: TimeMS::default(),
+                snapshots: Vec::new(),
             }
         }
     }
@@ -161,6 +209,15 @@ pub(crate) mod tests {
         fn terminate(self, step: TimeMS) {
             println!("End in MyBucket at {}", step);
         }
+
+        fn write_snapshot(
+            &mut self,
+            _step: TimeMS,
+            agent_id: Option<AgentId>,
+            views: Vec<serde_json::Value>,
+        ) {
+            self.snapshots.push((agent_id, views));
+        }
     }
 
     #[test]
@@ -0,0 +1,162 @@
+use crate::agent::AgentId;
+use crate::scheduler::Scheduler;
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Interval at which a paused simulation checks if it must stop.
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Commands that can be sent to a running simulation from outside the runner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlCommand {
+    Pause,
+    Resume,
+    Checkpoint,
+    /// Dumps the debug view of the agent, or of all the active agents.
+    Snapshot(Option<AgentId>),
+}
+
+/// A snapshot of the simulation progress and the KPIs reported by the bucket.
+#[derive(Clone, Debug, Default)]
+pub struct SimStatus {
+    pub now: u64,
+    pub duration: u64,
+    pub paused: bool,
+    pub finished: bool,
+    pub kpis: Vec<(String, f64)>,
+}
+
+/// Handle given to an external service (e.g. an RPC server) to control the simulation.
+/// Handles can be cloned and shared between threads.
+#[derive(Clone, Debug)]
+pub struct ControlHandle {
+    commands: Sender<ControlCommand>,
+    status: Arc<RwLock<SimStatus>>,
+}
+
+impl ControlHandle {
+    /// Sends a command to the simulation. Returns false if the simulation is no longer running.
+    pub fn send(&self, command: ControlCommand) -> bool {
+        self.commands.send(command).is_ok()
+    }
+
+    pub fn status(&self) -> SimStatus {
+        self.status.read().expect("failed to read status").clone()
+    }
+}
+
+/// Controller is owned by the runner. The runner calls it before every time step to apply the
+/// pending commands and after every time step to publish the status.
+#[derive(Debug)]
+pub struct Controller {
+    commands: Receiver<ControlCommand>,
+    status: Arc<RwLock<SimStatus>>,
+}
+
+impl Controller {
+    pub fn new(duration: u64) -> (Controller, ControlHandle) {
+        let (sender, receiver) = mpsc::channel();
+        let status = Arc::new(RwLock::new(SimStatus {
+            duration,
+            ..Default::default()
+        }));
+        let controller = Controller {
+            commands: receiver,
+            status: Arc::clone(&status),
+        };
+        let handle = ControlHandle {
+            commands: sender,
+            status,
+        };
+        (controller, handle)
+    }
+
+    /// Applies the pending commands. A pause blocks the simulation until a resume command is
+    /// received, all the handles are dropped or `quit` is raised, e.g. when the user closes the
+    /// user interface. Returns false if the simulation must stop.
+    pub fn apply_commands<S: Scheduler>(&mut self, scheduler: &mut S, quit: &AtomicBool) -> bool {
+        while let Ok(command) = self.commands.try_recv() {
+            self.apply(command, scheduler);
+        }
+        while self.is_paused() {
+            if quit.load(Ordering::Relaxed) {
+                return false;
+            }
+            match self.commands.recv_timeout(PAUSE_CHECK_INTERVAL) {
+                Ok(command) => self.apply(command, scheduler),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => self.set_paused(false),
+            }
+        }
+        true
+    }
+
+    pub fn update_status(&mut self, now: u64, kpis: Vec<(String, f64)>) {
+        let mut status = self.status.write().expect("failed to write status");
+        status.now = now;
+        status.kpis = kpis;
+    }
+
+    pub fn finish(&mut self) {
+        self.status
+            .write()
+            .expect("failed to write status")
+            .finished = true;
+    }
+
+    fn apply<S: Scheduler>(&mut self, command: ControlCommand, scheduler: &mut S) {
+        info!("Received control command {:?}", command);
+        match command {
+            ControlCommand::Pause => self.set_paused(true),
+            ControlCommand::Resume => self.set_paused(false),
+            ControlCommand::Checkpoint => scheduler.checkpoint(),
+            ControlCommand::Snapshot(agent_id) => scheduler.snapshot(agent_id),
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.status.read().expect("failed to read status").paused
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.status.write().expect("failed to write status").paused = paused;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::create_scheduler;
+
+    #[test]
+    fn test_controller_commands() {
+        let mut scheduler = create_scheduler();
+        let (mut controller, handle) = Controller::new(scheduler.duration().as_u64());
+        assert!(handle.send(ControlCommand::Pause));
+        assert!(handle.send(ControlCommand::Checkpoint));
+        assert!(handle.send(ControlCommand::Snapshot(Some(AgentId::from(3)))));
+        assert!(handle.send(ControlCommand::Resume));
+        assert!(controller.apply_commands(&mut scheduler, &AtomicBool::new(false)));
+        assert!(!handle.status().paused);
+        assert_eq!(scheduler.core.bucket.snapshots.len(), 1);
+
+        controller.update_status(10, vec![("kpi".to_string(), 1.0)]);
+        controller.finish();
+        let status = handle.status();
+        assert_eq!(status.now, 10);
+        assert_eq!(status.kpis.len(), 1);
+        assert!(status.finished);
+    }
+
+    #[test]
+    fn test_quit_stops_a_paused_simulation() {
+        let mut scheduler = create_scheduler();
+        let (mut controller, handle) = Controller::new(scheduler.duration().as_u64());
+        assert!(handle.send(ControlCommand::Pause));
+        assert!(!controller.apply_commands(&mut scheduler, &AtomicBool::new(true)));
+        assert!(handle.status().paused);
+    }
+}
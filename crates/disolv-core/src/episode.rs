@@ -0,0 +1,81 @@
+use crate::bucket::TimeMS;
+use std::collections::VecDeque;
+
+/// A marker trait for the changes an episode brings to the simulation. Extend this to a custom
+/// type (e.g. struct) that contains the settings to be swapped when the episode starts.
+pub trait EpisodeInfo: Clone + Send + Sync {}
+
+/// A timeline of episodes sorted by their start time. Episodes are released by the timeline
+/// when the simulation time reaches their start time.
+#[derive(Clone, Debug)]
+pub struct Episodes<E>
+where
+    E: EpisodeInfo,
+{
+    timeline: VecDeque<(TimeMS, E)>,
+}
+
+impl<E> Default for Episodes<E>
+where
+    E: EpisodeInfo,
+{
+    fn default() -> Self {
+        Self {
+            timeline: VecDeque::new(),
+        }
+    }
+}
+
+impl<E> Episodes<E>
+where
+    E: EpisodeInfo,
+{
+    pub fn new(mut episodes: Vec<(TimeMS, E)>) -> Self {
+        episodes.sort_by_key(|(start, _)| *start);
+        Self {
+            timeline: episodes.into(),
+        }
+    }
+
+    /// Removes and returns the episodes that should be started at the given time in the order
+    /// of their start time. Episodes whose start time has already passed are also returned.
+    pub fn due(&mut self, now: TimeMS) -> Vec<E> {
+        let mut due_episodes = Vec::new();
+        while let Some((start, _)) = self.timeline.front() {
+            if *start > now {
+                break;
+            }
+            if let Some((_, episode)) = self.timeline.pop_front() {
+                due_episodes.push(episode);
+            }
+        }
+        due_episodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timeline.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Change(u32);
+
+    impl EpisodeInfo for Change {}
+
+    #[test]
+    fn test_episodes_due() {
+        let mut episodes = Episodes::new(vec![
+            (TimeMS::from(200), Change(2)),
+            (TimeMS::from(100), Change(1)),
+            (TimeMS::from(200), Change(3)),
+        ]);
+        assert!(episodes.due(TimeMS::from(0)).is_empty());
+        assert_eq!(episodes.due(TimeMS::from(100)), vec![Change(1)]);
+        assert_eq!(episodes.due(TimeMS::from(300)), vec![Change(2), Change(3)]);
+        assert!(episodes.is_empty());
+    }
+}
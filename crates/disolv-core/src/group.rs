@@ -0,0 +1,132 @@
+use crate::agent::AgentId;
+use hashbrown::HashMap;
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Identifier of a named group of agents. The identifier is derived from the group name, so
+/// the same name always refers to the same group.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(from = "String")]
+pub struct GroupId(u64);
+
+impl Display for GroupId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl From<&str> for GroupId {
+    fn from(name: &str) -> Self {
+        // FNV-1a keeps the identifiers stable across runs and platforms.
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Self(hash)
+    }
+}
+
+impl From<u64> for GroupId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<String> for GroupId {
+    fn from(name: String) -> Self {
+        Self::from(name.as_str())
+    }
+}
+
+impl GroupId {
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Membership of agents in groups. An agent can be a member of any number of groups. Members
+/// are kept sorted so that iterating over a group is deterministic.
+#[derive(Clone, Debug, Default)]
+pub struct Groups {
+    members: HashMap<GroupId, Vec<AgentId>>,
+    memberships: HashMap<AgentId, Vec<GroupId>>,
+}
+
+impl Groups {
+    pub fn join(&mut self, group_id: GroupId, agent_id: AgentId) {
+        let members = self.members.entry(group_id).or_default();
+        if let Err(idx) = members.binary_search(&agent_id) {
+            members.insert(idx, agent_id);
+            self.memberships.entry(agent_id).or_default().push(group_id);
+        }
+    }
+
+    pub fn leave(&mut self, group_id: GroupId, agent_id: AgentId) {
+        if let Some(members) = self.members.get_mut(&group_id) {
+            if let Ok(idx) = members.binary_search(&agent_id) {
+                members.remove(idx);
+            }
+        }
+        if let Some(groups) = self.memberships.get_mut(&agent_id) {
+            groups.retain(|group| *group != group_id);
+        }
+    }
+
+    pub fn leave_all(&mut self, agent_id: AgentId) {
+        if let Some(groups) = self.memberships.remove(&agent_id) {
+            for group_id in groups.iter() {
+                if let Some(members) = self.members.get_mut(group_id) {
+                    members.retain(|member| *member != agent_id);
+                }
+            }
+        }
+    }
+
+    pub fn members(&self, group_id: &GroupId) -> &[AgentId] {
+        match self.members.get(group_id) {
+            Some(members) => members,
+            None => &[],
+        }
+    }
+
+    pub fn groups_of(&self, agent_id: &AgentId) -> &[GroupId] {
+        match self.memberships.get(agent_id) {
+            Some(groups) => groups,
+            None => &[],
+        }
+    }
+
+    pub fn is_member(&self, group_id: &GroupId, agent_id: &AgentId) -> bool {
+        self.members(group_id).binary_search(agent_id).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_membership() {
+        let platoon = GroupId::from("platoon");
+        let fleet = GroupId::from("fleet");
+        assert_eq!(platoon, GroupId::from("platoon".to_string()));
+        assert_ne!(platoon, fleet);
+
+        let mut groups = Groups::default();
+        groups.join(platoon, AgentId::from(3));
+        groups.join(platoon, AgentId::from(1));
+        groups.join(platoon, AgentId::from(1));
+        groups.join(fleet, AgentId::from(1));
+        assert_eq!(
+            groups.members(&platoon),
+            &[AgentId::from(1), AgentId::from(3)]
+        );
+        assert_eq!(groups.groups_of(&AgentId::from(1)).len(), 2);
+        assert!(groups.is_member(&fleet, &AgentId::from(1)));
+
+        groups.leave(platoon, AgentId::from(3));
+        assert_eq!(groups.members(&platoon), &[AgentId::from(1)]);
+        groups.leave_all(AgentId::from(1));
+        assert!(groups.members(&platoon).is_empty());
+        assert!(groups.groups_of(&AgentId::from(1)).is_empty());
+    }
+}
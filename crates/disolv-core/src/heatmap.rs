@@ -0,0 +1,121 @@
+use serde::Deserialize;
+
+pub const HEATMAP_ROWS: usize = 12;
+pub const HEATMAP_COLS: usize = 40;
+
+const SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Quantity shown in the heatmap of the user interface.
+#[derive(Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HeatmapKind {
+    #[default]
+    Density,
+    Traffic,
+}
+
+impl HeatmapKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeatmapKind::Density => "Agent density",
+            HeatmapKind::Traffic => "Payload traffic",
+        }
+    }
+}
+
+/// A coarse grid of counts over the field. The grid has a fixed size irrespective of the field
+/// size so that it can be sent to the user interface as a message. The first row is the top of
+/// the field.
+#[derive(Clone, Copy, Debug)]
+pub struct HeatmapData {
+    pub kind: HeatmapKind,
+    pub step: u64,
+    pub width: f64,
+    pub height: f64,
+    pub cells: [[u32; HEATMAP_COLS]; HEATMAP_ROWS],
+}
+
+impl HeatmapData {
+    pub fn new(kind: HeatmapKind, step: u64, width: f64, height: f64) -> Self {
+        Self {
+            kind,
+            step,
+            width: width.max(f64::EPSILON),
+            height: height.max(f64::EPSILON),
+            cells: [[0; HEATMAP_COLS]; HEATMAP_ROWS],
+        }
+    }
+
+    /// Adds the count to the cell of the position. Positions outside the field are counted in
+    /// the nearest cell at the border.
+    pub fn add(&mut self, x: f64, y: f64, count: u32) {
+        let col = Self::bin(x / self.width, HEATMAP_COLS);
+        let row = HEATMAP_ROWS - 1 - Self::bin(y / self.height, HEATMAP_ROWS);
+        self.cells[row][col] += count;
+    }
+
+    pub fn max(&self) -> u32 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.cells.iter().flatten().map(|count| *count as u64).sum()
+    }
+
+    /// Renders the grid as lines of characters shaded relative to the busiest cell.
+    pub fn render(&self) -> Vec<String> {
+        let max = self.max();
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|count| match (max, count) {
+                        (0, _) | (_, 0) => SHADES[0],
+                        _ => {
+                            let level = (*count as f64 / max as f64 * (SHADES.len() - 1) as f64)
+                                .ceil() as usize;
+                            SHADES[level.clamp(1, SHADES.len() - 1)]
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn bin(ratio: f64, bins: usize) -> usize {
+        if !ratio.is_finite() || ratio <= 0.0 {
+            return 0;
+        }
+        ((ratio * bins as f64) as usize).min(bins - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_bins() {
+        let mut heatmap = HeatmapData::new(HeatmapKind::Density, 0, 400.0, 120.0);
+        heatmap.add(0.0, 0.0, 1);
+        heatmap.add(399.0, 119.0, 2);
+        heatmap.add(1000.0, -5.0, 3);
+        assert_eq!(heatmap.cells[HEATMAP_ROWS - 1][0], 1);
+        assert_eq!(heatmap.cells[0][HEATMAP_COLS - 1], 2);
+        assert_eq!(heatmap.cells[HEATMAP_ROWS - 1][HEATMAP_COLS - 1], 3);
+        assert_eq!(heatmap.total(), 6);
+        assert_eq!(heatmap.max(), 3);
+    }
+
+    #[test]
+    fn test_heatmap_render() {
+        let mut heatmap = HeatmapData::new(HeatmapKind::Traffic, 0, 400.0, 120.0);
+        assert!(heatmap.render().iter().all(|line| line.trim().is_empty()));
+
+        heatmap.add(5.0, 115.0, 10);
+        heatmap.add(15.0, 115.0, 1);
+        let lines = heatmap.render();
+        assert_eq!(lines.len(), HEATMAP_ROWS);
+        assert_eq!(lines[0].chars().count(), HEATMAP_COLS);
+        assert!(lines[0].starts_with("@."));
+    }
+}
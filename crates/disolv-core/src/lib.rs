@@ -1,15 +1,25 @@
 #![forbid(unsafe_code)]
 
 pub mod agent;
+pub mod boundary;
 pub mod bucket;
+pub mod control;
 pub mod core;
+pub mod episode;
+pub mod group;
+pub mod heatmap;
 pub mod map_scheduler;
+pub mod memory;
 pub mod message;
 pub mod metrics;
 pub mod model;
+pub mod ordering;
 pub mod radio;
 pub mod runner;
 pub mod scheduler;
+pub mod state;
+pub mod streaming;
+pub mod timing;
 pub mod tui;
 pub mod ui;
 
@@ -1,14 +1,23 @@
-use std::cmp::Ordering;
+use tracing::info_span;
 
 use crate::agent::{Agent, AgentId, AgentImpl};
 use crate::bucket::{Bucket, TimeMS};
 use crate::core::Core;
 use crate::hashbrown::HashMap;
+use crate::heatmap::HeatmapData;
+use crate::memory::MemoryUsage;
+use crate::ordering::AgentOrdering;
 use crate::scheduler::Scheduler;
+use crate::streaming::StreamingController;
+use crate::timing::{Stage, StageTimer, StageTimes};
 use indexmap::IndexMap;
-use log::debug;
+use log::{debug, warn};
 use typed_builder::TypedBuilder;
 
+/// A scheduler that keeps the active agents in the order they run. The agents are sorted by
+/// their order and the tie-breaking rule of the `ordering` when agents are activated, and run in
+/// that order in the forward stages and in the reverse order in the reverse stages. Agents that
+/// are deactivated are replaced by the last agent until the agents are sorted again.
 #[derive(TypedBuilder)]
 pub struct MapScheduler<A, B>
 where
@@ -29,6 +38,12 @@ where
     pub streaming_step: TimeMS,
     #[builder(default = TimeMS::default())]
     pub output_step: TimeMS,
+    #[builder(default)]
+    pub timer: StageTimer,
+    #[builder(default)]
+    pub streaming_controller: Option<StreamingController>,
+    #[builder(default)]
+    pub ordering: AgentOrdering,
 }
 
 impl<A, B> MapScheduler<A, B>
@@ -43,27 +58,6 @@ where
             .expect("Agent not found in core")
             .agent;
     }
-
-    fn agent_cmp(
-        this_id: &AgentId,
-        this_agent: &AgentImpl<A, B>,
-        other_id: &AgentId,
-        other_agent: &AgentImpl<A, B>,
-    ) -> Ordering {
-        if this_agent.agent.order() == other_agent.agent.order() {
-            if this_id == other_id {
-                panic!("This should never happen!");
-            }
-            if this_id > other_id {
-                return Ordering::Greater;
-            }
-            return Ordering::Less;
-        }
-        if this_agent.agent.order() > other_agent.agent.order() {
-            return Ordering::Greater;
-        }
-        return Ordering::Less;
-    }
 }
 
 impl<A, B> Scheduler for MapScheduler<A, B>
@@ -82,9 +76,11 @@ where
                 .add_agent(agent.agent_id, agent.agent.time_to_activation());
         }
         self.core.bucket.initialize(self.now);
+        self.timer.start();
     }
 
     fn activate(&mut self) {
+        let stage = self.timer.begin(Stage::Activation);
         if self.core.agent_cache.contains_key(&self.now) {
             let agent_ids = self.core.agent_cache.remove(&self.now).unwrap();
             for agent_id in agent_ids.into_iter() {
@@ -99,72 +95,129 @@ where
                     .expect("agent not found")
                     .agent
                     .activate();
+                self.ordering.activate(agent_id);
             }
-            self.active_agents.sort_by(MapScheduler::agent_cmp);
+            self.active_agents
+                .sort_by(|this_id, this_agent, other_id, other_agent| {
+                    self.ordering.compare(
+                        this_id,
+                        this_agent.agent.order(),
+                        other_id,
+                        other_agent.agent.order(),
+                    )
+                });
         }
+        self.timer.end(stage);
     }
 
     fn collect_stats(&mut self) {
+        let stage = self.timer.begin(Stage::Stats);
         for agent in self.active_agents.values() {
             self.core
                 .agent_stats
                 .insert(agent.agent_id, agent.agent.stats());
         }
+        self.timer.end(stage);
     }
 
     fn trigger(&mut self) -> TimeMS {
+        let _step = info_span!("step", now = self.now.as_u64()).entered();
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.before_agents(self.now);
+        self.timer.end(stage);
 
         // This should be moved out of here.
         if self.now == self.streaming_step {
+            let stage = self.timer.begin(Stage::Input);
+            if let Some(ref mut controller) = self.streaming_controller {
+                let displacement = self.core.bucket.displacement();
+                self.streaming_interval = controller.adapt(displacement);
+                self.core.bucket.set_streaming_interval(
+                    self.now,
+                    self.streaming_interval,
+                    displacement,
+                );
+            }
             self.core.bucket.stream_input(self.now);
+            self.timer.end(stage);
             self.streaming_step += self.streaming_interval;
         }
 
         // This should be moved out of here.
         if self.now == self.output_step {
+            self.timer.end_interval();
+            let stage = self.timer.begin(Stage::Output);
             self.core.bucket.stream_output(self.now);
+            self.timer.end(stage);
             self.output_step += self.output_interval;
         }
 
         // Early return if the agent queue is empty.
         if self.active_agents.is_empty() {
+            let stage = self.timer.begin(Stage::BucketHooks);
             self.core.bucket.after_agents();
+            self.timer.end(stage);
+            self.timer.end_step();
             self.now += self.step_size;
             return self.now;
         }
 
+        if self.ordering.is_audited() {
+            let stage = self.timer.begin(Stage::BucketHooks);
+            let agent_ids: Vec<AgentId> = self.active_agents.keys().copied().collect();
+            self.core
+                .bucket
+                .record_execution_order(self.now, &agent_ids);
+            self.timer.end(stage);
+        }
+
+        let stage = self.timer.begin(Stage::StageOne);
         self.active_agents
             .values_mut()
             .for_each(|agent_impl| agent_impl.agent.stage_one(&mut self.core));
-
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_one();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageTwo);
         self.active_agents
             .values_mut()
             .rev()
             .for_each(|agent_impl| agent_impl.agent.stage_two_reverse(&mut self.core));
-
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_two();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageThree);
         self.active_agents
             .values_mut()
             .for_each(|agent_impl| agent_impl.agent.stage_three(&mut self.core));
-
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_three();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageFour);
         self.active_agents
             .values_mut()
             .rev()
             .for_each(|agent_impl| agent_impl.agent.stage_four_reverse(&mut self.core));
-
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_four();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageFive);
         self.active_agents
             .values_mut()
             .for_each(|agent_impl| agent_impl.agent.stage_five(&mut self.core));
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_agents();
+        self.timer.end(stage);
 
         self.deactivated = self
             .active_agents
@@ -183,13 +236,59 @@ where
         });
         self.deactivated.clear();
 
+        self.timer.end_step();
         self.now += self.step_size;
         self.now
     }
 
-    fn terminate(self) {
+    fn terminate(mut self) {
+        self.core.bucket.record_performance(&self.timer.summary());
         self.core.bucket.terminate(self.now);
     }
+
+    fn kpis(&self) -> Vec<(String, f64)> {
+        self.core.bucket.kpis()
+    }
+
+    fn checkpoint(&mut self) {
+        self.core.bucket.checkpoint(self.now);
+    }
+
+    fn snapshot(&mut self, agent_id: Option<AgentId>) {
+        let views: Vec<serde_json::Value> = match agent_id {
+            Some(agent_id) => self
+                .active_agents
+                .get(&agent_id)
+                .or_else(|| self.inactive_agents.get(&agent_id))
+                .map(|agent| agent.agent.debug_view(&self.core.bucket))
+                .into_iter()
+                .collect(),
+            None => self
+                .active_agents
+                .values()
+                .map(|agent| agent.agent.debug_view(&self.core.bucket))
+                .collect(),
+        };
+        if agent_id.is_some() && views.is_empty() {
+            warn!(
+                "Agent {:?} is not in the simulation, nothing to dump",
+                agent_id
+            );
+        }
+        self.core.bucket.write_snapshot(self.now, agent_id, views);
+    }
+
+    fn stage_times(&mut self) -> Option<StageTimes> {
+        self.timer.take_completed()
+    }
+
+    fn heatmap(&mut self) -> Option<HeatmapData> {
+        self.core.bucket.heatmap()
+    }
+
+    fn memory_usage(&mut self) -> Option<MemoryUsage> {
+        self.core.bucket.memory_usage()
+    }
 }
 
 #[cfg(test)]
@@ -224,6 +323,9 @@ pub(crate) mod tests {
             output_step: TimeMS::from(0),
             step_size: TimeMS::from(100),
             now: TimeMS::from(0),
+            timer: StageTimer::default(),
+            streaming_controller: None,
+            ordering: AgentOrdering::default(),
         }
     }
 
@@ -0,0 +1,48 @@
+pub const MAX_SUBSYSTEMS: usize = 8;
+
+/// Estimated memory held by a subsystem of the bucket.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SubsystemMemory {
+    pub id: u32,
+    pub name: &'static str,
+    pub entries: u64,
+    pub bytes: u64,
+    pub over_cap: bool,
+}
+
+/// Memory held by the subsystems of the bucket at a step. The number of subsystems is bounded
+/// so that the usage can be sent to the user interface as a message.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryUsage {
+    pub step: u64,
+    subsystems: [SubsystemMemory; MAX_SUBSYSTEMS],
+    count: usize,
+}
+
+impl MemoryUsage {
+    pub fn new(step: u64) -> Self {
+        Self {
+            step,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, subsystem: SubsystemMemory) {
+        if self.count == MAX_SUBSYSTEMS {
+            panic!("MemoryUsage::push: more than {} subsystems", MAX_SUBSYSTEMS);
+        }
+        self.subsystems[self.count] = subsystem;
+        self.count += 1;
+    }
+
+    pub fn subsystems(&self) -> &[SubsystemMemory] {
+        &self.subsystems[..self.count]
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.subsystems()
+            .iter()
+            .map(|subsystem| subsystem.bytes)
+            .sum()
+    }
+}
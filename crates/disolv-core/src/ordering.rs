@@ -0,0 +1,160 @@
+use crate::agent::{AgentId, AgentOrder};
+use crate::hashbrown::HashMap;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// Rule that orders the agents of the same order within a step.
+///
+/// * `Id` runs them by increasing agent id, the default.
+/// * `ReverseId` runs them by decreasing agent id.
+/// * `Insertion` runs them in the order they were activated, the latest activated last.
+/// * `Random` runs them in a random order drawn from the seed. The rank of an agent only depends
+///   on the seed and its id, so that the order is the same in every run with the seed.
+#[derive(Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TieBreak {
+    #[default]
+    Id,
+    ReverseId,
+    Insertion,
+    Random,
+}
+
+/// Settings of the execution order of the agents. The random tie-breaking uses `seed`, or the
+/// seed of the simulation when it is not given. With `audit`, the order in which the agents run
+/// is recorded in every step.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct OrderingSettings {
+    pub tie_break: TieBreak,
+    pub seed: Option<u64>,
+    pub audit: Option<bool>,
+}
+
+/// Orders the active agents of a scheduler. Agents run by increasing order and the ties are
+/// broken with the tie-breaking rule, then by the agent id, so that the order is total and the
+/// same in every run with the same settings.
+#[derive(Clone, Debug, Default)]
+pub struct AgentOrdering {
+    tie_break: TieBreak,
+    seed: u64,
+    audit: bool,
+    ranks: HashMap<AgentId, u64>,
+    activations: u64,
+}
+
+impl AgentOrdering {
+    pub fn new(settings: &OrderingSettings, seed: u64) -> Self {
+        Self {
+            tie_break: settings.tie_break,
+            seed: settings.seed.unwrap_or(seed),
+            audit: settings.audit.unwrap_or(false),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the execution order is recorded in every step.
+    pub fn is_audited(&self) -> bool {
+        self.audit
+    }
+
+    /// Ranks the agent when it is activated. Agents activated again are ranked again, so that
+    /// the insertion order follows their latest activation.
+    pub fn activate(&mut self, agent_id: AgentId) {
+        let rank = match self.tie_break {
+            TieBreak::Id | TieBreak::ReverseId => return,
+            TieBreak::Insertion => {
+                self.activations += 1;
+                self.activations
+            }
+            TieBreak::Random => Self::mix(self.seed ^ Self::mix(agent_id.as_u64())),
+        };
+        self.ranks.insert(agent_id, rank);
+    }
+
+    pub fn compare(
+        &self,
+        this_id: &AgentId,
+        this_order: AgentOrder,
+        other_id: &AgentId,
+        other_order: AgentOrder,
+    ) -> Ordering {
+        this_order
+            .cmp(&other_order)
+            .then_with(|| match self.tie_break {
+                TieBreak::Id => Ordering::Equal,
+                TieBreak::ReverseId => other_id.cmp(this_id),
+                TieBreak::Insertion | TieBreak::Random => {
+                    self.rank_of(this_id).cmp(&self.rank_of(other_id))
+                }
+            })
+            .then_with(|| this_id.cmp(other_id))
+    }
+
+    fn rank_of(&self, agent_id: &AgentId) -> u64 {
+        self.ranks.get(agent_id).copied().unwrap_or_default()
+    }
+
+    /// The SplitMix64 finalizer, which spreads the agent ids over the ranks.
+    fn mix(value: u64) -> u64 {
+        let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(ordering: &AgentOrdering, agents: &[(u64, u32)]) -> Vec<u64> {
+        let mut agents: Vec<(AgentId, AgentOrder)> = agents
+            .iter()
+            .map(|(id, order)| (AgentId::from(*id), AgentOrder::from(*order)))
+            .collect();
+        agents.sort_by(|this, other| ordering.compare(&this.0, this.1, &other.0, other.1));
+        agents.iter().map(|(id, _)| id.as_u64()).collect()
+    }
+
+    fn ordering_with(tie_break: TieBreak, seed: u64, activated: &[u64]) -> AgentOrdering {
+        let settings = OrderingSettings {
+            tie_break,
+            ..OrderingSettings::default()
+        };
+        let mut ordering = AgentOrdering::new(&settings, seed);
+        for id in activated {
+            ordering.activate(AgentId::from(*id));
+        }
+        ordering
+    }
+
+    #[test]
+    fn test_order_comes_before_tie_break() {
+        let agents = [(3, 1), (1, 1), (2, 0), (4, 0)];
+        let ordering = ordering_with(TieBreak::ReverseId, 0, &[3, 1, 2, 4]);
+        assert_eq!(sorted(&ordering, &agents), vec![4, 2, 3, 1]);
+        let ordering = ordering_with(TieBreak::Id, 0, &[]);
+        assert_eq!(sorted(&ordering, &agents), vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_insertion_follows_latest_activation() {
+        let agents = [(1, 0), (2, 0), (3, 0)];
+        let mut ordering = ordering_with(TieBreak::Insertion, 0, &[3, 1, 2]);
+        assert_eq!(sorted(&ordering, &agents), vec![3, 1, 2]);
+        ordering.activate(AgentId::from(3));
+        assert_eq!(sorted(&ordering, &agents), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_random_depends_only_on_seed() {
+        let agents: Vec<(u64, u32)> = (0..50).map(|id| (id, 0)).collect();
+        let ids: Vec<u64> = agents.iter().map(|(id, _)| *id).collect();
+        let reversed: Vec<u64> = ids.iter().rev().copied().collect();
+        let first = sorted(&ordering_with(TieBreak::Random, 7, &ids), &agents);
+        let again = sorted(&ordering_with(TieBreak::Random, 7, &reversed), &agents);
+        let other = sorted(&ordering_with(TieBreak::Random, 8, &ids), &agents);
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_ne!(first, ids);
+    }
+}
@@ -1,15 +1,98 @@
+use crate::control::Controller;
 use crate::scheduler::Scheduler;
 use crate::tui::{handle_sim_key_events, Tui};
 use crate::ui::{Message, SimContent, SimUIMetadata};
 use crossterm::event::{self, Event as CrosstermEvent};
-use log::info;
+use log::{info, warn};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
-use std::sync::mpsc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 use std::{io, thread};
 
-pub fn run_simulation<S>(mut scheduler: S, metadata: SimUIMetadata)
+const MEMORY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads the resident memory of the simulation process in kilobytes. The memory is read from
+/// the proc filesystem and is not available on other platforms.
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Registers the user signal (SIGUSR1) that requests a snapshot of all the active agents. The
+/// returned flag is checked by the simulation before every step.
+fn snapshot_requests() -> Arc<AtomicBool> {
+    let requests = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&requests))
+    {
+        warn!("Snapshots cannot be requested with SIGUSR1: {}", e);
+    }
+    requests
+}
+
+pub fn run_simulation<S>(scheduler: S, metadata: SimUIMetadata)
+where
+    S: Scheduler,
+{
+    run(scheduler, metadata, None);
+}
+
+/// Runs the simulation while accepting commands from the handle paired with the controller.
+pub fn run_controlled_simulation<S>(scheduler: S, metadata: SimUIMetadata, controller: Controller)
+where
+    S: Scheduler,
+{
+    run(scheduler, metadata, Some(controller));
+}
+
+/// Runs the simulation to the end without the user interface, e.g. in tests.
+pub fn run_headless<S>(mut scheduler: S)
+where
+    S: Scheduler,
+{
+    run_steps(&mut scheduler);
+    scheduler.terminate();
+}
+
+/// Runs the simulation to the end without the user interface and returns the KPIs at the end
+/// of the run, e.g. for the calibration runs of the fast mode.
+pub fn run_for_kpis<S>(mut scheduler: S) -> Vec<(String, f64)>
+where
+    S: Scheduler,
+{
+    run_steps(&mut scheduler);
+    let kpis = scheduler.kpis();
+    scheduler.terminate();
+    kpis
+}
+
+fn run_steps<S>(scheduler: &mut S)
+where
+    S: Scheduler,
+{
+    let end_time = scheduler.duration().as_u64();
+    let requests = snapshot_requests();
+    let mut now = 0;
+    scheduler.initialize();
+    while now < end_time {
+        if requests.swap(false, Ordering::Relaxed) {
+            scheduler.snapshot(None);
+        }
+        scheduler.activate();
+        scheduler.collect_stats();
+        now = scheduler.trigger().as_u64();
+    }
+}
+
+fn run<S>(mut scheduler: S, metadata: SimUIMetadata, mut controller: Option<Controller>)
 where
     S: Scheduler,
 {
@@ -17,10 +100,15 @@ where
     let sender = sender_ui.clone();
     let terminal_sender = sender_ui.clone();
     let duration = scheduler.duration().as_u64();
+    let requests = snapshot_requests();
+    let ui_requests = Arc::clone(&requests);
+    let ui_closed = Arc::new(AtomicBool::new(false));
+    let ui_closing = Arc::clone(&ui_closed);
 
     thread::scope(|s| {
         s.spawn(move || {
-            let mut ui_content = SimContent::new(duration, metadata);
+            let mut ui_content =
+                SimContent::new(duration, metadata).with_snapshot_requests(ui_requests);
             let backend = CrosstermBackend::new(io::stderr());
             let terminal = Terminal::new(backend).expect("failed to create terminal");
             let mut tui = Tui::new(terminal);
@@ -31,6 +119,10 @@ where
                 match receiver_ui.recv() {
                     Ok(message) => match message {
                         Message::CurrentTime(now) => ui_content.update_now(now),
+                        Message::Memory(memory_kb) => ui_content.update_memory(memory_kb),
+                        Message::StageTimes(times) => ui_content.update_stage_times(times),
+                        Message::Heatmap(heatmap) => ui_content.update_heatmap(*heatmap),
+                        Message::MemoryUsage(usage) => ui_content.update_memory_usage(usage),
                         Message::Quit => ui_content.quit(),
                         Message::Key(key_event) => {
                             handle_sim_key_events(key_event, &mut ui_content)
@@ -41,6 +133,7 @@ where
                     Err(_) => panic!("Error receiving message"),
                 }
             }
+            ui_closing.store(true, Ordering::Relaxed);
             tui.exit().expect("failed to exit");
         });
 
@@ -67,21 +160,62 @@ where
                 });
             });
             let mut now = 0;
+            let mut last_memory_check = Instant::now();
             scheduler.initialize();
             while now < end_time {
+                if let Some(ref mut controller) = controller {
+                    if !controller.apply_commands(&mut scheduler, &ui_closed) {
+                        info!(
+                            "User requested to quit while paused, terminating at {}",
+                            now
+                        );
+                        scheduler.terminate();
+                        controller.finish();
+                        return;
+                    }
+                }
+                if requests.swap(false, Ordering::Relaxed) {
+                    scheduler.snapshot(None);
+                }
                 scheduler.activate();
                 scheduler.collect_stats();
                 now = scheduler.trigger().as_u64();
+                if let Some(ref mut controller) = controller {
+                    controller.update_status(now, scheduler.kpis());
+                }
+                if let Some(stage_times) = scheduler.stage_times() {
+                    // A failure to send is caught when the time is sent below.
+                    let _ = terminal_sender.send(Message::StageTimes(stage_times));
+                }
+                if let Some(heatmap) = scheduler.heatmap() {
+                    let _ = terminal_sender.send(Message::Heatmap(Box::new(heatmap)));
+                }
+                if let Some(usage) = scheduler.memory_usage() {
+                    let _ = terminal_sender.send(Message::MemoryUsage(usage));
+                }
                 match terminal_sender.send(Message::CurrentTime(now)) {
                     Ok(_) => {}
                     Err(_) => {
                         info!("User must have requested to quit, terminating at {}", now);
                         scheduler.terminate();
+                        if let Some(ref mut controller) = controller {
+                            controller.finish();
+                        }
                         return;
                     }
                 };
+                if last_memory_check.elapsed() >= MEMORY_CHECK_INTERVAL {
+                    last_memory_check = Instant::now();
+                    if let Some(memory_kb) = resident_memory() {
+                        // A failure to send is caught when the time is sent in the next step.
+                        let _ = terminal_sender.send(Message::Memory(memory_kb));
+                    }
+                }
             }
             scheduler.terminate();
+            if let Some(ref mut controller) = controller {
+                controller.finish();
+            }
             sender_ui.send(Message::Quit).unwrap();
         });
     });
@@ -104,4 +238,10 @@ pub(crate) mod tests {
         let scheduler = create_map_scheduler();
         run_simulation(scheduler, SimUIMetadata::default());
     }
+
+    #[test]
+    fn test_run_headless() {
+        run_headless(create_scheduler());
+        run_headless(create_map_scheduler());
+    }
 }
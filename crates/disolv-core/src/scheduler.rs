@@ -1,9 +1,14 @@
 use crate::agent::{Agent, AgentId, AgentImpl, AgentOrder};
 use crate::bucket::{Bucket, TimeMS};
 use crate::core::Core;
+use crate::heatmap::HeatmapData;
+use crate::memory::MemoryUsage;
+use crate::streaming::StreamingController;
+use crate::timing::{Stage, StageTimer, StageTimes};
 use hashbrown::HashMap;
 use keyed_priority_queue::KeyedPriorityQueue;
-use log::debug;
+use log::{debug, warn};
+use tracing::info_span;
 use typed_builder::TypedBuilder;
 
 /// A trait used to represent a scheduler. A scheduler is used to schedule entities. The order
@@ -16,6 +21,16 @@ pub trait Scheduler: Send {
     fn collect_stats(&mut self);
     fn trigger(&mut self) -> TimeMS;
     fn terminate(self);
+    fn kpis(&self) -> Vec<(String, f64)>;
+    fn checkpoint(&mut self);
+    /// Dumps the debug view of the agent, or of all the active agents when no agent is given.
+    fn snapshot(&mut self, agent_id: Option<AgentId>);
+    /// Returns the stage times of the last completed output interval, once.
+    fn stage_times(&mut self) -> Option<StageTimes>;
+    /// Returns the heatmap of the last completed output interval, once.
+    fn heatmap(&mut self) -> Option<HeatmapData>;
+    /// Returns the memory held by the subsystems of the bucket when last sampled, once.
+    fn memory_usage(&mut self) -> Option<MemoryUsage>;
 }
 
 #[derive(TypedBuilder)]
@@ -38,6 +53,10 @@ where
     pub streaming_step: TimeMS,
     #[builder(default = TimeMS::default())]
     pub output_step: TimeMS,
+    #[builder(default)]
+    pub timer: StageTimer,
+    #[builder(default)]
+    pub streaming_controller: Option<StreamingController>,
 }
 
 impl<A, B> DefaultScheduler<A, B>
@@ -75,9 +94,11 @@ where
                 .add_agent(agent.agent_id, agent.agent.time_to_activation());
         }
         self.core.bucket.initialize(self.now);
+        self.timer.start();
     }
 
     fn activate(&mut self) {
+        let stage = self.timer.begin(Stage::Activation);
         if self.core.agent_cache.contains_key(&self.now) {
             let agent_ids = self.core.agent_cache.remove(&self.now).unwrap();
             for agent_id in agent_ids.iter() {
@@ -89,9 +110,11 @@ where
                     .activate();
             }
         }
+        self.timer.end(stage);
     }
 
     fn collect_stats(&mut self) {
+        let stage = self.timer.begin(Stage::Stats);
         for agent in self.agents.values() {
             if !agent.agent.is_deactivated() {
                 self.core
@@ -99,26 +122,47 @@ where
                     .insert(agent.agent_id, agent.agent.stats());
             }
         }
+        self.timer.end(stage);
     }
 
     fn trigger(&mut self) -> TimeMS {
+        let _step = info_span!("step", now = self.now.as_u64()).entered();
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.before_agents(self.now);
+        self.timer.end(stage);
 
         // This should be moved out of here.
         if self.now == self.streaming_step {
+            let stage = self.timer.begin(Stage::Input);
+            if let Some(ref mut controller) = self.streaming_controller {
+                let displacement = self.core.bucket.displacement();
+                self.streaming_interval = controller.adapt(displacement);
+                self.core.bucket.set_streaming_interval(
+                    self.now,
+                    self.streaming_interval,
+                    displacement,
+                );
+            }
             self.core.bucket.stream_input(self.now);
+            self.timer.end(stage);
             self.streaming_step += self.streaming_interval;
         }
 
         // This should be moved out of here.
         if self.now == self.output_step {
+            self.timer.end_interval();
+            let stage = self.timer.begin(Stage::Output);
             self.core.bucket.stream_output(self.now);
+            self.timer.end(stage);
             self.output_step += self.output_interval;
         }
 
         // Early return if the agent queue is empty.
         if self.agent_queue.is_empty() {
+            let stage = self.timer.begin(Stage::BucketHooks);
             self.core.bucket.after_agents();
+            self.timer.end(stage);
+            self.timer.end_step();
             self.now += self.step_size;
             return self.now;
         }
@@ -135,6 +179,7 @@ where
             }
         }
 
+        let stage = self.timer.begin(Stage::StageOne);
         agent_ids.iter_mut().rev().for_each(|agent_id| {
             self.agents
                 .get_mut(agent_id)
@@ -142,8 +187,12 @@ where
                 .agent
                 .stage_one(&mut self.core);
         });
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_one();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageTwo);
         agent_ids.iter_mut().for_each(|agent_id| {
             self.agents
                 .get_mut(agent_id)
@@ -151,8 +200,12 @@ where
                 .agent
                 .stage_two_reverse(&mut self.core);
         });
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_two();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageThree);
         agent_ids.iter_mut().rev().for_each(|agent_id| {
             self.agents
                 .get_mut(agent_id)
@@ -160,8 +213,12 @@ where
                 .agent
                 .stage_three(&mut self.core);
         });
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_three();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageFour);
         agent_ids.iter_mut().for_each(|agent_id| {
             self.agents
                 .get_mut(agent_id)
@@ -169,8 +226,12 @@ where
                 .agent
                 .stage_four_reverse(&mut self.core);
         });
+        self.timer.end(stage);
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_stage_four();
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::StageFive);
         agent_ids.iter_mut().rev().for_each(|agent_id| {
             self.agents
                 .get_mut(agent_id)
@@ -178,8 +239,11 @@ where
                 .agent
                 .stage_five(&mut self.core);
         });
+        self.timer.end(stage);
 
+        let stage = self.timer.begin(Stage::BucketHooks);
         self.core.bucket.after_agents();
+        self.timer.end(stage);
 
         // Reschedule the agents if not stopped.
         for agent_id in agent_ids.into_iter() {
@@ -189,13 +253,54 @@ where
             self.add_to_queue(agent_id, self.agent_of(&agent_id).order());
         }
 
+        self.timer.end_step();
         self.now += self.step_size;
         self.now
     }
 
-    fn terminate(self) {
+    fn terminate(mut self) {
+        self.core.bucket.record_performance(&self.timer.summary());
         self.core.bucket.terminate(self.now);
     }
+
+    fn kpis(&self) -> Vec<(String, f64)> {
+        self.core.bucket.kpis()
+    }
+
+    fn checkpoint(&mut self) {
+        self.core.bucket.checkpoint(self.now);
+    }
+
+    fn snapshot(&mut self, agent_id: Option<AgentId>) {
+        let views: Vec<serde_json::Value> = self
+            .agents
+            .values()
+            .filter(|agent| match agent_id {
+                Some(agent_id) => agent.agent_id == agent_id,
+                None => !agent.agent.is_deactivated(),
+            })
+            .map(|agent| agent.agent.debug_view(&self.core.bucket))
+            .collect();
+        if agent_id.is_some() && views.is_empty() {
+            warn!(
+                "Agent {:?} is not in the simulation, nothing to dump",
+                agent_id
+            );
+        }
+        self.core.bucket.write_snapshot(self.now, agent_id, views);
+    }
+
+    fn stage_times(&mut self) -> Option<StageTimes> {
+        self.timer.take_completed()
+    }
+
+    fn heatmap(&mut self) -> Option<HeatmapData> {
+        self.core.bucket.heatmap()
+    }
+
+    fn memory_usage(&mut self) -> Option<MemoryUsage> {
+        self.core.bucket.memory_usage()
+    }
 }
 
 #[cfg(test)]
@@ -230,6 +335,8 @@ pub(crate) mod tests {
             output_step: TimeMS::from(0),
             step_size: TimeMS::from(100),
             now: TimeMS::from(0),
+            timer: StageTimer::default(),
+            streaming_controller: None,
         }
     }
 
@@ -254,4 +361,22 @@ pub(crate) mod tests {
         scheduler.trigger();
         assert_eq!(scheduler.now, TimeMS::from(100));
     }
+
+    #[test]
+    fn test_snapshot() {
+        let mut scheduler = create_scheduler();
+        scheduler.snapshot(Some(AgentId::from(7)));
+        scheduler.snapshot(Some(AgentId::from(100000)));
+        scheduler.snapshot(None);
+        let snapshots = &scheduler.core.bucket.snapshots;
+        assert_eq!(
+            snapshots[0],
+            (
+                Some(AgentId::from(7)),
+                vec![serde_json::json!({ "id": 7, "size": 0.0 })]
+            )
+        );
+        assert!(snapshots[1].1.is_empty());
+        assert_eq!(snapshots[2].1.len(), 100000);
+    }
 }
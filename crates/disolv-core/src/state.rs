@@ -0,0 +1,246 @@
+use log::debug;
+use std::fmt::Debug;
+
+/// State of a state machine. Every kind of state machine has an id of its own so that the
+/// changes of different machines can be written to the same table.
+pub trait MachineState: Copy + Eq + Debug {
+    const MACHINE: u32;
+
+    fn as_int(&self) -> u32;
+}
+
+/// Condition on the context of the machine that must hold for a transition to be taken.
+pub type Guard<C> = fn(&C) -> bool;
+
+/// Hook run on the context of the machine when a state is entered or exited.
+pub type Hook<C> = fn(&mut C);
+
+#[derive(Clone, Debug)]
+struct Transition<S, C> {
+    from: S,
+    to: S,
+    guard: Option<Guard<C>>,
+}
+
+/// A change of the state taken by the machine. Changes are kept until the owner of the machine
+/// takes them, e.g. to write them to the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateChange<S: MachineState> {
+    pub from: S,
+    pub to: S,
+}
+
+/// State machine with the allowed transitions between the states of `S`. Transitions can be
+/// guarded by a condition on the context `C`, and the hooks of a state are run on the context
+/// when it is entered or exited.
+#[derive(Clone, Debug)]
+pub struct StateMachine<S: MachineState, C> {
+    state: S,
+    transitions: Vec<Transition<S, C>>,
+    entry_hooks: Vec<(S, Hook<C>)>,
+    exit_hooks: Vec<(S, Hook<C>)>,
+    changes: Vec<StateChange<S>>,
+}
+
+impl<S: MachineState, C> StateMachine<S, C> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            state: initial,
+            transitions: Vec::new(),
+            entry_hooks: Vec::new(),
+            exit_hooks: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Allows the transition, which is taken only when it is requested with `move_to`.
+    pub fn transition(mut self, from: S, to: S) -> Self {
+        self.transitions.push(Transition {
+            from,
+            to,
+            guard: None,
+        });
+        self
+    }
+
+    /// Allows the transition when the guard holds. Guarded transitions are also taken by
+    /// `advance` without being requested.
+    pub fn guarded(mut self, from: S, to: S, guard: Guard<C>) -> Self {
+        self.transitions.push(Transition {
+            from,
+            to,
+            guard: Some(guard),
+        });
+        self
+    }
+
+    pub fn on_entry(mut self, state: S, hook: Hook<C>) -> Self {
+        self.entry_hooks.push((state, hook));
+        self
+    }
+
+    pub fn on_exit(mut self, state: S, hook: Hook<C>) -> Self {
+        self.exit_hooks.push((state, hook));
+        self
+    }
+
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    pub fn is_in(&self, state: S) -> bool {
+        self.state == state
+    }
+
+    /// Moves to the state if the transition is allowed and its guard holds. Moving to the
+    /// current state does nothing. Returns whether the state is the requested state.
+    pub fn move_to(&mut self, to: S, context: &mut C) -> bool {
+        if self.state == to {
+            return true;
+        }
+        let allowed = self.transitions.iter().any(|transition| {
+            transition.from == self.state
+                && transition.to == to
+                && transition.guard.is_none_or(|guard| guard(context))
+        });
+        if !allowed {
+            debug!(
+                "Transition from {:?} to {:?} is not allowed",
+                self.state, to
+            );
+            return false;
+        }
+        self.change_to(to, context);
+        true
+    }
+
+    /// Takes the first guarded transition from the current state whose guard holds. Returns
+    /// whether the state changed.
+    pub fn advance(&mut self, context: &mut C) -> bool {
+        let next = self
+            .transitions
+            .iter()
+            .filter(|transition| transition.from == self.state)
+            .find(|transition| transition.guard.is_some_and(|guard| guard(context)))
+            .map(|transition| transition.to);
+        match next {
+            Some(to) => {
+                self.change_to(to, context);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Takes the changes made since the last call.
+    pub fn take_changes(&mut self) -> Vec<StateChange<S>> {
+        std::mem::take(&mut self.changes)
+    }
+
+    fn change_to(&mut self, to: S, context: &mut C) {
+        let from = self.state;
+        self.exit_hooks
+            .iter()
+            .filter(|(state, _)| *state == from)
+            .for_each(|(_, hook)| hook(context));
+        self.state = to;
+        self.entry_hooks
+            .iter()
+            .filter(|(state, _)| *state == to)
+            .for_each(|(_, hook)| hook(context));
+        debug!("State changed from {:?} to {:?}", from, to);
+        self.changes.push(StateChange { from, to });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Charge {
+        Driving,
+        Queued,
+        Charging,
+    }
+
+    impl MachineState for Charge {
+        const MACHINE: u32 = 99;
+
+        fn as_int(&self) -> u32 {
+            match self {
+                Charge::Driving => 0,
+                Charge::Queued => 1,
+                Charge::Charging => 2,
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Vehicle {
+        battery: u32,
+        free_chargers: u32,
+        entered_charging: u32,
+        left_driving: u32,
+    }
+
+    fn machine() -> StateMachine<Charge, Vehicle> {
+        StateMachine::<Charge, Vehicle>::new(Charge::Driving)
+            .guarded(Charge::Driving, Charge::Queued, |v| v.battery < 20)
+            .guarded(Charge::Queued, Charge::Charging, |v| v.free_chargers > 0)
+            .transition(Charge::Charging, Charge::Driving)
+            .on_entry(Charge::Charging, |v| v.entered_charging += 1)
+            .on_exit(Charge::Driving, |v| v.left_driving += 1)
+    }
+
+    #[test]
+    fn test_advance_takes_guarded_transitions() {
+        let mut vehicle = Vehicle {
+            battery: 50,
+            ..Default::default()
+        };
+        let mut machine = machine();
+        assert!(!machine.advance(&mut vehicle));
+        vehicle.battery = 10;
+        assert!(machine.advance(&mut vehicle));
+        assert!(machine.is_in(Charge::Queued));
+        assert!(!machine.advance(&mut vehicle));
+        vehicle.free_chargers = 1;
+        assert!(machine.advance(&mut vehicle));
+        assert_eq!(machine.state(), Charge::Charging);
+        assert_eq!(vehicle.entered_charging, 1);
+        assert_eq!(vehicle.left_driving, 1);
+        assert_eq!(
+            machine.take_changes(),
+            vec![
+                StateChange {
+                    from: Charge::Driving,
+                    to: Charge::Queued
+                },
+                StateChange {
+                    from: Charge::Queued,
+                    to: Charge::Charging
+                },
+            ]
+        );
+        assert!(machine.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_move_to_only_allowed_states() {
+        let mut vehicle = Vehicle::default();
+        let mut machine = machine();
+        assert!(!machine.move_to(Charge::Charging, &mut vehicle));
+        assert!(machine.move_to(Charge::Driving, &mut vehicle));
+        assert!(machine.take_changes().is_empty());
+        vehicle.battery = 5;
+        assert!(machine.move_to(Charge::Queued, &mut vehicle));
+        assert!(!machine.move_to(Charge::Charging, &mut vehicle));
+        vehicle.free_chargers = 2;
+        assert!(machine.move_to(Charge::Charging, &mut vehicle));
+        assert!(!machine.advance(&mut vehicle));
+        assert!(machine.move_to(Charge::Driving, &mut vehicle));
+        assert_eq!(machine.take_changes().len(), 3);
+        assert_eq!(Charge::Driving.as_int(), 0);
+    }
+}
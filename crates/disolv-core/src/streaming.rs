@@ -0,0 +1,122 @@
+use crate::bucket::TimeMS;
+use serde::Deserialize;
+
+/// Settings of the adaptive streaming interval. The interval is chosen in every streaming step
+/// so that the agents move about `target_displacement` meters on average between the streaming
+/// steps, within `min_interval` and `max_interval`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct AdaptiveStreamingSettings {
+    pub min_interval: TimeMS,
+    pub max_interval: TimeMS,
+    pub target_displacement: f64,
+}
+
+/// Chooses the streaming interval from the average displacement of the agents in the last
+/// interval. The intervals are multiples of the step size.
+#[derive(Clone, Debug)]
+pub struct StreamingController {
+    settings: AdaptiveStreamingSettings,
+    step_size: TimeMS,
+    interval: TimeMS,
+}
+
+impl StreamingController {
+    /// Controller that starts with the initial interval, bounded by the settings.
+    pub fn new(settings: &AdaptiveStreamingSettings, initial: TimeMS, step_size: TimeMS) -> Self {
+        if step_size.as_u64() == 0 {
+            panic!("StreamingController::new: the step size must be positive");
+        }
+        if settings.min_interval.as_u64() < step_size.as_u64()
+            || settings.min_interval.as_u64() > settings.max_interval.as_u64()
+        {
+            panic!(
+                "Adaptive streaming needs {} <= min_interval <= max_interval, got {} and {}",
+                step_size, settings.min_interval, settings.max_interval
+            );
+        }
+        if settings.target_displacement <= 0.0 {
+            panic!(
+                "Adaptive streaming needs a positive target_displacement, got {}",
+                settings.target_displacement
+            );
+        }
+        let mut controller = Self {
+            settings: *settings,
+            step_size,
+            interval: initial,
+        };
+        controller.interval = controller.bounded(initial.as_u64() as f64);
+        controller
+    }
+
+    pub fn interval(&self) -> TimeMS {
+        self.interval
+    }
+
+    /// Chooses the next interval from the average displacement in the last interval. The
+    /// interval is kept without a displacement and is longest when the agents did not move.
+    pub fn adapt(&mut self, displacement: Option<f64>) -> TimeMS {
+        let displacement = match displacement {
+            Some(displacement) => displacement,
+            None => return self.interval,
+        };
+        self.interval = match displacement > 0.0 {
+            true => self.bounded(
+                self.interval.as_u64() as f64 * self.settings.target_displacement / displacement,
+            ),
+            false => self.bounded(self.settings.max_interval.as_u64() as f64),
+        };
+        self.interval
+    }
+
+    fn bounded(&self, interval: f64) -> TimeMS {
+        let step_size = self.step_size.as_u64();
+        let interval = (interval as u64).clamp(
+            self.settings.min_interval.as_u64(),
+            self.settings.max_interval.as_u64(),
+        );
+        TimeMS::from((interval / step_size).max(1) * step_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> StreamingController {
+        let settings = AdaptiveStreamingSettings {
+            min_interval: TimeMS::from(500),
+            max_interval: TimeMS::from(5000),
+            target_displacement: 10.0,
+        };
+        StreamingController::new(&settings, TimeMS::from(1000), TimeMS::from(100))
+    }
+
+    #[test]
+    fn test_interval_follows_displacement() {
+        let mut controller = controller();
+        assert_eq!(controller.adapt(None), TimeMS::from(1000));
+        assert_eq!(controller.adapt(Some(5.0)), TimeMS::from(2000));
+        assert_eq!(controller.adapt(Some(30.0)), TimeMS::from(600));
+        assert_eq!(controller.adapt(Some(9.0)), TimeMS::from(600));
+    }
+
+    #[test]
+    fn test_interval_is_bounded() {
+        let mut controller = controller();
+        assert_eq!(controller.adapt(Some(100.0)), TimeMS::from(500));
+        assert_eq!(controller.adapt(Some(0.0)), TimeMS::from(5000));
+        assert_eq!(controller.adapt(Some(1.0)), TimeMS::from(5000));
+    }
+
+    #[test]
+    #[should_panic(expected = "min_interval <= max_interval")]
+    fn test_inverted_bounds_are_rejected() {
+        let settings = AdaptiveStreamingSettings {
+            min_interval: TimeMS::from(2000),
+            max_interval: TimeMS::from(1000),
+            target_displacement: 10.0,
+        };
+        StreamingController::new(&settings, TimeMS::from(1000), TimeMS::from(100));
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+use tracing::info_span;
+use tracing::span::EnteredSpan;
+
+pub const STAGE_COUNT: usize = 10;
+
+/// Parts of a simulation step that are timed separately.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    Activation,
+    Stats,
+    StageOne,
+    StageTwo,
+    StageThree,
+    StageFour,
+    StageFive,
+    BucketHooks,
+    Input,
+    Output,
+}
+
+pub const STAGES: [Stage; STAGE_COUNT] = [
+    Stage::Activation,
+    Stage::Stats,
+    Stage::StageOne,
+    Stage::StageTwo,
+    Stage::StageThree,
+    Stage::StageFour,
+    Stage::StageFive,
+    Stage::BucketHooks,
+    Stage::Input,
+    Stage::Output,
+];
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Activation => "activation",
+            Stage::Stats => "stats",
+            Stage::StageOne => "stage_one",
+            Stage::StageTwo => "stage_two",
+            Stage::StageThree => "stage_three",
+            Stage::StageFour => "stage_four",
+            Stage::StageFive => "stage_five",
+            Stage::BucketHooks => "bucket_hooks",
+            Stage::Input => "input",
+            Stage::Output => "output",
+        }
+    }
+}
+
+/// A stage in progress. The stage is entered as a tracing span, so that the subscribers see
+/// the stages of every step and their timings.
+#[derive(Debug)]
+pub struct StageRun {
+    stage: Stage,
+    start: Instant,
+    _span: EnteredSpan,
+}
+
+/// Time spent in each stage over a number of steps. The wall time includes the time spent
+/// outside the stages, e.g. in the user interface.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StageTimes {
+    pub steps: u64,
+    pub wall_time: Duration,
+    pub stages: [Duration; STAGE_COUNT],
+}
+
+impl StageTimes {
+    pub fn of(&self, stage: Stage) -> Duration {
+        self.stages[stage as usize]
+    }
+
+    pub fn ms_per_step(&self, stage: Stage) -> f64 {
+        match self.steps {
+            0 => 0.0,
+            steps => self.of(stage).as_secs_f64() * 1000.0 / steps as f64,
+        }
+    }
+
+    /// Share of the wall time spent in the stage.
+    pub fn share(&self, stage: Stage) -> f64 {
+        match self.wall_time.is_zero() {
+            true => 0.0,
+            false => self.of(stage).as_secs_f64() / self.wall_time.as_secs_f64(),
+        }
+    }
+}
+
+/// Collects the stage times of the whole simulation and of the current output interval.
+#[derive(Clone, Debug, Default)]
+pub struct StageTimer {
+    started: Option<Instant>,
+    interval_started: Option<Instant>,
+    interval: StageTimes,
+    total: StageTimes,
+    completed: Option<StageTimes>,
+}
+
+impl StageTimer {
+    pub fn start(&mut self) {
+        let now = Instant::now();
+        self.started = Some(now);
+        self.interval_started = Some(now);
+    }
+
+    /// Adds the time elapsed since `start` to the stage.
+    pub fn record(&mut self, stage: Stage, start: Instant) {
+        let elapsed = start.elapsed();
+        self.interval.stages[stage as usize] += elapsed;
+        self.total.stages[stage as usize] += elapsed;
+    }
+
+    /// Starts timing the stage and enters its span.
+    pub fn begin(&self, stage: Stage) -> StageRun {
+        StageRun {
+            stage,
+            start: Instant::now(),
+            _span: info_span!("stage", name = stage.name()).entered(),
+        }
+    }
+
+    /// Adds the time of the stage run to the stage and exits its span.
+    pub fn end(&mut self, run: StageRun) {
+        self.record(run.stage, run.start);
+    }
+
+    pub fn end_step(&mut self) {
+        self.interval.steps += 1;
+        self.total.steps += 1;
+    }
+
+    /// Closes the current output interval. Intervals without any steps are ignored.
+    pub fn end_interval(&mut self) {
+        let now = Instant::now();
+        if let Some(interval_started) = self.interval_started {
+            self.interval.wall_time = now.duration_since(interval_started);
+        }
+        self.interval_started = Some(now);
+        let interval = std::mem::take(&mut self.interval);
+        if interval.steps > 0 {
+            self.completed = Some(interval);
+        }
+    }
+
+    /// Returns the times of the last completed output interval, if not taken already.
+    pub fn take_completed(&mut self) -> Option<StageTimes> {
+        self.completed.take()
+    }
+
+    pub fn summary(&self) -> StageTimes {
+        let mut summary = self.total;
+        if let Some(started) = self.started {
+            summary.wall_time = started.elapsed();
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_timer() {
+        let mut timer = StageTimer::default();
+        timer.start();
+        timer.end_interval();
+        assert!(timer.take_completed().is_none());
+
+        for _ in 0..4 {
+            let start = Instant::now();
+            std::thread::sleep(Duration::from_millis(2));
+            timer.record(Stage::StageOne, start);
+            timer.end_step();
+        }
+        timer.end_interval();
+        let interval = timer.take_completed().expect("interval must be completed");
+        assert_eq!(interval.steps, 4);
+        assert!(interval.ms_per_step(Stage::StageOne) >= 2.0);
+        assert_eq!(interval.of(Stage::StageTwo), Duration::ZERO);
+        assert!(interval.share(Stage::StageOne) <= 1.0);
+        assert!(timer.take_completed().is_none());
+
+        let summary = timer.summary();
+        assert_eq!(summary.steps, 4);
+        assert!(summary.wall_time >= summary.of(Stage::StageOne));
+
+        let run = timer.begin(Stage::StageTwo);
+        std::thread::sleep(Duration::from_millis(2));
+        timer.end(run);
+        assert!(timer.summary().of(Stage::StageTwo) >= Duration::from_millis(2));
+    }
+}
@@ -81,6 +81,8 @@ pub fn handle_sim_key_events(key_event: KeyEvent, content: &mut SimContent) {
     match key_event.code {
         // Other handlers you could add here.
         KeyCode::Esc | KeyCode::Char('q') => content.quit(),
+        KeyCode::Char('h') => content.toggle_heatmap(),
+        KeyCode::Char('d') => content.request_snapshot(),
         _ => {}
     }
 }
@@ -90,6 +92,7 @@ pub fn handle_link_key_events(key_event: KeyEvent, content: &mut LinkContent) {
     match key_event.code {
         // Other handlers you could add here.
         KeyCode::Esc | KeyCode::Char('q') => content.quit(),
+        KeyCode::Char('h') => content.toggle_heatmap(),
         _ => {}
     }
 }
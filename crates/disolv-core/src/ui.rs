@@ -1,5 +1,8 @@
+use crate::heatmap::HeatmapData;
+use crate::memory::MemoryUsage;
+use crate::timing::{StageTimes, STAGES};
 use crossterm::event::{KeyEvent, MouseEvent};
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::widgets::{Borders, Gauge};
 use ratatui::{
     layout::Alignment,
@@ -8,15 +11,22 @@ use ratatui::{
     Frame,
 };
 use std::error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub type ContentResult<T> = Result<T, Box<dyn error::Error>>;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Message {
     Key(KeyEvent),
     Mouse(MouseEvent),
     Resize(u16, u16),
     CurrentTime(u64),
+    Memory(u64),
+    StageTimes(StageTimes),
+    Heatmap(Box<HeatmapData>),
+    MemoryUsage(MemoryUsage),
     Quit,
 }
 
@@ -37,6 +47,13 @@ pub struct SimContent {
     pub metadata: SimUIMetadata,
     pub total_agents: usize,
     pub active_agents: usize,
+    pub memory_kb: u64,
+    pub memory_usage: Option<MemoryUsage>,
+    pub started: Option<Instant>,
+    pub stage_times: StageTimes,
+    pub heatmap: Option<HeatmapData>,
+    pub hide_heatmap: bool,
+    pub snapshot_requests: Arc<AtomicBool>,
 }
 
 impl SimContent {
@@ -45,6 +62,7 @@ impl SimContent {
             total_steps,
             running: true,
             metadata,
+            started: Some(Instant::now()),
             ..Self::default()
         }
     }
@@ -58,9 +76,69 @@ impl SimContent {
         self.now = now;
     }
 
+    pub fn update_memory(&mut self, memory_kb: u64) {
+        self.memory_kb = memory_kb;
+    }
+
+    pub fn update_memory_usage(&mut self, usage: MemoryUsage) {
+        self.memory_usage = Some(usage);
+    }
+
+    pub fn update_stage_times(&mut self, stage_times: StageTimes) {
+        self.stage_times = stage_times;
+    }
+
+    pub fn update_heatmap(&mut self, heatmap: HeatmapData) {
+        self.heatmap = Some(heatmap);
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.hide_heatmap = !self.hide_heatmap;
+    }
+
+    /// Shares the flag the simulation checks before every step to dump all the agents.
+    pub fn with_snapshot_requests(mut self, snapshot_requests: Arc<AtomicBool>) -> Self {
+        self.snapshot_requests = snapshot_requests;
+        self
+    }
+
+    pub fn request_snapshot(&mut self) {
+        self.snapshot_requests.store(true, Ordering::Relaxed);
+    }
+
+    fn visible_heatmap(&self) -> Option<&HeatmapData> {
+        match self.hide_heatmap {
+            true => None,
+            false => self.heatmap.as_ref(),
+        }
+    }
+
     pub fn completion(&self) -> f64 {
         self.now as f64 / self.total_steps as f64
     }
+
+    /// Estimates the remaining wall time assuming the rest of the simulation runs at the
+    /// average speed so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let completion = self.completion();
+        if completion <= 0.0 || !completion.is_finite() {
+            return None;
+        }
+        let elapsed = self.started?.elapsed().as_secs_f64();
+        Some(Duration::from_secs_f64(
+            elapsed * (1.0 - completion).max(0.0) / completion,
+        ))
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
 }
 
 #[derive(Debug, Clone, Default)]
@@ -75,6 +153,8 @@ pub struct LinkContent {
     pub total_steps: u64,
     pub now: u64,
     pub metadata: LinkUIMetadata,
+    pub heatmap: Option<HeatmapData>,
+    pub hide_heatmap: bool,
 }
 
 impl LinkContent {
@@ -96,11 +176,57 @@ impl LinkContent {
         self.now = now;
     }
 
+    pub fn update_heatmap(&mut self, heatmap: HeatmapData) {
+        self.heatmap = Some(heatmap);
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.hide_heatmap = !self.hide_heatmap;
+    }
+
+    fn visible_heatmap(&self) -> Option<&HeatmapData> {
+        match self.hide_heatmap {
+            true => None,
+            false => self.heatmap.as_ref(),
+        }
+    }
+
     pub fn completion(&self) -> f64 {
         self.now as f64 / self.total_steps as f64
     }
 }
 
+/// Splits the area to make room for the heatmap on the right, when there is one to show.
+fn with_heatmap_area(area: Rect, heatmap: Option<&HeatmapData>) -> (Rect, Option<Rect>) {
+    match heatmap {
+        Some(_) => {
+            let split = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(area);
+            (split[0], Some(split[1]))
+        }
+        None => (area, None),
+    }
+}
+
+fn render_heatmap(heatmap: &HeatmapData, frame: &mut Frame, area: Rect) {
+    let title = format!(
+        "{} at {} (max {}, total {})",
+        heatmap.kind.name(),
+        heatmap.step,
+        heatmap.max(),
+        heatmap.total()
+    );
+    frame.render_widget(
+        Paragraph::new(heatmap.render().join("\n"))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(Style::default().fg(Color::LightYellow).bg(Color::Black))
+            .alignment(Alignment::Center),
+        area,
+    );
+}
+
 /// Renders the user interface widgets.
 pub(crate) fn render_sim_ui(content: &mut SimContent, frame: &mut Frame) {
     // This is where you add new widgets.
@@ -110,9 +236,10 @@ pub(crate) fn render_sim_ui(content: &mut SimContent, frame: &mut Frame) {
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
-            Constraint::Percentage(30),
-            Constraint::Percentage(20),
-            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
         ])
         .split(frame.size());
 
@@ -138,11 +265,16 @@ pub(crate) fn render_sim_ui(content: &mut SimContent, frame: &mut Frame) {
     );
 
     let completion = content.completion();
+    let eta = match content.eta() {
+        Some(eta) => format_duration(eta),
+        None => "-".to_string(),
+    };
     let progress_text = format!(
-        "Time Step: {} / {} steps. {:.2}%. ",
+        "Time Step: {} / {} steps. {:.2}%. ETA: {}. ",
         content.now,
         content.total_steps,
-        content.completion() * 100.0
+        content.completion() * 100.0,
+        eta
     );
     frame.render_widget(
         Gauge::default()
@@ -164,19 +296,64 @@ pub(crate) fn render_sim_ui(content: &mut SimContent, frame: &mut Frame) {
         layout[1],
     );
 
-    let simulation_details = format!(
+    let (details_area, heatmap_area) = with_heatmap_area(layout[2], content.visible_heatmap());
+    let mut simulation_details = format!(
         "Input File: {}\n\
         Output Path: {}\n\
         Log Path: {}\n\
+        Memory Usage: {:.1} MB\n\
         ",
-        content.metadata.input_file, content.metadata.output_path, content.metadata.log_path,
+        content.metadata.input_file,
+        content.metadata.output_path,
+        content.metadata.log_path,
+        content.memory_kb as f64 / 1024.0,
     );
+    if let Some(usage) = content.memory_usage.as_ref() {
+        for subsystem in usage.subsystems() {
+            simulation_details.push_str(&format!(
+                "  {:<16} {:>10} entries {:>10.1} MB{}\n",
+                subsystem.name,
+                subsystem.entries,
+                subsystem.bytes as f64 / (1024.0 * 1024.0),
+                if subsystem.over_cap {
+                    " (over cap)"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
     frame.render_widget(
         Paragraph::new(simulation_details)
             .block(Block::default().borders(Borders::ALL).title("More details"))
             .style(Style::default().fg(Color::White).bg(Color::Black))
             .alignment(Alignment::Left),
-        layout[2],
+        details_area,
+    );
+    if let (Some(heatmap), Some(area)) = (content.visible_heatmap(), heatmap_area) {
+        render_heatmap(heatmap, frame, area);
+    }
+
+    let stage_times = &content.stage_times;
+    let mut performance = format!(
+        "Last output interval: {} steps in {} ms\n",
+        stage_times.steps,
+        stage_times.wall_time.as_millis()
+    );
+    for stage in STAGES.iter() {
+        performance.push_str(&format!(
+            "{:<14} {:>10.3} ms/step {:>6.1}%\n",
+            stage.name(),
+            stage_times.ms_per_step(*stage),
+            stage_times.share(*stage) * 100.0
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(performance)
+            .block(Block::default().borders(Borders::ALL).title("Performance"))
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .alignment(Alignment::Left),
+        layout[3],
     );
 }
 
@@ -213,6 +390,7 @@ pub(crate) fn render_link_ui(content: &mut LinkContent, frame: &mut Frame) {
         layout[0],
     );
 
+    let (details_area, heatmap_area) = with_heatmap_area(layout[1], content.visible_heatmap());
     let simulation_details = format!(
         "Input File: {}\n\
         Output Path: {}\n\
@@ -224,6 +402,9 @@ pub(crate) fn render_link_ui(content: &mut LinkContent, frame: &mut Frame) {
             .block(Block::default().borders(Borders::ALL).title("More details"))
             .style(Style::default().fg(Color::White).bg(Color::Black))
             .alignment(Alignment::Left),
-        layout[1],
+        details_area,
     );
+    if let (Some(heatmap), Some(area)) = (content.visible_heatmap(), heatmap_area) {
+        render_heatmap(heatmap, frame, area);
+    }
 }
@@ -0,0 +1,126 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::net::message::{DPayload, TxMetrics, TxStatus};
+use disolv_models::net::metrics::Bandwidth;
+use log::{info, warn};
+use serde::Deserialize;
+
+/// Settings of the comparison of the simulated slice with its analytical baseline. A KPI is
+/// flagged when it differs from its expectation by more than the relative `tolerance`, 0.1 by
+/// default.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct BaselineSettings {
+    pub tolerance: Option<f64>,
+}
+
+/// Expected and simulated values of a KPI of the slice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+    pub expected: f64,
+    pub simulated: f64,
+}
+
+impl Comparison {
+    fn deviation(&self) -> f64 {
+        match self.expected > 0.0 {
+            true => ((self.simulated - self.expected) / self.expected).abs(),
+            false => self.simulated.abs(),
+        }
+    }
+}
+
+/// Compares the transfers over a single slice with a capacity to a fluid model of the slice.
+/// The bytes offered to the slice are served up to its capacity, so that the slice is expected
+/// to be used at the offered load `rho`, the offered bytes over the capacity, up to one, and to
+/// deliver the share 1 / `rho` of the offered bytes when it is overloaded. Transfers lost for
+/// other reasons than the capacity, or arrivals that are burstier than the steps, show up as
+/// mismatches.
+#[derive(Clone, Debug)]
+pub struct Baseline {
+    tolerance: f64,
+    step_size: TimeMS,
+    capacity_bytes: f64,
+    offered_bytes: u64,
+    delivered_bytes: u64,
+}
+
+impl Baseline {
+    pub fn new(settings: &BaselineSettings, step_size: TimeMS) -> Self {
+        Self {
+            tolerance: settings.tolerance.unwrap_or(0.1),
+            step_size,
+            capacity_bytes: 0.0,
+            offered_bytes: 0,
+            delivered_bytes: 0,
+        }
+    }
+
+    /// Adds the capacity of the slice in the step.
+    pub fn start_step(&mut self, capacity: Bandwidth) {
+        self.capacity_bytes += capacity.as_u64() as f64 * self.step_size.as_u64() as f64 / 1000.0;
+    }
+
+    pub fn record(&mut self, payload: &DPayload, tx_metrics: &TxMetrics) {
+        let bytes = payload.metadata.total_size.as_u64();
+        self.offered_bytes += bytes;
+        if tx_metrics.tx_status == TxStatus::Ok {
+            self.delivered_bytes += bytes;
+        }
+    }
+
+    fn load(&self) -> f64 {
+        match self.capacity_bytes > 0.0 {
+            true => self.offered_bytes as f64 / self.capacity_bytes,
+            false => 0.0,
+        }
+    }
+
+    pub fn utilization(&self) -> Comparison {
+        Comparison {
+            expected: self.load().min(1.0),
+            simulated: match self.capacity_bytes > 0.0 {
+                true => self.delivered_bytes as f64 / self.capacity_bytes,
+                false => 0.0,
+            },
+        }
+    }
+
+    pub fn delivery_ratio(&self) -> Comparison {
+        Comparison {
+            expected: match self.load() {
+                load if load > 1.0 => 1.0 / load,
+                _ => 1.0,
+            },
+            simulated: match self.offered_bytes {
+                0 => 1.0,
+                offered => self.delivered_bytes as f64 / offered as f64,
+            },
+        }
+    }
+
+    /// Names and values of the KPIs that deviate from their expectation beyond the tolerance.
+    pub fn mismatches(&self) -> Vec<(&'static str, Comparison)> {
+        [
+            ("utilization", self.utilization()),
+            ("delivery ratio", self.delivery_ratio()),
+        ]
+        .into_iter()
+        .filter(|(_, comparison)| comparison.deviation() > self.tolerance)
+        .collect()
+    }
+
+    /// Logs the comparison at the end of the run and flags the mismatches.
+    pub fn report(&self) {
+        info!(
+            "Slice load {:.3}, utilization {:?}, delivery ratio {:?}",
+            self.load(),
+            self.utilization(),
+            self.delivery_ratio()
+        );
+        for (kpi, comparison) in self.mismatches() {
+            warn!(
+                "Simulated {} {:.3} differs from the expected {:.3} by more than {}",
+                kpi, comparison.simulated, comparison.expected, self.tolerance
+            );
+        }
+    }
+}
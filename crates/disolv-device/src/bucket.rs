@@ -1,19 +1,63 @@
+use crate::baseline::Baseline;
+use crate::diagnostics::{payload_bytes, CapAction, MemoryMonitor, Subsystem};
+use crate::episode::DeviceEpisode;
 use crate::linker::Linker;
+use crate::region::RegionExchange;
+use crate::reload::{ReloadedSettings, SettingsWatcher};
 use crate::space::{Mapper, Space};
+use crate::tiles::TileStats;
+use crate::validate::Validator;
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::Bucket;
 use disolv_core::bucket::TimeMS;
-use disolv_core::hashbrown::HashMap;
+use disolv_core::episode::Episodes;
+use disolv_core::group::Groups;
+use disolv_core::hashbrown::{HashMap, HashSet};
+use disolv_core::heatmap::{HeatmapData, HeatmapKind};
+use disolv_core::memory::{MemoryUsage, SubsystemMemory};
+use disolv_core::metrics::{Consumable, Measurable};
 use disolv_core::model::BucketModel;
+use disolv_core::timing::StageTimes;
+use disolv_models::bucket::age::AgeRecord;
+use disolv_models::bucket::charging::ChargingStations;
+use disolv_models::bucket::digest::{RunDigest, StepCounts};
+use disolv_models::bucket::fairness::FairnessRegister;
+use disolv_models::bucket::fault::FaultInjector;
+use disolv_models::bucket::flow::FlowRegister;
 use disolv_models::bucket::lake::DataLake;
-use disolv_models::device::mobility::MapState;
+use disolv_models::bucket::lifetime::LinkLifetimes;
+use disolv_models::bucket::metrics::MetricRegistry;
+use disolv_models::bucket::sla::SlaMonitor;
+use disolv_models::bucket::sleep::SleepRegister;
+use disolv_models::bucket::volume::VolumeRegister;
+use disolv_models::device::duplicates::{ClassDuplicates, DuplicateCounts};
+use disolv_models::device::mobility::{MapState, Point2D};
+use disolv_models::device::power::{DeactivationReason, Lifecycle};
+use disolv_models::device::predict::MobilityPredictor;
+use disolv_models::device::routing::RuleBook;
+use disolv_models::device::throttle::Throttled;
+use disolv_models::device::tx_power::TxPowerChoice;
 use disolv_models::device::types::{DeviceClass, DeviceType};
-use disolv_models::net::network::Network;
+use disolv_models::net::bandwidth::BandwidthType;
+use disolv_models::net::fragment::{Fragmented, Fragments, Partial};
+use disolv_models::net::latency::{Jitter, LatencyType};
+use disolv_models::net::message::{DPayload, TxFailReason, TxMetrics, TxStatus};
+use disolv_models::net::metrics::{Bandwidth, Bytes};
+use disolv_models::net::network::{Network, NetworkRoute};
+use disolv_models::net::operator::{OperatorCounts, OperatorId};
 use disolv_models::net::radio::DLink;
+use disolv_models::net::session::{Session, Sessions};
+use disolv_models::profile::LoadProfile;
 use disolv_output::result::ResultWriter;
-use log::info;
+use log::{info, warn};
+use std::fs::File;
+use std::io::BufWriter;
+use std::mem::size_of;
 use typed_builder::TypedBuilder;
 
+/// Directory in the output path with the agent snapshots requested during the run.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
 #[derive(TypedBuilder)]
 pub struct BucketModels {
     pub result_writer: ResultWriter,
@@ -23,6 +67,130 @@ pub struct BucketModels {
     pub linker_holder: Vec<Linker>,
     #[builder(default)]
     pub data_lake: DataLake,
+    #[builder(default)]
+    pub episodes: Episodes<DeviceEpisode>,
+    #[builder(default)]
+    pub sleep_register: SleepRegister,
+    #[builder(default)]
+    pub region: Option<RegionExchange>,
+}
+
+/// Running totals of the transfers in the simulation, reported as KPIs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TxCounts {
+    pub attempted: u64,
+    pub succeeded: u64,
+    pub expired: u64,
+}
+
+impl TxCounts {
+    /// Transfers counted since the `earlier` totals were taken.
+    pub fn since(&self, earlier: &TxCounts) -> TxCounts {
+        TxCounts {
+            attempted: self.attempted - earlier.attempted,
+            succeeded: self.succeeded - earlier.succeeded,
+            expired: self.expired - earlier.expired,
+        }
+    }
+}
+
+/// Running totals of the steps in which the agents throttled their targets and of the targets
+/// they skipped, reported as KPIs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThrottleCounts {
+    pub engaged: u64,
+    pub dropped: u64,
+}
+
+/// Running totals of the processing slots that the background load of the shared edge servers
+/// took from the simulation and of the payloads dropped at the full queues of the loaded
+/// processors, reported as KPIs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BackgroundCounts {
+    pub withheld: u64,
+    pub overflows: u64,
+}
+
+/// Running totals of the transmissions sent with power control, of the transmissions that did
+/// not reach their target at the power picked for them and of the powers in dBm.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TxPowerCounts {
+    pub transmissions: u64,
+    pub out_of_range: u64,
+    pub power: f64,
+}
+
+impl TxPowerCounts {
+    pub fn mean_power(&self) -> f64 {
+        match self.transmissions {
+            0 => 0.0,
+            transmissions => self.power / transmissions as f64,
+        }
+    }
+}
+
+/// Running totals of the steps in which the agents of classes with a cadence acted and the
+/// steps they skipped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CadenceCounts {
+    pub acted: u64,
+    pub skipped: u64,
+}
+
+/// Objects detected by the sensors of the agents in the current step. An object detected by
+/// several agents is counted once in the objects and once per agent in the detections.
+#[derive(Clone, Debug, Default)]
+pub struct PerceptionCounts {
+    pub sensing_agents: u64,
+    pub detections: u64,
+    pub objects: HashSet<AgentId>,
+}
+
+/// Running totals of the errors of the predicted positions, reported as KPIs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PredictionCounts {
+    pub predictions: u64,
+    pub total_error: f64,
+}
+
+/// Collects the heatmap of the agent density or of the payload traffic of every output interval.
+/// Traffic is counted at the positions of the agents sending the payloads.
+#[derive(Clone, Debug)]
+pub struct HeatmapRecorder {
+    current: HeatmapData,
+    ready: Option<HeatmapData>,
+}
+
+impl HeatmapRecorder {
+    pub fn new(kind: HeatmapKind, width: f64, height: f64) -> Self {
+        Self {
+            current: HeatmapData::new(kind, 0, width, height),
+            ready: None,
+        }
+    }
+
+    fn record_tx(&mut self, position: &Point2D) {
+        if self.current.kind == HeatmapKind::Traffic {
+            self.current.add(position.x, position.y, 1);
+        }
+    }
+
+    fn finish_interval(&mut self, step: TimeMS, space: &Space) {
+        let mut next = HeatmapData::new(
+            self.current.kind,
+            step.as_u64(),
+            space.width(),
+            space.height(),
+        );
+        std::mem::swap(&mut self.current, &mut next);
+        if next.kind == HeatmapKind::Density {
+            space
+                .positions()
+                .for_each(|position| next.add(position.x, position.y, 1));
+        }
+        next.step = step.as_u64();
+        self.ready = Some(next);
+    }
 }
 
 #[derive(TypedBuilder)]
@@ -31,6 +199,74 @@ pub struct DeviceBucket {
     pub class_to_type: HashMap<DeviceClass, DeviceType>,
     #[builder(default)]
     pub step: TimeMS,
+    #[builder(default)]
+    pub started_episodes: Vec<DeviceEpisode>,
+    #[builder(default)]
+    pub tx_counts: TxCounts,
+    #[builder(default)]
+    pub warm_up_tx: Option<TxCounts>,
+    #[builder(default)]
+    pub perception: PerceptionCounts,
+    #[builder(default)]
+    pub groups: Groups,
+    #[builder(default)]
+    pub load_profile: Option<LoadProfile>,
+    #[builder(default)]
+    pub dormant: HashSet<AgentId>,
+    #[builder(default)]
+    pub heatmap: Option<HeatmapRecorder>,
+    #[builder(default)]
+    pub predictor: Option<MobilityPredictor>,
+    #[builder(default)]
+    pub prediction_counts: PredictionCounts,
+    #[builder(default)]
+    pub lifecycles: HashMap<AgentId, Lifecycle>,
+    #[builder(default)]
+    pub fairness: FairnessRegister,
+    #[builder(default)]
+    pub volumes: VolumeRegister,
+    #[builder(default)]
+    pub validator: Option<Validator>,
+    #[builder(default)]
+    pub baseline: Option<Baseline>,
+    #[builder(default)]
+    pub memory_monitor: Option<MemoryMonitor>,
+    #[builder(default)]
+    pub faults: Option<FaultInjector>,
+    #[builder(default)]
+    pub sessions: Option<Sessions>,
+    #[builder(default)]
+    pub fragments: Option<Fragments>,
+    #[builder(default)]
+    pub sla_monitor: Option<SlaMonitor>,
+    #[builder(default)]
+    pub streamed_positions: HashMap<AgentId, Point2D>,
+    #[builder(default)]
+    pub throttle_counts: Option<ThrottleCounts>,
+    #[builder(default)]
+    pub background_counts: Option<BackgroundCounts>,
+    #[builder(default)]
+    pub tx_power_counts: Option<TxPowerCounts>,
+    #[builder(default)]
+    pub cadence_counts: Option<CadenceCounts>,
+    #[builder(default)]
+    pub metrics: MetricRegistry,
+    #[builder(default)]
+    pub link_lifetimes: Option<LinkLifetimes>,
+    #[builder(default)]
+    pub rule_book: Option<RuleBook>,
+    #[builder(default)]
+    pub digest: Option<RunDigest>,
+    #[builder(default)]
+    pub operator_counts: Option<OperatorCounts>,
+    #[builder(default)]
+    pub duplicates: Option<ClassDuplicates>,
+    #[builder(default)]
+    pub charging: Option<ChargingStations>,
+    #[builder(default)]
+    pub watcher: Option<SettingsWatcher>,
+    #[builder(default)]
+    pub reloads: Vec<ReloadedSettings>,
 }
 
 impl DeviceBucket {
@@ -40,9 +276,368 @@ impl DeviceBucket {
         source_type: &DeviceType,
         target_class: &DeviceClass,
     ) -> Option<Vec<DLink>> {
-        match self.linker_for(source_type, target_class) {
-            Some(linker) => linker.links_of(agent_id),
-            None => None,
+        let links = match self.linker_for(source_type, target_class) {
+            Some(linker) => linker.links_of(agent_id)?,
+            None => return None,
+        };
+        if let Some(ref mut lifetimes) = self.link_lifetimes {
+            lifetimes.observe(agent_id, links.iter().map(|link| link.target), self.step);
+        }
+        let links = match self.models.network.mmwave {
+            Some(ref mut mmwave) if mmwave.applies_to(target_class) => {
+                let step = self.step;
+                links
+                    .into_iter()
+                    .filter_map(|mut link| {
+                        mmwave
+                            .steer(agent_id, link.target, &mut link.properties, step)
+                            .then_some(link)
+                    })
+                    .collect()
+            }
+            _ => links,
+        };
+        let attenuation = match self.models.network.attenuation {
+            Some(ref mut attenuation) => attenuation,
+            None => return Some(links),
+        };
+        let source = match self.models.space.position_of(agent_id) {
+            Some(source) => *source,
+            None => return Some(links),
+        };
+        let space = &self.models.space;
+        let step = self.step;
+        let links = links
+            .into_iter()
+            .filter_map(|mut link| match space.position_of(link.target) {
+                Some(target) => attenuation
+                    .attenuate(&mut link.properties, &source, target, step)
+                    .then_some(link),
+                None => Some(link),
+            })
+            .collect();
+        Some(links)
+    }
+
+    /// Transfers the payload to the target. Transfers from or to agents cut off by a fault
+    /// fail. Transfers over the access network are degraded by the interference of the
+    /// transfers to the cell of the target in the last step, and their SINR is sampled in the
+    /// radio environment map at the agent served by the link: the transmitter, or the receiver
+    /// when the transmitter is part of the infrastructure.
+    pub(crate) fn transfer(&mut self, payload: &DPayload, target: AgentId) -> TxMetrics {
+        if self.is_blocked(payload, target) {
+            let mut tx_metrics = TxMetrics::new(payload, 0);
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = TxFailReason::Outage;
+            return tx_metrics;
+        }
+        let mut tx_metrics = self.models.network.transfer(payload);
+        if payload.metadata.route == NetworkRoute::Backhaul
+            && self.models.network.backhaul.is_some()
+        {
+            return tx_metrics;
+        }
+        let interference = match self.models.network.interference {
+            Some(ref mut interference) => interference,
+            None => return tx_metrics,
+        };
+        let space = &self.models.space;
+        let source = payload.agent_state.device_info.id;
+        let (from, to, cell) = match (
+            space.position_of(source),
+            space.position_of(target),
+            space.cell_id(target),
+        ) {
+            (Some(from), Some(to), Some(cell)) => (from, to, *cell),
+            _ => return tx_metrics,
+        };
+        let interferers: Vec<Point2D> = interference
+            .interferers_of(cell, source)
+            .filter_map(|agent_id| space.position_of(*agent_id))
+            .copied()
+            .collect();
+        if let Some(ref mut radio_map) = self.models.network.radio_map {
+            let device_type = payload.agent_state.device_info.device_type;
+            let served = match device_type.is_infrastructure() {
+                true => to,
+                false => from,
+            };
+            radio_map.record(served, interference.sinr(from, to, &interferers));
+        }
+        let constraint = self
+            .models
+            .network
+            .slices
+            .first()
+            .expect("no slice found")
+            .metrics
+            .latency_type
+            .constraint();
+        interference.apply(&mut tx_metrics, constraint, from, to, &interferers);
+        interference.register(cell, source);
+        tx_metrics
+    }
+
+    /// Sends a broadcast payload over its slice once, whatever the number of its targets. The
+    /// interference is not applied as the broadcaster draws the reception of each target.
+    pub(crate) fn transfer_broadcast(&mut self, payload: &DPayload) -> TxMetrics {
+        self.models.network.transfer(payload)
+    }
+
+    /// Whether a fault blocks the transfers between the sender of the payload and the target.
+    pub(crate) fn is_blocked(&mut self, payload: &DPayload, target: AgentId) -> bool {
+        let faults = match self.faults {
+            Some(ref mut faults) => faults,
+            None => return false,
+        };
+        let space = &self.models.space;
+        let source = payload.agent_state.device_info.id;
+        faults.blocks(
+            (source, space.position_of(source)),
+            (target, space.position_of(target)),
+        )
+    }
+
+    /// Rate of the session in which the payload is sent, if sessions are enabled and the
+    /// payload needs one.
+    pub(crate) fn session_rate(&mut self, payload: &DPayload) -> Option<Bandwidth> {
+        let sessions = self.sessions.as_ref()?;
+        let rate = sessions.rate(self.models.network.rate_for(payload)?);
+        sessions.is_needed(payload, rate).then_some(rate)
+    }
+
+    /// Admits the payload to a session. Payloads that are not admitted are reported at once,
+    /// the others when their session ends.
+    pub(crate) fn admit_session(&mut self, payload: &DPayload, link: &DLink) -> Option<TxMetrics> {
+        let tx_metrics = match self.is_blocked(payload, link.target) {
+            true => {
+                let mut tx_metrics = TxMetrics::new(payload, 0);
+                tx_metrics.tx_status = TxStatus::Fail;
+                tx_metrics.tx_fail_reason = TxFailReason::Outage;
+                tx_metrics
+            }
+            false => self.models.network.admit_session(payload),
+        };
+        if tx_metrics.tx_status == TxStatus::Ok {
+            return Some(tx_metrics);
+        }
+        self.register_tx(payload, &tx_metrics);
+        self.models
+            .result_writer
+            .add_tx_data(self.step, link, payload, tx_metrics);
+        None
+    }
+
+    /// Starts a session that sends the admitted payload at the rate, and sends its first bytes
+    /// in this step.
+    pub(crate) fn start_session(
+        &mut self,
+        payload: DPayload,
+        link: DLink,
+        sidelink: bool,
+        tx_metrics: TxMetrics,
+        rate: Bandwidth,
+    ) {
+        let sessions = self.sessions.as_mut().expect("Sessions must be enabled");
+        sessions.start(payload, link, sidelink, tx_metrics, rate, self.step);
+        let session = sessions.take_last().expect("Session was just started");
+        if let Some(session) = self.send_session(session) {
+            self.sessions
+                .as_mut()
+                .expect("Sessions must be enabled")
+                .keep(session);
+        }
+    }
+
+    /// Sends the bytes of the sessions in progress in this step, before the agents send theirs.
+    /// Sessions whose link disappeared or that are cut off by a fault are aborted.
+    fn advance_sessions(&mut self) {
+        let active = match self.sessions {
+            Some(ref mut sessions) => sessions.take(),
+            None => return,
+        };
+        for session in active.into_iter() {
+            let source = session.payload.agent_state.device_info;
+            let target = session.link.target;
+            let has_link = self
+                .models
+                .linker_holder
+                .iter()
+                .filter(|linker| linker.source_type == source.device_type)
+                .any(|linker| linker.has_link(source.id, target));
+            let fail_reason = if !has_link {
+                Some(TxFailReason::LinkLost)
+            } else if self.is_blocked(&session.payload, target) {
+                Some(TxFailReason::Outage)
+            } else if self
+                .sessions
+                .as_ref()
+                .is_some_and(|sessions| sessions.is_timed_out(&session, self.step))
+            {
+                Some(TxFailReason::LatencyLimit)
+            } else {
+                None
+            };
+            if let Some(fail_reason) = fail_reason {
+                let tx_metrics = session.aborted(fail_reason);
+                self.end_session(session, tx_metrics);
+                continue;
+            }
+            if let Some(session) = self.send_session(session) {
+                self.sessions
+                    .as_mut()
+                    .expect("Sessions must be enabled")
+                    .keep(session);
+            }
+        }
+    }
+
+    /// Sends the bytes of the session in this step, as far as the slice has capacity left.
+    /// Returns the session if it is not complete.
+    fn send_session(&mut self, mut session: Session) -> Option<Session> {
+        let bytes = self
+            .sessions
+            .as_ref()
+            .expect("Sessions must be enabled")
+            .bytes_per_step(&session);
+        let sent = self.models.network.reserve(&session.payload, bytes);
+        if sent > 0 {
+            session.send(sent);
+        }
+        if !session.is_complete() {
+            return Some(session);
+        }
+        let tx_metrics = session.completed(self.step);
+        self.end_session(session, tx_metrics);
+        None
+    }
+
+    fn end_session(&mut self, session: Session, tx_metrics: TxMetrics) {
+        let completed = tx_metrics.tx_status == TxStatus::Ok;
+        if let Some(ref mut sessions) = self.sessions {
+            sessions.finish(completed);
+        }
+        self.register_tx(&session.payload, &tx_metrics);
+        self.models.result_writer.add_tx_data(
+            self.step,
+            &session.link,
+            &session.payload,
+            tx_metrics,
+        );
+        if !completed {
+            return;
+        }
+        match session.sidelink {
+            true => self.deliver_sl_payload(session.link.target, session.payload),
+            false => self.deliver_payload(session.link.target, session.payload),
+        }
+    }
+
+    /// MTU of the fragments the payload is sent in, if fragmentation is enabled and the payload
+    /// is larger than the MTU of its slice.
+    pub(crate) fn fragment_size(&mut self, payload: &DPayload) -> Option<Bytes> {
+        let fragments = self.fragments.as_ref()?;
+        let mtu = self.models.network.mtu_for(payload)?;
+        fragments.is_needed(payload, mtu).then_some(mtu)
+    }
+
+    /// Splits the payload into fragments that the target reassembles, and sends the fragments
+    /// of this step.
+    pub(crate) fn start_fragmented(
+        &mut self,
+        payload: DPayload,
+        link: DLink,
+        sidelink: bool,
+        mtu: Bytes,
+    ) {
+        let fragments = self.fragments.as_mut().expect("Fragments must be enabled");
+        let fragmented = fragments.start(payload, link, mtu);
+        if let Some(reassembly) = self.models.data_lake.reassembly_mut() {
+            reassembly.expect(&fragmented, sidelink, self.step);
+        }
+        if let Some(fragmented) = self.send_fragments(fragmented) {
+            self.fragments
+                .as_mut()
+                .expect("Fragments must be enabled")
+                .keep(fragmented);
+        }
+    }
+
+    /// Sends the fragments of the fragmented payloads in this step, after the sessions, and
+    /// drops the payloads whose fragments did not all arrive in time.
+    fn advance_fragments(&mut self) {
+        let active = match self.fragments {
+            Some(ref mut fragments) => fragments.take(),
+            None => return,
+        };
+        for fragmented in active.into_iter() {
+            if let Some(fragmented) = self.send_fragments(fragmented) {
+                self.fragments
+                    .as_mut()
+                    .expect("Fragments must be enabled")
+                    .keep(fragmented);
+            }
+        }
+        let expired = match self.models.data_lake.reassembly_mut() {
+            Some(reassembly) => reassembly.expire(self.step),
+            None => return,
+        };
+        for partial in expired.into_iter() {
+            let tx_metrics = partial.incomplete();
+            self.end_reassembly(partial, tx_metrics);
+        }
+    }
+
+    /// Sends the fragments of the payload in this step until the limit per step is reached or
+    /// the slice has no bandwidth left. Returns the payload if it has fragments left.
+    fn send_fragments(&mut self, mut fragmented: Fragmented) -> Option<Fragmented> {
+        let per_step = self
+            .fragments
+            .as_ref()
+            .expect("Fragments must be enabled")
+            .fragments_per_step();
+        let mut sent = 0;
+        while !fragmented.is_sent() && sent < per_step {
+            let fragment = fragmented.next_fragment();
+            let tx_metrics = self.transfer(&fragment, fragmented.link.target);
+            if tx_metrics.tx_fail_reason == TxFailReason::NoBandwidth {
+                break;
+            }
+            fragmented.advance();
+            sent += 1;
+            if let Some(ref mut fragments) = self.fragments {
+                fragments.register_fragment(&tx_metrics);
+            }
+            if tx_metrics.tx_status != TxStatus::Ok {
+                continue;
+            }
+            let reassembled = self
+                .models
+                .data_lake
+                .reassembly_mut()
+                .and_then(|reassembly| reassembly.arrive(fragmented.id, &tx_metrics));
+            if let Some(partial) = reassembled {
+                let tx_metrics = partial.reassembled(self.step);
+                self.end_reassembly(partial, tx_metrics);
+            }
+        }
+        (!fragmented.is_sent()).then_some(fragmented)
+    }
+
+    fn end_reassembly(&mut self, partial: Partial, tx_metrics: TxMetrics) {
+        self.register_tx(&partial.payload, &tx_metrics);
+        self.models.result_writer.add_tx_data(
+            self.step,
+            &partial.link,
+            &partial.payload,
+            tx_metrics,
+        );
+        if tx_metrics.tx_status != TxStatus::Ok {
+            return;
+        }
+        match partial.sidelink {
+            true => self.deliver_sl_payload(partial.link.target, partial.payload),
+            false => self.deliver_payload(partial.link.target, partial.payload),
         }
     }
 
@@ -54,6 +649,551 @@ impl DeviceBucket {
         self.mapper_for(device_type).map_state_of(agent_id)
     }
 
+    /// Updates the predicted positions with the position of the agent in this step and records
+    /// the error of the position predicted for this step.
+    pub(crate) fn observe_position(&mut self, agent_id: AgentId, pos: &Point2D) {
+        let predictor = match self.predictor {
+            Some(ref mut predictor) => predictor,
+            None => return,
+        };
+        if let Some(error) = predictor.observe(agent_id, self.step, pos) {
+            self.prediction_counts.predictions += 1;
+            self.prediction_counts.total_error += error.distance();
+            self.models.result_writer.add_prediction(self.step, &error);
+        }
+    }
+
+    pub(crate) fn register_cadence(&mut self, acted: bool) {
+        if let Some(ref mut counts) = self.cadence_counts {
+            match acted {
+                true => counts.acted += 1,
+                false => counts.skipped += 1,
+            }
+        }
+    }
+
+    pub(crate) fn register_activation(&mut self, agent_id: AgentId) {
+        self.lifecycles
+            .entry(agent_id)
+            .or_default()
+            .activate(self.step);
+    }
+
+    pub(crate) fn register_deactivation(&mut self, agent_id: AgentId, reason: DeactivationReason) {
+        if let Some(lifecycle) = self.lifecycles.get_mut(&agent_id) {
+            lifecycle.deactivate(self.step, reason);
+        }
+    }
+
+    /// Checks if the agent is simulated by this process. All the agents are local unless the map
+    /// is partitioned into regions.
+    pub(crate) fn is_local(&self, agent_id: AgentId) -> bool {
+        match self.models.region {
+            Some(ref region) => region.is_local(agent_id),
+            None => true,
+        }
+    }
+
+    /// Hands the payload to the target, or sends it to the region of the target.
+    pub(crate) fn deliver_payload(&mut self, target: AgentId, payload: DPayload) {
+        let to_lake = self.is_local(target);
+        if let Some(ref mut validator) = self.validator {
+            validator.record_delivery(target, &payload, to_lake);
+        }
+        match self.models.region {
+            Some(ref mut region) if !region.is_local(target) => {
+                region.send(target, &payload, false)
+            }
+            _ => self.models.data_lake.add_payload_to(target, payload),
+        }
+    }
+
+    pub(crate) fn deliver_sl_payload(&mut self, target: AgentId, payload: DPayload) {
+        let to_lake = self.is_local(target);
+        if let Some(ref mut validator) = self.validator {
+            validator.record_delivery(target, &payload, to_lake);
+        }
+        match self.models.region {
+            Some(ref mut region) if !region.is_local(target) => region.send(target, &payload, true),
+            _ => self.models.data_lake.add_sl_payload_to(target, payload),
+        }
+    }
+
+    /// Assigns the agents to the regions of their positions in this step and exchanges the
+    /// payloads with the other regions. Payloads waiting for the agents that left this region
+    /// are sent to their new region.
+    fn exchange_boundary(&mut self) {
+        let models = &mut self.models;
+        let region = match models.region {
+            Some(ref mut region) => region,
+            None => return,
+        };
+        let departed = region.assign(
+            models
+                .mapper_holder
+                .iter()
+                .flat_map(|(_, mapper)| mapper.map_states()),
+        );
+        for agent_id in departed.into_iter() {
+            for payload in models.data_lake.payloads_for(agent_id).unwrap_or_default() {
+                region.send(agent_id, &payload, false);
+            }
+            for payload in models
+                .data_lake
+                .sl_payloads_for(agent_id)
+                .unwrap_or_default()
+            {
+                region.send(agent_id, &payload, true);
+            }
+        }
+        for delivery in region.exchange(self.step) {
+            match delivery.sidelink {
+                true => models
+                    .data_lake
+                    .add_sl_payload_to(delivery.target, delivery.payload),
+                false => models
+                    .data_lake
+                    .add_payload_to(delivery.target, delivery.payload),
+            }
+        }
+    }
+
+    /// Removes the agent from the space when it is switched off.
+    pub(crate) fn remove_from_space(&mut self, agent_id: AgentId) {
+        self.models.space.remove_agent(agent_id);
+        if let Some(ref mut predictor) = self.predictor {
+            predictor.remove(agent_id);
+        }
+    }
+
+    /// Position of the agent at the end of the prediction horizon, if positions are predicted.
+    pub fn predicted_position(&self, agent_id: AgentId) -> Option<Point2D> {
+        self.predictor
+            .as_ref()
+            .and_then(|predictor| predictor.predict(agent_id, predictor.horizon()))
+    }
+
+    /// Distance between the agents at the end of the prediction horizon. Can be used to check
+    /// whether an agent is likely to stay within the range of another agent.
+    pub fn predicted_distance(&self, agent_id: AgentId, other_id: AgentId) -> Option<f32> {
+        let this = self.predicted_position(agent_id)?;
+        let other = self.predicted_position(other_id)?;
+        let dx = this.x - other.x;
+        let dy = this.y - other.y;
+        Some((dx * dx + dy * dy).sqrt() as f32)
+    }
+
+    /// Expected remaining lifetime of the link between the agents, if link lifetimes are tracked.
+    pub fn expected_lifetime(&self, agent_id: AgentId, other_id: AgentId) -> Option<TimeMS> {
+        self.link_lifetimes
+            .as_ref()
+            .and_then(|lifetimes| lifetimes.expected_remaining(agent_id, other_id, self.step))
+    }
+
+    /// Episodes with agent setting changes in the order they were started. Agents keep track of
+    /// the episodes they have applied, so that agents activated later also apply them.
+    pub(crate) fn started_episodes(&self) -> &[DeviceEpisode] {
+        &self.started_episodes
+    }
+
+    pub(crate) fn reloads(&self) -> &[ReloadedSettings] {
+        &self.reloads
+    }
+
+    pub(crate) fn register_tx(&mut self, payload: &DPayload, tx_metrics: &TxMetrics) {
+        self.tx_counts.attempted += 1;
+        if let Some(ref mut validator) = self.validator {
+            validator.record_transfer(payload, tx_metrics);
+        }
+        if let Some(ref mut baseline) = self.baseline {
+            baseline.record(payload, tx_metrics);
+        }
+        if let Some(ref mut monitor) = self.sla_monitor {
+            monitor.record(payload, tx_metrics);
+        }
+        if let Some(ref mut counts) = self.operator_counts {
+            counts.register_tx(
+                &payload.metadata.carrier,
+                tx_metrics.tx_status == TxStatus::Ok,
+            );
+        }
+        if self.models.result_writer.writes_volumes() {
+            self.volumes
+                .record(payload, tx_metrics.tx_status == TxStatus::Ok);
+        }
+        if tx_metrics.tx_status == TxStatus::Ok {
+            self.tx_counts.succeeded += 1;
+            if let Some(recorder) = &mut self.heatmap {
+                if let Some(position) = self.models.space.position_of(tx_metrics.from_agent) {
+                    recorder.record_tx(position);
+                }
+            }
+        }
+    }
+
+    /// Counts the link options that could not be used because of a gap in the coverage.
+    pub(crate) fn register_gap_links(&mut self, count: usize) {
+        if let Some(ref mut coverage) = self.models.network.coverage {
+            coverage.register_gap_links(count);
+        }
+    }
+
+    /// Counts a selection in which an agent of the operator could use none of its links.
+    pub(crate) fn register_denied(&mut self, operator: OperatorId) {
+        if let Some(ref mut counts) = self.operator_counts {
+            counts.register_denied(operator);
+        }
+    }
+
+    pub(crate) fn register_duplicates(
+        &mut self,
+        device_class: DeviceClass,
+        counts: &DuplicateCounts,
+    ) {
+        if let Some(ref mut duplicates) = self.duplicates {
+            duplicates.register(device_class, counts);
+        }
+    }
+
+    pub(crate) fn register_tx_power(
+        &mut self,
+        agent_id: AgentId,
+        link: &DLink,
+        choice: &TxPowerChoice,
+    ) {
+        if let Some(ref mut counts) = self.tx_power_counts {
+            counts.transmissions += 1;
+            counts.power += choice.power as f64;
+            if !choice.reached {
+                counts.out_of_range += 1;
+            }
+        }
+        self.models.result_writer.add_tx_power(
+            self.step,
+            agent_id,
+            link.target,
+            link.properties.distance,
+            choice,
+        );
+    }
+
+    pub(crate) fn register_throttle(&mut self, throttled: Throttled) {
+        if let Some(ref mut counts) = self.throttle_counts {
+            if throttled.engaged {
+                counts.engaged += 1;
+                counts.dropped += throttled.dropped;
+            }
+        }
+    }
+
+    pub(crate) fn register_background(&mut self, withheld: u32, overflows: usize) {
+        if let Some(ref mut counts) = self.background_counts {
+            counts.withheld += withheld as u64;
+            counts.overflows += overflows as u64;
+        }
+    }
+
+    /// Adds the flows of the agent in this step to the fairness of the output interval. Flows
+    /// are only collected when a fairness table is written.
+    pub(crate) fn register_flows(
+        &mut self,
+        agent_id: AgentId,
+        device_class: DeviceClass,
+        flows: &[&FlowRegister],
+    ) {
+        if self.models.result_writer.writes_fairness() {
+            self.fairness.record(agent_id, device_class, flows);
+        }
+    }
+
+    /// Writes the fairness of the flows collected since the previous output interval.
+    fn write_fairness(&mut self, step: TimeMS) {
+        if self.fairness.is_empty() {
+            return;
+        }
+        for class_fairness in self.fairness.class_fairness().iter() {
+            self.models
+                .result_writer
+                .add_class_fairness(step, class_fairness);
+        }
+        for (agent_id, share) in self.fairness.agent_shares().into_iter() {
+            self.models
+                .result_writer
+                .add_agent_fairness(step, agent_id, share);
+        }
+        self.fairness.reset();
+    }
+
+    /// Writes the volume of the data sent per data type since the previous output interval.
+    fn write_volumes(&mut self, step: TimeMS) {
+        if self.volumes.is_empty() {
+            return;
+        }
+        for volume in self.volumes.volumes().iter() {
+            self.models.result_writer.add_data_volume(step, volume);
+        }
+        self.volumes.reset();
+    }
+
+    /// Writes the compliance with the SLAs of the transfers since the previous output interval.
+    fn write_slas(&mut self, step: TimeMS) {
+        let monitor = match self.sla_monitor {
+            Some(ref mut monitor) => monitor,
+            None => return,
+        };
+        for record in monitor.finish_interval(step.as_u64()).iter() {
+            self.models.result_writer.add_sla_record(step, record);
+        }
+    }
+
+    /// Writes the cells of the radio environment map sampled in the output interval.
+    fn write_radio_map(&mut self, step: TimeMS) {
+        let radio_map = match self.models.network.radio_map {
+            Some(ref mut radio_map) => radio_map,
+            None => return,
+        };
+        for cell in radio_map.take_cells().iter() {
+            self.models.result_writer.add_radio_map_cell(step, cell);
+        }
+    }
+
+    /// Writes the values of the registered metrics at the end of the output interval.
+    fn write_metrics(&mut self, step: TimeMS) {
+        for sample in self.metrics.samples() {
+            self.models.result_writer.add_metric(step, &sample);
+        }
+    }
+
+    /// Updates the metrics of the bucket itself, only when the metrics are written.
+    fn update_metrics(&mut self) {
+        if !self.models.result_writer.writes_metrics() {
+            return;
+        }
+        let mut transfers = 0.0;
+        for slice in self.models.network.all_slices() {
+            let id = self.metrics.gauge(&format!("slice{}_transfers", slice.id));
+            self.metrics.set(id, slice.tx_order as f64);
+            transfers += slice.tx_order as f64;
+        }
+        let id = self.metrics.counter("transfers");
+        self.metrics.increment(id, transfers);
+    }
+
+    /// Samples the memory of the subsystems when due, writes it to the output and cleans the
+    /// subsystems over their caps.
+    fn sample_memory(&mut self, step: TimeMS) {
+        let due = self
+            .memory_monitor
+            .as_mut()
+            .is_some_and(|monitor| monitor.is_due(step));
+        if !due {
+            return;
+        }
+        let mut usage = MemoryUsage::new(step.as_u64());
+        for subsystem in Subsystem::ALL.iter() {
+            let mut memory = match self.memory_of(*subsystem) {
+                Some(memory) => memory,
+                None => continue,
+            };
+            let action = self
+                .memory_monitor
+                .as_mut()
+                .expect("Memory monitor must be present to sample the memory")
+                .check(step, *subsystem, &mut memory);
+            if action == Some(CapAction::Clean) {
+                self.clean(*subsystem, step);
+            }
+            self.models.result_writer.add_memory(step, &memory);
+            usage.push(memory);
+        }
+        if let Some(ref mut monitor) = self.memory_monitor {
+            monitor.finish_sample(usage);
+        }
+    }
+
+    /// Memory held by the subsystem, if the subsystem is part of the simulation.
+    fn memory_of(&self, subsystem: Subsystem) -> Option<SubsystemMemory> {
+        let lake = &self.models.data_lake;
+        let memory = match subsystem {
+            Subsystem::DataLake => {
+                let payloads = lake.payloads.values().chain(lake.sl_payloads.values());
+                Self::payload_memory(subsystem, payloads.flatten())
+            }
+            Subsystem::ExpiredPayloads => {
+                let payloads = lake.expired.values().chain(lake.sl_expired.values());
+                Self::payload_memory(subsystem, payloads.flatten())
+            }
+            Subsystem::SleepBuffers => {
+                Self::payload_memory(subsystem, self.models.sleep_register.buffered())
+            }
+            Subsystem::AgeRecords => {
+                let records = lake.age_tracker()?.record_count();
+                let record_bytes = size_of::<((AgentId, AgentId), AgeRecord)>();
+                subsystem.memory(records as u64, (records * record_bytes) as u64)
+            }
+            Subsystem::OutputBuffers => {
+                let (rows, bytes) = self.models.result_writer.buffered();
+                subsystem.memory(rows, bytes)
+            }
+            Subsystem::TileCache => {
+                let stats: Vec<TileStats> = self
+                    .models
+                    .mapper_holder
+                    .iter()
+                    .filter_map(|(_, mapper)| mapper.tile_stats())
+                    .collect();
+                if stats.is_empty() {
+                    return None;
+                }
+                let rows: u64 = stats.iter().map(|stats| stats.resident_rows).sum();
+                let row_bytes = size_of::<(AgentId, MapState)>() as u64;
+                subsystem.memory(rows, rows * row_bytes)
+            }
+        };
+        Some(memory)
+    }
+
+    fn payload_memory<'a>(
+        subsystem: Subsystem,
+        payloads: impl Iterator<Item = &'a DPayload>,
+    ) -> SubsystemMemory {
+        let (entries, bytes) = payloads.fold((0, 0), |(entries, bytes), payload| {
+            (entries + 1, bytes + payload_bytes(payload))
+        });
+        subsystem.memory(entries, bytes)
+    }
+
+    fn clean(&mut self, subsystem: Subsystem, step: TimeMS) {
+        let cleaned = match subsystem {
+            Subsystem::DataLake => {
+                let expired = self.models.data_lake.expire_waiting();
+                self.tx_counts.expired += expired as u64;
+                expired
+            }
+            Subsystem::ExpiredPayloads => self.models.data_lake.discard_expired(),
+            Subsystem::SleepBuffers => self.models.sleep_register.drop_buffers(),
+            Subsystem::OutputBuffers => {
+                self.models.result_writer.write_all_output(step);
+                0
+            }
+            Subsystem::AgeRecords | Subsystem::TileCache => {
+                panic!("{} cannot be cleaned", subsystem.name())
+            }
+        };
+        info!(
+            "Cleaned {} entries of {} at {}",
+            cleaned,
+            subsystem.name(),
+            step
+        );
+    }
+
+    pub(crate) fn register_detections(&mut self, detected: &[AgentId]) {
+        self.perception.sensing_agents += 1;
+        self.perception.detections += detected.len() as u64;
+        self.perception.objects.extend(detected.iter().copied());
+    }
+
+    /// Load intensity at the given position in the current step, 1 when there is no profile.
+    pub(crate) fn load_intensity(&self, pos: &Point2D) -> f64 {
+        match self.load_profile {
+            Some(ref profile) => profile.intensity_at(self.step, pos),
+            None => 1.0,
+        }
+    }
+
+    /// Decides if an agent activated at the given position takes part in the simulation. All
+    /// the activations are admitted unless the load profile modulates them.
+    pub(crate) fn admits_activation(&mut self, pos: &Point2D) -> bool {
+        match self.load_profile {
+            Some(ref mut profile) if profile.modulates_activations() => {
+                profile.admits_activation(self.step, pos)
+            }
+            _ => true,
+        }
+    }
+
+    /// Marks the agent as sitting out after the load profile rejected its activation. Dormant
+    /// agents are not reachable by the other agents.
+    pub(crate) fn set_dormant(&mut self, agent_id: AgentId, dormant: bool) {
+        match dormant {
+            true => self.dormant.insert(agent_id),
+            false => self.dormant.remove(&agent_id),
+        };
+    }
+
+    pub(crate) fn is_dormant(&self, agent_id: &AgentId) -> bool {
+        self.dormant.contains(agent_id)
+    }
+
+    fn start_episodes(&mut self) {
+        for episode in self.models.episodes.due(self.step).into_iter() {
+            info!("Starting episode scheduled at {}", episode.start);
+            if let Some(ref slice_episodes) = episode.slices {
+                slice_episodes.iter().for_each(|slice_episode| {
+                    match self
+                        .models
+                        .network
+                        .all_slices_mut()
+                        .find(|slice| slice.id == slice_episode.id)
+                    {
+                        Some(slice) => {
+                            if let Some(ref latency) = slice_episode.latency {
+                                slice.metrics.latency_type = LatencyType::with_settings(latency);
+                                slice.metrics.jitter = Jitter::with_settings(latency);
+                            }
+                            if let Some(ref bandwidth) = slice_episode.bandwidth {
+                                slice.resources.bandwidth_type =
+                                    BandwidthType::with_settings(bandwidth.clone());
+                            }
+                        }
+                        None => warn!("Episode refers to unknown slice {}", slice_episode.id),
+                    }
+                });
+            }
+            if episode.has_agent_changes() {
+                self.started_episodes.push(episode);
+            }
+        }
+    }
+
+    /// Applies the settings of the watch file when it changed. Slice capacities change at once,
+    /// while the agents apply their changes in their next step.
+    fn reload_settings(&mut self) {
+        let settings = match self.watcher {
+            Some(ref mut watcher) => match watcher.poll() {
+                Some(settings) => settings,
+                None => return,
+            },
+            None => return,
+        };
+        info!("Reloading the settings at step {}", self.step);
+        for slice_reload in settings.slice.iter() {
+            match self
+                .models
+                .network
+                .all_slices_mut()
+                .find(|slice| slice.id == slice_reload.id)
+            {
+                Some(slice) => {
+                    if !slice.set_capacity(slice_reload.capacity) {
+                        warn!("Slice {} has no capacity to change", slice_reload.id);
+                    }
+                }
+                None => warn!("Watch file refers to unknown slice {}", slice_reload.id),
+            }
+        }
+        for (parameter, value) in settings.changes().into_iter() {
+            info!("Setting {} to {}", parameter, value);
+            self.models
+                .result_writer
+                .add_setting_change(self.step, parameter, value);
+        }
+        if settings.has_agent_changes() {
+            self.reloads.push(settings);
+        }
+    }
+
     fn linker_for(
         &mut self,
         source_type: &DeviceType,
@@ -95,10 +1235,33 @@ impl Bucket for DeviceBucket {
 
     fn before_agents(&mut self, step: TimeMS) {
         self.step = step;
+        self.models.result_writer.start_step(step);
+        if self.warm_up_tx.is_none() && !self.models.result_writer.is_warming_up() {
+            self.warm_up_tx = Some(self.tx_counts);
+        }
         info!("Before agents in bucket at step {}", step);
+        self.start_episodes();
         self.models.network.reset_slices();
+        self.models.network.trace_capacities(step);
+        if let Some(ref mut faults) = self.faults {
+            for change in faults.start_step(step, &mut self.models.network).iter() {
+                self.models.result_writer.add_fault_change(step, change);
+            }
+        }
+        if let Some(ref mut interference) = self.models.network.interference {
+            interference.start_step();
+        }
+        if let Some(ref mut baseline) = self.baseline {
+            if let Some(capacity) = self.models.network.slices[0].capacity() {
+                baseline.start_step(capacity);
+            }
+        }
+        if let Some(ref mut lifetimes) = self.link_lifetimes {
+            lifetimes.start_step(step);
+        }
+        self.perception = PerceptionCounts::default();
 
-        self.models.data_lake.clean_payloads();
+        self.tx_counts.expired += self.models.data_lake.clean_payloads(step) as u64;
         self.models.data_lake.clean_responses();
         self.models
             .mapper_holder
@@ -109,29 +1272,422 @@ impl Bucket for DeviceBucket {
         self.models.linker_holder.iter_mut().for_each(|linker| {
             linker.before_agent_step(self.step);
         });
+        self.exchange_boundary();
+        self.sample_memory(step);
+        if let Some(ref mut validator) = self.validator {
+            validator.begin_step(step, &self.models.data_lake);
+        }
+        self.advance_sessions();
+        self.advance_fragments();
     }
 
     fn after_agents(&mut self) {
-        for slice in self.models.network.slices.iter() {
+        if let Some(ref mut validator) = self.validator {
+            validator.end_step(&self.models.data_lake);
+        }
+        for slice in self.models.network.all_slices() {
             self.models.result_writer.add_net_stats(self.step, slice);
         }
+        self.update_metrics();
+        if self.perception.sensing_agents > 0 {
+            self.models.result_writer.add_perception(
+                self.step,
+                self.perception.detections,
+                self.perception.objects.len() as u64,
+            );
+        }
+        if let Some(age_tracker) = self.models.data_lake.age_tracker() {
+            for ((source, target), record) in age_tracker.records().iter() {
+                self.models
+                    .result_writer
+                    .add_age(self.step, *source, *target, record);
+            }
+        }
+        if let Some(ref mut counts) = self.operator_counts {
+            for (operator, stats) in counts.take_step().iter() {
+                self.models
+                    .result_writer
+                    .add_operator_stats(self.step, *operator, stats);
+            }
+        }
+        if let Some(ref mut duplicates) = self.duplicates {
+            for (device_class, counts) in duplicates.take_step().iter() {
+                self.models
+                    .result_writer
+                    .add_duplicates(self.step, *device_class, counts);
+            }
+        }
+        if let Some(ref mut charging) = self.charging {
+            for occupancy in charging.take_occupancy().iter() {
+                self.models
+                    .result_writer
+                    .add_station_occupancy(self.step, occupancy);
+            }
+            for session in charging.take_sessions().iter() {
+                self.models
+                    .result_writer
+                    .add_charging_session(session.end, session);
+            }
+        }
+        if let Some(ref mut digest) = self.digest {
+            let counts = StepCounts {
+                attempted: self.tx_counts.attempted,
+                succeeded: self.tx_counts.succeeded,
+                expired: self.tx_counts.expired,
+                delivered: self.models.data_lake.taken(),
+            };
+            digest.add_step(self.step, counts);
+        }
+        self.models.result_writer.write_due_output(self.step);
     }
 
     fn stream_input(&mut self, step: TimeMS) {
-        self.models.mapper_holder.iter_mut().for_each(|(_, space)| {
-            space.stream_data(self.step);
-        });
+        let space = &self.models.space;
+        self.models
+            .mapper_holder
+            .iter_mut()
+            .for_each(|(device_type, mapper)| {
+                mapper.focus_on(space.positions());
+                mapper.stream_data(self.step);
+                if let Some(stats) = mapper.tile_stats() {
+                    info!(
+                        "{} tiles resident for {} with {} rows",
+                        stats.resident_tiles, device_type, stats.resident_rows
+                    );
+                }
+            });
         self.models.linker_holder.iter_mut().for_each(|linker| {
             linker.stream_data(self.step);
         });
     }
 
     fn stream_output(&mut self, step: TimeMS) {
+        self.write_fairness(self.step);
+        self.write_volumes(self.step);
+        self.write_slas(self.step);
+        self.write_metrics(self.step);
+        self.write_radio_map(self.step);
         self.models.result_writer.write_output(self.step);
+        if let Some(recorder) = &mut self.heatmap {
+            recorder.finish_interval(self.step, &self.models.space);
+        }
+        self.reload_settings();
     }
 
     fn terminate(mut self, step: TimeMS) {
+        self.write_fairness(step);
+        self.write_volumes(step);
+        self.write_slas(step);
+        self.write_metrics(step);
+        self.write_radio_map(step);
         self.models.result_writer.write_output(step);
+        let mut lifecycles: Vec<(AgentId, Lifecycle)> = self.lifecycles.drain().collect();
+        lifecycles.sort_by_key(|(agent_id, _)| *agent_id);
+        for (agent_id, mut lifecycle) in lifecycles.into_iter() {
+            lifecycle.deactivate(step, DeactivationReason::SimulationEnd);
+            self.models
+                .result_writer
+                .add_lifecycle(agent_id, &lifecycle);
+        }
+        self.models
+            .result_writer
+            .add_lake_dump(step, &self.models.data_lake);
         self.models.result_writer.close_files(step);
+        if let Some(ref baseline) = self.baseline {
+            baseline.report();
+        }
+        if let Some(ref digest) = self.digest {
+            digest.finish();
+        }
+    }
+
+    /// The transfer KPIs leave out the transfers of the warm-up.
+    fn kpis(&self) -> Vec<(String, f64)> {
+        let tx_counts = match self.warm_up_tx {
+            Some(ref warm_up_tx) => self.tx_counts.since(warm_up_tx),
+            None => TxCounts::default(),
+        };
+        let delivery_ratio = match tx_counts.attempted {
+            0 => 0.0,
+            attempted => tx_counts.succeeded as f64 / attempted as f64,
+        };
+        let mut kpis = vec![
+            ("tx_attempted".to_string(), tx_counts.attempted as f64),
+            ("tx_succeeded".to_string(), tx_counts.succeeded as f64),
+            ("tx_expired".to_string(), tx_counts.expired as f64),
+            ("delivery_ratio".to_string(), delivery_ratio),
+        ];
+        if self.predictor.is_some() {
+            let mean_error = match self.prediction_counts.predictions {
+                0 => 0.0,
+                predictions => self.prediction_counts.total_error / predictions as f64,
+            };
+            kpis.push(("prediction_error_mean".to_string(), mean_error));
+        }
+        if let Some(mean_age) = self
+            .models
+            .data_lake
+            .age_tracker()
+            .and_then(|age_tracker| age_tracker.mean_age(self.step))
+        {
+            kpis.push(("aoi_mean".to_string(), mean_age));
+        }
+        for (device_type, mapper) in self.models.mapper_holder.iter() {
+            if let Some(stats) = mapper.tile_stats() {
+                let prefix = format!("tiles_{}", device_type.to_string().to_lowercase());
+                kpis.push((format!("{}_loads", prefix), stats.loads as f64));
+                kpis.push((format!("{}_hits", prefix), stats.hits as f64));
+                kpis.push((format!("{}_evictions", prefix), stats.evictions as f64));
+                kpis.push((format!("{}_resident", prefix), stats.resident_tiles as f64));
+            }
+            if let Some(stats) = mapper.position_stats() {
+                let prefix = format!("positions_{}", device_type.to_string().to_lowercase());
+                kpis.push((format!("{}_hits", prefix), stats.hits as f64));
+                kpis.push((format!("{}_misses", prefix), stats.misses as f64));
+            }
+        }
+        if let Some(ref attenuation) = self.models.network.attenuation {
+            let counts = attenuation.counts();
+            kpis.push(("links_attenuated".to_string(), counts.attenuated as f64));
+            kpis.push(("links_blocked".to_string(), counts.blocked as f64));
+        }
+        if let Some(ref interference) = self.models.network.interference {
+            let counts = interference.counts();
+            kpis.push(("tx_interfered".to_string(), counts.degraded as f64));
+            kpis.push(("tx_interference_failed".to_string(), counts.failed as f64));
+        }
+        if let Some(ref mmwave) = self.models.network.mmwave {
+            let counts = mmwave.counts();
+            kpis.push(("beam_blockages".to_string(), counts.blockages as f64));
+            kpis.push(("beam_realignments".to_string(), counts.realignments as f64));
+            kpis.push(("beam_links_unusable".to_string(), counts.unusable as f64));
+        }
+        if let Some(ref coverage) = self.models.network.coverage {
+            kpis.push((
+                "links_in_coverage_gap".to_string(),
+                coverage.counts().gap_links as f64,
+            ));
+        }
+        if let Some(ref baseline) = self.baseline {
+            let utilization = baseline.utilization();
+            let delivery_ratio = baseline.delivery_ratio();
+            kpis.push(("slice_utilization".to_string(), utilization.simulated));
+            kpis.push((
+                "slice_utilization_expected".to_string(),
+                utilization.expected,
+            ));
+            kpis.push(("slice_delivery_ratio".to_string(), delivery_ratio.simulated));
+            kpis.push((
+                "slice_delivery_ratio_expected".to_string(),
+                delivery_ratio.expected,
+            ));
+            kpis.push((
+                "baseline_mismatches".to_string(),
+                baseline.mismatches().len() as f64,
+            ));
+        }
+        if let Some(ref validator) = self.validator {
+            kpis.push((
+                "invariant_violations".to_string(),
+                validator.violations() as f64,
+            ));
+        }
+        if let Some(ref faults) = self.faults {
+            let counts = faults.counts();
+            kpis.push(("faults_started".to_string(), counts.started as f64));
+            kpis.push(("tx_blocked_by_faults".to_string(), counts.blocked as f64));
+        }
+        if let Some(ref sessions) = self.sessions {
+            let counts = sessions.counts();
+            kpis.push(("sessions_started".to_string(), counts.started as f64));
+            kpis.push(("sessions_completed".to_string(), counts.completed as f64));
+            kpis.push(("sessions_aborted".to_string(), counts.aborted as f64));
+            kpis.push((
+                "sessions_active".to_string(),
+                sessions.active_count() as f64,
+            ));
+        }
+        if let Some(ref fragments) = self.fragments {
+            let counts = fragments.counts();
+            kpis.push(("payloads_fragmented".to_string(), counts.fragmented as f64));
+            kpis.push(("fragments_sent".to_string(), counts.sent as f64));
+            kpis.push(("fragments_lost".to_string(), counts.lost as f64));
+        }
+        if let Some(reassembly) = self.models.data_lake.reassembly() {
+            let counts = reassembly.counts();
+            kpis.push((
+                "payloads_reassembled".to_string(),
+                counts.reassembled as f64,
+            ));
+            kpis.push(("reassembly_timeouts".to_string(), counts.timed_out as f64));
+        }
+        if let Some(ref counts) = self.throttle_counts {
+            kpis.push(("throttle_engaged".to_string(), counts.engaged as f64));
+            kpis.push(("targets_throttled".to_string(), counts.dropped as f64));
+        }
+        if let Some(ref counts) = self.background_counts {
+            kpis.push((
+                "background_slots_withheld".to_string(),
+                counts.withheld as f64,
+            ));
+            kpis.push((
+                "background_queue_overflows".to_string(),
+                counts.overflows as f64,
+            ));
+        }
+        if let Some(ref counts) = self.tx_power_counts {
+            kpis.push(("tx_power_mean".to_string(), counts.mean_power()));
+            kpis.push(("links_out_of_range".to_string(), counts.out_of_range as f64));
+        }
+        if let Some(ref counts) = self.cadence_counts {
+            kpis.push(("agent_steps_acted".to_string(), counts.acted as f64));
+            kpis.push(("agent_steps_skipped".to_string(), counts.skipped as f64));
+        }
+        if let Some(ref monitor) = self.sla_monitor {
+            kpis.push(("sla_violations".to_string(), monitor.violations() as f64));
+        }
+        if let Some(ref duplicates) = self.duplicates {
+            let total = duplicates.total();
+            kpis.push(("duplicates_suppressed".to_string(), total.duplicates as f64));
+            kpis.push(("rebroadcasts_withheld".to_string(), total.withheld as f64));
+            kpis.push(("duplication_overhead".to_string(), total.overhead()));
+        }
+        if let Some(ref charging) = self.charging {
+            let (completed, denied) = charging.totals();
+            kpis.push(("charging_sessions".to_string(), completed as f64));
+            kpis.push(("reservations_denied".to_string(), denied as f64));
+        }
+        if let Some(ref counts) = self.operator_counts {
+            for (operator, stats) in counts.totals().iter() {
+                let prefix = format!("operator_{}", operator);
+                kpis.push((format!("{}_tx_attempted", prefix), stats.attempted as f64));
+                kpis.push((format!("{}_tx_succeeded", prefix), stats.succeeded as f64));
+                kpis.push((format!("{}_visiting", prefix), stats.visiting as f64));
+                kpis.push((format!("{}_denied", prefix), stats.denied as f64));
+            }
+        }
+        if let Some(ref monitor) = self.memory_monitor {
+            let counts = monitor.counts();
+            kpis.push(("memory_over_cap".to_string(), counts.exceeded as f64));
+            kpis.push(("memory_cleanups".to_string(), counts.cleaned as f64));
+        }
+        if let Some(ref region) = self.models.region {
+            let counts = region.counts();
+            kpis.push(("region_handoffs".to_string(), counts.handoffs as f64));
+            kpis.push((
+                "region_payloads_sent".to_string(),
+                counts.payloads_sent as f64,
+            ));
+            kpis.push((
+                "region_payloads_received".to_string(),
+                counts.payloads_received as f64,
+            ));
+        }
+        if let Some(reachability) = self.models.sleep_register.total_reachability() {
+            kpis.push(("reachability".to_string(), reachability.ratio() as f64));
+        }
+        for slice in self.models.network.all_slices() {
+            kpis.push((
+                format!("slice_{}_bandwidth", slice.id),
+                slice.resources.bandwidth_type.available().as_u64() as f64,
+            ));
+        }
+        kpis
+    }
+
+    fn checkpoint(&mut self, step: TimeMS) {
+        info!("Writing all the buffered output at {}", step);
+        self.models.result_writer.write_all_output(step);
+    }
+
+    fn write_snapshot(
+        &mut self,
+        step: TimeMS,
+        agent_id: Option<AgentId>,
+        views: Vec<serde_json::Value>,
+    ) {
+        let snapshot_path = match self.models.result_writer.output_path() {
+            Some(output_path) => output_path.join(SNAPSHOT_DIR),
+            None => {
+                warn!("Snapshots are not written when the output is kept in memory");
+                return;
+            }
+        };
+        let file_name = match agent_id {
+            Some(agent_id) => format!("snapshot_{}_agent_{}.json", step, agent_id),
+            None => format!("snapshot_{}.json", step),
+        };
+        let snapshot_file = snapshot_path.join(file_name);
+        let agent_count = views.len();
+        let snapshot = serde_json::json!({ "step": step.as_u64(), "agents": views });
+        let written = std::fs::create_dir_all(&snapshot_path)
+            .and_then(|_| File::create(&snapshot_file))
+            .and_then(|file| {
+                serde_json::to_writer_pretty(BufWriter::new(file), &snapshot)
+                    .map_err(std::io::Error::from)
+            });
+        match written {
+            Ok(_) => info!(
+                "Dumped {} agents at {} to {}",
+                agent_count,
+                step,
+                snapshot_file.display()
+            ),
+            Err(e) => warn!(
+                "Failed to write the snapshot {}: {}",
+                snapshot_file.display(),
+                e
+            ),
+        }
+    }
+
+    fn record_execution_order(&mut self, step: TimeMS, agent_ids: &[AgentId]) {
+        self.models
+            .result_writer
+            .add_execution_order(step, agent_ids);
+    }
+
+    fn record_performance(&mut self, summary: &StageTimes) {
+        self.models.result_writer.write_performance(summary);
+    }
+
+    fn heatmap(&mut self) -> Option<HeatmapData> {
+        self.heatmap
+            .as_mut()
+            .and_then(|recorder| recorder.ready.take())
+    }
+
+    fn memory_usage(&mut self) -> Option<MemoryUsage> {
+        self.memory_monitor
+            .as_mut()
+            .and_then(|monitor| monitor.take_usage())
+    }
+
+    fn displacement(&mut self) -> Option<f64> {
+        let (displacement, positions) = self
+            .models
+            .space
+            .displacement_since(&self.streamed_positions);
+        self.streamed_positions = positions;
+        displacement
+    }
+
+    fn set_streaming_interval(
+        &mut self,
+        step: TimeMS,
+        interval: TimeMS,
+        displacement: Option<f64>,
+    ) {
+        self.models
+            .mapper_holder
+            .iter_mut()
+            .for_each(|(_, mapper)| mapper.set_streaming_step(interval));
+        self.models
+            .linker_holder
+            .iter_mut()
+            .for_each(|linker| linker.set_streaming_step(interval));
+        self.models
+            .result_writer
+            .add_streaming_interval(step, interval, displacement);
     }
 }
@@ -1,26 +1,51 @@
 use crate::bucket::DeviceBucket;
-use disolv_core::agent::{Activatable, Agent, Movable, Orderable};
+use crate::episode::DeviceEpisode;
+use crate::region::RemotePayload;
+use crate::reload::ReloadedSettings;
+use disolv_core::agent::{Activatable, Agent, DebugView, Movable, Orderable};
 use disolv_core::agent::{AgentId, AgentOrder};
 use disolv_core::bucket::TimeMS;
 use disolv_core::core::Core;
-use disolv_core::metrics::Measurable;
+use disolv_core::group::GroupId;
 use disolv_core::metrics::Resource;
+use disolv_core::metrics::{Feasibility, Measurable};
+use disolv_core::model::Model;
 use disolv_core::radio::{Receiver, Responder, Transmitter};
 use disolv_models::bucket::flow::FlowRegister;
+use disolv_models::device::actions::Pipelines;
 use disolv_models::device::actions::{do_actions, filter_blobs_to_fwd, set_actions_before_tx};
 use disolv_models::device::actor::Actor;
+use disolv_models::device::broadcast::{BroadcastReception, Broadcaster};
+use disolv_models::device::cache::ContentCache;
+use disolv_models::device::cadence::Cadence;
+use disolv_models::device::charging::{Battery, StationSettings};
 use disolv_models::device::compose::Composer;
+use disolv_models::device::duplicates::DuplicateFilter;
+use disolv_models::device::duty::DutyCycle;
 use disolv_models::device::energy::EnergyType;
 use disolv_models::device::hardware::StorageType;
+use disolv_models::device::metrics::Energy;
+use disolv_models::device::mobility::emissions::EmissionModel;
 use disolv_models::device::mobility::MapState;
-use disolv_models::device::power::{PowerManager, PowerState};
+use disolv_models::device::positioning::PositionError;
+use disolv_models::device::power::{
+    power_machine, DeactivationReason, PowerMachine, PowerManager, PowerState,
+};
+use disolv_models::device::queue::Processor;
 use disolv_models::device::reply::Replier;
+use disolv_models::device::routing::RuleTable;
 use disolv_models::device::select::Selector;
+use disolv_models::device::sensor::Sensor;
+use disolv_models::device::throttle::Throttle;
+use disolv_models::device::tx_power::TxPowerControl;
 use disolv_models::device::types::{DeviceClass, DeviceInfo, DeviceStats};
-use disolv_models::net::message::{DPayload, DeviceContent, PayloadInfo, TxStatus};
-use disolv_models::net::message::{DResponse, DataSource, TxMetrics};
-use disolv_models::net::radio::{DLink, LinkProperties};
-use log::debug;
+use disolv_models::net::message::{DPayload, DeviceContent, PayloadInfo, TxFailReason, TxStatus};
+use disolv_models::net::message::{DResponse, DataSource, DataType, TxMetrics};
+use disolv_models::net::operator::Carrier;
+use disolv_models::net::radio::{DLink, LinkDirection, LinkProperties};
+use log::{debug, info};
+use serde_json::json;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use typed_builder::TypedBuilder;
 
@@ -33,8 +58,39 @@ pub struct DeviceModel {
     pub replier: Replier,
     pub storage: StorageType,
     pub energy: EnergyType,
+    pub processor: Processor,
     pub actor: Actor,
     pub selector: Vec<(DeviceClass, Selector)>,
+    #[builder(default)]
+    pub sensor: Option<Sensor>,
+    #[builder(default)]
+    pub cache: Option<ContentCache>,
+    #[builder(default)]
+    pub target_groups: Vec<(DeviceClass, GroupId)>,
+    #[builder(default)]
+    pub duty_cycle: Option<DutyCycle>,
+    #[builder(default)]
+    pub pipelines: Pipelines,
+    #[builder(default)]
+    pub throttle: Option<Throttle>,
+    #[builder(default)]
+    pub broadcaster: Option<Broadcaster>,
+    #[builder(default)]
+    pub emissions: Option<EmissionModel>,
+    #[builder(default)]
+    pub routing: Option<RuleTable>,
+    #[builder(default)]
+    pub duplicates: Option<DuplicateFilter>,
+    #[builder(default)]
+    pub battery: Option<Battery>,
+    #[builder(default)]
+    pub station: Option<StationSettings>,
+    #[builder(default)]
+    pub tx_power: Option<TxPowerControl>,
+    #[builder(default)]
+    pub positioning: Option<PositionError>,
+    #[builder(default)]
+    pub cadence: Option<Cadence>,
 }
 
 impl DeviceModel {
@@ -43,14 +99,88 @@ impl DeviceModel {
         link_options: Vec<DLink>,
         target_class: &DeviceClass,
         stats: &Vec<&DeviceStats>,
+        forecast: &[Option<f32>],
+        lifetimes: &[Option<TimeMS>],
     ) -> Option<Vec<DLink>> {
         for selectors in self.selector.iter() {
             if selectors.0 == *target_class {
-                return Some(selectors.1.do_selection(link_options, stats));
+                return Some(
+                    selectors
+                        .1
+                        .do_selection(link_options, stats, forecast, lifetimes),
+                );
             }
         }
         None
     }
+
+    fn broadcasts_to(&self, target_class: &DeviceClass) -> bool {
+        self.broadcaster
+            .as_ref()
+            .is_some_and(|broadcaster| broadcaster.target_class == *target_class)
+    }
+
+    fn uses_forecast(&self, target_class: &DeviceClass) -> bool {
+        self.selector
+            .iter()
+            .any(|(class, selector)| class == target_class && selector.uses_forecast())
+    }
+
+    fn uses_lifetimes(&self, target_class: &DeviceClass) -> bool {
+        self.selector
+            .iter()
+            .any(|(class, selector)| class == target_class && selector.uses_lifetimes())
+    }
+
+    fn target_group(&self, target_class: &DeviceClass) -> Option<GroupId> {
+        self.target_groups
+            .iter()
+            .find(|(class, _)| class == target_class)
+            .map(|(_, group_id)| *group_id)
+    }
+
+    fn apply_episode(&mut self, episode: &DeviceEpisode) {
+        if let Some(ref composer_settings) = episode.composer {
+            self.composer = Composer::new(composer_settings, self.composer.content_seed());
+        }
+        if let Some(ref selector_settings) = episode.selector {
+            self.selector = selector_settings
+                .iter()
+                .map(|settings| (settings.target_class, Selector::with_settings(settings)))
+                .collect();
+            self.target_groups = selector_settings
+                .iter()
+                .filter_map(|settings| settings.target_group.map(|id| (settings.target_class, id)))
+                .collect();
+        }
+        if episode.actions.is_some() {
+            self.actor = Actor::new(&episode.actions);
+        }
+    }
+
+    fn apply_reload(&mut self, device_class: &DeviceClass, reload: &ReloadedSettings) {
+        reload
+            .composer
+            .iter()
+            .filter(|composer| composer.agent_class == *device_class)
+            .for_each(|composer| {
+                self.composer
+                    .set_source_step(composer.data_type, composer.source_step)
+            });
+        for selector_reload in reload
+            .selector
+            .iter()
+            .filter(|selector| selector.agent_class == *device_class)
+        {
+            self.selector
+                .iter_mut()
+                .filter(|(target_class, _)| *target_class == selector_reload.target_class)
+                .for_each(|(_, selector)| {
+                    selector
+                        .set_thresholds(selector_reload.link_count, selector_reload.dist_threshold)
+                });
+        }
+    }
 }
 
 #[derive(Clone, Debug, TypedBuilder)]
@@ -59,21 +189,152 @@ pub struct Device {
     pub models: DeviceModel,
     #[builder(default)]
     pub step: TimeMS,
-    #[builder(default)]
-    pub power_state: PowerState,
+    #[builder(default = power_machine())]
+    pub power_state: PowerMachine,
     #[builder(default)]
     pub map_state: MapState,
     #[builder(default)]
+    pub perceived: MapState,
+    #[builder(default)]
     pub content: DeviceContent,
     #[builder(default)]
     pub stats: DeviceStats,
+    #[builder(default)]
+    pub episode_cursor: usize,
+    #[builder(default)]
+    pub reload_cursor: usize,
+    #[builder(default)]
+    pub activation_pending: bool,
+    #[builder(default)]
+    pub dormant: bool,
+    #[builder(default)]
+    pub skipping: bool,
+    #[builder(default)]
+    pub remote: bool,
+    #[builder(default)]
+    pub blob_sequence: u64,
 }
 
 impl Device {
+    fn write_state_changes(&mut self, bucket: &mut DeviceBucket) {
+        for change in self.power_state.take_changes() {
+            bucket
+                .models
+                .result_writer
+                .add_state_change(self.step, self.device_info.id, &change);
+        }
+        if let Some(ref mut battery) = self.models.battery {
+            for change in battery.take_changes() {
+                bucket.models.result_writer.add_state_change(
+                    self.step,
+                    self.device_info.id,
+                    &change,
+                );
+            }
+        }
+    }
+
+    /// Charges or drains the battery of an electric vehicle, which parks at the station that
+    /// reserved a slot for it.
+    fn update_battery(&mut self, bucket: &mut DeviceBucket) {
+        if let (Some(battery), Some(stations)) = (&mut self.models.battery, &mut bucket.charging) {
+            battery.update(self.device_info.id, stations, self.step);
+        }
+        self.write_state_changes(bucket);
+    }
+
+    /// Drains the battery by the energy the energy model measures for the transfer, scaled by
+    /// the share of the maximum transmission power used for it.
+    fn drain_battery(&mut self, tx_metrics: &TxMetrics, payload: &DPayload, energy_factor: f64) {
+        if let Some(ref mut battery) = self.models.battery {
+            let energy = match self.models.energy.measure(tx_metrics, &payload.metadata) {
+                Feasibility::Feasible(energy) | Feasibility::Infeasible(energy) => energy,
+            };
+            let energy = (energy.as_u64() as f64 * energy_factor).round() as u64;
+            battery.drain(Energy::new(energy));
+        }
+    }
+
+    /// Draws the position the agent perceives in this step. The true position is kept in the
+    /// map state for the links, the space and the outputs, while the application models and
+    /// the content sent to the other agents see the perceived one.
+    fn perceive_position(&mut self, bucket: &mut DeviceBucket) {
+        let positioning = match self.models.positioning {
+            Some(ref mut positioning) => positioning,
+            None => {
+                self.perceived = self.map_state;
+                return;
+            }
+        };
+        self.perceived = positioning.perceive(&self.map_state);
+        bucket.models.result_writer.add_perceived_pos(
+            self.step,
+            self.device_info.id,
+            &self.map_state,
+            &self.perceived,
+        );
+    }
+
+    /// Transfers the payload at the transmission power picked for the link, and returns the
+    /// share of the energy at the maximum power spent on it. Targets out of the range of the
+    /// power are not reached.
+    fn transfer_at_power(
+        &mut self,
+        payload: &DPayload,
+        target_link: &DLink,
+        bucket: &mut DeviceBucket,
+    ) -> (TxMetrics, f64) {
+        let control = match self.models.tx_power {
+            Some(ref control) => control,
+            None => return (bucket.transfer(payload, target_link.target), 1.0),
+        };
+        let battery = self.models.battery.as_ref().map(|battery| battery.share());
+        let choice = control.choose(target_link.properties.distance, battery);
+        bucket.register_tx_power(self.device_info.id, target_link, &choice);
+        let energy_factor = control.energy_factor(choice.power);
+        if choice.reached {
+            return (bucket.transfer(payload, target_link.target), energy_factor);
+        }
+        let mut tx_metrics = TxMetrics::new(payload, 0);
+        tx_metrics.tx_status = TxStatus::Fail;
+        tx_metrics.tx_fail_reason = TxFailReason::OutOfRange;
+        (tx_metrics, energy_factor)
+    }
+
+    /// Reserves the slots of a charging station for the vehicles whose requests it received.
+    fn accept_reservations(&self, payloads: &[DPayload], bucket: &mut DeviceBucket) {
+        let stations = match (&self.models.station, &mut bucket.charging) {
+            (Some(_), Some(stations)) => stations,
+            _ => return,
+        };
+        for payload in payloads.iter() {
+            for blob in payload
+                .metadata
+                .data_blobs
+                .iter()
+                .filter(|blob| blob.data_type == DataType::Reservation)
+            {
+                let vehicle_id = blob
+                    .origin
+                    .map_or(payload.agent_state.device_info.id, |origin| origin.agent_id);
+                stations.request(self.device_info.id, vehicle_id, self.step);
+            }
+        }
+    }
+
+    /// Drops the copies of the blobs the agent already received, and counts them for the class
+    /// of the agent.
+    fn suppress_duplicates(&mut self, payloads: &mut [DPayload], bucket: &mut DeviceBucket) {
+        if let Some(ref mut duplicates) = self.models.duplicates {
+            duplicates.suppress(self.device_info.id, payloads, self.step);
+            bucket.register_duplicates(self.device_info.device_class, &duplicates.take_counts());
+        }
+    }
+
     fn compose_content(&self) -> DeviceContent {
         DeviceContent {
             device_info: self.device_info,
-            map_state: self.map_state,
+            map_state: self.perceived,
         }
     }
 
@@ -85,6 +346,178 @@ impl Device {
             .build();
     }
 
+    fn apply_episodes(&mut self, bucket: &mut DeviceBucket) {
+        let new_episodes = bucket.started_episodes()[self.episode_cursor..].to_vec();
+        self.episode_cursor += new_episodes.len();
+        for episode in new_episodes.iter() {
+            if episode.applies_to(&self.device_info) {
+                debug!(
+                    "Applying episode started at {} to agent {}",
+                    episode.start, self.device_info.id
+                );
+                self.models.apply_episode(episode);
+                episode.leave_groups.iter().flatten().for_each(|group_id| {
+                    bucket.groups.leave(*group_id, self.device_info.id);
+                });
+                episode.join_groups.iter().flatten().for_each(|group_id| {
+                    bucket.groups.join(*group_id, self.device_info.id);
+                });
+            }
+        }
+    }
+
+    /// Applies the settings reloaded from the watch file since the last step.
+    fn apply_reloads(&mut self, bucket: &DeviceBucket) {
+        let reloads = &bucket.reloads()[self.reload_cursor..];
+        self.reload_cursor += reloads.len();
+        for reload in reloads.iter() {
+            self.models
+                .apply_reload(&self.device_info.device_class, reload);
+        }
+    }
+
+    /// Checks if the agent acts in this step. Agents of classes without a cadence act in every
+    /// step.
+    fn acts_in_step(&mut self, bucket: &mut DeviceBucket) -> bool {
+        let acts = match self.models.cadence {
+            Some(ref mut cadence) => cadence.acts(self.device_info.id, self.step),
+            None => return true,
+        };
+        bucket.register_cadence(acts);
+        acts
+    }
+
+    /// Checks if the radio is awake in this step. Radios without a duty cycle are always awake.
+    fn radio_awake(&mut self) -> bool {
+        match self.models.duty_cycle {
+            Some(ref mut duty_cycle) => duty_cycle.tick(self.step),
+            None => true,
+        }
+    }
+
+    fn register_duty_cycle(&self, bucket: &mut DeviceBucket) {
+        if let Some(ref duty_cycle) = self.models.duty_cycle {
+            bucket
+                .models
+                .sleep_register
+                .register(self.device_info.id, duty_cycle.policy);
+        }
+    }
+
+    fn write_duty_cycle(&self, bucket: &mut DeviceBucket) {
+        let duty_cycle = match self.models.duty_cycle {
+            Some(ref duty_cycle) => duty_cycle,
+            None => return,
+        };
+        if let Some(reachability) = bucket
+            .models
+            .sleep_register
+            .reachability_of(self.device_info.id)
+        {
+            bucket.models.result_writer.add_duty_cycle(
+                self.step,
+                self.device_info.id,
+                duty_cycle.policy.schedule.is_awake(self.step),
+                &reachability,
+                duty_cycle.energy(),
+            );
+        }
+    }
+
+    fn sense_neighbours(&mut self, bucket: &mut DeviceBucket) {
+        bucket
+            .models
+            .space
+            .add_agent(self.device_info.id, &self.map_state.pos);
+        let sensor = match self.models.sensor {
+            Some(ref mut sensor) => sensor,
+            None => return,
+        };
+        let neighbours = bucket.models.space.neighbours_of(
+            self.device_info.id,
+            &self.map_state.pos,
+            sensor.sensing_range(),
+        );
+        sensor.sense(&self.perceived, &neighbours);
+        bucket.register_detections(sensor.detected());
+    }
+
+    /// Serves the content requested in the received payloads from the cache. Cached content is
+    /// not forwarded any further.
+    fn serve_from_cache(
+        &mut self,
+        rx_payloads: &mut Option<Vec<DPayload>>,
+        bucket: &mut DeviceBucket,
+    ) {
+        let cache = match self.models.cache {
+            Some(ref mut cache) => cache,
+            None => return,
+        };
+        if let Some(ref mut payloads) = rx_payloads {
+            payloads.iter_mut().for_each(|payload| cache.serve(payload));
+        }
+        bucket.models.result_writer.add_cache_stats(
+            self.step,
+            self.device_info.id,
+            &cache.take_stats(),
+        );
+    }
+
+    /// Installs the rules sent in the control messages received from the controllers.
+    fn install_rules(&mut self, payloads: &[DPayload], bucket: &DeviceBucket) {
+        let (routing, rule_book) = match (&mut self.models.routing, &bucket.rule_book) {
+            (Some(routing), Some(rule_book)) => (routing, rule_book),
+            _ => return,
+        };
+        let sent_at = payloads
+            .iter()
+            .flat_map(|payload| payload.metadata.data_blobs.iter())
+            .filter(|blob| blob.data_type == DataType::Control)
+            .map(|blob| blob.created_at)
+            .max();
+        let (version, rules) = match sent_at
+            .and_then(|sent_at| rule_book.rules_at(&self.device_info.device_class, sent_at))
+        {
+            Some(rules) => rules,
+            None => return,
+        };
+        if routing.install(version, rules) {
+            info!(
+                "Agent {} installed {} rules of {} at step {}",
+                self.device_info.id,
+                rules.len(),
+                version,
+                self.step
+            );
+            rules.iter().for_each(|rule| debug!("{}", rule));
+        }
+    }
+
+    /// Counts the payloads sent by this agent that expired in the data lake before they were
+    /// received.
+    fn register_expired(&mut self, bucket: &mut DeviceBucket) {
+        if let Some(expired) = bucket.models.data_lake.expired_for(self.device_info.id) {
+            self.models.flow.register_expired(&expired);
+        }
+        if let Some(expired) = bucket.models.data_lake.sl_expired_for(self.device_info.id) {
+            self.models.sl_flow.register_expired(&expired);
+        }
+    }
+
+    fn drop_payloads(&mut self, dropped: Vec<DPayload>, bucket: &mut DeviceBucket) {
+        self.models.flow.register_dropped(&dropped);
+        dropped.into_iter().for_each(|payload| {
+            let mut tx_metrics = TxMetrics::new(&payload, 0);
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = TxFailReason::QueueOverflow;
+            let response = self.models.replier.compose_response(None, tx_metrics);
+            bucket
+                .models
+                .data_lake
+                .add_response_to(payload.agent_state.device_info.id, response);
+        });
+    }
+
     fn talk_to_class(
         &mut self,
         target_class: &DeviceClass,
@@ -97,40 +530,192 @@ impl Device {
             target_class,
         ) {
             Some(links) => links,
-            None => return,
+            None => {
+                self.models.composer.cache_payload(target_class);
+                return;
+            }
+        };
+
+        // Links to the dormant agents are ignored. Only the members of the target group are
+        // reachable when a group is targeted.
+        let target_group = self.models.target_group(target_class);
+        let link_options: Vec<DLink> = link_options
+            .into_iter()
+            .filter(|link| !core.bucket.is_dormant(&link.target))
+            .filter(|link| match target_group {
+                Some(group_id) => core.bucket.groups.is_member(&group_id, &link.target),
+                None => true,
+            })
+            .collect();
+
+        // Agents of an operator only use the links that the operator and its roaming agreements
+        // allow.
+        let link_options = match core.bucket.models.network.operators {
+            Some(ref operators) if !link_options.is_empty() => {
+                let usable = operators.usable_links(&self.device_info, link_options, |target| {
+                    core.stats_of(&target).device_content.device_info
+                });
+                if usable.is_empty() && !self.device_info.device_type.is_infrastructure() {
+                    if let Some(operator) = self.device_info.operator {
+                        core.bucket.register_denied(operator);
+                    }
+                }
+                usable
+            }
+            _ => link_options,
+        };
+        // Agents in a gap of the infrastructure coverage only reach their own class.
+        let link_options = match core.bucket.models.network.coverage {
+            Some(ref coverage) if target_class != &self.device_info.device_class => {
+                let space = &core.bucket.models.space;
+                let options = link_options.len();
+                let usable = coverage.usable_links(
+                    &self.device_info,
+                    &self.map_state.pos,
+                    link_options,
+                    |target| {
+                        (
+                            core.stats_of(&target).device_content.device_info,
+                            space.position_of(target).copied(),
+                        )
+                    },
+                );
+                core.bucket.register_gap_links(options - usable.len());
+                usable
+            }
+            _ => link_options,
         };
+        if link_options.is_empty() {
+            self.models.composer.cache_payload(target_class);
+            return;
+        }
+        if self.models.broadcasts_to(target_class) {
+            self.broadcast(target_class, link_options, core);
+            return;
+        }
 
         let stats: Vec<&DeviceStats> = link_options
             .iter()
             .map(|link| core.stats_of(&link.target))
             .collect();
 
-        let targets = match self.models.select_links(link_options, target_class, &stats) {
-            Some(links) => links,
-            None => return,
+        let forecast: Vec<Option<f32>> = match self.models.uses_forecast(target_class) {
+            true => link_options
+                .iter()
+                .map(|link| {
+                    core.bucket
+                        .predicted_distance(self.device_info.id, link.target)
+                })
+                .collect(),
+            false => Vec::new(),
+        };
+        let lifetimes: Vec<Option<TimeMS>> = match self.models.uses_lifetimes(target_class) {
+            true => link_options
+                .iter()
+                .map(|link| {
+                    core.bucket
+                        .expected_lifetime(self.device_info.id, link.target)
+                })
+                .collect(),
+            false => Vec::new(),
         };
 
-        let payload = self
+        let mut targets = match self.models.select_links(
+            link_options,
+            target_class,
+            &stats,
+            &forecast,
+            &lifetimes,
+        ) {
+            Some(links) if !links.is_empty() => links,
+            _ => {
+                self.models.composer.cache_payload(target_class);
+                return;
+            }
+        };
+        if let Some(ref mut throttle) = self.models.throttle {
+            let throttled = throttle.limit(*target_class, &mut targets);
+            core.bucket.register_throttle(throttled);
+        }
+
+        let mut payload = self
             .models
             .composer
             .compose_payload(target_class, self.content);
+        if let Some(ref sensor) = self.models.sensor {
+            if let Some(blob) = sensor.perception_blob(target_class, self.step) {
+                self.models
+                    .composer
+                    .append_blobs_to(&mut payload, &mut vec![blob]);
+            }
+        }
+        // Reservations are only requested from the classes that know how to handle them.
+        if let Some(ref battery) = self.models.battery {
+            let handled = self
+                .models
+                .actor
+                .actions_for(target_class)
+                .action_for(&DataType::Reservation)
+                .is_some();
+            if let Some(blob) = battery.request_blob(self.step).filter(|_| handled) {
+                self.models
+                    .composer
+                    .append_blobs_to(&mut payload, &mut vec![blob]);
+            }
+        }
+        payload
+            .metadata
+            .stamp_origin(self.device_info.id, &mut self.blob_sequence);
 
         self.models.storage.consume(&payload.metadata);
 
+        let mut talked_classes = self.models.actor.target_classes.clone();
+        talked_classes.push(self.device_info.device_class);
         targets.into_iter().for_each(|target_link| {
             let target_stats = core.stats_of(&target_link.target);
             let mut this_payload = payload.clone();
-            match rx_payloads {
-                Some(ref payloads) => {
-                    let mut blobs = filter_blobs_to_fwd(&target_stats.device_content, payloads);
-                    self.models
-                        .composer
-                        .append_blobs_to(&mut this_payload, &mut blobs);
+            let target_groups = core.bucket.groups.groups_of(&target_link.target);
+            let blobs = match (rx_payloads, &self.models.routing) {
+                (Some(ref payloads), Some(ref routing)) => routing.route_blobs_to_fwd(
+                    &target_stats.device_content,
+                    target_groups,
+                    &talked_classes,
+                    payloads,
+                ),
+                (Some(ref payloads), None) => {
+                    filter_blobs_to_fwd(&target_stats.device_content, target_groups, payloads)
                 }
-                None => (),
-            }
+                (None, _) => Vec::new(),
+            };
+            let mut blobs = self.models.pipelines.on_forward(
+                blobs,
+                *target_class,
+                target_link.target,
+                self.step,
+            );
+            self.models
+                .composer
+                .append_blobs_to(&mut this_payload, &mut blobs);
+            this_payload.metadata.selected_link = target_link;
+            this_payload.metadata.direction =
+                LinkDirection::between(&self.device_info, &target_stats.device_content.device_info);
+            this_payload.metadata.route = core
+                .bucket
+                .models
+                .network
+                .route_between(&self.device_info, &target_stats.device_content.device_info);
+            this_payload.metadata.carrier =
+                Carrier::between(&self.device_info, &target_stats.device_content.device_info);
             let actions = self.models.actor.actions_for(target_class);
-            let prepared_payload = set_actions_before_tx(this_payload, actions);
+            let prepared_payload = match core.bucket.models.sleep_register.deliver(
+                target_link.target,
+                set_actions_before_tx(this_payload, actions),
+                self.step,
+                self.device_info.device_type.is_infrastructure(),
+            ) {
+                Some(payload) => payload,
+                None => return,
+            };
             if target_class == &self.device_info.device_class {
                 self.transmit_sl(prepared_payload, target_link, &mut core.bucket);
             } else {
@@ -138,6 +723,118 @@ impl Device {
             }
         });
     }
+
+    /// Sends a single payload to all the targets in the coverage of the broadcaster. The slice
+    /// is used once for the payload, and each covered target receives it with the reception
+    /// probability of its distance. Broadcasts are not sent in sessions and do not carry the
+    /// blobs forwarded to a target.
+    fn broadcast(
+        &mut self,
+        target_class: &DeviceClass,
+        link_options: Vec<DLink>,
+        core: &mut Core<Self, DeviceBucket>,
+    ) {
+        let broadcaster = match self.models.broadcaster {
+            Some(ref mut broadcaster) => broadcaster,
+            None => return,
+        };
+        let covered: Vec<DLink> = link_options
+            .into_iter()
+            .filter(|link| {
+                link.properties
+                    .distance
+                    .is_some_and(|distance| broadcaster.covers(distance))
+            })
+            .collect();
+        let nearest = match covered.iter().min_by(|a, b| {
+            let a = a.properties.distance.unwrap_or(f32::MAX);
+            let b = b.properties.distance.unwrap_or(f32::MAX);
+            a.total_cmp(&b)
+        }) {
+            Some(link) => *link,
+            None => {
+                self.models.composer.cache_payload(target_class);
+                return;
+            }
+        };
+
+        let mut payload = self
+            .models
+            .composer
+            .compose_payload(target_class, self.content);
+        if let Some(ref sensor) = self.models.sensor {
+            if let Some(blob) = sensor.perception_blob(target_class, self.step) {
+                self.models
+                    .composer
+                    .append_blobs_to(&mut payload, &mut vec![blob]);
+            }
+        }
+        payload
+            .metadata
+            .stamp_origin(self.device_info.id, &mut self.blob_sequence);
+        self.models.storage.consume(&payload.metadata);
+
+        let nearest_info = core.stats_of(&nearest.target).device_content.device_info;
+        payload.metadata.selected_link = nearest;
+        payload.metadata.direction = LinkDirection::between(&self.device_info, &nearest_info);
+        payload.metadata.route = core
+            .bucket
+            .models
+            .network
+            .route_between(&self.device_info, &nearest_info);
+        payload.metadata.carrier = Carrier::between(&self.device_info, &nearest_info);
+        let actions = self.models.actor.actions_for(target_class);
+        let payload = set_actions_before_tx(payload, actions);
+
+        let is_sidelink = target_class == &self.device_info.device_class;
+        let flow = match is_sidelink {
+            true => &mut self.models.sl_flow,
+            false => &mut self.models.flow,
+        };
+        flow.register_outgoing_attempt(&payload);
+        let bucket = &mut core.bucket;
+        let tx_metrics = bucket.transfer_broadcast(&payload);
+        bucket.register_tx(&payload, &tx_metrics);
+        bucket
+            .models
+            .result_writer
+            .add_tx_data(self.step, &nearest, &payload, tx_metrics);
+
+        let mut reception = BroadcastReception {
+            covered: covered.len() as u32,
+            received: 0,
+        };
+        if tx_metrics.tx_status == TxStatus::Ok {
+            flow.register_outgoing_feasible(&payload);
+            for link in covered.iter() {
+                let distance = link.properties.distance.unwrap_or(f32::MAX);
+                if bucket.is_blocked(&payload, link.target) || !broadcaster.receives(distance) {
+                    continue;
+                }
+                let delivered = match bucket.models.sleep_register.deliver(
+                    link.target,
+                    payload.clone(),
+                    self.step,
+                    self.device_info.device_type.is_infrastructure(),
+                ) {
+                    Some(delivered) => delivered,
+                    None => continue,
+                };
+                reception.received += 1;
+                match is_sidelink {
+                    true => bucket.deliver_sl_payload(link.target, delivered),
+                    false => bucket.deliver_payload(link.target, delivered),
+                }
+            }
+        }
+        bucket.models.result_writer.add_broadcast(
+            self.step,
+            self.device_info.id,
+            *target_class,
+            tx_metrics.tx_status,
+            &reception,
+        );
+    }
 }
 
 impl Movable<DeviceBucket> for Device {
@@ -151,26 +848,48 @@ impl Movable<DeviceBucket> for Device {
         self.map_state = bucket
             .positions_for(self.device_info.id, &self.device_info.device_type)
             .unwrap_or(self.map_state);
+        if let Some(position) = self
+            .models
+            .battery
+            .as_ref()
+            .and_then(|battery| battery.parked_at())
+        {
+            self.map_state.pos = position;
+        }
+        if let (Some(settings), Some(stations)) = (&self.models.station, &mut bucket.charging) {
+            stations.register_station(self.device_info.id, settings, self.map_state.pos);
+        }
+        bucket.observe_position(self.device_info.id, &self.map_state.pos);
         bucket
             .models
             .result_writer
             .add_agent_pos(self.step, self.device_info.id, &self.map_state);
+        self.perceive_position(bucket);
+        if let Some(ref mut emissions) = self.models.emissions {
+            if let Some(emission) = emissions.update(self.step, &self.map_state) {
+                bucket
+                    .models
+                    .result_writer
+                    .add_emission(self.step, self.device_info.id, &emission);
+            }
+        }
     }
 }
 
 impl Activatable for Device {
     fn activate(&mut self) {
         debug!("Starting agent: {}", self.device_info.id);
-        self.power_state = PowerState::On;
+        self.power_state.move_to(PowerState::On, &mut ());
+        self.activation_pending = true;
     }
 
     fn deactivate(&mut self) {
         debug!("Stopping agent: {}", self.device_info.id);
-        self.power_state = PowerState::On;
+        self.power_state.move_to(PowerState::On, &mut ());
     }
 
     fn is_deactivated(&self) -> bool {
-        self.power_state == PowerState::Off
+        self.power_state.is_in(PowerState::Off)
     }
 
     fn time_to_activation(&mut self) -> TimeMS {
@@ -196,7 +915,23 @@ impl Transmitter<DeviceContent, DeviceBucket, LinkProperties, PayloadInfo> for D
         );
 
         self.models.flow.register_outgoing_attempt(&payload);
-        let tx_metrics = bucket.models.network.transfer(&payload);
+        // Fragmented payloads count as feasible once they are split.
+        if let Some(mtu) = bucket.fragment_size(&payload) {
+            self.models.flow.register_outgoing_feasible(&payload);
+            bucket.start_fragmented(payload, target_link, false, mtu);
+            return;
+        }
+        if let Some(rate) = bucket.session_rate(&payload) {
+            // Payloads sent in a session count as feasible once the network admits them.
+            if let Some(tx_metrics) = bucket.admit_session(&payload, &target_link) {
+                self.models.flow.register_outgoing_feasible(&payload);
+                bucket.start_session(payload, target_link, false, tx_metrics, rate);
+            }
+            return;
+        }
+        let (tx_metrics, energy_factor) = self.transfer_at_power(&payload, &target_link, bucket);
+        bucket.register_tx(&payload, &tx_metrics);
+        self.drain_battery(&tx_metrics, &payload, energy_factor);
         bucket
             .models
             .result_writer
@@ -204,10 +939,7 @@ impl Transmitter<DeviceContent, DeviceBucket, LinkProperties, PayloadInfo> for D
 
         if tx_metrics.tx_status == TxStatus::Ok {
             self.models.flow.register_outgoing_feasible(&payload);
-            bucket
-                .models
-                .data_lake
-                .add_payload_to(target_link.target, payload);
+            bucket.deliver_payload(target_link.target, payload);
         }
     }
 
@@ -218,7 +950,21 @@ impl Transmitter<DeviceContent, DeviceBucket, LinkProperties, PayloadInfo> for D
         );
 
         self.models.sl_flow.register_outgoing_attempt(&payload);
-        let sl_metrics = bucket.models.network.transfer(&payload);
+        if let Some(mtu) = bucket.fragment_size(&payload) {
+            self.models.sl_flow.register_outgoing_feasible(&payload);
+            bucket.start_fragmented(payload, target_link, true, mtu);
+            return;
+        }
+        if let Some(rate) = bucket.session_rate(&payload) {
+            if let Some(sl_metrics) = bucket.admit_session(&payload, &target_link) {
+                self.models.sl_flow.register_outgoing_feasible(&payload);
+                bucket.start_session(payload, target_link, true, sl_metrics, rate);
+            }
+            return;
+        }
+        let (sl_metrics, energy_factor) = self.transfer_at_power(&payload, &target_link, bucket);
+        bucket.register_tx(&payload, &sl_metrics);
+        self.drain_battery(&sl_metrics, &payload, energy_factor);
         bucket
             .models
             .result_writer
@@ -226,10 +972,7 @@ impl Transmitter<DeviceContent, DeviceBucket, LinkProperties, PayloadInfo> for D
 
         if sl_metrics.tx_status == TxStatus::Ok {
             self.models.sl_flow.register_outgoing_feasible(&payload);
-            bucket
-                .models
-                .data_lake
-                .add_sl_payload_to(target_link.target, payload);
+            bucket.deliver_sl_payload(target_link.target, payload);
         }
     }
 }
@@ -238,7 +981,18 @@ impl Receiver<DeviceContent, DeviceBucket, PayloadInfo> for Device {
     type C = DeviceClass;
 
     fn receive(&mut self, bucket: &mut DeviceBucket) -> Option<Vec<DPayload>> {
-        bucket.models.data_lake.payloads_for(self.device_info.id)
+        let received = bucket.models.data_lake.payloads_for(self.device_info.id);
+        match bucket.models.sleep_register.release(self.device_info.id) {
+            Some(mut buffered) => {
+                bucket
+                    .models
+                    .data_lake
+                    .register_delivery(self.device_info.id, &buffered);
+                buffered.extend(received.unwrap_or_default());
+                Some(buffered)
+            }
+            None => received,
+        }
     }
 
     fn receive_sl(&mut self, bucket: &mut DeviceBucket) -> Option<Vec<DPayload>> {
@@ -256,6 +1010,72 @@ impl Responder<DeviceBucket, DataSource, TxMetrics> for Device {
     }
 }
 
+impl DebugView<DeviceBucket> for Device {
+    fn debug_view(&self, bucket: &DeviceBucket) -> serde_json::Value {
+        let id = self.device_info.id;
+        let lake = &bucket.models.data_lake;
+        let queued: Vec<RemotePayload> = lake
+            .payloads
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|payload| RemotePayload::new(id, payload, false))
+            .chain(
+                lake.sl_payloads
+                    .get(&id)
+                    .into_iter()
+                    .flatten()
+                    .map(|payload| RemotePayload::new(id, payload, true)),
+            )
+            .collect();
+        let times = |times: &VecDeque<TimeMS>| -> Vec<u64> {
+            times.iter().map(|time| time.as_u64()).collect()
+        };
+        json!({
+            "id": id,
+            "device_type": self.device_info.device_type,
+            "device_class": self.device_info.device_class,
+            "agent_order": self.device_info.agent_order.as_u32(),
+            "step": self.step.as_u64(),
+            "power": {
+                "state": format!("{:?}", self.power_state.state()),
+                "on_times": times(&self.models.power.on_times),
+                "off_times": times(&self.models.power.off_times),
+                "activation_pending": self.activation_pending,
+                "dormant": self.dormant,
+                "skipping": self.skipping,
+                "remote": self.remote,
+            },
+            "position": {
+                "x": self.map_state.pos.x,
+                "y": self.map_state.pos.y,
+                "z": self.map_state.z,
+                "velocity": self.map_state.velocity.map(|velocity| velocity.to_string()),
+                "road_id": self.map_state.road_id.map(|road_id| road_id.to_string()),
+            },
+            "stats": {
+                "outgoing": format!("{:?}", self.stats.outgoing_stats),
+                "incoming": format!("{:?}", self.stats.incoming_stats),
+            },
+            "models": {
+                "composer": format!("{:?}", self.models.composer),
+                "selector": format!("{:?}", self.models.selector),
+                "actor": format!("{:?}", self.models.actor),
+                "processor": format!("{:?}", self.models.processor),
+                "storage": format!("{:?}", self.models.storage),
+                "energy": format!("{:?}", self.models.energy),
+                "flow": format!("{:?}", self.models.flow),
+                "sl_flow": format!("{:?}", self.models.sl_flow),
+                "cache": self.models.cache.as_ref().map(|cache| format!("{:?}", cache)),
+                "duty_cycle": self.models.duty_cycle.as_ref().map(|duty| format!("{:?}", duty)),
+                "throttle": self.models.throttle.as_ref().map(|throttle| format!("{:?}", throttle)),
+                "routing": self.models.routing.as_ref().map(|routing| format!("{:?}", routing)),
+            },
+            "queued_payloads": queued,
+        })
+    }
+}
+
 impl Agent<DeviceBucket> for Device {
     type AS = DeviceStats;
 
@@ -270,7 +1090,48 @@ impl Agent<DeviceBucket> for Device {
     fn stage_one(&mut self, core: &mut Core<Self, DeviceBucket>) {
         self.step = core.bucket.step;
         let bucket = &mut core.bucket;
+        self.write_state_changes(bucket);
+
+        // Agents in other regions of the map are simulated by the processes of those regions.
+        self.remote = !bucket.is_local(self.device_info.id);
+        if self.remote {
+            bucket.remove_from_space(self.device_info.id);
+            return;
+        }
+        self.apply_episodes(bucket);
+        self.apply_reloads(bucket);
+        self.models.composer.update_step(self.step);
+        self.update_battery(bucket);
         self.set_mobility(bucket);
+
+        // Agents not admitted by the load profile sit out until they are activated again.
+        if self.activation_pending {
+            self.activation_pending = false;
+            self.dormant = !bucket.admits_activation(&self.map_state.pos);
+            bucket.set_dormant(self.device_info.id, self.dormant);
+            if !self.dormant {
+                bucket.register_activation(self.device_info.id);
+                self.register_duty_cycle(bucket);
+            }
+        }
+        if self.dormant {
+            bucket.remove_from_space(self.device_info.id);
+            return;
+        }
+
+        // Agents skip the steps that are not theirs in all the stages, and the payloads sent
+        // to them wait in the data lake until they act again.
+        self.skipping = !self.acts_in_step(bucket);
+        if self.skipping {
+            bucket
+                .models
+                .space
+                .add_agent(self.device_info.id, &self.map_state.pos);
+            return;
+        }
+        let intensity = bucket.load_intensity(&self.map_state.pos);
+        self.models.composer.update_intensity(intensity);
+        self.sense_neighbours(bucket);
         self.content = self.compose_content();
 
         debug!(
@@ -278,15 +1139,48 @@ impl Agent<DeviceBucket> for Device {
             self.device_info.id, self.step
         );
         self.models.flow.reset();
+        self.models.sl_flow.reset();
+        self.register_expired(bucket);
+
+        // A sleeping radio neither receives nor transmits. Payloads for the targets are cached
+        // until the radio wakes up when the composer caches them.
+        if !self.radio_awake() {
+            for target_class in self.models.actor.target_classes.iter() {
+                self.models.composer.cache_payload(target_class);
+            }
+            return;
+        }
 
         // Receive data from the downstream agents.
-        let mut rx_payloads = self.receive(bucket);
-        if let Some(ref mut payloads) = rx_payloads {
+        let received = self.receive(bucket);
+        if let Some(ref payloads) = received {
             self.models.flow.register_incoming(payloads);
+        }
+
+        // Received data is available only after it is processed.
+        let (mut rx_payloads, dropped) = self.models.processor.process(received, self.step);
+        if self.models.processor.has_background() {
+            bucket.register_background(self.models.processor.withheld(), dropped.len());
+        }
+        if let Some(stats) = self.models.processor.take_stats() {
+            bucket
+                .models
+                .result_writer
+                .add_queue_stats(self.step, self.device_info.id, &stats);
+        }
+        self.drop_payloads(dropped, bucket);
+
+        if let Some(ref mut payloads) = rx_payloads {
+            self.suppress_duplicates(payloads, bucket);
+            self.accept_reservations(payloads, bucket);
+            self.install_rules(payloads, bucket);
+            let groups = bucket.groups.groups_of(&self.device_info.id);
             payloads.iter_mut().for_each(|payload| {
-                do_actions(payload, &self.content);
+                self.models.pipelines.on_receive(payload, self.step);
+                do_actions(payload, &self.content, groups);
             });
         }
+        self.serve_from_cache(&mut rx_payloads, bucket);
 
         for target_class in self.models.actor.target_classes.clone().iter() {
             self.talk_to_class(target_class, &rx_payloads, core);
@@ -297,8 +1191,13 @@ impl Agent<DeviceBucket> for Device {
     fn stage_two_reverse(&mut self, _core: &mut Core<Self, DeviceBucket>) {}
 
     fn stage_three(&mut self, core: &mut Core<Self, DeviceBucket>) {
+        if self.remote || self.dormant || self.skipping {
+            return;
+        }
         // Receive data from the peers.
-        self.receive_sl(&mut core.bucket);
+        if let Some(ref payloads) = self.receive_sl(&mut core.bucket) {
+            self.models.sl_flow.register_incoming(payloads);
+        }
     }
 
     fn stage_four_reverse(&mut self, core: &mut Core<Self, DeviceBucket>) {
@@ -306,25 +1205,53 @@ impl Agent<DeviceBucket> for Device {
             "Downlink stage for agent: {} id at step: {}",
             self.device_info.id, self.step
         );
-        let bucket = &mut core.bucket;
-        let response = bucket.models.data_lake.response_for(self.device_info.id);
-        self.respond(response, bucket);
+        if !self.remote && !self.dormant && !self.skipping {
+            let bucket = &mut core.bucket;
+            let response = bucket.models.data_lake.response_for(self.device_info.id);
+            self.respond(response, bucket);
 
-        core.bucket.models.result_writer.add_rx_counts(
-            self.step,
-            self.device_info.id,
-            &self.models.flow.out_stats,
-        );
+            core.bucket.models.result_writer.add_rx_counts(
+                self.step,
+                self.device_info.id,
+                &self.models.flow.out_stats,
+            );
+            self.write_duty_cycle(&mut core.bucket);
+        }
 
         if self.step == self.models.power.peek_time_to_off() {
-            self.power_state = PowerState::Off;
+            self.power_state.move_to(PowerState::Off, &mut ());
+            if let (Some(battery), Some(stations)) =
+                (&mut self.models.battery, &mut core.bucket.charging)
+            {
+                battery.leave(self.device_info.id, stations, self.step);
+            }
+            self.write_state_changes(&mut core.bucket);
+            core.bucket.remove_from_space(self.device_info.id);
+            core.bucket
+                .models
+                .sleep_register
+                .switch_off(self.device_info.id);
+            if let Some(ref mut duty_cycle) = self.models.duty_cycle {
+                duty_cycle.pause();
+            }
+            core.bucket
+                .register_deactivation(self.device_info.id, DeactivationReason::Schedule);
+            core.bucket.set_dormant(self.device_info.id, false);
             if self.models.power.has_next_time_to_on() {
                 core.add_agent(self.device_info.id, self.models.power.pop_time_to_on());
             }
         }
     }
 
-    fn stage_five(&mut self, _core: &mut Core<Self, DeviceBucket>) {
+    fn stage_five(&mut self, core: &mut Core<Self, DeviceBucket>) {
         self.compute_stats();
+        if self.remote || self.dormant || self.skipping {
+            return;
+        }
+        core.bucket.register_flows(
+            self.device_info.id,
+            self.device_info.device_class,
+            &[&self.models.flow, &self.models.sl_flow],
+        );
     }
 }
@@ -0,0 +1,269 @@
+use disolv_core::bucket::TimeMS;
+use disolv_core::memory::{MemoryUsage, SubsystemMemory};
+use disolv_models::net::message::{DPayload, DataBlob, DeviceContent};
+use log::{error, warn};
+use serde::Deserialize;
+use std::mem::size_of;
+
+/// Subsystems of the bucket whose memory is sampled. Memory is estimated from the number of
+/// entries held and the size of their types, so it is a lower bound of the allocated memory.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    DataLake,
+    ExpiredPayloads,
+    SleepBuffers,
+    AgeRecords,
+    OutputBuffers,
+    TileCache,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 6] = [
+        Subsystem::DataLake,
+        Subsystem::ExpiredPayloads,
+        Subsystem::SleepBuffers,
+        Subsystem::AgeRecords,
+        Subsystem::OutputBuffers,
+        Subsystem::TileCache,
+    ];
+
+    pub const fn as_int(&self) -> u32 {
+        match self {
+            Subsystem::DataLake => 0,
+            Subsystem::ExpiredPayloads => 1,
+            Subsystem::SleepBuffers => 2,
+            Subsystem::AgeRecords => 3,
+            Subsystem::OutputBuffers => 4,
+            Subsystem::TileCache => 5,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Subsystem::DataLake => "Data lake",
+            Subsystem::ExpiredPayloads => "Expired payloads",
+            Subsystem::SleepBuffers => "Sleep buffers",
+            Subsystem::AgeRecords => "Age records",
+            Subsystem::OutputBuffers => "Output buffers",
+            Subsystem::TileCache => "Tile cache",
+        }
+    }
+
+    /// Whether the entries of the subsystem can be dropped without breaking the simulation.
+    const fn can_clean(&self) -> bool {
+        !matches!(self, Subsystem::AgeRecords | Subsystem::TileCache)
+    }
+
+    pub fn memory(&self, entries: u64, bytes: u64) -> SubsystemMemory {
+        SubsystemMemory {
+            id: self.as_int(),
+            name: self.name(),
+            entries,
+            bytes,
+            over_cap: false,
+        }
+    }
+}
+
+/// What to do when a subsystem exceeds its cap. Cleaning expires the payloads of the data lake,
+/// discards the uncollected expired payloads, drops the sleep buffers or writes the output
+/// buffers to the files.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CapAction {
+    #[default]
+    Warn,
+    Clean,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct MemoryCap {
+    pub subsystem: Subsystem,
+    pub max_entries: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub action: Option<CapAction>,
+}
+
+impl MemoryCap {
+    fn is_exceeded(&self, memory: &SubsystemMemory) -> bool {
+        self.max_entries.is_some_and(|max| memory.entries > max)
+            || self.max_bytes.is_some_and(|max| memory.bytes > max)
+    }
+}
+
+/// Settings of the memory diagnostics. The memory of the subsystems is sampled every
+/// `interval` and checked against the `caps`.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiagnosticsSettings {
+    pub interval: TimeMS,
+    pub caps: Option<Vec<MemoryCap>>,
+}
+
+/// Running totals of the samples over a cap and of the clean-ups they triggered.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CapCounts {
+    pub exceeded: u64,
+    pub cleaned: u64,
+}
+
+/// Samples the memory held by the subsystems of the bucket to find the ones that grow without
+/// bound in long simulations.
+#[derive(Clone, Debug)]
+pub struct MemoryMonitor {
+    interval: TimeMS,
+    next_sample: TimeMS,
+    caps: Vec<MemoryCap>,
+    counts: CapCounts,
+    ready: Option<MemoryUsage>,
+}
+
+impl MemoryMonitor {
+    pub fn new(settings: &DiagnosticsSettings) -> Self {
+        if settings.interval == TimeMS::default() {
+            panic!("Interval of the memory diagnostics must be larger than zero");
+        }
+        let caps = settings.caps.clone().unwrap_or_default();
+        for cap in caps.iter() {
+            if cap.action == Some(CapAction::Clean) && !cap.subsystem.can_clean() {
+                error!("{} cannot be cleaned", cap.subsystem.name());
+                panic!("Invalid memory cap for {}", cap.subsystem.name());
+            }
+        }
+        Self {
+            interval: settings.interval,
+            next_sample: TimeMS::default(),
+            caps,
+            counts: CapCounts::default(),
+            ready: None,
+        }
+    }
+
+    pub fn counts(&self) -> CapCounts {
+        self.counts
+    }
+
+    pub fn is_due(&mut self, step: TimeMS) -> bool {
+        if step < self.next_sample {
+            return false;
+        }
+        self.next_sample = step + self.interval;
+        true
+    }
+
+    /// Marks the memory of the subsystem that exceeds its cap and returns the action to take.
+    pub fn check(
+        &mut self,
+        step: TimeMS,
+        subsystem: Subsystem,
+        memory: &mut SubsystemMemory,
+    ) -> Option<CapAction> {
+        let cap = self
+            .caps
+            .iter()
+            .find(|cap| cap.subsystem == subsystem && cap.is_exceeded(memory))?;
+        memory.over_cap = true;
+        self.counts.exceeded += 1;
+        let action = cap.action.unwrap_or_default();
+        warn!(
+            "{} holds {} entries of {} bytes at step {}, over its cap",
+            subsystem.name(),
+            memory.entries,
+            memory.bytes,
+            step
+        );
+        if action == CapAction::Clean {
+            self.counts.cleaned += 1;
+        }
+        Some(action)
+    }
+
+    pub fn finish_sample(&mut self, usage: MemoryUsage) {
+        self.ready = Some(usage);
+    }
+
+    /// Memory usage of the last sample, returned once.
+    pub fn take_usage(&mut self) -> Option<MemoryUsage> {
+        self.ready.take()
+    }
+}
+
+/// Estimated memory held by a payload, including its blobs and the states gathered in it.
+pub fn payload_bytes(payload: &DPayload) -> u64 {
+    let blobs = payload.metadata.data_blobs.len() * size_of::<DataBlob>();
+    let states = payload
+        .gathered_states
+        .as_ref()
+        .map_or(0, |states| states.len() * size_of::<DeviceContent>());
+    (size_of::<DPayload>() + blobs + states) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(caps: Vec<MemoryCap>) -> MemoryMonitor {
+        MemoryMonitor::new(&DiagnosticsSettings {
+            interval: TimeMS::from(1000),
+            caps: Some(caps),
+        })
+    }
+
+    #[test]
+    fn test_samples_at_interval() {
+        let mut monitor = monitor(Vec::new());
+        assert!(monitor.is_due(TimeMS::from(0)));
+        assert!(!monitor.is_due(TimeMS::from(500)));
+        assert!(monitor.is_due(TimeMS::from(1000)));
+        assert!(!monitor.is_due(TimeMS::from(1900)));
+        assert!(monitor.is_due(TimeMS::from(2100)));
+    }
+
+    #[test]
+    fn test_caps_are_checked() {
+        let mut monitor = monitor(vec![
+            MemoryCap {
+                subsystem: Subsystem::DataLake,
+                max_entries: Some(10),
+                max_bytes: None,
+                action: Some(CapAction::Clean),
+            },
+            MemoryCap {
+                subsystem: Subsystem::OutputBuffers,
+                max_entries: None,
+                max_bytes: Some(1000),
+                action: None,
+            },
+        ]);
+        let step = TimeMS::from(100);
+        let mut lake = Subsystem::DataLake.memory(10, 5000);
+        assert_eq!(monitor.check(step, Subsystem::DataLake, &mut lake), None);
+        assert!(!lake.over_cap);
+        let mut lake = Subsystem::DataLake.memory(11, 5000);
+        assert_eq!(
+            monitor.check(step, Subsystem::DataLake, &mut lake),
+            Some(CapAction::Clean)
+        );
+        assert!(lake.over_cap);
+        let mut buffers = Subsystem::OutputBuffers.memory(1, 2000);
+        assert_eq!(
+            monitor.check(step, Subsystem::OutputBuffers, &mut buffers),
+            Some(CapAction::Warn)
+        );
+        let mut tiles = Subsystem::TileCache.memory(1000, 100000);
+        assert_eq!(monitor.check(step, Subsystem::TileCache, &mut tiles), None);
+        assert_eq!(monitor.counts().exceeded, 2);
+        assert_eq!(monitor.counts().cleaned, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid memory cap for Age records")]
+    fn test_age_records_cannot_be_cleaned() {
+        monitor(vec![MemoryCap {
+            subsystem: Subsystem::AgeRecords,
+            max_entries: Some(10),
+            max_bytes: None,
+            action: Some(CapAction::Clean),
+        }]);
+    }
+}
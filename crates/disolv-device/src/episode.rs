@@ -0,0 +1,66 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::episode::EpisodeInfo;
+use disolv_core::group::GroupId;
+use disolv_models::device::compose::ComposerSettings;
+use disolv_models::device::select::SelectorSettings;
+use disolv_models::device::types::{DeviceClass, DeviceInfo};
+use disolv_models::net::bandwidth::BandwidthConfig;
+use disolv_models::net::latency::LatencyConfig;
+use disolv_models::net::radio::ActionSettings;
+use serde::Deserialize;
+
+/// Changes to the metrics and resources of a slice identified by its ID.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct SliceEpisode {
+    pub id: u32,
+    pub latency: Option<LatencyConfig>,
+    pub bandwidth: Option<BandwidthConfig>,
+}
+
+/// An episode swaps the settings of agents or slices at the given time. Agent settings are
+/// applied to the agents of the target class and the target agents. When neither of them
+/// is given, the episode applies to all the agents. Agents can also join or leave groups.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeviceEpisode {
+    pub start: TimeMS,
+    pub target_class: Option<DeviceClass>,
+    pub target_agents: Option<Vec<AgentId>>,
+    pub composer: Option<ComposerSettings>,
+    pub selector: Option<Vec<SelectorSettings>>,
+    pub actions: Option<Vec<ActionSettings>>,
+    pub slices: Option<Vec<SliceEpisode>>,
+    pub join_groups: Option<Vec<GroupId>>,
+    pub leave_groups: Option<Vec<GroupId>>,
+}
+
+impl EpisodeInfo for DeviceEpisode {}
+
+impl DeviceEpisode {
+    pub fn has_agent_changes(&self) -> bool {
+        self.composer.is_some()
+            || self.selector.is_some()
+            || self.actions.is_some()
+            || self.join_groups.is_some()
+            || self.leave_groups.is_some()
+    }
+
+    pub fn applies_to(&self, device_info: &DeviceInfo) -> bool {
+        if self.target_class.is_none() && self.target_agents.is_none() {
+            return true;
+        }
+        if let Some(target_class) = self.target_class {
+            if target_class == device_info.device_class {
+                return true;
+            }
+        }
+        if let Some(ref target_agents) = self.target_agents {
+            if target_agents.contains(&device_info.id) {
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -1,4 +1,12 @@
+pub mod baseline;
 pub mod bucket;
 pub mod device;
+pub mod diagnostics;
+pub mod episode;
 pub mod linker;
+pub mod positions;
+pub mod region;
+pub mod reload;
 pub mod space;
+pub mod tiles;
+pub mod validate;
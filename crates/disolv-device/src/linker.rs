@@ -16,11 +16,14 @@ pub struct LinkerSettings {
     pub is_streaming: bool,
 }
 
+/// Links of the agents of a type to the agents of the target type. Links are read from the links
+/// file, or given in memory when there is no reader.
 #[derive(Clone, TypedBuilder)]
 pub struct Linker {
     pub source_type: DeviceType,
     pub target_type: DeviceType,
-    pub reader: LinkReader,
+    #[builder(default)]
+    pub reader: Option<LinkReader>,
     pub is_static: bool,
     #[builder(default)]
     pub links: LinkMap,
@@ -35,17 +38,35 @@ impl Linker {
         }
         self.link_cache.remove(&agent_id)
     }
+
+    /// Whether the agent has a link to the target in this step, without taking its links.
+    pub fn has_link(&self, agent_id: AgentId, target: AgentId) -> bool {
+        self.link_cache
+            .get(&agent_id)
+            .is_some_and(|links| links.iter().any(|link| link.target == target))
+    }
+
+    /// Changes the length of the interval of links read in every streaming step.
+    pub fn set_streaming_step(&mut self, streaming_step: TimeMS) {
+        if let Some(ref mut reader) = self.reader {
+            reader.set_streaming_step(streaming_step);
+        }
+    }
 }
 
 impl BucketModel for Linker {
     fn init(&mut self, step: TimeMS) {
-        self.links = self.reader.fetch_links_data(step);
+        if let Some(ref reader) = self.reader {
+            self.links = reader.fetch_links_data(step);
+        }
         self.link_cache = self.links.remove(&step).unwrap_or_default();
     }
 
     fn stream_data(&mut self, step: TimeMS) {
-        if self.reader.is_streaming {
-            self.links = self.reader.fetch_links_data(step);
+        if let Some(ref reader) = self.reader {
+            if reader.is_streaming {
+                self.links = reader.fetch_links_data(step);
+            }
         }
     }
 
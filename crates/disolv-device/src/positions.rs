@@ -0,0 +1,283 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_input::mobility::{MapReader, TraceMap};
+use disolv_models::device::mobility::road::RoadId;
+use disolv_models::device::mobility::velocity::Velocity;
+use disolv_models::device::mobility::{MapState, Point2D};
+use log::info;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"DISOLVPC";
+const HEADER: usize = 16;
+const COLUMNS: usize = 7;
+const WIDTH: usize = 8;
+const NO_ROAD: u64 = u64::MAX;
+
+type Row = (TimeMS, AgentId, MapState);
+
+/// Running totals of the position cache. A hit is a position found for an agent in the step, a
+/// miss is an agent without a position in the step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PositionStats {
+    pub rows: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Positions of a trace in a memory-mapped file with one column per field. The rows are sorted
+/// by time step and agent ID, so the rows of a step are found with a binary search on the time
+/// steps and the row of an agent with a binary search on the agent IDs of the step. Nothing is
+/// decoded before it is looked up.
+///
+/// The file holds a header with a magic number and the row count followed by the time step,
+/// agent ID, x, y, z, velocity and road ID columns, all 8 bytes wide in little endian. Missing
+/// altitudes and velocities are NaN, missing road IDs are `u64::MAX`.
+#[derive(Clone, Debug)]
+pub struct PositionCache {
+    map: Arc<Mmap>,
+    rows: usize,
+    step_rows: Range<usize>,
+    stats: PositionStats,
+}
+
+impl PositionCache {
+    /// Opens the cache file, after building it from the trace file when it is missing or older
+    /// than the trace.
+    pub fn open_or_build(cache_file: &Path, trace_file: &Path) -> Self {
+        if Self::is_stale(cache_file, trace_file) {
+            info!(
+                "Building the position cache {} from {}",
+                cache_file.display(),
+                trace_file.display()
+            );
+            let reader = MapReader::builder()
+                .file_path(trace_file.to_path_buf())
+                .streaming_step(TimeMS::default())
+                .is_streaming(false)
+                .build();
+            Self::write(cache_file, &reader.fetch_traffic_data(TimeMS::default()));
+        }
+        Self::open(cache_file)
+    }
+
+    pub fn open(cache_file: &Path) -> Self {
+        let file = match File::open(cache_file) {
+            Ok(file) => file,
+            Err(e) => panic!("Error opening the position cache: {}", e),
+        };
+        // SAFETY: the cache file is only written by `write`, before it is mapped. Changing it
+        // while the simulation runs is not supported.
+        let map = match unsafe { Mmap::map(&file) } {
+            Ok(map) => map,
+            Err(e) => panic!("Error mapping the position cache: {}", e),
+        };
+        if map.len() < HEADER || &map[..8] != MAGIC {
+            panic!("Invalid position cache {}.", cache_file.display());
+        }
+        let rows = u64::from_le_bytes(map[8..HEADER].try_into().expect("header is 16 bytes"));
+        let rows = rows as usize;
+        if map.len() != HEADER + rows * COLUMNS * WIDTH {
+            panic!("Invalid position cache {}.", cache_file.display());
+        }
+        Self {
+            map: Arc::new(map),
+            rows,
+            step_rows: 0..0,
+            stats: PositionStats {
+                rows: rows as u64,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Writes the positions of the trace to the cache file in the order of the lookups.
+    pub fn write(cache_file: &Path, trace: &TraceMap) {
+        let mut rows: Vec<Row> = trace
+            .iter()
+            .flat_map(|(step, states)| {
+                states
+                    .iter()
+                    .map(move |(agent_id, state)| (*step, *agent_id, *state))
+            })
+            .collect();
+        rows.sort_by_key(|(step, agent_id, _)| (step.as_u64(), agent_id.as_u64()));
+
+        let file = match File::create(cache_file) {
+            Ok(file) => file,
+            Err(e) => panic!("Error creating the position cache: {}", e),
+        };
+        let columns: [fn(&Row) -> [u8; WIDTH]; COLUMNS] = [
+            |row| row.0.as_u64().to_le_bytes(),
+            |row| row.1.as_u64().to_le_bytes(),
+            |row| row.2.pos.x.to_le_bytes(),
+            |row| row.2.pos.y.to_le_bytes(),
+            |row| row.2.z.unwrap_or(f64::NAN).to_le_bytes(),
+            |row| {
+                let velocity = row.2.velocity.map(|v| v.as_f64());
+                velocity.unwrap_or(f64::NAN).to_le_bytes()
+            },
+            |row| {
+                let road_id = row.2.road_id.map(|road| road.as_u32() as u64);
+                road_id.unwrap_or(NO_ROAD).to_le_bytes()
+            },
+        ];
+        let mut writer = BufWriter::new(file);
+        let mut written = writer
+            .write_all(MAGIC)
+            .and_then(|_| writer.write_all(&(rows.len() as u64).to_le_bytes()));
+        for column in columns.iter() {
+            for row in rows.iter() {
+                written = written.and_then(|_| writer.write_all(&column(row)));
+            }
+        }
+        if let Err(e) = written.and_then(|_| writer.flush()) {
+            panic!("Error writing the position cache: {}", e);
+        }
+    }
+
+    pub fn stats(&self) -> PositionStats {
+        self.stats
+    }
+
+    /// Selects the rows of the step for the following lookups.
+    pub fn start_step(&mut self, step: TimeMS) {
+        let step = step.as_u64();
+        let start = self.partition_point(0, 0..self.rows, |time| time < step);
+        let end = self.partition_point(0, start..self.rows, |time| time <= step);
+        self.step_rows = start..end;
+    }
+
+    /// Position of the agent in the step selected last.
+    pub fn map_state_of(&mut self, agent_id: AgentId) -> Option<MapState> {
+        let agent = agent_id.as_u64();
+        let row = self.partition_point(1, self.step_rows.clone(), |id| id < agent);
+        if row == self.step_rows.end || self.value(1, row) != agent {
+            self.stats.misses += 1;
+            return None;
+        }
+        self.stats.hits += 1;
+        Some(self.map_state(row))
+    }
+
+    /// Positions of all the agents in the step selected last.
+    pub fn map_states(&self) -> impl Iterator<Item = (AgentId, MapState)> + '_ {
+        self.step_rows
+            .clone()
+            .map(|row| (AgentId::from(self.value(1, row)), self.map_state(row)))
+    }
+
+    fn map_state(&self, row: usize) -> MapState {
+        let x = f64::from_bits(self.value(2, row));
+        let y = f64::from_bits(self.value(3, row));
+        let z = f64::from_bits(self.value(4, row));
+        let velocity = f64::from_bits(self.value(5, row));
+        let road_id = self.value(6, row);
+        MapState::builder()
+            .pos(Point2D::builder().x(x).y(y).build())
+            .z((!z.is_nan()).then_some(z))
+            .velocity((!velocity.is_nan()).then(|| Velocity::from(velocity)))
+            .road_id((road_id != NO_ROAD).then(|| RoadId::from(road_id as u32)))
+            .build()
+    }
+
+    /// First row in the range whose value in the column does not satisfy the predicate. The
+    /// values in the range must be sorted.
+    fn partition_point<P>(&self, column: usize, rows: Range<usize>, mut predicate: P) -> usize
+    where
+        P: FnMut(u64) -> bool,
+    {
+        let (mut low, mut high) = (rows.start, rows.end);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match predicate(self.value(column, mid)) {
+                true => low = mid + 1,
+                false => high = mid,
+            }
+        }
+        low
+    }
+
+    #[inline]
+    fn value(&self, column: usize, row: usize) -> u64 {
+        let offset = HEADER + (column * self.rows + row) * WIDTH;
+        let bytes = &self.map[offset..offset + WIDTH];
+        u64::from_le_bytes(bytes.try_into().expect("values are 8 bytes"))
+    }
+
+    fn is_stale(cache_file: &Path, trace_file: &Path) -> bool {
+        let modified = |path: &Path| path.metadata().and_then(|meta| meta.modified()).ok();
+        match (modified(cache_file), modified(trace_file)) {
+            (Some(cache), Some(trace)) => cache < trace,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use disolv_core::hashbrown::HashMap;
+
+    fn state(x: f64, road: Option<u32>) -> MapState {
+        MapState::builder()
+            .pos(Point2D::builder().x(x).y(x * 2.0).build())
+            .road_id(road.map(RoadId::from))
+            .build()
+    }
+
+    #[test]
+    fn test_positions_are_looked_up_by_step_and_agent() {
+        let mut trace: TraceMap = HashMap::new();
+        for step in [0u64, 100, 200] {
+            let states = trace.entry(TimeMS::from(step)).or_default();
+            for agent in [7u64, 3, 5] {
+                states.insert(AgentId::from(agent), state((step + agent) as f64, Some(1)));
+            }
+        }
+        trace
+            .get_mut(&TimeMS::from(100))
+            .expect("step is in the trace")
+            .insert(AgentId::from(9), state(1.0, None));
+        let file = std::env::temp_dir().join("disolv_position_cache.bin");
+        PositionCache::write(&file, &trace);
+
+        let mut cache = PositionCache::open(&file);
+        cache.start_step(TimeMS::from(100));
+        let found = cache
+            .map_state_of(AgentId::from(5))
+            .expect("agent 5 is present");
+        assert_eq!(found.pos.x, 105.0);
+        assert_eq!(found.pos.y, 210.0);
+        assert_eq!(found.road_id, Some(RoadId::from(1u32)));
+        assert_eq!(found.z, None);
+        let found = cache
+            .map_state_of(AgentId::from(9))
+            .expect("agent 9 is present");
+        assert_eq!(found.road_id, None);
+        assert!(cache.map_state_of(AgentId::from(4)).is_none());
+        assert_eq!(cache.map_states().count(), 4);
+
+        cache.start_step(TimeMS::from(200));
+        assert!(cache.map_state_of(AgentId::from(9)).is_none());
+        cache.start_step(TimeMS::from(150));
+        assert_eq!(cache.map_states().count(), 0);
+
+        let stats = cache.stats();
+        assert_eq!((stats.rows, stats.hits, stats.misses), (10, 2, 2));
+        std::fs::remove_file(&file).expect("file is removed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid position cache")]
+    fn test_foreign_file_is_rejected() {
+        let file = std::env::temp_dir().join("disolv_position_cache_foreign.bin");
+        std::fs::write(&file, b"not a position cache").expect("file is written");
+        PositionCache::open(&file);
+    }
+}
@@ -0,0 +1,402 @@
+use disolv_core::agent::{AgentId, AgentOrder};
+use disolv_core::boundary::{Boundary, BoundarySettings, RegionId};
+use disolv_core::bucket::TimeMS;
+use disolv_core::group::GroupId;
+use disolv_core::hashbrown::HashMap;
+use disolv_core::uuid::Uuid;
+use disolv_models::device::mobility::{MapState, Point2D};
+use disolv_models::device::types::{DeviceClass, DeviceInfo, DeviceType};
+use disolv_models::net::message::PayloadInfo;
+use disolv_models::net::message::{BlobOrigin, DPayload, DataBlob, DataType, DeviceContent};
+use disolv_models::net::metrics::Bytes;
+use disolv_models::net::operator::OperatorId;
+use disolv_models::net::radio::{Action, ActionType, DLink, LinkProperties};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+/// Splits the field into vertical stripes of equal width, one per region. Positions outside the
+/// field belong to the first or the last stripe.
+#[derive(Clone, Copy, Debug)]
+pub struct Partition {
+    stripe_width: f64,
+    regions: u32,
+}
+
+impl Partition {
+    pub fn new(field_width: f64, regions: u32) -> Self {
+        Self {
+            stripe_width: field_width / regions as f64,
+            regions,
+        }
+    }
+
+    pub fn region_of(&self, position: &Point2D) -> RegionId {
+        let stripe = (position.x / self.stripe_width).floor().max(0.0) as u32;
+        stripe.min(self.regions - 1)
+    }
+}
+
+/// State of an agent as sent to another region.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct RemoteState {
+    id: AgentId,
+    device_type: DeviceType,
+    device_class: DeviceClass,
+    agent_order: u32,
+    operator: Option<OperatorId>,
+    x: f64,
+    y: f64,
+    z: Option<f64>,
+}
+
+impl RemoteState {
+    fn from_content(content: &DeviceContent) -> Self {
+        Self {
+            id: content.device_info.id,
+            device_type: content.device_info.device_type,
+            device_class: content.device_info.device_class,
+            agent_order: content.device_info.agent_order.0,
+            operator: content.device_info.operator,
+            x: content.map_state.pos.x,
+            y: content.map_state.pos.y,
+            z: content.map_state.z,
+        }
+    }
+
+    fn to_content(self) -> DeviceContent {
+        DeviceContent {
+            device_info: DeviceInfo::builder()
+                .id(self.id)
+                .device_type(self.device_type)
+                .device_class(self.device_class)
+                .agent_order(AgentOrder(self.agent_order))
+                .operator(self.operator)
+                .build(),
+            map_state: MapState::builder()
+                .pos(Point2D::builder().x(self.x).y(self.y).build())
+                .z(self.z)
+                .build(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct RemoteBlob {
+    data_type: DataType,
+    data_size: u64,
+    action_type: ActionType,
+    to_class: Option<DeviceClass>,
+    to_agent: Option<AgentId>,
+    to_kind: Option<DeviceType>,
+    to_group: Option<u64>,
+    content_id: Option<u64>,
+    created_at: u64,
+    raw_size: Option<u64>,
+    origin: Option<(AgentId, u64)>,
+}
+
+impl RemoteBlob {
+    fn from_blob(blob: &DataBlob) -> Self {
+        Self {
+            data_type: blob.data_type,
+            data_size: blob.data_size.as_u64(),
+            action_type: blob.action.action_type,
+            to_class: blob.action.to_class,
+            to_agent: blob.action.to_agent,
+            to_kind: blob.action.to_kind,
+            to_group: blob.action.to_group.map(|group_id| group_id.as_u64()),
+            content_id: blob.content_id,
+            created_at: blob.created_at.as_u64(),
+            raw_size: blob.raw_size.map(|raw_size| raw_size.as_u64()),
+            origin: blob.origin.map(|origin| (origin.agent_id, origin.sequence)),
+        }
+    }
+
+    fn to_blob(self) -> DataBlob {
+        let action = Action::builder()
+            .action_type(self.action_type)
+            .to_class(self.to_class)
+            .to_agent(self.to_agent)
+            .to_kind(self.to_kind)
+            .to_group(self.to_group.map(GroupId::from))
+            .build();
+        DataBlob::builder()
+            .data_type(self.data_type)
+            .data_size(Bytes::new(self.data_size))
+            .action(action)
+            .content_id(self.content_id)
+            .created_at(TimeMS::from(self.created_at))
+            .raw_size(self.raw_size.map(Bytes::new))
+            .origin(
+                self.origin
+                    .map(|(agent_id, sequence)| BlobOrigin { agent_id, sequence }),
+            )
+            .build()
+    }
+}
+
+/// A payload delivered to an agent in another region. Only the properties of the payload that
+/// the receiving agent uses are sent.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct RemotePayload {
+    target: AgentId,
+    sidelink: bool,
+    id: String,
+    source: RemoteState,
+    gathered: Option<Vec<RemoteState>>,
+    total_size: u64,
+    total_count: u32,
+    blobs: Vec<RemoteBlob>,
+    distance: Option<f32>,
+    expires_at: Option<u64>,
+}
+
+impl RemotePayload {
+    pub(crate) fn new(target: AgentId, payload: &DPayload, sidelink: bool) -> Self {
+        let metadata = &payload.metadata;
+        Self {
+            target,
+            sidelink,
+            id: metadata.id.to_string(),
+            source: RemoteState::from_content(&payload.agent_state),
+            gathered: payload
+                .gathered_states
+                .as_ref()
+                .map(|states| states.iter().map(RemoteState::from_content).collect()),
+            total_size: metadata.total_size.as_u64(),
+            total_count: metadata.total_count,
+            blobs: metadata
+                .data_blobs
+                .iter()
+                .map(RemoteBlob::from_blob)
+                .collect(),
+            distance: metadata.selected_link.properties.distance,
+            expires_at: metadata.expires_at.map(|expires_at| expires_at.as_u64()),
+        }
+    }
+
+    fn into_payload(self) -> DPayload {
+        let id = match Uuid::parse_str(&self.id) {
+            Ok(id) => id,
+            Err(e) => panic!("Invalid payload id {} from another region: {}", self.id, e),
+        };
+        let selected_link = DLink::builder()
+            .target(self.target)
+            .properties(LinkProperties {
+                distance: self.distance,
+                ..Default::default()
+            })
+            .build();
+        let metadata = PayloadInfo::builder()
+            .id(id)
+            .total_size(Bytes::new(self.total_size))
+            .total_count(self.total_count)
+            .data_blobs(self.blobs.into_iter().map(RemoteBlob::to_blob).collect())
+            .selected_link(selected_link)
+            .expires_at(self.expires_at.map(TimeMS::from))
+            .build();
+        DPayload::builder()
+            .agent_state(self.source.to_content())
+            .metadata(metadata)
+            .gathered_states(
+                self.gathered
+                    .map(|states| states.into_iter().map(RemoteState::to_content).collect()),
+            )
+            .build()
+    }
+}
+
+/// Running totals of the exchange with the other regions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RegionCounts {
+    pub handoffs: u64,
+    pub payloads_sent: u64,
+    pub payloads_received: u64,
+}
+
+/// Payload received from another region for an agent of this region.
+pub struct BoundaryDelivery {
+    pub target: AgentId,
+    pub payload: DPayload,
+    pub sidelink: bool,
+}
+
+/// Region of the map simulated by this process in the distributed mode. Every process reads the
+/// positions of all the agents, but simulates only the agents in its region. Agents without a
+/// position belong to the first region. Payloads to the agents in other regions are sent over
+/// the boundary at the start of the next step, along with the payloads waiting for the agents
+/// that left the region.
+pub struct RegionExchange {
+    partition: Partition,
+    boundary: Boundary,
+    owners: HashMap<AgentId, RegionId>,
+    outgoing: HashMap<RegionId, Vec<RemotePayload>>,
+    counts: RegionCounts,
+}
+
+impl RegionExchange {
+    pub fn new(settings: &BoundarySettings, field_width: f64) -> Self {
+        let boundary = Boundary::connect(settings);
+        Self {
+            partition: Partition::new(field_width, boundary.regions()),
+            boundary,
+            owners: HashMap::new(),
+            outgoing: HashMap::new(),
+            counts: RegionCounts::default(),
+        }
+    }
+
+    pub fn region(&self) -> RegionId {
+        self.boundary.region()
+    }
+
+    pub fn counts(&self) -> RegionCounts {
+        self.counts
+    }
+
+    pub fn owner_of(&self, agent_id: AgentId) -> RegionId {
+        self.owners.get(&agent_id).copied().unwrap_or_default()
+    }
+
+    pub fn is_local(&self, agent_id: AgentId) -> bool {
+        self.owner_of(agent_id) == self.region()
+    }
+
+    /// Assigns the agents to the regions of their positions in this step. Returns the agents
+    /// that left this region.
+    pub fn assign(
+        &mut self,
+        map_states: impl Iterator<Item = (AgentId, MapState)>,
+    ) -> Vec<AgentId> {
+        let region = self.region();
+        let mut departed = Vec::new();
+        for (agent_id, map_state) in map_states {
+            let owner = self.partition.region_of(&map_state.pos);
+            let previous = self.owners.insert(agent_id, owner);
+            if previous == Some(region) && owner != region {
+                debug!("Agent {} moves to region {}", agent_id, owner);
+                departed.push(agent_id);
+            }
+        }
+        self.counts.handoffs += departed.len() as u64;
+        departed
+    }
+
+    /// Queues the payload for the agent in another region.
+    pub fn send(&mut self, target: AgentId, payload: &DPayload, sidelink: bool) {
+        self.counts.payloads_sent += 1;
+        self.outgoing
+            .entry(self.owner_of(target))
+            .or_default()
+            .push(RemotePayload::new(target, payload, sidelink));
+    }
+
+    /// Sends the queued payloads to the other regions and returns the payloads they sent.
+    pub fn exchange(&mut self, step: TimeMS) -> Vec<BoundaryDelivery> {
+        let outgoing = self
+            .outgoing
+            .drain()
+            .map(|(region, payloads)| match serde_json::to_vec(&payloads) {
+                Ok(content) => (region, content),
+                Err(e) => panic!("Failed to encode the payloads for region {}: {}", region, e),
+            })
+            .collect();
+        let mut deliveries = Vec::new();
+        for (region, content) in self.boundary.exchange(step, outgoing) {
+            if content.is_empty() {
+                continue;
+            }
+            let payloads: Vec<RemotePayload> = match serde_json::from_slice(&content) {
+                Ok(payloads) => payloads,
+                Err(e) => panic!(
+                    "Failed to decode the payloads from region {}: {}",
+                    region, e
+                ),
+            };
+            deliveries.extend(payloads.into_iter().map(|payload| BoundaryDelivery {
+                target: payload.target,
+                sidelink: payload.sidelink,
+                payload: payload.into_payload(),
+            }));
+        }
+        self.counts.payloads_received += deliveries.len() as u64;
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_into_stripes() {
+        let partition = Partition::new(1000.0, 4);
+        let at = |x: f64| Point2D::builder().x(x).y(50.0).build();
+        assert_eq!(partition.region_of(&at(0.0)), 0);
+        assert_eq!(partition.region_of(&at(249.9)), 0);
+        assert_eq!(partition.region_of(&at(250.0)), 1);
+        assert_eq!(partition.region_of(&at(999.0)), 3);
+        assert_eq!(partition.region_of(&at(-10.0)), 0);
+        assert_eq!(partition.region_of(&at(1200.0)), 3);
+    }
+
+    #[test]
+    fn test_remote_payload_round_trip() {
+        let source = DeviceContent {
+            device_info: DeviceInfo::builder()
+                .id(AgentId::from(7))
+                .device_type(DeviceType::Vehicle)
+                .device_class(DeviceClass::Vehicle5G)
+                .agent_order(AgentOrder(1))
+                .build(),
+            map_state: MapState::builder()
+                .pos(Point2D::builder().x(10.0).y(20.0).build())
+                .build(),
+        };
+        let blob = DataBlob::builder()
+            .data_type(DataType::CAM)
+            .data_size(Bytes::new(300))
+            .action(
+                Action::builder()
+                    .action_type(ActionType::Forward)
+                    .to_class(Some(DeviceClass::RSU5G))
+                    .to_agent(None)
+                    .to_kind(None)
+                    .to_group(Some(GroupId::from("platoon")))
+                    .build(),
+            )
+            .created_at(TimeMS::from(500))
+            .build();
+        let payload = DPayload::builder()
+            .agent_state(source)
+            .metadata(
+                PayloadInfo::builder()
+                    .id(Uuid::new_v4())
+                    .total_size(Bytes::new(300))
+                    .total_count(1)
+                    .data_blobs(vec![blob])
+                    .selected_link(DLink::new(AgentId::from(100)))
+                    .build(),
+            )
+            .gathered_states(None)
+            .build();
+        let remote = RemotePayload::new(AgentId::from(100), &payload, false);
+        let encoded = serde_json::to_vec(&vec![remote]).expect("Failed to encode");
+        let decoded: Vec<RemotePayload> =
+            serde_json::from_slice(&encoded).expect("Failed to decode");
+        let received = decoded
+            .into_iter()
+            .next()
+            .expect("Payload missing")
+            .into_payload();
+
+        assert_eq!(received.metadata.id, payload.metadata.id);
+        assert_eq!(received.agent_state.device_info.id, AgentId::from(7));
+        assert_eq!(received.agent_state.map_state.pos.y, 20.0);
+        assert_eq!(received.metadata.total_size, Bytes::new(300));
+        let blob = received.metadata.data_blobs[0];
+        assert_eq!(blob.action.to_class, Some(DeviceClass::RSU5G));
+        assert_eq!(blob.action.to_group, Some(GroupId::from("platoon")));
+        assert_eq!(blob.created_at, TimeMS::from(500));
+    }
+}
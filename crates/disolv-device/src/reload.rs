@@ -0,0 +1,182 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::types::DeviceClass;
+use disolv_models::net::message::DataType;
+use disolv_models::net::metrics::Bandwidth;
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// New step of the data sources of a data type in the composers of an agent class.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ComposerReload {
+    pub agent_class: DeviceClass,
+    pub data_type: DataType,
+    pub source_step: TimeMS,
+}
+
+/// New thresholds of the selector of an agent class for a target class.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SelectorReload {
+    pub agent_class: DeviceClass,
+    pub target_class: DeviceClass,
+    pub link_count: Option<u32>,
+    pub dist_threshold: Option<f32>,
+}
+
+/// New nominal capacity of the slice identified by its ID.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SliceReload {
+    pub id: u32,
+    pub capacity: Bandwidth,
+}
+
+/// Parameters that can be changed during the run through the watch file. Only the parameters
+/// listed here are accepted, a watch file with any other parameter is rejected as a whole.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ReloadedSettings {
+    #[serde(default)]
+    pub composer: Vec<ComposerReload>,
+    #[serde(default)]
+    pub selector: Vec<SelectorReload>,
+    #[serde(default)]
+    pub slice: Vec<SliceReload>,
+}
+
+impl ReloadedSettings {
+    pub fn has_agent_changes(&self) -> bool {
+        !self.composer.is_empty() || !self.selector.is_empty()
+    }
+
+    /// Parameters changed by the settings with their new values.
+    pub fn changes(&self) -> Vec<(String, String)> {
+        let mut changes = Vec::new();
+        for composer in self.composer.iter() {
+            changes.push((
+                format!(
+                    "composer.{}.{}.source_step",
+                    composer.agent_class, composer.data_type
+                ),
+                composer.source_step.to_string(),
+            ));
+        }
+        for selector in self.selector.iter() {
+            let prefix = format!(
+                "selector.{}.{}",
+                selector.agent_class, selector.target_class
+            );
+            if let Some(link_count) = selector.link_count {
+                changes.push((format!("{}.link_count", prefix), link_count.to_string()));
+            }
+            if let Some(dist_threshold) = selector.dist_threshold {
+                changes.push((
+                    format!("{}.dist_threshold", prefix),
+                    dist_threshold.to_string(),
+                ));
+            }
+        }
+        for slice in self.slice.iter() {
+            changes.push((
+                format!("slice.{}.capacity", slice.id),
+                slice.capacity.as_u64().to_string(),
+            ));
+        }
+        changes
+    }
+}
+
+/// Watches a TOML file whose changes adjust the settings of the running simulation. The file is
+/// read when polled, and its settings are returned whenever its content differs from the last
+/// read, including the first time the file is found.
+#[derive(Clone, Debug)]
+pub struct SettingsWatcher {
+    watch_file: PathBuf,
+    content: Option<String>,
+}
+
+impl SettingsWatcher {
+    pub fn new(watch_file: &Path) -> Self {
+        info!("Watching {} for settings changes", watch_file.display());
+        Self {
+            watch_file: watch_file.to_path_buf(),
+            content: None,
+        }
+    }
+
+    /// Settings of the watch file if it changed since the last poll. A watch file that cannot
+    /// be parsed is ignored until it changes again.
+    pub fn poll(&mut self) -> Option<ReloadedSettings> {
+        let content = std::fs::read_to_string(&self.watch_file).ok()?;
+        if self.content.as_ref() == Some(&content) {
+            return None;
+        }
+        let settings = toml::from_str(&content);
+        self.content = Some(content);
+        match settings {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                warn!(
+                    "Ignoring the invalid watch file {}: {}",
+                    self.watch_file.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch_file(name: &str, content: &str) -> PathBuf {
+        let watch_file = std::env::temp_dir().join(name);
+        std::fs::write(&watch_file, content).expect("watch file is written");
+        watch_file
+    }
+
+    #[test]
+    fn test_changes_are_picked_up_once() {
+        let file = watch_file(
+            "disolv_reload_changes.toml",
+            "[[composer]]\nagent_class = \"Vehicle5G\"\ndata_type = \"CAM\"\nsource_step = 500\n",
+        );
+        let mut watcher = SettingsWatcher::new(&file);
+        let settings = watcher.poll().expect("first read is a change");
+        assert_eq!(
+            settings.changes(),
+            vec![(
+                "composer.Vehicle5G.CAM.source_step".to_string(),
+                "500".to_string()
+            )]
+        );
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&file, "[[slice]]\nid = 0\ncapacity = 1000\n").expect("file is written");
+        let settings = watcher.poll().expect("file changed");
+        assert!(!settings.has_agent_changes());
+        assert_eq!(
+            settings.changes(),
+            vec![("slice.0.capacity".to_string(), "1000".to_string())]
+        );
+        std::fs::remove_file(&file).expect("file is removed");
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_parameters_outside_whitelist_are_rejected() {
+        let file = watch_file(
+            "disolv_reload_whitelist.toml",
+            "[[slice]]\nid = 0\ncapacity = 1000\nlatency = 10\n",
+        );
+        let mut watcher = SettingsWatcher::new(&file);
+        assert!(watcher.poll().is_none());
+        assert!(watcher.poll().is_none());
+        std::fs::remove_file(&file).expect("file is removed");
+    }
+}
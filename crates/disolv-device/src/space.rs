@@ -1,8 +1,11 @@
+use crate::positions::{PositionCache, PositionStats};
+use crate::tiles::{TileCache, TileSettings, TileStats};
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::TimeMS;
 use disolv_core::hashbrown::{HashMap, HashSet};
 use disolv_core::model::BucketModel;
 use disolv_input::mobility::{MapReader, TraceMap};
+use disolv_input::tiles::TileReader;
 use disolv_models::device::mobility::cell::CellId;
 use disolv_models::device::mobility::{MapState, MobilityType, Point2D};
 use serde::Deserialize;
@@ -18,21 +21,72 @@ pub struct Space {
     cell2agent: HashMap<CellId, HashSet<AgentId>>,
     #[builder(default)]
     agent2cell: HashMap<AgentId, CellId>,
+    #[builder(default)]
+    positions: HashMap<AgentId, Point2D>,
 }
 
 impl Space {
     pub fn add_agent(&mut self, agent_id: AgentId, location: &Point2D) {
         let cell_id = self.get_cell_id(location);
-        let old_cell_id = self.agent2cell.entry(agent_id).or_default();
-        if *old_cell_id != cell_id {
+        self.positions.insert(agent_id, *location);
+        match self.agent2cell.insert(agent_id, cell_id) {
+            Some(old_cell_id) if old_cell_id == cell_id => return,
+            Some(old_cell_id) => {
+                if let Some(agents) = self.cell2agent.get_mut(&old_cell_id) {
+                    agents.remove(&agent_id);
+                }
+            }
+            None => (),
+        }
+        self.add_agent_to_cell(agent_id, cell_id);
+    }
+
+    pub fn remove_agent(&mut self, agent_id: AgentId) {
+        self.positions.remove(&agent_id);
+        if let Some(cell_id) = self.agent2cell.remove(&agent_id) {
             if let Some(agents) = self.cell2agent.get_mut(&cell_id) {
                 agents.remove(&agent_id);
             }
-            self.add_agent_to_cell(agent_id, cell_id);
-            self.agent2cell.entry(agent_id).and_modify(|e| *e = cell_id);
         }
     }
 
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn position_of(&self, agent_id: AgentId) -> Option<&Point2D> {
+        self.positions.get(&agent_id)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Point2D> {
+        self.positions.values()
+    }
+
+    /// Average distance between the positions of the agents and their earlier positions, over
+    /// the agents with both. Returns the positions to compare with later.
+    pub fn displacement_since(
+        &self,
+        earlier: &HashMap<AgentId, Point2D>,
+    ) -> (Option<f64>, HashMap<AgentId, Point2D>) {
+        let distances: Vec<f64> = self
+            .positions
+            .iter()
+            .filter_map(|(agent_id, now)| {
+                let before = earlier.get(agent_id)?;
+                Some(((now.x - before.x).powi(2) + (now.y - before.y).powi(2)).sqrt())
+            })
+            .collect();
+        let displacement = match distances.is_empty() {
+            true => None,
+            false => Some(distances.iter().sum::<f64>() / distances.len() as f64),
+        };
+        (displacement, self.positions.clone())
+    }
+
     pub fn agents(&self, cell_id: CellId) -> Option<&HashSet<AgentId>> {
         self.cell2agent.get(&cell_id)
     }
@@ -41,15 +95,54 @@ impl Space {
         self.agent2cell.get(&agent_id)
     }
 
+    /// Agents within the range of the given location, excluding the agent itself. Only the
+    /// cells that overlap the range are searched.
+    pub fn neighbours_of(
+        &self,
+        agent_id: AgentId,
+        location: &Point2D,
+        range: f64,
+    ) -> Vec<(AgentId, Point2D)> {
+        let (cell_x, cell_y) = self.cell_coords(location);
+        let reach = (range / self.cell_size).floor() + 1.0;
+        let mut neighbours = Vec::new();
+        let mut x = (cell_x - reach).max(0.0);
+        while x <= cell_x + reach {
+            let mut y = (cell_y - reach).max(0.0);
+            while y <= cell_y + reach {
+                if let Some(agents) = self.cell2agent.get(&CellId::from(x + (y * self.width))) {
+                    for other_id in agents.iter().filter(|other| **other != agent_id) {
+                        let other = self.positions[other_id];
+                        let dx = other.x - location.x;
+                        let dy = other.y - location.y;
+                        if (dx * dx + dy * dy).sqrt() <= range {
+                            neighbours.push((*other_id, other));
+                        }
+                    }
+                }
+                y += 1.0;
+            }
+            x += 1.0;
+        }
+        neighbours
+    }
+
     #[inline]
     fn add_agent_to_cell(&mut self, agent_id: AgentId, cell_id: CellId) {
         self.cell2agent.entry(cell_id).or_default().insert(agent_id);
     }
 
+    #[inline]
+    fn cell_coords(&self, location: &Point2D) -> (f64, f64) {
+        (
+            (location.x / self.cell_size).round(),
+            (location.y / self.cell_size).round(),
+        )
+    }
+
     #[inline]
     fn get_cell_id(&self, location: &Point2D) -> CellId {
-        let cell_x = (location.x / self.cell_size).round();
-        let cell_y = (location.y / self.cell_size).round();
+        let (cell_x, cell_y) = self.cell_coords(location);
         CellId::from(cell_x + (cell_y * self.width))
     }
 }
@@ -67,27 +160,50 @@ pub struct MobilitySettings {
     pub is_streaming: bool,
     pub mobility_step: Option<TimeMS>,
     pub trace_file: String,
+    pub tiling: Option<TileSettings>,
+    pub position_cache: Option<String>,
 }
 
+/// Positions of the agents of a type. Positions are read from the trace file, from the tiles
+/// of a tiled trace or from the memory-mapped position cache built from the trace, or given in
+/// memory when there is no reader.
 #[derive(Clone)]
 pub struct Mapper {
-    reader: MapReader,
+    reader: Option<MapReader>,
+    tiles: Option<(TileReader, TileCache)>,
+    positions: Option<PositionCache>,
     map_states: TraceMap,
     map_cache: HashMap<AgentId, MapState>,
 }
 
 impl BucketModel for Mapper {
     fn init(&mut self, step: TimeMS) {
-        self.map_states = self.reader.fetch_traffic_data(step);
+        if let Some(ref reader) = self.reader {
+            self.map_states = reader.fetch_traffic_data(step);
+        }
+        if let Some((ref reader, ref mut cache)) = self.tiles {
+            self.map_states = cache.fetch(reader, step);
+        }
     }
 
+    /// Tiles are read in every streaming step as the agents move, also when the trace is not
+    /// streamed.
     fn stream_data(&mut self, step: TimeMS) {
-        if self.reader.is_streaming {
-            self.map_states = self.reader.fetch_traffic_data(step);
+        if let Some(ref reader) = self.reader {
+            if reader.is_streaming {
+                self.map_states = reader.fetch_traffic_data(step);
+            }
+        }
+        if let Some((ref reader, ref mut cache)) = self.tiles {
+            self.map_states = cache.fetch(reader, step);
         }
     }
 
     fn before_agent_step(&mut self, step: TimeMS) {
+        if let Some(ref mut positions) = self.positions {
+            positions.start_step(step);
+            return;
+        }
         self.map_cache = self.map_states.remove(&step).unwrap_or_default()
     }
 }
@@ -97,8 +213,57 @@ impl Mapper {
         MapperBuilder::new(config_path)
     }
 
+    /// Mapper with all the positions given in memory.
+    pub fn with_trace(trace: TraceMap) -> Self {
+        Mapper {
+            reader: None,
+            tiles: None,
+            positions: None,
+            map_states: trace,
+            map_cache: HashMap::default(),
+        }
+    }
+
     pub fn map_state_of(&mut self, agent_id: AgentId) -> Option<MapState> {
-        self.map_cache.remove(&agent_id)
+        match self.positions {
+            Some(ref mut positions) => positions.map_state_of(agent_id),
+            None => self.map_cache.remove(&agent_id),
+        }
+    }
+
+    /// Positions of the agents in this step. Positions that are not from the position cache are
+    /// left out once they are taken by the agents.
+    pub fn map_states(&self) -> Box<dyn Iterator<Item = (AgentId, MapState)> + '_> {
+        match self.positions {
+            Some(ref positions) => Box::new(positions.map_states()),
+            None => Box::new(self.map_cache.iter().map(|(id, state)| (*id, *state))),
+        }
+    }
+
+    /// Sets the area of the tiles read in the next streaming step to the bounding box of the
+    /// given positions.
+    pub fn focus_on<'a>(&mut self, positions: impl Iterator<Item = &'a Point2D>) {
+        if let Some((_, ref mut cache)) = self.tiles {
+            cache.focus_on(positions);
+        }
+    }
+
+    pub fn tile_stats(&self) -> Option<TileStats> {
+        self.tiles.as_ref().map(|(_, cache)| cache.stats())
+    }
+
+    pub fn position_stats(&self) -> Option<PositionStats> {
+        self.positions.as_ref().map(|positions| positions.stats())
+    }
+
+    /// Changes the length of the interval of positions read in every streaming step.
+    pub fn set_streaming_step(&mut self, streaming_step: TimeMS) {
+        if let Some(ref mut reader) = self.reader {
+            reader.set_streaming_step(streaming_step);
+        }
+        if let Some((ref mut reader, _)) = self.tiles {
+            reader.set_streaming_step(streaming_step);
+        }
     }
 }
 
@@ -135,6 +300,32 @@ impl MapperBuilder {
 
     pub fn build(self) -> Mapper {
         let file_path = self.config_path.join(&self.space_settings.trace_file);
+        if let Some(ref cache_file) = self.space_settings.position_cache {
+            let cache_file = self.config_path.join(cache_file);
+            return Mapper {
+                reader: None,
+                tiles: None,
+                positions: Some(PositionCache::open_or_build(&cache_file, &file_path)),
+                map_states: HashMap::default(),
+                map_cache: HashMap::default(),
+            };
+        }
+        if let Some(ref tiling) = self.space_settings.tiling {
+            let tile_reader = TileReader::builder()
+                .is_streaming(self.space_settings.is_streaming)
+                .tile_size(tiling.tile_size)
+                .directory(file_path)
+                .streaming_step(self.streaming_step)
+                .build()
+                .scan();
+            return Mapper {
+                reader: None,
+                tiles: Some((tile_reader, TileCache::new(tiling))),
+                positions: None,
+                map_states: HashMap::default(),
+                map_cache: HashMap::default(),
+            };
+        }
         let map_reader = MapReader::builder()
             .file_path(file_path)
             .streaming_step(self.streaming_step)
@@ -142,7 +333,9 @@ impl MapperBuilder {
             .build();
 
         Mapper {
-            reader: map_reader,
+            reader: Some(map_reader),
+            tiles: None,
+            positions: None,
             map_states: HashMap::default(),
             map_cache: HashMap::default(),
         }
@@ -169,4 +362,50 @@ mod tests {
         assert_eq!(map_state.velocity, Some(Velocity::from(4.0)));
         assert_eq!(map_state.road_id, Some(RoadId::from(5u32)));
     }
+
+    #[test]
+    fn space_neighbours() {
+        let mut space = Space::builder()
+            .width(1000.0)
+            .height(1000.0)
+            .cell_size(100.0)
+            .build();
+        let point = |x: f64, y: f64| Point2D::builder().x(x).y(y).build();
+        space.add_agent(AgentId::from(1), &point(500.0, 500.0));
+        space.add_agent(AgentId::from(2), &point(640.0, 500.0));
+        space.add_agent(AgentId::from(3), &point(800.0, 500.0));
+        let neighbours = space.neighbours_of(AgentId::from(1), &point(500.0, 500.0), 150.0);
+        assert_eq!(neighbours.len(), 1);
+        assert_eq!(neighbours[0].0, AgentId::from(2));
+
+        space.add_agent(AgentId::from(3), &point(550.0, 450.0));
+        space.remove_agent(AgentId::from(2));
+        let neighbours = space.neighbours_of(AgentId::from(1), &point(500.0, 500.0), 150.0);
+        assert_eq!(neighbours.len(), 1);
+        assert_eq!(neighbours[0].0, AgentId::from(3));
+    }
+
+    #[test]
+    fn space_displacement() {
+        let mut space = Space::builder()
+            .width(1000.0)
+            .height(1000.0)
+            .cell_size(100.0)
+            .build();
+        let point = |x: f64, y: f64| Point2D::builder().x(x).y(y).build();
+        let (displacement, earlier) = space.displacement_since(&HashMap::default());
+        assert_eq!(displacement, None);
+        assert!(earlier.is_empty());
+
+        space.add_agent(AgentId::from(1), &point(100.0, 100.0));
+        space.add_agent(AgentId::from(2), &point(200.0, 100.0));
+        let (displacement, earlier) = space.displacement_since(&HashMap::default());
+        assert_eq!(displacement, None);
+
+        space.add_agent(AgentId::from(1), &point(130.0, 140.0));
+        space.add_agent(AgentId::from(2), &point(200.0, 100.0));
+        space.add_agent(AgentId::from(3), &point(900.0, 900.0));
+        let (displacement, _) = space.displacement_since(&earlier);
+        assert_eq!(displacement, Some(25.0));
+    }
 }
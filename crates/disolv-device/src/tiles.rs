@@ -0,0 +1,238 @@
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_input::mobility::TraceMap;
+use disolv_input::tiles::{TileId, TileReader};
+use disolv_models::device::mobility::Point2D;
+use log::debug;
+use serde::Deserialize;
+
+/// Settings to read the positions of an agent type from a tiled trace. The trace file of the
+/// mobility settings is then the directory of the tiles. Only the tiles that overlap the
+/// bounding box of the active agents, extended by `margin` on every side, are read in every
+/// streaming step. At most `cache_size` tiles are kept in memory, the least recently used tiles
+/// are evicted first.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileSettings {
+    pub tile_size: f64,
+    pub margin: Option<f64>,
+    pub cache_size: Option<usize>,
+}
+
+/// Running totals of the tile cache. Resident tiles and rows are the ones in the cache now.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TileStats {
+    pub loads: u64,
+    pub hits: u64,
+    pub evictions: u64,
+    pub resident_tiles: u64,
+    pub resident_rows: u64,
+}
+
+#[derive(Clone, Debug)]
+struct CachedTile {
+    trace: TraceMap,
+    rows: u64,
+    last_used: u64,
+}
+
+/// Least recently used cache of the tiles of a trace. Tiles of a streamed trace are cached per
+/// streaming interval, tiles of other traces hold all the positions of the tile.
+#[derive(Clone, Debug)]
+pub struct TileCache {
+    capacity: usize,
+    margin: f64,
+    focus: Option<(Point2D, Point2D)>,
+    tiles: HashMap<(TileId, TimeMS), CachedTile>,
+    clock: u64,
+    stats: TileStats,
+}
+
+impl TileCache {
+    pub fn new(settings: &TileSettings) -> Self {
+        if settings.tile_size <= 0.0 {
+            panic!("Tile size must be positive.");
+        }
+        Self {
+            capacity: settings.cache_size.unwrap_or(usize::MAX),
+            margin: settings.margin.unwrap_or_default(),
+            focus: None,
+            tiles: HashMap::new(),
+            clock: 0,
+            stats: TileStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> TileStats {
+        self.stats
+    }
+
+    /// Sets the bounding box of the active agents. All the tiles are read while there is no
+    /// active agent.
+    pub fn focus_on<'a>(&mut self, positions: impl Iterator<Item = &'a Point2D>) {
+        let mut focus: Option<(Point2D, Point2D)> = None;
+        for position in positions {
+            let (lower, upper) = focus.get_or_insert((*position, *position));
+            lower.x = lower.x.min(position.x);
+            lower.y = lower.y.min(position.y);
+            upper.x = upper.x.max(position.x);
+            upper.y = upper.y.max(position.y);
+        }
+        self.focus = focus.map(|(lower, upper)| {
+            (
+                Point2D::builder()
+                    .x(lower.x - self.margin)
+                    .y(lower.y - self.margin)
+                    .build(),
+                Point2D::builder()
+                    .x(upper.x + self.margin)
+                    .y(upper.y + self.margin)
+                    .build(),
+            )
+        });
+    }
+
+    /// Positions in the tiles overlapping the focus for the streaming interval of the step.
+    pub fn fetch(&mut self, reader: &TileReader, step: TimeMS) -> TraceMap {
+        let wanted = match self.focus {
+            Some((ref lower, ref upper)) => reader.tiles_within(lower, upper),
+            None => reader.tiles().to_vec(),
+        };
+        let interval = match reader.is_streaming {
+            true => step,
+            false => TimeMS::default(),
+        };
+        self.fetch_tiles(&wanted, interval, |tile| reader.fetch_tile(tile, step))
+    }
+
+    fn fetch_tiles<F>(&mut self, wanted: &[TileId], interval: TimeMS, mut load: F) -> TraceMap
+    where
+        F: FnMut(TileId) -> TraceMap,
+    {
+        self.clock += 1;
+        let mut trace_map: TraceMap = HashMap::new();
+        for tile in wanted.iter() {
+            let cached = match self.tiles.get_mut(&(*tile, interval)) {
+                Some(cached) => {
+                    self.stats.hits += 1;
+                    cached
+                }
+                None => {
+                    self.stats.loads += 1;
+                    let trace = load(*tile);
+                    let rows = trace.values().map(|states| states.len() as u64).sum();
+                    self.tiles.entry((*tile, interval)).or_insert(CachedTile {
+                        trace,
+                        rows,
+                        last_used: 0,
+                    })
+                }
+            };
+            cached.last_used = self.clock;
+            for (time_step, states) in cached.trace.iter() {
+                trace_map
+                    .entry(*time_step)
+                    .or_default()
+                    .extend(states.iter().map(|(agent_id, state)| (*agent_id, *state)));
+            }
+        }
+        self.evict();
+        trace_map
+    }
+
+    /// Evicts the least recently used tiles until the cache fits. Tiles used in this step are
+    /// kept even when they do not fit.
+    fn evict(&mut self) {
+        while self.tiles.len() > self.capacity {
+            let coldest = self
+                .tiles
+                .iter()
+                .filter(|(_, cached)| cached.last_used < self.clock)
+                .min_by_key(|(key, cached)| (cached.last_used, **key))
+                .map(|(key, _)| *key);
+            match coldest {
+                Some(key) => {
+                    debug!("Evicting tile ({}, {})", key.0.x, key.0.y);
+                    self.tiles.remove(&key);
+                    self.stats.evictions += 1;
+                }
+                None => break,
+            }
+        }
+        self.stats.resident_tiles = self.tiles.len() as u64;
+        self.stats.resident_rows = self.tiles.values().map(|cached| cached.rows).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use disolv_core::agent::AgentId;
+    use disolv_models::device::mobility::MapState;
+
+    fn cache(cache_size: usize) -> TileCache {
+        TileCache::new(&TileSettings {
+            tile_size: 100.0,
+            margin: Some(10.0),
+            cache_size: Some(cache_size),
+        })
+    }
+
+    fn tile(x: i64) -> TileId {
+        TileId { x, y: 0 }
+    }
+
+    /// Trace of a tile with a single agent named after the tile.
+    fn load(tile: TileId) -> TraceMap {
+        let state = MapState::builder()
+            .pos(Point2D::builder().x(tile.x as f64 * 100.0).y(0.0).build())
+            .build();
+        let mut trace = TraceMap::new();
+        trace
+            .entry(TimeMS::default())
+            .or_default()
+            .insert(AgentId::from(tile.x as u64), state);
+        trace
+    }
+
+    #[test]
+    fn test_focus_with_margin() {
+        let mut cache = cache(4);
+        let positions = [
+            Point2D::builder().x(50.0).y(20.0).build(),
+            Point2D::builder().x(150.0).y(5.0).build(),
+        ];
+        cache.focus_on(positions.iter());
+        let (lower, upper) = cache.focus.expect("Focus must be set");
+        assert_eq!((lower.x, lower.y), (40.0, -5.0));
+        assert_eq!((upper.x, upper.y), (160.0, 30.0));
+        cache.focus_on(std::iter::empty());
+        assert!(cache.focus.is_none());
+    }
+
+    #[test]
+    fn test_hits_and_merged_trace() {
+        let mut cache = cache(4);
+        let trace = cache.fetch_tiles(&[tile(0), tile(1)], TimeMS::default(), load);
+        assert_eq!(trace[&TimeMS::default()].len(), 2);
+        cache.fetch_tiles(&[tile(1)], TimeMS::default(), load);
+        let stats = cache.stats();
+        assert_eq!((stats.loads, stats.hits), (2, 1));
+        assert_eq!((stats.resident_tiles, stats.resident_rows), (2, 2));
+    }
+
+    #[test]
+    fn test_least_recently_used_eviction() {
+        let mut cache = cache(2);
+        cache.fetch_tiles(&[tile(0)], TimeMS::default(), load);
+        cache.fetch_tiles(&[tile(1)], TimeMS::default(), load);
+        cache.fetch_tiles(&[tile(0)], TimeMS::default(), load);
+        cache.fetch_tiles(&[tile(2)], TimeMS::default(), load);
+        assert!(cache.tiles.contains_key(&(tile(0), TimeMS::default())));
+        assert!(!cache.tiles.contains_key(&(tile(1), TimeMS::default())));
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Tiles in use are kept even if they do not fit.
+        cache.fetch_tiles(&[tile(3), tile(4), tile(5)], TimeMS::default(), load);
+        assert_eq!(cache.stats().resident_tiles, 3);
+    }
+}
@@ -0,0 +1,268 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashSet;
+use disolv_models::bucket::lake::DataLake;
+use disolv_models::net::message::{DPayload, TxMetrics, TxStatus};
+use log::error;
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Settings of the validation mode, in which the invariants of the simulation are checked in
+/// every step. Violations are logged and counted, and stop the simulation when
+/// `fail_on_violation` is set.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ValidationSettings {
+    pub fail_on_violation: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    UnknownSender,
+    UnknownTarget,
+    DeliveryMismatch,
+    LakeMismatch,
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Invariant::UnknownSender => write!(f, "payload sent by an unknown agent"),
+            Invariant::UnknownTarget => write!(f, "payload addressed to an unknown agent"),
+            Invariant::DeliveryMismatch => write!(f, "deliveries differ from transfers"),
+            Invariant::LakeMismatch => write!(f, "data lake differs from the flows"),
+        }
+    }
+}
+
+/// Counts of the payloads in a step that the invariants are checked against.
+#[derive(Clone, Copy, Debug, Default)]
+struct StepCounts {
+    carried: usize,
+    taken_before: u64,
+    transferred: u64,
+    transferred_bytes: u64,
+    delivered: u64,
+    delivered_bytes: u64,
+    stored: usize,
+}
+
+/// Checks the invariants of the bucket in every step:
+/// * payloads are only sent by and addressed to the agents of the simulation,
+/// * every successful transfer delivers its payload exactly once,
+/// * the data lake holds the payloads carried over, plus those delivered to it, minus those
+///   received from it.
+#[derive(Clone, Debug)]
+pub struct Validator {
+    known_agents: HashSet<AgentId>,
+    fail_on_violation: bool,
+    step: TimeMS,
+    counts: StepCounts,
+    violations: u64,
+}
+
+impl Validator {
+    pub fn new(settings: &ValidationSettings, known_agents: HashSet<AgentId>) -> Self {
+        Self {
+            known_agents,
+            fail_on_violation: settings.fail_on_violation.unwrap_or(false),
+            step: TimeMS::default(),
+            counts: StepCounts::default(),
+            violations: 0,
+        }
+    }
+
+    pub fn violations(&self) -> u64 {
+        self.violations
+    }
+
+    /// Starts the checks of a step once the lake is cleaned and the payloads from the other
+    /// regions are added.
+    pub fn begin_step(&mut self, step: TimeMS, data_lake: &DataLake) {
+        self.step = step;
+        self.counts = StepCounts {
+            carried: data_lake.stored(),
+            taken_before: data_lake.taken(),
+            ..Default::default()
+        };
+    }
+
+    pub fn record_transfer(&mut self, payload: &DPayload, tx_metrics: &TxMetrics) {
+        if tx_metrics.tx_status == TxStatus::Ok {
+            self.counts.transferred += 1;
+            self.counts.transferred_bytes += payload.metadata.total_size.as_u64();
+        }
+    }
+
+    /// Records a payload handed to the target, either in the data lake or in another region.
+    pub fn record_delivery(&mut self, target: AgentId, payload: &DPayload, to_lake: bool) {
+        let sender = payload.agent_state.device_info.id;
+        if !self.known_agents.contains(&sender) {
+            self.report(
+                Invariant::UnknownSender,
+                format!("agent {} sent payload {}", sender, payload.metadata.id),
+            );
+        }
+        if !self.known_agents.contains(&target) {
+            self.report(
+                Invariant::UnknownTarget,
+                format!(
+                    "agent {} sent payload {} to agent {}",
+                    sender, payload.metadata.id, target
+                ),
+            );
+        }
+        self.counts.delivered += 1;
+        self.counts.delivered_bytes += payload.metadata.total_size.as_u64();
+        if to_lake {
+            self.counts.stored += 1;
+        }
+    }
+
+    /// Checks the counts of the step once all the agents are done.
+    pub fn end_step(&mut self, data_lake: &DataLake) {
+        let counts = self.counts;
+        if counts.transferred != counts.delivered
+            || counts.transferred_bytes != counts.delivered_bytes
+        {
+            self.report(
+                Invariant::DeliveryMismatch,
+                format!(
+                    "{} payloads of {} bytes transferred, {} payloads of {} bytes delivered",
+                    counts.transferred,
+                    counts.transferred_bytes,
+                    counts.delivered,
+                    counts.delivered_bytes
+                ),
+            );
+        }
+        let taken = (data_lake.taken() - counts.taken_before) as usize;
+        let expected = (counts.carried + counts.stored).checked_sub(taken);
+        if expected != Some(data_lake.stored()) {
+            self.report(
+                Invariant::LakeMismatch,
+                format!(
+                    "{} payloads carried over, {} stored and {} received, but {} in the lake",
+                    counts.carried,
+                    counts.stored,
+                    taken,
+                    data_lake.stored()
+                ),
+            );
+        }
+        let unknown: Vec<AgentId> = data_lake
+            .targets()
+            .filter(|target| !self.known_agents.contains(*target))
+            .copied()
+            .collect();
+        for target in unknown.into_iter() {
+            self.report(
+                Invariant::UnknownTarget,
+                format!("payloads for agent {} are in the lake", target),
+            );
+        }
+    }
+
+    fn report(&mut self, invariant: Invariant, context: String) {
+        self.violations += 1;
+        error!(
+            "Invariant violated at step {}: {} ({})",
+            self.step, invariant, context
+        );
+        if self.fail_on_violation {
+            panic!(
+                "Invariant violated at step {}: {} ({})",
+                self.step, invariant, context
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use disolv_models::net::message::{DeviceContent, PayloadInfo};
+    use disolv_models::net::metrics::Bytes;
+
+    fn payload_from(sender: u64) -> DPayload {
+        let mut content = DeviceContent::default();
+        content.device_info.id = AgentId::from(sender);
+        DPayload::builder()
+            .agent_state(content)
+            .metadata(
+                PayloadInfo::builder()
+                    .id(disolv_core::uuid::Uuid::new_v4())
+                    .total_size(Bytes::new(100))
+                    .total_count(1)
+                    .data_blobs(Vec::new())
+                    .selected_link(Default::default())
+                    .build(),
+            )
+            .gathered_states(None)
+            .build()
+    }
+
+    fn validator() -> Validator {
+        let known = [1u64, 2].into_iter().map(AgentId::from).collect();
+        Validator::new(
+            &ValidationSettings {
+                fail_on_violation: None,
+            },
+            known,
+        )
+    }
+
+    fn delivered(validator: &mut Validator, lake: &mut DataLake, target: u64) {
+        let payload = payload_from(1);
+        let tx_metrics = TxMetrics {
+            tx_status: TxStatus::Ok,
+            ..Default::default()
+        };
+        validator.record_transfer(&payload, &tx_metrics);
+        validator.record_delivery(AgentId::from(target), &payload, true);
+        lake.add_payload_to(AgentId::from(target), payload);
+    }
+
+    #[test]
+    fn test_consistent_step() {
+        let mut validator = validator();
+        let mut lake = DataLake::default();
+        validator.begin_step(TimeMS::from(100), &lake);
+        delivered(&mut validator, &mut lake, 2);
+        delivered(&mut validator, &mut lake, 2);
+        lake.payloads_for(AgentId::from(2));
+        validator.end_step(&lake);
+        assert_eq!(validator.violations(), 0);
+    }
+
+    #[test]
+    fn test_violations_are_counted() {
+        let mut validator = validator();
+        let mut lake = DataLake::default();
+        validator.begin_step(TimeMS::from(100), &lake);
+        delivered(&mut validator, &mut lake, 7);
+        // A payload added to the lake without a transfer.
+        lake.add_payload_to(AgentId::from(2), payload_from(1));
+        validator.end_step(&lake);
+        // The unknown target is reported on delivery and in the lake.
+        assert_eq!(validator.violations(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "deliveries differ from transfers")]
+    fn test_fail_on_violation() {
+        let mut validator = Validator::new(
+            &ValidationSettings {
+                fail_on_violation: Some(true),
+            },
+            HashSet::new(),
+        );
+        let lake = DataLake::default();
+        validator.begin_step(TimeMS::from(100), &lake);
+        let tx_metrics = TxMetrics {
+            tx_status: TxStatus::Ok,
+            ..Default::default()
+        };
+        validator.record_transfer(&payload_from(1), &tx_metrics);
+        validator.end_step(&lake);
+    }
+}
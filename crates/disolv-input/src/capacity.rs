@@ -0,0 +1,81 @@
+use crate::batch::{read_u32_column, read_u64_column};
+use crate::columns::{CAPACITY, SLICE_ID, TIME_STEP};
+use crate::power::get_batch_reader;
+use arrow_array::RecordBatch;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_models::net::metrics::Bandwidth;
+use log::error;
+use std::path::{Path, PathBuf};
+
+pub type CapacityEntries = HashMap<u32, Vec<(TimeMS, Bandwidth)>>;
+
+/// Reads the capacities of the slices from a parquet file, or from a CSV file with the same
+/// `time_step`, `slice_id` and `capacity` columns.
+pub fn read_capacity_trace(trace_file: &Path) -> CapacityEntries {
+    match trace_file
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("csv") => read_csv_trace(trace_file),
+        _ => read_parquet_trace(&trace_file.to_path_buf()),
+    }
+}
+
+fn read_parquet_trace(trace_file: &PathBuf) -> CapacityEntries {
+    let mut entries: CapacityEntries = HashMap::new();
+    let reader = get_batch_reader(trace_file);
+    for record_batch in reader {
+        let record_batch: RecordBatch = match record_batch {
+            Ok(batch) => batch,
+            Err(e) => panic!("Error reading record batch: {}", e),
+        };
+        let time_steps = read_u64_column(TIME_STEP, &record_batch);
+        let slice_ids = read_u32_column(SLICE_ID, &record_batch);
+        let capacities = read_u64_column(CAPACITY, &record_batch);
+        for (idx, slice_id) in slice_ids.into_iter().enumerate() {
+            entries.entry(slice_id).or_default().push((
+                TimeMS::from(time_steps[idx]),
+                Bandwidth::new(capacities[idx]),
+            ));
+        }
+    }
+    entries
+}
+
+fn read_csv_trace(trace_file: &Path) -> CapacityEntries {
+    let content = match std::fs::read_to_string(trace_file) {
+        Ok(content) => content,
+        Err(e) => panic!("Error reading file from disk: {}", e),
+    };
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = match lines.next() {
+        Some(header) => header.split(',').map(|column| column.trim()).collect(),
+        None => return HashMap::new(),
+    };
+    let position = |column: &str| match header.iter().position(|name| *name == column) {
+        Some(idx) => idx,
+        None => panic!("Failed to read column {}", column),
+    };
+    let (time_idx, slice_idx, capacity_idx) =
+        (position(TIME_STEP), position(SLICE_ID), position(CAPACITY));
+
+    let mut entries: CapacityEntries = HashMap::new();
+    for line in lines {
+        let values: Vec<&str> = line.split(',').map(|value| value.trim()).collect();
+        let value = |idx: usize| -> u64 {
+            match values.get(idx).and_then(|value| value.parse().ok()) {
+                Some(value) => value,
+                None => {
+                    error!("Capacity trace lines must be unsigned integers");
+                    panic!("Invalid capacity trace line {}.", line);
+                }
+            }
+        };
+        entries.entry(value(slice_idx) as u32).or_default().push((
+            TimeMS::from(value(time_idx)),
+            Bandwidth::new(value(capacity_idx)),
+        ));
+    }
+    entries
+}
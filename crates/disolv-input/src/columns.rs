@@ -3,6 +3,11 @@ pub const TARGET_ID: &str = "target_id";
 
 pub const DISTANCE: &str = "distance";
 pub const LOAD_FACTOR: &str = "load_factor";
+pub const UPLINK_CAPACITY: &str = "uplink_capacity";
+pub const DOWNLINK_CAPACITY: &str = "downlink_capacity";
+pub const UPLINK_LOSS: &str = "uplink_loss";
+pub const DOWNLINK_LOSS: &str = "downlink_loss";
+pub const OBSTRUCTION: &str = "obstruction";
 pub const VELOCITY: &str = "velocity";
 pub const ROAD_ID: &str = "road_id";
 
@@ -13,3 +18,7 @@ pub const TIME_STEP: &str = "time_step";
 pub const COORD_X: &str = "x";
 pub const COORD_Y: &str = "y";
 pub const COORD_Z: &str = "z";
+
+pub const SLICE_ID: &str = "slice_id";
+pub const CAPACITY: &str = "capacity";
+pub const UTILIZATION: &str = "utilization";
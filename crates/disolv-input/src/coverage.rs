@@ -0,0 +1,27 @@
+use log::error;
+use std::path::Path;
+
+/// Reads a coverage grid with a line per row of cells and a comma separated value per cell,
+/// 1 for the covered cells and 0 for the gaps. Empty lines are skipped.
+pub fn read_coverage_grid(coverage_file: &Path) -> Vec<Vec<bool>> {
+    let content = match std::fs::read_to_string(coverage_file) {
+        Ok(content) => content,
+        Err(e) => panic!("Error reading file from disk: {}", e),
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|value| match value.trim() {
+                    "1" => true,
+                    "0" => false,
+                    _ => {
+                        error!("Coverage grid cells must be 0 or 1");
+                        panic!("Invalid coverage grid line {}.", line);
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
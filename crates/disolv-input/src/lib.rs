@@ -1,6 +1,10 @@
 #![forbid(unsafe_code)]
 pub mod batch;
+pub mod capacity;
 pub mod columns;
+pub mod coverage;
 pub mod links;
 pub mod mobility;
 pub mod power;
+pub mod tiles;
+pub mod utilization;
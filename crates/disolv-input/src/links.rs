@@ -1,8 +1,10 @@
 use crate::batch::{get_row_groups_for_time, read_f64_column, read_u64_column};
-use crate::columns::{AGENT_ID, DISTANCE, LOAD_FACTOR, TARGET_ID, TIME_STEP};
+use crate::columns::{AGENT_ID, DISTANCE, LOAD_FACTOR, OBSTRUCTION, TARGET_ID, TIME_STEP};
+use crate::columns::{DOWNLINK_CAPACITY, DOWNLINK_LOSS, UPLINK_CAPACITY, UPLINK_LOSS};
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::TimeMS;
 use disolv_core::hashbrown::HashMap;
+use disolv_models::net::metrics::Bandwidth;
 use disolv_models::net::radio::DLink;
 use log::debug;
 use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
@@ -20,6 +22,11 @@ pub struct LinkReader {
 }
 
 impl LinkReader {
+    /// Changes the length of the interval read in every streaming step.
+    pub fn set_streaming_step(&mut self, streaming_step: TimeMS) {
+        self.streaming_step = streaming_step;
+    }
+
     pub fn fetch_links_data(&self, step: TimeMS) -> LinkMap {
         let mut link_map: LinkMap = HashMap::new();
         let reader = self.get_batch_reader(step);
@@ -59,6 +66,39 @@ impl LinkReader {
                 }
             }
 
+            if record_batch.column_by_name(OBSTRUCTION).is_some() {
+                let obstruction = read_f64_column(OBSTRUCTION, &record_batch);
+                for (idx, link) in link_vec.iter_mut().enumerate() {
+                    link.properties.obstruction = Some(obstruction[idx] as f32);
+                }
+            }
+
+            // Per-direction properties are optional and only present for asymmetric links.
+            if record_batch.column_by_name(UPLINK_CAPACITY).is_some() {
+                let capacity = read_f64_column(UPLINK_CAPACITY, &record_batch);
+                for (idx, link) in link_vec.iter_mut().enumerate() {
+                    link.properties.uplink.capacity = Some(Bandwidth::new(capacity[idx] as u64));
+                }
+            }
+            if record_batch.column_by_name(DOWNLINK_CAPACITY).is_some() {
+                let capacity = read_f64_column(DOWNLINK_CAPACITY, &record_batch);
+                for (idx, link) in link_vec.iter_mut().enumerate() {
+                    link.properties.downlink.capacity = Some(Bandwidth::new(capacity[idx] as u64));
+                }
+            }
+            if record_batch.column_by_name(UPLINK_LOSS).is_some() {
+                let loss = read_f64_column(UPLINK_LOSS, &record_batch);
+                for (idx, link) in link_vec.iter_mut().enumerate() {
+                    link.properties.uplink.loss = Some(loss[idx] as f32);
+                }
+            }
+            if record_batch.column_by_name(DOWNLINK_LOSS).is_some() {
+                let loss = read_f64_column(DOWNLINK_LOSS, &record_batch);
+                for (idx, link) in link_vec.iter_mut().enumerate() {
+                    link.properties.downlink.loss = Some(loss[idx] as f32);
+                }
+            }
+
             for ((time, agent_id), link) in time_steps
                 .into_iter()
                 .zip(agent_ids.into_iter())
@@ -23,6 +23,11 @@ pub struct MapReader {
 }
 
 impl MapReader {
+    /// Changes the length of the interval read in every streaming step.
+    pub fn set_streaming_step(&mut self, streaming_step: TimeMS) {
+        self.streaming_step = streaming_step;
+    }
+
     pub fn fetch_traffic_data(&self, step: TimeMS) -> TraceMap {
         let mut trace_map: TraceMap = HashMap::new();
         let reader = self.get_batch_reader(step);
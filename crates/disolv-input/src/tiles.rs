@@ -0,0 +1,152 @@
+use crate::mobility::{MapReader, TraceMap};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use log::{debug, warn};
+use std::path::PathBuf;
+use typed_builder::TypedBuilder;
+
+/// Square tile of the map. Tile (i, j) covers the positions from `i * tile_size` to
+/// `(i + 1) * tile_size` along x and likewise along y.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct TileId {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl TileId {
+    pub fn of(position: &Point2D, tile_size: f64) -> Self {
+        Self {
+            x: (position.x / tile_size).floor() as i64,
+            y: (position.y / tile_size).floor() as i64,
+        }
+    }
+
+    /// Name of the trace file of the tile in the tile directory.
+    pub fn file_name(&self) -> String {
+        format!("tile_{}_{}.parquet", self.x, self.y)
+    }
+
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let coords = file_name.strip_prefix("tile_")?.strip_suffix(".parquet")?;
+        let (x, y) = coords.rsplit_once('_')?;
+        Some(Self {
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+        })
+    }
+}
+
+/// Reads the positions of a tiled trace. The trace is split into a directory of trace files, one
+/// per tile, named after the tile. Tiles without a file have no positions.
+#[derive(Clone, Debug, TypedBuilder)]
+pub struct TileReader {
+    pub is_streaming: bool,
+    pub tile_size: f64,
+    directory: PathBuf,
+    streaming_step: TimeMS,
+    #[builder(default, setter(skip))]
+    available: Vec<TileId>,
+}
+
+impl TileReader {
+    /// Lists the tiles in the tile directory.
+    pub fn scan(mut self) -> Self {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) => panic!(
+                "Error reading tile directory {}: {}",
+                self.directory.display(),
+                e
+            ),
+        };
+        self.available = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| TileId::from_file_name(&entry.file_name().to_string_lossy()))
+            .collect();
+        self.available.sort();
+        if self.available.is_empty() {
+            warn!("No tiles found in {}", self.directory.display());
+        }
+        self
+    }
+
+    /// Changes the length of the interval read in every streaming step.
+    pub fn set_streaming_step(&mut self, streaming_step: TimeMS) {
+        self.streaming_step = streaming_step;
+    }
+
+    pub fn tiles(&self) -> &[TileId] {
+        &self.available
+    }
+
+    /// Tiles with a file that overlap the area between the corners.
+    pub fn tiles_within(&self, lower: &Point2D, upper: &Point2D) -> Vec<TileId> {
+        let first = TileId::of(lower, self.tile_size);
+        let last = TileId::of(upper, self.tile_size);
+        self.available
+            .iter()
+            .filter(|tile| tile.x >= first.x && tile.x <= last.x)
+            .filter(|tile| tile.y >= first.y && tile.y <= last.y)
+            .copied()
+            .collect()
+    }
+
+    /// Positions in the tile from the given step until the next streaming step, or all the
+    /// positions when the trace is not streamed.
+    pub fn fetch_tile(&self, tile: TileId, step: TimeMS) -> TraceMap {
+        debug!("Reading tile ({}, {}) for step {}", tile.x, tile.y, step);
+        MapReader::builder()
+            .file_path(self.directory.join(tile.file_name()))
+            .streaming_step(self.streaming_step)
+            .is_streaming(self.is_streaming)
+            .build()
+            .fetch_traffic_data(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(available: Vec<TileId>) -> TileReader {
+        let mut reader = TileReader::builder()
+            .is_streaming(false)
+            .tile_size(100.0)
+            .directory(PathBuf::from("tiles"))
+            .streaming_step(TimeMS::from(1000))
+            .build();
+        reader.available = available;
+        reader
+    }
+
+    #[test]
+    fn test_tile_of_position() {
+        let inside = Point2D::builder().x(250.0).y(99.9).build();
+        assert_eq!(TileId::of(&inside, 100.0), TileId { x: 2, y: 0 });
+        let negative = Point2D::builder().x(-0.5).y(100.0).build();
+        assert_eq!(TileId::of(&negative, 100.0), TileId { x: -1, y: 1 });
+    }
+
+    #[test]
+    fn test_tile_file_names() {
+        let tile = TileId { x: -3, y: 12 };
+        assert_eq!(tile.file_name(), "tile_-3_12.parquet");
+        assert_eq!(TileId::from_file_name(&tile.file_name()), Some(tile));
+        assert_eq!(TileId::from_file_name("trace.parquet"), None);
+    }
+
+    #[test]
+    fn test_tiles_within() {
+        let reader = reader(vec![
+            TileId { x: 0, y: 0 },
+            TileId { x: 1, y: 0 },
+            TileId { x: 5, y: 5 },
+        ]);
+        let lower = Point2D::builder().x(50.0).y(0.0).build();
+        let upper = Point2D::builder().x(150.0).y(80.0).build();
+        assert_eq!(
+            reader.tiles_within(&lower, &upper),
+            vec![TileId { x: 0, y: 0 }, TileId { x: 1, y: 0 }]
+        );
+    }
+}
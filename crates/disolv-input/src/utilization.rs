@@ -0,0 +1,92 @@
+use crate::batch::{read_f64_column, read_u64_column};
+use crate::columns::{AGENT_ID, TIME_STEP, UTILIZATION};
+use crate::power::get_batch_reader;
+use arrow_array::RecordBatch;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use log::error;
+use std::path::{Path, PathBuf};
+
+pub type UtilizationEntries = HashMap<AgentId, Vec<(TimeMS, f64)>>;
+
+/// Reads the busy fractions of the processors of the agents from a parquet file, or from a CSV
+/// file with the same `time_step`, `agent_id` and `utilization` columns.
+pub fn read_utilization_trace(trace_file: &Path) -> UtilizationEntries {
+    match trace_file
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("csv") => read_csv_trace(trace_file),
+        _ => read_parquet_trace(&trace_file.to_path_buf()),
+    }
+}
+
+fn read_parquet_trace(trace_file: &PathBuf) -> UtilizationEntries {
+    let mut entries: UtilizationEntries = HashMap::new();
+    let reader = get_batch_reader(trace_file);
+    for record_batch in reader {
+        let record_batch: RecordBatch = match record_batch {
+            Ok(batch) => batch,
+            Err(e) => panic!("Error reading record batch: {}", e),
+        };
+        let time_steps = read_u64_column(TIME_STEP, &record_batch);
+        let agent_ids = read_u64_column(AGENT_ID, &record_batch);
+        let utilizations = read_f64_column(UTILIZATION, &record_batch);
+        for (idx, agent_id) in agent_ids.into_iter().enumerate() {
+            entries
+                .entry(AgentId::from(agent_id))
+                .or_default()
+                .push((TimeMS::from(time_steps[idx]), utilizations[idx]));
+        }
+    }
+    entries
+}
+
+fn read_csv_trace(trace_file: &Path) -> UtilizationEntries {
+    let content = match std::fs::read_to_string(trace_file) {
+        Ok(content) => content,
+        Err(e) => panic!("Error reading file from disk: {}", e),
+    };
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = match lines.next() {
+        Some(header) => header.split(',').map(|column| column.trim()).collect(),
+        None => return HashMap::new(),
+    };
+    let position = |column: &str| match header.iter().position(|name| *name == column) {
+        Some(idx) => idx,
+        None => panic!("Failed to read column {}", column),
+    };
+    let (time_idx, agent_idx, utilization_idx) = (
+        position(TIME_STEP),
+        position(AGENT_ID),
+        position(UTILIZATION),
+    );
+
+    let mut entries: UtilizationEntries = HashMap::new();
+    for line in lines {
+        let values: Vec<&str> = line.split(',').map(|value| value.trim()).collect();
+        let parsed = (
+            values
+                .get(time_idx)
+                .and_then(|value| value.parse::<u64>().ok()),
+            values
+                .get(agent_idx)
+                .and_then(|value| value.parse::<u64>().ok()),
+            values
+                .get(utilization_idx)
+                .and_then(|value| value.parse::<f64>().ok()),
+        );
+        match parsed {
+            (Some(time_step), Some(agent_id), Some(utilization)) => entries
+                .entry(AgentId::from(agent_id))
+                .or_default()
+                .push((TimeMS::from(time_step), utilization)),
+            _ => {
+                error!("Utilization trace lines must have a time step, an agent and a fraction");
+                panic!("Invalid utilization trace line {}.", line);
+            }
+        }
+    }
+    entries
+}
@@ -3,16 +3,18 @@ use crate::linker::{LinkType, LinkerImpl};
 use crate::logger;
 use crate::reader::{ConstantReader, MobileReader, Reader, TraceType};
 use disolv_core::bucket::TimeMS;
+use disolv_core::heatmap::{HeatmapData, HeatmapKind};
 use disolv_core::ui::LinkUIMetadata;
 use disolv_models::device::types::DeviceType;
+use disolv_models::net::attenuation::Obstacles;
 use hashbrown::HashMap;
 use log::debug;
 use std::path::PathBuf;
 
-pub(crate) struct LinkBuilder {
-    pub(crate) step_size: TimeMS,
-    pub(crate) start: TimeMS,
-    pub(crate) end: TimeMS,
+pub struct LinkBuilder {
+    pub step_size: TimeMS,
+    pub start: TimeMS,
+    pub end: TimeMS,
     config_path: PathBuf,
     config: Config,
     readers: HashMap<DeviceType, Reader>,
@@ -20,7 +22,7 @@ pub(crate) struct LinkBuilder {
 }
 
 impl LinkBuilder {
-    pub(crate) fn new(config: Config, config_path: PathBuf) -> Self {
+    pub fn new(config: Config, config_path: PathBuf) -> Self {
         Self {
             start: config.settings.start,
             end: config.settings.end,
@@ -32,7 +34,7 @@ impl LinkBuilder {
         }
     }
 
-    pub(crate) fn build_link_metadata(&self) -> LinkUIMetadata {
+    pub fn build_link_metadata(&self) -> LinkUIMetadata {
         LinkUIMetadata {
             input_file: self
                 .config_path
@@ -43,7 +45,7 @@ impl LinkBuilder {
         }
     }
 
-    pub(crate) fn initialize(&mut self) {
+    pub fn initialize(&mut self) {
         logger::initiate_logger(&self.config_path, &self.config.log_settings);
         for pos_file in self.config.position_files.iter() {
             self.readers.insert(pos_file.device, Reader::new(pos_file));
@@ -80,11 +82,13 @@ impl LinkBuilder {
             }
         }
 
-        // Initialize link finders.
+        // Initialize link finders. Links crossing the obstacles get their obstruction.
+        let obstacles = Obstacles::new(self.config.obstacles.as_deref().unwrap_or_default());
         for linker_setting in self.config.link_settings.iter() {
             self.linkers.push(LinkerImpl::new(
                 self.config.settings.output_path.as_str(),
                 linker_setting,
+                obstacles.clone(),
             ));
         }
 
@@ -94,7 +98,7 @@ impl LinkBuilder {
         });
     }
 
-    pub(crate) fn build_links_at(&mut self, step: TimeMS) {
+    pub fn build_links_at(&mut self, step: TimeMS) {
         self.readers.values_mut().for_each(|reader| {
             reader.update_positions_at(step);
         });
@@ -120,17 +124,49 @@ impl LinkBuilder {
                 None => continue,
             };
 
-            let position_tree = self
+            let target_reader = self
                 .readers
                 .get(&link_setting.target)
-                .expect("missing reader for device type")
-                .get_kd_tree();
+                .expect("missing reader for device type");
+            let target_positions = target_reader
+                .read_positions_at(step)
+                .cloned()
+                .unwrap_or_default();
 
-            writer.write_links(positions, position_tree, step);
+            writer.write_links(
+                positions,
+                &target_positions,
+                target_reader.get_kd_tree(),
+                step,
+            );
         }
     }
 
-    pub(crate) fn complete(self) {
+    /// Heatmap of the density of the agents at the step. The field is the bounding box of the
+    /// positions, as the link configuration does not describe it.
+    pub fn heatmap_at(&self, step: TimeMS) -> HeatmapData {
+        let positions: Vec<&[f64; 3]> = self
+            .readers
+            .values()
+            .filter_map(|reader| reader.read_positions_at(step))
+            .flat_map(|positions| positions.iter().map(|(_, position)| position))
+            .collect();
+        let width = positions
+            .iter()
+            .map(|position| position[0])
+            .fold(0.0, f64::max);
+        let height = positions
+            .iter()
+            .map(|position| position[1])
+            .fold(0.0, f64::max);
+        let mut heatmap = HeatmapData::new(HeatmapKind::Density, step.as_u64(), width, height);
+        positions
+            .iter()
+            .for_each(|position| heatmap.add(position[0], position[1], 1));
+        heatmap
+    }
+
+    pub fn complete(self) {
         self.linkers.into_iter().for_each(|w| w.flush())
     }
 }
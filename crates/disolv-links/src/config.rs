@@ -6,6 +6,7 @@ use crate::linker::{DeviceCount, LinkType, Radius};
 use crate::reader::TraceType;
 use disolv_core::bucket::TimeMS;
 use disolv_models::device::types::DeviceType;
+use disolv_models::net::attenuation::ObstacleSettings;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -14,6 +15,7 @@ pub struct Config {
     pub settings: Settings,
     pub link_settings: Vec<LinkSettings>,
     pub position_files: Vec<PositionFiles>,
+    pub obstacles: Option<Vec<ObstacleSettings>>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -40,6 +42,15 @@ pub struct PositionFiles {
     pub position_file: String,
 }
 
+/// Properties of the links in one direction. Capacity is in bytes per second and loss is the
+/// probability of losing a payload.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DirectionSettings {
+    pub capacity: Option<f64>,
+    pub loss: Option<f64>,
+}
+
 #[serde_with::skip_serializing_none]
 #[derive(Deserialize, Debug, Clone)]
 pub struct LinkSettings {
@@ -50,9 +61,11 @@ pub struct LinkSettings {
     pub link_model: String,
     pub link_type: LinkType,
     pub links_file: String,
+    pub uplink: Option<DirectionSettings>,
+    pub downlink: Option<DirectionSettings>,
 }
 
-pub(crate) fn read_config(file_path: &PathBuf) -> Config {
+pub fn read_config(file_path: &PathBuf) -> Config {
     let input_toml = match std::fs::read_to_string(file_path) {
         Ok(parsed_string) => parsed_string,
         Err(_) => panic!("Failed to read input TOML file"),
@@ -0,0 +1,5 @@
+pub mod builder;
+pub mod config;
+pub mod linker;
+pub mod logger;
+pub mod reader;
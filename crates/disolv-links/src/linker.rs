@@ -3,7 +3,11 @@ use crate::reader::AgentIdPos;
 use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use disolv_core::bucket::TimeMS;
-use disolv_input::columns::{AGENT_ID, DISTANCE, TARGET_ID, TIME_STEP};
+use disolv_input::columns::{AGENT_ID, DISTANCE, OBSTRUCTION, TARGET_ID, TIME_STEP};
+use disolv_input::columns::{DOWNLINK_CAPACITY, DOWNLINK_LOSS, UPLINK_CAPACITY, UPLINK_LOSS};
+use disolv_models::device::mobility::Point2D;
+use disolv_models::net::attenuation::Obstacles;
+use hashbrown::HashMap;
 use kiddo::{KdTree, NearestNeighbour, SquaredEuclidean};
 use log::debug;
 use parquet::arrow::ArrowWriter;
@@ -49,11 +53,19 @@ pub(crate) enum LinkModel {
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
-pub(crate) enum LinkType {
+pub enum LinkType {
     Static,
     Dynamic,
 }
 
+/// A per-direction property written for every link, e.g. the uplink capacity.
+#[derive(Clone)]
+struct LinkAttribute {
+    column: &'static str,
+    value: f64,
+    values: Vec<f64>,
+}
+
 #[derive(Clone, Default)]
 struct WriterCache {
     cache_size: usize,
@@ -61,16 +73,45 @@ struct WriterCache {
     targets: Vec<u64>,
     distances: Vec<f64>,
     times: Vec<u64>,
+    attributes: Vec<LinkAttribute>,
+    obstructions: Option<Vec<f64>>,
 }
 
 impl WriterCache {
-    fn new(cache_size: usize) -> Self {
+    fn new(cache_size: usize, link_settings: &LinkSettings, with_obstruction: bool) -> Self {
+        let mut attributes = Vec::new();
+        let directions = [
+            (link_settings.uplink, UPLINK_CAPACITY, UPLINK_LOSS),
+            (link_settings.downlink, DOWNLINK_CAPACITY, DOWNLINK_LOSS),
+        ];
+        for (direction, capacity_column, loss_column) in directions.into_iter() {
+            let direction = match direction {
+                Some(direction) => direction,
+                None => continue,
+            };
+            if let Some(capacity) = direction.capacity {
+                attributes.push(LinkAttribute {
+                    column: capacity_column,
+                    value: capacity,
+                    values: Vec::with_capacity(cache_size),
+                });
+            }
+            if let Some(loss) = direction.loss {
+                attributes.push(LinkAttribute {
+                    column: loss_column,
+                    value: loss,
+                    values: Vec::with_capacity(cache_size),
+                });
+            }
+        }
         Self {
             sources: Vec::with_capacity(cache_size),
             targets: Vec::with_capacity(cache_size),
             distances: Vec::with_capacity(cache_size),
             times: Vec::with_capacity(cache_size),
             cache_size: cache_size,
+            attributes,
+            obstructions: with_obstruction.then(|| Vec::with_capacity(cache_size)),
         }
     }
 
@@ -78,25 +119,78 @@ impl WriterCache {
         self.sources.len() > self.cache_size
     }
 
+    fn add_link(
+        &mut self,
+        now: TimeMS,
+        source: u64,
+        target: u64,
+        distance: f64,
+        obstruction: Option<f64>,
+    ) {
+        self.times.push(now.as_u64());
+        self.sources.push(source);
+        self.targets.push(target);
+        self.distances.push(distance);
+        for attribute in self.attributes.iter_mut() {
+            attribute.values.push(attribute.value);
+        }
+        if let Some(ref mut obstructions) = self.obstructions {
+            obstructions.push(obstruction.unwrap_or_default());
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        let mut fields = vec![
+            Field::new(TIME_STEP, DataType::UInt64, false),
+            Field::new(AGENT_ID, DataType::UInt64, false),
+            Field::new(TARGET_ID, DataType::UInt64, false),
+            Field::new(DISTANCE, DataType::Float64, false),
+        ];
+        for attribute in self.attributes.iter() {
+            fields.push(Field::new(attribute.column, DataType::Float64, false));
+        }
+        if self.obstructions.is_some() {
+            fields.push(Field::new(OBSTRUCTION, DataType::Float64, false));
+        }
+        Schema::new(fields)
+    }
+
     fn as_record_batch(&mut self) -> RecordBatch {
-        RecordBatch::try_from_iter(vec![
-            (
-                TIME_STEP,
-                Arc::new(UInt64Array::from(std::mem::take(&mut self.times))) as ArrayRef,
-            ),
+        let attributes = self.attributes.iter_mut().map(|attribute| {
             (
-                AGENT_ID,
-                Arc::new(UInt64Array::from(std::mem::take(&mut self.sources))) as ArrayRef,
-            ),
+                attribute.column,
+                Arc::new(Float64Array::from(std::mem::take(&mut attribute.values))) as ArrayRef,
+            )
+        });
+        let obstructions = self.obstructions.as_mut().map(|obstructions| {
             (
-                TARGET_ID,
-                Arc::new(UInt64Array::from(std::mem::take(&mut self.targets))) as ArrayRef,
-            ),
-            (
-                DISTANCE,
-                Arc::new(Float64Array::from(std::mem::take(&mut self.distances))) as ArrayRef,
-            ),
-        ])
+                OBSTRUCTION,
+                Arc::new(Float64Array::from(std::mem::take(obstructions))) as ArrayRef,
+            )
+        });
+        RecordBatch::try_from_iter(
+            vec![
+                (
+                    TIME_STEP,
+                    Arc::new(UInt64Array::from(std::mem::take(&mut self.times))) as ArrayRef,
+                ),
+                (
+                    AGENT_ID,
+                    Arc::new(UInt64Array::from(std::mem::take(&mut self.sources))) as ArrayRef,
+                ),
+                (
+                    TARGET_ID,
+                    Arc::new(UInt64Array::from(std::mem::take(&mut self.targets))) as ArrayRef,
+                ),
+                (
+                    DISTANCE,
+                    Arc::new(Float64Array::from(std::mem::take(&mut self.distances))) as ArrayRef,
+                ),
+            ]
+            .into_iter()
+            .chain(attributes)
+            .chain(obstructions),
+        )
         .expect("Failed to convert writer cache to record batch")
     }
 }
@@ -106,35 +200,36 @@ pub(crate) struct LinkerImpl {
     link_model: LinkModel,
     linker_settings: LinkSettings,
     writer_cache: WriterCache,
+    obstacles: Obstacles,
 }
 
 impl LinkerImpl {
-    pub(crate) fn new(output_path: &str, link_settings: &LinkSettings) -> Self {
+    pub(crate) fn new(
+        output_path: &str,
+        link_settings: &LinkSettings,
+        obstacles: Obstacles,
+    ) -> Self {
         let output_file = output_path.to_owned() + link_settings.links_file.as_str() + ".parquet";
         let link_model = match link_settings.link_model.to_lowercase().as_str() {
             "circular" => LinkModel::Circular,
             _ => panic!("Invalid linker model"),
         };
+        let writer_cache = WriterCache::new(125000, link_settings, !obstacles.is_empty());
         Self {
             link_model,
-            writer: Self::create_writer(output_file.as_str()),
+            writer: Self::create_writer(output_file.as_str(), writer_cache.schema()),
             linker_settings: link_settings.clone(),
-            writer_cache: WriterCache::new(125000),
+            writer_cache,
+            obstacles,
         }
     }
 
-    fn create_writer(output_file: &str) -> ArrowWriter<File> {
+    fn create_writer(output_file: &str, schema: Schema) -> ArrowWriter<File> {
         debug!("Creating links file {}", output_file);
-        let time_ms = Field::new(TIME_STEP, DataType::UInt64, false);
-        let source_id = Field::new(AGENT_ID, DataType::UInt64, false);
-        let target_id = Field::new(TARGET_ID, DataType::UInt64, false);
-        let distance = Field::new(DISTANCE, DataType::Float64, false);
-
         let props = WriterProperties::builder()
             .set_compression(Compression::SNAPPY)
             .build();
 
-        let schema = Schema::new(vec![time_ms, source_id, target_id, distance]);
         let output_file = match File::create(output_file) {
             Ok(file) => file,
             Err(_) => panic!("Failed to create links file to write"),
@@ -148,20 +243,33 @@ impl LinkerImpl {
     pub(crate) fn write_links(
         &mut self,
         source_positions: &AgentIdPos,
-        target_tree: &KdTree<f64, 2>,
+        target_positions: &AgentIdPos,
+        target_tree: &KdTree<f64, 3>,
         now: TimeMS,
     ) {
         debug!("Calculating links for {}", now);
+        let targets: HashMap<u64, &[f64; 3]> = match self.obstacles.is_empty() {
+            true => HashMap::new(),
+            false => target_positions
+                .iter()
+                .map(|(agent_id, position)| (agent_id.as_u64(), position))
+                .collect(),
+        };
         for agent_id_pos in source_positions.iter() {
             if let Some(radius) = self.linker_settings.link_radius {
                 let neighbours: Vec<NearestNeighbour<f64, u64>> = target_tree
                     .within::<SquaredEuclidean>(&agent_id_pos.1, radius.as_f64() * radius.as_f64());
                 neighbours.into_iter().for_each(|neigh_dist| {
                     if neigh_dist.distance > 0. {
-                        self.writer_cache.times.push(now.as_u64());
-                        self.writer_cache.sources.push(agent_id_pos.0.as_u64());
-                        self.writer_cache.targets.push(neigh_dist.item);
-                        self.writer_cache.distances.push(neigh_dist.distance);
+                        let obstruction =
+                            self.obstruction_of(&agent_id_pos.1, &targets, neigh_dist.item);
+                        self.writer_cache.add_link(
+                            now,
+                            agent_id_pos.0.as_u64(),
+                            neigh_dist.item,
+                            neigh_dist.distance,
+                            obstruction,
+                        );
                     }
                 });
                 continue;
@@ -171,10 +279,15 @@ impl LinkerImpl {
                     target_tree.nearest_n::<SquaredEuclidean>(&agent_id_pos.1, count.as_usize());
                 neighbours.into_iter().for_each(|neigh_dist| {
                     if neigh_dist.distance > 0. {
-                        self.writer_cache.times.push(now.as_u64());
-                        self.writer_cache.sources.push(agent_id_pos.0.as_u64());
-                        self.writer_cache.targets.push(neigh_dist.item);
-                        self.writer_cache.distances.push(neigh_dist.distance);
+                        let obstruction =
+                            self.obstruction_of(&agent_id_pos.1, &targets, neigh_dist.item);
+                        self.writer_cache.add_link(
+                            now,
+                            agent_id_pos.0.as_u64(),
+                            neigh_dist.item,
+                            neigh_dist.distance,
+                            obstruction,
+                        );
                     }
                 });
             }
@@ -187,6 +300,20 @@ impl LinkerImpl {
         }
     }
 
+    /// Attenuation in dB by the obstacles between the source and the target, if there are
+    /// obstacles and the position of the target is known.
+    fn obstruction_of(
+        &self,
+        source: &[f64; 3],
+        targets: &HashMap<u64, &[f64; 3]>,
+        target: u64,
+    ) -> Option<f64> {
+        let target = targets.get(&target)?;
+        let from = Point2D::builder().x(source[0]).y(source[1]).build();
+        let to = Point2D::builder().x(target[0]).y(target[1]).build();
+        Some(self.obstacles.obstruction(&from, &to) as f64)
+    }
+
     pub(crate) fn flush(mut self) {
         debug!(r"Link calculation done. Flushing the cache to file");
         self.writer
@@ -195,3 +322,72 @@ impl LinkerImpl {
         let _ = self.writer.close().expect("Failed to close the link file");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LinkSettings;
+    use disolv_core::agent::AgentId;
+    use disolv_input::batch::{read_f64_column, read_u64_column};
+    use disolv_models::device::types::DeviceType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn settings(links_file: &str, radius: f64) -> LinkSettings {
+        LinkSettings {
+            source: DeviceType::Vehicle,
+            target: DeviceType::UAV,
+            link_count: None,
+            link_radius: Some(Radius::from(radius)),
+            link_model: "circular".to_string(),
+            link_type: LinkType::Dynamic,
+            links_file: links_file.to_string(),
+            uplink: None,
+            downlink: None,
+        }
+    }
+
+    fn links_to_uav(links_file: &str, radius: f64) -> Vec<(u64, f64)> {
+        let output_path = std::env::temp_dir().display().to_string() + "/";
+        let vehicles: AgentIdPos = vec![(AgentId::from(1), [0.0, 0.0, 0.0])];
+        let uavs: AgentIdPos = vec![(AgentId::from(10), [30.0, 40.0, 120.0])];
+        let mut uav_tree: KdTree<f64, 3> = KdTree::default();
+        uavs.iter()
+            .for_each(|(id, position)| uav_tree.add(position, id.as_u64()));
+
+        let mut linker = LinkerImpl::new(
+            &output_path,
+            &settings(links_file, radius),
+            Obstacles::default(),
+        );
+        linker.write_links(&vehicles, &uavs, &uav_tree, TimeMS::default());
+        linker.flush();
+
+        let file = File::open(output_path + links_file + ".parquet").expect("links file");
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .expect("parquet reader")
+            .build()
+            .expect("record batch reader");
+        let mut links = Vec::new();
+        for batch in reader {
+            let batch = batch.expect("record batch");
+            let targets = read_u64_column(TARGET_ID, &batch);
+            let distances = read_f64_column(DISTANCE, &batch);
+            links.extend(targets.into_iter().zip(distances));
+        }
+        links
+    }
+
+    #[test]
+    fn test_uav_beyond_the_slant_range_is_not_linked() {
+        // The UAV is 50 m away on the ground but 130 m away through the air.
+        assert!(links_to_uav("disolv_links_uav_far", 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_uav_within_the_slant_range_is_linked() {
+        assert_eq!(
+            links_to_uav("disolv_links_uav_near", 150.0),
+            vec![(10, 130.0 * 130.0)]
+        );
+    }
+}
@@ -1,15 +1,9 @@
-mod builder;
-mod config;
-mod linker;
-mod logger;
-mod reader;
-
-use crate::builder::LinkBuilder;
-use crate::config::{read_config, Config};
 use clap::Parser;
 use crossterm::event::{self, Event as CrosstermEvent};
 use disolv_core::tui::{handle_link_key_events, Tui};
 use disolv_core::ui::{LinkContent, Message};
+use disolv_links::builder::LinkBuilder;
+use disolv_links::config::{read_config, Config};
 use log::{debug, info};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
@@ -18,6 +12,8 @@ use std::sync::mpsc;
 use std::time::Duration;
 use std::{io, thread};
 
+const HEATMAP_INTERVAL: u64 = 10;
+
 #[derive(Parser, Debug)]
 #[command(author, version, long_about = None)]
 struct CliArgs {
@@ -56,6 +52,10 @@ fn generate_links(mut builder: LinkBuilder) {
                 match receiver_ui.recv() {
                     Ok(message) => match message {
                         Message::CurrentTime(now) => ui_content.update_now(now),
+                        Message::Memory(_) => {}
+                        Message::StageTimes(_) => {}
+                        Message::MemoryUsage(_) => {}
+                        Message::Heatmap(heatmap) => ui_content.update_heatmap(*heatmap),
                         Message::Quit => ui_content.quit(),
                         Message::Key(key_event) => {
                             handle_link_key_events(key_event, &mut ui_content)
@@ -93,8 +93,15 @@ fn generate_links(mut builder: LinkBuilder) {
             builder.initialize();
             debug!("{} {}", builder.start, builder.end);
             let mut now = builder.start;
+            let mut steps: u64 = 0;
             while now < builder.end {
                 builder.build_links_at(now);
+                if steps.is_multiple_of(HEATMAP_INTERVAL) {
+                    // A failure to send is caught when the time is sent below.
+                    let _ =
+                        terminal_sender.send(Message::Heatmap(Box::new(builder.heatmap_at(now))));
+                }
+                steps += 1;
                 match terminal_sender.send(Message::CurrentTime(now.as_u64())) {
                     Ok(_) => {}
                     Err(_) => {
@@ -3,7 +3,7 @@ use arrow::array::RecordBatch;
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::TimeMS;
 use disolv_input::batch::{read_f64_column, read_u64_column};
-use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, TIME_STEP};
+use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, COORD_Z, TIME_STEP};
 use hashbrown::HashMap;
 use kiddo::KdTree;
 use log::debug;
@@ -13,11 +13,11 @@ use serde::Deserialize;
 use std::fs::File;
 use std::path::PathBuf;
 
-pub type AgentIdPos = Vec<(AgentId, [f64; 2])>;
+pub type AgentIdPos = Vec<(AgentId, [f64; 3])>;
 pub type PositionMap = HashMap<TimeMS, AgentIdPos>;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
-pub(crate) enum TraceType {
+pub enum TraceType {
     Constant = 0,
     Mobile,
 }
@@ -66,7 +66,7 @@ impl Reader {
         }
     }
 
-    pub(crate) fn get_kd_tree(&self) -> &KdTree<f64, 2> {
+    pub(crate) fn get_kd_tree(&self) -> &KdTree<f64, 3> {
         match self {
             Reader::Constant(reader) => &reader.kd_tree,
             Reader::Mobile(reader) => &reader.kd_tree,
@@ -74,6 +74,15 @@ impl Reader {
     }
 }
 
+/// Reads the altitude of the agents if present. Agents without altitude are on the ground,
+/// which makes the distance calculation identical to the planar one.
+fn read_z_column(record_batch: &RecordBatch, batch_size: usize) -> Vec<f64> {
+    match record_batch.column_by_name(COORD_Z) {
+        Some(_) => read_f64_column(COORD_Z, record_batch),
+        None => vec![0.0; batch_size],
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct MobileReader {
     pub(crate) file_path: PathBuf,
@@ -82,7 +91,7 @@ pub(crate) struct MobileReader {
     pub(crate) positions: PositionMap,
     pub(crate) file_read: bool,
     pub(crate) max_row_groups: usize,
-    pub(crate) kd_tree: KdTree<f64, 2>,
+    pub(crate) kd_tree: KdTree<f64, 3>,
 }
 
 impl MobileReader {
@@ -148,12 +157,13 @@ impl MobileReader {
                 .collect();
             let x_positions = read_f64_column(COORD_X, &record_batch);
             let y_positions = read_f64_column(COORD_Y, &record_batch);
+            let z_positions = read_z_column(&record_batch, batch_size);
 
             for batch in 0..batch_size {
-                self.positions
-                    .entry(time_steps[batch])
-                    .or_default()
-                    .push((agent_ids[batch], [x_positions[batch], y_positions[batch]]));
+                self.positions.entry(time_steps[batch]).or_default().push((
+                    agent_ids[batch],
+                    [x_positions[batch], y_positions[batch], z_positions[batch]],
+                ));
             }
             self.max_ts_in_row_group = *time_steps.iter().max().expect("cannot find max time");
         }
@@ -179,7 +189,7 @@ impl MobileReader {
 pub(crate) struct ConstantReader {
     pub(crate) file_path: PathBuf,
     pub(crate) positions: AgentIdPos,
-    pub(crate) kd_tree: KdTree<f64, 2>,
+    pub(crate) kd_tree: KdTree<f64, 3>,
 }
 
 impl ConstantReader {
@@ -217,10 +227,13 @@ impl ConstantReader {
                 .collect();
             let x_positions = read_f64_column(COORD_X, &record_batch);
             let y_positions = read_f64_column(COORD_Y, &record_batch);
+            let z_positions = read_z_column(&record_batch, batch_size);
 
             for batch in 0..batch_size {
-                self.positions
-                    .push((agent_ids[batch], [x_positions[batch], y_positions[batch]]));
+                self.positions.push((
+                    agent_ids[batch],
+                    [x_positions[batch], y_positions[batch], z_positions[batch]],
+                ));
             }
         }
     }
@@ -245,4 +258,87 @@ impl ConstantReader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use arrow::array::{ArrayRef, Float64Array, UInt64Array};
+    use kiddo::SquaredEuclidean;
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    fn write_trace(name: &str, positions: &[(u64, f64, f64, Option<f64>)]) -> PositionFiles {
+        let file = std::env::temp_dir().join(name);
+        let mut columns = vec![
+            (
+                TIME_STEP,
+                Arc::new(UInt64Array::from(vec![0u64; positions.len()])) as ArrayRef,
+            ),
+            (
+                AGENT_ID,
+                Arc::new(UInt64Array::from_iter_values(positions.iter().map(|p| p.0))) as ArrayRef,
+            ),
+            (
+                COORD_X,
+                Arc::new(Float64Array::from_iter_values(
+                    positions.iter().map(|p| p.1),
+                )) as ArrayRef,
+            ),
+            (
+                COORD_Y,
+                Arc::new(Float64Array::from_iter_values(
+                    positions.iter().map(|p| p.2),
+                )) as ArrayRef,
+            ),
+        ];
+        if positions.iter().all(|p| p.3.is_some()) {
+            let z = positions.iter().map(|p| p.3.expect("altitude is set"));
+            columns.push((
+                COORD_Z,
+                Arc::new(Float64Array::from_iter_values(z)) as ArrayRef,
+            ));
+        }
+        let batch = RecordBatch::try_from_iter(columns).expect("valid position batch");
+        let mut writer = ArrowWriter::try_new(
+            File::create(&file).expect("temp file"),
+            batch.schema(),
+            None,
+        )
+        .expect("parquet writer");
+        writer.write(&batch).expect("positions are written");
+        writer.close().expect("position file is closed");
+        PositionFiles {
+            device: disolv_models::device::types::DeviceType::UAV,
+            trace_type: TraceType::Constant,
+            position_file: file.display().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_missing_altitude_places_agents_on_the_ground() {
+        let files = write_trace("disolv_links_planar.parquet", &[(1, 3.0, 4.0, None)]);
+        let mut reader = Reader::new(&files);
+        reader.initialize();
+        let positions = reader
+            .read_positions_at(TimeMS::default())
+            .expect("constant positions");
+        assert_eq!(positions, &vec![(AgentId::from(1), [3.0, 4.0, 0.0])]);
+    }
+
+    #[test]
+    fn test_kd_tree_measures_the_slant_range_to_aerial_agents() {
+        let files = write_trace(
+            "disolv_links_aerial.parquet",
+            &[(1, 0.0, 0.0, Some(100.0)), (2, 60.0, 0.0, Some(0.0))],
+        );
+        let mut reader = Reader::new(&files);
+        reader.initialize();
+        let in_range = reader
+            .get_kd_tree()
+            .within::<SquaredEuclidean>(&[0.0, 0.0, 0.0], 80.0 * 80.0);
+        let ids: Vec<u64> = in_range.iter().map(|neighbour| neighbour.item).collect();
+        assert_eq!(ids, vec![2]);
+
+        let nearest = reader
+            .get_kd_tree()
+            .nearest_one::<SquaredEuclidean>(&[0.0, 0.0, 90.0]);
+        assert_eq!(nearest.item, 1);
+        assert_eq!(nearest.distance, 100.0);
+    }
 }
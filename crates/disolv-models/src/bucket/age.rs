@@ -0,0 +1,110 @@
+use crate::net::message::{DPayload, DataType};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use serde::Deserialize;
+
+/// Settings of the Age-of-Information tracking. Only the updates of the given data types are
+/// tracked.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AgeSettings {
+    pub data_types: Vec<DataType>,
+}
+
+/// Freshness of the information a target has about a source. The age is the time since the
+/// freshest update received from the source was created. The peak age is the largest age just
+/// before a fresher update was received.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AgeRecord {
+    pub freshest_update: TimeMS,
+    pub updates: u64,
+    pub peak_age: TimeMS,
+}
+
+impl AgeRecord {
+    pub fn age_at(&self, now: TimeMS) -> TimeMS {
+        TimeMS::from(now.as_u64().saturating_sub(self.freshest_update.as_u64()))
+    }
+}
+
+/// Records the age of the information per source and target pair from the updates delivered to
+/// the targets.
+#[derive(Clone, Debug)]
+pub struct AgeTracker {
+    data_types: Vec<DataType>,
+    records: HashMap<(AgentId, AgentId), AgeRecord>,
+}
+
+impl AgeTracker {
+    pub fn new(settings: &AgeSettings) -> Self {
+        Self {
+            data_types: settings.data_types.clone(),
+            records: HashMap::new(),
+        }
+    }
+
+    /// Updates the age of the information the target has about the senders of the payloads.
+    pub fn record_delivery(&mut self, target: AgentId, payloads: &[DPayload], now: TimeMS) {
+        for payload in payloads.iter() {
+            let created_at = payload
+                .metadata
+                .data_blobs
+                .iter()
+                .filter(|blob| self.data_types.contains(&blob.data_type))
+                .map(|blob| blob.created_at)
+                .max();
+            let created_at = match created_at {
+                Some(created_at) => created_at,
+                None => continue,
+            };
+            let source = payload.agent_state.device_info.id;
+            match self.records.get_mut(&(source, target)) {
+                Some(record) => {
+                    if created_at <= record.freshest_update {
+                        continue;
+                    }
+                    record.peak_age = record.peak_age.max(record.age_at(now));
+                    record.freshest_update = created_at;
+                    record.updates += 1;
+                }
+                None => {
+                    self.records.insert(
+                        (source, target),
+                        AgeRecord {
+                            freshest_update: created_at,
+                            updates: 1,
+                            peak_age: TimeMS::default(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Records of all the source and target pairs, ordered by the pair.
+    pub fn records(&self) -> Vec<((AgentId, AgentId), AgeRecord)> {
+        let mut records: Vec<((AgentId, AgentId), AgeRecord)> = self
+            .records
+            .iter()
+            .map(|(pair, record)| (*pair, *record))
+            .collect();
+        records.sort_by_key(|(pair, _)| *pair);
+        records
+    }
+
+    pub fn record_count(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn mean_age(&self, now: TimeMS) -> Option<f64> {
+        if self.records.is_empty() {
+            return None;
+        }
+        let total: u64 = self
+            .records
+            .values()
+            .map(|record| record.age_at(now).as_u64())
+            .sum();
+        Some(total as f64 / self.records.len() as f64)
+    }
+}
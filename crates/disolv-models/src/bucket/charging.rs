@@ -0,0 +1,166 @@
+use crate::device::charging::StationSettings;
+use crate::device::metrics::Energy;
+use crate::device::mobility::Point2D;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+
+/// Slots of a charging station in the current step, and the reservations it granted and
+/// denied in the step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StationOccupancy {
+    pub station_id: AgentId,
+    pub slots: u32,
+    pub occupied: u32,
+    pub granted: u64,
+    pub denied: u64,
+}
+
+/// Stay of a vehicle at a charging station from the reservation until it left the station.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChargingSession {
+    pub vehicle_id: AgentId,
+    pub station_id: AgentId,
+    pub start: TimeMS,
+    pub end: TimeMS,
+    pub energy: Energy,
+}
+
+#[derive(Clone, Debug)]
+struct Station {
+    slots: u32,
+    charge_rate: Energy,
+    position: Point2D,
+    occupants: Vec<AgentId>,
+    granted: u64,
+    denied: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Reservation {
+    station_id: AgentId,
+    start: TimeMS,
+}
+
+/// Charging stations of the simulation with the slots they reserved for the vehicles. Stations
+/// reserve a slot for a vehicle when they receive its request and have a free slot, and the
+/// slot stays occupied until the vehicle leaves the station.
+#[derive(Clone, Debug, Default)]
+pub struct ChargingStations {
+    stations: HashMap<AgentId, Station>,
+    reservations: HashMap<AgentId, Reservation>,
+    sessions: Vec<ChargingSession>,
+    completed: u64,
+    denied: u64,
+}
+
+impl ChargingStations {
+    /// Registers the station, or updates its position when it is already registered.
+    pub fn register_station(
+        &mut self,
+        station_id: AgentId,
+        settings: &StationSettings,
+        position: Point2D,
+    ) {
+        self.stations
+            .entry(station_id)
+            .and_modify(|station| station.position = position)
+            .or_insert(Station {
+                slots: settings.slots,
+                charge_rate: settings.charge_rate,
+                position,
+                occupants: Vec::new(),
+                granted: 0,
+                denied: 0,
+            });
+    }
+
+    /// Reserves a slot of the station for the vehicle. Vehicles that already hold a
+    /// reservation keep it. Returns whether the vehicle holds a reservation at the station.
+    pub fn request(&mut self, station_id: AgentId, vehicle_id: AgentId, step: TimeMS) -> bool {
+        if let Some(reservation) = self.reservations.get(&vehicle_id) {
+            return reservation.station_id == station_id;
+        }
+        let station = match self.stations.get_mut(&station_id) {
+            Some(station) => station,
+            None => return false,
+        };
+        if station.occupants.len() as u32 >= station.slots {
+            station.denied += 1;
+            self.denied += 1;
+            return false;
+        }
+        station.occupants.push(vehicle_id);
+        station.granted += 1;
+        self.reservations.insert(
+            vehicle_id,
+            Reservation {
+                station_id,
+                start: step,
+            },
+        );
+        true
+    }
+
+    /// Station that reserved a slot for the vehicle, with its position.
+    pub fn reservation_of(&self, vehicle_id: AgentId) -> Option<(AgentId, Point2D)> {
+        let reservation = self.reservations.get(&vehicle_id)?;
+        let station = self.stations.get(&reservation.station_id)?;
+        Some((reservation.station_id, station.position))
+    }
+
+    /// Energy charged in a step by the station the vehicle holds a reservation at.
+    pub fn charge_rate_for(&self, vehicle_id: AgentId) -> Energy {
+        self.reservations
+            .get(&vehicle_id)
+            .and_then(|reservation| self.stations.get(&reservation.station_id))
+            .map(|station| station.charge_rate)
+            .unwrap_or_default()
+    }
+
+    /// Frees the slot of the vehicle and records its charging session.
+    pub fn release(&mut self, vehicle_id: AgentId, energy: Energy, step: TimeMS) {
+        let reservation = match self.reservations.remove(&vehicle_id) {
+            Some(reservation) => reservation,
+            None => return,
+        };
+        if let Some(station) = self.stations.get_mut(&reservation.station_id) {
+            station.occupants.retain(|occupant| *occupant != vehicle_id);
+        }
+        self.completed += 1;
+        self.sessions.push(ChargingSession {
+            vehicle_id,
+            station_id: reservation.station_id,
+            start: reservation.start,
+            end: step,
+            energy,
+        });
+    }
+
+    /// Takes the occupancy of the stations in this step, ordered by the station.
+    pub fn take_occupancy(&mut self) -> Vec<StationOccupancy> {
+        let mut occupancy: Vec<StationOccupancy> = self
+            .stations
+            .iter_mut()
+            .map(|(station_id, station)| StationOccupancy {
+                station_id: *station_id,
+                slots: station.slots,
+                occupied: station.occupants.len() as u32,
+                granted: std::mem::take(&mut station.granted),
+                denied: std::mem::take(&mut station.denied),
+            })
+            .collect();
+        occupancy.sort_by_key(|occupancy| occupancy.station_id);
+        occupancy
+    }
+
+    /// Takes the sessions completed since the previous call.
+    pub fn take_sessions(&mut self) -> Vec<ChargingSession> {
+        std::mem::take(&mut self.sessions)
+    }
+
+    /// Sessions completed and reservations denied since the start of the run.
+    pub fn totals(&self) -> (u64, u64) {
+        (self.completed, self.denied)
+    }
+}
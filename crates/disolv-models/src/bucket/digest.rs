@@ -0,0 +1,197 @@
+use disolv_core::bucket::TimeMS;
+use log::{error, info};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+const DIGEST_HEADER: &str = "time_step,attempted,succeeded,expired,delivered,digest";
+
+/// Running totals of the transfers at the end of a step that go into the digest of the run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepCounts {
+    pub attempted: u64,
+    pub succeeded: u64,
+    pub expired: u64,
+    pub delivered: u64,
+}
+
+/// Digest of the run up to the end of the step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepDigest {
+    pub time_step: TimeMS,
+    pub counts: StepCounts,
+    pub digest: u64,
+}
+
+impl StepDigest {
+    fn parse(line: &str) -> Option<Self> {
+        let values: Vec<&str> = line.trim().split(',').collect();
+        if values.len() != 6 {
+            return None;
+        }
+        Some(Self {
+            time_step: values[0].parse().ok()?,
+            counts: StepCounts {
+                attempted: values[1].parse().ok()?,
+                succeeded: values[2].parse().ok()?,
+                expired: values[3].parse().ok()?,
+                delivered: values[4].parse().ok()?,
+            },
+            digest: u64::from_str_radix(values[5], 16).ok()?,
+        })
+    }
+}
+
+/// Whether the digest of the run is written to the file or compared with the one in the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DigestMode {
+    Record(PathBuf),
+    Verify(PathBuf),
+}
+
+/// Rolling FNV-1a hash of the transfer counts at the end of every step. A recorded digest keeps
+/// the counts of every step, so that a run verified against it stops at the first step that
+/// diverged and shows the counts that differ.
+#[derive(Clone, Debug)]
+pub struct RunDigest {
+    mode: DigestMode,
+    digest: u64,
+    steps: Vec<StepDigest>,
+    verified: usize,
+}
+
+impl RunDigest {
+    pub fn new(mode: DigestMode) -> Self {
+        let steps = match mode {
+            DigestMode::Record(_) => Vec::new(),
+            DigestMode::Verify(ref digest_file) => Self::read(digest_file),
+        };
+        Self {
+            mode,
+            digest: 0xcbf29ce484222325,
+            steps,
+            verified: 0,
+        }
+    }
+
+    fn read(digest_file: &Path) -> Vec<StepDigest> {
+        let content = match std::fs::read_to_string(digest_file) {
+            Ok(content) => content,
+            Err(e) => panic!(
+                "Failed to read the digest file {}: {}",
+                digest_file.display(),
+                e
+            ),
+        };
+        content
+            .lines()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match StepDigest::parse(line) {
+                Some(step) => step,
+                None => {
+                    error!("Digest lines must be {}", DIGEST_HEADER);
+                    panic!("Invalid digest line {}.", line);
+                }
+            })
+            .collect()
+    }
+
+    pub fn digest(&self) -> u64 {
+        self.digest
+    }
+
+    /// Adds the counts of the step to the digest. A verified run panics when the digest differs
+    /// from the recorded one.
+    pub fn add_step(&mut self, time_step: TimeMS, counts: StepCounts) {
+        let values = [
+            time_step.as_u64(),
+            counts.attempted,
+            counts.succeeded,
+            counts.expired,
+            counts.delivered,
+        ];
+        self.digest = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .fold(self.digest, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        let step = StepDigest {
+            time_step,
+            counts,
+            digest: self.digest,
+        };
+        match self.mode {
+            DigestMode::Record(_) => self.steps.push(step),
+            DigestMode::Verify(_) => self.verify(step),
+        }
+    }
+
+    fn verify(&mut self, step: StepDigest) {
+        let recorded = match self.steps.get(self.verified) {
+            Some(recorded) => *recorded,
+            None => {
+                error!("The recorded run ended before step {}", step.time_step);
+                panic!("Digest mismatch at step {}.", step.time_step);
+            }
+        };
+        if recorded != step {
+            error!(
+                "Run diverged from the recorded digest at step {}: recorded {:?}, found {:?}",
+                step.time_step, recorded, step
+            );
+            panic!("Digest mismatch at step {}.", step.time_step);
+        }
+        self.verified += 1;
+    }
+
+    /// Writes the recorded digest, or checks that the verified run covered all the recorded
+    /// steps.
+    pub fn finish(&self) {
+        match self.mode {
+            DigestMode::Record(ref digest_file) => {
+                let mut content = format!("{}\n", DIGEST_HEADER);
+                for step in self.steps.iter() {
+                    let _ = writeln!(
+                        content,
+                        "{},{},{},{},{},{:016x}",
+                        step.time_step,
+                        step.counts.attempted,
+                        step.counts.succeeded,
+                        step.counts.expired,
+                        step.counts.delivered,
+                        step.digest
+                    );
+                }
+                if let Err(e) = std::fs::write(digest_file, content) {
+                    panic!(
+                        "Failed to write the digest file {}: {}",
+                        digest_file.display(),
+                        e
+                    );
+                }
+                info!(
+                    "Recorded digest {:016x} of {} steps to {}",
+                    self.digest,
+                    self.steps.len(),
+                    digest_file.display()
+                );
+            }
+            DigestMode::Verify(ref digest_file) => {
+                if self.verified < self.steps.len() {
+                    error!(
+                        "The run ended after {} of the {} recorded steps",
+                        self.verified,
+                        self.steps.len()
+                    );
+                    panic!("Digest mismatch at the end of the run.");
+                }
+                info!(
+                    "Verified digest {:016x} against {}",
+                    self.digest,
+                    digest_file.display()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::bucket::flow::FlowRegister;
+use crate::device::types::DeviceClass;
+use disolv_core::agent::AgentId;
+use disolv_core::hashbrown::HashMap;
+
+/// Jain's fairness index of the values. It is 1 when all the values are equal and approaches
+/// 1/n when a single value dominates. Values that are all zero are considered to be fair.
+pub fn jain_index(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let sum: f64 = values.iter().sum();
+    let sum_of_squares: f64 = values.iter().map(|value| value * value).sum();
+    if sum_of_squares == 0.0 {
+        return Some(1.0);
+    }
+    Some(sum * sum / (values.len() as f64 * sum_of_squares))
+}
+
+/// Bytes an agent tried to send and delivered in an output interval. The agent starved in a
+/// step when it tried to send but nothing was delivered.
+#[derive(Clone, Debug, Default)]
+pub struct FlowShare {
+    pub device_class: DeviceClass,
+    pub attempted_bytes: u64,
+    pub delivered_bytes: u64,
+    pub starved_steps: u64,
+    pub source_bytes: HashMap<AgentId, u64>,
+}
+
+impl FlowShare {
+    /// Fairness of the bytes this agent received from each of its sources.
+    pub fn source_fairness(&self) -> Option<f64> {
+        let received: Vec<f64> = self
+            .source_bytes
+            .values()
+            .map(|bytes| *bytes as f64)
+            .collect();
+        jain_index(&received)
+    }
+}
+
+/// Fairness of the bytes delivered by the agents of a class in an output interval.
+#[derive(Clone, Copy, Debug)]
+pub struct ClassFairness {
+    pub device_class: DeviceClass,
+    pub agent_count: u64,
+    pub delivered_bytes: u64,
+    pub jain_index: f64,
+    pub starved_agents: u64,
+    pub starved_steps: u64,
+}
+
+/// Collects the flows of all the agents in every step and computes the fairness of the
+/// delivered bytes per class and per agent at the end of an output interval.
+#[derive(Clone, Debug, Default)]
+pub struct FairnessRegister {
+    shares: HashMap<AgentId, FlowShare>,
+}
+
+impl FairnessRegister {
+    /// Adds the uplink and sidelink flows of the agent in this step.
+    pub fn record(
+        &mut self,
+        agent_id: AgentId,
+        device_class: DeviceClass,
+        flows: &[&FlowRegister],
+    ) {
+        let share = self.shares.entry(agent_id).or_default();
+        share.device_class = device_class;
+        let mut attempted = 0;
+        let mut delivered = 0;
+        for flow in flows.iter() {
+            attempted += flow.out_stats.attempted.data_size.as_u64();
+            delivered += flow.out_stats.feasible.data_size.as_u64();
+            for (source, bytes) in flow.in_source_bytes.iter() {
+                *share.source_bytes.entry(*source).or_default() += bytes;
+            }
+        }
+        share.attempted_bytes += attempted;
+        share.delivered_bytes += delivered;
+        if attempted > 0 && delivered == 0 {
+            share.starved_steps += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+
+    /// Fairness of every class in the interval, ordered by the class.
+    pub fn class_fairness(&self) -> Vec<ClassFairness> {
+        let mut delivered: HashMap<DeviceClass, Vec<&FlowShare>> = HashMap::new();
+        for share in self.shares.values() {
+            delivered.entry(share.device_class).or_default().push(share);
+        }
+        let mut fairness: Vec<ClassFairness> = delivered
+            .into_iter()
+            .map(|(device_class, shares)| {
+                let bytes: Vec<f64> = shares
+                    .iter()
+                    .map(|share| share.delivered_bytes as f64)
+                    .collect();
+                ClassFairness {
+                    device_class,
+                    agent_count: shares.len() as u64,
+                    delivered_bytes: shares.iter().map(|share| share.delivered_bytes).sum(),
+                    jain_index: jain_index(&bytes).unwrap_or(1.0),
+                    starved_agents: shares
+                        .iter()
+                        .filter(|share| share.starved_steps > 0)
+                        .count() as u64,
+                    starved_steps: shares.iter().map(|share| share.starved_steps).sum(),
+                }
+            })
+            .collect();
+        fairness.sort_by_key(|class| class.device_class.as_int());
+        fairness
+    }
+
+    /// Flow shares of the agents in the interval, ordered by the agent.
+    pub fn agent_shares(&self) -> Vec<(AgentId, &FlowShare)> {
+        let mut shares: Vec<(AgentId, &FlowShare)> = self
+            .shares
+            .iter()
+            .map(|(agent_id, share)| (*agent_id, share))
+            .collect();
+        shares.sort_by_key(|(agent_id, _)| *agent_id);
+        shares
+    }
+
+    /// Starts a new output interval.
+    pub fn reset(&mut self) {
+        self.shares.clear();
+    }
+}
@@ -0,0 +1,244 @@
+use crate::device::mobility::Point2D;
+use crate::net::network::Network;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::{HashMap, HashSet};
+use log::{error, info};
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Kinds of faults that can be injected into the simulation.
+/// * `Outage`: the agents in `agent_ids` can neither send nor receive.
+/// * `SliceDegradation`: the capacity of the slice `slice_id` drops to `capacity_factor` of its
+///   nominal capacity.
+/// * `Blackout`: the agents in the `area` given as `[x_min, y_min, x_max, y_max]` can neither
+///   send nor receive.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultType {
+    Outage,
+    SliceDegradation,
+    Blackout,
+}
+
+impl FaultType {
+    pub const fn as_int(&self) -> u32 {
+        match self {
+            FaultType::Outage => 0,
+            FaultType::SliceDegradation => 1,
+            FaultType::Blackout => 2,
+        }
+    }
+}
+
+/// Settings of a fault. The fault starts at `start` and lasts for `duration`. Without a start,
+/// the fault occurs `occurrences` times (once by default) at random starts drawn with the seed
+/// of the simulation.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct FaultSettings {
+    pub fault_type: FaultType,
+    pub duration: TimeMS,
+    pub start: Option<TimeMS>,
+    pub occurrences: Option<u32>,
+    pub agent_ids: Option<Vec<AgentId>>,
+    pub slice_id: Option<u32>,
+    pub capacity_factor: Option<f64>,
+    pub area: Option<[f64; 4]>,
+}
+
+/// A fault that started or ended in a step. Faults are identified by their position in the
+/// settings, so the occurrences of a fault share the id.
+#[derive(Clone, Copy, Debug)]
+pub struct FaultChange {
+    pub fault_id: u32,
+    pub fault_type: FaultType,
+    pub active: bool,
+}
+
+/// Running totals of the fault occurrences started and of the transfers they blocked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultCounts {
+    pub started: u64,
+    pub blocked: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Occurrence {
+    fault_id: usize,
+    start: TimeMS,
+    end: TimeMS,
+    active: bool,
+}
+
+/// Injects the configured faults by changing the network and the reachability of the agents
+/// while the faults are active.
+#[derive(Clone, Debug)]
+pub struct FaultInjector {
+    faults: Vec<FaultSettings>,
+    occurrences: Vec<Occurrence>,
+    down: HashSet<AgentId>,
+    blackouts: Vec<(Point2D, Point2D)>,
+    counts: FaultCounts,
+}
+
+impl FaultInjector {
+    pub fn new(faults: &[FaultSettings], duration: TimeMS, seed: u128, network: &Network) -> Self {
+        let mut rng = Pcg64Mcg::new(seed);
+        let mut occurrences = Vec::new();
+        for (fault_id, fault) in faults.iter().enumerate() {
+            Self::validate(fault, network);
+            let starts = match fault.start {
+                Some(start) => vec![start],
+                None => {
+                    let latest = duration.as_u64().saturating_sub(fault.duration.as_u64());
+                    (0..fault.occurrences.unwrap_or(1))
+                        .map(|_| TimeMS::from(rng.gen_range(0..=latest)))
+                        .collect()
+                }
+            };
+            for start in starts.into_iter() {
+                info!("Fault {} scheduled at {}", fault_id, start);
+                occurrences.push(Occurrence {
+                    fault_id,
+                    start,
+                    end: start + fault.duration,
+                    active: false,
+                });
+            }
+        }
+        Self {
+            faults: faults.to_vec(),
+            occurrences,
+            down: HashSet::new(),
+            blackouts: Vec::new(),
+            counts: FaultCounts::default(),
+        }
+    }
+
+    fn validate(fault: &FaultSettings, network: &Network) {
+        let valid = match fault.fault_type {
+            FaultType::Outage => fault.agent_ids.is_some(),
+            FaultType::SliceDegradation => {
+                let has_capacity = network
+                    .all_slices()
+                    .any(|slice| Some(slice.id) == fault.slice_id && slice.has_capacity());
+                has_capacity
+                    && fault
+                        .capacity_factor
+                        .is_some_and(|factor| (0.0..=1.0).contains(&factor))
+            }
+            FaultType::Blackout => fault.area.is_some(),
+        };
+        if !valid {
+            error!("Invalid settings of the fault {:?}", fault);
+            panic!("Invalid settings of a {:?} fault", fault.fault_type);
+        }
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        self.counts
+    }
+
+    /// Starts and ends the faults at the step and applies the active ones. Returns the faults
+    /// that started or ended.
+    pub fn start_step(&mut self, step: TimeMS, network: &mut Network) -> Vec<FaultChange> {
+        let mut changes = Vec::new();
+        for occurrence in self.occurrences.iter_mut() {
+            let active = occurrence.start <= step && step < occurrence.end;
+            if active == occurrence.active {
+                continue;
+            }
+            occurrence.active = active;
+            if active {
+                self.counts.started += 1;
+            }
+            changes.push(FaultChange {
+                fault_id: occurrence.fault_id as u32,
+                fault_type: self.faults[occurrence.fault_id].fault_type,
+                active,
+            });
+        }
+        if !changes.is_empty() {
+            self.apply(network);
+        }
+        changes
+    }
+
+    fn apply(&mut self, network: &mut Network) {
+        self.down.clear();
+        self.blackouts.clear();
+        let mut capacity_factors: HashMap<u32, f64> = HashMap::new();
+        for fault in self.faults.iter() {
+            if let (FaultType::SliceDegradation, Some(slice_id)) =
+                (fault.fault_type, fault.slice_id)
+            {
+                capacity_factors.insert(slice_id, 1.0);
+            }
+        }
+        for occurrence in self
+            .occurrences
+            .iter()
+            .filter(|occurrence| occurrence.active)
+        {
+            let fault = &self.faults[occurrence.fault_id];
+            match fault.fault_type {
+                FaultType::Outage => {
+                    self.down.extend(fault.agent_ids.iter().flatten().copied());
+                }
+                FaultType::SliceDegradation => {
+                    let slice_id = fault
+                        .slice_id
+                        .expect("Slice of the fault must be validated");
+                    let factor = fault
+                        .capacity_factor
+                        .expect("Capacity factor of the fault must be validated");
+                    *capacity_factors.entry(slice_id).or_insert(1.0) *= factor;
+                }
+                FaultType::Blackout => {
+                    let [x_min, y_min, x_max, y_max] =
+                        fault.area.expect("Area of the fault must be validated");
+                    self.blackouts.push((
+                        Point2D::builder().x(x_min).y(y_min).build(),
+                        Point2D::builder().x(x_max).y(y_max).build(),
+                    ));
+                }
+            }
+        }
+        for slice in network.all_slices_mut() {
+            if let Some(factor) = capacity_factors.get(&slice.id) {
+                slice.scale_capacity(*factor);
+            }
+        }
+    }
+
+    /// Whether an agent at the position is cut off by an outage or a blackout.
+    pub fn is_cut_off(&self, agent_id: AgentId, position: Option<&Point2D>) -> bool {
+        if self.down.contains(&agent_id) {
+            return true;
+        }
+        match position {
+            Some(position) => self.blackouts.iter().any(|(lower, upper)| {
+                lower.x <= position.x
+                    && position.x <= upper.x
+                    && lower.y <= position.y
+                    && position.y <= upper.y
+            }),
+            None => false,
+        }
+    }
+
+    /// Whether the transfer between the agents is blocked by a fault. Blocked transfers are
+    /// counted.
+    pub fn blocks(
+        &mut self,
+        sender: (AgentId, Option<&Point2D>),
+        target: (AgentId, Option<&Point2D>),
+    ) -> bool {
+        let blocked = self.is_cut_off(sender.0, sender.1) || self.is_cut_off(target.0, target.1);
+        if blocked {
+            self.counts.blocked += 1;
+        }
+        blocked
+    }
+}
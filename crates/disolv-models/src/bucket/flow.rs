@@ -10,12 +10,14 @@ pub struct FlowRegister {
     pub out_link_agents: HashMap<DeviceClass, Vec<AgentId>>,
     pub in_stats: IncomingStats,
     pub in_link_agents: HashMap<DeviceClass, Vec<AgentId>>,
+    pub in_source_bytes: HashMap<AgentId, u64>,
 }
 
 impl FlowRegister {
     pub fn reset(&mut self) {
-        self.out_stats.reset();
+        self.in_stats.reset();
         self.in_link_agents.clear();
+        self.in_source_bytes.clear();
         self.out_stats.reset();
         self.out_link_agents.clear();
     }
@@ -32,13 +34,29 @@ impl FlowRegister {
             .push(payload.agent_state.device_info.id);
     }
 
+    pub fn register_expired(&mut self, payloads: &[DPayload]) {
+        payloads.iter().for_each(|payload| {
+            self.out_stats.add_expired(&payload.metadata);
+        });
+    }
+
     pub fn register_incoming(&mut self, payloads: &Vec<DPayload>) {
         payloads.iter().for_each(|payload| {
             self.in_stats.update(&payload.metadata);
+            *self
+                .in_source_bytes
+                .entry(payload.agent_state.device_info.id)
+                .or_default() += payload.metadata.total_size.as_u64();
             self.in_link_agents
                 .entry(payload.agent_state.device_info.device_class)
                 .or_default()
                 .push(payload.agent_state.device_info.id);
         });
     }
+
+    pub fn register_dropped(&mut self, payloads: &[DPayload]) {
+        payloads.iter().for_each(|payload| {
+            self.in_stats.add_dropped(&payload.metadata);
+        });
+    }
 }
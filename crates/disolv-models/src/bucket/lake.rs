@@ -1,34 +1,154 @@
+use crate::bucket::age::{AgeSettings, AgeTracker};
+use crate::net::fragment::{FragmentSettings, Reassembly};
 use crate::net::message::DPayload;
 use crate::net::message::DResponse;
 use disolv_core::agent::AgentId;
-use disolv_core::hashbrown::HashMap;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::{HashMap, HashSet};
+use log::info;
+use serde::Deserialize;
+use std::fmt::Display;
 
 pub type PayloadMap = HashMap<AgentId, Vec<DPayload>>;
 pub type ResponseMap = HashMap<AgentId, DResponse>;
 
+/// Settings of the data lake. Payloads that are not received within `ttl` of their transfer
+/// expire. Without these settings, payloads that are not received in the step of their transfer
+/// are discarded.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct LakeSettings {
+    pub ttl: TimeMS,
+    pub log_expired: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExpiryReason {
+    TargetInactive,
+    TargetNeverActive,
+}
+
+impl Display for ExpiryReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpiryReason::TargetInactive => write!(f, "target stopped receiving"),
+            ExpiryReason::TargetNeverActive => write!(f, "target never received"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct DataLake {
     pub payloads: PayloadMap,
     pub sl_payloads: PayloadMap,
     pub responses: ResponseMap,
     pub sl_responses: ResponseMap,
+    pub expired: PayloadMap,
+    pub sl_expired: PayloadMap,
+    ttl: Option<TimeMS>,
+    log_expired: bool,
+    now: TimeMS,
+    receivers: HashSet<AgentId>,
+    age_tracker: Option<AgeTracker>,
+    reassembly: Option<Reassembly>,
+    taken: u64,
 }
 
 impl DataLake {
+    pub fn new(lake_settings: Option<LakeSettings>) -> Self {
+        match lake_settings {
+            Some(settings) => Self {
+                ttl: Some(settings.ttl),
+                log_expired: settings.log_expired.unwrap_or(false),
+                ..Self::default()
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// Tracks the age of the information delivered to the agents.
+    pub fn with_age_tracking(mut self, age_settings: Option<&AgeSettings>) -> Self {
+        self.age_tracker = age_settings.map(AgeTracker::new);
+        self
+    }
+
+    pub fn age_tracker(&self) -> Option<&AgeTracker> {
+        self.age_tracker.as_ref()
+    }
+
+    /// Reassembles the payloads sent in fragments at their targets.
+    pub fn with_reassembly(mut self, fragment_settings: Option<&FragmentSettings>) -> Self {
+        self.reassembly = fragment_settings.map(Reassembly::new);
+        self
+    }
+
+    pub fn reassembly(&self) -> Option<&Reassembly> {
+        self.reassembly.as_ref()
+    }
+
+    pub fn reassembly_mut(&mut self) -> Option<&mut Reassembly> {
+        self.reassembly.as_mut()
+    }
+
     pub fn payloads_for(&mut self, agent_id: AgentId) -> Option<Vec<DPayload>> {
-        self.payloads.remove(&agent_id)
+        self.receivers.insert(agent_id);
+        let payloads = self.payloads.remove(&agent_id);
+        if let Some(ref payloads) = payloads {
+            self.taken += payloads.len() as u64;
+            self.register_delivery(agent_id, payloads);
+        }
+        payloads
+    }
+
+    /// Registers the payloads delivered to the agent outside the lake.
+    pub fn register_delivery(&mut self, agent_id: AgentId, payloads: &[DPayload]) {
+        if let Some(ref mut age_tracker) = self.age_tracker {
+            age_tracker.record_delivery(agent_id, payloads, self.now);
+        }
     }
 
     pub fn add_payload_to(&mut self, agent_id: AgentId, payload: DPayload) {
+        let payload = self.with_expiry(payload);
         self.payloads.entry(agent_id).or_default().push(payload);
     }
 
     pub fn add_sl_payload_to(&mut self, agent_id: AgentId, payload: DPayload) {
+        let payload = self.with_expiry(payload);
         self.sl_payloads.entry(agent_id).or_default().push(payload);
     }
 
     pub fn sl_payloads_for(&mut self, agent_id: AgentId) -> Option<Vec<DPayload>> {
-        self.sl_payloads.remove(&agent_id)
+        self.receivers.insert(agent_id);
+        let payloads = self.sl_payloads.remove(&agent_id);
+        if let Some(ref payloads) = payloads {
+            self.taken += payloads.len() as u64;
+            self.register_delivery(agent_id, payloads);
+        }
+        payloads
+    }
+
+    /// Number of payloads waiting to be received.
+    pub fn stored(&self) -> usize {
+        Self::count(&self.payloads) + Self::count(&self.sl_payloads)
+    }
+
+    /// Number of payloads received from the lake since the start of the simulation.
+    pub fn taken(&self) -> u64 {
+        self.taken
+    }
+
+    /// Agents with payloads waiting to be received.
+    pub fn targets(&self) -> impl Iterator<Item = &AgentId> {
+        self.payloads.keys().chain(self.sl_payloads.keys())
+    }
+
+    /// Payloads sent by the agent that expired before they were received.
+    pub fn expired_for(&mut self, agent_id: AgentId) -> Option<Vec<DPayload>> {
+        self.expired.remove(&agent_id)
+    }
+
+    pub fn sl_expired_for(&mut self, agent_id: AgentId) -> Option<Vec<DPayload>> {
+        self.sl_expired.remove(&agent_id)
     }
 
     pub fn response_for(&mut self, agent_id: AgentId) -> Option<DResponse> {
@@ -47,13 +167,111 @@ impl DataLake {
         self.responses.entry(agent_id).or_insert(response);
     }
 
-    pub fn clean_payloads(&mut self) {
-        self.payloads.clear();
-        self.sl_payloads.clear();
+    /// Removes the payloads that can no longer be received at the given step and returns the
+    /// number of expired payloads. Expired payloads that were not collected by their senders in
+    /// the previous step are discarded.
+    pub fn clean_payloads(&mut self, now: TimeMS) -> usize {
+        self.now = now;
+        self.expired.clear();
+        self.sl_expired.clear();
+        if self.ttl.is_none() {
+            self.payloads.clear();
+            self.sl_payloads.clear();
+            return 0;
+        }
+
+        let expired = Self::purge(&mut self.payloads, now);
+        let sl_expired = Self::purge(&mut self.sl_payloads, now);
+        self.store_expired(expired, sl_expired)
+    }
+
+    /// Expires all the payloads waiting to be received and returns their number. The senders
+    /// collect them like the payloads that expired on their own.
+    pub fn expire_waiting(&mut self) -> usize {
+        let expired = Self::drain(&mut self.payloads);
+        let sl_expired = Self::drain(&mut self.sl_payloads);
+        self.store_expired(expired, sl_expired)
+    }
+
+    /// Discards the expired payloads not yet collected by their senders and returns their
+    /// number.
+    pub fn discard_expired(&mut self) -> usize {
+        let discarded = Self::count(&self.expired) + Self::count(&self.sl_expired);
+        self.expired.clear();
+        self.sl_expired.clear();
+        discarded
     }
 
     pub fn clean_responses(&mut self) {
         self.responses.clear();
         self.sl_responses.clear();
     }
+
+    fn store_expired(
+        &mut self,
+        mut expired: Vec<(AgentId, DPayload)>,
+        mut sl_expired: Vec<(AgentId, DPayload)>,
+    ) -> usize {
+        let expired_count = expired.len() + sl_expired.len();
+        if self.log_expired {
+            for (target, payload) in expired.iter().chain(sl_expired.iter()) {
+                self.log_expiry(*target, payload);
+            }
+        }
+        for (_, payload) in expired.drain(..) {
+            let sender = payload.agent_state.device_info.id;
+            self.expired.entry(sender).or_default().push(payload);
+        }
+        for (_, payload) in sl_expired.drain(..) {
+            let sender = payload.agent_state.device_info.id;
+            self.sl_expired.entry(sender).or_default().push(payload);
+        }
+        expired_count
+    }
+
+    fn with_expiry(&self, mut payload: DPayload) -> DPayload {
+        if let Some(ttl) = self.ttl {
+            if payload.metadata.expires_at.is_none() {
+                payload.metadata.expires_at = Some(self.now + ttl);
+            }
+        }
+        payload
+    }
+
+    fn purge(payload_map: &mut PayloadMap, now: TimeMS) -> Vec<(AgentId, DPayload)> {
+        let mut expired = Vec::new();
+        for (target, payloads) in payload_map.iter_mut() {
+            let (gone, kept): (Vec<DPayload>, Vec<DPayload>) = std::mem::take(payloads)
+                .into_iter()
+                .partition(|payload| payload.metadata.expires_at.is_some_and(|at| at <= now));
+            *payloads = kept;
+            expired.extend(gone.into_iter().map(|payload| (*target, payload)));
+        }
+        payload_map.retain(|_, payloads| !payloads.is_empty());
+        expired
+    }
+
+    fn drain(payload_map: &mut PayloadMap) -> Vec<(AgentId, DPayload)> {
+        payload_map
+            .drain()
+            .flat_map(|(target, payloads)| {
+                payloads.into_iter().map(move |payload| (target, payload))
+            })
+            .collect()
+    }
+
+    fn count(payload_map: &PayloadMap) -> usize {
+        payload_map.values().map(|payloads| payloads.len()).sum()
+    }
+
+    fn log_expiry(&self, target: AgentId, payload: &DPayload) {
+        let reason = match self.receivers.contains(&target) {
+            true => ExpiryReason::TargetInactive,
+            false => ExpiryReason::TargetNeverActive,
+        };
+        info!(
+            "Payload {} from agent {} to agent {} expired at {}: {}",
+            payload.metadata.id, payload.agent_state.device_info.id, target, self.now, reason
+        );
+    }
 }
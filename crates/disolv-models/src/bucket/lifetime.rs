@@ -0,0 +1,102 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use std::collections::VecDeque;
+
+/// Number of the most recent lifetimes of the ended links that are kept.
+const HISTORY: usize = 1000;
+
+#[derive(Clone, Copy, Debug)]
+struct LinkPresence {
+    since: TimeMS,
+    last_seen: TimeMS,
+}
+
+/// Tracks how long the links between the agent pairs have been present. A link ends when it is
+/// not seen in a step. The lifetimes of the recently ended links are used to estimate how much
+/// longer the present links last.
+#[derive(Clone, Debug)]
+pub struct LinkLifetimes {
+    step_size: TimeMS,
+    present: HashMap<(AgentId, AgentId), LinkPresence>,
+    ended: VecDeque<u64>,
+}
+
+impl LinkLifetimes {
+    pub fn new(step_size: TimeMS) -> Self {
+        Self {
+            step_size,
+            present: HashMap::new(),
+            ended: VecDeque::with_capacity(HISTORY),
+        }
+    }
+
+    /// Ends the links that were not seen in the last step.
+    pub fn start_step(&mut self, step: TimeMS) {
+        let step_size = self.step_size.as_u64();
+        let mut ended = Vec::new();
+        self.present.retain(|_, presence| {
+            let end = presence.last_seen.as_u64() + step_size;
+            if end >= step.as_u64() {
+                return true;
+            }
+            ended.push(end - presence.since.as_u64());
+            false
+        });
+        for lifetime in ended.into_iter() {
+            if self.ended.len() == HISTORY {
+                self.ended.pop_front();
+            }
+            self.ended.push_back(lifetime);
+        }
+    }
+
+    /// Registers the links from the source to the targets seen in the step.
+    pub fn observe(
+        &mut self,
+        source: AgentId,
+        targets: impl Iterator<Item = AgentId>,
+        step: TimeMS,
+    ) {
+        for target in targets {
+            self.present
+                .entry((source, target))
+                .and_modify(|presence| presence.last_seen = step)
+                .or_insert(LinkPresence {
+                    since: step,
+                    last_seen: step,
+                });
+        }
+    }
+
+    /// Time for which the link between the agents has been present.
+    pub fn age(&self, source: AgentId, target: AgentId, step: TimeMS) -> Option<TimeMS> {
+        let presence = self.present.get(&(source, target))?;
+        Some(TimeMS::from(
+            step.as_u64().saturating_sub(presence.since.as_u64()),
+        ))
+    }
+
+    /// Expected remaining lifetime of the link between the agents. It is the mean of what the
+    /// ended links that lived longer than the age of the link had left at that age. Without
+    /// such links, the link is expected to last as long again as it has been present.
+    pub fn expected_remaining(
+        &self,
+        source: AgentId,
+        target: AgentId,
+        step: TimeMS,
+    ) -> Option<TimeMS> {
+        let age = self.age(source, target, step)?.as_u64();
+        let (count, remaining) = self
+            .ended
+            .iter()
+            .filter(|lifetime| **lifetime > age)
+            .fold((0u64, 0u64), |(count, remaining), lifetime| {
+                (count + 1, remaining + lifetime - age)
+            });
+        match count {
+            0 => Some(TimeMS::from(age)),
+            _ => Some(TimeMS::from(remaining / count)),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use disolv_core::hashbrown::HashMap;
+
+/// Kind of a registered metric. A gauge holds the value it was last set to and a counter holds
+/// the total of its increments since the start of the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+impl MetricKind {
+    pub const fn as_int(&self) -> u32 {
+        match self {
+            MetricKind::Gauge => 0,
+            MetricKind::Counter => 1,
+        }
+    }
+}
+
+/// Handle of a registered metric, used to update it without looking up its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MetricId(usize);
+
+/// Value of a metric when the metrics were sampled.
+#[derive(Clone, Debug)]
+pub struct MetricSample<'a> {
+    pub name: &'a str,
+    pub kind: MetricKind,
+    pub value: f64,
+}
+
+/// Named gauges and counters registered by the models. The metrics are sampled at the output
+/// intervals into the metrics table, so that a new measurement does not need a table of its own.
+/// Metrics are sampled in the order they were registered.
+#[derive(Clone, Debug, Default)]
+pub struct MetricRegistry {
+    names: Vec<String>,
+    kinds: Vec<MetricKind>,
+    values: Vec<f64>,
+    ids: HashMap<String, MetricId>,
+}
+
+impl MetricRegistry {
+    /// Registers the metric, or returns the metric registered earlier with the name. Panics if
+    /// the name is registered with another kind.
+    pub fn register(&mut self, name: &str, kind: MetricKind) -> MetricId {
+        if let Some(id) = self.ids.get(name) {
+            if self.kinds[id.0] != kind {
+                panic!(
+                    "Metric {} is registered as a {:?}, not a {:?}",
+                    name, self.kinds[id.0], kind
+                );
+            }
+            return *id;
+        }
+        let id = MetricId(self.names.len());
+        self.names.push(name.to_string());
+        self.kinds.push(kind);
+        self.values.push(0.0);
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    pub fn gauge(&mut self, name: &str) -> MetricId {
+        self.register(name, MetricKind::Gauge)
+    }
+
+    pub fn counter(&mut self, name: &str) -> MetricId {
+        self.register(name, MetricKind::Counter)
+    }
+
+    pub fn set(&mut self, id: MetricId, value: f64) {
+        if self.kinds[id.0] != MetricKind::Gauge {
+            panic!("Metric {} is a counter and cannot be set", self.names[id.0]);
+        }
+        self.values[id.0] = value;
+    }
+
+    pub fn increment(&mut self, id: MetricId, by: f64) {
+        if self.kinds[id.0] != MetricKind::Counter {
+            panic!(
+                "Metric {} is a gauge and cannot be incremented",
+                self.names[id.0]
+            );
+        }
+        self.values[id.0] += by;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = MetricSample<'_>> {
+        self.names
+            .iter()
+            .zip(self.kinds.iter())
+            .zip(self.values.iter())
+            .map(|((name, kind), value)| MetricSample {
+                name,
+                kind: *kind,
+                value: *value,
+            })
+    }
+}
@@ -1,2 +1,12 @@
+pub mod age;
+pub mod charging;
+pub mod digest;
+pub mod fairness;
+pub mod fault;
 pub mod flow;
 pub mod lake;
+pub mod lifetime;
+pub mod metrics;
+pub mod sla;
+pub mod sleep;
+pub mod volume;
@@ -0,0 +1,144 @@
+use crate::device::types::DeviceClass;
+use crate::net::message::{DPayload, DataType, TxMetrics, TxStatus};
+use crate::net::metrics::Latency;
+use log::{error, warn};
+use serde::Deserialize;
+
+/// Settings of a service level agreement on the transfers of the payloads with blobs of the
+/// `data_type`, sent by the agents of the `agent_class`. Without either, the agreement covers
+/// the transfers of all the data types or classes. In every output interval, at least
+/// `min_delivery_ratio` of the transfers must be delivered within `max_latency`. Without a
+/// ratio every transfer must be, and without a maximum latency every delivery counts.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct SlaSettings {
+    pub name: String,
+    pub data_type: Option<DataType>,
+    pub agent_class: Option<DeviceClass>,
+    pub max_latency: Option<Latency>,
+    pub min_delivery_ratio: Option<f64>,
+}
+
+impl SlaSettings {
+    fn covers(&self, payload: &DPayload) -> bool {
+        let class_matches = self
+            .agent_class
+            .is_none_or(|class| class == payload.agent_state.device_info.device_class);
+        let type_matches = self.data_type.is_none_or(|data_type| {
+            payload
+                .metadata
+                .data_blobs
+                .iter()
+                .any(|blob| blob.data_type == data_type)
+        });
+        class_matches && type_matches
+    }
+
+    fn is_on_time(&self, tx_metrics: &TxMetrics) -> bool {
+        self.max_latency
+            .is_none_or(|max_latency| tx_metrics.latency <= max_latency)
+    }
+}
+
+/// Compliance with an agreement in an output interval. The worst latency is the highest
+/// latency of the delivered transfers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SlaRecord {
+    pub sla_id: u32,
+    pub attempted: u64,
+    pub delivered: u64,
+    pub on_time: u64,
+    pub worst_latency: u64,
+    pub delivery_ratio: f64,
+    pub violated: bool,
+}
+
+/// Checks the transfers against the agreements and evaluates the compliance in every output
+/// interval.
+#[derive(Clone, Debug)]
+pub struct SlaMonitor {
+    slas: Vec<SlaSettings>,
+    records: Vec<SlaRecord>,
+    violations: u64,
+}
+
+impl SlaMonitor {
+    pub fn new(slas: &[SlaSettings]) -> Self {
+        for sla in slas.iter() {
+            let valid_ratio = sla
+                .min_delivery_ratio
+                .is_none_or(|ratio| (0.0..=1.0).contains(&ratio));
+            if !valid_ratio || (sla.max_latency.is_none() && sla.min_delivery_ratio.is_none()) {
+                error!("Invalid settings of the SLA {:?}", sla);
+                panic!(
+                    "SLA {} needs a max_latency or a min_delivery_ratio within 0 and 1",
+                    sla.name
+                );
+            }
+        }
+        let records = (0..slas.len())
+            .map(|sla_id| SlaRecord {
+                sla_id: sla_id as u32,
+                ..Default::default()
+            })
+            .collect();
+        Self {
+            slas: slas.to_vec(),
+            records,
+            violations: 0,
+        }
+    }
+
+    /// Output intervals in which an agreement was violated, over all the agreements.
+    pub fn violations(&self) -> u64 {
+        self.violations
+    }
+
+    pub fn record(&mut self, payload: &DPayload, tx_metrics: &TxMetrics) {
+        for (sla, record) in self.slas.iter().zip(self.records.iter_mut()) {
+            if !sla.covers(payload) {
+                continue;
+            }
+            record.attempted += 1;
+            if tx_metrics.tx_status != TxStatus::Ok {
+                continue;
+            }
+            record.delivered += 1;
+            record.worst_latency = record.worst_latency.max(tx_metrics.latency.as_u64());
+            if sla.is_on_time(tx_metrics) {
+                record.on_time += 1;
+            }
+        }
+    }
+
+    /// Evaluates the agreements with transfers in the interval ending at the step and starts
+    /// the next interval. Violations are logged with the counts that caused them.
+    pub fn finish_interval(&mut self, step: u64) -> Vec<SlaRecord> {
+        let mut finished = Vec::new();
+        for (sla, record) in self.slas.iter().zip(self.records.iter_mut()) {
+            if record.attempted == 0 {
+                continue;
+            }
+            record.delivery_ratio = record.on_time as f64 / record.attempted as f64;
+            record.violated = record.delivery_ratio < sla.min_delivery_ratio.unwrap_or(1.0);
+            if record.violated {
+                self.violations += 1;
+                warn!(
+                    "SLA {} violated at step {}: {} of {} transfers delivered in time, {} delivered, worst latency {} ms",
+                    sla.name,
+                    step,
+                    record.on_time,
+                    record.attempted,
+                    record.delivered,
+                    record.worst_latency
+                );
+            }
+            finished.push(*record);
+            *record = SlaRecord {
+                sla_id: record.sla_id,
+                ..Default::default()
+            };
+        }
+        finished
+    }
+}
@@ -0,0 +1,142 @@
+use crate::device::duty::SleepPolicy;
+use crate::net::message::DPayload;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+
+/// Running totals of the payloads addressed to an agent with a duty-cycled radio. Payloads are
+/// reached when they are delivered while the agent is awake or released from the buffer when it
+/// wakes up.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reachability {
+    pub addressed: u64,
+    pub reached: u64,
+    pub buffered: u64,
+    pub dropped: u64,
+}
+
+impl Reachability {
+    pub fn ratio(&self) -> f32 {
+        match self.addressed {
+            0 => 1.0,
+            addressed => self.reached as f32 / addressed as f32,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Sleeper {
+    policy: SleepPolicy,
+    buffer: Vec<DPayload>,
+    reachability: Reachability,
+}
+
+/// Keeps track of the agents with a duty-cycled radio. Payloads sent to the sleeping agents are
+/// held here until the agents wake up, or dropped. Agents that are not registered are always
+/// awake.
+#[derive(Clone, Debug, Default)]
+pub struct SleepRegister {
+    sleepers: HashMap<AgentId, Sleeper>,
+}
+
+impl SleepRegister {
+    pub fn register(&mut self, agent_id: AgentId, policy: SleepPolicy) {
+        self.sleepers.entry(agent_id).or_insert(Sleeper {
+            policy,
+            buffer: Vec::new(),
+            reachability: Reachability::default(),
+        });
+    }
+
+    /// Drops the payloads buffered for the agent when it is switched off.
+    pub fn switch_off(&mut self, agent_id: AgentId) {
+        if let Some(sleeper) = self.sleepers.get_mut(&agent_id) {
+            sleeper.reachability.dropped += sleeper.buffer.len() as u64;
+            sleeper.buffer.clear();
+        }
+    }
+
+    pub fn is_awake(&self, agent_id: AgentId, now: TimeMS) -> bool {
+        match self.sleepers.get(&agent_id) {
+            Some(sleeper) => sleeper.policy.schedule.is_awake(now),
+            None => true,
+        }
+    }
+
+    /// Returns the payload when the target is awake. Otherwise, the payload is buffered if it
+    /// is sent by the infrastructure and the target buffers its payloads, or dropped.
+    pub fn deliver(
+        &mut self,
+        target: AgentId,
+        payload: DPayload,
+        now: TimeMS,
+        from_infrastructure: bool,
+    ) -> Option<DPayload> {
+        let sleeper = match self.sleepers.get_mut(&target) {
+            Some(sleeper) => sleeper,
+            None => return Some(payload),
+        };
+        sleeper.reachability.addressed += 1;
+        if sleeper.policy.schedule.is_awake(now) {
+            sleeper.reachability.reached += 1;
+            return Some(payload);
+        }
+        let policy = sleeper.policy;
+        if from_infrastructure && policy.buffering && sleeper.buffer.len() < policy.buffer_size {
+            sleeper.buffer.push(payload);
+            sleeper.reachability.buffered += 1;
+        } else {
+            sleeper.reachability.dropped += 1;
+        }
+        None
+    }
+
+    /// Payloads buffered for the agent while it was asleep.
+    pub fn release(&mut self, agent_id: AgentId) -> Option<Vec<DPayload>> {
+        let sleeper = self.sleepers.get_mut(&agent_id)?;
+        if sleeper.buffer.is_empty() {
+            return None;
+        }
+        sleeper.reachability.reached += sleeper.buffer.len() as u64;
+        Some(std::mem::take(&mut sleeper.buffer))
+    }
+
+    /// Payloads buffered for all the sleeping agents.
+    pub fn buffered(&self) -> impl Iterator<Item = &DPayload> {
+        self.sleepers
+            .values()
+            .flat_map(|sleeper| sleeper.buffer.iter())
+    }
+
+    /// Drops the payloads buffered for all the sleeping agents and returns their number.
+    pub fn drop_buffers(&mut self) -> usize {
+        let mut dropped = 0;
+        for sleeper in self.sleepers.values_mut() {
+            dropped += sleeper.buffer.len();
+            sleeper.reachability.dropped += sleeper.buffer.len() as u64;
+            sleeper.buffer.clear();
+        }
+        dropped
+    }
+
+    pub fn reachability_of(&self, agent_id: AgentId) -> Option<Reachability> {
+        self.sleepers
+            .get(&agent_id)
+            .map(|sleeper| sleeper.reachability)
+    }
+
+    /// Reachability of all the agents with a duty-cycled radio.
+    pub fn total_reachability(&self) -> Option<Reachability> {
+        if self.sleepers.is_empty() {
+            return None;
+        }
+        let mut total = Reachability::default();
+        for sleeper in self.sleepers.values() {
+            total.addressed += sleeper.reachability.addressed;
+            total.reached += sleeper.reachability.reached;
+            total.buffered += sleeper.reachability.buffered;
+            total.dropped += sleeper.reachability.dropped;
+        }
+        Some(total)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::net::message::{DPayload, DataType};
+use disolv_core::hashbrown::HashMap;
+
+/// Volume of the data of a type sent in an output interval. The raw bytes are the sizes before
+/// the data was compressed or aggregated, the sent bytes are the sizes seen by the network.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DataVolume {
+    pub data_type: DataType,
+    pub blobs: u64,
+    pub raw_bytes: u64,
+    pub sent_bytes: u64,
+    pub delivered_bytes: u64,
+}
+
+/// Collects the volume of the data sent per data type in an output interval.
+#[derive(Clone, Debug, Default)]
+pub struct VolumeRegister {
+    volumes: HashMap<DataType, DataVolume>,
+}
+
+impl VolumeRegister {
+    pub fn record(&mut self, payload: &DPayload, delivered: bool) {
+        for blob in payload.metadata.data_blobs.iter() {
+            let volume = self
+                .volumes
+                .entry(blob.data_type)
+                .or_insert_with(|| DataVolume {
+                    data_type: blob.data_type,
+                    ..Default::default()
+                });
+            volume.blobs += 1;
+            volume.raw_bytes += blob.uncompressed_size().as_u64();
+            volume.sent_bytes += blob.data_size.as_u64();
+            if delivered {
+                volume.delivered_bytes += blob.data_size.as_u64();
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.volumes.is_empty()
+    }
+
+    /// Volumes of the data types sent in the interval, ordered by the data type.
+    pub fn volumes(&self) -> Vec<DataVolume> {
+        let mut volumes: Vec<DataVolume> = self.volumes.values().copied().collect();
+        volumes.sort_by_key(|volume| volume.data_type.as_int());
+        volumes
+    }
+
+    pub fn reset(&mut self) {
+        self.volumes.clear();
+    }
+}
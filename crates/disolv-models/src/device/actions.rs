@@ -1,18 +1,27 @@
-use crate::device::types::DeviceInfo;
-use crate::net::message::{DPayload, DataBlob, DeviceContent};
+use crate::device::types::{DeviceClass, DeviceInfo};
+use crate::net::message::{DPayload, DataBlob, DataType, DeviceContent};
+use crate::net::metrics::Bytes;
 use crate::net::radio::{Action, ActionType, DActions};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::group::GroupId;
+use disolv_core::hashbrown::HashMap;
+use disolv_core::model::{Model, ModelSettings};
 use log::{debug, error};
+use serde::Deserialize;
 
 /// Prepares a list of data blobs that the payload should consider forwarding.
 ///
 /// # Arguments
 /// * `target_info` - The target agent details
+/// * `target_groups` - The groups the target agent is a member of
 /// * `to_forward` - Payloads that requested to be forwarded
 ///
 /// # Returns
 /// * `Vec<DataBlob>` - List of data blobs that need to be forwarded
 pub fn filter_blobs_to_fwd(
     target_info: &DeviceContent,
+    target_groups: &[GroupId],
     to_forward: &Vec<DPayload>,
 ) -> Vec<DataBlob> {
     let mut blobs_to_forward: Vec<DataBlob> = Vec::new();
@@ -24,7 +33,7 @@ pub fn filter_blobs_to_fwd(
             target_info.device_info.id
         );
         for blob in payload.metadata.data_blobs.iter() {
-            if should_i_forward(blob, &target_info.device_info) {
+            if should_i_forward(blob, &target_info.device_info, target_groups) {
                 blobs_to_forward.push(blob.to_owned());
             } else {
                 debug!(
@@ -67,10 +76,11 @@ pub fn set_actions_before_tx(mut payload: DPayload, actions: &DActions) -> DPayl
 /// # Arguments
 /// * `payload` - The payload to set actions for
 /// * `agent_info` - The agent info of the current agent
+/// * `groups` - The groups the current agent is a member of
 ///
 /// # Returns
 /// * `DPayload` - The payload with the new actions set
-pub fn do_actions(payload: &mut DPayload, agent_content: &DeviceContent) {
+pub fn do_actions(payload: &mut DPayload, agent_content: &DeviceContent, groups: &[GroupId]) {
     payload
         .metadata
         .data_blobs
@@ -78,7 +88,7 @@ pub fn do_actions(payload: &mut DPayload, agent_content: &DeviceContent) {
         .for_each(|blob| match blob.action.action_type {
             ActionType::Consume => {}
             ActionType::Forward => {
-                if am_i_target(&blob.action, &agent_content.device_info) {
+                if am_i_target(&blob.action, &agent_content.device_info, groups) {
                     blob.action.action_type = ActionType::Consume;
                 }
             }
@@ -91,16 +101,22 @@ pub fn do_actions(payload: &mut DPayload, agent_content: &DeviceContent) {
 /// # Arguments
 /// * `action` - The action to check
 /// * `agent_info` - The agent info of the current agent
+/// * `groups` - The groups the current agent is a member of
 ///
 /// # Returns
 /// * `bool` - True if the current agent is the intended target, false otherwise
-pub(crate) fn am_i_target(action: &Action, agent_info: &DeviceInfo) -> bool {
-    // Order of precedence: Agent -> Class -> Kind
+pub(crate) fn am_i_target(action: &Action, agent_info: &DeviceInfo, groups: &[GroupId]) -> bool {
+    // Order of precedence: Agent -> Group -> Class -> Kind
     if let Some(target_agent) = action.to_agent {
         if target_agent == agent_info.id {
             return true;
         }
     }
+    if let Some(target_group) = action.to_group {
+        if groups.contains(&target_group) {
+            return true;
+        }
+    }
     if let Some(target_class) = action.to_class {
         if target_class == agent_info.device_class {
             return true;
@@ -129,6 +145,9 @@ fn assign_actions(data_blob: &mut DataBlob, new_action: &Action) {
             if let Some(target_agent) = new_action.to_agent {
                 data_blob.action.to_agent = Some(target_agent);
             }
+            if let Some(target_group) = new_action.to_group {
+                data_blob.action.to_group = Some(target_group);
+            }
             if let Some(target_class) = new_action.to_class {
                 data_blob.action.to_class = Some(target_class);
             }
@@ -145,10 +164,15 @@ fn assign_actions(data_blob: &mut DataBlob, new_action: &Action) {
 /// # Arguments
 /// * `blob` - The data blob to check
 /// * `target_info` - The agent info of the target agent
+/// * `target_groups` - The groups the target agent is a member of
 ///
 /// # Returns
 /// * `bool` - True if the current agent should forward the data blob, false otherwise
-fn should_i_forward(blob: &DataBlob, target_info: &DeviceInfo) -> bool {
+pub(crate) fn should_i_forward(
+    blob: &DataBlob,
+    target_info: &DeviceInfo,
+    target_groups: &[GroupId],
+) -> bool {
     if blob.action.action_type == ActionType::Consume {
         error!("This should have been consumed by now");
         panic!("This should have been consumed by now");
@@ -158,6 +182,11 @@ fn should_i_forward(blob: &DataBlob, target_info: &DeviceInfo) -> bool {
             return true;
         }
     }
+    if let Some(target_group) = blob.action.to_group {
+        if target_groups.contains(&target_group) {
+            return true;
+        }
+    }
     if let Some(class) = blob.action.to_class {
         if class == target_info.device_class {
             return true;
@@ -170,3 +199,365 @@ fn should_i_forward(blob: &DataBlob, target_info: &DeviceInfo) -> bool {
     }
     false
 }
+
+/// Point in the message handling at which a pipeline runs. Receive pipelines run on the blobs of
+/// every received payload before the actions instructed by the sender are performed. Forward
+/// pipelines run on the blobs forwarded to every target.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStage {
+    Receive,
+    Forward,
+}
+
+/// Settings of a pipeline step. The step is selected by the name and uses the settings below:
+/// * `filter` keeps the blobs sent by `from_class` (receive), forwarded to `to_class` (forward)
+///   and not larger than `max_size`, whichever are given.
+/// * `duplicate` keeps a copy of the received blobs to be forwarded to `to_class`.
+/// * `compress` scales the size of the blobs by `ratio`.
+/// * `aggregate` merges the blobs forwarded to a target into one blob, which is the size of the
+///   largest blob plus `ratio` times the size of the others.
+/// * `delay` holds the forwarded blobs for `delay` before they are sent to the same target.
+/// * `drop_if_stale` drops the blobs created more than `max_age` ago.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct PipelineStepSettings {
+    pub name: String,
+    pub from_class: Option<DeviceClass>,
+    pub to_class: Option<DeviceClass>,
+    pub max_size: Option<Bytes>,
+    pub ratio: Option<f32>,
+    pub delay: Option<TimeMS>,
+    pub max_age: Option<TimeMS>,
+}
+
+impl ModelSettings for PipelineStepSettings {}
+
+/// Sequence of steps run on the blobs of a data type at a stage.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PipelineSettings {
+    pub data_type: DataType,
+    pub stage: PipelineStage,
+    pub steps: Vec<PipelineStepSettings>,
+}
+
+/// What is known about the blobs when a pipeline runs. The sender class is known when the
+/// blobs are received, the target when they are forwarded.
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineContext {
+    pub now: TimeMS,
+    pub from_class: Option<DeviceClass>,
+    pub to_class: Option<DeviceClass>,
+    pub target: Option<AgentId>,
+}
+
+#[derive(Clone, Debug)]
+pub enum PipelineStep {
+    Filter(FilterStep),
+    Duplicate(DeviceClass),
+    Compress(f32),
+    Aggregate(f32),
+    Delay(DelayStep),
+    DropIfStale(TimeMS),
+}
+
+impl Model for PipelineStep {
+    type Settings = PipelineStepSettings;
+
+    fn with_settings(settings: &PipelineStepSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "filter" => PipelineStep::Filter(FilterStep {
+                from_class: settings.from_class,
+                to_class: settings.to_class,
+                max_size: settings.max_size,
+            }),
+            "duplicate" => PipelineStep::Duplicate(
+                settings
+                    .to_class
+                    .expect("Duplicate step requires a target class"),
+            ),
+            "compress" => {
+                let ratio = settings.ratio.expect("Compress step requires a ratio");
+                if ratio <= 0.0 {
+                    panic!("Compression ratio must be positive.");
+                }
+                PipelineStep::Compress(ratio)
+            }
+            "aggregate" => {
+                let ratio = settings.ratio.expect("Aggregate step requires a ratio");
+                if !(0.0..=1.0).contains(&ratio) {
+                    panic!("Aggregation ratio must be between 0 and 1.");
+                }
+                PipelineStep::Aggregate(ratio)
+            }
+            "delay" => PipelineStep::Delay(DelayStep {
+                delay: settings.delay.expect("Delay step requires a delay"),
+                held: HashMap::new(),
+            }),
+            "drop_if_stale" => PipelineStep::DropIfStale(
+                settings
+                    .max_age
+                    .expect("Drop if stale step requires a maximum age"),
+            ),
+            _ => {
+                error!(
+                    "Only filter, duplicate, compress, aggregate, delay and drop_if_stale steps \
+                    are supported"
+                );
+                panic!("Unsupported pipeline step {}.", settings.name);
+            }
+        }
+    }
+}
+
+impl PipelineStep {
+    fn check_stage(&self, stage: PipelineStage) {
+        let supported = match self {
+            PipelineStep::Filter(filter) => match stage {
+                PipelineStage::Receive => filter.to_class.is_none(),
+                PipelineStage::Forward => filter.from_class.is_none(),
+            },
+            PipelineStep::Duplicate(_) => stage == PipelineStage::Receive,
+            PipelineStep::Aggregate(_) | PipelineStep::Delay(_) => stage == PipelineStage::Forward,
+            PipelineStep::Compress(_) | PipelineStep::DropIfStale(_) => true,
+        };
+        if !supported {
+            error!(
+                "Pipeline step {:?} cannot run at the {:?} stage",
+                self, stage
+            );
+            panic!("Unsupported pipeline step at the {:?} stage.", stage);
+        }
+    }
+
+    fn apply(&mut self, blobs: Vec<DataBlob>, context: &PipelineContext) -> Vec<DataBlob> {
+        match self {
+            PipelineStep::Filter(filter) => blobs
+                .into_iter()
+                .filter(|blob| filter.keeps(blob, context))
+                .collect(),
+            PipelineStep::Duplicate(to_class) => {
+                let mut duplicated = blobs.clone();
+                duplicated.extend(blobs.into_iter().map(|mut blob| {
+                    blob.action = Action::builder()
+                        .action_type(ActionType::Forward)
+                        .to_class(Some(*to_class))
+                        .to_agent(None)
+                        .to_kind(None)
+                        .build();
+                    blob
+                }));
+                duplicated
+            }
+            PipelineStep::Compress(ratio) => blobs
+                .into_iter()
+                .map(|mut blob| {
+                    blob.compress(*ratio);
+                    blob
+                })
+                .collect(),
+            PipelineStep::Aggregate(ratio) => aggregate(blobs, *ratio),
+            PipelineStep::Delay(delay) => delay.hold(blobs, context),
+            PipelineStep::DropIfStale(max_age) => blobs
+                .into_iter()
+                .filter(|blob| {
+                    context
+                        .now
+                        .as_u64()
+                        .saturating_sub(blob.created_at.as_u64())
+                        <= max_age.as_u64()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Merges the blobs into the first of them. The merged blob is the size of the largest blob plus
+/// the ratio of the size of the others, and was created when the oldest blob was created.
+fn aggregate(blobs: Vec<DataBlob>, ratio: f32) -> Vec<DataBlob> {
+    if blobs.len() < 2 {
+        return blobs;
+    }
+    let largest = blobs
+        .iter()
+        .map(|blob| blob.data_size.as_u64())
+        .max()
+        .unwrap_or_default();
+    let total: u64 = blobs.iter().map(|blob| blob.data_size.as_u64()).sum();
+    let extra = ((total - largest) as f64 * ratio as f64).ceil() as u64;
+    let mut merged = blobs[0];
+    merged.data_size = Bytes::new(largest + extra);
+    merged.raw_size = Some(blobs.iter().map(|blob| blob.uncompressed_size()).sum());
+    merged.content_id = None;
+    merged.created_at = blobs
+        .iter()
+        .map(|blob| blob.created_at)
+        .min()
+        .unwrap_or_default();
+    vec![merged]
+}
+
+#[derive(Clone, Debug)]
+pub struct FilterStep {
+    from_class: Option<DeviceClass>,
+    to_class: Option<DeviceClass>,
+    max_size: Option<Bytes>,
+}
+
+impl FilterStep {
+    fn keeps(&self, blob: &DataBlob, context: &PipelineContext) -> bool {
+        if self.from_class.is_some() && self.from_class != context.from_class {
+            return false;
+        }
+        if self.to_class.is_some() && self.to_class != context.to_class {
+            return false;
+        }
+        match self.max_size {
+            Some(max_size) => blob.data_size <= max_size,
+            None => true,
+        }
+    }
+}
+
+/// Holds the forwarded blobs per target until they are due. Blobs held for a target are sent
+/// when the target is selected again after they are due.
+#[derive(Clone, Debug)]
+pub struct DelayStep {
+    delay: TimeMS,
+    held: HashMap<AgentId, Vec<(TimeMS, DataBlob)>>,
+}
+
+impl DelayStep {
+    fn hold(&mut self, blobs: Vec<DataBlob>, context: &PipelineContext) -> Vec<DataBlob> {
+        let target = context.target.expect("Delayed blobs require a target");
+        let held = self.held.entry(target).or_default();
+        let due_at = context.now + self.delay;
+        held.extend(blobs.into_iter().map(|blob| (due_at, blob)));
+        let mut due = Vec::new();
+        held.retain(|(due_at, blob)| match *due_at <= context.now {
+            true => {
+                due.push(*blob);
+                false
+            }
+            false => true,
+        });
+        due
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Pipeline {
+    data_type: DataType,
+    stage: PipelineStage,
+    steps: Vec<PipelineStep>,
+}
+
+/// Pipelines of an agent class. Blobs of data types without a pipeline at a stage pass through
+/// unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct Pipelines {
+    pipelines: Vec<Pipeline>,
+}
+
+impl Pipelines {
+    pub fn new(pipeline_settings: &Option<Vec<PipelineSettings>>) -> Self {
+        let pipeline_settings = match pipeline_settings {
+            Some(settings) => settings,
+            None => return Self::default(),
+        };
+        let pipelines = pipeline_settings
+            .iter()
+            .map(|settings| {
+                let steps: Vec<PipelineStep> = settings
+                    .steps
+                    .iter()
+                    .map(PipelineStep::with_settings)
+                    .collect();
+                steps
+                    .iter()
+                    .for_each(|step| step.check_stage(settings.stage));
+                Pipeline {
+                    data_type: settings.data_type,
+                    stage: settings.stage,
+                    steps,
+                }
+            })
+            .collect();
+        Self { pipelines }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Runs the receive pipelines on the blobs of a received payload and updates its totals.
+    pub fn on_receive(&mut self, payload: &mut DPayload, now: TimeMS) {
+        if !self.has_stage(PipelineStage::Receive) {
+            return;
+        }
+        let context = PipelineContext {
+            now,
+            from_class: Some(payload.agent_state.device_info.device_class),
+            to_class: None,
+            target: None,
+        };
+        let blobs = std::mem::take(&mut payload.metadata.data_blobs);
+        payload.metadata.data_blobs = self.run(PipelineStage::Receive, blobs, &context);
+        payload.metadata.total_size = payload
+            .metadata
+            .data_blobs
+            .iter()
+            .map(|blob| blob.data_size)
+            .sum();
+        payload.metadata.total_count = payload.metadata.data_blobs.len() as u32;
+    }
+
+    /// Runs the forward pipelines on the blobs forwarded to the target. Delayed blobs that are
+    /// due are included even if there is nothing else to forward.
+    pub fn on_forward(
+        &mut self,
+        blobs: Vec<DataBlob>,
+        to_class: DeviceClass,
+        target: AgentId,
+        now: TimeMS,
+    ) -> Vec<DataBlob> {
+        if !self.has_stage(PipelineStage::Forward) {
+            return blobs;
+        }
+        let context = PipelineContext {
+            now,
+            from_class: None,
+            to_class: Some(to_class),
+            target: Some(target),
+        };
+        self.run(PipelineStage::Forward, blobs, &context)
+    }
+
+    fn has_stage(&self, stage: PipelineStage) -> bool {
+        self.pipelines
+            .iter()
+            .any(|pipeline| pipeline.stage == stage)
+    }
+
+    fn run(
+        &mut self,
+        stage: PipelineStage,
+        mut blobs: Vec<DataBlob>,
+        context: &PipelineContext,
+    ) -> Vec<DataBlob> {
+        for pipeline in self
+            .pipelines
+            .iter_mut()
+            .filter(|pipeline| pipeline.stage == stage)
+        {
+            let (mut selected, others): (Vec<DataBlob>, Vec<DataBlob>) = blobs
+                .into_iter()
+                .partition(|blob| blob.data_type == pipeline.data_type);
+            for step in pipeline.steps.iter_mut() {
+                selected = step.apply(selected, context);
+            }
+            blobs = others;
+            blobs.append(&mut selected);
+        }
+        blobs
+    }
+}
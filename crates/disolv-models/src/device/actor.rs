@@ -22,6 +22,7 @@ impl Actor {
                 .to_kind(action_setting.to_kind)
                 .to_class(action_setting.to_class)
                 .to_agent(action_setting.to_agent)
+                .to_group(action_setting.to_group)
                 .build();
 
             if let Some(class_actions) = actions.iter_mut().find(|x| x.0 == action_setting.target) {
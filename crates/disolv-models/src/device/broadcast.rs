@@ -0,0 +1,86 @@
+use crate::device::types::DeviceClass;
+use log::error;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the broadcast of an agent class to a target class. A single payload is sent per
+/// step to all the targets within the `coverage`, using the slice once. Targets within the
+/// `reliable_range` always receive the payload, and the probability of the reception falls
+/// linearly to zero at the edge of the coverage. Without a reliable range, all the covered
+/// targets receive the payload.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct BroadcastSettings {
+    pub target_class: DeviceClass,
+    pub coverage: f32,
+    pub reliable_range: Option<f32>,
+}
+
+/// Reception of a broadcast payload by the targets in the coverage.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BroadcastReception {
+    pub covered: u32,
+    pub received: u32,
+}
+
+impl BroadcastReception {
+    pub fn reception_ratio(&self) -> f64 {
+        match self.covered {
+            0 => 0.0,
+            _ => self.received as f64 / self.covered as f64,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Broadcaster {
+    pub target_class: DeviceClass,
+    coverage: f32,
+    reliable_range: f32,
+    rng: Pcg64Mcg,
+}
+
+impl Broadcaster {
+    pub fn new(settings: &BroadcastSettings, seed: u64) -> Self {
+        if settings.coverage <= 0.0 {
+            error!("Broadcast coverage must be positive");
+            panic!("Invalid broadcast coverage {}.", settings.coverage);
+        }
+        let reliable_range = settings.reliable_range.unwrap_or(settings.coverage);
+        if !(0.0..=settings.coverage).contains(&reliable_range) {
+            error!("Broadcast reliable range must be within the coverage");
+            panic!("Invalid broadcast reliable range {}.", reliable_range);
+        }
+        Self {
+            target_class: settings.target_class,
+            coverage: settings.coverage,
+            reliable_range,
+            rng: Pcg64Mcg::new(seed as u128),
+        }
+    }
+
+    pub fn covers(&self, distance: f32) -> bool {
+        distance <= self.coverage
+    }
+
+    pub fn reception_probability(&self, distance: f32) -> f64 {
+        if distance <= self.reliable_range {
+            return 1.0;
+        }
+        if distance > self.coverage {
+            return 0.0;
+        }
+        ((self.coverage - distance) / (self.coverage - self.reliable_range)) as f64
+    }
+
+    /// Draws whether a target at the distance receives the broadcast.
+    pub fn receives(&mut self, distance: f32) -> bool {
+        let probability = self.reception_probability(distance);
+        match probability {
+            p if p >= 1.0 => true,
+            p if p <= 0.0 => false,
+            p => self.rng.gen_bool(p),
+        }
+    }
+}
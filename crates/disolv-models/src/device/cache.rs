@@ -0,0 +1,243 @@
+use crate::device::hardware::{StorageSettings, StorageType};
+use crate::net::message::{ContentId, DPayload, PayloadInfo};
+use crate::net::metrics::Bytes;
+use disolv_core::metrics::{Feasibility, Resource};
+use disolv_core::model::{Model, ModelSettings};
+use log::{debug, error};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// Settings of the content cache of an agent. The memory budget of the cache is described with
+/// the storage settings of the agent hardware.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CacheSettings {
+    pub name: String,
+    pub storage: StorageSettings,
+}
+
+impl ModelSettings for CacheSettings {}
+
+/// Cache statistics of the current time step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheStats {
+    pub requests: u64,
+    pub hits: u64,
+    pub cached_bytes: Bytes,
+}
+
+#[derive(Clone, Debug)]
+pub enum ContentCache {
+    Lru(LruCache),
+}
+
+impl Model for ContentCache {
+    type Settings = CacheSettings;
+
+    fn with_settings(settings: &CacheSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "lru" => ContentCache::Lru(LruCache::new(settings)),
+            _ => {
+                error!("Only LRU content cache is supported.");
+                panic!("Unsupported content cache {}.", settings.name);
+            }
+        }
+    }
+}
+
+impl ContentCache {
+    /// Serves the content requested in the payload from the cache. Blobs of the cached content
+    /// are removed from the payload so that they are not forwarded, and the content of the
+    /// remaining blobs is added to the cache.
+    pub fn serve(&mut self, payload: &mut DPayload) {
+        match self {
+            ContentCache::Lru(cache) => cache.serve(payload),
+        }
+    }
+
+    /// Returns the statistics of the current step and resets the counters.
+    pub fn take_stats(&mut self) -> CacheStats {
+        match self {
+            ContentCache::Lru(cache) => cache.take_stats(),
+        }
+    }
+}
+
+/// A cache that evicts the least recently requested content when the memory budget is exceeded.
+#[derive(Clone, Debug)]
+pub struct LruCache {
+    storage: StorageType,
+    entries: VecDeque<(ContentId, Bytes)>,
+    stats: CacheStats,
+}
+
+impl LruCache {
+    fn new(settings: &CacheSettings) -> Self {
+        Self {
+            storage: StorageType::with_settings(&settings.storage),
+            entries: VecDeque::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn serve(&mut self, payload: &mut DPayload) {
+        let mut served = Vec::new();
+        for (idx, blob) in payload.metadata.data_blobs.iter().enumerate() {
+            let content_id = match blob.content_id {
+                Some(content_id) => content_id,
+                None => continue,
+            };
+            self.stats.requests += 1;
+            if self.touch(content_id) {
+                self.stats.hits += 1;
+                served.push(idx);
+            } else {
+                self.insert(content_id, blob.data_size);
+            }
+        }
+        if served.is_empty() {
+            return;
+        }
+        debug!(
+            "Served {} blobs of payload {} from the cache",
+            served.len(),
+            payload.metadata.id
+        );
+        for idx in served.into_iter().rev() {
+            let blob = payload.metadata.data_blobs.remove(idx);
+            payload.metadata.total_size -= blob.data_size;
+            payload.metadata.total_count -= 1;
+        }
+    }
+
+    /// Moves the content to the most recently used position, if it is cached.
+    fn touch(&mut self, content_id: ContentId) -> bool {
+        match self.entries.iter().position(|(id, _)| *id == content_id) {
+            Some(position) => {
+                let entry = self
+                    .entries
+                    .remove(position)
+                    .expect("Cache entry must exist");
+                self.entries.push_back(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, content_id: ContentId, size: Bytes) {
+        let to_store = PayloadInfo {
+            total_size: size,
+            ..Default::default()
+        };
+        loop {
+            if let Feasibility::Feasible(_) = self.storage.consume(&to_store) {
+                self.entries.push_back((content_id, size));
+                return;
+            }
+            match self.entries.pop_front() {
+                Some((_, evicted_size)) => self.storage.release(evicted_size),
+                // Content larger than the memory budget is never cached.
+                None => return,
+            }
+        }
+    }
+
+    fn take_stats(&mut self) -> CacheStats {
+        self.stats.cached_bytes = self.storage.available();
+        std::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::message::{DataBlob, DeviceContent};
+    use crate::net::radio::Action;
+
+    fn cache(limit: u64) -> LruCache {
+        LruCache::new(&CacheSettings {
+            name: "lru".to_string(),
+            storage: StorageSettings {
+                variant: "constant".to_string(),
+                limit: Bytes::new(limit),
+            },
+        })
+    }
+
+    fn payload(contents: &[(Option<ContentId>, u64)]) -> DPayload {
+        let data_blobs: Vec<DataBlob> = contents
+            .iter()
+            .map(|(content_id, size)| {
+                DataBlob::builder()
+                    .data_type(Default::default())
+                    .data_size(Bytes::new(*size))
+                    .action(Action::default())
+                    .content_id(*content_id)
+                    .build()
+            })
+            .collect();
+        DPayload {
+            agent_state: DeviceContent::default(),
+            metadata: PayloadInfo {
+                total_size: data_blobs.iter().map(|blob| blob.data_size).sum(),
+                total_count: data_blobs.len() as u32,
+                data_blobs,
+                ..Default::default()
+            },
+            gathered_states: None,
+        }
+    }
+
+    fn cached(cache: &LruCache) -> Vec<ContentId> {
+        cache.entries.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn test_hits_are_served_and_misses_are_forwarded() {
+        let mut cache = cache(1000);
+        let mut first = payload(&[(Some(1), 400), (None, 100)]);
+        cache.serve(&mut first);
+        assert_eq!(first.metadata.data_blobs.len(), 2);
+        assert_eq!(first.metadata.total_size, Bytes::new(500));
+
+        let mut second = payload(&[(Some(1), 400), (Some(2), 300)]);
+        cache.serve(&mut second);
+        assert_eq!(second.metadata.data_blobs.len(), 1);
+        assert_eq!(second.metadata.data_blobs[0].content_id, Some(2));
+        assert_eq!(second.metadata.total_size, Bytes::new(300));
+        assert_eq!(second.metadata.total_count, 1);
+
+        let stats = cache.take_stats();
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.cached_bytes, Bytes::new(700));
+        assert_eq!(cache.take_stats().requests, 0);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted_under_budget() {
+        let mut cache = cache(1000);
+        cache.serve(&mut payload(&[(Some(1), 400), (Some(2), 400)]));
+        cache.serve(&mut payload(&[(Some(1), 400)]));
+        assert_eq!(cached(&cache), vec![2, 1]);
+
+        cache.serve(&mut payload(&[(Some(3), 400)]));
+        assert_eq!(cached(&cache), vec![1, 3]);
+        assert_eq!(cache.storage.available(), Bytes::new(800));
+
+        cache.serve(&mut payload(&[(Some(4), 1000)]));
+        assert_eq!(cached(&cache), vec![4]);
+        assert_eq!(cache.storage.available(), Bytes::new(1000));
+    }
+
+    #[test]
+    fn test_content_over_budget_is_not_cached() {
+        let mut cache = cache(1000);
+        cache.serve(&mut payload(&[(Some(1), 400)]));
+        let mut large = payload(&[(Some(2), 2000)]);
+        cache.serve(&mut large);
+        assert_eq!(large.metadata.data_blobs.len(), 1);
+        assert!(cached(&cache).is_empty());
+        assert_eq!(cache.storage.available(), Bytes::new(0));
+    }
+}
@@ -0,0 +1,89 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use log::error;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the steps in which the agents of a class act. With the `every` policy, all the
+/// agents of the class act in every `interval`-th step starting at the `offset`, e.g. RSUs that
+/// act in every fifth step. With `round_robin`, the agents take turns so that a share of them
+/// acts in each step, and with `probabilistic` every agent acts in a step with the
+/// `probability`. Agents of classes without a cadence act in every step.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct CadenceSettings {
+    pub policy: String,
+    pub interval: Option<u64>,
+    pub offset: Option<u64>,
+    pub probability: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CadencePolicy {
+    Every { interval: u64, offset: u64 },
+    RoundRobin { interval: u64 },
+    Probabilistic(f64),
+}
+
+#[derive(Clone, Debug)]
+pub struct Cadence {
+    policy: CadencePolicy,
+    step_size: TimeMS,
+    rng: Pcg64Mcg,
+}
+
+impl Cadence {
+    pub fn new(settings: &CadenceSettings, step_size: TimeMS, seed: u64) -> Self {
+        let interval = settings.interval.unwrap_or(1);
+        if interval == 0 {
+            error!("Cadence interval must be at least one step");
+            panic!("Invalid cadence interval {}.", interval);
+        }
+        let policy = match settings.policy.to_lowercase().as_str() {
+            "every" => {
+                let offset = settings.offset.unwrap_or_default();
+                if offset >= interval {
+                    error!("Cadence offset must be less than the interval");
+                    panic!("Invalid cadence offset {}.", offset);
+                }
+                CadencePolicy::Every { interval, offset }
+            }
+            "round_robin" => CadencePolicy::RoundRobin { interval },
+            "probabilistic" => {
+                let probability = settings.probability.unwrap_or(1.0);
+                if !(0.0..=1.0).contains(&probability) {
+                    error!("Cadence probability must be between 0 and 1");
+                    panic!("Invalid cadence probability {}.", probability);
+                }
+                CadencePolicy::Probabilistic(probability)
+            }
+            _ => {
+                error!("Only every, round_robin and probabilistic cadences are supported");
+                panic!("Unsupported cadence policy {}.", settings.policy);
+            }
+        };
+        Self {
+            policy,
+            step_size,
+            rng: Pcg64Mcg::new(seed as u128),
+        }
+    }
+
+    pub fn policy(&self) -> CadencePolicy {
+        self.policy
+    }
+
+    /// Checks if the agent acts in the step. Round robin turns follow the agent ids, so agents
+    /// with consecutive ids act in consecutive steps.
+    pub fn acts(&mut self, agent_id: AgentId, step: TimeMS) -> bool {
+        let step_index = step.as_u64() / self.step_size.as_u64().max(1);
+        match self.policy {
+            CadencePolicy::Every { interval, offset } => step_index % interval == offset,
+            CadencePolicy::RoundRobin { interval } => {
+                step_index % interval == agent_id.as_u64() % interval
+            }
+            CadencePolicy::Probabilistic(probability) => self.rng.gen_bool(probability),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::bucket::charging::ChargingStations;
+use crate::device::metrics::Energy;
+use crate::device::mobility::Point2D;
+use crate::net::message::{DataBlob, DataType};
+use crate::net::metrics::Bytes;
+use crate::net::radio::Action;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::state::{MachineState, StateChange, StateMachine};
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the battery of an electric vehicle. The battery is drained by `drive_drain` in
+/// every step and by the energy that the energy model measures for every transfer. The vehicle
+/// seeks a charging station when its level falls below the `seek_below` share of the
+/// `capacity`, and leaves the station when the level reaches the `leave_above` share, by
+/// default when the battery is full. The reservation requests sent by a seeking vehicle are
+/// `request_size` bytes.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct BatterySettings {
+    pub capacity: Energy,
+    pub initial_level: Option<f64>,
+    pub drive_drain: Option<Energy>,
+    pub seek_below: f64,
+    pub leave_above: Option<f64>,
+    pub request_size: Option<Bytes>,
+}
+
+/// Settings of an infrastructure agent acting as a charging station with `slots` chargers,
+/// each charging a vehicle by `charge_rate` in every step.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct StationSettings {
+    pub slots: u32,
+    pub charge_rate: Energy,
+}
+
+#[derive(Clone, Default, Copy, Debug, PartialEq, Eq)]
+pub enum ChargeState {
+    #[default]
+    Driving,
+    Seeking,
+    Charging,
+}
+
+impl MachineState for ChargeState {
+    const MACHINE: u32 = 1;
+
+    fn as_int(&self) -> u32 {
+        match self {
+            ChargeState::Driving => 0,
+            ChargeState::Seeking => 1,
+            ChargeState::Charging => 2,
+        }
+    }
+}
+
+/// Level of the battery with the shares of the capacity at which the vehicle seeks a station
+/// and leaves it.
+#[derive(Clone, Copy, Debug)]
+pub struct BatteryLevel {
+    level: u64,
+    capacity: u64,
+    seek_below: f64,
+    leave_above: f64,
+}
+
+impl BatteryLevel {
+    fn share(&self) -> f64 {
+        match self.capacity {
+            0 => 0.0,
+            capacity => self.level as f64 / capacity as f64,
+        }
+    }
+
+    fn needs_charge(&self) -> bool {
+        self.share() < self.seek_below
+    }
+
+    fn is_charged(&self) -> bool {
+        self.share() >= self.leave_above
+    }
+}
+
+/// Charging state of a vehicle. A seeking vehicle starts charging once a station reserves a
+/// slot for it, and seeks again when it leaves a station before it is charged.
+pub type ChargeMachine = StateMachine<ChargeState, BatteryLevel>;
+
+pub fn charge_machine() -> ChargeMachine {
+    ChargeMachine::new(ChargeState::Driving)
+        .guarded(
+            ChargeState::Driving,
+            ChargeState::Seeking,
+            BatteryLevel::needs_charge,
+        )
+        .transition(ChargeState::Seeking, ChargeState::Charging)
+        .guarded(
+            ChargeState::Charging,
+            ChargeState::Driving,
+            BatteryLevel::is_charged,
+        )
+        .transition(ChargeState::Charging, ChargeState::Seeking)
+}
+
+/// Battery of an electric vehicle and the station it is parked at while charging.
+#[derive(Clone, Debug)]
+pub struct Battery {
+    level: BatteryLevel,
+    machine: ChargeMachine,
+    drive_drain: Energy,
+    request_size: Bytes,
+    station: Option<(AgentId, Point2D)>,
+    charged: Energy,
+}
+
+impl Battery {
+    pub fn new(settings: &BatterySettings) -> Self {
+        let leave_above = settings.leave_above.unwrap_or(1.0);
+        let initial_level = settings.initial_level.unwrap_or(1.0);
+        for share in [initial_level, settings.seek_below, leave_above] {
+            if !(0.0..=1.0).contains(&share) {
+                error!("Battery levels must be shares of the capacity between 0 and 1");
+                panic!("Invalid battery level {}.", share);
+            }
+        }
+        if settings.seek_below >= leave_above {
+            error!("Vehicles must leave the stations above the level at which they seek them");
+            panic!("Invalid battery level {}.", settings.seek_below);
+        }
+        let capacity = settings.capacity.as_u64();
+        Self {
+            level: BatteryLevel {
+                level: (capacity as f64 * initial_level) as u64,
+                capacity,
+                seek_below: settings.seek_below,
+                leave_above,
+            },
+            machine: charge_machine(),
+            drive_drain: settings.drive_drain.unwrap_or_default(),
+            request_size: settings.request_size.unwrap_or(Bytes::new(100)),
+            station: None,
+            charged: Energy::default(),
+        }
+    }
+
+    pub fn state(&self) -> ChargeState {
+        self.machine.state()
+    }
+
+    pub fn level(&self) -> Energy {
+        Energy::new(self.level.level)
+    }
+
+    /// Level of the battery as a share of its capacity.
+    pub fn share(&self) -> f64 {
+        self.level.share()
+    }
+
+    pub fn drain(&mut self, energy: Energy) {
+        self.level.level = self.level.level.saturating_sub(energy.as_u64());
+    }
+
+    /// Charges the vehicle at its station, or drains the battery for driving otherwise. A
+    /// charged vehicle leaves its station, and a seeking vehicle parks at the station that
+    /// reserved a slot for it.
+    ///
+    /// # Arguments
+    /// * `agent_id` - The vehicle with the battery
+    /// * `stations` - The charging stations of the simulation
+    /// * `step` - The current time step
+    pub fn update(&mut self, agent_id: AgentId, stations: &mut ChargingStations, step: TimeMS) {
+        if self.machine.is_in(ChargeState::Charging) {
+            let charge = stations.charge_rate_for(agent_id).as_u64();
+            let charge = charge.min(self.level.capacity - self.level.level);
+            self.level.level += charge;
+            self.charged = self.charged + Energy::new(charge);
+            if self.machine.advance(&mut self.level) {
+                self.release(agent_id, stations, step);
+            }
+            return;
+        }
+        self.drain(self.drive_drain);
+        self.machine.advance(&mut self.level);
+        if self.machine.is_in(ChargeState::Seeking) {
+            if let Some(station) = stations.reservation_of(agent_id) {
+                self.machine.move_to(ChargeState::Charging, &mut self.level);
+                self.station = Some(station);
+            }
+        }
+    }
+
+    /// Leaves the station, or gives up the reservation, before the vehicle is charged, e.g.
+    /// when it is switched off.
+    pub fn leave(&mut self, agent_id: AgentId, stations: &mut ChargingStations, step: TimeMS) {
+        self.machine.move_to(ChargeState::Seeking, &mut self.level);
+        self.release(agent_id, stations, step);
+    }
+
+    fn release(&mut self, agent_id: AgentId, stations: &mut ChargingStations, step: TimeMS) {
+        stations.release(agent_id, self.charged, step);
+        self.station = None;
+        self.charged = Energy::default();
+    }
+
+    /// Position of the station the vehicle is parked at while charging.
+    pub fn parked_at(&self) -> Option<Point2D> {
+        self.station.map(|(_, position)| position)
+    }
+
+    /// Reservation request sent by a seeking vehicle to the stations.
+    pub fn request_blob(&self, now: TimeMS) -> Option<DataBlob> {
+        if !self.machine.is_in(ChargeState::Seeking) {
+            return None;
+        }
+        Some(
+            DataBlob::builder()
+                .data_type(DataType::Reservation)
+                .data_size(self.request_size)
+                .action(Action::default())
+                .created_at(now)
+                .build(),
+        )
+    }
+
+    /// Takes the changes of the charging state since the last call.
+    pub fn take_changes(&mut self) -> Vec<StateChange<ChargeState>> {
+        self.machine.take_changes()
+    }
+}
@@ -1,16 +1,23 @@
 use crate::device::types::DeviceClass;
-use crate::net::message::{DPayload, DataBlob, DataSource, DeviceContent, PayloadInfo};
+use crate::net::message::{ContentId, ContentSettings, DPayload, DataBlob, DataSource, DataType};
+use crate::net::message::{DeviceContent, PayloadInfo};
 use crate::net::radio::{Action, DLink};
 use disolv_core::bucket::TimeMS;
 use disolv_core::model::{Model, ModelSettings};
 use disolv_core::uuid;
 use log::{debug, error};
+use rand::Rng;
+use rand_distr::Zipf;
+use rand_pcg::Pcg64Mcg;
 use serde::Deserialize;
+use std::collections::VecDeque;
 
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct ComposerSettings {
     pub name: String,
     pub source_settings: Vec<DataSource>,
+    pub cache_freshness: Option<TimeMS>,
+    pub cache_size: Option<u32>,
 }
 
 impl ModelSettings for ComposerSettings {}
@@ -19,28 +26,57 @@ impl ModelSettings for ComposerSettings {}
 pub enum Composer {
     Basic(BasicComposer),
     Status(StatusComposer),
+    Cached(CachedComposer),
 }
 
 impl Model for Composer {
     type Settings = ComposerSettings;
 
     fn with_settings(settings: &ComposerSettings) -> Self {
+        Self::new(settings, 0)
+    }
+}
+
+impl Composer {
+    /// Creates the composer with the seed of the content drawn by its data sources.
+    pub fn new(settings: &ComposerSettings, seed: u64) -> Self {
         match settings.name.to_lowercase().as_str() {
-            "basic" => Composer::Basic(BasicComposer::new(settings)),
+            "basic" => Composer::Basic(BasicComposer::new(settings, seed)),
             "status" => Composer::Status(StatusComposer::new(settings)),
+            "cached" => Composer::Cached(CachedComposer::new(settings, seed)),
             _ => {
-                error!("Only Basic and Status composers are supported.");
+                error!("Only Basic, Status and Cached composers are supported.");
                 panic!("Unsupported composer type {}.", settings.name);
             }
         }
     }
-}
 
-impl Composer {
-    pub fn compose_payload(&self, target_class: &DeviceClass, content: DeviceContent) -> DPayload {
+    /// Seed of the content drawn by the data sources, 0 for the composers without sources.
+    pub fn content_seed(&self) -> u64 {
+        match self {
+            Composer::Basic(composer) => composer.seed,
+            Composer::Status(_) => 0,
+            Composer::Cached(composer) => composer.composer.seed,
+        }
+    }
+
+    pub fn compose_payload(
+        &mut self,
+        target_class: &DeviceClass,
+        content: DeviceContent,
+    ) -> DPayload {
         match self {
             Composer::Basic(composer) => composer.compose_payload(target_class, content),
             Composer::Status(composer) => composer.compose_payload(target_class, content),
+            Composer::Cached(composer) => composer.compose_payload(target_class, content),
+        }
+    }
+
+    /// Holds back the data that could not be sent to the target class in this step.
+    pub fn cache_payload(&mut self, target_class: &DeviceClass) {
+        match self {
+            Composer::Basic(_) | Composer::Status(_) => (),
+            Composer::Cached(composer) => composer.cache_payload(target_class),
         }
     }
 
@@ -48,6 +84,33 @@ impl Composer {
         match self {
             Composer::Basic(composer) => composer.update_sources(data_sources),
             Composer::Status(_) => (),
+            Composer::Cached(composer) => composer.composer.update_sources(data_sources),
+        }
+    }
+
+    pub fn update_step(&mut self, step: TimeMS) {
+        match self {
+            Composer::Basic(composer) => composer.update_step(step),
+            Composer::Status(_) => (),
+            Composer::Cached(composer) => composer.composer.update_step(step),
+        }
+    }
+
+    /// Changes the step of the data sources of the data type.
+    pub fn set_source_step(&mut self, data_type: DataType, source_step: TimeMS) {
+        match self {
+            Composer::Basic(composer) => composer.set_source_step(data_type, source_step),
+            Composer::Status(_) => (),
+            Composer::Cached(composer) => composer.composer.set_source_step(data_type, source_step),
+        }
+    }
+
+    /// Scales the generation rates of the data sources with the load intensity.
+    pub fn update_intensity(&mut self, intensity: f64) {
+        match self {
+            Composer::Basic(composer) => composer.update_intensity(intensity),
+            Composer::Status(_) => (),
+            Composer::Cached(composer) => composer.composer.update_intensity(intensity),
         }
     }
 
@@ -60,29 +123,70 @@ impl Composer {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+/// A composer that generates a blob from each data source at every source step. The load
+/// intensity scales the number of blobs generated: each source accumulates the intensity at
+/// every source step and generates a blob for every whole unit accumulated.
+///
+/// Sources with a content catalog draw the content of their blobs from a Zipf distribution
+/// sampled with the seeded generator of the composer.
+#[derive(Clone, Debug)]
 pub struct BasicComposer {
     pub data_sources: Vec<DataSource>,
     pub step: TimeMS,
+    pub intensity: f64,
+    credits: Vec<f64>,
+    catalogs: Vec<Option<Zipf<f32>>>,
+    seed: u64,
+    rng: Pcg64Mcg,
 }
 
 impl BasicComposer {
-    pub fn new(composer_settings: &ComposerSettings) -> Self {
+    pub fn new(composer_settings: &ComposerSettings, seed: u64) -> Self {
         Self {
             data_sources: composer_settings.source_settings.to_owned(),
             step: TimeMS::default(),
+            intensity: 1.0,
+            credits: vec![0.0; composer_settings.source_settings.len()],
+            catalogs: Self::catalogs_of(&composer_settings.source_settings),
+            seed,
+            rng: Pcg64Mcg::new(seed as u128),
         }
     }
 
+    fn catalogs_of(data_sources: &[DataSource]) -> Vec<Option<Zipf<f32>>> {
+        data_sources
+            .iter()
+            .map(|data_source| data_source.content.as_ref().map(Self::catalog))
+            .collect()
+    }
+
+    fn catalog(content: &ContentSettings) -> Zipf<f32> {
+        Zipf::new(content.catalog_size, content.popularity.unwrap_or(1.0))
+            .expect("Content catalog must not be empty and popularity must be positive")
+    }
+
     pub fn update_sources(&mut self, data_sources: &Vec<DataSource>) {
         self.data_sources = data_sources.to_owned();
+        self.credits = vec![0.0; data_sources.len()];
+        self.catalogs = Self::catalogs_of(data_sources);
     }
 
     pub fn update_step(&mut self, step: TimeMS) {
         self.step = step;
     }
 
-    fn compose_payload(&self, target_class: &DeviceClass, content: DeviceContent) -> DPayload {
+    pub fn update_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    pub fn set_source_step(&mut self, data_type: DataType, source_step: TimeMS) {
+        self.data_sources
+            .iter_mut()
+            .filter(|data_source| data_source.data_type == data_type)
+            .for_each(|data_source| data_source.source_step = source_step);
+    }
+
+    fn compose_payload(&mut self, target_class: &DeviceClass, content: DeviceContent) -> DPayload {
         let payload_info = self.compose_metadata(target_class);
         DPayload::builder()
             .metadata(payload_info)
@@ -91,10 +195,15 @@ impl BasicComposer {
             .build()
     }
 
-    fn compose_metadata(&self, target_class: &DeviceClass) -> PayloadInfo {
+    fn compose_metadata(&mut self, target_class: &DeviceClass) -> PayloadInfo {
         let mut data_blobs = Vec::with_capacity(self.data_sources.len());
         let mut data_count: u32 = 0;
-        for ds_settings in self.data_sources.iter() {
+        for (idx, (ds_settings, catalog)) in self
+            .data_sources
+            .iter()
+            .zip(self.catalogs.iter())
+            .enumerate()
+        {
             if ds_settings.agent_class != *target_class {
                 continue;
             }
@@ -102,13 +211,26 @@ impl BasicComposer {
                 continue;
             }
 
-            let data_blob = DataBlob::builder()
-                .data_type(ds_settings.data_type)
-                .data_size(ds_settings.data_size)
-                .action(Action::default())
-                .build();
-            data_blobs.push(data_blob);
-            data_count += 1;
+            self.credits[idx] += self.intensity;
+            while self.credits[idx] >= 1.0 {
+                self.credits[idx] -= 1.0;
+                let mut data_blob = DataBlob::builder()
+                    .data_type(ds_settings.data_type)
+                    .data_size(ds_settings.data_size)
+                    .action(Action::default())
+                    .created_at(self.step)
+                    .content_id(
+                        catalog
+                            .as_ref()
+                            .map(|zipf| self.rng.sample(zipf) as ContentId),
+                    )
+                    .build();
+                if let Some(ratio) = ds_settings.compression {
+                    data_blob.compress(ratio);
+                }
+                data_blobs.push(data_blob);
+                data_count += 1;
+            }
         }
         let payload_info = PayloadInfo::builder()
             .id(uuid::Uuid::new_v4())
@@ -143,28 +265,225 @@ impl StatusComposer {
     }
 }
 
-// #[derive(Clone, Debug)]
-// pub struct CachedComposer {
-//     pub data_sources: Vec<DataSource>,
-//     pub data_cache: VecDeque<DataBlob>,
-//     pub last_contacted: TimeMS,
-// }
-//
-// impl CachedComposer {
-//     pub fn new(composer_settings: &ComposerSettings) -> Self {
-//         Self {
-//             data_sources: composer_settings.source_settings.to_owned(),
-//             data_cache: VecDeque::new(),
-//             last_contacted: TimeMS::default(),
-//         }
-//     }
-//
-//     fn compose_payload(&self, target_class: &AgentClass, content: DeviceContent) -> DPayload {
-//         let payload_info = self.compose_metadata(target_class);
-//         DPayload::builder()
-//             .metadata(payload_info)
-//             .agent_state(content)
-//             .gathered_states(Some(Vec::new()))
-//             .build()
-//     }
-// }
+/// A composer that caches the data blobs that could not be sent due to the absence of a link
+/// to the target class. Cached blobs are added to the next payload sent to the same class,
+/// irrespective of the agent selected as the target. Blobs older than the freshness cutoff are
+/// discarded and the oldest blobs are discarded when the cache is full.
+#[derive(Clone, Debug)]
+pub struct CachedComposer {
+    pub composer: BasicComposer,
+    pub freshness: Option<TimeMS>,
+    pub cache_size: Option<u32>,
+    pub data_cache: VecDeque<(TimeMS, DeviceClass, DataBlob)>,
+}
+
+impl CachedComposer {
+    pub fn new(composer_settings: &ComposerSettings, seed: u64) -> Self {
+        Self {
+            composer: BasicComposer::new(composer_settings, seed),
+            freshness: composer_settings.cache_freshness,
+            cache_size: composer_settings.cache_size,
+            data_cache: VecDeque::new(),
+        }
+    }
+
+    fn compose_payload(&mut self, target_class: &DeviceClass, content: DeviceContent) -> DPayload {
+        self.discard_stale();
+        let mut payload = self.composer.compose_payload(target_class, content);
+        let mut cached_blobs = Vec::new();
+        self.data_cache.retain(|(_, class, blob)| {
+            if class == target_class {
+                cached_blobs.push(blob.to_owned());
+                return false;
+            }
+            true
+        });
+        debug!(
+            "Adding {} cached blobs to payload {}",
+            cached_blobs.len(),
+            payload.metadata.id
+        );
+        cached_blobs.iter().for_each(|blob| {
+            payload.metadata.total_size += blob.data_size;
+            payload.metadata.total_count += 1;
+        });
+        payload.metadata.data_blobs.append(&mut cached_blobs);
+        payload
+    }
+
+    fn cache_payload(&mut self, target_class: &DeviceClass) {
+        let payload_info = self.composer.compose_metadata(target_class);
+        for blob in payload_info.data_blobs.into_iter() {
+            self.data_cache
+                .push_back((self.composer.step, *target_class, blob));
+        }
+        self.discard_stale();
+        if let Some(cache_size) = self.cache_size {
+            while self.data_cache.len() > cache_size as usize {
+                self.data_cache.pop_front();
+            }
+        }
+    }
+
+    fn discard_stale(&mut self) {
+        let freshness = match self.freshness {
+            Some(freshness) => freshness.as_u64(),
+            None => return,
+        };
+        let step = self.composer.step.as_u64();
+        self.data_cache
+            .retain(|(cached_at, _, _)| step - cached_at.as_u64() <= freshness);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::metrics::Bytes;
+
+    fn composer(seed: u64) -> BasicComposer {
+        let settings = ComposerSettings {
+            name: "basic".to_string(),
+            source_settings: vec![DataSource {
+                data_type: DataType::default(),
+                agent_class: DeviceClass::default(),
+                data_size: Bytes::new(100),
+                source_step: TimeMS::from(100),
+                content: Some(ContentSettings {
+                    catalog_size: 1000,
+                    popularity: Some(1.0),
+                }),
+                compression: None,
+            }],
+            ..Default::default()
+        };
+        BasicComposer::new(&settings, seed)
+    }
+
+    fn contents(composer: &mut BasicComposer) -> Vec<ContentId> {
+        (0..50)
+            .flat_map(|_| {
+                composer
+                    .compose_metadata(&DeviceClass::default())
+                    .data_blobs
+            })
+            .map(|blob| blob.content_id.expect("Blob must have a content"))
+            .collect()
+    }
+
+    #[test]
+    fn test_content_depends_only_on_seed() {
+        let first = contents(&mut composer(7));
+        assert_eq!(first, contents(&mut composer(7)));
+        assert_ne!(first, contents(&mut composer(8)));
+        assert!(first.iter().all(|id| (1..=1000).contains(id)));
+    }
+
+    fn blob_count_at(composer: &mut BasicComposer, step: u64) -> usize {
+        composer.update_step(TimeMS::from(step));
+        composer
+            .compose_metadata(&DeviceClass::default())
+            .data_blobs
+            .len()
+    }
+
+    #[test]
+    fn test_reloaded_source_step_applies_to_basic_composer() {
+        let mut composer = composer(7);
+        assert_eq!(blob_count_at(&mut composer, 100), 1);
+        assert_eq!(blob_count_at(&mut composer, 200), 1);
+
+        composer.set_source_step(DataType::default(), TimeMS::from(500));
+        assert_eq!(blob_count_at(&mut composer, 200), 0);
+        assert_eq!(blob_count_at(&mut composer, 500), 1);
+    }
+
+    #[test]
+    fn test_intensity_scales_blob_count() {
+        let mut composer = composer(7);
+        composer.update_intensity(0.5);
+        let halved: Vec<usize> = (1..=4)
+            .map(|step| blob_count_at(&mut composer, step * 100))
+            .collect();
+        assert_eq!(halved, vec![0, 1, 0, 1]);
+
+        composer.update_intensity(2.0);
+        assert_eq!(blob_count_at(&mut composer, 500), 2);
+        assert_eq!(blob_count_at(&mut composer, 550), 0);
+    }
+
+    fn cached_composer() -> CachedComposer {
+        let settings = ComposerSettings {
+            name: "cached".to_string(),
+            source_settings: vec![DataSource {
+                data_type: DataType::default(),
+                agent_class: DeviceClass::RSU5G,
+                data_size: Bytes::new(100),
+                source_step: TimeMS::from(100),
+                content: None,
+                compression: None,
+            }],
+            cache_freshness: Some(TimeMS::from(200)),
+            cache_size: Some(2),
+        };
+        CachedComposer::new(&settings, 7)
+    }
+
+    fn cache_at(composer: &mut CachedComposer, step: u64) {
+        composer.composer.update_step(TimeMS::from(step));
+        composer.cache_payload(&DeviceClass::RSU5G);
+    }
+
+    fn compose_at(composer: &mut CachedComposer, step: u64, class: DeviceClass) -> PayloadInfo {
+        composer.composer.update_step(TimeMS::from(step));
+        composer
+            .compose_payload(&class, DeviceContent::default())
+            .metadata
+    }
+
+    #[test]
+    fn test_cached_blobs_join_the_next_payload_to_the_class() {
+        let mut composer = cached_composer();
+        cache_at(&mut composer, 100);
+
+        let other_class = compose_at(&mut composer, 200, DeviceClass::BaseStation5G);
+        assert_eq!(other_class.total_count, 0);
+        assert_eq!(composer.data_cache.len(), 1);
+
+        let same_class = compose_at(&mut composer, 300, DeviceClass::RSU5G);
+        assert_eq!(same_class.total_count, 2);
+        assert_eq!(same_class.total_size, Bytes::new(200));
+        let created: Vec<TimeMS> = same_class
+            .data_blobs
+            .iter()
+            .map(|blob| blob.created_at)
+            .collect();
+        assert_eq!(created, vec![TimeMS::from(300), TimeMS::from(100)]);
+        assert!(composer.data_cache.is_empty());
+    }
+
+    #[test]
+    fn test_stale_blobs_are_discarded() {
+        let mut composer = cached_composer();
+        cache_at(&mut composer, 100);
+
+        let payload = compose_at(&mut composer, 400, DeviceClass::RSU5G);
+        assert_eq!(payload.total_count, 1);
+        assert_eq!(payload.data_blobs[0].created_at, TimeMS::from(400));
+        assert!(composer.data_cache.is_empty());
+    }
+
+    #[test]
+    fn test_oldest_blobs_are_dropped_from_a_full_cache() {
+        let mut composer = cached_composer();
+        for step in [100, 200, 300] {
+            cache_at(&mut composer, step);
+        }
+        let cached_at: Vec<TimeMS> = composer
+            .data_cache
+            .iter()
+            .map(|(cached_at, _, _)| *cached_at)
+            .collect();
+        assert_eq!(cached_at, vec![TimeMS::from(200), TimeMS::from(300)]);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::device::types::DeviceClass;
+use crate::net::message::{BlobOrigin, DPayload};
+use crate::net::radio::ActionType;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use log::error;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the duplicate suppression of an agent class. An agent drops the copies of the
+/// blobs it generated or already received within the `memory`, or during the whole run without
+/// it. With a `rebroadcast_probability`, the agent forwards each new blob with the probability
+/// and consumes it otherwise, as in probabilistic flooding.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct DuplicateSettings {
+    pub memory: Option<TimeMS>,
+    pub rebroadcast_probability: Option<f64>,
+}
+
+/// Blobs received by the agents. Blobs without an origin are never duplicates. Of the new blobs
+/// to forward, the rebroadcast ones are forwarded and the withheld ones are consumed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DuplicateCounts {
+    pub received: u64,
+    pub duplicates: u64,
+    pub rebroadcast: u64,
+    pub withheld: u64,
+}
+
+impl DuplicateCounts {
+    pub fn add(&mut self, other: &DuplicateCounts) {
+        self.received += other.received;
+        self.duplicates += other.duplicates;
+        self.rebroadcast += other.rebroadcast;
+        self.withheld += other.withheld;
+    }
+
+    /// Duplicates received for every new blob.
+    pub fn overhead(&self) -> f64 {
+        match self.received - self.duplicates {
+            0 => 0.0,
+            unique => self.duplicates as f64 / unique as f64,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DuplicateFilter {
+    seen: HashMap<BlobOrigin, TimeMS>,
+    memory: Option<TimeMS>,
+    rebroadcast_probability: Option<f64>,
+    rng: Pcg64Mcg,
+    counts: DuplicateCounts,
+}
+
+impl DuplicateFilter {
+    pub fn new(settings: &DuplicateSettings, seed: u64) -> Self {
+        if let Some(probability) = settings.rebroadcast_probability {
+            if !(0.0..=1.0).contains(&probability) {
+                error!("Rebroadcast probability must be between 0 and 1");
+                panic!("Invalid rebroadcast probability {}.", probability);
+            }
+        }
+        Self {
+            seen: HashMap::new(),
+            memory: settings.memory,
+            rebroadcast_probability: settings.rebroadcast_probability,
+            rng: Pcg64Mcg::new(seed as u128),
+            counts: DuplicateCounts::default(),
+        }
+    }
+
+    /// Drops the blobs of the payloads that the agent generated or already received, and
+    /// decides which of the new blobs to forward are rebroadcast.
+    ///
+    /// # Arguments
+    /// * `agent_id` - The agent receiving the payloads
+    /// * `payloads` - The payloads received in this step
+    /// * `step` - The current time step
+    pub fn suppress(&mut self, agent_id: AgentId, payloads: &mut [DPayload], step: TimeMS) {
+        if let Some(memory) = self.memory {
+            self.seen
+                .retain(|_, seen_at| step.as_u64() - seen_at.as_u64() < memory.as_u64());
+        }
+        for payload in payloads.iter_mut() {
+            let metadata = &mut payload.metadata;
+            let mut kept = Vec::with_capacity(metadata.data_blobs.len());
+            for mut blob in metadata.data_blobs.drain(..) {
+                self.counts.received += 1;
+                let origin = match blob.origin {
+                    Some(origin) => origin,
+                    None => {
+                        kept.push(blob);
+                        continue;
+                    }
+                };
+                if origin.agent_id == agent_id || self.seen.contains_key(&origin) {
+                    self.counts.duplicates += 1;
+                    metadata.total_size -= blob.data_size;
+                    metadata.total_count -= 1;
+                    continue;
+                }
+                self.seen.insert(origin, step);
+                if blob.action.action_type == ActionType::Forward {
+                    match self.rebroadcast_probability {
+                        Some(probability) if !self.rng.gen_bool(probability) => {
+                            blob.action.action_type = ActionType::Consume;
+                            self.counts.withheld += 1;
+                        }
+                        _ => self.counts.rebroadcast += 1,
+                    }
+                }
+                kept.push(blob);
+            }
+            metadata.data_blobs = kept;
+        }
+    }
+
+    /// Takes the counts since the previous call.
+    pub fn take_counts(&mut self) -> DuplicateCounts {
+        std::mem::take(&mut self.counts)
+    }
+}
+
+/// Duplicates received by the agents of every class in the current step, and by all the agents
+/// since the start of the run.
+#[derive(Clone, Debug, Default)]
+pub struct ClassDuplicates {
+    step: HashMap<DeviceClass, DuplicateCounts>,
+    total: DuplicateCounts,
+}
+
+impl ClassDuplicates {
+    pub fn register(&mut self, device_class: DeviceClass, counts: &DuplicateCounts) {
+        self.step.entry(device_class).or_default().add(counts);
+        self.total.add(counts);
+    }
+
+    /// Takes the counts of the step, ordered by the class.
+    pub fn take_step(&mut self) -> Vec<(DeviceClass, DuplicateCounts)> {
+        let mut step: Vec<(DeviceClass, DuplicateCounts)> = self.step.drain().collect();
+        step.sort_by_key(|(device_class, _)| device_class.as_int());
+        step
+    }
+
+    pub fn total(&self) -> &DuplicateCounts {
+        &self.total
+    }
+}
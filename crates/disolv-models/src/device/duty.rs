@@ -0,0 +1,149 @@
+use crate::device::metrics::Energy;
+use disolv_core::bucket::TimeMS;
+use disolv_core::model::{Model, ModelSettings};
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the radio duty cycle of an agent class. With the `periodic` schedule, the radio
+/// is awake for `wake_duration` at the start of every `period`, shifted by `offset`, and sleeps
+/// otherwise. Payloads sent to a sleeping agent by the infrastructure are buffered until the
+/// agent wakes up when `buffering` is set, up to `buffer_size` payloads. Other payloads sent to
+/// a sleeping agent are dropped. Radio energy is measured with `wake_power` and `sleep_power`
+/// given in energy units per second.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct DutyCycleSettings {
+    pub name: String,
+    pub period: Option<TimeMS>,
+    pub wake_duration: Option<TimeMS>,
+    pub offset: Option<TimeMS>,
+    pub buffering: Option<bool>,
+    pub buffer_size: Option<usize>,
+    pub wake_power: Option<u64>,
+    pub sleep_power: Option<u64>,
+}
+
+impl ModelSettings for DutyCycleSettings {}
+
+#[derive(Clone, Copy, Debug)]
+pub enum WakeSchedule {
+    AlwaysOn,
+    Periodic(PeriodicWake),
+}
+
+impl Model for WakeSchedule {
+    type Settings = DutyCycleSettings;
+
+    fn with_settings(settings: &DutyCycleSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "always_on" => WakeSchedule::AlwaysOn,
+            "periodic" => WakeSchedule::Periodic(PeriodicWake::with_settings(settings)),
+            _ => {
+                error!("Only always_on and periodic duty cycles are supported");
+                panic!("Unsupported duty cycle {}.", settings.name);
+            }
+        }
+    }
+}
+
+impl WakeSchedule {
+    pub fn is_awake(&self, now: TimeMS) -> bool {
+        match self {
+            WakeSchedule::AlwaysOn => true,
+            WakeSchedule::Periodic(periodic) => periodic.is_awake(now),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PeriodicWake {
+    pub period: u64,
+    pub wake_duration: u64,
+    pub offset: u64,
+}
+
+impl PeriodicWake {
+    fn with_settings(settings: &DutyCycleSettings) -> Self {
+        let period = settings
+            .period
+            .expect("Periodic duty cycle requires a period")
+            .as_u64();
+        let wake_duration = settings
+            .wake_duration
+            .expect("Periodic duty cycle requires a wake duration")
+            .as_u64();
+        if period == 0 || wake_duration > period {
+            panic!("Wake duration must be within a non-zero period.");
+        }
+        Self {
+            period,
+            wake_duration,
+            offset: settings.offset.unwrap_or_default().as_u64() % period,
+        }
+    }
+
+    fn is_awake(&self, now: TimeMS) -> bool {
+        (now.as_u64() + self.period - self.offset) % self.period < self.wake_duration
+    }
+}
+
+/// What happens to the payloads sent to an agent while its radio sleeps.
+#[derive(Clone, Copy, Debug)]
+pub struct SleepPolicy {
+    pub schedule: WakeSchedule,
+    pub buffering: bool,
+    pub buffer_size: usize,
+}
+
+/// Radio duty cycle of an agent. Keeps track of the time the radio spent awake and asleep to
+/// measure the radio energy.
+#[derive(Clone, Debug)]
+pub struct DutyCycle {
+    pub policy: SleepPolicy,
+    wake_power: u64,
+    sleep_power: u64,
+    awake_time: u64,
+    sleep_time: u64,
+    last_tick: Option<(TimeMS, bool)>,
+}
+
+impl DutyCycle {
+    pub fn new(settings: &DutyCycleSettings) -> Self {
+        Self {
+            policy: SleepPolicy {
+                schedule: WakeSchedule::with_settings(settings),
+                buffering: settings.buffering.unwrap_or(true),
+                buffer_size: settings.buffer_size.unwrap_or(usize::MAX),
+            },
+            wake_power: settings.wake_power.unwrap_or_default(),
+            sleep_power: settings.sleep_power.unwrap_or_default(),
+            awake_time: 0,
+            sleep_time: 0,
+            last_tick: None,
+        }
+    }
+
+    /// Checks if the radio is awake at the given step. The time since the previous step is
+    /// accounted to the state of the radio in the previous step.
+    pub fn tick(&mut self, now: TimeMS) -> bool {
+        if let Some((last_step, was_awake)) = self.last_tick {
+            let elapsed = now.as_u64().saturating_sub(last_step.as_u64());
+            match was_awake {
+                true => self.awake_time += elapsed,
+                false => self.sleep_time += elapsed,
+            }
+        }
+        let awake = self.policy.schedule.is_awake(now);
+        self.last_tick = Some((now, awake));
+        awake
+    }
+
+    /// Restarts the time accounting, e.g. after the agent was switched off.
+    pub fn pause(&mut self) {
+        self.last_tick = None;
+    }
+
+    pub fn energy(&self) -> Energy {
+        Energy::new((self.wake_power * self.awake_time + self.sleep_power * self.sleep_time) / 1000)
+    }
+}
@@ -41,6 +41,15 @@ impl Resource<Bytes> for StorageType {
     }
 }
 
+impl StorageType {
+    /// Frees the given amount of the consumed storage.
+    pub fn release(&mut self, amount: Bytes) {
+        match self {
+            StorageType::Constant(storage) => storage.release(amount),
+        }
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ConstantStorage {
     pub available: Bytes,
@@ -59,7 +68,7 @@ impl Resource<Bytes> for ConstantStorage {
     }
 
     fn consume(&mut self, metadata: &Self::P) -> Feasibility<Bytes> {
-        let to_consume = self.available - metadata.total_size;
+        let to_consume = metadata.total_size;
         if self.available + to_consume > self.limit {
             Feasibility::Infeasible(self.available)
         } else {
@@ -72,3 +81,49 @@ impl Resource<Bytes> for ConstantStorage {
         self.available
     }
 }
+
+impl ConstantStorage {
+    pub fn release(&mut self, amount: Bytes) {
+        if amount > self.available {
+            self.available = Bytes::default();
+        } else {
+            self.available -= amount;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(size: u64) -> PayloadInfo {
+        PayloadInfo {
+            total_size: Bytes::new(size),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_storage_is_consumed_up_to_limit() {
+        let mut storage = ConstantStorage::with_settings(&StorageSettings {
+            variant: "constant".to_string(),
+            limit: Bytes::new(1000),
+        });
+        assert!(matches!(
+            storage.consume(&payload(400)),
+            Feasibility::Feasible(_)
+        ));
+        assert!(matches!(
+            storage.consume(&payload(500)),
+            Feasibility::Feasible(_)
+        ));
+        assert_eq!(storage.available(), Bytes::new(900));
+        assert!(matches!(
+            storage.consume(&payload(200)),
+            Feasibility::Infeasible(_)
+        ));
+        assert_eq!(storage.available(), Bytes::new(900));
+        storage.release(Bytes::new(300));
+        assert_eq!(storage.available(), Bytes::new(600));
+    }
+}
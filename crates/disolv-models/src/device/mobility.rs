@@ -1,3 +1,5 @@
+pub mod emissions;
+
 use crate::device::mobility::road::RoadId;
 use crate::device::mobility::velocity::Velocity;
 use disolv_core::agent::MobilityInfo;
@@ -17,6 +19,8 @@ pub struct Point2D {
     pub y: f64,
 }
 
+/// Positional information of an agent. The optional `z` coordinate is the altitude of the
+/// agent, which is relevant for aerial agents such as UAVs.
 #[derive(Clone, Copy, Debug, Default, TypedBuilder)]
 pub struct MapState {
     pub pos: Point2D,
@@ -28,6 +32,16 @@ pub struct MapState {
     pub road_id: Option<RoadId>,
 }
 
+impl MapState {
+    /// Calculates the slant range between two agents. Missing altitude is treated as ground level.
+    pub fn slant_range(&self, other: &MapState) -> f64 {
+        let dx = self.pos.x - other.pos.x;
+        let dy = self.pos.y - other.pos.y;
+        let dz = self.z.unwrap_or_default() - other.z.unwrap_or_default();
+        dx.hypot(dy).hypot(dz)
+    }
+}
+
 impl MobilityInfo for MapState {}
 
 pub mod road {
@@ -0,0 +1,119 @@
+use crate::device::mobility::{MapState, Point2D};
+use disolv_core::bucket::TimeMS;
+use log::error;
+use serde::Deserialize;
+
+const AIR_DENSITY: f64 = 1.2;
+const GRAVITY: f64 = 9.81;
+
+/// Vehicle parameters of an agent class to estimate the fuel and CO2 of its movement. The
+/// tractive power is the sum of the inertial, aerodynamic and rolling resistance powers, with
+/// the `mass` in kg and the `drag_area` as the drag coefficient times the frontal area in m².
+/// The fuel in ml is `idle_fuel` per second plus `fuel_per_kj` for every kJ of positive
+/// tractive power, and every ml of fuel emits `co2_per_ml` grams of CO2.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmissionSettings {
+    pub mass: f64,
+    pub drag_area: f64,
+    pub rolling_resistance: f64,
+    pub idle_fuel: f64,
+    pub fuel_per_kj: f64,
+    pub co2_per_ml: f64,
+}
+
+/// Fuel and CO2 of an agent between two of its positions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Emission {
+    pub speed: f64,
+    pub acceleration: f64,
+    pub fuel: f64,
+    pub co2: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Motion {
+    step: TimeMS,
+    pos: Point2D,
+    speed: Option<f64>,
+}
+
+/// Estimates the fuel and CO2 of an agent from its speed and acceleration. The speed is the
+/// velocity of the trace, or the distance moved since the last position when the trace has no
+/// velocity. The acceleration is the change of the speed since the last position, it is zero
+/// when the speed at the last position is not known.
+#[derive(Clone, Debug)]
+pub struct EmissionModel {
+    settings: EmissionSettings,
+    last: Option<Motion>,
+}
+
+impl EmissionModel {
+    pub fn new(settings: &EmissionSettings) -> Self {
+        if settings.mass <= 0.0 {
+            error!("Vehicle mass must be positive");
+            panic!("Invalid vehicle mass {}.", settings.mass);
+        }
+        let rates = [
+            settings.drag_area,
+            settings.rolling_resistance,
+            settings.idle_fuel,
+            settings.fuel_per_kj,
+            settings.co2_per_ml,
+        ];
+        if rates.iter().any(|rate| *rate < 0.0) {
+            error!("Vehicle parameters must not be negative");
+            panic!("Invalid vehicle parameters {:?}.", settings);
+        }
+        Self {
+            settings: settings.clone(),
+            last: None,
+        }
+    }
+
+    /// Emission since the last position of the agent. There is no emission for the first
+    /// position or for a position of the same step.
+    pub fn update(&mut self, step: TimeMS, map_state: &MapState) -> Option<Emission> {
+        let last = self.last;
+        let elapsed = last.map_or(0.0, |last| {
+            step.as_u64().saturating_sub(last.step.as_u64()) as f64 / 1000.0
+        });
+        let speed = match (map_state.velocity, last) {
+            (Some(velocity), _) => Some(velocity.as_f64()),
+            (None, Some(last)) if elapsed > 0.0 => {
+                let dx = map_state.pos.x - last.pos.x;
+                let dy = map_state.pos.y - last.pos.y;
+                Some((dx * dx + dy * dy).sqrt() / elapsed)
+            }
+            _ => None,
+        };
+        self.last = Some(Motion {
+            step,
+            pos: map_state.pos,
+            speed,
+        });
+        let (last, speed) = (last?, speed?);
+        if elapsed <= 0.0 {
+            return None;
+        }
+        let acceleration = last
+            .speed
+            .map_or(0.0, |last_speed| (speed - last_speed) / elapsed);
+        let fuel = self.fuel_rate(speed, acceleration) * elapsed;
+        Some(Emission {
+            speed,
+            acceleration,
+            fuel,
+            co2: fuel * self.settings.co2_per_ml,
+        })
+    }
+
+    /// Fuel in ml per second at the speed and acceleration.
+    fn fuel_rate(&self, speed: f64, acceleration: f64) -> f64 {
+        let settings = &self.settings;
+        let inertia = settings.mass * acceleration * speed;
+        let drag = 0.5 * AIR_DENSITY * settings.drag_area * speed.powi(3);
+        let rolling = settings.mass * GRAVITY * settings.rolling_resistance * speed;
+        let power_kw = (inertia + drag + rolling) / 1000.0;
+        settings.idle_fuel + settings.fuel_per_kj * power_kw.max(0.0)
+    }
+}
@@ -1,11 +1,24 @@
 pub mod actions;
 pub mod actor;
+pub mod broadcast;
+pub mod cache;
+pub mod cadence;
+pub mod charging;
 pub mod compose;
+pub mod duplicates;
+pub mod duty;
 pub mod energy;
 pub mod hardware;
 pub mod metrics;
 pub mod mobility;
+pub mod positioning;
 pub mod power;
+pub mod predict;
+pub mod queue;
 pub mod reply;
+pub mod routing;
 pub mod select;
+pub mod sensor;
+pub mod throttle;
+pub mod tx_power;
 pub mod types;
@@ -0,0 +1,77 @@
+use crate::device::mobility::{MapState, Point2D};
+use log::error;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the positioning error of an agent class, e.g. the GPS noise of the vehicles. The
+/// perceived position is off by the constant `bias_x` and `bias_y` in m plus an error with the
+/// `std_dev` in m on both axes. The error of consecutive steps follows a Gauss-Markov process
+/// with the `correlation`, and is drawn anew in every step without one.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct PositioningSettings {
+    pub std_dev: f64,
+    pub bias_x: Option<f64>,
+    pub bias_y: Option<f64>,
+    pub correlation: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PositionError {
+    std_dev: f64,
+    bias: Point2D,
+    correlation: f64,
+    error: Option<Point2D>,
+    rng: Pcg64Mcg,
+}
+
+impl PositionError {
+    pub fn new(settings: &PositioningSettings, seed: u64) -> Self {
+        if settings.std_dev < 0.0 {
+            error!("Standard deviation of the positioning error cannot be negative");
+            panic!("Invalid positioning error deviation {}.", settings.std_dev);
+        }
+        let correlation = settings.correlation.unwrap_or_default();
+        if !(0.0..1.0).contains(&correlation) {
+            error!("Correlation of the positioning error must be in [0, 1)");
+            panic!("Invalid positioning error correlation {}.", correlation);
+        }
+        Self {
+            std_dev: settings.std_dev,
+            bias: Point2D::builder()
+                .x(settings.bias_x.unwrap_or_default())
+                .y(settings.bias_y.unwrap_or_default())
+                .build(),
+            correlation,
+            error: None,
+            rng: Pcg64Mcg::new(seed as u128),
+        }
+    }
+
+    fn draw(&mut self) -> f64 {
+        let noise: f64 = self.rng.sample(StandardNormal);
+        noise * self.std_dev
+    }
+
+    /// Draws the error of the step and returns the position the agent perceives. Only the
+    /// position is perturbed, the rest of the map state is kept.
+    pub fn perceive(&mut self, map_state: &MapState) -> MapState {
+        let error = match self.error {
+            Some(previous) => {
+                let innovation = (1.0 - self.correlation * self.correlation).sqrt();
+                Point2D::builder()
+                    .x(self.correlation * previous.x + innovation * self.draw())
+                    .y(self.correlation * previous.y + innovation * self.draw())
+                    .build()
+            }
+            None => Point2D::builder().x(self.draw()).y(self.draw()).build(),
+        };
+        self.error = Some(error);
+        let mut perceived = *map_state;
+        perceived.pos.x += self.bias.x + error.x;
+        perceived.pos.y += self.bias.y + error.y;
+        perceived
+    }
+}
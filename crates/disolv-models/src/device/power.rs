@@ -1,14 +1,35 @@
 use disolv_core::bucket::TimeMS;
+use disolv_core::state::{MachineState, StateMachine};
 use std::collections::VecDeque;
 use typed_builder::TypedBuilder;
 
-#[derive(Clone, Default, Copy, Debug, PartialEq)]
+#[derive(Clone, Default, Copy, Debug, PartialEq, Eq)]
 pub enum PowerState {
     #[default]
     Off,
     On,
 }
 
+impl MachineState for PowerState {
+    const MACHINE: u32 = 0;
+
+    fn as_int(&self) -> u32 {
+        match self {
+            PowerState::Off => 0,
+            PowerState::On => 1,
+        }
+    }
+}
+
+/// Power state of an agent, which is switched on and off by the power schedule.
+pub type PowerMachine = StateMachine<PowerState, ()>;
+
+pub fn power_machine() -> PowerMachine {
+    PowerMachine::new(PowerState::Off)
+        .transition(PowerState::Off, PowerState::On)
+        .transition(PowerState::On, PowerState::Off)
+}
+
 #[derive(Clone, Default, Debug, PartialEq, TypedBuilder)]
 pub struct PowerManager {
     pub on_times: VecDeque<TimeMS>,
@@ -36,3 +57,95 @@ impl PowerManager {
         self.off_times.pop_front();
     }
 }
+
+/// Reason for the last deactivation of an agent. Agents that are active at the end of the
+/// simulation are deactivated by the end of the simulation.
+#[derive(Clone, Default, Copy, Debug, PartialEq, Eq)]
+pub enum DeactivationReason {
+    #[default]
+    SimulationEnd,
+    Schedule,
+}
+
+impl DeactivationReason {
+    pub fn as_int(&self) -> u32 {
+        match self {
+            DeactivationReason::SimulationEnd => 0,
+            DeactivationReason::Schedule => 1,
+        }
+    }
+}
+
+/// Activations and deactivations of an agent over the simulation, aggregated over its power
+/// cycles. The active duration adds up the time of all the cycles, while the deactivation and
+/// its reason are those of the last cycle.
+#[derive(Clone, Default, Copy, Debug)]
+pub struct Lifecycle {
+    pub first_activation: TimeMS,
+    pub last_activation: TimeMS,
+    pub last_deactivation: Option<TimeMS>,
+    pub active_duration: TimeMS,
+    pub power_cycles: u32,
+    pub reason: DeactivationReason,
+    pub is_active: bool,
+}
+
+impl Lifecycle {
+    pub fn activate(&mut self, step: TimeMS) {
+        if self.is_active {
+            return;
+        }
+        if self.power_cycles == 0 {
+            self.first_activation = step;
+        }
+        self.last_activation = step;
+        self.power_cycles += 1;
+        self.is_active = true;
+    }
+
+    pub fn deactivate(&mut self, step: TimeMS, reason: DeactivationReason) {
+        if !self.is_active {
+            return;
+        }
+        self.active_duration += TimeMS::from(step.as_u64() - self.last_activation.as_u64());
+        self.last_deactivation = Some(step);
+        self.reason = reason;
+        self.is_active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_aggregates_power_cycles() {
+        let mut lifecycle = Lifecycle::default();
+        lifecycle.activate(TimeMS::from(100));
+        lifecycle.activate(TimeMS::from(200));
+        lifecycle.deactivate(TimeMS::from(500), DeactivationReason::Schedule);
+        lifecycle.deactivate(TimeMS::from(600), DeactivationReason::SimulationEnd);
+        assert_eq!(lifecycle.power_cycles, 1);
+        assert_eq!(lifecycle.active_duration, TimeMS::from(400));
+        assert_eq!(lifecycle.reason, DeactivationReason::Schedule);
+
+        lifecycle.activate(TimeMS::from(1000));
+        lifecycle.deactivate(TimeMS::from(1300), DeactivationReason::SimulationEnd);
+        assert_eq!(lifecycle.first_activation, TimeMS::from(100));
+        assert_eq!(lifecycle.last_activation, TimeMS::from(1000));
+        assert_eq!(lifecycle.last_deactivation, Some(TimeMS::from(1300)));
+        assert_eq!(lifecycle.active_duration, TimeMS::from(700));
+        assert_eq!(lifecycle.power_cycles, 2);
+        assert_eq!(lifecycle.reason, DeactivationReason::SimulationEnd);
+        assert!(!lifecycle.is_active);
+    }
+
+    #[test]
+    fn test_lifecycle_without_deactivation() {
+        let mut lifecycle = Lifecycle::default();
+        lifecycle.activate(TimeMS::from(300));
+        assert!(lifecycle.is_active);
+        assert_eq!(lifecycle.last_deactivation, None);
+        assert_eq!(lifecycle.active_duration, TimeMS::default());
+    }
+}
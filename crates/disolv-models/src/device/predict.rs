@@ -0,0 +1,379 @@
+use crate::device::mobility::Point2D;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_core::model::{Model, ModelSettings};
+use log::error;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// Variance of the velocity of a newly observed agent in the Kalman filter, in (m/s)^2.
+const INITIAL_VELOCITY_VARIANCE: f64 = 100.0;
+
+/// Settings of the mobility prediction. Positions are predicted `horizon` steps ahead. The
+/// noise settings are used by the Kalman filter only: `process_noise` is the standard deviation
+/// of the acceleration in m/s^2 and `measurement_noise` is the standard deviation of the
+/// observed positions in m.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct PredictorSettings {
+    pub name: String,
+    pub horizon: u32,
+    pub process_noise: Option<f64>,
+    pub measurement_noise: Option<f64>,
+}
+
+impl ModelSettings for PredictorSettings {}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Predictor {
+    ConstantVelocity,
+    Kalman(KalmanNoise),
+}
+
+impl Model for Predictor {
+    type Settings = PredictorSettings;
+
+    fn with_settings(settings: &PredictorSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "constant_velocity" => Predictor::ConstantVelocity,
+            "kalman" => Predictor::Kalman(KalmanNoise {
+                acceleration: settings.process_noise.unwrap_or(1.0),
+                position: settings.measurement_noise.unwrap_or(2.0),
+            }),
+            _ => {
+                error!("Only constant_velocity and kalman predictors are supported");
+                panic!("Unsupported predictor type {}.", settings.name);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct KalmanNoise {
+    pub acceleration: f64,
+    pub position: f64,
+}
+
+/// Kalman filter of the position and the velocity along one axis.
+#[derive(Clone, Copy, Debug)]
+struct AxisFilter {
+    pos: f64,
+    vel: f64,
+    cov: [[f64; 2]; 2],
+}
+
+impl AxisFilter {
+    fn new(pos: f64, noise: &KalmanNoise) -> Self {
+        Self {
+            pos,
+            vel: 0.0,
+            cov: [
+                [noise.position * noise.position, 0.0],
+                [0.0, INITIAL_VELOCITY_VARIANCE],
+            ],
+        }
+    }
+
+    fn predict(&mut self, dt: f64, noise: &KalmanNoise) {
+        let q = noise.acceleration * noise.acceleration;
+        let [[p00, p01], [p10, p11]] = self.cov;
+        self.pos += self.vel * dt;
+        self.cov = [
+            [
+                p00 + dt * (p10 + p01) + dt * dt * p11 + q * dt.powi(4) / 4.0,
+                p01 + dt * p11 + q * dt.powi(3) / 2.0,
+            ],
+            [p10 + dt * p11 + q * dt.powi(3) / 2.0, p11 + q * dt * dt],
+        ];
+    }
+
+    fn update(&mut self, observed: f64, noise: &KalmanNoise) {
+        let [[p00, p01], [p10, p11]] = self.cov;
+        let innovation = observed - self.pos;
+        let variance = p00 + noise.position * noise.position;
+        let (gain_pos, gain_vel) = (p00 / variance, p10 / variance);
+        self.pos += gain_pos * innovation;
+        self.vel += gain_vel * innovation;
+        self.cov = [
+            [(1.0 - gain_pos) * p00, (1.0 - gain_pos) * p01],
+            [p10 - gain_vel * p00, p11 - gain_vel * p01],
+        ];
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TrackState {
+    ConstantVelocity { pos: Point2D, vel: (f64, f64) },
+    Kalman { x: AxisFilter, y: AxisFilter },
+}
+
+impl TrackState {
+    fn new(predictor: &Predictor, pos: &Point2D) -> Self {
+        match predictor {
+            Predictor::ConstantVelocity => TrackState::ConstantVelocity {
+                pos: *pos,
+                vel: (0.0, 0.0),
+            },
+            Predictor::Kalman(noise) => TrackState::Kalman {
+                x: AxisFilter::new(pos.x, noise),
+                y: AxisFilter::new(pos.y, noise),
+            },
+        }
+    }
+
+    fn observe(&mut self, predictor: &Predictor, observed: &Point2D, dt: f64) {
+        match (self, predictor) {
+            (TrackState::ConstantVelocity { pos, vel }, _) => {
+                *vel = ((observed.x - pos.x) / dt, (observed.y - pos.y) / dt);
+                *pos = *observed;
+            }
+            (TrackState::Kalman { x, y }, Predictor::Kalman(noise)) => {
+                x.predict(dt, noise);
+                x.update(observed.x, noise);
+                y.predict(dt, noise);
+                y.update(observed.y, noise);
+            }
+            (TrackState::Kalman { .. }, _) => {
+                panic!("Kalman track must be updated with a Kalman predictor")
+            }
+        }
+    }
+
+    fn extrapolate(&self, dt: f64) -> Point2D {
+        let (pos, vel) = match self {
+            TrackState::ConstantVelocity { pos, vel } => ((pos.x, pos.y), *vel),
+            TrackState::Kalman { x, y } => ((x.pos, y.pos), (x.vel, y.vel)),
+        };
+        Point2D::builder()
+            .x(pos.0 + vel.0 * dt)
+            .y(pos.1 + vel.1 * dt)
+            .build()
+    }
+}
+
+/// State of an agent being tracked. Predictions made for the future steps are kept until the
+/// agent is observed at those steps to measure the prediction error.
+#[derive(Clone, Debug)]
+struct Track {
+    state: TrackState,
+    observed_at: TimeMS,
+    pending: VecDeque<(TimeMS, Point2D)>,
+}
+
+/// Difference between the position predicted for an agent and the position it was observed at.
+#[derive(Clone, Copy, Debug)]
+pub struct PredictionError {
+    pub agent_id: AgentId,
+    pub predicted: Point2D,
+    pub observed: Point2D,
+}
+
+impl PredictionError {
+    pub fn distance(&self) -> f64 {
+        let dx = self.predicted.x - self.observed.x;
+        let dy = self.predicted.y - self.observed.y;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// Predicts the positions of the agents from their past positions so that the decisions that
+/// depend on the future positions of the agents can be made ahead of time.
+#[derive(Clone, Debug)]
+pub struct MobilityPredictor {
+    predictor: Predictor,
+    horizon: u32,
+    step_size: TimeMS,
+    tracks: HashMap<AgentId, Track>,
+}
+
+impl MobilityPredictor {
+    pub fn new(settings: &PredictorSettings, step_size: TimeMS) -> Self {
+        Self {
+            predictor: Predictor::with_settings(settings),
+            horizon: settings.horizon,
+            step_size,
+            tracks: HashMap::default(),
+        }
+    }
+
+    pub fn horizon(&self) -> u32 {
+        self.horizon
+    }
+
+    /// Updates the track of the agent with the observed position. Returns the error of the
+    /// prediction made for this step, if there is one.
+    pub fn observe(
+        &mut self,
+        agent_id: AgentId,
+        now: TimeMS,
+        pos: &Point2D,
+    ) -> Option<PredictionError> {
+        let predictor = self.predictor;
+        let track = match self.tracks.get_mut(&agent_id) {
+            Some(track) => track,
+            None => {
+                self.tracks.insert(
+                    agent_id,
+                    Track {
+                        state: TrackState::new(&predictor, pos),
+                        observed_at: now,
+                        pending: VecDeque::new(),
+                    },
+                );
+                return None;
+            }
+        };
+        if now.as_u64() <= track.observed_at.as_u64() {
+            return None;
+        }
+
+        let dt = Self::seconds(now.as_u64() - track.observed_at.as_u64());
+        track.state.observe(&predictor, pos, dt);
+        track.observed_at = now;
+
+        while track.pending.front().is_some_and(|(at, _)| *at < now) {
+            track.pending.pop_front();
+        }
+        let error = match track.pending.front() {
+            Some((at, predicted)) if *at == now => Some(PredictionError {
+                agent_id,
+                predicted: *predicted,
+                observed: *pos,
+            }),
+            _ => None,
+        };
+        if error.is_some() {
+            track.pending.pop_front();
+        }
+
+        let ahead = self.step_size.as_u64() * self.horizon as u64;
+        if ahead > 0 {
+            let predicted = track.state.extrapolate(Self::seconds(ahead));
+            track
+                .pending
+                .push_back((now + TimeMS::from(ahead), predicted));
+        }
+        error
+    }
+
+    /// Position of the agent the given number of steps after it was last observed. Agents
+    /// observed only once are assumed to stay where they are.
+    pub fn predict(&self, agent_id: AgentId, steps: u32) -> Option<Point2D> {
+        self.tracks.get(&agent_id).map(|track| {
+            let ahead = self.step_size.as_u64() * steps as u64;
+            track.state.extrapolate(Self::seconds(ahead))
+        })
+    }
+
+    pub fn remove(&mut self, agent_id: AgentId) {
+        self.tracks.remove(&agent_id);
+    }
+
+    #[inline]
+    fn seconds(duration_ms: u64) -> f64 {
+        duration_ms as f64 / 1000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Point2D {
+        Point2D::builder().x(x).y(y).build()
+    }
+
+    fn predictor(name: &str, horizon: u32) -> MobilityPredictor {
+        let settings = PredictorSettings {
+            name: name.to_string(),
+            horizon,
+            process_noise: Some(0.1),
+            measurement_noise: Some(0.5),
+        };
+        MobilityPredictor::new(&settings, TimeMS::from(100))
+    }
+
+    #[test]
+    fn test_filter_converges_on_constant_velocity() {
+        let noise = KalmanNoise {
+            acceleration: 0.1,
+            position: 0.5,
+        };
+        let mut filter = AxisFilter::new(5.0, &noise);
+        let initial = filter.cov;
+        for step in 1..=200 {
+            let truth = 5.0 + 2.0 * step as f64 * 0.1;
+            filter.predict(0.1, &noise);
+            let predicted = filter.cov;
+            filter.update(truth, &noise);
+            assert!(filter.cov[0][0] < predicted[0][0]);
+            assert!(filter.cov[1][1] <= predicted[1][1]);
+        }
+        let truth = 5.0 + 2.0 * 200.0 * 0.1;
+        assert!((filter.pos - truth).abs() < 0.05);
+        assert!((filter.vel - 2.0).abs() < 0.05);
+        assert!(filter.cov[0][0] < initial[0][0]);
+        assert!(filter.cov[1][1] < initial[1][1] / 100.0);
+    }
+
+    #[test]
+    fn test_error_is_paired_with_prediction_at_horizon() {
+        let mut predictor = predictor("constant_velocity", 2);
+        let agent = AgentId::from(1);
+        for step in 0..=2u64 {
+            let pos = point(step as f64, 0.0);
+            assert!(predictor
+                .observe(agent, TimeMS::from(step * 100), &pos)
+                .is_none());
+        }
+
+        let error = predictor
+            .observe(agent, TimeMS::from(300), &point(3.0, 0.0))
+            .expect("prediction made at 100 is due at 300");
+        assert_eq!(error.agent_id, agent);
+        assert!((error.predicted.x - 3.0).abs() < 1e-9);
+        assert!(error.distance() < 1e-9);
+        assert!(predictor
+            .observe(agent, TimeMS::from(300), &point(3.0, 0.0))
+            .is_none());
+
+        let error = predictor
+            .observe(agent, TimeMS::from(400), &point(4.0, 0.0))
+            .expect("prediction made at 200 is due at 400");
+        assert!((error.predicted.x - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missed_predictions_are_discarded() {
+        let mut predictor = predictor("constant_velocity", 2);
+        let agent = AgentId::from(1);
+        for step in 0..=4u64 {
+            predictor.observe(agent, TimeMS::from(step * 100), &point(step as f64, 0.0));
+        }
+        // The prediction for 500 is never paired, the one made at 400 is due at 600.
+        let error = predictor
+            .observe(agent, TimeMS::from(600), &point(10.0, 0.0))
+            .expect("prediction made at 400 is due at 600");
+        assert!((error.predicted.x - 6.0).abs() < 1e-9);
+        assert!((error.distance() - 4.0).abs() < 1e-9);
+        assert!(predictor
+            .observe(agent, TimeMS::from(700), &point(11.0, 0.0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_kalman_prediction_error_is_small_on_straight_track() {
+        let mut predictor = predictor("kalman", 5);
+        let agent = AgentId::from(1);
+        let mut last_error = None;
+        for step in 0..=100u64 {
+            let pos = point(1.5 * step as f64, 0.5 * step as f64);
+            if let Some(error) = predictor.observe(agent, TimeMS::from(step * 100), &pos) {
+                last_error = Some(error);
+            }
+        }
+        let error = last_error.expect("predictions are paired after the horizon");
+        assert!(error.distance() < 0.1);
+    }
+}
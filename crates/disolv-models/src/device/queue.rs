@@ -0,0 +1,325 @@
+use crate::net::message::DPayload;
+use disolv_core::bucket::TimeMS;
+use disolv_core::model::{Model, ModelSettings};
+use log::{debug, error, warn};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// Settings of the processing queue of an agent. Service rate is the number of payloads that
+/// can be processed in a single time step and queue length is the maximum number of payloads
+/// that can wait to be processed. Payloads arriving at a full queue are dropped.
+///
+/// A `utilization_trace` imposes the background load of a shared edge server. It has the busy
+/// fraction of the processor of an agent from a time step on, and only the remaining share of the
+/// service rate is left to the payloads of the simulation.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProcessorSettings {
+    pub name: String,
+    pub service_rate: Option<u32>,
+    pub queue_length: Option<u32>,
+    pub utilization_trace: Option<String>,
+}
+
+impl ModelSettings for ProcessorSettings {}
+
+/// Processor models the compute side of an agent. Received payloads are handed over to the
+/// processor, which decides when they are available for forwarding or consumption.
+#[derive(Clone, Debug, Default)]
+pub enum Processor {
+    #[default]
+    Instant,
+    Fifo(FifoProcessor),
+}
+
+impl Model for Processor {
+    type Settings = ProcessorSettings;
+
+    fn with_settings(settings: &ProcessorSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "instant" => Processor::Instant,
+            "fifo" => Processor::Fifo(FifoProcessor::new(settings)),
+            _ => {
+                error!("Only Instant and Fifo processors are supported.");
+                panic!("Unsupported processor type {}.", settings.name);
+            }
+        }
+    }
+}
+
+impl Processor {
+    /// Passes the received payloads through the processing queue.
+    ///
+    /// # Arguments
+    /// * `payloads` - Payloads received in this step
+    /// * `step` - Current time step
+    ///
+    /// # Returns
+    /// * `Option<Vec<DPayload>>` - Payloads that completed processing in this step
+    /// * `Vec<DPayload>` - Payloads dropped due to queue overflow
+    pub fn process(
+        &mut self,
+        payloads: Option<Vec<DPayload>>,
+        step: TimeMS,
+    ) -> (Option<Vec<DPayload>>, Vec<DPayload>) {
+        match self {
+            Processor::Instant => (payloads, Vec::new()),
+            Processor::Fifo(processor) => {
+                let dropped = match payloads {
+                    Some(payloads) => processor.enqueue(payloads, step),
+                    None => Vec::new(),
+                };
+                (processor.dequeue(step), dropped)
+            }
+        }
+    }
+
+    pub fn queue_size(&self) -> usize {
+        match self {
+            Processor::Instant => 0,
+            Processor::Fifo(processor) => processor.queue.len(),
+        }
+    }
+
+    /// Sets the busy fractions of the processor over time. Only a processor with a service rate
+    /// can be loaded.
+    pub fn set_background(&mut self, mut background: Vec<(TimeMS, f64)>) {
+        match self {
+            Processor::Fifo(processor) if processor.service_rate.is_some() => {
+                background.sort_by_key(|(time_step, _)| *time_step);
+                processor.background = background;
+            }
+            _ => warn!("Background load is ignored by a processor without a service rate"),
+        }
+    }
+
+    pub fn has_background(&self) -> bool {
+        match self {
+            Processor::Instant => false,
+            Processor::Fifo(processor) => !processor.background.is_empty(),
+        }
+    }
+
+    /// Payloads that the background load kept the processor from serving in the last step.
+    pub fn withheld(&self) -> u32 {
+        match self {
+            Processor::Instant => 0,
+            Processor::Fifo(processor) => processor.withheld,
+        }
+    }
+
+    /// Returns the queue statistics of the current step and resets the counters. An instant
+    /// processor has no queue and no statistics.
+    pub fn take_stats(&mut self) -> Option<QueueStats> {
+        match self {
+            Processor::Instant => None,
+            Processor::Fifo(processor) => Some(std::mem::take(&mut processor.stats)),
+        }
+    }
+}
+
+/// Queue statistics of the current time step. The delays are the queueing delays of the
+/// payloads served in the step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueueStats {
+    pub served: u64,
+    pub dropped: u64,
+    pub queued: u64,
+    pub total_delay: TimeMS,
+    pub max_delay: TimeMS,
+}
+
+/// A first-in-first-out processor that serves a fixed number of payloads per time step.
+/// Queueing delay is the time a payload waits in the queue before being served. The background
+/// load, when given, lowers the service rate to the share that the load leaves free.
+#[derive(Clone, Debug, Default)]
+pub struct FifoProcessor {
+    pub service_rate: Option<u32>,
+    pub queue_length: Option<u32>,
+    pub queue: VecDeque<(TimeMS, DPayload)>,
+    background: Vec<(TimeMS, f64)>,
+    withheld: u32,
+    stats: QueueStats,
+}
+
+impl FifoProcessor {
+    fn new(settings: &ProcessorSettings) -> Self {
+        if settings.service_rate == Some(0) {
+            error!("Service rate of a fifo processor must be positive.");
+            panic!("Fifo processor with zero service rate never serves its queue.");
+        }
+        Self {
+            service_rate: settings.service_rate,
+            queue_length: settings.queue_length,
+            queue: VecDeque::new(),
+            background: Vec::new(),
+            withheld: 0,
+            stats: QueueStats::default(),
+        }
+    }
+
+    /// Busy fraction of the processor at the step, from the last entry of the trace before it.
+    fn busy_at(&self, step: TimeMS) -> f64 {
+        let next = self.background.partition_point(|(at, _)| *at <= step);
+        match next.checked_sub(1) {
+            Some(idx) => self.background[idx].1.clamp(0.0, 1.0),
+            None => 0.0,
+        }
+    }
+
+    fn free_rate(&mut self, step: TimeMS) -> Option<u32> {
+        let rate = self.service_rate?;
+        let free = (rate as f64 * (1.0 - self.busy_at(step))).floor() as u32;
+        self.withheld = rate - free;
+        Some(free)
+    }
+
+    fn enqueue(&mut self, payloads: Vec<DPayload>, step: TimeMS) -> Vec<DPayload> {
+        let mut dropped = Vec::new();
+        for payload in payloads.into_iter() {
+            if let Some(limit) = self.queue_length {
+                if self.queue.len() >= limit as usize {
+                    debug!(
+                        "Queue full, dropping payload {} from agent {}",
+                        payload.metadata.id, payload.agent_state.device_info.id
+                    );
+                    dropped.push(payload);
+                    continue;
+                }
+            }
+            self.queue.push_back((step, payload));
+        }
+        self.stats.dropped += dropped.len() as u64;
+        dropped
+    }
+
+    fn dequeue(&mut self, step: TimeMS) -> Option<Vec<DPayload>> {
+        let free_rate = self.free_rate(step);
+        if self.queue.is_empty() {
+            self.stats.queued = 0;
+            return None;
+        }
+        let to_serve = match free_rate {
+            Some(rate) => self.queue.len().min(rate as usize),
+            None => self.queue.len(),
+        };
+        let served: Vec<DPayload> = self
+            .queue
+            .drain(..to_serve)
+            .map(|(queued_at, payload)| {
+                let delay = TimeMS::from(step.as_u64() - queued_at.as_u64());
+                debug!(
+                    "Payload {} served after a queueing delay of {}",
+                    payload.metadata.id, delay
+                );
+                self.stats.total_delay += delay;
+                self.stats.max_delay = self.stats.max_delay.max(delay);
+                payload
+            })
+            .collect();
+        self.stats.served += served.len() as u64;
+        self.stats.queued = self.queue.len() as u64;
+        Some(served)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::message::{DeviceContent, PayloadInfo};
+
+    fn settings(service_rate: Option<u32>, queue_length: Option<u32>) -> ProcessorSettings {
+        ProcessorSettings {
+            name: "fifo".to_string(),
+            service_rate,
+            queue_length,
+            utilization_trace: None,
+        }
+    }
+
+    fn payloads(markers: &[u32]) -> Option<Vec<DPayload>> {
+        let payloads = markers
+            .iter()
+            .map(|marker| DPayload {
+                agent_state: DeviceContent::default(),
+                metadata: PayloadInfo {
+                    total_count: *marker,
+                    ..Default::default()
+                },
+                gathered_states: None,
+            })
+            .collect();
+        Some(payloads)
+    }
+
+    fn markers(payloads: &Option<Vec<DPayload>>) -> Vec<u32> {
+        payloads
+            .iter()
+            .flatten()
+            .map(|payload| payload.metadata.total_count)
+            .collect()
+    }
+
+    #[test]
+    fn test_full_queue_drops_arrivals() {
+        let mut processor = Processor::with_settings(&settings(Some(1), Some(2)));
+        let (served, dropped) = processor.process(payloads(&[1, 2, 3, 4]), TimeMS::from(0));
+        assert_eq!(markers(&served), vec![1]);
+        assert_eq!(dropped.len(), 2);
+        assert_eq!(processor.queue_size(), 1);
+
+        let (served, dropped) = processor.process(payloads(&[5, 6]), TimeMS::from(100));
+        assert_eq!(markers(&served), vec![2]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].metadata.total_count, 6);
+    }
+
+    #[test]
+    fn test_payloads_are_served_in_arrival_order() {
+        let mut processor = Processor::with_settings(&settings(Some(2), None));
+        let (served, _) = processor.process(payloads(&[1, 2, 3]), TimeMS::from(0));
+        assert_eq!(markers(&served), vec![1, 2]);
+        let (served, _) = processor.process(payloads(&[4]), TimeMS::from(100));
+        assert_eq!(markers(&served), vec![3, 4]);
+        let (served, _) = processor.process(None, TimeMS::from(200));
+        assert!(served.is_none());
+    }
+
+    #[test]
+    fn test_queueing_delay_is_recorded() {
+        let mut processor = Processor::with_settings(&settings(Some(1), Some(2)));
+        processor.process(payloads(&[1, 2, 3]), TimeMS::from(0));
+        let stats = processor
+            .take_stats()
+            .expect("fifo processor has statistics");
+        assert_eq!(stats.served, 1);
+        assert_eq!(stats.dropped, 1);
+        assert_eq!(stats.queued, 1);
+        assert_eq!(stats.max_delay, TimeMS::from(0));
+
+        processor.process(None, TimeMS::from(100));
+        let stats = processor
+            .take_stats()
+            .expect("fifo processor has statistics");
+        assert_eq!(stats.served, 1);
+        assert_eq!(stats.dropped, 0);
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.total_delay, TimeMS::from(100));
+        assert_eq!(stats.max_delay, TimeMS::from(100));
+    }
+
+    #[test]
+    fn test_instant_processor_has_no_statistics() {
+        let mut processor = Processor::default();
+        let (served, dropped) = processor.process(payloads(&[1, 2]), TimeMS::from(0));
+        assert_eq!(markers(&served), vec![1, 2]);
+        assert!(dropped.is_empty());
+        assert!(processor.take_stats().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_service_rate_is_rejected() {
+        Processor::with_settings(&settings(Some(0), None));
+    }
+}
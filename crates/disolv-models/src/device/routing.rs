@@ -0,0 +1,257 @@
+use crate::device::actions::should_i_forward;
+use crate::device::types::DeviceClass;
+use crate::net::message::{DPayload, DataBlob, DataType, DeviceContent};
+use disolv_core::bucket::TimeMS;
+use disolv_core::group::GroupId;
+use disolv_core::hashbrown::HashMap;
+use log::{debug, error};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+
+/// Settings of a match-action rule. A rule matches the blobs of a data type, received from an
+/// agent of the source class, that are about to be sent to an agent of the target class. A
+/// missing field matches anything. The `action` is one of `forward`, `drop`, `duplicate` or
+/// `redirect`, which needs the class in `to_class`.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct RuleSettings {
+    pub data_type: Option<DataType>,
+    pub source_class: Option<DeviceClass>,
+    pub target_class: Option<DeviceClass>,
+    pub action: String,
+    pub to_class: Option<DeviceClass>,
+}
+
+/// Rules that replace the rules of the controller from the time `at`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RuleUpdateSettings {
+    pub at: TimeMS,
+    pub rules: Vec<RuleSettings>,
+}
+
+/// Settings of a controller class that programs the forwarding of the agents of the target
+/// class. The controllers send their rules in the control messages composed for the target
+/// class, and an agent installs the rules of a control message when it receives it. The rules
+/// sent are the ones in force when the message was composed.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct ControllerSettings {
+    pub target_class: DeviceClass,
+    pub rules: Vec<RuleSettings>,
+    pub updates: Option<Vec<RuleUpdateSettings>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteAction {
+    Forward,
+    Drop,
+    Duplicate,
+    Redirect(DeviceClass),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MatchRule {
+    data_type: Option<DataType>,
+    source_class: Option<DeviceClass>,
+    target_class: Option<DeviceClass>,
+    action: RouteAction,
+}
+
+impl MatchRule {
+    pub fn new(settings: &RuleSettings) -> Self {
+        let action = match (settings.action.to_lowercase().as_str(), settings.to_class) {
+            ("forward", _) => RouteAction::Forward,
+            ("drop", _) => RouteAction::Drop,
+            ("duplicate", _) => RouteAction::Duplicate,
+            ("redirect", Some(to_class)) => RouteAction::Redirect(to_class),
+            ("redirect", None) => {
+                error!("A redirect rule needs the class to redirect to");
+                panic!("Missing to_class for the redirect rule.");
+            }
+            _ => {
+                error!("Only forward, drop, duplicate and redirect rules are supported");
+                panic!("Unsupported rule action {}.", settings.action);
+            }
+        };
+        Self {
+            data_type: settings.data_type,
+            source_class: settings.source_class,
+            target_class: settings.target_class,
+            action,
+        }
+    }
+
+    fn matches(
+        &self,
+        data_type: DataType,
+        source_class: DeviceClass,
+        target_class: DeviceClass,
+    ) -> bool {
+        self.data_type.is_none_or(|rule| rule == data_type)
+            && self.source_class.is_none_or(|rule| rule == source_class)
+            && self.target_class.is_none_or(|rule| rule == target_class)
+    }
+}
+
+impl Display for MatchRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let any = |field: Option<String>| field.unwrap_or_else(|| "*".to_string());
+        write!(
+            f,
+            "{} from {} to {}: ",
+            any(self.data_type.map(|data_type| data_type.to_string())),
+            any(self.source_class.map(|class| class.to_string())),
+            any(self.target_class.map(|class| class.to_string())),
+        )?;
+        match self.action {
+            RouteAction::Forward => write!(f, "forward"),
+            RouteAction::Drop => write!(f, "drop"),
+            RouteAction::Duplicate => write!(f, "duplicate"),
+            RouteAction::Redirect(to_class) => write!(f, "redirect to {}", to_class),
+        }
+    }
+}
+
+/// Rules of the controllers for every controlled class, in the order they come into force.
+#[derive(Clone, Debug, Default)]
+pub struct RuleBook {
+    schedules: HashMap<DeviceClass, Vec<(TimeMS, Vec<MatchRule>)>>,
+}
+
+impl RuleBook {
+    pub fn new(controllers: &[&ControllerSettings]) -> Self {
+        let mut schedules: HashMap<DeviceClass, Vec<(TimeMS, Vec<MatchRule>)>> = HashMap::new();
+        for controller in controllers.iter() {
+            let schedule = schedules.entry(controller.target_class).or_default();
+            if !schedule.is_empty() {
+                error!("Only one controller class can program a class");
+                panic!("Class {} has several controllers.", controller.target_class);
+            }
+            schedule.push((
+                TimeMS::default(),
+                controller.rules.iter().map(MatchRule::new).collect(),
+            ));
+            for update in controller.updates.iter().flatten() {
+                schedule.push((update.at, update.rules.iter().map(MatchRule::new).collect()));
+            }
+            schedule.sort_by_key(|(at, _)| *at);
+        }
+        Self { schedules }
+    }
+
+    pub fn controls(&self, device_class: &DeviceClass) -> bool {
+        self.schedules.contains_key(device_class)
+    }
+
+    /// Rules for the class in force at the time, with the time they came into force.
+    pub fn rules_at(
+        &self,
+        device_class: &DeviceClass,
+        time: TimeMS,
+    ) -> Option<(TimeMS, &[MatchRule])> {
+        self.schedules
+            .get(device_class)?
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= time)
+            .map(|(at, rules)| (*at, rules.as_slice()))
+    }
+}
+
+/// Rules installed in an agent by the control messages. The first matching rule decides what
+/// happens to a blob, and blobs without a matching rule follow the actions of their sender. The
+/// table is empty until the first control message is received, and older rules than the
+/// installed ones are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct RuleTable {
+    rules: Vec<MatchRule>,
+    version: Option<TimeMS>,
+}
+
+impl RuleTable {
+    /// Installs the rules that came into force at the version. Returns whether the rules changed.
+    pub fn install(&mut self, version: TimeMS, rules: &[MatchRule]) -> bool {
+        if self.version.is_some_and(|installed| installed >= version) {
+            return false;
+        }
+        self.version = Some(version);
+        self.rules = rules.to_vec();
+        true
+    }
+
+    pub fn rules(&self) -> &[MatchRule] {
+        &self.rules
+    }
+
+    pub fn version(&self) -> Option<TimeMS> {
+        self.version
+    }
+
+    pub fn decide(
+        &self,
+        data_type: DataType,
+        source_class: DeviceClass,
+        target_class: DeviceClass,
+    ) -> Option<RouteAction> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(data_type, source_class, target_class))
+            .map(|rule| rule.action)
+    }
+
+    /// Prepares the blobs to forward to the target following the rules. The blobs redirected to
+    /// the class of the target from the other classes the agent talks to are added as well.
+    ///
+    /// # Arguments
+    /// * `target_info` - The target agent details
+    /// * `target_groups` - The groups the target agent is a member of
+    /// * `talked_classes` - Classes the agent sends to
+    /// * `to_forward` - Payloads that requested to be forwarded
+    ///
+    /// # Returns
+    /// * `Vec<DataBlob>` - List of data blobs that need to be forwarded
+    pub fn route_blobs_to_fwd(
+        &self,
+        target_info: &DeviceContent,
+        target_groups: &[GroupId],
+        talked_classes: &[DeviceClass],
+        to_forward: &[DPayload],
+    ) -> Vec<DataBlob> {
+        let target_class = target_info.device_info.device_class;
+        let mut blobs_to_forward: Vec<DataBlob> = Vec::new();
+        for payload in to_forward.iter() {
+            let source_class = payload.agent_state.device_info.device_class;
+            for blob in payload.metadata.data_blobs.iter() {
+                let copies = match self.decide(blob.data_type, source_class, target_class) {
+                    Some(RouteAction::Forward) => 1,
+                    Some(RouteAction::Duplicate) => 2,
+                    Some(RouteAction::Drop) | Some(RouteAction::Redirect(_)) => 0,
+                    None => usize::from(should_i_forward(
+                        blob,
+                        &target_info.device_info,
+                        target_groups,
+                    )),
+                };
+                let redirected = talked_classes
+                    .iter()
+                    .filter(|class| **class != target_class)
+                    .filter(|class| {
+                        self.decide(blob.data_type, source_class, **class)
+                            == Some(RouteAction::Redirect(target_class))
+                    })
+                    .count();
+                debug!(
+                    "Routing {} copies of blob {} from agent {} to agent {}",
+                    copies + redirected,
+                    blob.data_type,
+                    payload.agent_state.device_info.id,
+                    target_info.device_info.id
+                );
+                for _ in 0..copies + redirected {
+                    blobs_to_forward.push(blob.to_owned());
+                }
+            }
+        }
+        blobs_to_forward
+    }
+}
@@ -1,5 +1,7 @@
 use crate::device::types::{DeviceClass, DeviceStats};
 use crate::net::radio::DLink;
+use disolv_core::bucket::TimeMS;
+use disolv_core::group::GroupId;
 use disolv_core::model::{Model, ModelSettings};
 use log::error;
 use serde::Deserialize;
@@ -11,6 +13,8 @@ pub struct SelectorSettings {
     pub name: String,
     pub link_count: Option<u32>,
     pub dist_threshold: Option<f32>,
+    pub target_group: Option<GroupId>,
+    pub lifetime_weight: Option<f32>,
 }
 
 impl ModelSettings for SelectorSettings {}
@@ -24,6 +28,8 @@ pub enum Selector {
     Random(RandomSelector),
     MinimumNeighbors(MinimumNeighborSelector),
     MinimumData(MinimumDataSelector),
+    Stable(StableSelector),
+    Lifetime(LifetimeSelector),
 }
 
 impl Model for Selector {
@@ -37,8 +43,10 @@ impl Model for Selector {
             "random" => Selector::Random(RandomSelector::new(settings)),
             "min_neighbors" => Selector::Random(RandomSelector::new(settings)),
             "min_data" => Selector::Random(RandomSelector::new(settings)),
+            "stable" => Selector::Stable(StableSelector::new(settings)),
+            "lifetime" => Selector::Lifetime(LifetimeSelector::new(settings)),
             _ => {
-                error!("Only basic, nearest, random, min_neighbors and min_data neighbors are supported");
+                error!("Only basic, nearest, random, min_neighbors, min_data, stable and lifetime neighbors are supported");
                 panic!("Unsupported selector type {}.", settings.name);
             }
         }
@@ -46,8 +54,52 @@ impl Model for Selector {
 }
 
 impl Selector {
-    pub fn do_selection(&self, links: Vec<DLink>, stats: &Vec<&DeviceStats>) -> Vec<DLink> {
-        if links.len() == 1 {
+    /// Selectors that need the predicted distances of the links to select them.
+    pub fn uses_forecast(&self) -> bool {
+        matches!(self, Selector::Stable(_))
+    }
+
+    /// Changes the thresholds of the selection. Thresholds that are not given are kept.
+    pub fn set_thresholds(&mut self, link_count: Option<u32>, dist_threshold: Option<f32>) {
+        let (count, threshold) = match self {
+            Selector::None | Selector::All => return,
+            Selector::Nearest(selector) => (&mut selector.link_count, &mut selector.dist_threshold),
+            Selector::Random(selector) => (&mut selector.link_count, &mut selector.dist_threshold),
+            Selector::MinimumNeighbors(selector) => {
+                (&mut selector.link_count, &mut selector.dist_threshold)
+            }
+            Selector::MinimumData(selector) => {
+                (&mut selector.link_count, &mut selector.dist_threshold)
+            }
+            Selector::Stable(selector) => (&mut selector.link_count, &mut selector.dist_threshold),
+            Selector::Lifetime(selector) => {
+                (&mut selector.link_count, &mut selector.dist_threshold)
+            }
+        };
+        if link_count.is_some() {
+            *count = link_count;
+        }
+        if dist_threshold.is_some() {
+            *threshold = dist_threshold;
+        }
+    }
+
+    /// Selectors that need the expected remaining lifetimes of the links to select them.
+    pub fn uses_lifetimes(&self) -> bool {
+        matches!(self, Selector::Lifetime(_))
+    }
+
+    /// Selects the links to transfer the data. The forecast holds the predicted distance to the
+    /// target of each link, if the positions of the agents are predicted. The lifetimes hold the
+    /// expected remaining lifetime of each link, if the lifetimes of the links are tracked.
+    pub fn do_selection(
+        &self,
+        links: Vec<DLink>,
+        stats: &Vec<&DeviceStats>,
+        forecast: &[Option<f32>],
+        lifetimes: &[Option<TimeMS>],
+    ) -> Vec<DLink> {
+        if links.len() == 1 && !self.uses_forecast() && !self.uses_lifetimes() {
             return links;
         }
 
@@ -58,6 +110,8 @@ impl Selector {
             Selector::Nearest(selector) => selector.select_link(links),
             Selector::MinimumNeighbors(selector) => selector.select_link(links, stats),
             Selector::MinimumData(selector) => selector.select_link(links, stats),
+            Selector::Stable(selector) => selector.select_link(links, forecast),
+            Selector::Lifetime(selector) => selector.select_link(links, lifetimes),
         }
     }
 }
@@ -137,3 +191,114 @@ impl MinimumDataSelector {
         links
     }
 }
+
+/// Selects the links whose targets are predicted to be within `dist_threshold` at the end of the
+/// prediction horizon, nearest first. Links without a forecast are judged by their current
+/// distance.
+#[derive(Clone, Debug, Default)]
+pub struct StableSelector {
+    pub link_count: Option<u32>,
+    pub dist_threshold: Option<f32>,
+}
+
+impl StableSelector {
+    fn new(settings: &SelectorSettings) -> Self {
+        Self {
+            link_count: settings.link_count,
+            dist_threshold: settings.dist_threshold,
+        }
+    }
+
+    fn select_link(&self, links: Vec<DLink>, forecast: &[Option<f32>]) -> Vec<DLink> {
+        let mut candidates: Vec<(f32, DLink)> = links
+            .into_iter()
+            .enumerate()
+            .map(|(idx, link)| {
+                let distance = forecast
+                    .get(idx)
+                    .copied()
+                    .flatten()
+                    .or(link.properties.distance)
+                    .unwrap_or(f32::MAX);
+                (distance, link)
+            })
+            .filter(|(distance, _)| match self.dist_threshold {
+                Some(threshold) => *distance <= threshold,
+                None => true,
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candidates
+            .into_iter()
+            .take(self.link_count.unwrap_or(1) as usize)
+            .map(|(_, link)| link)
+            .collect()
+    }
+}
+
+/// Selects the links that are expected to last the longest. The expected remaining lifetime of
+/// a link is weighed against its distance with `lifetime_weight`, both scaled to the largest
+/// among the links. With the default weight of 1, only the lifetime counts and with 0, only the
+/// distance counts. Links beyond `dist_threshold` are not selected.
+#[derive(Clone, Debug, Default)]
+pub struct LifetimeSelector {
+    pub link_count: Option<u32>,
+    pub dist_threshold: Option<f32>,
+    pub lifetime_weight: f32,
+}
+
+impl LifetimeSelector {
+    fn new(settings: &SelectorSettings) -> Self {
+        let lifetime_weight = settings.lifetime_weight.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&lifetime_weight) {
+            error!("Lifetime weight of the selector must be between 0 and 1");
+            panic!("Invalid lifetime weight {}.", lifetime_weight);
+        }
+        Self {
+            link_count: settings.link_count,
+            dist_threshold: settings.dist_threshold,
+            lifetime_weight,
+        }
+    }
+
+    fn select_link(&self, links: Vec<DLink>, lifetimes: &[Option<TimeMS>]) -> Vec<DLink> {
+        let longest = lifetimes
+            .iter()
+            .flatten()
+            .map(|lifetime| lifetime.as_u64())
+            .max()
+            .unwrap_or_default()
+            .max(1) as f32;
+        let farthest = links
+            .iter()
+            .filter_map(|link| link.properties.distance)
+            .fold(f32::EPSILON, f32::max);
+        let mut candidates: Vec<(f32, DLink)> = links
+            .into_iter()
+            .enumerate()
+            .filter(
+                |(_, link)| match (self.dist_threshold, link.properties.distance) {
+                    (Some(threshold), Some(distance)) => distance <= threshold,
+                    _ => true,
+                },
+            )
+            .map(|(idx, link)| {
+                let lifetime = lifetimes
+                    .get(idx)
+                    .copied()
+                    .flatten()
+                    .map_or(0.0, |lifetime| lifetime.as_u64() as f32);
+                let distance = link.properties.distance.unwrap_or(farthest);
+                let score = self.lifetime_weight * lifetime / longest
+                    - (1.0 - self.lifetime_weight) * distance / farthest;
+                (score, link)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates
+            .into_iter()
+            .take(self.link_count.unwrap_or(1) as usize)
+            .map(|(_, link)| link)
+            .collect()
+    }
+}
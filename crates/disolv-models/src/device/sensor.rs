@@ -0,0 +1,154 @@
+use crate::device::mobility::{MapState, Point2D};
+use crate::device::types::DeviceClass;
+use crate::net::message::{DataBlob, DataType};
+use crate::net::metrics::Bytes;
+use crate::net::radio::Action;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::model::{Model, ModelSettings};
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the on-board sensors used for collective perception. The field of view is
+/// given in degrees around the heading of the agent; sensors see all around when it is not
+/// given. Perception messages carry a header and a fixed size per detected object.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SensorSettings {
+    pub name: String,
+    pub target_class: DeviceClass,
+    pub sensing_range: f64,
+    pub field_of_view: Option<f64>,
+    pub header_size: Bytes,
+    pub object_size: Bytes,
+}
+
+impl ModelSettings for SensorSettings {}
+
+#[derive(Clone, Debug)]
+pub enum Sensor {
+    Perception(PerceptionSensor),
+}
+
+impl Model for Sensor {
+    type Settings = SensorSettings;
+
+    fn with_settings(settings: &SensorSettings) -> Self {
+        match settings.name.to_lowercase().as_str() {
+            "perception" => Sensor::Perception(PerceptionSensor::new(settings)),
+            _ => {
+                error!("Only Perception sensor is supported.");
+                panic!("Unsupported sensor type {}.", settings.name);
+            }
+        }
+    }
+}
+
+impl Sensor {
+    /// Detects the neighbouring agents that are within the sensing range and field of view.
+    pub fn sense(&mut self, map_state: &MapState, neighbours: &[(AgentId, Point2D)]) {
+        match self {
+            Sensor::Perception(sensor) => sensor.sense(map_state, neighbours),
+        }
+    }
+
+    pub fn sensing_range(&self) -> f64 {
+        match self {
+            Sensor::Perception(sensor) => sensor.sensing_range,
+        }
+    }
+
+    pub fn detected(&self) -> &[AgentId] {
+        match self {
+            Sensor::Perception(sensor) => &sensor.detected,
+        }
+    }
+
+    /// A collective perception message with the objects detected in this step.
+    pub fn perception_blob(&self, target_class: &DeviceClass, now: TimeMS) -> Option<DataBlob> {
+        match self {
+            Sensor::Perception(sensor) => sensor.perception_blob(target_class, now),
+        }
+    }
+}
+
+/// A sensor that detects the agents around it. The heading of the agent is derived from
+/// its last two positions, so a stationary agent keeps its last heading.
+#[derive(Clone, Debug)]
+pub struct PerceptionSensor {
+    pub target_class: DeviceClass,
+    pub sensing_range: f64,
+    pub field_of_view: Option<f64>,
+    pub header_size: Bytes,
+    pub object_size: Bytes,
+    pub heading: Option<f64>,
+    pub last_position: Option<Point2D>,
+    pub detected: Vec<AgentId>,
+}
+
+impl PerceptionSensor {
+    pub fn new(settings: &SensorSettings) -> Self {
+        Self {
+            target_class: settings.target_class,
+            sensing_range: settings.sensing_range,
+            field_of_view: settings.field_of_view.map(|fov| fov.to_radians()),
+            header_size: settings.header_size,
+            object_size: settings.object_size,
+            heading: None,
+            last_position: None,
+            detected: Vec::new(),
+        }
+    }
+
+    fn sense(&mut self, map_state: &MapState, neighbours: &[(AgentId, Point2D)]) {
+        self.update_heading(&map_state.pos);
+        let position = map_state.pos;
+        self.detected = neighbours
+            .iter()
+            .filter(|(_, other)| self.is_visible(&position, other))
+            .map(|(agent_id, _)| *agent_id)
+            .collect();
+    }
+
+    fn update_heading(&mut self, position: &Point2D) {
+        if let Some(last) = self.last_position {
+            let dx = position.x - last.x;
+            let dy = position.y - last.y;
+            if dx != 0.0 || dy != 0.0 {
+                self.heading = Some(dy.atan2(dx));
+            }
+        }
+        self.last_position = Some(*position);
+    }
+
+    fn is_visible(&self, position: &Point2D, other: &Point2D) -> bool {
+        let dx = other.x - position.x;
+        let dy = other.y - position.y;
+        if (dx * dx + dy * dy).sqrt() > self.sensing_range {
+            return false;
+        }
+        let (field_of_view, heading) = match (self.field_of_view, self.heading) {
+            (Some(fov), Some(heading)) => (fov, heading),
+            _ => return true,
+        };
+        let mut offset = (dy.atan2(dx) - heading).abs() % std::f64::consts::TAU;
+        if offset > std::f64::consts::PI {
+            offset = std::f64::consts::TAU - offset;
+        }
+        offset <= field_of_view / 2.0
+    }
+
+    fn perception_blob(&self, target_class: &DeviceClass, now: TimeMS) -> Option<DataBlob> {
+        if self.target_class != *target_class {
+            return None;
+        }
+        let object_bytes = self.object_size.as_u64() * self.detected.len() as u64;
+        Some(
+            DataBlob::builder()
+                .data_type(DataType::CPM)
+                .data_size(self.header_size + Bytes::new(object_bytes))
+                .action(Action::default())
+                .created_at(now)
+                .build(),
+        )
+    }
+}
@@ -0,0 +1,115 @@
+use crate::device::types::DeviceClass;
+use crate::net::radio::DLink;
+use log::error;
+use rand::seq::SliceRandom;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the density throttling of an agent class. An agent sends to at most
+/// `max_targets` of the targets selected for a class in a step. The targets are picked with the
+/// `policy`: `nearest` picks the closest targets, `random` picks them at random and
+/// `round_robin` takes turns over the targets in the following steps.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ThrottleSettings {
+    pub max_targets: u32,
+    pub policy: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottlePolicy {
+    Nearest,
+    Random,
+    RoundRobin,
+}
+
+/// Outcome of throttling the targets of a step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Throttled {
+    pub engaged: bool,
+    pub dropped: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Throttle {
+    max_targets: usize,
+    policy: ThrottlePolicy,
+    rng: Pcg64Mcg,
+    cursors: Vec<(DeviceClass, usize)>,
+}
+
+impl Throttle {
+    pub fn new(settings: &ThrottleSettings, seed: u64) -> Self {
+        let policy = match settings.policy.to_lowercase().as_str() {
+            "nearest" => ThrottlePolicy::Nearest,
+            "random" => ThrottlePolicy::Random,
+            "round_robin" => ThrottlePolicy::RoundRobin,
+            _ => {
+                error!("Only nearest, random and round_robin throttling are supported");
+                panic!("Unsupported throttling policy {}.", settings.policy);
+            }
+        };
+        if settings.max_targets == 0 {
+            error!("Throttling needs at least one target per step");
+            panic!(
+                "Invalid max_targets {} for throttling.",
+                settings.max_targets
+            );
+        }
+        Self {
+            max_targets: settings.max_targets as usize,
+            policy,
+            rng: Pcg64Mcg::new(seed as u128),
+            cursors: Vec::new(),
+        }
+    }
+
+    /// Keeps at most the maximum number of the links selected for the target class.
+    pub fn limit(&mut self, target_class: DeviceClass, links: &mut Vec<DLink>) -> Throttled {
+        if links.len() <= self.max_targets {
+            return Throttled::default();
+        }
+        let dropped = (links.len() - self.max_targets) as u64;
+        match self.policy {
+            ThrottlePolicy::Nearest => {
+                links.sort_by(|a, b| {
+                    let a = a.properties.distance.unwrap_or(f32::MAX);
+                    let b = b.properties.distance.unwrap_or(f32::MAX);
+                    a.total_cmp(&b)
+                });
+                links.truncate(self.max_targets);
+            }
+            ThrottlePolicy::Random => {
+                links.shuffle(&mut self.rng);
+                links.truncate(self.max_targets);
+            }
+            ThrottlePolicy::RoundRobin => {
+                links.sort_by_key(|link| link.target);
+                let max_targets = self.max_targets;
+                let cursor = self.cursor_of(target_class);
+                let start = *cursor % links.len();
+                *cursor = start + max_targets;
+                links.rotate_left(start);
+                links.truncate(self.max_targets);
+            }
+        }
+        Throttled {
+            engaged: true,
+            dropped,
+        }
+    }
+
+    fn cursor_of(&mut self, target_class: DeviceClass) -> &mut usize {
+        let idx = match self
+            .cursors
+            .iter()
+            .position(|(class, _)| *class == target_class)
+        {
+            Some(idx) => idx,
+            None => {
+                self.cursors.push((target_class, 0));
+                self.cursors.len() - 1
+            }
+        };
+        &mut self.cursors[idx].1
+    }
+}
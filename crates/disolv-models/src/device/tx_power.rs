@@ -0,0 +1,130 @@
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the transmission power control of an agent class. Transmissions at `max_power`
+/// dBm reach `max_range` m, and the range shrinks with the `path_loss_exponent` as the power is
+/// lowered down to `min_power` dBm. The power is picked with the `policy`: `fixed` transmits at
+/// `power` dBm, `distance` at the lowest power that reaches the target with a `margin` in dB
+/// and `energy` lowers the power from the maximum to the minimum as the battery drains. The
+/// energy of a transmission scales with its power relative to the maximum.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct TxPowerSettings {
+    pub policy: String,
+    pub max_power: f32,
+    pub min_power: Option<f32>,
+    pub max_range: f32,
+    pub power: Option<f32>,
+    pub margin: Option<f32>,
+    pub path_loss_exponent: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxPowerPolicy {
+    Fixed(f32),
+    Distance(f32),
+    EnergyAware,
+}
+
+/// Power picked for a transmission, the range at that power and whether the target is in it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TxPowerChoice {
+    pub power: f32,
+    pub range: f32,
+    pub reached: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TxPowerControl {
+    policy: TxPowerPolicy,
+    max_power: f32,
+    min_power: f32,
+    max_range: f32,
+    path_loss_exponent: f32,
+}
+
+impl TxPowerControl {
+    pub fn new(settings: &TxPowerSettings) -> Self {
+        let min_power = settings.min_power.unwrap_or(settings.max_power);
+        if min_power > settings.max_power {
+            error!("The minimum transmission power cannot exceed the maximum power");
+            panic!("Invalid minimum transmission power {}.", min_power);
+        }
+        if settings.max_range <= 0.0 {
+            error!("Transmissions at the maximum power must have a range");
+            panic!("Invalid transmission range {}.", settings.max_range);
+        }
+        let policy = match settings.policy.to_lowercase().as_str() {
+            "fixed" => {
+                let power = settings.power.unwrap_or(settings.max_power);
+                if !(min_power..=settings.max_power).contains(&power) {
+                    error!("Fixed transmission power must be between the minimum and the maximum");
+                    panic!("Invalid transmission power {}.", power);
+                }
+                TxPowerPolicy::Fixed(power)
+            }
+            "distance" => TxPowerPolicy::Distance(settings.margin.unwrap_or_default()),
+            "energy" => TxPowerPolicy::EnergyAware,
+            _ => {
+                error!("Only fixed, distance and energy power control are supported");
+                panic!("Unsupported power control policy {}.", settings.policy);
+            }
+        };
+        Self {
+            policy,
+            max_power: settings.max_power,
+            min_power,
+            max_range: settings.max_range,
+            path_loss_exponent: settings.path_loss_exponent.unwrap_or(3.0),
+        }
+    }
+
+    pub fn policy(&self) -> TxPowerPolicy {
+        self.policy
+    }
+
+    /// Range of a transmission at the power in dBm.
+    pub fn range_at(&self, power: f32) -> f32 {
+        let exponent = (power - self.max_power) / (10.0 * self.path_loss_exponent);
+        self.max_range * 10f32.powf(exponent)
+    }
+
+    /// Picks the power of a transmission to a target at the distance, if it is known. Agents
+    /// without a battery transmit at the maximum power with the energy-aware policy.
+    ///
+    /// # Arguments
+    /// * `distance` - The distance to the target in m
+    /// * `battery` - The level of the battery as a share of its capacity
+    pub fn choose(&self, distance: Option<f32>, battery: Option<f64>) -> TxPowerChoice {
+        let power = match self.policy {
+            TxPowerPolicy::Fixed(power) => power,
+            TxPowerPolicy::Distance(margin) => match distance {
+                Some(distance) => {
+                    let needed = self.max_power
+                        + 10.0 * self.path_loss_exponent * (distance / self.max_range).log10();
+                    needed + margin
+                }
+                None => self.max_power,
+            },
+            TxPowerPolicy::EnergyAware => match battery {
+                Some(share) => {
+                    self.min_power
+                        + (self.max_power - self.min_power) * share.clamp(0.0, 1.0) as f32
+                }
+                None => self.max_power,
+            },
+        };
+        let power = power.clamp(self.min_power, self.max_power);
+        let range = self.range_at(power);
+        TxPowerChoice {
+            power,
+            range,
+            reached: distance.is_none_or(|distance| distance <= range),
+        }
+    }
+
+    /// Share of the energy of a transmission at the maximum power spent at the power.
+    pub fn energy_factor(&self, power: f32) -> f64 {
+        10f64.powf((power - self.max_power) as f64 / 10.0)
+    }
+}
@@ -1,7 +1,8 @@
 use crate::net::message::DeviceContent;
+use crate::net::operator::OperatorId;
 use crate::net::radio::{IncomingStats, OutgoingStats};
 use disolv_core::agent::{AgentClass, AgentId, AgentKind, AgentOrder, AgentStats};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use typed_builder::TypedBuilder;
 
@@ -11,15 +12,18 @@ pub struct DeviceInfo {
     pub device_type: DeviceType,
     pub device_class: DeviceClass,
     pub agent_order: AgentOrder,
+    #[builder(default)]
+    pub operator: Option<OperatorId>,
 }
 
-#[derive(Deserialize, Default, Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum DeviceClass {
     #[default]
     None,
     Vehicle5G,
     RSU5G,
     BaseStation5G,
+    UAV5G,
     Controller,
 }
 
@@ -30,19 +34,34 @@ impl Display for DeviceClass {
             DeviceClass::Vehicle5G => write!(f, "Vehicle5G"),
             DeviceClass::RSU5G => write!(f, "RSU5G"),
             DeviceClass::BaseStation5G => write!(f, "BaseStation5G"),
+            DeviceClass::UAV5G => write!(f, "UAV5G"),
             DeviceClass::Controller => write!(f, "Controller"),
         }
     }
 }
 
+impl DeviceClass {
+    pub fn as_int(&self) -> u32 {
+        match self {
+            DeviceClass::None => 0,
+            DeviceClass::Vehicle5G => 1,
+            DeviceClass::RSU5G => 2,
+            DeviceClass::BaseStation5G => 3,
+            DeviceClass::UAV5G => 4,
+            DeviceClass::Controller => 5,
+        }
+    }
+}
+
 impl AgentClass for DeviceClass {}
 
-#[derive(Deserialize, Debug, Hash, Copy, Default, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Hash, Copy, Default, Clone, PartialEq, Eq)]
 pub enum DeviceType {
     #[default]
     Vehicle = 0,
     RSU,
     BaseStation,
+    UAV,
     Controller,
 }
 
@@ -52,6 +71,7 @@ impl Display for DeviceType {
             DeviceType::Vehicle => write!(f, "Vehicle"),
             DeviceType::RSU => write!(f, "RSU"),
             DeviceType::BaseStation => write!(f, "BaseStation"),
+            DeviceType::UAV => write!(f, "UAV"),
             DeviceType::Controller => write!(f, "Controller"),
         }
     }
@@ -59,6 +79,13 @@ impl Display for DeviceType {
 
 impl AgentKind for DeviceType {}
 
+impl DeviceType {
+    /// Checks if the agents of this type are part of the infrastructure serving the other agents.
+    pub fn is_infrastructure(&self) -> bool {
+        matches!(self, DeviceType::RSU | DeviceType::BaseStation)
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, TypedBuilder)]
 pub struct DeviceStats {
     pub outgoing_stats: OutgoingStats,
@@ -67,3 +94,35 @@ pub struct DeviceStats {
 }
 
 impl AgentStats for DeviceStats {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct AgentSettings {
+        device_type: DeviceType,
+        device_class: DeviceClass,
+    }
+
+    #[test]
+    fn test_uav_kinds_are_read_from_config() {
+        let settings: AgentSettings =
+            toml::from_str("device_type = \"UAV\"\ndevice_class = \"UAV5G\"")
+                .expect("UAV kinds are valid");
+        assert_eq!(settings.device_type, DeviceType::UAV);
+        assert_eq!(settings.device_class, DeviceClass::UAV5G);
+        assert_eq!(settings.device_type.to_string(), "UAV");
+        assert_eq!(settings.device_class.to_string(), "UAV5G");
+    }
+
+    #[test]
+    fn test_uavs_are_not_infrastructure() {
+        assert!(!DeviceType::UAV.is_infrastructure());
+        assert_ne!(DeviceClass::UAV5G.as_int(), DeviceClass::RSU5G.as_int());
+        assert_ne!(
+            DeviceClass::UAV5G.as_int(),
+            DeviceClass::Controller.as_int()
+        );
+    }
+}
@@ -1,14 +1,21 @@
-use rand_distr::{Distribution, Exp, Gamma, LogNormal, Normal, Uniform};
+use rand::Rng;
+use rand_distr::{Beta, Distribution, Exp, Gamma, LogNormal, Normal, Pareto, Uniform, Weibull};
 use rand_pcg::Pcg64Mcg;
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum DistType {
     Uniform(Uniform<f32>),
     Normal(Normal<f32>),
     LogNormal(LogNormal<f32>),
     Exponential(Exp<f32>),
     Gamma(Gamma<f32>),
+    Weibull(Weibull<f32>),
+    Beta(ScaledBeta),
+    Pareto(Pareto<f32>),
+    TruncatedNormal(TruncatedNormal),
+    Empirical(EmpiricalCdf),
 }
 
 #[serde_with::skip_serializing_none]
@@ -22,8 +29,52 @@ pub struct DistParams {
     pub scale: Option<f32>,
     pub shape: Option<f32>,
     pub rate: Option<f32>,
+    pub alpha: Option<f32>,
+    pub beta: Option<f32>,
     pub min: Option<f32>,
     pub max: Option<f32>,
+    pub cdf_file: Option<String>,
+}
+
+impl DistParams {
+    /// Resolves the CDF file relative to the given directory.
+    pub fn with_base_path(mut self, base_path: &Path) -> Self {
+        self.cdf_file = self
+            .cdf_file
+            .map(|file| base_path.join(file).to_string_lossy().to_string());
+        self
+    }
+
+    /// Uses the seed when the parameters do not give one.
+    pub fn with_default_seed(mut self, seed: u64) -> Self {
+        self.seed = self.seed.or(Some(seed));
+        self
+    }
+}
+
+/// Seeds of the random streams of a simulation. The seed of each stream is derived from the
+/// simulation seed and the name of the stream, so that the streams are reproducible and
+/// independent of each other and of the order they are created in.
+#[derive(Debug, Clone, Copy)]
+pub struct SeedRegistry {
+    seed: u64,
+}
+
+impl SeedRegistry {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed_for(&self, stream: &str) -> u64 {
+        // FNV-1a hash of the stream name mixed into the seed with SplitMix64.
+        let name_hash = stream.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        let mut z = (self.seed ^ name_hash).wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
 }
 
 impl DistType {
@@ -49,6 +100,26 @@ impl DistType {
                 Ok(dist) => dist,
                 Err(_) => panic!("Invalid distribution parameters"),
             },
+            "weibull" => match Self::build_weibull(params) {
+                Ok(dist) => dist,
+                Err(_) => panic!("Invalid distribution parameters"),
+            },
+            "beta" => match Self::build_beta(params) {
+                Ok(dist) => dist,
+                Err(_) => panic!("Invalid distribution parameters"),
+            },
+            "pareto" => match Self::build_pareto(params) {
+                Ok(dist) => dist,
+                Err(_) => panic!("Invalid distribution parameters"),
+            },
+            "truncated_normal" => match Self::build_truncated_normal(params) {
+                Ok(dist) => dist,
+                Err(e) => panic!("Invalid truncated normal distribution: {}", e),
+            },
+            "empirical" => match Self::build_empirical(params) {
+                Ok(dist) => dist,
+                Err(e) => panic!("Invalid empirical distribution: {}", e),
+            },
             _ => panic!("Invalid distribution name"),
         }
     }
@@ -81,6 +152,156 @@ impl DistType {
         let scale = dist_params.scale.ok_or("Missing scale")?;
         Ok(Self::Gamma(Gamma::new(shape, scale)?))
     }
+
+    fn build_weibull(dist_params: DistParams) -> Result<Self, Box<dyn std::error::Error>> {
+        let scale = dist_params.scale.ok_or("Missing scale")?;
+        let shape = dist_params.shape.ok_or("Missing shape")?;
+        Ok(Self::Weibull(Weibull::new(scale, shape)?))
+    }
+
+    fn build_beta(dist_params: DistParams) -> Result<Self, Box<dyn std::error::Error>> {
+        let alpha = dist_params.alpha.ok_or("Missing alpha")?;
+        let beta = dist_params.beta.ok_or("Missing beta")?;
+        Ok(Self::Beta(ScaledBeta {
+            beta: Beta::new(alpha, beta)?,
+            location: dist_params.location.unwrap_or(0.0),
+            scale: dist_params.scale.unwrap_or(1.0),
+        }))
+    }
+
+    fn build_pareto(dist_params: DistParams) -> Result<Self, Box<dyn std::error::Error>> {
+        let scale = dist_params.scale.ok_or("Missing scale")?;
+        let shape = dist_params.shape.ok_or("Missing shape")?;
+        Ok(Self::Pareto(Pareto::new(scale, shape)?))
+    }
+
+    fn build_truncated_normal(dist_params: DistParams) -> Result<Self, Box<dyn std::error::Error>> {
+        let mean = dist_params.mean.ok_or("Missing mean")?;
+        let std_dev = dist_params.std_dev.ok_or("Missing std_dev")?;
+        let min = dist_params.min.ok_or("Missing min")?;
+        let max = dist_params.max.ok_or("Missing max")?;
+        if min >= max {
+            return Err("min must be below max".into());
+        }
+        Ok(Self::TruncatedNormal(TruncatedNormal {
+            normal: Normal::new(mean, std_dev)?,
+            min,
+            max,
+        }))
+    }
+
+    fn build_empirical(dist_params: DistParams) -> Result<Self, Box<dyn std::error::Error>> {
+        let cdf_file = dist_params.cdf_file.ok_or("Missing cdf_file")?;
+        Ok(Self::Empirical(EmpiricalCdf::read(&PathBuf::from(
+            cdf_file,
+        ))?))
+    }
+}
+
+/// A beta distribution moved to `location` and stretched by `scale`, so that its samples lie
+/// between `location` and `location + scale`.
+#[derive(Debug, Clone)]
+pub struct ScaledBeta {
+    beta: Beta<f32>,
+    location: f32,
+    scale: f32,
+}
+
+impl ScaledBeta {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        self.location + self.scale * self.beta.sample(rng)
+    }
+}
+
+/// A normal distribution truncated to the samples between `min` and `max`. Samples outside the
+/// bounds are redrawn, and after too many redraws the last sample is clamped to the bounds.
+#[derive(Debug, Clone)]
+pub struct TruncatedNormal {
+    normal: Normal<f32>,
+    min: f32,
+    max: f32,
+}
+
+impl TruncatedNormal {
+    const MAX_DRAWS: usize = 100;
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        let mut sample = self.normal.sample(rng);
+        for _ in 1..Self::MAX_DRAWS {
+            if (self.min..=self.max).contains(&sample) {
+                return sample;
+            }
+            sample = self.normal.sample(rng);
+        }
+        sample.clamp(self.min, self.max)
+    }
+}
+
+/// An empirical distribution given as points of its cumulative distribution function. The
+/// CDF file has a value and its cumulative probability on each line, separated by a comma.
+/// Samples are drawn by inverse transform sampling with linear interpolation between points.
+#[derive(Debug, Clone)]
+pub struct EmpiricalCdf {
+    values: Vec<f32>,
+    probabilities: Vec<f32>,
+}
+
+impl EmpiricalCdf {
+    pub fn read(cdf_file: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(cdf_file)
+            .map_err(|e| format!("Failed to read {}: {}", cdf_file.display(), e))?;
+        let mut values = Vec::new();
+        let mut probabilities = Vec::new();
+        for line in content.lines().map(|line| line.trim()) {
+            let mut columns = line.split(',').map(|column| column.trim());
+            let (value, probability) = match (columns.next(), columns.next()) {
+                (Some(value), Some(probability)) => (value, probability),
+                _ => continue,
+            };
+            // Lines that are not numeric, e.g. the header, are skipped.
+            if let (Ok(value), Ok(probability)) = (value.parse(), probability.parse()) {
+                values.push(value);
+                probabilities.push(probability);
+            }
+        }
+        Self::new(values, probabilities)
+    }
+
+    pub fn new(
+        values: Vec<f32>,
+        probabilities: Vec<f32>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if values.is_empty() || values.len() != probabilities.len() {
+            return Err("CDF needs the same number of values and probabilities".into());
+        }
+        if probabilities.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err("CDF probabilities must be non-decreasing".into());
+        }
+        if values.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err("CDF values must be sorted".into());
+        }
+        if probabilities[probabilities.len() - 1] <= 0.0 {
+            return Err("CDF must end with a positive probability".into());
+        }
+        Ok(Self {
+            values,
+            probabilities,
+        })
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        let quantile: f32 = rng.gen_range(0.0..self.probabilities[self.probabilities.len() - 1]);
+        let idx = self.probabilities.partition_point(|prob| *prob < quantile);
+        if idx == 0 {
+            return self.values[0];
+        }
+        let (low_prob, high_prob) = (self.probabilities[idx - 1], self.probabilities[idx]);
+        let (low_value, high_value) = (self.values[idx - 1], self.values[idx]);
+        if high_prob == low_prob {
+            return high_value;
+        }
+        low_value + (high_value - low_value) * (quantile - low_prob) / (high_prob - low_prob)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -106,6 +327,11 @@ impl RngSampler {
             DistType::LogNormal(ref mut dist) => dist.sample(&mut self.rng),
             DistType::Exponential(ref mut dist) => dist.sample(&mut self.rng),
             DistType::Gamma(ref mut dist) => dist.sample(&mut self.rng),
+            DistType::Weibull(ref mut dist) => dist.sample(&mut self.rng),
+            DistType::Beta(ref dist) => dist.sample(&mut self.rng),
+            DistType::Pareto(ref dist) => dist.sample(&mut self.rng),
+            DistType::TruncatedNormal(ref dist) => dist.sample(&mut self.rng),
+            DistType::Empirical(ref dist) => dist.sample(&mut self.rng),
         }
     }
 }
@@ -2,3 +2,4 @@ pub mod bucket;
 pub mod device;
 pub mod dist;
 pub mod net;
+pub mod profile;
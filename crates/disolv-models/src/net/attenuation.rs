@@ -0,0 +1,284 @@
+use crate::device::mobility::Point2D;
+use crate::net::radio::LinkProperties;
+use disolv_core::bucket::TimeMS;
+use serde::Deserialize;
+
+/// An obstacle, e.g. a building, given by the corners of its outline. A link crossing the
+/// obstacle is attenuated by `loss` dB.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ObstacleSettings {
+    pub vertices: Vec<[f64; 2]>,
+    pub loss: f32,
+}
+
+/// Weather from `start` until `end`. A link is attenuated by `loss_per_km` dB for every km of
+/// the link inside the `area`, or for every km of the link when there is no area.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct WeatherSettings {
+    pub start: TimeMS,
+    pub end: Option<TimeMS>,
+    pub loss_per_km: f32,
+    pub area: Option<Vec<[f64; 2]>>,
+}
+
+/// Settings of the attenuation of the links by obstacles and weather. Every dB of attenuation
+/// raises the loss probability of the link by `loss_per_db`. Links attenuated by more than
+/// `max_attenuation` dB are not usable.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct AttenuationSettings {
+    pub obstacles: Option<Vec<ObstacleSettings>>,
+    pub weather: Option<Vec<WeatherSettings>>,
+    pub loss_per_db: f32,
+    pub max_attenuation: Option<f32>,
+}
+
+/// Running totals of the links attenuated and of the links blocked by the attenuation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AttenuationCounts {
+    pub attenuated: u64,
+    pub blocked: u64,
+}
+
+/// Closed polygon with its bounding box to skip the links that are far away from it.
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    vertices: Vec<Point2D>,
+    lower: Point2D,
+    upper: Point2D,
+}
+
+impl Polygon {
+    pub fn new(vertices: &[[f64; 2]]) -> Self {
+        if vertices.len() < 3 {
+            panic!("A polygon needs at least three vertices.");
+        }
+        let vertices: Vec<Point2D> = vertices
+            .iter()
+            .map(|vertex| Point2D::builder().x(vertex[0]).y(vertex[1]).build())
+            .collect();
+        let mut lower = vertices[0];
+        let mut upper = vertices[0];
+        for vertex in vertices.iter() {
+            lower.x = lower.x.min(vertex.x);
+            lower.y = lower.y.min(vertex.y);
+            upper.x = upper.x.max(vertex.x);
+            upper.y = upper.y.max(vertex.y);
+        }
+        Self {
+            vertices,
+            lower,
+            upper,
+        }
+    }
+
+    pub fn contains(&self, point: &Point2D) -> bool {
+        let mut inside = false;
+        let mut previous = self.vertices[self.vertices.len() - 1];
+        for vertex in self.vertices.iter() {
+            if (vertex.y > point.y) != (previous.y > point.y) {
+                let cross_x = vertex.x
+                    + (point.y - vertex.y) * (previous.x - vertex.x) / (previous.y - vertex.y);
+                if point.x < cross_x {
+                    inside = !inside;
+                }
+            }
+            previous = *vertex;
+        }
+        inside
+    }
+
+    /// Length of the segment between the points that lies inside the polygon. The segment is
+    /// split where it crosses the edges and every piece is either inside or outside.
+    pub fn length_inside(&self, from: &Point2D, to: &Point2D) -> f64 {
+        if from.x.max(to.x) < self.lower.x
+            || from.x.min(to.x) > self.upper.x
+            || from.y.max(to.y) < self.lower.y
+            || from.y.min(to.y) > self.upper.y
+        {
+            return 0.0;
+        }
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let mut cuts = vec![0.0, 1.0];
+        let mut previous = self.vertices[self.vertices.len() - 1];
+        for vertex in self.vertices.iter() {
+            let (ex, ey) = (vertex.x - previous.x, vertex.y - previous.y);
+            let denominator = dx * ey - dy * ex;
+            if denominator != 0.0 {
+                let (px, py) = (previous.x - from.x, previous.y - from.y);
+                let along_link = (px * ey - py * ex) / denominator;
+                let along_edge = (px * dy - py * dx) / denominator;
+                if (0.0..=1.0).contains(&along_link) && (0.0..=1.0).contains(&along_edge) {
+                    cuts.push(along_link);
+                }
+            }
+            previous = *vertex;
+        }
+        cuts.sort_by(|a, b| a.total_cmp(b));
+        let inside: f64 = cuts
+            .windows(2)
+            .filter(|pair| {
+                let middle = (pair[0] + pair[1]) / 2.0;
+                self.contains(
+                    &Point2D::builder()
+                        .x(from.x + dx * middle)
+                        .y(from.y + dy * middle)
+                        .build(),
+                )
+            })
+            .map(|pair| pair[1] - pair[0])
+            .sum();
+        inside * (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Obstacle {
+    outline: Polygon,
+    loss: f32,
+}
+
+#[derive(Clone, Debug)]
+struct Weather {
+    start: TimeMS,
+    end: Option<TimeMS>,
+    loss_per_km: f32,
+    area: Option<Polygon>,
+}
+
+impl Weather {
+    fn is_active(&self, now: TimeMS) -> bool {
+        now >= self.start && self.end.is_none_or(|end| now < end)
+    }
+
+    fn loss(&self, from: &Point2D, to: &Point2D) -> f32 {
+        let length = match self.area {
+            Some(ref area) => area.length_inside(from, to),
+            None => ((to.x - from.x).powi(2) + (to.y - from.y).powi(2)).sqrt(),
+        };
+        self.loss_per_km * (length / 1000.0) as f32
+    }
+}
+
+/// Obstacles in the field that attenuate the links crossing them.
+#[derive(Clone, Debug, Default)]
+pub struct Obstacles {
+    obstacles: Vec<Obstacle>,
+}
+
+impl Obstacles {
+    pub fn new(settings: &[ObstacleSettings]) -> Self {
+        Self {
+            obstacles: settings
+                .iter()
+                .map(|obstacle| Obstacle {
+                    outline: Polygon::new(&obstacle.vertices),
+                    loss: obstacle.loss,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+
+    /// Attenuation in dB of the link between the points by the obstacles it crosses.
+    pub fn obstruction(&self, from: &Point2D, to: &Point2D) -> f32 {
+        self.obstacles
+            .iter()
+            .filter(|obstacle| obstacle.outline.length_inside(from, to) > 0.0)
+            .map(|obstacle| obstacle.loss)
+            .sum()
+    }
+}
+
+/// Attenuates the links by the obstacles they cross and the weather at the time of the
+/// transfer. Obstruction already computed by the link producer is used instead of the
+/// obstacles.
+#[derive(Clone, Debug)]
+pub struct Attenuation {
+    obstacles: Obstacles,
+    weather: Vec<Weather>,
+    loss_per_db: f32,
+    max_attenuation: Option<f32>,
+    counts: AttenuationCounts,
+}
+
+impl Attenuation {
+    pub fn new(settings: &AttenuationSettings) -> Self {
+        let weather = settings
+            .weather
+            .iter()
+            .flatten()
+            .map(|weather| Weather {
+                start: weather.start,
+                end: weather.end,
+                loss_per_km: weather.loss_per_km,
+                area: weather.area.as_ref().map(|area| Polygon::new(area)),
+            })
+            .collect();
+        Self {
+            obstacles: Obstacles::new(settings.obstacles.as_deref().unwrap_or_default()),
+            weather,
+            loss_per_db: settings.loss_per_db,
+            max_attenuation: settings.max_attenuation,
+            counts: AttenuationCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> AttenuationCounts {
+        self.counts
+    }
+
+    /// Attenuation in dB of the link between the points at the given time.
+    pub fn attenuation_of(
+        &self,
+        link: &LinkProperties,
+        from: &Point2D,
+        to: &Point2D,
+        now: TimeMS,
+    ) -> f32 {
+        let obstruction = match link.obstruction {
+            Some(obstruction) => obstruction,
+            None => self.obstacles.obstruction(from, to),
+        };
+        let weather: f32 = self
+            .weather
+            .iter()
+            .filter(|weather| weather.is_active(now))
+            .map(|weather| weather.loss(from, to))
+            .sum();
+        obstruction + weather
+    }
+
+    /// Raises the loss of the link in both directions by its attenuation. Returns false when
+    /// the link is attenuated too much to be used.
+    pub fn attenuate(
+        &mut self,
+        link: &mut LinkProperties,
+        from: &Point2D,
+        to: &Point2D,
+        now: TimeMS,
+    ) -> bool {
+        let attenuation = self.attenuation_of(link, from, to, now);
+        if attenuation <= 0.0 {
+            return true;
+        }
+        self.counts.attenuated += 1;
+        if self
+            .max_attenuation
+            .is_some_and(|max_attenuation| attenuation > max_attenuation)
+        {
+            self.counts.blocked += 1;
+            return false;
+        }
+        let extra_loss = attenuation * self.loss_per_db;
+        for direction in [&mut link.uplink, &mut link.downlink] {
+            let loss = direction.loss.unwrap_or_default() + extra_loss;
+            direction.loss = Some(loss.min(1.0));
+        }
+        true
+    }
+}
@@ -0,0 +1,78 @@
+use crate::net::metrics::Bandwidth;
+use crate::net::slice::Slice;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use log::{info, warn};
+use serde::Deserialize;
+
+/// Settings of a trace of the capacities of the slices, such as a measured cellular capacity. The
+/// `trace_file` has the capacity in bytes per second of a slice from a time step on. Between the
+/// entries of a slice, the capacity stays at the previous entry, or changes linearly towards the
+/// next entry when `interpolate` is set. Slices keep their configured capacity until their first
+/// entry and the capacity of their last entry after it.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct CapacityTraceSettings {
+    pub trace_file: String,
+    pub interpolate: Option<bool>,
+}
+
+/// Capacities of the slices over time, applied to the slices at the start of every step.
+#[derive(Clone, Debug)]
+pub struct CapacityTrace {
+    entries: HashMap<u32, Vec<(TimeMS, Bandwidth)>>,
+    interpolate: bool,
+    applied: HashMap<u32, Bandwidth>,
+}
+
+impl CapacityTrace {
+    pub fn new(mut entries: HashMap<u32, Vec<(TimeMS, Bandwidth)>>, interpolate: bool) -> Self {
+        entries
+            .values_mut()
+            .for_each(|slice_entries| slice_entries.sort_by_key(|(time_step, _)| *time_step));
+        Self {
+            entries,
+            interpolate,
+            applied: HashMap::new(),
+        }
+    }
+
+    /// Capacity of the slice at the time, if the trace has reached the slice.
+    pub fn capacity_at(&self, slice_id: u32, time_step: TimeMS) -> Option<Bandwidth> {
+        let slice_entries = self.entries.get(&slice_id)?;
+        let next = slice_entries.partition_point(|(at, _)| *at <= time_step);
+        let (from, capacity) = *slice_entries.get(next.checked_sub(1)?)?;
+        let (to, next_capacity) = match (self.interpolate, slice_entries.get(next)) {
+            (true, Some(entry)) => *entry,
+            _ => return Some(capacity),
+        };
+        let progress =
+            (time_step.as_u64() - from.as_u64()) as f64 / (to.as_u64() - from.as_u64()) as f64;
+        let capacity = capacity.as_u64() as f64
+            + (next_capacity.as_u64() as f64 - capacity.as_u64() as f64) * progress;
+        Some(Bandwidth::new(capacity.round() as u64))
+    }
+
+    /// Sets the capacities of the slices at the time. Changes of the capacity are logged.
+    pub fn apply<'a>(&mut self, time_step: TimeMS, slices: impl Iterator<Item = &'a mut Slice>) {
+        for slice in slices {
+            let capacity = match self.capacity_at(slice.id, time_step) {
+                Some(capacity) => capacity,
+                None => continue,
+            };
+            if self.applied.get(&slice.id) == Some(&capacity) {
+                continue;
+            }
+            match slice.set_capacity(capacity) {
+                true => info!(
+                    "Slice {} capacity set to {} at step {}",
+                    slice.id,
+                    capacity.as_u64(),
+                    time_step
+                ),
+                false => warn!("Slice {} has no capacity to trace", slice.id),
+            }
+            self.applied.insert(slice.id, capacity);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::device::mobility::Point2D;
+use crate::device::types::DeviceInfo;
+use crate::net::radio::DLink;
+use disolv_core::agent::AgentId;
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the gaps in the coverage of the infrastructure, e.g. the remote parts of a rural
+/// map. The `coverage_file` is a grid of the field in square cells of `cell_size` m, with a line
+/// per row of cells starting at y = 0 and a comma separated value per cell starting at x = 0: 1
+/// where the infrastructure covers the cell and 0 where it does not. Cells outside the grid are
+/// covered.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct CoverageSettings {
+    pub coverage_file: String,
+    pub cell_size: f64,
+}
+
+/// Running totals of the link options that could not be used because one of their ends was in
+/// a coverage gap.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CoverageCounts {
+    pub gap_links: u64,
+}
+
+/// Coverage of the infrastructure over the field. Agents in a gap cannot use the infrastructure
+/// and only reach the agents of their own class over the sidelink.
+#[derive(Clone, Debug)]
+pub struct CoverageMap {
+    cell_size: f64,
+    rows: Vec<Vec<bool>>,
+    counts: CoverageCounts,
+}
+
+impl CoverageMap {
+    pub fn new(settings: &CoverageSettings, rows: Vec<Vec<bool>>) -> Self {
+        if settings.cell_size <= 0.0 {
+            error!("Cells of the coverage grid must have a size");
+            panic!("Invalid coverage cell size {}.", settings.cell_size);
+        }
+        Self {
+            cell_size: settings.cell_size,
+            rows,
+            counts: CoverageCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> CoverageCounts {
+        self.counts
+    }
+
+    pub fn is_covered(&self, position: &Point2D) -> bool {
+        if position.x < 0.0 || position.y < 0.0 {
+            return true;
+        }
+        let col = (position.x / self.cell_size).floor() as usize;
+        let row = (position.y / self.cell_size).floor() as usize;
+        self.rows
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .copied()
+            .unwrap_or(true)
+    }
+
+    pub fn register_gap_links(&mut self, count: usize) {
+        self.counts.gap_links += count as u64;
+    }
+
+    /// Infrastructure agents are never in a gap of their own coverage.
+    fn in_gap(&self, info: &DeviceInfo, position: &Point2D) -> bool {
+        !info.device_type.is_infrastructure() && !self.is_covered(position)
+    }
+
+    /// Filters the links over the infrastructure to the ones whose ends are both covered.
+    /// Targets without a position are covered.
+    ///
+    /// # Arguments
+    /// * `agent_info` - The details of the agent selecting the links
+    /// * `position` - The position of the agent selecting the links
+    /// * `links` - The link options of the agent
+    /// * `target_of` - The details and the position of the target agent of a link
+    ///
+    /// # Returns
+    /// * `Vec<DLink>` - The links the agent can use
+    pub fn usable_links<F>(
+        &self,
+        agent_info: &DeviceInfo,
+        position: &Point2D,
+        links: Vec<DLink>,
+        target_of: F,
+    ) -> Vec<DLink>
+    where
+        F: Fn(AgentId) -> (DeviceInfo, Option<Point2D>),
+    {
+        if self.in_gap(agent_info, position) {
+            return Vec::new();
+        }
+        links
+            .into_iter()
+            .filter(|link| match target_of(link.target) {
+                (info, Some(target)) => !self.in_gap(&info, &target),
+                (_, None) => true,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::net::message::{DPayload, TxFailReason, TxMetrics, TxStatus};
+use crate::net::metrics::{Bytes, Latency};
+use crate::net::radio::DLink;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use log::error;
+use serde::Deserialize;
+
+/// Settings of the fragmentation of the payloads larger than the MTU of their slice. At most
+/// `fragments_per_step` fragments of a payload are sent in a step, and all of them when it is
+/// not given. Fragments that find no bandwidth left are sent again in the next step, while lost
+/// fragments are not. The receiver drops a payload whose fragments did not all arrive within
+/// `reassembly_timeout` of its first fragment being sent.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FragmentSettings {
+    pub reassembly_timeout: TimeMS,
+    pub fragments_per_step: Option<u32>,
+}
+
+/// A payload that is sent in fragments of at most the MTU.
+#[derive(Clone, Debug)]
+pub struct Fragmented {
+    pub id: u64,
+    pub payload: DPayload,
+    pub link: DLink,
+    mtu: u64,
+    count: u32,
+    next: u32,
+}
+
+impl Fragmented {
+    pub fn is_sent(&self) -> bool {
+        self.next == self.count
+    }
+
+    /// The next fragment to send, which carries the metadata of the payload with the size of
+    /// the fragment.
+    pub fn next_fragment(&self) -> DPayload {
+        let size = self.payload.metadata.total_size.as_u64();
+        let sent = self.mtu * self.next as u64;
+        let mut fragment = self.payload.clone();
+        fragment.metadata.total_size = Bytes::new(self.mtu.min(size - sent));
+        fragment
+    }
+
+    pub fn advance(&mut self) {
+        self.next += 1;
+    }
+}
+
+/// Running totals of the payloads that were fragmented and of their fragments.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FragmentCounts {
+    pub fragmented: u64,
+    pub sent: u64,
+    pub lost: u64,
+}
+
+/// Fragmented payloads whose fragments are still being sent, in the order they were started.
+#[derive(Clone, Debug)]
+pub struct Fragments {
+    settings: FragmentSettings,
+    active: Vec<Fragmented>,
+    next_id: u64,
+    counts: FragmentCounts,
+}
+
+impl Fragments {
+    pub fn new(settings: &FragmentSettings) -> Self {
+        if settings.fragments_per_step == Some(0) {
+            error!("At least one fragment must be sent per step");
+            panic!("Invalid fragments per step 0.");
+        }
+        Self {
+            settings: *settings,
+            active: Vec::new(),
+            next_id: 0,
+            counts: FragmentCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> FragmentCounts {
+        self.counts
+    }
+
+    /// Whether the payload must be fragmented to be sent on a slice with the MTU.
+    pub fn is_needed(&self, payload: &DPayload, mtu: Bytes) -> bool {
+        payload.metadata.total_size.as_u64() > mtu.as_u64()
+    }
+
+    /// Splits the payload into fragments of the MTU. The fragments are sent from the step the
+    /// payload is started in.
+    pub fn start(&mut self, payload: DPayload, link: DLink, mtu: Bytes) -> Fragmented {
+        let mtu = mtu.as_u64().max(1);
+        let count = payload.metadata.total_size.as_u64().div_ceil(mtu) as u32;
+        self.next_id += 1;
+        self.counts.fragmented += 1;
+        Fragmented {
+            id: self.next_id,
+            payload,
+            link,
+            mtu,
+            count,
+            next: 0,
+        }
+    }
+
+    pub fn fragments_per_step(&self) -> u32 {
+        self.settings.fragments_per_step.unwrap_or(u32::MAX)
+    }
+
+    pub fn register_fragment(&mut self, tx_metrics: &TxMetrics) {
+        match tx_metrics.tx_status {
+            TxStatus::Ok => self.counts.sent += 1,
+            TxStatus::Fail => self.counts.lost += 1,
+        }
+    }
+
+    /// Takes the fragmented payloads to send their fragments of the step. The payloads with
+    /// fragments left are returned with `keep`.
+    pub fn take(&mut self) -> Vec<Fragmented> {
+        std::mem::take(&mut self.active)
+    }
+
+    pub fn keep(&mut self, fragmented: Fragmented) {
+        self.active.push(fragmented);
+    }
+}
+
+/// A payload the receiver is reassembling from its fragments.
+#[derive(Clone, Debug)]
+pub struct Partial {
+    pub payload: DPayload,
+    pub link: DLink,
+    pub sidelink: bool,
+    pub started: TimeMS,
+    expected: u32,
+    arrived: u32,
+    tx_metrics: TxMetrics,
+}
+
+impl Partial {
+    /// Metrics of the reassembled payload. The latency adds the time from the first fragment
+    /// until the last one was sent to the latency of the last fragment.
+    pub fn reassembled(&self, step: TimeMS) -> TxMetrics {
+        let mut tx_metrics = self.tx_metrics;
+        tx_metrics.payload_size = self.payload.metadata.total_size;
+        tx_metrics.latency =
+            Latency::new(tx_metrics.latency.as_u64() + step.as_u64() - self.started.as_u64());
+        tx_metrics.tx_status = TxStatus::Ok;
+        tx_metrics
+    }
+
+    /// Metrics of the payload dropped before all its fragments arrived.
+    pub fn incomplete(&self) -> TxMetrics {
+        let mut tx_metrics = TxMetrics::new(&self.payload, self.tx_metrics.tx_order);
+        tx_metrics.tx_status = TxStatus::Fail;
+        tx_metrics.tx_fail_reason = TxFailReason::Incomplete;
+        tx_metrics
+    }
+}
+
+/// Running totals of the payloads reassembled by the receivers and of those dropped
+/// incomplete.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReassemblyCounts {
+    pub reassembled: u64,
+    pub timed_out: u64,
+}
+
+/// Payloads the receivers are reassembling from their fragments.
+#[derive(Clone, Debug)]
+pub struct Reassembly {
+    timeout: TimeMS,
+    partial: HashMap<u64, Partial>,
+    counts: ReassemblyCounts,
+}
+
+impl Reassembly {
+    pub fn new(settings: &FragmentSettings) -> Self {
+        Self {
+            timeout: settings.reassembly_timeout,
+            partial: HashMap::new(),
+            counts: ReassemblyCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> ReassemblyCounts {
+        self.counts
+    }
+
+    pub fn pending(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// Expects the fragments of the payload at its target.
+    pub fn expect(&mut self, fragmented: &Fragmented, sidelink: bool, step: TimeMS) {
+        self.partial.insert(
+            fragmented.id,
+            Partial {
+                payload: fragmented.payload.clone(),
+                link: fragmented.link,
+                sidelink,
+                started: step,
+                expected: fragmented.count,
+                arrived: 0,
+                tx_metrics: TxMetrics::default(),
+            },
+        );
+    }
+
+    /// Registers the arrival of a fragment of the payload. Returns the payload once all its
+    /// fragments arrived.
+    pub fn arrive(&mut self, id: u64, tx_metrics: &TxMetrics) -> Option<Partial> {
+        let partial = self.partial.get_mut(&id)?;
+        partial.arrived += 1;
+        partial.tx_metrics = *tx_metrics;
+        if partial.arrived < partial.expected {
+            return None;
+        }
+        self.counts.reassembled += 1;
+        self.partial.remove(&id)
+    }
+
+    /// Drops the payloads whose fragments did not all arrive within the timeout.
+    pub fn expire(&mut self, step: TimeMS) -> Vec<Partial> {
+        let timeout = self.timeout.as_u64();
+        let expired: Vec<u64> = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| step.as_u64() - partial.started.as_u64() > timeout)
+            .map(|(id, _)| *id)
+            .collect();
+        self.counts.timed_out += expired.len() as u64;
+        let mut expired: Vec<Partial> = expired
+            .into_iter()
+            .filter_map(|id| self.partial.remove(&id))
+            .collect();
+        expired.sort_by_key(|partial| (partial.started, partial.link.target));
+        expired
+    }
+}
@@ -0,0 +1,144 @@
+use crate::device::mobility::cell::CellId;
+use crate::device::mobility::Point2D;
+use crate::net::message::{TxFailReason, TxMetrics, TxStatus};
+use crate::net::metrics::Latency;
+use disolv_core::agent::AgentId;
+use disolv_core::hashbrown::HashMap;
+use serde::Deserialize;
+
+/// Settings of the interference between the transfers to the same cell of the field. The power
+/// received from a transmitter falls with the distance to the power of `path_loss_exponent`,
+/// and `noise` is the noise power in dB relative to the power received at 1 m. Transfers with a
+/// SINR below `min_sinr` dB fail.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct InterferenceSettings {
+    pub path_loss_exponent: Option<f32>,
+    pub noise: f32,
+    pub min_sinr: f32,
+}
+
+/// Running totals of the transfers slowed down and of the transfers failed by interference.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InterferenceCounts {
+    pub degraded: u64,
+    pub failed: u64,
+}
+
+/// Interference between the transfers received in the same cell. The transmitters of a cell in
+/// a step interfere with the transfers to that cell in the next step, which keeps the transfers
+/// of a step independent of the order in which the agents transmit. The receiver shares the
+/// capacity of the link in proportion to the rate it achieves at its SINR compared to its SNR,
+/// so the latency of a transfer grows with the interference.
+#[derive(Clone, Debug)]
+pub struct Interference {
+    path_loss_exponent: f64,
+    noise: f64,
+    min_sinr: f64,
+    transmitters: HashMap<CellId, Vec<AgentId>>,
+    interferers: HashMap<CellId, Vec<AgentId>>,
+    counts: InterferenceCounts,
+}
+
+impl Interference {
+    pub fn new(settings: &InterferenceSettings) -> Self {
+        Self {
+            path_loss_exponent: settings.path_loss_exponent.unwrap_or(3.0) as f64,
+            noise: from_db(settings.noise as f64),
+            min_sinr: settings.min_sinr as f64,
+            transmitters: HashMap::new(),
+            interferers: HashMap::new(),
+            counts: InterferenceCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> InterferenceCounts {
+        self.counts
+    }
+
+    /// The transmitters of the last step become the interferers of this step.
+    pub fn start_step(&mut self) {
+        self.interferers = std::mem::take(&mut self.transmitters);
+        self.interferers.values_mut().for_each(|agents| {
+            agents.sort();
+            agents.dedup();
+        });
+    }
+
+    pub fn register(&mut self, cell: CellId, transmitter: AgentId) {
+        self.transmitters.entry(cell).or_default().push(transmitter);
+    }
+
+    /// Agents other than the transmitter that transmitted to the cell in the last step.
+    pub fn interferers_of(
+        &self,
+        cell: CellId,
+        transmitter: AgentId,
+    ) -> impl Iterator<Item = &AgentId> {
+        self.interferers
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .filter(move |agent_id| **agent_id != transmitter)
+    }
+
+    /// SINR in dB at the receiver of the transfer from the transmitter.
+    pub fn sinr(&self, transmitter: &Point2D, receiver: &Point2D, interferers: &[Point2D]) -> f64 {
+        let signal = self.received_power(transmitter, receiver);
+        let interference: f64 = interferers
+            .iter()
+            .map(|interferer| self.received_power(interferer, receiver))
+            .sum();
+        to_db(signal / (self.noise + interference))
+    }
+
+    /// Fails the successful transfer when the SINR is too low and otherwise scales its latency
+    /// by the loss of rate to the interference. Transfers slowed down beyond the latency
+    /// constraint of the slice fail as well.
+    pub fn apply(
+        &mut self,
+        tx_metrics: &mut TxMetrics,
+        constraint: Latency,
+        transmitter: &Point2D,
+        receiver: &Point2D,
+        interferers: &[Point2D],
+    ) {
+        if tx_metrics.tx_status != TxStatus::Ok || interferers.is_empty() {
+            return;
+        }
+        let sinr = self.sinr(transmitter, receiver, interferers);
+        if sinr < self.min_sinr {
+            self.counts.failed += 1;
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = TxFailReason::Interference;
+            return;
+        }
+        let snr = self.sinr(transmitter, receiver, &[]);
+        let share = (1.0 + from_db(sinr)).log2() / (1.0 + from_db(snr)).log2();
+        if share >= 1.0 {
+            return;
+        }
+        let latency = (tx_metrics.latency.as_u64() as f64 / share).ceil() as u64;
+        tx_metrics.latency = Latency::new(latency);
+        if tx_metrics.latency > constraint {
+            self.counts.failed += 1;
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = TxFailReason::LatencyLimit;
+            return;
+        }
+        self.counts.degraded += 1;
+    }
+
+    fn received_power(&self, from: &Point2D, to: &Point2D) -> f64 {
+        let distance = ((to.x - from.x).powi(2) + (to.y - from.y).powi(2)).sqrt();
+        distance.max(1.0).powf(-self.path_loss_exponent)
+    }
+}
+
+fn from_db(value: f64) -> f64 {
+    10f64.powf(value / 10.0)
+}
+
+fn to_db(value: f64) -> f64 {
+    10.0 * value.log10()
+}
@@ -1,13 +1,14 @@
-use crate::dist::{DistParams, RngSampler};
+use crate::dist::{DistParams, RngSampler, SeedRegistry};
 use crate::net::message::{PayloadInfo, TxMetrics};
 use crate::net::metrics::Latency;
 use disolv_core::metrics::{Feasibility, Measurable, MetricSettings};
 use log::error;
-use rand::RngCore;
 use serde::Deserialize;
+use std::path::Path;
 
 /// All the latency configuration parameters are optional, but at least one of them must be present.
-/// Name of the variant is mandatory.
+/// Name of the variant is mandatory. Jitter is sampled separately and added to the latency of any
+/// variant, bounded by the maximum jitter when given.
 #[serde_with::skip_serializing_none]
 #[derive(Deserialize, Debug, Clone)]
 pub struct LatencyConfig {
@@ -18,10 +19,34 @@ pub struct LatencyConfig {
     pub max_latency: Option<Latency>,
     pub factor: Option<f32>,
     pub dist_params: Option<DistParams>,
+    pub jitter: Option<DistParams>,
+    pub max_jitter: Option<Latency>,
 }
 
 impl MetricSettings for LatencyConfig {}
 
+impl LatencyConfig {
+    /// Resolves the files referred to by the distributions relative to the given directory.
+    pub fn with_base_path(mut self, base_path: &Path) -> Self {
+        self.dist_params = self
+            .dist_params
+            .map(|params| params.with_base_path(base_path));
+        self.jitter = self.jitter.map(|params| params.with_base_path(base_path));
+        self
+    }
+
+    /// Seeds the distributions without a seed of their own from the streams of the slice.
+    pub fn with_default_seeds(mut self, seeds: &SeedRegistry, slice_id: u32) -> Self {
+        self.dist_params = self.dist_params.map(|params| {
+            params.with_default_seed(seeds.seed_for(&format!("slice_{}_latency", slice_id)))
+        });
+        self.jitter = self.jitter.map(|params| {
+            params.with_default_seed(seeds.seed_for(&format!("slice_{}_jitter", slice_id)))
+        });
+        self
+    }
+}
+
 /// Latency variant is a wrapper around all the possible latency variants. It is used to
 /// instantiate the correct variant based on the configuration.
 #[derive(Debug, Clone)]
@@ -66,9 +91,22 @@ impl Measurable<Latency> for LatencyType {
     }
 }
 
+impl LatencyType {
+    /// Latency above which the transfers of the slice are infeasible.
+    pub fn constraint(&self) -> Latency {
+        match self {
+            LatencyType::Constant(latency) => latency.constraint,
+            LatencyType::Random(latency) => latency.constraint,
+            LatencyType::Distance(latency) => latency.constraint,
+            LatencyType::Ordered(latency) => latency.constraint,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ConstantLatency {
     pub latency: Latency,
+    pub constraint: Latency,
 }
 
 impl Measurable<Latency> for ConstantLatency {
@@ -81,7 +119,10 @@ impl Measurable<Latency> for ConstantLatency {
             error!("Missing constant, setting it to 0.");
             Latency::new(0)
         });
-        ConstantLatency { latency }
+        ConstantLatency {
+            latency,
+            constraint: config.constraint,
+        }
     }
 
     fn measure(&mut self, _rx_metrics: &TxMetrics, _payload: &PayloadInfo) -> Feasibility<Latency> {
@@ -90,11 +131,12 @@ impl Measurable<Latency> for ConstantLatency {
 }
 
 /// Random latency is sampled from a distribution of user's choice. Distribution parameters are
-/// mandatory and must be valid for the chosen distribution.
+/// mandatory and must be valid for the chosen distribution. Samples are clamped to the minimum
+/// and maximum latency, which controls the tail of the distribution.
 #[derive(Debug, Clone)]
 pub struct RandomLatency {
     pub min_latency: Latency,
-    pub max_latency: Latency,
+    pub max_latency: Option<Latency>,
     pub constraint: Latency,
     pub sampler: RngSampler,
 }
@@ -106,8 +148,8 @@ impl Measurable<Latency> for RandomLatency {
 
     fn with_settings(config: &LatencyConfig) -> Self {
         RandomLatency {
-            min_latency: config.min_latency.expect("Missing min latency"),
-            max_latency: config.max_latency.expect("Missing max latency"),
+            min_latency: config.min_latency.unwrap_or_default(),
+            max_latency: config.max_latency,
             constraint: config.constraint,
             sampler: RngSampler::new(
                 config
@@ -119,8 +161,15 @@ impl Measurable<Latency> for RandomLatency {
     }
 
     fn measure(&mut self, _rx_metrics: &TxMetrics, _payload: &PayloadInfo) -> Feasibility<Latency> {
-        let latency_factor = self.sampler.rng.next_u64();
-        let latency = Latency::new(5); // todo: Fix this
+        let mut latency = Latency::new(self.sampler.sample().max(0.0).round() as u64);
+        if latency < self.min_latency {
+            latency = self.min_latency;
+        }
+        if let Some(max_latency) = self.max_latency {
+            if latency > max_latency {
+                latency = max_latency;
+            }
+        }
         if latency > self.constraint {
             return Feasibility::Infeasible(latency);
         }
@@ -197,3 +246,40 @@ impl Measurable<Latency> for OrderedLatency {
         Feasibility::Feasible(latency)
     }
 }
+
+/// Jitter is the variation added to the latency of a slice, independent of the latency variant.
+/// It is sampled from its own distribution and may be negative, but the latency never drops below
+/// zero. Transfers whose latency exceeds the constraint after adding jitter are infeasible.
+#[derive(Debug, Clone)]
+pub struct Jitter {
+    pub max_jitter: Option<Latency>,
+    pub constraint: Latency,
+    pub sampler: RngSampler,
+}
+
+impl Jitter {
+    pub fn with_settings(config: &LatencyConfig) -> Option<Self> {
+        config.jitter.as_ref().map(|params| Jitter {
+            max_jitter: config.max_jitter,
+            constraint: config.constraint,
+            sampler: RngSampler::new(params.clone()),
+        })
+    }
+
+    pub fn apply(&mut self, latency: Feasibility<Latency>) -> Feasibility<Latency> {
+        let latency = match latency {
+            Feasibility::Feasible(latency) => latency,
+            Feasibility::Infeasible(latency) => return Feasibility::Infeasible(latency),
+        };
+        let mut jitter = self.sampler.sample();
+        if let Some(max_jitter) = self.max_jitter {
+            let max_jitter = max_jitter.as_u64() as f32;
+            jitter = jitter.clamp(-max_jitter, max_jitter);
+        }
+        let jittered = Latency::new((latency.as_u64() as f32 + jitter).max(0.0).round() as u64);
+        if jittered > self.constraint {
+            return Feasibility::Infeasible(jittered);
+        }
+        Feasibility::Feasible(jittered)
+    }
+}
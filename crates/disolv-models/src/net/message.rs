@@ -1,7 +1,9 @@
 use crate::device::mobility::MapState;
 use crate::device::types::{DeviceClass, DeviceInfo};
 use crate::net::metrics::{Bandwidth, Bytes, Latency};
-use crate::net::radio::{Action, ActionType, DLink};
+use crate::net::network::NetworkRoute;
+use crate::net::operator::Carrier;
+use crate::net::radio::{Action, ActionType, DLink, LinkDirection};
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::TimeMS;
 use disolv_core::message::{AgentState, DataUnit, GPayload, Metadata, PayloadStatus};
@@ -11,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use typed_builder::TypedBuilder;
 
-#[derive(Deserialize, Default, Debug, Hash, Copy, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Default, Debug, Hash, Copy, Clone, PartialEq, Eq)]
 pub enum DataType {
     #[default]
     CAM,
@@ -20,6 +22,9 @@ pub enum DataType {
     Lidar2D,
     Lidar3D,
     Radar,
+    CPM,
+    Control,
+    Reservation,
 }
 
 impl Display for DataType {
@@ -31,6 +36,25 @@ impl Display for DataType {
             DataType::Lidar2D => write!(f, "Lidar2D"),
             DataType::Lidar3D => write!(f, "Lidar3D"),
             DataType::Radar => write!(f, "Radar"),
+            DataType::CPM => write!(f, "CPM"),
+            DataType::Control => write!(f, "Control"),
+            DataType::Reservation => write!(f, "Reservation"),
+        }
+    }
+}
+
+impl DataType {
+    pub fn as_int(&self) -> u32 {
+        match self {
+            DataType::CAM => 0,
+            DataType::Image => 1,
+            DataType::Video => 2,
+            DataType::Lidar2D => 3,
+            DataType::Lidar3D => 4,
+            DataType::Radar => 5,
+            DataType::CPM => 6,
+            DataType::Control => 7,
+            DataType::Reservation => 8,
         }
     }
 }
@@ -45,11 +69,45 @@ pub struct DeviceContent {
 
 impl AgentState for DeviceContent {}
 
+/// Identifier of a content item in the catalog of a data source.
+pub type ContentId = u64;
+
+/// Agent that generated a blob and the sequence number of the blob among the blobs of the agent.
+/// Copies of a blob forwarded over several paths keep the origin of the blob.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct BlobOrigin {
+    pub agent_id: AgentId,
+    pub sequence: u64,
+}
+
+/// A unit of data of a payload. The `data_size` is the size sent over the network, and the
+/// `raw_size` is the size before the blob was compressed or aggregated, if it was.
 #[derive(Clone, Copy, Debug, Default, TypedBuilder)]
 pub struct DataBlob {
     pub data_type: DataType,
     pub data_size: Bytes,
     pub action: Action,
+    #[builder(default)]
+    pub content_id: Option<ContentId>,
+    #[builder(default)]
+    pub created_at: TimeMS,
+    #[builder(default)]
+    pub raw_size: Option<Bytes>,
+    #[builder(default)]
+    pub origin: Option<BlobOrigin>,
+}
+
+impl DataBlob {
+    pub fn uncompressed_size(&self) -> Bytes {
+        self.raw_size.unwrap_or(self.data_size)
+    }
+
+    /// Scales the size sent over the network by the ratio, keeping the uncompressed size.
+    pub fn compress(&mut self, ratio: f32) {
+        self.raw_size = Some(self.uncompressed_size());
+        let size = (self.data_size.as_u64() as f64 * ratio as f64).ceil() as u64;
+        self.data_size = Bytes::new(size.max(1));
+    }
 }
 
 impl DataUnit for DataBlob {}
@@ -61,6 +119,14 @@ pub struct PayloadInfo {
     pub total_count: u32,
     pub data_blobs: Vec<DataBlob>,
     pub selected_link: DLink,
+    #[builder(default)]
+    pub direction: LinkDirection,
+    #[builder(default)]
+    pub route: NetworkRoute,
+    #[builder(default)]
+    pub carrier: Carrier,
+    #[builder(default)]
+    pub expires_at: Option<TimeMS>,
 }
 
 impl PayloadInfo {
@@ -74,18 +140,46 @@ impl PayloadInfo {
         self.data_blobs
             .retain(|blob| blob.action.action_type != ActionType::Consume);
     }
+
+    /// Makes the agent the origin of the blobs that have none, numbering them from the next
+    /// sequence number of the agent.
+    pub fn stamp_origin(&mut self, agent_id: AgentId, next_sequence: &mut u64) {
+        for blob in self
+            .data_blobs
+            .iter_mut()
+            .filter(|blob| blob.origin.is_none())
+        {
+            blob.origin = Some(BlobOrigin {
+                agent_id,
+                sequence: *next_sequence,
+            });
+            *next_sequence += 1;
+        }
+    }
 }
 
 impl Metadata for PayloadInfo {}
 
 pub type DPayload = GPayload<DeviceContent, PayloadInfo>;
 
+/// Catalog of the content requested by a data source. Each generated blob requests one of the
+/// `catalog_size` items, picked with a Zipf popularity of the given exponent (1.0 by default).
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ContentSettings {
+    pub catalog_size: u64,
+    pub popularity: Option<f32>,
+}
+
+/// A source of data of an agent class. The blobs of the source are compressed to
+/// `compression` times their `data_size` before they are sent, if a compression is given.
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct DataSource {
     pub data_type: DataType,
     pub agent_class: DeviceClass,
     pub data_size: Bytes,
     pub source_step: TimeMS,
+    pub content: Option<ContentSettings>,
+    pub compression: Option<f32>,
 }
 
 impl Reply for DataSource {}
@@ -114,6 +208,13 @@ pub enum TxFailReason {
     None = 0,
     LatencyLimit,
     NoBandwidth,
+    QueueOverflow,
+    LinkLoss,
+    Interference,
+    Outage,
+    LinkLost,
+    OutOfRange,
+    Incomplete,
 }
 
 impl TxFailReason {
@@ -122,6 +223,13 @@ impl TxFailReason {
             TxFailReason::None => 0,
             TxFailReason::LatencyLimit => 1,
             TxFailReason::NoBandwidth => 2,
+            TxFailReason::QueueOverflow => 3,
+            TxFailReason::LinkLoss => 4,
+            TxFailReason::Interference => 5,
+            TxFailReason::Outage => 6,
+            TxFailReason::LinkLost => 7,
+            TxFailReason::OutOfRange => 8,
+            TxFailReason::Incomplete => 9,
         }
     }
 }
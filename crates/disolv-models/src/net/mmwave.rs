@@ -0,0 +1,169 @@
+use crate::device::types::DeviceClass;
+use crate::net::metrics::Bandwidth;
+use crate::net::radio::LinkProperties;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use log::error;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+/// Settings of the mmWave links towards the `target_classes`, or of all the links without
+/// them. In every step, the line of sight of an aligned beam is blocked with the probability
+/// `los_to_nlos` and a blocked beam is cleared with the probability `nlos_to_los`. A cleared
+/// beam is realigned for `realignment` before it can be used again. Aligned links carry the
+/// `aligned_capacity`, and blocked links carry the `nlos_capacity` or cannot be used without it.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct MmWaveSettings {
+    pub los_to_nlos: f64,
+    pub nlos_to_los: f64,
+    pub realignment: Option<TimeMS>,
+    pub aligned_capacity: Bandwidth,
+    pub nlos_capacity: Option<Bandwidth>,
+    pub target_classes: Option<Vec<DeviceClass>>,
+}
+
+/// State of the beam between two agents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BeamState {
+    #[default]
+    Aligned,
+    Blocked,
+    Realigning(TimeMS),
+}
+
+/// Running totals of the beams blocked, of the beams realigned after a blockage and of the
+/// link options that could not be used while blocked or realigning.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MmWaveCounts {
+    pub blockages: u64,
+    pub realignments: u64,
+    pub unusable: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Beam {
+    state: BeamState,
+    updated: TimeMS,
+}
+
+/// Beams of the mmWave links. The beam between two agents is shared by the links in both
+/// directions, and its state advances once in every step in which one of the links is an
+/// option.
+#[derive(Clone, Debug)]
+pub struct MmWave {
+    beams: HashMap<(AgentId, AgentId), Beam>,
+    los_to_nlos: f64,
+    nlos_to_los: f64,
+    realignment: TimeMS,
+    aligned_capacity: Bandwidth,
+    nlos_capacity: Option<Bandwidth>,
+    target_classes: Option<Vec<DeviceClass>>,
+    rng: Pcg64Mcg,
+    counts: MmWaveCounts,
+}
+
+impl MmWave {
+    pub fn new(settings: &MmWaveSettings, seed: u64) -> Self {
+        for probability in [settings.los_to_nlos, settings.nlos_to_los] {
+            if !(0.0..=1.0).contains(&probability) {
+                error!("Beam transition probabilities must be between 0 and 1");
+                panic!("Invalid beam transition probability {}.", probability);
+            }
+        }
+        Self {
+            beams: HashMap::new(),
+            los_to_nlos: settings.los_to_nlos,
+            nlos_to_los: settings.nlos_to_los,
+            realignment: settings.realignment.unwrap_or_default(),
+            aligned_capacity: settings.aligned_capacity,
+            nlos_capacity: settings.nlos_capacity,
+            target_classes: settings.target_classes.clone(),
+            rng: Pcg64Mcg::new(seed as u128),
+            counts: MmWaveCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> MmWaveCounts {
+        self.counts
+    }
+
+    pub fn applies_to(&self, target_class: &DeviceClass) -> bool {
+        self.target_classes
+            .as_ref()
+            .is_none_or(|classes| classes.contains(target_class))
+    }
+
+    pub fn state_of(&self, source: AgentId, target: AgentId) -> Option<BeamState> {
+        self.beams
+            .get(&Self::key(source, target))
+            .map(|beam| beam.state)
+    }
+
+    /// Advances the beam of the link and sets the capacity of the link in both directions to
+    /// the capacity of the beam. Returns false when the link cannot be used in this step.
+    ///
+    /// # Arguments
+    /// * `source` - The agent selecting the link
+    /// * `target` - The target agent of the link
+    /// * `link` - The properties of the link
+    /// * `step` - The current time step
+    pub fn steer(
+        &mut self,
+        source: AgentId,
+        target: AgentId,
+        link: &mut LinkProperties,
+        step: TimeMS,
+    ) -> bool {
+        let state = self.advance(Self::key(source, target), step);
+        let capacity = match state {
+            BeamState::Aligned => Some(self.aligned_capacity),
+            BeamState::Blocked => self.nlos_capacity,
+            BeamState::Realigning(_) => None,
+        };
+        match capacity {
+            Some(capacity) => {
+                link.uplink.capacity = Some(capacity);
+                link.downlink.capacity = Some(capacity);
+                true
+            }
+            None => {
+                self.counts.unusable += 1;
+                false
+            }
+        }
+    }
+
+    fn advance(&mut self, key: (AgentId, AgentId), step: TimeMS) -> BeamState {
+        let beam = self.beams.entry(key).or_insert(Beam {
+            state: BeamState::Aligned,
+            updated: step,
+        });
+        if beam.updated == step {
+            return beam.state;
+        }
+        beam.updated = step;
+        beam.state = match beam.state {
+            BeamState::Aligned if self.rng.gen_bool(self.los_to_nlos) => {
+                self.counts.blockages += 1;
+                BeamState::Blocked
+            }
+            BeamState::Blocked if self.rng.gen_bool(self.nlos_to_los) => {
+                self.counts.realignments += 1;
+                match self.realignment.as_u64() {
+                    0 => BeamState::Aligned,
+                    _ => BeamState::Realigning(step + self.realignment),
+                }
+            }
+            BeamState::Realigning(until) if step >= until => BeamState::Aligned,
+            state => state,
+        };
+        beam.state
+    }
+
+    fn key(source: AgentId, target: AgentId) -> (AgentId, AgentId) {
+        (source.min(target), source.max(target))
+    }
+}
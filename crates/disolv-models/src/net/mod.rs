@@ -1,7 +1,16 @@
+pub mod attenuation;
 pub mod bandwidth;
+pub mod capacity;
+pub mod coverage;
+pub mod fragment;
+pub mod interference;
 pub mod latency;
 pub mod message;
 pub mod metrics;
+pub mod mmwave;
 pub mod network;
+pub mod operator;
 pub mod radio;
+pub mod rem;
+pub mod session;
 pub mod slice;
@@ -1,21 +1,195 @@
+use crate::device::types::{DeviceClass, DeviceInfo};
+use crate::net::attenuation::Attenuation;
+use crate::net::capacity::CapacityTrace;
+use crate::net::coverage::CoverageMap;
+use crate::net::interference::Interference;
 use crate::net::message::{DPayload, TxMetrics};
-use crate::net::slice::Slice;
+use crate::net::metrics::{Bandwidth, Bytes, Latency};
+use crate::net::mmwave::MmWave;
+use crate::net::operator::{Carrier, Operators};
+use crate::net::rem::RadioMap;
+use crate::net::slice::{Slice, SliceSettings};
+use disolv_core::bucket::TimeMS;
+use serde::Deserialize;
 use typed_builder::TypedBuilder;
 
+/// Settings of the backhaul between the infrastructure and the servers. The backhaul is a slice
+/// of its own, carrying the transfers from the `sources` to the `targets`. By default, these are
+/// the transfers from the RSUs and the base stations to the controllers.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct BackhaulSettings {
+    #[serde(flatten)]
+    pub slice: SliceSettings,
+    pub sources: Option<Vec<DeviceClass>>,
+    pub targets: Option<Vec<DeviceClass>>,
+}
+
+/// Part of the network that carries a transfer.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum NetworkRoute {
+    #[default]
+    Access,
+    Backhaul,
+}
+
+#[derive(Clone, Debug)]
+pub struct Backhaul {
+    pub slice: Slice,
+    pub sources: Vec<DeviceClass>,
+    pub targets: Vec<DeviceClass>,
+}
+
+impl Backhaul {
+    pub fn new(settings: &BackhaulSettings, slice: Slice) -> Self {
+        Self {
+            slice,
+            sources: settings
+                .sources
+                .clone()
+                .unwrap_or(vec![DeviceClass::RSU5G, DeviceClass::BaseStation5G]),
+            targets: settings
+                .targets
+                .clone()
+                .unwrap_or(vec![DeviceClass::Controller]),
+        }
+    }
+
+    fn carries(&self, source: &DeviceInfo, target: &DeviceInfo) -> bool {
+        self.sources.contains(&source.device_class) && self.targets.contains(&target.device_class)
+    }
+}
+
 #[derive(Clone, Debug, TypedBuilder)]
 pub struct Network {
     pub slices: Vec<Slice>,
+    #[builder(default)]
+    pub backhaul: Option<Backhaul>,
+    #[builder(default)]
+    pub attenuation: Option<Attenuation>,
+    #[builder(default)]
+    pub interference: Option<Interference>,
+    #[builder(default)]
+    pub operators: Option<Operators>,
+    #[builder(default)]
+    pub capacity_trace: Option<CapacityTrace>,
+    #[builder(default)]
+    pub mmwave: Option<MmWave>,
+    #[builder(default)]
+    pub coverage: Option<CoverageMap>,
+    #[builder(default)]
+    pub radio_map: Option<RadioMap>,
 }
 
 impl Network {
     pub fn transfer(&mut self, payload: &DPayload) -> TxMetrics {
+        let penalty = self.roaming_penalty(&payload.metadata.carrier);
+        let slice = self.slice_on(payload.metadata.route, &payload.metadata.carrier);
+        let mut tx_metrics = slice.transfer(payload);
+        if let Some(penalty) = penalty {
+            slice.delay(&mut tx_metrics, penalty);
+        }
+        tx_metrics
+    }
+
+    /// Admits a payload that is sent in a transfer session on its route.
+    pub fn admit_session(&mut self, payload: &DPayload) -> TxMetrics {
+        let penalty = self.roaming_penalty(&payload.metadata.carrier);
+        let slice = self.slice_on(payload.metadata.route, &payload.metadata.carrier);
+        let mut tx_metrics = slice.admit_session(payload);
+        if let Some(penalty) = penalty {
+            slice.delay(&mut tx_metrics, penalty);
+        }
+        tx_metrics
+    }
+
+    /// Reserves up to the bytes from the capacity left in this step on the route of the payload.
+    pub fn reserve(&mut self, payload: &DPayload, bytes: u64) -> u64 {
+        self.slice_on(payload.metadata.route, &payload.metadata.carrier)
+            .reserve(bytes)
+    }
+
+    /// MTU of the slice on the route of the payload, if it has one.
+    pub fn mtu_for(&mut self, payload: &DPayload) -> Option<Bytes> {
+        self.slice_on(payload.metadata.route, &payload.metadata.carrier)
+            .mtu
+    }
+
+    fn roaming_penalty(&self, carrier: &Carrier) -> Option<Latency> {
+        self.operators
+            .as_ref()
+            .and_then(|operators| operators.penalty(carrier))
+    }
+
+    /// Rate at which the payload can be sent, the lower of the capacity of its link and of the
+    /// slice on its route. There is no rate when neither of them has a capacity.
+    pub fn rate_for(&mut self, payload: &DPayload) -> Option<Bandwidth> {
+        let link_capacity = payload
+            .metadata
+            .selected_link
+            .properties
+            .towards(payload.metadata.direction)
+            .capacity;
+        let slice_capacity = self
+            .slice_on(payload.metadata.route, &payload.metadata.carrier)
+            .capacity();
+        match (link_capacity, slice_capacity) {
+            (Some(link), Some(slice)) => Some(Bandwidth::new(link.as_u64().min(slice.as_u64()))),
+            (link, slice) => link.or(slice),
+        }
+    }
+
+    /// Slice carrying the transfers on the route. The access network carries a transfer on the
+    /// slice of the operator serving it, and on the first slice when the operator has none.
+    fn slice_on(&mut self, route: NetworkRoute, carrier: &Carrier) -> &mut Slice {
+        if let (NetworkRoute::Backhaul, Some(backhaul)) = (route, &mut self.backhaul) {
+            return &mut backhaul.slice;
+        }
+        let index = match carrier.operator {
+            Some(operator) => self
+                .slices
+                .iter()
+                .position(|slice| slice.operator == Some(operator))
+                .unwrap_or(0),
+            None => 0,
+        };
+        self.slices.get_mut(index).expect("no slice found")
+    }
+
+    /// Route of the transfers between the agents. Transfers go through the backhaul when it
+    /// connects the agents and through the access network otherwise.
+    pub fn route_between(&self, source: &DeviceInfo, target: &DeviceInfo) -> NetworkRoute {
+        match self.backhaul {
+            Some(ref backhaul) if backhaul.carries(source, target) => NetworkRoute::Backhaul,
+            _ => NetworkRoute::Access,
+        }
+    }
+
+    /// Slices of the access network followed by the backhaul slice.
+    pub fn all_slices(&self) -> impl Iterator<Item = &Slice> {
+        self.slices
+            .iter()
+            .chain(self.backhaul.iter().map(|backhaul| &backhaul.slice))
+    }
+
+    pub fn all_slices_mut(&mut self) -> impl Iterator<Item = &mut Slice> {
         self.slices
-            .get_mut(0)
-            .expect("no slice found")
-            .transfer(payload)
+            .iter_mut()
+            .chain(self.backhaul.iter_mut().map(|backhaul| &mut backhaul.slice))
     }
 
     pub fn reset_slices(&mut self) {
-        self.slices.iter_mut().for_each(|slice| slice.reset());
+        self.all_slices_mut().for_each(|slice| slice.reset());
+    }
+
+    /// Sets the capacities of the slices that the capacity trace has at the step.
+    pub fn trace_capacities(&mut self, step: TimeMS) {
+        if let Some(ref mut capacity_trace) = self.capacity_trace {
+            let slices = self
+                .slices
+                .iter_mut()
+                .chain(self.backhaul.iter_mut().map(|backhaul| &mut backhaul.slice));
+            capacity_trace.apply(step, slices);
+        }
     }
 }
@@ -0,0 +1,202 @@
+use crate::device::types::DeviceInfo;
+use crate::net::metrics::Latency;
+use crate::net::radio::DLink;
+use disolv_core::agent::AgentId;
+use disolv_core::hashbrown::HashMap;
+use serde::Deserialize;
+
+pub type OperatorId = u32;
+
+/// Roaming agreement that lets the agents of the `home` operator use the infrastructure of the
+/// `visited` operator when the home operator has no coverage. The transfers of a visiting agent
+/// take the `extra_latency` on top of the latency of the slice. Agents cannot roam without an
+/// agreement.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct RoamingSettings {
+    pub home: OperatorId,
+    pub visited: OperatorId,
+    pub extra_latency: Option<Latency>,
+}
+
+/// Operator serving a transfer, and the home operator of the agent when it is visiting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Carrier {
+    pub operator: Option<OperatorId>,
+    pub roaming_from: Option<OperatorId>,
+}
+
+impl Carrier {
+    /// The transfer is carried by the operator of the infrastructure end of the transfer.
+    /// Transfers between agents that are both outside the infrastructure have no operator.
+    pub fn between(source: &DeviceInfo, target: &DeviceInfo) -> Self {
+        let (serving, user) = match (
+            source.device_type.is_infrastructure(),
+            target.device_type.is_infrastructure(),
+        ) {
+            (_, true) => (target, source),
+            (true, false) => (source, target),
+            (false, false) => return Self::default(),
+        };
+        let roaming_from = match (serving.operator, user.operator) {
+            (Some(operator), Some(home)) if operator != home => Some(home),
+            _ => None,
+        };
+        Self {
+            operator: serving.operator,
+            roaming_from,
+        }
+    }
+
+    pub fn is_roaming(&self) -> bool {
+        self.roaming_from.is_some()
+    }
+}
+
+/// Roaming agreements between the operators owning the agents and the slices.
+#[derive(Clone, Debug, Default)]
+pub struct Operators {
+    agreements: HashMap<(OperatorId, OperatorId), Latency>,
+}
+
+impl Operators {
+    pub fn new(roaming_settings: &[RoamingSettings]) -> Self {
+        let agreements = roaming_settings
+            .iter()
+            .map(|settings| {
+                (
+                    (settings.home, settings.visited),
+                    settings.extra_latency.unwrap_or_default(),
+                )
+            })
+            .collect();
+        Self { agreements }
+    }
+
+    pub fn can_roam(&self, home: OperatorId, visited: OperatorId) -> bool {
+        home == visited || self.agreements.contains_key(&(home, visited))
+    }
+
+    /// Latency added to the transfers of an agent visiting the network of another operator.
+    pub fn penalty(&self, carrier: &Carrier) -> Option<Latency> {
+        let home = carrier.roaming_from?;
+        let visited = carrier.operator?;
+        self.agreements
+            .get(&(home, visited))
+            .copied()
+            .filter(|latency| latency.as_u64() > 0)
+    }
+
+    /// Whether the agents can reach each other over the infrastructure of the operators. Agents
+    /// or infrastructure without an operator are reachable by all.
+    fn reaches(&self, source: &DeviceInfo, target: &DeviceInfo) -> bool {
+        let carrier = Carrier::between(source, target);
+        match (carrier.roaming_from, carrier.operator) {
+            (Some(home), Some(visited)) => self.can_roam(home, visited),
+            _ => true,
+        }
+    }
+
+    /// Filters the links of the agent to the ones its operator lets it use. An agent outside
+    /// the infrastructure uses the infrastructure of its own operator, and roams to the
+    /// infrastructure of the operators it has an agreement with only when its own operator has
+    /// no link in the options. Infrastructure reaches the agents that can roam on it. Links
+    /// between agents outside the infrastructure are always kept.
+    ///
+    /// # Arguments
+    /// * `agent_info` - The details of the agent selecting the links
+    /// * `links` - The link options of the agent
+    /// * `info_of` - The details of the target agent of a link
+    ///
+    /// # Returns
+    /// * `Vec<DLink>` - The links the agent can use
+    pub fn usable_links<F>(
+        &self,
+        agent_info: &DeviceInfo,
+        links: Vec<DLink>,
+        info_of: F,
+    ) -> Vec<DLink>
+    where
+        F: Fn(AgentId) -> DeviceInfo,
+    {
+        if agent_info.device_type.is_infrastructure() || agent_info.operator.is_none() {
+            return links
+                .into_iter()
+                .filter(|link| self.reaches(agent_info, &info_of(link.target)))
+                .collect();
+        }
+        let (home_links, visited_links): (Vec<DLink>, Vec<DLink>) = links
+            .into_iter()
+            .partition(|link| !Carrier::between(agent_info, &info_of(link.target)).is_roaming());
+        let has_home_coverage = home_links
+            .iter()
+            .any(|link| info_of(link.target).device_type.is_infrastructure());
+        if has_home_coverage {
+            return home_links;
+        }
+        home_links
+            .into_iter()
+            .chain(
+                visited_links
+                    .into_iter()
+                    .filter(|link| self.reaches(agent_info, &info_of(link.target))),
+            )
+            .collect()
+    }
+}
+
+/// Transfers of an operator. The transfers carried by the infrastructure of the operator count
+/// as attempted and succeeded, and the ones of agents visiting from other operators as visiting.
+/// Selections in which the agents of the operator had links but could use none of them count as
+/// denied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperatorStats {
+    pub attempted: u64,
+    pub succeeded: u64,
+    pub visiting: u64,
+    pub denied: u64,
+}
+
+/// Transfers of the operators in the current step and since the start of the run.
+#[derive(Clone, Debug, Default)]
+pub struct OperatorCounts {
+    step: HashMap<OperatorId, OperatorStats>,
+    total: HashMap<OperatorId, OperatorStats>,
+}
+
+impl OperatorCounts {
+    pub fn register_tx(&mut self, carrier: &Carrier, succeeded: bool) {
+        let operator = match carrier.operator {
+            Some(operator) => operator,
+            None => return,
+        };
+        for stats in [
+            self.step.entry(operator).or_default(),
+            self.total.entry(operator).or_default(),
+        ] {
+            stats.attempted += 1;
+            stats.succeeded += u64::from(succeeded);
+            stats.visiting += u64::from(carrier.is_roaming());
+        }
+    }
+
+    pub fn register_denied(&mut self, operator: OperatorId) {
+        self.step.entry(operator).or_default().denied += 1;
+        self.total.entry(operator).or_default().denied += 1;
+    }
+
+    /// Takes the counts of the step, ordered by the operator.
+    pub fn take_step(&mut self) -> Vec<(OperatorId, OperatorStats)> {
+        let mut step: Vec<(OperatorId, OperatorStats)> = self.step.drain().collect();
+        step.sort_by_key(|(operator, _)| *operator);
+        step
+    }
+
+    /// Counts since the start of the run, ordered by the operator.
+    pub fn totals(&self) -> Vec<(OperatorId, OperatorStats)> {
+        let mut totals: Vec<(OperatorId, OperatorStats)> =
+            self.total.iter().map(|(id, stats)| (*id, *stats)).collect();
+        totals.sort_by_key(|(operator, _)| *operator);
+        totals
+    }
+}
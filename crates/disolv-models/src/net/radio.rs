@@ -1,23 +1,65 @@
-use crate::device::types::{DeviceClass, DeviceType};
+use crate::device::types::{DeviceClass, DeviceInfo, DeviceType};
 use crate::net::message::{DataType, PayloadInfo};
-use crate::net::metrics::{Bytes, Latency};
+use crate::net::metrics::{Bandwidth, Bytes, Latency};
 use disolv_core::agent::AgentId;
+use disolv_core::group::GroupId;
 use disolv_core::radio::{ActionInfo, Actionable, Actions, GLink, LinkFeatures};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use typed_builder::TypedBuilder;
 
+/// Direction of a transfer. Transfers towards the agents of a higher order, e.g. from vehicles
+/// to RSUs, are uplink and the transfers towards the agents of a lower order are downlink.
+/// Transfers between agents of the same order use the uplink properties of the link.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LinkDirection {
+    #[default]
+    Uplink,
+    Downlink,
+}
+
+impl LinkDirection {
+    pub fn between(source: &DeviceInfo, target: &DeviceInfo) -> Self {
+        match target.agent_order < source.agent_order {
+            true => LinkDirection::Downlink,
+            false => LinkDirection::Uplink,
+        }
+    }
+}
+
+/// Properties of a link in one direction. Capacity is in bytes per second and loss is the
+/// probability of losing a payload.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DirectionProperties {
+    pub capacity: Option<Bandwidth>,
+    pub loss: Option<f32>,
+}
+
+/// Properties of a link. Obstruction is the attenuation in dB by the obstacles between the
+/// agents when the link producer computed it.
 #[derive(Debug, Copy, Clone, Default)]
 pub struct LinkProperties {
     pub distance: Option<f32>,
     pub load_factor: Option<f32>,
+    pub obstruction: Option<f32>,
+    pub uplink: DirectionProperties,
+    pub downlink: DirectionProperties,
 }
 
 impl LinkFeatures for LinkProperties {}
 
+impl LinkProperties {
+    pub fn towards(&self, direction: LinkDirection) -> &DirectionProperties {
+        match direction {
+            LinkDirection::Uplink => &self.uplink,
+            LinkDirection::Downlink => &self.downlink,
+        }
+    }
+}
+
 pub type DLink = GLink<LinkProperties>;
 
-#[derive(Deserialize, Clone, Debug, Copy, Eq, PartialEq, Default)]
+#[derive(Deserialize, Serialize, Clone, Debug, Copy, Eq, PartialEq, Default)]
 pub enum ActionType {
     #[default]
     Consume,
@@ -32,6 +74,8 @@ pub struct Action {
     pub to_class: Option<DeviceClass>,
     pub to_agent: Option<AgentId>,
     pub to_kind: Option<DeviceType>,
+    #[builder(default)]
+    pub to_group: Option<GroupId>,
 }
 
 impl ActionInfo for Action {}
@@ -47,6 +91,7 @@ pub struct ActionSettings {
     pub to_class: Option<DeviceClass>,
     pub to_agent: Option<AgentId>,
     pub to_kind: Option<DeviceType>,
+    pub to_group: Option<GroupId>,
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -78,6 +123,7 @@ impl Display for Counts {
 pub struct OutgoingStats {
     pub attempted: Counts,
     pub feasible: Counts,
+    pub expired: Counts,
     pub avg_latency: Latency,
 }
 
@@ -85,8 +131,8 @@ impl Display for OutgoingStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "(attempted: {}, feasible: {}, avg_latency: {})",
-            self.attempted, self.feasible, self.avg_latency
+            "(attempted: {}, feasible: {}, expired: {}, avg_latency: {})",
+            self.attempted, self.feasible, self.expired, self.avg_latency
         )
     }
 }
@@ -95,6 +141,7 @@ impl OutgoingStats {
     pub fn reset(&mut self) {
         self.attempted.reset();
         self.feasible.reset();
+        self.expired.reset();
         self.avg_latency = Latency::default();
     }
 
@@ -116,16 +163,30 @@ impl OutgoingStats {
         self.feasible.data_size += metadata.total_size;
         self.feasible.data_count += metadata.total_count;
     }
+
+    pub fn add_expired(&mut self, metadata: &PayloadInfo) {
+        self.expired.agent_count += 1;
+        self.expired.data_size += metadata.total_size;
+        self.expired.data_count += metadata.total_count;
+    }
 }
 
 #[derive(Default, Copy, Clone, Debug)]
 pub struct IncomingStats {
     pub in_counts: Counts,
+    pub dropped: Counts,
 }
 
 impl IncomingStats {
     pub fn reset(&mut self) {
         self.in_counts.reset();
+        self.dropped.reset();
+    }
+
+    pub fn add_dropped(&mut self, metadata: &PayloadInfo) {
+        self.dropped.agent_count += 1;
+        self.dropped.data_size += metadata.total_size;
+        self.dropped.data_count += metadata.total_count;
     }
 
     pub fn update(&mut self, metadata: &PayloadInfo) {
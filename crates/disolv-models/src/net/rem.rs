@@ -0,0 +1,118 @@
+use crate::device::mobility::Point2D;
+use disolv_core::hashbrown::HashMap;
+use log::error;
+use serde::Deserialize;
+
+/// Lowest SINR in dB kept apart in the bins, lower samples fall in the first bin.
+const MIN_SINR: f64 = -50.0;
+/// Highest SINR in dB kept apart in the bins, higher samples fall in the last bin.
+const MAX_SINR: f64 = 100.0;
+
+/// Settings of the radio environment map. The SINR of the transfers over the access network is
+/// sampled at the agent served by the link and aggregated in square cells of `cell_size` m of
+/// the field. The samples are counted in bins of `bin_width` dB (0.5 by default) to estimate
+/// the 95th percentile, and the samples below `outage_sinr` dB (the `min_sinr` of the
+/// interference by default) are outages. The map needs the interference settings.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct RemSettings {
+    pub cell_size: f64,
+    pub bin_width: Option<f32>,
+    pub outage_sinr: Option<f32>,
+}
+
+/// Summary of the SINR samples of a cell of the map in an output interval.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RemCell {
+    pub cell_x: u32,
+    pub cell_y: u32,
+    pub samples: u64,
+    pub mean_sinr: f64,
+    pub p95_sinr: f64,
+    pub outage: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct CellSamples {
+    count: u64,
+    sum: f64,
+    outages: u64,
+    bins: Vec<u32>,
+}
+
+/// Radio environment map of the SINR over the field. Only the bins of the cells with samples in
+/// the current output interval are kept, and they are cleared when the cells are taken.
+#[derive(Clone, Debug)]
+pub struct RadioMap {
+    cell_size: f64,
+    bin_width: f64,
+    outage_sinr: f64,
+    cells: HashMap<(u32, u32), CellSamples>,
+}
+
+impl RadioMap {
+    pub fn new(settings: &RemSettings, min_sinr: f32) -> Self {
+        let bin_width = settings.bin_width.unwrap_or(0.5) as f64;
+        if settings.cell_size <= 0.0 || bin_width <= 0.0 {
+            error!("Cells and bins of the radio environment map must have a size");
+            panic!(
+                "Invalid radio environment map cell size {} or bin width {}.",
+                settings.cell_size, bin_width
+            );
+        }
+        Self {
+            cell_size: settings.cell_size,
+            bin_width,
+            outage_sinr: settings.outage_sinr.unwrap_or(min_sinr) as f64,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, position: &Point2D, sinr: f64) {
+        let cell_x = (position.x.max(0.0) / self.cell_size).floor() as u32;
+        let cell_y = (position.y.max(0.0) / self.cell_size).floor() as u32;
+        let bin_count = ((MAX_SINR - MIN_SINR) / self.bin_width).ceil() as usize;
+        let bin = ((sinr.clamp(MIN_SINR, MAX_SINR) - MIN_SINR) / self.bin_width) as usize;
+        let samples = self.cells.entry((cell_x, cell_y)).or_default();
+        if samples.bins.is_empty() {
+            samples.bins = vec![0; bin_count];
+        }
+        samples.bins[bin.min(bin_count - 1)] += 1;
+        samples.count += 1;
+        samples.sum += sinr;
+        if sinr < self.outage_sinr {
+            samples.outages += 1;
+        }
+    }
+
+    /// Summaries of the cells sampled since the last call, by row and then by column.
+    pub fn take_cells(&mut self) -> Vec<RemCell> {
+        let mut cells: Vec<RemCell> = self
+            .cells
+            .drain()
+            .map(|((cell_x, cell_y), samples)| RemCell {
+                cell_x,
+                cell_y,
+                samples: samples.count,
+                mean_sinr: samples.sum / samples.count as f64,
+                p95_sinr: Self::percentile(&samples, 0.95, self.bin_width),
+                outage: samples.outages as f64 / samples.count as f64,
+            })
+            .collect();
+        cells.sort_by_key(|cell| (cell.cell_y, cell.cell_x));
+        cells
+    }
+
+    /// Upper edge of the bin in which the percentile of the samples falls.
+    fn percentile(samples: &CellSamples, percentile: f64, bin_width: f64) -> f64 {
+        let rank = (percentile * samples.count as f64).ceil() as u64;
+        let mut seen = 0u64;
+        for (bin, count) in samples.bins.iter().enumerate() {
+            seen += *count as u64;
+            if seen >= rank {
+                return MIN_SINR + (bin + 1) as f64 * bin_width;
+            }
+        }
+        MAX_SINR
+    }
+}
@@ -0,0 +1,178 @@
+use crate::net::message::{DPayload, TxFailReason, TxMetrics, TxStatus};
+use crate::net::metrics::{Bandwidth, Bytes, Latency};
+use crate::net::radio::DLink;
+use disolv_core::bucket::TimeMS;
+use serde::Deserialize;
+
+/// Settings of the transfer sessions, in which the payloads that cannot be sent within a step
+/// are sent over several steps. A session sends its payload at the rate allocated to it, the
+/// capacity of its link or of the slice, whichever is lower, limited to `max_rate` in bytes per
+/// second. Payloads of at least `min_size` are sent in sessions even if they fit in a step.
+/// Sessions that last longer than `timeout` are aborted.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SessionSettings {
+    pub min_size: Option<Bytes>,
+    pub max_rate: Option<Bandwidth>,
+    pub timeout: Option<TimeMS>,
+}
+
+/// A payload that is sent over several steps. The payload is delivered once all its bytes are
+/// sent.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub payload: DPayload,
+    pub link: DLink,
+    pub sidelink: bool,
+    pub started: TimeMS,
+    tx_metrics: TxMetrics,
+    rate: u64,
+    remaining: u64,
+    last_sent: u64,
+}
+
+impl Session {
+    pub fn send(&mut self, bytes: u64) {
+        self.remaining -= bytes;
+        self.last_sent = bytes;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Metrics of the completed session. The latency adds the time from the start of the
+    /// session until its last bytes were sent to the latency of the slice.
+    pub fn completed(&self, step: TimeMS) -> TxMetrics {
+        let elapsed = (step.as_u64() - self.started.as_u64())
+            + (self.last_sent * 1000).div_ceil(self.rate.max(1));
+        let mut tx_metrics = self.tx_metrics;
+        tx_metrics.latency = Latency::new(tx_metrics.latency.as_u64() + elapsed);
+        tx_metrics.bandwidth = Bandwidth::new(self.rate);
+        tx_metrics.tx_status = TxStatus::Ok;
+        tx_metrics
+    }
+
+    /// Metrics of the session aborted for the reason.
+    pub fn aborted(&self, reason: TxFailReason) -> TxMetrics {
+        let mut tx_metrics = self.tx_metrics;
+        tx_metrics.bandwidth = Bandwidth::new(self.rate);
+        tx_metrics.tx_status = TxStatus::Fail;
+        tx_metrics.tx_fail_reason = reason;
+        tx_metrics
+    }
+}
+
+/// Running totals of the sessions that were started, completed and aborted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SessionCounts {
+    pub started: u64,
+    pub completed: u64,
+    pub aborted: u64,
+}
+
+/// Sessions in progress. Sessions send their bytes in the order they were started.
+#[derive(Clone, Debug)]
+pub struct Sessions {
+    settings: SessionSettings,
+    step_size: TimeMS,
+    active: Vec<Session>,
+    counts: SessionCounts,
+}
+
+impl Sessions {
+    pub fn new(settings: &SessionSettings, step_size: TimeMS) -> Self {
+        Self {
+            settings: *settings,
+            step_size,
+            active: Vec::new(),
+            counts: SessionCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> SessionCounts {
+        self.counts
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Rate allocated to a payload that can be sent at most at the capacity.
+    pub fn rate(&self, capacity: Bandwidth) -> Bandwidth {
+        match self.settings.max_rate {
+            Some(max_rate) if max_rate.as_u64() < capacity.as_u64() => max_rate,
+            _ => capacity,
+        }
+    }
+
+    /// Whether the payload is sent in a session at the rate.
+    pub fn is_needed(&self, payload: &DPayload, rate: Bandwidth) -> bool {
+        let size = payload.metadata.total_size;
+        let step_bytes = rate.as_u64() * self.step_size.as_u64() / 1000;
+        size.as_u64() > step_bytes
+            || self
+                .settings
+                .min_size
+                .is_some_and(|min_size| size.as_u64() >= min_size.as_u64())
+    }
+
+    /// Starts a session for the payload that was admitted by the network with the metrics.
+    pub fn start(
+        &mut self,
+        payload: DPayload,
+        link: DLink,
+        sidelink: bool,
+        tx_metrics: TxMetrics,
+        rate: Bandwidth,
+        step: TimeMS,
+    ) {
+        self.counts.started += 1;
+        self.active.push(Session {
+            remaining: payload.metadata.total_size.as_u64(),
+            payload,
+            link,
+            sidelink,
+            started: step,
+            tx_metrics,
+            rate: rate.as_u64(),
+            last_sent: 0,
+        });
+    }
+
+    /// Bytes the session can send in a step at its rate.
+    pub fn bytes_per_step(&self, session: &Session) -> u64 {
+        (session.rate * self.step_size.as_u64() / 1000)
+            .max(1)
+            .min(session.remaining)
+    }
+
+    /// Whether the session has lasted longer than the timeout at the step.
+    pub fn is_timed_out(&self, session: &Session, step: TimeMS) -> bool {
+        self.settings
+            .timeout
+            .is_some_and(|timeout| step.as_u64() - session.started.as_u64() > timeout.as_u64())
+    }
+
+    /// Takes the sessions in progress to send their bytes of the step. The sessions that are
+    /// not finished are returned with `keep`.
+    pub fn take(&mut self) -> Vec<Session> {
+        std::mem::take(&mut self.active)
+    }
+
+    /// Takes the last session started, to send its first bytes in the step it was started.
+    pub fn take_last(&mut self) -> Option<Session> {
+        self.active.pop()
+    }
+
+    pub fn keep(&mut self, session: Session) {
+        self.active.push(session);
+    }
+
+    pub fn finish(&mut self, completed: bool) {
+        match completed {
+            true => self.counts.completed += 1,
+            false => self.counts.aborted += 1,
+        }
+    }
+}
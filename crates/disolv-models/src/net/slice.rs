@@ -1,22 +1,123 @@
 use crate::net::bandwidth::{BandwidthConfig, BandwidthType};
-use crate::net::latency::{LatencyConfig, LatencyType};
+use crate::net::latency::{Jitter, LatencyConfig, LatencyType};
 use crate::net::message::{DPayload, TxFailReason, TxMetrics, TxStatus};
+use crate::net::metrics::{Bandwidth, Bytes, Latency};
+use crate::net::operator::OperatorId;
 use disolv_core::bucket::TimeMS;
 use disolv_core::metrics::{Consumable, Feasibility, Measurable};
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
 use serde::Deserialize;
 use typed_builder::TypedBuilder;
 
+/// Settings of a slice. When the `capacity` of the slice is given in bytes per second, the
+/// transfers in a step contend for it and the step is resolved in `sub_steps` network steps. A
+/// slice of an `operator` carries the transfers served by the infrastructure of the operator.
+/// Payloads larger than the `mtu` in bytes are sent in fragments when fragmentation is enabled.
+#[serde_with::skip_serializing_none]
 #[derive(Deserialize, Debug, Clone)]
 pub struct SliceSettings {
     pub id: u32,
     pub name: String,
     pub latency: LatencyConfig,
     pub bandwidth: BandwidthConfig,
+    pub capacity: Option<Bandwidth>,
+    pub sub_steps: Option<u32>,
+    pub operator: Option<OperatorId>,
+    pub mtu: Option<Bytes>,
+}
+
+/// Contention for the capacity of a slice within an agent step. The step is divided into
+/// sub-steps that each carry their share of the capacity. Transfers are served in the order they
+/// are attempted and complete at the end of the sub-step in which their last byte is served.
+/// Transfers that cannot complete within the step are not served.
+#[derive(Clone, Debug)]
+pub struct SubSteps {
+    count: u32,
+    nominal_capacity: u64,
+    step_capacity: u64,
+    step_micros: u64,
+    current: u32,
+    served: u64,
+}
+
+impl SubSteps {
+    pub fn new(capacity: Bandwidth, count: u32, step_size: TimeMS) -> Self {
+        if count == 0 {
+            panic!("Slice must have at least one sub-step.");
+        }
+        let step_capacity = capacity.as_u64() * step_size.as_u64() / 1000;
+        Self {
+            count,
+            nominal_capacity: step_capacity,
+            step_capacity,
+            step_micros: step_size.as_u64() * 1000,
+            current: 0,
+            served: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+        self.served = 0;
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.step_capacity = (self.nominal_capacity as f64 * factor) as u64;
+    }
+
+    /// Changes the nominal capacity. A scaled capacity keeps its factor of the nominal capacity.
+    fn set_capacity(&mut self, capacity: Bandwidth) {
+        let factor = match self.nominal_capacity {
+            0 => 1.0,
+            nominal_capacity => self.step_capacity as f64 / nominal_capacity as f64,
+        };
+        self.nominal_capacity = capacity.as_u64() * self.step_micros / (1000 * 1000);
+        self.scale(factor);
+    }
+
+    /// Serves the bytes after the transfers queued before them. Returns the time in
+    /// microseconds from the start of the step until the bytes are served.
+    fn serve(&mut self, bytes: u64) -> Option<u64> {
+        let target = self.served + bytes;
+        if target > self.step_capacity {
+            return None;
+        }
+        let mut sub_step = self.current;
+        while self.capacity_until(sub_step) < target {
+            sub_step += 1;
+        }
+        self.current = sub_step;
+        self.served = target;
+        Some((sub_step as u64 + 1) * self.step_micros / self.count as u64)
+    }
+
+    /// Reserves as many of the bytes as the step has capacity left for. Returns the bytes
+    /// reserved.
+    fn reserve(&mut self, bytes: u64) -> u64 {
+        let reserved = bytes.min(self.step_capacity.saturating_sub(self.served));
+        self.served += reserved;
+        while self.current + 1 < self.count && self.capacity_until(self.current) < self.served {
+            self.current += 1;
+        }
+        reserved
+    }
+
+    /// Bytes that can be served until the end of the sub-step.
+    fn capacity_until(&self, sub_step: u32) -> u64 {
+        self.step_capacity * (sub_step as u64 + 1) / self.count as u64
+    }
+
+    fn capacity(&self) -> Bandwidth {
+        Bandwidth::new(self.step_capacity * 1000 * 1000 / self.step_micros)
+    }
 }
 
 #[derive(Clone, Debug, TypedBuilder)]
 pub struct RadioMetrics {
     pub latency_type: LatencyType,
+    #[builder(default)]
+    pub jitter: Option<Jitter>,
 }
 
 #[derive(Clone, Debug, TypedBuilder)]
@@ -33,30 +134,75 @@ pub struct Slice {
     pub step_size: TimeMS,
     #[builder(default)]
     pub tx_order: u32,
+    /// Generator of the link losses, built from the seed of the loss stream of the slice.
+    #[builder(setter(transform = |seed: u64| Pcg64Mcg::new(seed as u128)))]
+    pub loss_rng: Pcg64Mcg,
+    #[builder(default)]
+    pub sub_steps: Option<SubSteps>,
+    #[builder(default)]
+    pub operator: Option<OperatorId>,
+    #[builder(default)]
+    pub mtu: Option<Bytes>,
 }
 
 impl Slice {
     pub fn reset(&mut self) {
         self.tx_order = 0;
         self.resources.bandwidth_type.reset();
+        if let Some(ref mut sub_steps) = self.sub_steps {
+            sub_steps.reset();
+        }
+    }
+
+    pub fn has_capacity(&self) -> bool {
+        self.sub_steps.is_some()
+    }
+
+    /// Scales the capacity of the slice to the factor of its nominal capacity.
+    pub fn scale_capacity(&mut self, factor: f64) {
+        if let Some(ref mut sub_steps) = self.sub_steps {
+            sub_steps.scale(factor);
+        }
+    }
+
+    /// Changes the nominal capacity of the slice. Returns false if the slice has no capacity.
+    pub fn set_capacity(&mut self, capacity: Bandwidth) -> bool {
+        match self.sub_steps {
+            Some(ref mut sub_steps) => {
+                sub_steps.set_capacity(capacity);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Capacity of the slice in this step, if the transfers contend for it.
+    pub fn capacity(&self) -> Option<Bandwidth> {
+        self.sub_steps
+            .as_ref()
+            .map(|sub_steps| sub_steps.capacity())
+    }
+
+    /// Reserves up to the bytes from the capacity left in this step. Returns the bytes reserved,
+    /// which are all the bytes when the slice has no capacity.
+    pub fn reserve(&mut self, bytes: u64) -> u64 {
+        match self.sub_steps {
+            Some(ref mut sub_steps) => sub_steps.reserve(bytes),
+            None => bytes,
+        }
     }
 
     pub fn transfer(&mut self, payload: &DPayload) -> TxMetrics {
         self.tx_order += 1;
         let mut tx_metrics = TxMetrics::new(payload, self.tx_order);
-        match self
-            .metrics
-            .latency_type
-            .measure(&tx_metrics, &payload.metadata)
+        if let Some(fail_reason) = self
+            .check_link(payload, &mut tx_metrics, true)
+            .or_else(|| self.measure_latency(payload, &mut tx_metrics))
         {
-            Feasibility::Feasible(latency) => tx_metrics.latency = latency,
-            Feasibility::Infeasible(latency) => {
-                tx_metrics.latency = latency;
-                tx_metrics.tx_status = TxStatus::Fail;
-                tx_metrics.tx_fail_reason = TxFailReason::LatencyLimit;
-                return tx_metrics;
-            }
-        };
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = fail_reason;
+            return tx_metrics;
+        }
 
         match self.resources.bandwidth_type.consume(&payload.metadata) {
             Feasibility::Feasible(bandwidth) => tx_metrics.bandwidth = bandwidth,
@@ -68,7 +214,253 @@ impl Slice {
             }
         };
 
+        // The time spent waiting for the transfers ahead in the step adds to the latency.
+        if let Some(ref mut sub_steps) = self.sub_steps {
+            match sub_steps.serve(payload.metadata.total_size.as_u64()) {
+                Some(delay) => {
+                    let queueing = delay.div_ceil(1000);
+                    tx_metrics.latency = Latency::new(tx_metrics.latency.as_u64() + queueing);
+                }
+                None => {
+                    tx_metrics.bandwidth = sub_steps.capacity();
+                    tx_metrics.tx_status = TxStatus::Fail;
+                    tx_metrics.tx_fail_reason = TxFailReason::NoBandwidth;
+                    return tx_metrics;
+                }
+            }
+        }
+
+        tx_metrics.tx_status = TxStatus::Ok;
+        tx_metrics
+    }
+
+    /// Admits a payload that is sent in a transfer session over several steps. The payload can
+    /// be lost and must meet the latency constraint as in a transfer, while the capacity is
+    /// reserved by the session in every step it lasts.
+    pub fn admit_session(&mut self, payload: &DPayload) -> TxMetrics {
+        self.tx_order += 1;
+        let mut tx_metrics = TxMetrics::new(payload, self.tx_order);
+        if let Some(fail_reason) = self
+            .check_link(payload, &mut tx_metrics, false)
+            .or_else(|| self.measure_latency(payload, &mut tx_metrics))
+        {
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = fail_reason;
+            return tx_metrics;
+        }
         tx_metrics.tx_status = TxStatus::Ok;
         tx_metrics
     }
+
+    /// Adds the latency to a successful transfer. The transfer fails when it no longer meets the
+    /// latency constraint of the slice.
+    pub fn delay(&self, tx_metrics: &mut TxMetrics, extra: Latency) {
+        if tx_metrics.tx_status != TxStatus::Ok {
+            return;
+        }
+        tx_metrics.latency = Latency::new(tx_metrics.latency.as_u64() + extra.as_u64());
+        if tx_metrics.latency > self.metrics.latency_type.constraint() {
+            tx_metrics.tx_status = TxStatus::Fail;
+            tx_metrics.tx_fail_reason = TxFailReason::LatencyLimit;
+        }
+    }
+
+    fn measure_latency(
+        &mut self,
+        payload: &DPayload,
+        tx_metrics: &mut TxMetrics,
+    ) -> Option<TxFailReason> {
+        let mut latency = self
+            .metrics
+            .latency_type
+            .measure(tx_metrics, &payload.metadata);
+        if let Some(ref mut jitter) = self.metrics.jitter {
+            latency = jitter.apply(latency);
+        }
+        match latency {
+            Feasibility::Feasible(latency) => {
+                tx_metrics.latency = latency;
+                None
+            }
+            Feasibility::Infeasible(latency) => {
+                tx_metrics.latency = latency;
+                Some(TxFailReason::LatencyLimit)
+            }
+        }
+    }
+
+    /// Checks the properties of the selected link in the direction of the transfer. A payload
+    /// is lost with the loss probability of the link and, when it must be carried within the
+    /// step, a payload larger than the link can carry in a step does not fit the link.
+    fn check_link(
+        &mut self,
+        payload: &DPayload,
+        tx_metrics: &mut TxMetrics,
+        within_step: bool,
+    ) -> Option<TxFailReason> {
+        let link = *payload
+            .metadata
+            .selected_link
+            .properties
+            .towards(payload.metadata.direction);
+        if let Some(loss) = link.loss {
+            if self.loss_rng.gen::<f32>() < loss {
+                return Some(TxFailReason::LinkLoss);
+            }
+        }
+        if let (true, Some(capacity)) = (within_step, link.capacity) {
+            let step_capacity = capacity.as_u64() * self.step_size.as_u64() / 1000;
+            if payload.metadata.total_size.as_u64() > step_capacity {
+                tx_metrics.bandwidth = capacity;
+                return Some(TxFailReason::NoBandwidth);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::types::DeviceInfo;
+    use crate::net::message::{DeviceContent, PayloadInfo};
+    use crate::net::radio::{DLink, DirectionProperties, LinkDirection, LinkProperties};
+    use disolv_core::agent::{AgentId, AgentOrder};
+
+    fn slice(loss_seed: u64) -> Slice {
+        let latency_config = LatencyConfig {
+            variant: "constant".to_string(),
+            constraint: Latency::new(1000),
+            constant_term: Some(Latency::new(10)),
+            min_latency: None,
+            max_latency: None,
+            factor: None,
+            dist_params: None,
+            jitter: None,
+            max_jitter: None,
+        };
+        Slice::builder()
+            .id(0)
+            .name("slice".to_string())
+            .metrics(
+                RadioMetrics::builder()
+                    .latency_type(LatencyType::with_settings(&latency_config))
+                    .build(),
+            )
+            .resources(
+                RadioResources::builder()
+                    .bandwidth_type(BandwidthType::with_settings(BandwidthConfig {
+                        variant: "constant".to_string(),
+                    }))
+                    .build(),
+            )
+            .step_size(TimeMS::from(100))
+            .loss_rng(loss_seed)
+            .build()
+    }
+
+    fn payload(size: u64, direction: LinkDirection, properties: LinkProperties) -> DPayload {
+        let mut selected_link = DLink::new(AgentId::from(1));
+        selected_link.properties = properties;
+        DPayload {
+            agent_state: DeviceContent::default(),
+            metadata: PayloadInfo {
+                total_size: Bytes::new(size),
+                total_count: 1,
+                selected_link,
+                direction,
+                ..Default::default()
+            },
+            gathered_states: None,
+        }
+    }
+
+    fn lossy_uplink() -> LinkProperties {
+        LinkProperties {
+            uplink: DirectionProperties {
+                capacity: None,
+                loss: Some(1.0),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn device(order: u32) -> DeviceInfo {
+        DeviceInfo {
+            agent_order: AgentOrder::from(order),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_direction_follows_agent_order() {
+        assert_eq!(
+            LinkDirection::between(&device(1), &device(2)),
+            LinkDirection::Uplink
+        );
+        assert_eq!(
+            LinkDirection::between(&device(2), &device(1)),
+            LinkDirection::Downlink
+        );
+        assert_eq!(
+            LinkDirection::between(&device(1), &device(1)),
+            LinkDirection::Uplink
+        );
+    }
+
+    #[test]
+    fn test_loss_applies_to_the_direction_of_the_transfer() {
+        let mut slice = slice(7);
+        let uplink = slice.transfer(&payload(100, LinkDirection::Uplink, lossy_uplink()));
+        assert_eq!(uplink.tx_status, TxStatus::Fail);
+        assert_eq!(uplink.tx_fail_reason, TxFailReason::LinkLoss);
+
+        let downlink = slice.transfer(&payload(100, LinkDirection::Downlink, lossy_uplink()));
+        assert_eq!(downlink.tx_status, TxStatus::Ok);
+    }
+
+    #[test]
+    fn test_losses_depend_only_on_seed() {
+        let half_lossy = LinkProperties {
+            uplink: DirectionProperties {
+                capacity: None,
+                loss: Some(0.5),
+            },
+            ..Default::default()
+        };
+        let outcomes = |seed: u64| -> Vec<TxStatus> {
+            let mut slice = slice(seed);
+            (0..50)
+                .map(|_| {
+                    slice
+                        .transfer(&payload(100, LinkDirection::Uplink, half_lossy))
+                        .tx_status
+                })
+                .collect()
+        };
+        assert_eq!(outcomes(7), outcomes(7));
+        assert_ne!(outcomes(7), outcomes(8));
+    }
+
+    #[test]
+    fn test_payload_larger_than_step_capacity_does_not_fit() {
+        let mut slice = slice(7);
+        let link = LinkProperties {
+            downlink: DirectionProperties {
+                capacity: Some(Bandwidth::new(10_000)),
+                loss: None,
+            },
+            ..Default::default()
+        };
+        let fits = slice.transfer(&payload(1_000, LinkDirection::Downlink, link));
+        assert_eq!(fits.tx_status, TxStatus::Ok);
+
+        let too_large = slice.transfer(&payload(1_001, LinkDirection::Downlink, link));
+        assert_eq!(too_large.tx_status, TxStatus::Fail);
+        assert_eq!(too_large.tx_fail_reason, TxFailReason::NoBandwidth);
+        assert_eq!(too_large.bandwidth, Bandwidth::new(10_000));
+
+        let session = slice.admit_session(&payload(1_001, LinkDirection::Downlink, link));
+        assert_eq!(session.tx_status, TxStatus::Ok);
+    }
 }
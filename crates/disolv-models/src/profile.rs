@@ -0,0 +1,244 @@
+use crate::device::mobility::Point2D;
+use disolv_core::bucket::TimeMS;
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+use std::path::Path;
+
+const DAY_LENGTH: u64 = 86_400_000;
+
+/// Intensity of the load from the given time of the day until the start of the next level.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct IntensityLevel {
+    pub from: TimeMS,
+    pub intensity: f64,
+}
+
+/// A rectangular zone of the map with an intensity that scales the time-of-day intensity.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Zone {
+    pub name: String,
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+    pub intensity: f64,
+}
+
+impl Zone {
+    fn contains(&self, pos: &Point2D) -> bool {
+        pos.x >= self.x_min && pos.x <= self.x_max && pos.y >= self.y_min && pos.y <= self.y_max
+    }
+}
+
+/// Contents of a load profile file. The simulation starts at `start_time` of the day, which is
+/// `day_length` long. Levels must be sorted by their start. Positions outside all the zones have
+/// an intensity of 1.
+#[serde_with::skip_serializing_none]
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoadProfileSettings {
+    pub start_time: Option<TimeMS>,
+    pub day_length: Option<TimeMS>,
+    pub modulate_activations: Option<bool>,
+    pub seed: Option<u64>,
+    pub time_of_day: Vec<IntensityLevel>,
+    pub zones: Option<Vec<Zone>>,
+}
+
+/// Load intensity over the time of the day and the zones of the map. An intensity of 1 keeps the
+/// configured generation rates, 2 doubles them and 0.5 halves them. Activations are admitted
+/// with a probability relative to the peak intensity of the profile.
+#[derive(Debug, Clone)]
+pub struct LoadProfile {
+    start_time: u64,
+    day_length: u64,
+    modulate_activations: bool,
+    levels: Vec<IntensityLevel>,
+    zones: Vec<Zone>,
+    peak_intensity: f64,
+    rng: Pcg64Mcg,
+}
+
+impl LoadProfile {
+    pub fn read(profile_file: &Path) -> Self {
+        let profile_toml = match std::fs::read_to_string(profile_file) {
+            Ok(content) => content,
+            Err(e) => panic!(
+                "Error while reading the load profile {}: {}",
+                profile_file.display(),
+                e
+            ),
+        };
+        match toml::from_str(&profile_toml) {
+            Ok(settings) => Self::new(settings),
+            Err(e) => panic!("Error while parsing the load profile: {}", e),
+        }
+    }
+
+    pub fn new(settings: LoadProfileSettings) -> Self {
+        let day_length = settings.day_length.map_or(DAY_LENGTH, |day| day.as_u64());
+        if day_length == 0 {
+            panic!("Day length of the load profile must be positive.");
+        }
+        if settings.time_of_day.is_empty() {
+            panic!("Load profile must have at least one time of day level.");
+        }
+        if settings
+            .time_of_day
+            .windows(2)
+            .any(|levels| levels[0].from >= levels[1].from)
+        {
+            panic!("Time of day levels of the load profile must be sorted by their start.");
+        }
+        let zones = settings.zones.unwrap_or_default();
+        if settings
+            .time_of_day
+            .iter()
+            .map(|level| level.intensity)
+            .chain(zones.iter().map(|zone| zone.intensity))
+            .any(|intensity| intensity < 0.0)
+        {
+            panic!("Intensities of the load profile must not be negative.");
+        }
+
+        let peak_level = settings
+            .time_of_day
+            .iter()
+            .map(|level| level.intensity)
+            .fold(0.0, f64::max);
+        let peak_zone = zones.iter().map(|zone| zone.intensity).fold(1.0, f64::max);
+        Self {
+            start_time: settings.start_time.unwrap_or_default().as_u64(),
+            day_length,
+            modulate_activations: settings.modulate_activations.unwrap_or(false),
+            levels: settings.time_of_day,
+            zones,
+            peak_intensity: peak_level * peak_zone,
+            rng: Pcg64Mcg::new(settings.seed.unwrap_or(0) as u128),
+        }
+    }
+
+    pub fn modulates_activations(&self) -> bool {
+        self.modulate_activations
+    }
+
+    pub fn intensity_at(&self, step: TimeMS, pos: &Point2D) -> f64 {
+        let time_of_day = (self.start_time + step.as_u64()) % self.day_length;
+        // Times before the first level belong to the last level of the previous day.
+        let level = self
+            .levels
+            .iter()
+            .rev()
+            .find(|level| level.from.as_u64() <= time_of_day)
+            .unwrap_or(self.levels.last().expect("Load profile must have levels"));
+        let zone_intensity = self
+            .zones
+            .iter()
+            .find(|zone| zone.contains(pos))
+            .map_or(1.0, |zone| zone.intensity);
+        level.intensity * zone_intensity
+    }
+
+    pub fn activation_probability(&self, step: TimeMS, pos: &Point2D) -> f64 {
+        match self.peak_intensity > 0.0 {
+            true => self.intensity_at(step, pos) / self.peak_intensity,
+            false => 0.0,
+        }
+    }
+
+    /// Decides if an agent activated at the given time and position takes part in the
+    /// simulation.
+    pub fn admits_activation(&mut self, step: TimeMS, pos: &Point2D) -> bool {
+        let probability = self.activation_probability(step, pos);
+        self.rng.gen::<f64>() < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(from: u64, intensity: f64) -> IntensityLevel {
+        IntensityLevel {
+            from: TimeMS::from(from),
+            intensity,
+        }
+    }
+
+    fn point(x: f64, y: f64) -> Point2D {
+        Point2D::builder().x(x).y(y).build()
+    }
+
+    fn profile(start_time: u64, modulate_activations: bool) -> LoadProfile {
+        LoadProfile::new(LoadProfileSettings {
+            start_time: Some(TimeMS::from(start_time)),
+            day_length: Some(TimeMS::from(1000)),
+            modulate_activations: Some(modulate_activations),
+            seed: Some(7),
+            time_of_day: vec![level(200, 2.0), level(600, 0.5)],
+            zones: Some(vec![Zone {
+                name: "centre".to_string(),
+                x_min: 0.0,
+                y_min: 0.0,
+                x_max: 100.0,
+                y_max: 100.0,
+                intensity: 2.0,
+            }]),
+        })
+    }
+
+    #[test]
+    fn test_intensity_follows_time_of_day_and_zones() {
+        let profile = profile(0, false);
+        let outside = point(500.0, 500.0);
+        assert_eq!(profile.intensity_at(TimeMS::from(300), &outside), 2.0);
+        assert_eq!(profile.intensity_at(TimeMS::from(700), &outside), 0.5);
+        // Times before the first level belong to the last level of the previous day.
+        assert_eq!(profile.intensity_at(TimeMS::from(100), &outside), 0.5);
+        assert_eq!(profile.intensity_at(TimeMS::from(1300), &outside), 2.0);
+        assert_eq!(
+            profile.intensity_at(TimeMS::from(300), &point(50.0, 50.0)),
+            4.0
+        );
+    }
+
+    #[test]
+    fn test_start_time_shifts_the_day() {
+        let profile = profile(500, false);
+        let outside = point(500.0, 500.0);
+        assert_eq!(profile.intensity_at(TimeMS::from(0), &outside), 2.0);
+        assert_eq!(profile.intensity_at(TimeMS::from(100), &outside), 0.5);
+    }
+
+    #[test]
+    fn test_activations_are_admitted_relative_to_peak() {
+        let mut profile = profile(0, true);
+        assert!(profile.modulates_activations());
+        let centre = point(50.0, 50.0);
+        let outside = point(500.0, 500.0);
+        assert_eq!(
+            profile.activation_probability(TimeMS::from(300), &centre),
+            1.0
+        );
+        assert!((0..100).all(|_| profile.admits_activation(TimeMS::from(300), &centre)));
+
+        let admitted = (0..10000)
+            .filter(|_| profile.admits_activation(TimeMS::from(700), &outside))
+            .count();
+        // The intensity is 0.5 of the peak of 4.
+        assert!((1000..1500).contains(&admitted));
+    }
+
+    #[test]
+    #[should_panic(expected = "sorted by their start")]
+    fn test_unsorted_levels_are_rejected() {
+        LoadProfile::new(LoadProfileSettings {
+            start_time: None,
+            day_length: None,
+            modulate_activations: None,
+            seed: None,
+            time_of_day: vec![level(600, 1.0), level(200, 2.0)],
+            zones: None,
+        });
+    }
+}
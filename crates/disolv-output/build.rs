@@ -0,0 +1,6 @@
+fn main() {
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc binary is not available");
+    std::env::set_var("PROTOC", protoc);
+    prost_build::compile_protos(&["proto/trace.proto", "proto/dump.proto"], &["proto"])
+        .expect("failed to compile the output schemas");
+}
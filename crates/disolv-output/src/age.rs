@@ -0,0 +1,127 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::age::AgeRecord;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the age of the information every target has about the sources it received updates
+/// from, along with the peak age so far.
+#[derive(Debug)]
+pub(crate) struct AgeWriter {
+    time_step: Vec<u64>,
+    source_id: Vec<u64>,
+    target_id: Vec<u64>,
+    age: Vec<u64>,
+    peak_age: Vec<u64>,
+    updates: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl AgeWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::AgeOfInformation)
+            .expect("AgeWriter::new: No AgeWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            source_id: Vec::new(),
+            target_id: Vec::new(),
+            age: Vec::new(),
+            peak_age: Vec::new(),
+            updates: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let source_id = Field::new("source_id", DataType::UInt64, false);
+        let target_id = Field::new("target_id", DataType::UInt64, false);
+        let age = Field::new("age", DataType::UInt64, false);
+        let peak_age = Field::new("peak_age", DataType::UInt64, false);
+        let updates = Field::new("updates", DataType::UInt64, false);
+        Schema::new(vec![time_ms, source_id, target_id, age, peak_age, updates])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        source_id: AgentId,
+        target_id: AgentId,
+        record: &AgeRecord,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.source_id.push(source_id.as_u64());
+        self.target_id.push(target_id.as_u64());
+        self.age.push(record.age_at(time_step).as_u64());
+        self.peak_age.push(record.peak_age.as_u64());
+        self.updates.push(record.updates);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "source_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.source_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "target_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.target_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "age",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.age))) as ArrayRef,
+                    ),
+                    (
+                        "peak_age",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.peak_age))) as ArrayRef,
+                    ),
+                    (
+                        "updates",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.updates))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::broadcast::BroadcastReception;
+use disolv_models::device::types::DeviceClass;
+use disolv_models::net::message::TxStatus;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the reception of the payloads broadcast by the agents, one row per broadcast.
+#[derive(Debug)]
+pub(crate) struct BroadcastWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    target_class: Vec<u32>,
+    tx_status: Vec<u32>,
+    covered: Vec<u32>,
+    received: Vec<u32>,
+    reception_ratio: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl BroadcastWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::BroadcastReception)
+            .expect("BroadcastWriter::new: No BroadcastWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            target_class: Vec::new(),
+            tx_status: Vec::new(),
+            covered: Vec::new(),
+            received: Vec::new(),
+            reception_ratio: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let target_class = Field::new("target_class", DataType::UInt32, false);
+        let tx_status = Field::new("tx_status", DataType::UInt32, false);
+        let covered = Field::new("covered", DataType::UInt32, false);
+        let received = Field::new("received", DataType::UInt32, false);
+        let reception_ratio = Field::new("reception_ratio", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            target_class,
+            tx_status,
+            covered,
+            received,
+            reception_ratio,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        target_class: DeviceClass,
+        tx_status: TxStatus,
+        reception: &BroadcastReception,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.target_class.push(target_class.as_int());
+        self.tx_status.push(tx_status.as_int());
+        self.covered.push(reception.covered);
+        self.received.push(reception.received);
+        self.reception_ratio.push(reception.reception_ratio());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "target_class",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.target_class)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "tx_status",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.tx_status)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "covered",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.covered))) as ArrayRef,
+                    ),
+                    (
+                        "received",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.received))) as ArrayRef,
+                    ),
+                    (
+                        "reception_ratio",
+                        Arc::new(Float64Array::from(std::mem::take(
+                            &mut self.reception_ratio,
+                        ))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::cache::CacheStats;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the content cache statistics of every caching agent at every time step.
+#[derive(Debug)]
+pub(crate) struct CacheWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    requests: Vec<u64>,
+    hits: Vec<u64>,
+    hit_ratio: Vec<f64>,
+    cached_bytes: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl CacheWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Cache)
+            .expect("CacheWriter::new: No CacheWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            requests: Vec::new(),
+            hits: Vec::new(),
+            hit_ratio: Vec::new(),
+            cached_bytes: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let requests = Field::new("requests", DataType::UInt64, false);
+        let hits = Field::new("hits", DataType::UInt64, false);
+        let hit_ratio = Field::new("hit_ratio", DataType::Float64, false);
+        let cached_bytes = Field::new("cached_bytes", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            requests,
+            hits,
+            hit_ratio,
+            cached_bytes,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, agent_id: AgentId, stats: &CacheStats) {
+        let hit_ratio = match stats.requests {
+            0 => 0.0,
+            requests => stats.hits as f64 / requests as f64,
+        };
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.requests.push(stats.requests);
+        self.hits.push(stats.hits);
+        self.hit_ratio.push(hit_ratio);
+        self.cached_bytes.push(stats.cached_bytes.as_u64());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "requests",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.requests))) as ArrayRef,
+                    ),
+                    (
+                        "hits",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.hits))) as ArrayRef,
+                    ),
+                    (
+                        "hit_ratio",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.hit_ratio)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "cached_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.cached_bytes)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
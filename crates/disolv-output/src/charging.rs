@@ -0,0 +1,236 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::charging::{ChargingSession, StationOccupancy};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the occupied slots of every charging station and the reservations it granted and
+/// denied in every time step.
+#[derive(Debug)]
+pub(crate) struct OccupancyWriter {
+    time_step: Vec<u64>,
+    station_id: Vec<u64>,
+    slots: Vec<u32>,
+    occupied: Vec<u32>,
+    granted: Vec<u64>,
+    denied: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl OccupancyWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StationOccupancy)
+            .expect("OccupancyWriter::new: No OccupancyWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            station_id: Vec::new(),
+            slots: Vec::new(),
+            occupied: Vec::new(),
+            granted: Vec::new(),
+            denied: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let station_id = Field::new("station_id", DataType::UInt64, false);
+        let slots = Field::new("slots", DataType::UInt32, false);
+        let occupied = Field::new("occupied", DataType::UInt32, false);
+        let granted = Field::new("granted", DataType::UInt64, false);
+        let denied = Field::new("denied", DataType::UInt64, false);
+        Schema::new(vec![time_ms, station_id, slots, occupied, granted, denied])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, occupancy: &StationOccupancy) {
+        self.time_step.push(time_step.as_u64());
+        self.station_id.push(occupancy.station_id.as_u64());
+        self.slots.push(occupancy.slots);
+        self.occupied.push(occupancy.occupied);
+        self.granted.push(occupancy.granted);
+        self.denied.push(occupancy.denied);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "station_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.station_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "slots",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.slots))) as ArrayRef,
+                    ),
+                    (
+                        "occupied",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.occupied))) as ArrayRef,
+                    ),
+                    (
+                        "granted",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.granted))) as ArrayRef,
+                    ),
+                    (
+                        "denied",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.denied))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
+
+/// Writes the charging sessions of the vehicles when they leave the stations. A session starts
+/// when the station reserves a slot for the vehicle.
+#[derive(Debug)]
+pub(crate) struct ChargingSessionWriter {
+    time_step: Vec<u64>,
+    vehicle_id: Vec<u64>,
+    station_id: Vec<u64>,
+    start: Vec<u64>,
+    duration: Vec<u64>,
+    energy: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl ChargingSessionWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::ChargingSessions)
+            .expect("ChargingSessionWriter::new: No ChargingSessionWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            vehicle_id: Vec::new(),
+            station_id: Vec::new(),
+            start: Vec::new(),
+            duration: Vec::new(),
+            energy: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let vehicle_id = Field::new("vehicle_id", DataType::UInt64, false);
+        let station_id = Field::new("station_id", DataType::UInt64, false);
+        let start = Field::new("start", DataType::UInt64, false);
+        let duration = Field::new("duration", DataType::UInt64, false);
+        let energy = Field::new("energy", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms, vehicle_id, station_id, start, duration, energy,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, session: &ChargingSession) {
+        self.time_step.push(time_step.as_u64());
+        self.vehicle_id.push(session.vehicle_id.as_u64());
+        self.station_id.push(session.station_id.as_u64());
+        self.start.push(session.start.as_u64());
+        self.duration
+            .push(session.end.as_u64() - session.start.as_u64());
+        self.energy.push(session.energy.as_u64());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "vehicle_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.vehicle_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "station_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.station_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "start",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.start))) as ArrayRef,
+                    ),
+                    (
+                        "duration",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.duration))) as ArrayRef,
+                    ),
+                    (
+                        "energy",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.energy))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
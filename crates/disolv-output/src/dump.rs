@@ -0,0 +1,143 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::open_sink;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashSet;
+use disolv_models::bucket::lake::{DataLake, PayloadMap};
+use disolv_models::net::message::DPayload;
+use prost::Message;
+use std::fmt::{Debug, Formatter};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Bindings of the dump schema in `proto/dump.proto`, for the tools reading the dumps.
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/disolv.dump.rs"));
+}
+
+/// Writes the payloads left in the data lake at the end of the simulation as a single protobuf
+/// message of the dump schema, so that failed or surprising runs can be inspected offline.
+pub(crate) struct LakeDumpWriter {
+    sink: Box<dyn Write + Send>,
+}
+
+impl Debug for LakeDumpWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LakeDumpWriter").finish()
+    }
+}
+
+impl LakeDumpWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::LakeDump)
+            .expect("LakeDumpWriter::new: No LakeDump config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            sink: open_sink(output_settings, &output_file),
+        }
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, data_lake: &DataLake) {
+        let waiting = Self::waiting_payloads(&data_lake.payloads, false)
+            .chain(Self::waiting_payloads(&data_lake.sl_payloads, true))
+            .collect::<Vec<proto::LakePayload>>();
+        let expired = Self::expired_payloads(&data_lake.expired, false)
+            .chain(Self::expired_payloads(&data_lake.sl_expired, true))
+            .collect::<Vec<proto::LakePayload>>();
+        let targets: HashSet<u64> = waiting.iter().map(|payload| payload.target_id).collect();
+        let sources: HashSet<u64> = waiting.iter().map(|payload| payload.source_id).collect();
+        let summary = proto::LakeSummary {
+            waiting: waiting.iter().filter(|payload| !payload.sidelink).count() as u64,
+            sl_waiting: waiting.iter().filter(|payload| payload.sidelink).count() as u64,
+            expired: expired.iter().filter(|payload| !payload.sidelink).count() as u64,
+            sl_expired: expired.iter().filter(|payload| payload.sidelink).count() as u64,
+            taken: data_lake.taken(),
+            waiting_bytes: waiting.iter().map(|payload| payload.payload_size).sum(),
+            targets: targets.len() as u64,
+            sources: sources.len() as u64,
+        };
+        let dump = proto::LakeDump {
+            time_step: time_step.as_u64(),
+            summary: Some(summary),
+            waiting,
+            expired,
+        };
+        self.sink
+            .write_all(&dump.encode_to_vec())
+            .expect("Failed to write the lake dump to file");
+    }
+
+    /// Payloads waiting to be received, keyed by their target.
+    fn waiting_payloads(
+        payload_map: &PayloadMap,
+        sidelink: bool,
+    ) -> impl Iterator<Item = proto::LakePayload> + '_ {
+        let mut targets: Vec<&AgentId> = payload_map.keys().collect();
+        targets.sort();
+        targets.into_iter().flat_map(move |target| {
+            payload_map[target]
+                .iter()
+                .map(move |payload| Self::to_proto(*target, payload, sidelink))
+        })
+    }
+
+    /// Expired payloads, keyed by their sender.
+    fn expired_payloads(
+        payload_map: &PayloadMap,
+        sidelink: bool,
+    ) -> impl Iterator<Item = proto::LakePayload> + '_ {
+        let mut sources: Vec<&AgentId> = payload_map.keys().collect();
+        sources.sort();
+        sources.into_iter().flat_map(move |source| {
+            payload_map[source].iter().map(move |payload| {
+                Self::to_proto(payload.metadata.selected_link.target, payload, sidelink)
+            })
+        })
+    }
+
+    fn to_proto(target: AgentId, payload: &DPayload, sidelink: bool) -> proto::LakePayload {
+        let data_blobs = payload
+            .metadata
+            .data_blobs
+            .iter()
+            .map(|blob| proto::DumpBlob {
+                data_type: blob.data_type.as_int(),
+                data_size: blob.data_size.as_u64(),
+                created_at: blob.created_at.as_u64(),
+            })
+            .collect();
+        proto::LakePayload {
+            payload_id: payload.metadata.id.to_string(),
+            source_id: payload.agent_state.device_info.id.as_u64(),
+            target_id: target.as_u64(),
+            sidelink,
+            payload_size: payload.metadata.total_size.as_u64(),
+            expires_at: payload
+                .metadata
+                .expires_at
+                .map(|at| at.as_u64())
+                .unwrap_or(0),
+            data_blobs,
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        self.sink
+            .flush()
+            .expect("Failed to close the lake dump file");
+    }
+}
+
+/// Decodes a lake dump from its content.
+pub fn decode_lake_dump(content: &[u8]) -> Result<proto::LakeDump, Error> {
+    proto::LakeDump::decode(content).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Reads the lake dump written at the end of a simulation.
+pub fn read_lake_dump(dump_file: &Path) -> Result<proto::LakeDump, Error> {
+    decode_lake_dump(&std::fs::read(dump_file)?)
+}
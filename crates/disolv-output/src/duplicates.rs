@@ -0,0 +1,145 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::duplicates::DuplicateCounts;
+use disolv_models::device::types::DeviceClass;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the duplicates received by the agents of every class in every time step. The overhead
+/// is the number of duplicates received for every new blob.
+#[derive(Debug)]
+pub(crate) struct DuplicateWriter {
+    time_step: Vec<u64>,
+    device_class: Vec<u32>,
+    received: Vec<u64>,
+    duplicates: Vec<u64>,
+    rebroadcast: Vec<u64>,
+    withheld: Vec<u64>,
+    overhead: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl DuplicateWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Duplicates)
+            .expect("DuplicateWriter::new: No DuplicateWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            device_class: Vec::new(),
+            received: Vec::new(),
+            duplicates: Vec::new(),
+            rebroadcast: Vec::new(),
+            withheld: Vec::new(),
+            overhead: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let device_class = Field::new("device_class", DataType::UInt32, false);
+        let received = Field::new("received", DataType::UInt64, false);
+        let duplicates = Field::new("duplicates", DataType::UInt64, false);
+        let rebroadcast = Field::new("rebroadcast", DataType::UInt64, false);
+        let withheld = Field::new("withheld", DataType::UInt64, false);
+        let overhead = Field::new("overhead", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms,
+            device_class,
+            received,
+            duplicates,
+            rebroadcast,
+            withheld,
+            overhead,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        device_class: DeviceClass,
+        counts: &DuplicateCounts,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.device_class.push(device_class.as_int());
+        self.received.push(counts.received);
+        self.duplicates.push(counts.duplicates);
+        self.rebroadcast.push(counts.rebroadcast);
+        self.withheld.push(counts.withheld);
+        self.overhead.push(counts.overhead());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "device_class",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.device_class)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "received",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.received))) as ArrayRef,
+                    ),
+                    (
+                        "duplicates",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.duplicates)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "rebroadcast",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.rebroadcast)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "withheld",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.withheld))) as ArrayRef,
+                    ),
+                    (
+                        "overhead",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.overhead)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
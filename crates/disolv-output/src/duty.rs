@@ -0,0 +1,163 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float32Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::sleep::Reachability;
+use disolv_models::device::metrics::Energy;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the radio state, the running totals of the payloads addressed to the agent and the
+/// radio energy of the agents with a duty-cycled radio.
+#[derive(Debug)]
+pub(crate) struct DutyCycleWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    awake: Vec<u32>,
+    addressed: Vec<u64>,
+    reached: Vec<u64>,
+    buffered: Vec<u64>,
+    dropped: Vec<u64>,
+    reachability: Vec<f32>,
+    energy: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl DutyCycleWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::DutyCycle)
+            .expect("DutyCycleWriter::new: No DutyCycleWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            awake: Vec::new(),
+            addressed: Vec::new(),
+            reached: Vec::new(),
+            buffered: Vec::new(),
+            dropped: Vec::new(),
+            reachability: Vec::new(),
+            energy: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let awake = Field::new("awake", DataType::UInt32, false);
+        let addressed = Field::new("addressed", DataType::UInt64, false);
+        let reached = Field::new("reached", DataType::UInt64, false);
+        let buffered = Field::new("buffered", DataType::UInt64, false);
+        let dropped = Field::new("dropped", DataType::UInt64, false);
+        let reachability = Field::new("reachability", DataType::Float32, false);
+        let energy = Field::new("energy", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            awake,
+            addressed,
+            reached,
+            buffered,
+            dropped,
+            reachability,
+            energy,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        awake: bool,
+        reachability: &Reachability,
+        energy: Energy,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.awake.push(awake as u32);
+        self.addressed.push(reachability.addressed);
+        self.reached.push(reachability.reached);
+        self.buffered.push(reachability.buffered);
+        self.dropped.push(reachability.dropped);
+        self.reachability.push(reachability.ratio());
+        self.energy.push(energy.as_u64());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "awake",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.awake))) as ArrayRef,
+                    ),
+                    (
+                        "addressed",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.addressed)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "reached",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.reached))) as ArrayRef,
+                    ),
+                    (
+                        "buffered",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.buffered))) as ArrayRef,
+                    ),
+                    (
+                        "dropped",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.dropped))) as ArrayRef,
+                    ),
+                    (
+                        "reachability",
+                        Arc::new(Float32Array::from(std::mem::take(&mut self.reachability)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "energy",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.energy))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
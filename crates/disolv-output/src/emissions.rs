@@ -0,0 +1,119 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::emissions::Emission;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the fuel and CO2 of the agents since their last position, one row per agent and step.
+#[derive(Debug)]
+pub(crate) struct EmissionWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    speed: Vec<f64>,
+    acceleration: Vec<f64>,
+    fuel: Vec<f64>,
+    co2: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl EmissionWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Emissions)
+            .expect("EmissionWriter::new: No EmissionWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            speed: Vec::new(),
+            acceleration: Vec::new(),
+            fuel: Vec::new(),
+            co2: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let speed = Field::new("speed", DataType::Float64, false);
+        let acceleration = Field::new("acceleration", DataType::Float64, false);
+        let fuel = Field::new("fuel", DataType::Float64, false);
+        let co2 = Field::new("co2", DataType::Float64, false);
+        Schema::new(vec![time_ms, agent_id, speed, acceleration, fuel, co2])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, agent_id: AgentId, emission: &Emission) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.speed.push(emission.speed);
+        self.acceleration.push(emission.acceleration);
+        self.fuel.push(emission.fuel);
+        self.co2.push(emission.co2);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "speed",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.speed))) as ArrayRef,
+                    ),
+                    (
+                        "acceleration",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.acceleration)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "fuel",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.fuel))) as ArrayRef,
+                    ),
+                    (
+                        "co2",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.co2))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::fairness::{ClassFairness, FlowShare};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the fairness of the bytes delivered by the agents of each class in an output interval.
+#[derive(Debug)]
+pub(crate) struct FairnessWriter {
+    time_step: Vec<u64>,
+    device_class: Vec<u32>,
+    agent_count: Vec<u64>,
+    delivered_bytes: Vec<u64>,
+    jain_index: Vec<f64>,
+    starved_agents: Vec<u64>,
+    starved_steps: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl FairnessWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Fairness)
+            .expect("FairnessWriter::new: No FairnessWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            device_class: Vec::new(),
+            agent_count: Vec::new(),
+            delivered_bytes: Vec::new(),
+            jain_index: Vec::new(),
+            starved_agents: Vec::new(),
+            starved_steps: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let device_class = Field::new("device_class", DataType::UInt32, false);
+        let agent_count = Field::new("agent_count", DataType::UInt64, false);
+        let delivered_bytes = Field::new("delivered_bytes", DataType::UInt64, false);
+        let jain_index = Field::new("jain_index", DataType::Float64, false);
+        let starved_agents = Field::new("starved_agents", DataType::UInt64, false);
+        let starved_steps = Field::new("starved_steps", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms,
+            device_class,
+            agent_count,
+            delivered_bytes,
+            jain_index,
+            starved_agents,
+            starved_steps,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, fairness: &ClassFairness) {
+        self.time_step.push(time_step.as_u64());
+        self.device_class.push(fairness.device_class.as_int());
+        self.agent_count.push(fairness.agent_count);
+        self.delivered_bytes.push(fairness.delivered_bytes);
+        self.jain_index.push(fairness.jain_index);
+        self.starved_agents.push(fairness.starved_agents);
+        self.starved_steps.push(fairness.starved_steps);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "device_class",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.device_class)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_count",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_count)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "delivered_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.delivered_bytes)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "jain_index",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.jain_index)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "starved_agents",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.starved_agents)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "starved_steps",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.starved_steps)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
+
+/// Writes the bytes every agent delivered in an output interval, the steps in which it starved
+/// and the fairness of the bytes it received from its sources.
+#[derive(Debug)]
+pub(crate) struct AgentFairnessWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    device_class: Vec<u32>,
+    attempted_bytes: Vec<u64>,
+    delivered_bytes: Vec<u64>,
+    starved_steps: Vec<u64>,
+    source_count: Vec<u64>,
+    source_fairness: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl AgentFairnessWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::AgentFairness)
+            .expect("AgentFairnessWriter::new: No AgentFairnessWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            device_class: Vec::new(),
+            attempted_bytes: Vec::new(),
+            delivered_bytes: Vec::new(),
+            starved_steps: Vec::new(),
+            source_count: Vec::new(),
+            source_fairness: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let device_class = Field::new("device_class", DataType::UInt32, false);
+        let attempted_bytes = Field::new("attempted_bytes", DataType::UInt64, false);
+        let delivered_bytes = Field::new("delivered_bytes", DataType::UInt64, false);
+        let starved_steps = Field::new("starved_steps", DataType::UInt64, false);
+        let source_count = Field::new("source_count", DataType::UInt64, false);
+        let source_fairness = Field::new("source_fairness", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            device_class,
+            attempted_bytes,
+            delivered_bytes,
+            starved_steps,
+            source_count,
+            source_fairness,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, agent_id: AgentId, share: &FlowShare) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.device_class.push(share.device_class.as_int());
+        self.attempted_bytes.push(share.attempted_bytes);
+        self.delivered_bytes.push(share.delivered_bytes);
+        self.starved_steps.push(share.starved_steps);
+        self.source_count.push(share.source_bytes.len() as u64);
+        self.source_fairness
+            .push(share.source_fairness().unwrap_or(1.0));
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "device_class",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.device_class)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "attempted_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.attempted_bytes)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "delivered_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.delivered_bytes)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "starved_steps",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.starved_steps)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "source_count",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.source_count)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "source_fairness",
+                        Arc::new(Float64Array::from(std::mem::take(
+                            &mut self.source_fairness,
+                        ))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::fault::FaultChange;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the faults injected into the simulation when they start and end, to align the other
+/// tables with the faults.
+#[derive(Debug)]
+pub(crate) struct FaultWriter {
+    time_step: Vec<u64>,
+    fault_id: Vec<u32>,
+    fault_type: Vec<u32>,
+    active: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl FaultWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::FaultEvents)
+            .expect("FaultWriter::new: No FaultWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            fault_id: Vec::new(),
+            fault_type: Vec::new(),
+            active: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let fault_id = Field::new("fault_id", DataType::UInt32, false);
+        let fault_type = Field::new("fault_type", DataType::UInt32, false);
+        let active = Field::new("active", DataType::UInt32, false);
+        Schema::new(vec![time_ms, fault_id, fault_type, active])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, change: &FaultChange) {
+        self.time_step.push(time_step.as_u64());
+        self.fault_id.push(change.fault_id);
+        self.fault_type.push(change.fault_type.as_int());
+        self.active.push(change.active as u32);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "fault_id",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.fault_id))) as ArrayRef,
+                    ),
+                    (
+                        "fault_type",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.fault_type)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "active",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.active))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
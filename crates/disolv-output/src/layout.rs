@@ -0,0 +1,173 @@
+use log::{error, info, warn};
+use serde::Deserialize;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the link in the output path to the directory of the last run.
+pub const LATEST_LINK: &str = "latest";
+
+/// Layout of the outputs of successive runs. Every run writes to a directory of its own in the
+/// output path, named with the `run_name` template. The template can refer to `{scenario}`,
+/// `{seed}`, `{timestamp}` (UTC, e.g. `20240501T130501`) and `{tags}`, the tags joined by an
+/// underscore. A run whose directory already exists gets the first free name with a numbered
+/// suffix, e.g. `highway_1`. With `latest_link`, the `latest` link in the output path points to
+/// the directory of the last run. The processes of a distributed run share the directory of the
+/// run, so their run names must be the same, which rules out the timestamp.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RunLayout {
+    pub run_name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub latest_link: bool,
+}
+
+impl RunLayout {
+    /// Name of the directory of a run, with the path separators and the spaces of the values
+    /// replaced by underscores.
+    pub fn run_name(&self, scenario: &str, seed: u64, started: SystemTime) -> String {
+        let mut name = String::new();
+        let mut rest = self.run_name.as_str();
+        while let Some(open) = rest.find('{') {
+            name.push_str(&rest[..open]);
+            let close = match rest[open..].find('}') {
+                Some(close) => open + close,
+                None => {
+                    error!("Unclosed placeholder in the run name template");
+                    panic!("Invalid run name template {}.", self.run_name);
+                }
+            };
+            let value = match &rest[open + 1..close] {
+                "scenario" => scenario.to_string(),
+                "seed" => seed.to_string(),
+                "timestamp" => timestamp(started),
+                "tags" => self.tags.join("_"),
+                placeholder => {
+                    error!("Only scenario, seed, timestamp and tags can be used in a run name");
+                    panic!("Invalid run name placeholder {}.", placeholder);
+                }
+            };
+            name.push_str(&value);
+            rest = &rest[close + 1..];
+        }
+        name.push_str(rest);
+        let name: String = name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' => '_',
+                c if c.is_whitespace() => '_',
+                c => c,
+            })
+            .collect();
+        if name.is_empty() || name == "." || name == ".." {
+            panic!("Invalid run name {}.", name);
+        }
+        name
+    }
+
+    /// Creates the directory of the run in the output path and points the latest link to it.
+    /// A `shared` directory is used as it is when it exists already, so that the processes of a
+    /// distributed run write to the same directory.
+    pub fn create_run_directory(
+        &self,
+        output_path: &Path,
+        run_name: &str,
+        shared: bool,
+    ) -> PathBuf {
+        if let Err(e) = std::fs::create_dir_all(output_path) {
+            panic!(
+                "Failed to create the output directory {}: {}",
+                output_path.display(),
+                e
+            );
+        }
+        let mut suffix = 0;
+        let run_dir = loop {
+            let name = match suffix {
+                0 => run_name.to_string(),
+                _ => format!("{}_{}", run_name, suffix),
+            };
+            let run_dir = output_path.join(&name);
+            match std::fs::create_dir(&run_dir) {
+                Ok(_) => break run_dir,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists && shared => break run_dir,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => suffix += 1,
+                Err(e) => panic!(
+                    "Failed to create the run directory {}: {}",
+                    run_dir.display(),
+                    e
+                ),
+            }
+        };
+        info!("Writing the outputs of the run to {}", run_dir.display());
+        if self.latest_link {
+            link_latest(output_path, &run_dir);
+        }
+        run_dir
+    }
+}
+
+/// Points the latest link in the output path to the run directory. Anything at the place of
+/// the link that is not a link is left alone.
+fn link_latest(output_path: &Path, run_dir: &Path) {
+    let link = output_path.join(LATEST_LINK);
+    if let Ok(metadata) = std::fs::symlink_metadata(&link) {
+        if !metadata.file_type().is_symlink() {
+            warn!(
+                "Not linking the latest run, {} is not a link",
+                link.display()
+            );
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(&link) {
+            warn!("Failed to remove the link {}: {}", link.display(), e);
+            return;
+        }
+    }
+    let target = run_dir.file_name().map(PathBuf::from).unwrap_or_default();
+    #[cfg(unix)]
+    if let Err(e) = std::os::unix::fs::symlink(&target, &link) {
+        warn!("Failed to link the latest run {}: {}", link.display(), e);
+    }
+    #[cfg(not(unix))]
+    warn!(
+        "Links to the latest run are not supported, {} is not created",
+        link.display()
+    );
+}
+
+/// UTC time in the basic ISO 8601 format, which is safe to use in file names.
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let (days, time_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date of the days since the epoch, from H. Hinnant's date algorithms.
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        (time_of_day % 3600) / 60,
+        time_of_day % 60
+    )
+}
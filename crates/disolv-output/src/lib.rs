@@ -1,6 +1,33 @@
+pub mod age;
+pub mod broadcast;
+pub mod cache;
+pub mod charging;
+pub mod dump;
+pub mod duplicates;
+pub mod duty;
+pub mod emissions;
+pub mod fairness;
+pub mod fault;
+pub mod layout;
+pub mod lifecycle;
+pub mod memory;
+pub mod metadata;
+pub mod metrics;
 pub mod net;
+pub mod operator;
+pub mod order;
+pub mod perception;
 pub mod position;
+pub mod prediction;
+pub mod queue;
+pub mod rem;
 pub mod result;
 pub mod rx_counts;
+pub mod sla;
+pub mod state;
+pub mod streaming;
+pub mod trace;
 pub mod tx;
+pub mod tx_power;
+pub mod volume;
 pub mod writer;
@@ -0,0 +1,133 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_models::device::power::Lifecycle;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the lifecycle of every agent that was activated in the simulation. The table is
+/// written once at the end of the simulation, with one row per agent that aggregates its power
+/// cycles: the first activation, the last deactivation and its reason, the active duration
+/// summed over the cycles and the number of cycles.
+#[derive(Debug)]
+pub(crate) struct LifecycleWriter {
+    agent_id: Vec<u64>,
+    first_activation: Vec<u64>,
+    last_deactivation: Vec<u64>,
+    active_duration: Vec<u64>,
+    power_cycles: Vec<u32>,
+    reason: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl LifecycleWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Lifecycle)
+            .expect("LifecycleWriter::new: No LifecycleWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            agent_id: Vec::new(),
+            first_activation: Vec::new(),
+            last_deactivation: Vec::new(),
+            active_duration: Vec::new(),
+            power_cycles: Vec::new(),
+            reason: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let first_activation = Field::new("first_activation", DataType::UInt64, false);
+        let last_deactivation = Field::new("last_deactivation", DataType::UInt64, false);
+        let active_duration = Field::new("active_duration", DataType::UInt64, false);
+        let power_cycles = Field::new("power_cycles", DataType::UInt32, false);
+        let reason = Field::new("reason", DataType::UInt32, false);
+        Schema::new(vec![
+            agent_id,
+            first_activation,
+            last_deactivation,
+            active_duration,
+            power_cycles,
+            reason,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.agent_id.len()
+    }
+
+    pub fn add_data(&mut self, agent_id: AgentId, lifecycle: &Lifecycle) {
+        self.agent_id.push(agent_id.as_u64());
+        self.first_activation
+            .push(lifecycle.first_activation.as_u64());
+        self.last_deactivation
+            .push(lifecycle.last_deactivation.unwrap_or_default().as_u64());
+        self.active_duration
+            .push(lifecycle.active_duration.as_u64());
+        self.power_cycles.push(lifecycle.power_cycles);
+        self.reason.push(lifecycle.reason.as_int());
+        if self.flush_policy.is_full(self.agent_id.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "first_activation",
+                        Arc::new(UInt64Array::from(std::mem::take(
+                            &mut self.first_activation,
+                        ))) as ArrayRef,
+                    ),
+                    (
+                        "last_deactivation",
+                        Arc::new(UInt64Array::from(std::mem::take(
+                            &mut self.last_deactivation,
+                        ))) as ArrayRef,
+                    ),
+                    (
+                        "active_duration",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.active_duration)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "power_cycles",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.power_cycles)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "reason",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.reason))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        self.write_to_file();
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_core::memory::SubsystemMemory;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the estimated memory held by the subsystems of the bucket whenever it is sampled.
+#[derive(Debug)]
+pub(crate) struct MemoryWriter {
+    time_step: Vec<u64>,
+    subsystem: Vec<u32>,
+    entries: Vec<u64>,
+    bytes: Vec<u64>,
+    over_cap: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl MemoryWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Memory)
+            .expect("MemoryWriter::new: No MemoryWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            subsystem: Vec::new(),
+            entries: Vec::new(),
+            bytes: Vec::new(),
+            over_cap: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let subsystem = Field::new("subsystem", DataType::UInt32, false);
+        let entries = Field::new("entries", DataType::UInt64, false);
+        let bytes = Field::new("bytes", DataType::UInt64, false);
+        let over_cap = Field::new("over_cap", DataType::UInt32, false);
+        Schema::new(vec![time_ms, subsystem, entries, bytes, over_cap])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, memory: &SubsystemMemory) {
+        self.time_step.push(time_step.as_u64());
+        self.subsystem.push(memory.id);
+        self.entries.push(memory.entries);
+        self.bytes.push(memory.bytes);
+        self.over_cap.push(memory.over_cap as u32);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "subsystem",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.subsystem)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "entries",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.entries))) as ArrayRef,
+                    ),
+                    (
+                        "bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.bytes))) as ArrayRef,
+                    ),
+                    (
+                        "over_cap",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.over_cap))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use disolv_core::bucket::TimeMS;
+use disolv_core::timing::{StageTimes, STAGES};
+use std::fmt::Write;
+use std::path::Path;
+
+pub const RUN_METADATA_FILE: &str = "run_metadata.toml";
+
+/// A parameter whose value was changed during the run.
+#[derive(Clone, Debug)]
+pub struct SettingChange {
+    pub time_step: TimeMS,
+    pub parameter: String,
+    pub value: String,
+}
+
+/// Writes the performance summary of the simulation, the end of the warm-up and the settings
+/// changed during the run to the run metadata in the output path.
+pub(crate) fn write_run_metadata(
+    output_path: &Path,
+    summary: &StageTimes,
+    warm_up: Option<TimeMS>,
+    setting_changes: &[SettingChange],
+) {
+    let mut content = String::from("[performance]\n");
+    let _ = writeln!(content, "steps = {}", summary.steps);
+    let _ = writeln!(content, "wall_time_ms = {}", summary.wall_time.as_millis());
+    let ms_per_step = match summary.steps {
+        0 => 0.0,
+        steps => summary.wall_time.as_secs_f64() * 1000.0 / steps as f64,
+    };
+    let _ = writeln!(content, "ms_per_step = {:.6}", ms_per_step);
+    for stage in STAGES.iter() {
+        let _ = write!(
+            content,
+            "\n[performance.stages.{}]\ntotal_ms = {:.3}\nms_per_step = {:.6}\nshare = {:.6}\n",
+            stage.name(),
+            summary.of(*stage).as_secs_f64() * 1000.0,
+            summary.ms_per_step(*stage),
+            summary.share(*stage)
+        );
+    }
+    if let Some(warm_up) = warm_up {
+        let _ = write!(content, "\n[warm_up]\nend_ms = {}\n", warm_up);
+    }
+    for change in setting_changes.iter() {
+        let _ = write!(
+            content,
+            "\n[[setting_changes]]\ntime_step = {}\nparameter = \"{}\"\nvalue = \"{}\"\n",
+            change.time_step, change.parameter, change.value
+        );
+    }
+
+    let output_file = output_path.join(RUN_METADATA_FILE);
+    std::fs::write(&output_file, content)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", output_file.display(), e));
+}
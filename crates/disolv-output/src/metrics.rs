@@ -0,0 +1,113 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{
+    ArrayRef, DictionaryArray, Float64Array, RecordBatch, UInt32Array, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, UInt16Type};
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::metrics::MetricSample;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the metrics registered by the models in long format, one row per metric and output
+/// interval. The metric names repeat in every interval and are dictionary encoded.
+#[derive(Debug)]
+pub(crate) struct MetricWriter {
+    time_step: Vec<u64>,
+    metric: Vec<String>,
+    kind: Vec<u32>,
+    value: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl MetricWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Metrics)
+            .expect("MetricWriter::new: No MetricWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            metric: Vec::new(),
+            kind: Vec::new(),
+            value: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let metric = Field::new(
+            "metric",
+            DataType::Dictionary(Box::new(DataType::UInt16), Box::new(DataType::Utf8)),
+            false,
+        );
+        let kind = Field::new("kind", DataType::UInt32, false);
+        let value = Field::new("value", DataType::Float64, false);
+        Schema::new(vec![time_ms, metric, kind, value])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, sample: &MetricSample) {
+        self.time_step.push(time_step.as_u64());
+        self.metric.push(sample.name.to_string());
+        self.kind.push(sample.kind.as_int());
+        self.value.push(sample.value);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "metric",
+                        Arc::new(
+                            std::mem::take(&mut self.metric)
+                                .iter()
+                                .map(String::as_str)
+                                .collect::<DictionaryArray<UInt16Type>>(),
+                        ) as ArrayRef,
+                    ),
+                    (
+                        "kind",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.kind))) as ArrayRef,
+                    ),
+                    (
+                        "value",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.value))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::result::{OutputSettings, OutputType};
-use crate::writer::DataOutput;
+use crate::writer::{DataOutput, FlushPolicy};
 use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use disolv_core::bucket::TimeMS;
@@ -13,7 +13,9 @@ pub(crate) struct NetStatWriter {
     time_step: Vec<u64>,
     slice_id: Vec<u32>,
     bandwidth: Vec<u64>,
+    capacity: Vec<Option<u64>>,
     to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
 }
 
 impl NetStatWriter {
@@ -27,10 +29,12 @@ impl NetStatWriter {
         let output_file = output_path.join(&config.output_filename);
 
         Self {
-            to_output: DataOutput::new(&output_file, Self::schema()),
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
             time_step: Vec::new(),
             slice_id: Vec::new(),
             bandwidth: Vec::new(),
+            capacity: Vec::new(),
         }
     }
 
@@ -38,7 +42,12 @@ impl NetStatWriter {
         let time_ms = Field::new("time_step", DataType::UInt64, false);
         let slice_id = Field::new("slice_id", DataType::UInt32, false);
         let bandwidth = Field::new("bandwidth", DataType::UInt64, false);
-        Schema::new(vec![time_ms, slice_id, bandwidth])
+        let capacity = Field::new("capacity", DataType::UInt64, true);
+        Schema::new(vec![time_ms, slice_id, bandwidth, capacity])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
     }
 
     pub fn add_data(&mut self, time_step: TimeMS, slice: &Slice) {
@@ -46,6 +55,11 @@ impl NetStatWriter {
         self.slice_id.push(slice.id);
         self.bandwidth
             .push(slice.resources.bandwidth_type.available().as_u64());
+        self.capacity
+            .push(slice.capacity().map(|capacity| capacity.as_u64()));
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
     }
 
     pub fn write_to_file(&mut self) {
@@ -66,6 +80,10 @@ impl NetStatWriter {
                         Arc::new(UInt64Array::from(std::mem::take(&mut self.bandwidth)))
                             as ArrayRef,
                     ),
+                    (
+                        "capacity",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.capacity))) as ArrayRef,
+                    ),
                 ])
                 .expect("Failed to convert results to record batch");
                 to_output
@@ -76,7 +94,10 @@ impl NetStatWriter {
         }
     }
 
-    pub(crate) fn close_files(self) {
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
         match self.to_output {
             DataOutput::Parquet(to_output) => to_output.close(),
         }
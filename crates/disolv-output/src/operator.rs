@@ -0,0 +1,129 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::net::operator::{OperatorId, OperatorStats};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the transfers of every operator in every time step, so that the operators sharing
+/// the infrastructure can be compared.
+#[derive(Debug)]
+pub(crate) struct OperatorWriter {
+    time_step: Vec<u64>,
+    operator_id: Vec<u32>,
+    tx_attempted: Vec<u64>,
+    tx_succeeded: Vec<u64>,
+    visiting: Vec<u64>,
+    denied: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl OperatorWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::OperatorStats)
+            .expect("OperatorWriter::new: No OperatorWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            operator_id: Vec::new(),
+            tx_attempted: Vec::new(),
+            tx_succeeded: Vec::new(),
+            visiting: Vec::new(),
+            denied: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let operator_id = Field::new("operator_id", DataType::UInt32, false);
+        let tx_attempted = Field::new("tx_attempted", DataType::UInt64, false);
+        let tx_succeeded = Field::new("tx_succeeded", DataType::UInt64, false);
+        let visiting = Field::new("visiting", DataType::UInt64, false);
+        let denied = Field::new("denied", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms,
+            operator_id,
+            tx_attempted,
+            tx_succeeded,
+            visiting,
+            denied,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, operator_id: OperatorId, stats: &OperatorStats) {
+        self.time_step.push(time_step.as_u64());
+        self.operator_id.push(operator_id);
+        self.tx_attempted.push(stats.attempted);
+        self.tx_succeeded.push(stats.succeeded);
+        self.visiting.push(stats.visiting);
+        self.denied.push(stats.denied);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "operator_id",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.operator_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "tx_attempted",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.tx_attempted)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "tx_succeeded",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.tx_succeeded)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "visiting",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.visiting))) as ArrayRef,
+                    ),
+                    (
+                        "denied",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.denied))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
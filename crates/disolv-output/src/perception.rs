@@ -0,0 +1,109 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the collective perception statistics of every time step. Redundancy is the average
+/// number of agents that detected each object.
+#[derive(Debug)]
+pub(crate) struct PerceptionWriter {
+    time_step: Vec<u64>,
+    detections: Vec<u64>,
+    unique_objects: Vec<u64>,
+    redundancy: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl PerceptionWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Perception)
+            .expect("PerceptionWriter::new: No PerceptionWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            detections: Vec::new(),
+            unique_objects: Vec::new(),
+            redundancy: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let detections = Field::new("detections", DataType::UInt64, false);
+        let unique_objects = Field::new("unique_objects", DataType::UInt64, false);
+        let redundancy = Field::new("redundancy", DataType::Float64, false);
+        Schema::new(vec![time_ms, detections, unique_objects, redundancy])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, detections: u64, unique_objects: u64) {
+        let redundancy = match unique_objects {
+            0 => 0.0,
+            unique => detections as f64 / unique as f64,
+        };
+        self.time_step.push(time_step.as_u64());
+        self.detections.push(detections);
+        self.unique_objects.push(unique_objects);
+        self.redundancy.push(redundancy);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "detections",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.detections)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "unique_objects",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.unique_objects)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "redundancy",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.redundancy)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::result::{OutputSettings, OutputType};
-use crate::writer::DataOutput;
+use crate::writer::{DataOutput, FlushPolicy};
 use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use disolv_core::agent::AgentId;
@@ -15,6 +15,7 @@ pub(crate) struct PosWriter {
     x: Vec<f64>,
     y: Vec<f64>,
     to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
 }
 
 impl PosWriter {
@@ -27,7 +28,8 @@ impl PosWriter {
             .expect("PosWriter::new: No PosWriter config found");
         let output_file = output_path.join(&config.output_filename);
         Self {
-            to_output: DataOutput::new(&output_file, Self::schema()),
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
             time_step: Vec::new(),
             agent_id: Vec::new(),
             x: Vec::new(),
@@ -43,11 +45,18 @@ impl PosWriter {
         Schema::new(vec![time_ms, agent_id, x, y])
     }
 
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
     pub fn add_data(&mut self, time_step: TimeMS, agent_id: AgentId, map_state: &MapState) {
         self.time_step.push(time_step.as_u64());
         self.agent_id.push(agent_id.as_u64());
         self.x.push(map_state.pos.x);
         self.y.push(map_state.pos.y);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
     }
 
     pub fn write_to_file(&mut self) {
@@ -81,7 +90,144 @@ impl PosWriter {
         }
     }
 
-    pub(crate) fn close_files(self) {
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
+
+/// Writes the true and the perceived positions of the agents with a positioning error, and the
+/// distance between them.
+#[derive(Debug)]
+pub(crate) struct PerceivedPosWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    perceived_x: Vec<f64>,
+    perceived_y: Vec<f64>,
+    error: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl PerceivedPosWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::PerceivedPos)
+            .expect("PerceivedPosWriter::new: No PerceivedPosWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            x: Vec::new(),
+            y: Vec::new(),
+            perceived_x: Vec::new(),
+            perceived_y: Vec::new(),
+            error: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let x = Field::new("x", DataType::Float64, false);
+        let y = Field::new("y", DataType::Float64, false);
+        let perceived_x = Field::new("perceived_x", DataType::Float64, false);
+        let perceived_y = Field::new("perceived_y", DataType::Float64, false);
+        let error = Field::new("error", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            x,
+            y,
+            perceived_x,
+            perceived_y,
+            error,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        map_state: &MapState,
+        perceived: &MapState,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.x.push(map_state.pos.x);
+        self.y.push(map_state.pos.y);
+        self.perceived_x.push(perceived.pos.x);
+        self.perceived_y.push(perceived.pos.y);
+        self.error.push(map_state.slant_range(perceived));
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "x",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.x))) as ArrayRef,
+                    ),
+                    (
+                        "y",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.y))) as ArrayRef,
+                    ),
+                    (
+                        "perceived_x",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.perceived_x)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "perceived_y",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.perceived_y)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "error",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.error))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
         match self.to_output {
             DataOutput::Parquet(to_output) => to_output.close(),
         }
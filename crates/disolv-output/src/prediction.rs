@@ -0,0 +1,136 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::predict::PredictionError;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the positions predicted for the agents next to the positions they were observed at,
+/// to validate the mobility prediction.
+#[derive(Debug)]
+pub(crate) struct PredictionWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    predicted_x: Vec<f64>,
+    predicted_y: Vec<f64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    error: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl PredictionWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Prediction)
+            .expect("PredictionWriter::new: No PredictionWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            predicted_x: Vec::new(),
+            predicted_y: Vec::new(),
+            x: Vec::new(),
+            y: Vec::new(),
+            error: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let predicted_x = Field::new("predicted_x", DataType::Float64, false);
+        let predicted_y = Field::new("predicted_y", DataType::Float64, false);
+        let x = Field::new("x", DataType::Float64, false);
+        let y = Field::new("y", DataType::Float64, false);
+        let error = Field::new("error", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms,
+            agent_id,
+            predicted_x,
+            predicted_y,
+            x,
+            y,
+            error,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, error: &PredictionError) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(error.agent_id.as_u64());
+        self.predicted_x.push(error.predicted.x);
+        self.predicted_y.push(error.predicted.y);
+        self.x.push(error.observed.x);
+        self.y.push(error.observed.y);
+        self.error.push(error.distance());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "predicted_x",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.predicted_x)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "predicted_y",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.predicted_y)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "x",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.x))) as ArrayRef,
+                    ),
+                    (
+                        "y",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.y))) as ArrayRef,
+                    ),
+                    (
+                        "error",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.error))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::queue::QueueStats;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the processing queue statistics of every agent with a fifo processor at every time
+/// step, including the queueing delays of the payloads served in the step.
+#[derive(Debug)]
+pub(crate) struct QueueWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    served: Vec<u64>,
+    dropped: Vec<u64>,
+    queued: Vec<u64>,
+    mean_delay: Vec<f64>,
+    max_delay: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl QueueWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Queue)
+            .expect("QueueWriter::new: No QueueWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            served: Vec::new(),
+            dropped: Vec::new(),
+            queued: Vec::new(),
+            mean_delay: Vec::new(),
+            max_delay: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let served = Field::new("served", DataType::UInt64, false);
+        let dropped = Field::new("dropped", DataType::UInt64, false);
+        let queued = Field::new("queued", DataType::UInt64, false);
+        let mean_delay = Field::new("mean_delay", DataType::Float64, false);
+        let max_delay = Field::new("max_delay", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms, agent_id, served, dropped, queued, mean_delay, max_delay,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, agent_id: AgentId, stats: &QueueStats) {
+        let mean_delay = match stats.served {
+            0 => 0.0,
+            served => stats.total_delay.as_u64() as f64 / served as f64,
+        };
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.served.push(stats.served);
+        self.dropped.push(stats.dropped);
+        self.queued.push(stats.queued);
+        self.mean_delay.push(mean_delay);
+        self.max_delay.push(stats.max_delay.as_u64());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "served",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.served))) as ArrayRef,
+                    ),
+                    (
+                        "dropped",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.dropped))) as ArrayRef,
+                    ),
+                    (
+                        "queued",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.queued))) as ArrayRef,
+                    ),
+                    (
+                        "mean_delay",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.mean_delay)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "max_delay",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.max_delay)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
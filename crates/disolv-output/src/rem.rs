@@ -0,0 +1,130 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::net::rem::RemCell;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the radio environment map, one row per sampled cell and output interval with the mean
+/// and 95th percentile of the SINR and the share of the samples in outage.
+#[derive(Debug)]
+pub(crate) struct RadioMapWriter {
+    time_step: Vec<u64>,
+    cell_x: Vec<u32>,
+    cell_y: Vec<u32>,
+    samples: Vec<u64>,
+    mean_sinr: Vec<f64>,
+    p95_sinr: Vec<f64>,
+    outage: Vec<f64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl RadioMapWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::RadioMap)
+            .expect("RadioMapWriter::new: No RadioMap config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            cell_x: Vec::new(),
+            cell_y: Vec::new(),
+            samples: Vec::new(),
+            mean_sinr: Vec::new(),
+            p95_sinr: Vec::new(),
+            outage: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let cell_x = Field::new("cell_x", DataType::UInt32, false);
+        let cell_y = Field::new("cell_y", DataType::UInt32, false);
+        let samples = Field::new("samples", DataType::UInt64, false);
+        let mean_sinr = Field::new("mean_sinr", DataType::Float64, false);
+        let p95_sinr = Field::new("p95_sinr", DataType::Float64, false);
+        let outage = Field::new("outage", DataType::Float64, false);
+        Schema::new(vec![
+            time_ms, cell_x, cell_y, samples, mean_sinr, p95_sinr, outage,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, cell: &RemCell) {
+        self.time_step.push(time_step.as_u64());
+        self.cell_x.push(cell.cell_x);
+        self.cell_y.push(cell.cell_y);
+        self.samples.push(cell.samples);
+        self.mean_sinr.push(cell.mean_sinr);
+        self.p95_sinr.push(cell.p95_sinr);
+        self.outage.push(cell.outage);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "cell_x",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.cell_x))) as ArrayRef,
+                    ),
+                    (
+                        "cell_y",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.cell_y))) as ArrayRef,
+                    ),
+                    (
+                        "samples",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.samples))) as ArrayRef,
+                    ),
+                    (
+                        "mean_sinr",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.mean_sinr)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "p95_sinr",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.p95_sinr)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "outage",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.outage))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
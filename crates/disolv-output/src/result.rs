@@ -1,15 +1,68 @@
+use crate::age::AgeWriter;
+use crate::broadcast::BroadcastWriter;
+use crate::cache::CacheWriter;
+use crate::charging::{ChargingSessionWriter, OccupancyWriter};
+use crate::dump::LakeDumpWriter;
+use crate::duplicates::DuplicateWriter;
+use crate::duty::DutyCycleWriter;
+use crate::emissions::EmissionWriter;
+use crate::fairness::{AgentFairnessWriter, FairnessWriter};
+use crate::fault::FaultWriter;
+use crate::layout::RunLayout;
+use crate::lifecycle::LifecycleWriter;
+use crate::memory::MemoryWriter;
+use crate::metadata::{write_run_metadata, SettingChange};
+use crate::metrics::MetricWriter;
 use crate::net::NetStatWriter;
-use crate::position::PosWriter;
+use crate::operator::OperatorWriter;
+use crate::order::ExecutionOrderWriter;
+use crate::perception::PerceptionWriter;
+use crate::position::{PerceivedPosWriter, PosWriter};
+use crate::prediction::PredictionWriter;
+use crate::queue::QueueWriter;
+use crate::rem::RadioMapWriter;
 use crate::rx_counts::RxCountWriter;
+use crate::sla::SlaWriter;
+use crate::state::StateWriter;
+use crate::streaming::StreamingWriter;
+use crate::trace::TraceWriter;
 use crate::tx::TxDataWriter;
+use crate::tx_power::TxPowerWriter;
+use crate::volume::VolumeWriter;
+use crate::writer::{Cadence, MemoryTables, RunMetadata};
 use disolv_core::agent::AgentId;
 use disolv_core::bucket::TimeMS;
+use disolv_core::memory::SubsystemMemory;
+use disolv_core::state::{MachineState, StateChange};
+use disolv_core::timing::StageTimes;
+use disolv_models::bucket::age::AgeRecord;
+use disolv_models::bucket::charging::{ChargingSession, StationOccupancy};
+use disolv_models::bucket::fairness::{ClassFairness, FlowShare};
+use disolv_models::bucket::fault::FaultChange;
+use disolv_models::bucket::lake::DataLake;
+use disolv_models::bucket::metrics::MetricSample;
+use disolv_models::bucket::sla::SlaRecord;
+use disolv_models::bucket::sleep::Reachability;
+use disolv_models::bucket::volume::DataVolume;
+use disolv_models::device::broadcast::BroadcastReception;
+use disolv_models::device::cache::CacheStats;
+use disolv_models::device::duplicates::DuplicateCounts;
+use disolv_models::device::metrics::Energy;
+use disolv_models::device::mobility::emissions::Emission;
 use disolv_models::device::mobility::MapState;
-use disolv_models::net::message::{DPayload, TxMetrics};
+use disolv_models::device::power::Lifecycle;
+use disolv_models::device::predict::PredictionError;
+use disolv_models::device::queue::QueueStats;
+use disolv_models::device::tx_power::TxPowerChoice;
+use disolv_models::device::types::DeviceClass;
+use disolv_models::net::message::{DPayload, TxMetrics, TxStatus};
+use disolv_models::net::operator::{OperatorId, OperatorStats};
 use disolv_models::net::radio::{DLink, OutgoingStats};
+use disolv_models::net::rem::RemCell;
 use disolv_models::net::slice::Slice;
 use log::debug;
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
 
 #[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum OutputType {
@@ -17,6 +70,34 @@ pub enum OutputType {
     TxData,
     AgentPos,
     NetStat,
+    Perception,
+    Cache,
+    Prediction,
+    Lifecycle,
+    DutyCycle,
+    AgeOfInformation,
+    Fairness,
+    AgentFairness,
+    StateChanges,
+    DataVolume,
+    PayloadTrace,
+    Memory,
+    FaultEvents,
+    SlaCompliance,
+    StreamingIntervals,
+    Metrics,
+    BroadcastReception,
+    Emissions,
+    OperatorStats,
+    Duplicates,
+    StationOccupancy,
+    ChargingSessions,
+    TxPower,
+    PerceivedPos,
+    LakeDump,
+    ExecutionOrder,
+    RadioMap,
+    Queue,
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -25,17 +106,33 @@ pub enum FileType {
     Parquet,
 }
 
+/// Output configuration of a table. The buffered rows are written to the file when they
+/// exceed `max_rows` or `max_bytes`. Tables with an `output_interval` are written at that
+/// interval instead of the output interval of the simulation. Tables with a `sample_interval`
+/// only record their rows every `sample_interval`, e.g. positions every second while the
+/// transfers are recorded in every step. The state changes are recorded as they happen, and the
+/// fairness, volume and lifecycle tables summarise the output intervals, so they ignore it.
 #[derive(Deserialize, Debug, Clone)]
 pub struct FileOutConfig {
     pub output_type: OutputType,
     pub output_filename: String,
+    pub output_interval: Option<TimeMS>,
+    pub sample_interval: Option<TimeMS>,
+    pub max_rows: Option<usize>,
+    pub max_bytes: Option<usize>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct OutputSettings {
     pub output_interval: TimeMS,
     pub output_path: String,
+    pub run_layout: Option<RunLayout>,
     pub file_out_config: Vec<FileOutConfig>,
+    pub warm_up: Option<TimeMS>,
+    #[serde(skip)]
+    pub memory: Option<MemoryTables>,
+    #[serde(skip)]
+    pub run_metadata: Option<RunMetadata>,
 }
 
 #[derive(Debug)]
@@ -44,6 +141,40 @@ pub struct ResultWriter {
     rx_count_writer: Option<RxCountWriter>,
     agent_pos_writer: Option<PosWriter>,
     net_stat_writer: Option<NetStatWriter>,
+    perception_writer: Option<PerceptionWriter>,
+    cache_writer: Option<CacheWriter>,
+    prediction_writer: Option<PredictionWriter>,
+    lifecycle_writer: Option<LifecycleWriter>,
+    duty_cycle_writer: Option<DutyCycleWriter>,
+    age_writer: Option<AgeWriter>,
+    fairness_writer: Option<FairnessWriter>,
+    agent_fairness_writer: Option<AgentFairnessWriter>,
+    state_writer: Option<StateWriter>,
+    volume_writer: Option<VolumeWriter>,
+    trace_writer: Option<TraceWriter>,
+    memory_writer: Option<MemoryWriter>,
+    fault_writer: Option<FaultWriter>,
+    sla_writer: Option<SlaWriter>,
+    streaming_writer: Option<StreamingWriter>,
+    metric_writer: Option<MetricWriter>,
+    broadcast_writer: Option<BroadcastWriter>,
+    emission_writer: Option<EmissionWriter>,
+    operator_writer: Option<OperatorWriter>,
+    duplicate_writer: Option<DuplicateWriter>,
+    occupancy_writer: Option<OccupancyWriter>,
+    session_writer: Option<ChargingSessionWriter>,
+    tx_power_writer: Option<TxPowerWriter>,
+    perceived_pos_writer: Option<PerceivedPosWriter>,
+    lake_dump_writer: Option<LakeDumpWriter>,
+    order_writer: Option<ExecutionOrderWriter>,
+    radio_map_writer: Option<RadioMapWriter>,
+    queue_writer: Option<QueueWriter>,
+    cadences: Vec<(OutputType, Cadence)>,
+    setting_changes: Vec<SettingChange>,
+    warm_up: Option<TimeMS>,
+    warming_up: bool,
+    output_path: PathBuf,
+    in_memory: bool,
 }
 
 impl ResultWriter {
@@ -68,12 +199,221 @@ impl ResultWriter {
             .iter()
             .find(|&file_out_config| file_out_config.output_type == OutputType::NetStat)
             .map(|_| NetStatWriter::new(output_settings));
+        let perception_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Perception)
+            .map(|_| PerceptionWriter::new(output_settings));
+        let cache_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Cache)
+            .map(|_| CacheWriter::new(output_settings));
+        let prediction_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Prediction)
+            .map(|_| PredictionWriter::new(output_settings));
+        let lifecycle_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Lifecycle)
+            .map(|_| LifecycleWriter::new(output_settings));
+        let duty_cycle_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::DutyCycle)
+            .map(|_| DutyCycleWriter::new(output_settings));
+        let age_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::AgeOfInformation)
+            .map(|_| AgeWriter::new(output_settings));
+        let fairness_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Fairness)
+            .map(|_| FairnessWriter::new(output_settings));
+        let agent_fairness_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::AgentFairness)
+            .map(|_| AgentFairnessWriter::new(output_settings));
+        let state_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StateChanges)
+            .map(|_| StateWriter::new(output_settings));
+        let volume_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::DataVolume)
+            .map(|_| VolumeWriter::new(output_settings));
+        let trace_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::PayloadTrace)
+            .map(|_| TraceWriter::new(output_settings));
+        let memory_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Memory)
+            .map(|_| MemoryWriter::new(output_settings));
+        let fault_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::FaultEvents)
+            .map(|_| FaultWriter::new(output_settings));
+        let sla_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::SlaCompliance)
+            .map(|_| SlaWriter::new(output_settings));
+        let streaming_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StreamingIntervals)
+            .map(|_| StreamingWriter::new(output_settings));
+        let metric_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Metrics)
+            .map(|_| MetricWriter::new(output_settings));
+        let broadcast_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::BroadcastReception)
+            .map(|_| BroadcastWriter::new(output_settings));
+        let emission_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Emissions)
+            .map(|_| EmissionWriter::new(output_settings));
+        let operator_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::OperatorStats)
+            .map(|_| OperatorWriter::new(output_settings));
+        let duplicate_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Duplicates)
+            .map(|_| DuplicateWriter::new(output_settings));
+        let occupancy_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StationOccupancy)
+            .map(|_| OccupancyWriter::new(output_settings));
+        let session_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::ChargingSessions)
+            .map(|_| ChargingSessionWriter::new(output_settings));
+        let tx_power_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::TxPower)
+            .map(|_| TxPowerWriter::new(output_settings));
+        let perceived_pos_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::PerceivedPos)
+            .map(|_| PerceivedPosWriter::new(output_settings));
+        let lake_dump_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::LakeDump)
+            .map(|_| LakeDumpWriter::new(output_settings));
+        let order_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::ExecutionOrder)
+            .map(|_| ExecutionOrderWriter::new(output_settings));
+        let radio_map_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::RadioMap)
+            .map(|_| RadioMapWriter::new(output_settings));
+        let queue_writer = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::Queue)
+            .map(|_| QueueWriter::new(output_settings));
+        let cadences = output_settings
+            .file_out_config
+            .iter()
+            .filter_map(|file_out_config| {
+                file_out_config
+                    .sample_interval
+                    .map(|interval| (file_out_config.output_type, Cadence::new(interval)))
+            })
+            .collect();
         Self {
             tx_writer,
             rx_count_writer,
             agent_pos_writer,
             net_stat_writer,
+            perception_writer,
+            cache_writer,
+            prediction_writer,
+            lifecycle_writer,
+            duty_cycle_writer,
+            age_writer,
+            fairness_writer,
+            agent_fairness_writer,
+            state_writer,
+            volume_writer,
+            trace_writer,
+            memory_writer,
+            fault_writer,
+            sla_writer,
+            streaming_writer,
+            metric_writer,
+            broadcast_writer,
+            emission_writer,
+            operator_writer,
+            duplicate_writer,
+            occupancy_writer,
+            session_writer,
+            tx_power_writer,
+            perceived_pos_writer,
+            lake_dump_writer,
+            order_writer,
+            radio_map_writer,
+            queue_writer,
+            cadences,
+            setting_changes: Vec::new(),
+            warm_up: output_settings.warm_up,
+            warming_up: output_settings
+                .warm_up
+                .is_some_and(|warm_up| warm_up.as_u64() > 0),
+            output_path: PathBuf::from(&output_settings.output_path),
+            in_memory: output_settings.memory.is_some(),
+        }
+    }
+
+    /// Decides which tables record their rows in this step. No table records its rows before
+    /// the end of the warm-up.
+    pub fn start_step(&mut self, step: TimeMS) {
+        self.warming_up = self.warm_up.is_some_and(|warm_up| step < warm_up);
+        self.cadences
+            .iter_mut()
+            .for_each(|(_, cadence)| cadence.start_step(step));
+    }
+
+    /// Whether the run is still in the warm-up, during which the outputs are not recorded.
+    pub fn is_warming_up(&self) -> bool {
+        self.warming_up
+    }
+
+    fn is_sampled(&self, output_type: OutputType) -> bool {
+        if self.warming_up {
+            return false;
         }
+        self.cadences
+            .iter()
+            .find(|(table, _)| *table == output_type)
+            .is_none_or(|(_, cadence)| cadence.is_due())
     }
 
     pub fn add_rx_counts(
@@ -82,6 +422,9 @@ impl ResultWriter {
         agent_id: AgentId,
         in_data_stats: &OutgoingStats,
     ) {
+        if !self.is_sampled(OutputType::RxCounts) {
+            return;
+        }
         match &mut self.rx_count_writer {
             Some(rx) => {
                 rx.add_data(time_step, agent_id, in_data_stats);
@@ -97,6 +440,14 @@ impl ResultWriter {
         payload: &DPayload,
         tx_metrics: TxMetrics,
     ) {
+        if self.is_sampled(OutputType::PayloadTrace) {
+            if let Some(trace) = &mut self.trace_writer {
+                trace.add_data(time_step, link, payload, tx_metrics);
+            }
+        }
+        if !self.is_sampled(OutputType::TxData) {
+            return;
+        }
         match &mut self.tx_writer {
             Some(tx) => {
                 tx.add_data(time_step, link, payload, tx_metrics);
@@ -106,6 +457,9 @@ impl ResultWriter {
     }
 
     pub fn add_agent_pos(&mut self, time_step: TimeMS, agent_id: AgentId, map_state: &MapState) {
+        if !self.is_sampled(OutputType::AgentPos) {
+            return;
+        }
         match &mut self.agent_pos_writer {
             Some(pos) => pos.add_data(time_step, agent_id, map_state),
             None => (),
@@ -113,30 +467,857 @@ impl ResultWriter {
     }
 
     pub fn add_net_stats(&mut self, time_step: TimeMS, slice: &Slice) {
+        if !self.is_sampled(OutputType::NetStat) {
+            return;
+        }
         match &mut self.net_stat_writer {
             Some(net) => net.add_data(time_step, slice),
             None => (),
         }
     }
 
+    pub fn add_perception(&mut self, time_step: TimeMS, detections: u64, unique_objects: u64) {
+        if !self.is_sampled(OutputType::Perception) {
+            return;
+        }
+        if let Some(perception) = &mut self.perception_writer {
+            perception.add_data(time_step, detections, unique_objects);
+        }
+    }
+
+    pub fn add_cache_stats(&mut self, time_step: TimeMS, agent_id: AgentId, stats: &CacheStats) {
+        if !self.is_sampled(OutputType::Cache) {
+            return;
+        }
+        if let Some(cache) = &mut self.cache_writer {
+            cache.add_data(time_step, agent_id, stats);
+        }
+    }
+
+    pub fn add_queue_stats(&mut self, time_step: TimeMS, agent_id: AgentId, stats: &QueueStats) {
+        if !self.is_sampled(OutputType::Queue) {
+            return;
+        }
+        if let Some(queue) = &mut self.queue_writer {
+            queue.add_data(time_step, agent_id, stats);
+        }
+    }
+
+    pub fn add_prediction(&mut self, time_step: TimeMS, error: &PredictionError) {
+        if !self.is_sampled(OutputType::Prediction) {
+            return;
+        }
+        if let Some(prediction) = &mut self.prediction_writer {
+            prediction.add_data(time_step, error);
+        }
+    }
+
+    pub fn add_duty_cycle(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        awake: bool,
+        reachability: &Reachability,
+        energy: Energy,
+    ) {
+        if !self.is_sampled(OutputType::DutyCycle) {
+            return;
+        }
+        if let Some(writer) = &mut self.duty_cycle_writer {
+            writer.add_data(time_step, agent_id, awake, reachability, energy);
+        }
+    }
+
+    pub fn add_age(
+        &mut self,
+        time_step: TimeMS,
+        source_id: AgentId,
+        target_id: AgentId,
+        record: &AgeRecord,
+    ) {
+        if !self.is_sampled(OutputType::AgeOfInformation) {
+            return;
+        }
+        if let Some(writer) = &mut self.age_writer {
+            writer.add_data(time_step, source_id, target_id, record);
+        }
+    }
+
+    pub fn add_class_fairness(&mut self, time_step: TimeMS, fairness: &ClassFairness) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.fairness_writer {
+            writer.add_data(time_step, fairness);
+        }
+    }
+
+    pub fn add_agent_fairness(&mut self, time_step: TimeMS, agent_id: AgentId, share: &FlowShare) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.agent_fairness_writer {
+            writer.add_data(time_step, agent_id, share);
+        }
+    }
+
+    pub fn add_state_change<S: MachineState>(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        change: &StateChange<S>,
+    ) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.state_writer {
+            writer.add_data(time_step, agent_id, change);
+        }
+    }
+
+    pub fn add_data_volume(&mut self, time_step: TimeMS, volume: &DataVolume) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.volume_writer {
+            writer.add_data(time_step, volume);
+        }
+    }
+
+    pub fn add_memory(&mut self, time_step: TimeMS, memory: &SubsystemMemory) {
+        if !self.is_sampled(OutputType::Memory) {
+            return;
+        }
+        if let Some(writer) = &mut self.memory_writer {
+            writer.add_data(time_step, memory);
+        }
+    }
+
+    pub fn add_fault_change(&mut self, time_step: TimeMS, change: &FaultChange) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.fault_writer {
+            writer.add_data(time_step, change);
+        }
+    }
+
+    pub fn add_sla_record(&mut self, time_step: TimeMS, record: &SlaRecord) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.sla_writer {
+            writer.add_data(time_step, record);
+        }
+    }
+
+    pub fn add_streaming_interval(
+        &mut self,
+        time_step: TimeMS,
+        interval: TimeMS,
+        displacement: Option<f64>,
+    ) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.streaming_writer {
+            writer.add_data(time_step, interval, displacement);
+        }
+    }
+
+    pub fn add_metric(&mut self, time_step: TimeMS, sample: &MetricSample) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.metric_writer {
+            writer.add_data(time_step, sample);
+        }
+    }
+
+    pub fn writes_metrics(&self) -> bool {
+        self.metric_writer.is_some()
+    }
+
+    pub fn add_broadcast(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        target_class: DeviceClass,
+        tx_status: TxStatus,
+        reception: &BroadcastReception,
+    ) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.broadcast_writer {
+            writer.add_data(time_step, agent_id, target_class, tx_status, reception);
+        }
+    }
+
+    pub fn add_emission(&mut self, time_step: TimeMS, agent_id: AgentId, emission: &Emission) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.emission_writer {
+            writer.add_data(time_step, agent_id, emission);
+        }
+    }
+
+    pub fn add_operator_stats(
+        &mut self,
+        time_step: TimeMS,
+        operator_id: OperatorId,
+        stats: &OperatorStats,
+    ) {
+        if !self.is_sampled(OutputType::OperatorStats) {
+            return;
+        }
+        if let Some(writer) = &mut self.operator_writer {
+            writer.add_data(time_step, operator_id, stats);
+        }
+    }
+
+    pub fn add_duplicates(
+        &mut self,
+        time_step: TimeMS,
+        device_class: DeviceClass,
+        counts: &DuplicateCounts,
+    ) {
+        if !self.is_sampled(OutputType::Duplicates) {
+            return;
+        }
+        if let Some(writer) = &mut self.duplicate_writer {
+            writer.add_data(time_step, device_class, counts);
+        }
+    }
+
+    pub fn add_station_occupancy(&mut self, time_step: TimeMS, occupancy: &StationOccupancy) {
+        if !self.is_sampled(OutputType::StationOccupancy) {
+            return;
+        }
+        if let Some(writer) = &mut self.occupancy_writer {
+            writer.add_data(time_step, occupancy);
+        }
+    }
+
+    pub fn add_charging_session(&mut self, time_step: TimeMS, session: &ChargingSession) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.session_writer {
+            writer.add_data(time_step, session);
+        }
+    }
+
+    pub fn add_tx_power(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        target_id: AgentId,
+        distance: Option<f32>,
+        choice: &TxPowerChoice,
+    ) {
+        if !self.is_sampled(OutputType::TxPower) {
+            return;
+        }
+        if let Some(writer) = &mut self.tx_power_writer {
+            writer.add_data(time_step, agent_id, target_id, distance, choice);
+        }
+    }
+
+    pub fn add_perceived_pos(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        map_state: &MapState,
+        perceived: &MapState,
+    ) {
+        if !self.is_sampled(OutputType::PerceivedPos) {
+            return;
+        }
+        if let Some(writer) = &mut self.perceived_pos_writer {
+            writer.add_data(time_step, agent_id, map_state, perceived);
+        }
+    }
+
+    pub fn add_execution_order(&mut self, time_step: TimeMS, agent_ids: &[AgentId]) {
+        if !self.is_sampled(OutputType::ExecutionOrder) {
+            return;
+        }
+        if let Some(writer) = &mut self.order_writer {
+            writer.add_data(time_step, agent_ids);
+        }
+    }
+
+    pub fn add_radio_map_cell(&mut self, time_step: TimeMS, cell: &RemCell) {
+        if self.warming_up {
+            return;
+        }
+        if let Some(writer) = &mut self.radio_map_writer {
+            writer.add_data(time_step, cell);
+        }
+    }
+
+    /// Dumps the payloads left in the data lake, at the end of the simulation.
+    pub fn add_lake_dump(&mut self, time_step: TimeMS, data_lake: &DataLake) {
+        if let Some(writer) = &mut self.lake_dump_writer {
+            writer.add_data(time_step, data_lake);
+        }
+    }
+
+    pub fn writes_volumes(&self) -> bool {
+        self.volume_writer.is_some()
+    }
+
+    /// Whether any of the fairness tables is written.
+    pub fn writes_fairness(&self) -> bool {
+        self.fairness_writer.is_some() || self.agent_fairness_writer.is_some()
+    }
+
+    /// Adds the lifecycle of an agent. Lifecycles are written when the files are closed.
+    pub fn add_lifecycle(&mut self, agent_id: AgentId, lifecycle: &Lifecycle) {
+        if let Some(writer) = &mut self.lifecycle_writer {
+            writer.add_data(agent_id, lifecycle);
+        }
+    }
+
+    /// Rows buffered in memory by all the tables and their estimated size in bytes.
+    pub fn buffered(&self) -> (u64, u64) {
+        let mut buffered = Vec::new();
+        if let Some(writer) = &self.tx_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.rx_count_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.agent_pos_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.net_stat_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.perception_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.cache_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.queue_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.prediction_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.lifecycle_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.duty_cycle_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.age_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.fairness_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.agent_fairness_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.state_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.volume_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.trace_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.memory_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.fault_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.sla_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.streaming_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.metric_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.broadcast_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.emission_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.operator_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.duplicate_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.occupancy_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.session_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.tx_power_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.perceived_pos_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.order_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        if let Some(writer) = &self.radio_map_writer {
+            buffered.push((writer.buffered_rows(), &writer.flush_policy));
+        }
+        buffered
+            .into_iter()
+            .fold((0, 0), |(rows, bytes), (buffered_rows, flush_policy)| {
+                (
+                    rows + buffered_rows as u64,
+                    bytes + flush_policy.bytes_of(buffered_rows) as u64,
+                )
+            })
+    }
+
+    /// Records a change to a setting made during the run, written with the run metadata.
+    pub fn add_setting_change(&mut self, time_step: TimeMS, parameter: String, value: String) {
+        self.setting_changes.push(SettingChange {
+            time_step,
+            parameter,
+            value,
+        });
+    }
+
+    /// Path of the output files, or none when the tables are kept in memory.
+    pub fn output_path(&self) -> Option<&Path> {
+        (!self.in_memory).then_some(self.output_path.as_path())
+    }
+
+    /// Writes the performance summary, the end of the warm-up and the setting changes to the run
+    /// metadata. Nothing is
+    /// written when the tables are kept in memory.
+    pub fn write_performance(&self, summary: &StageTimes) {
+        if self.in_memory {
+            return;
+        }
+        write_run_metadata(
+            &self.output_path,
+            summary,
+            self.warm_up,
+            &self.setting_changes,
+        );
+    }
+
+    /// Writes the tables that follow the output interval of the simulation.
     pub fn write_output(&mut self, step: TimeMS) {
         debug!("Writing output at step {}", step);
-        match &mut self.tx_writer {
-            Some(writer) => writer.write_to_file(),
-            None => (),
-        };
-        match &mut self.rx_count_writer {
-            Some(writer) => writer.write_to_file(),
-            None => (),
-        };
-        match &mut self.agent_pos_writer {
-            Some(writer) => writer.write_to_file(),
-            None => (),
-        };
-        match &mut self.net_stat_writer {
-            Some(writer) => writer.write_to_file(),
-            None => (),
-        };
+        if let Some(writer) = &mut self.tx_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.rx_count_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.agent_pos_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.net_stat_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.perception_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.cache_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.queue_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.prediction_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.duty_cycle_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.age_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.fairness_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.agent_fairness_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.state_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.volume_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.trace_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.memory_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.fault_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.sla_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.streaming_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.metric_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.broadcast_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.emission_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.operator_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.duplicate_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.occupancy_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.session_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.tx_power_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.perceived_pos_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.order_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.radio_map_writer {
+            if !writer.flush_policy.has_interval() {
+                writer.write_to_file();
+            }
+        }
+    }
+
+    /// Writes the tables with an output interval of their own when the interval is due.
+    pub fn write_due_output(&mut self, step: TimeMS) {
+        if let Some(writer) = &mut self.tx_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.rx_count_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.agent_pos_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.net_stat_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.perception_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.cache_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.queue_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.prediction_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.duty_cycle_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.age_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.fairness_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.agent_fairness_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.state_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.volume_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.trace_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.memory_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.fault_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.sla_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.streaming_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.metric_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.broadcast_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.emission_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.operator_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.duplicate_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.occupancy_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.session_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.tx_power_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.perceived_pos_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.order_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+        if let Some(writer) = &mut self.radio_map_writer {
+            if writer.flush_policy.is_due(step) {
+                writer.write_to_file();
+            }
+        }
+    }
+
+    /// Writes all the buffered rows irrespective of the output intervals.
+    pub fn write_all_output(&mut self, step: TimeMS) {
+        debug!("Writing all output at step {}", step);
+        if let Some(writer) = &mut self.tx_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.rx_count_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.agent_pos_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.net_stat_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.perception_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.cache_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.queue_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.prediction_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.duty_cycle_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.age_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.fairness_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.agent_fairness_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.state_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.volume_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.trace_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.memory_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.fault_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.sla_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.streaming_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.metric_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.broadcast_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.emission_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.operator_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.duplicate_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.occupancy_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.session_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.tx_power_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.perceived_pos_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.order_writer {
+            writer.write_to_file();
+        }
+        if let Some(writer) = &mut self.radio_map_writer {
+            writer.write_to_file();
+        }
     }
 
     pub fn close_files(self, step: TimeMS) {
@@ -152,5 +1333,89 @@ impl ResultWriter {
         if let Some(writer) = self.net_stat_writer {
             writer.close_files()
         };
+        if let Some(writer) = self.perception_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.cache_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.queue_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.prediction_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.lifecycle_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.duty_cycle_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.age_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.fairness_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.agent_fairness_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.state_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.volume_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.trace_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.memory_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.fault_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.sla_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.streaming_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.metric_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.broadcast_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.emission_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.operator_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.duplicate_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.occupancy_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.session_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.tx_power_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.perceived_pos_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.lake_dump_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.order_writer {
+            writer.close_files()
+        };
+        if let Some(writer) = self.radio_map_writer {
+            writer.close_files()
+        };
     }
 }
@@ -1,5 +1,5 @@
 use crate::result::{OutputSettings, OutputType};
-use crate::writer::DataOutput;
+use crate::writer::{DataOutput, FlushPolicy};
 use arrow::array::{ArrayRef, Float32Array, RecordBatch, UInt32Array, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use disolv_core::agent::AgentId;
@@ -18,8 +18,12 @@ pub(crate) struct RxCountWriter {
     feasible_in_agent_count: Vec<u32>,
     feasible_in_data_size: Vec<u64>,
     feasible_in_data_count: Vec<u32>,
+    expired_agent_count: Vec<u32>,
+    expired_data_size: Vec<u64>,
+    expired_data_count: Vec<u32>,
     success_rate: Vec<f32>,
     to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
 }
 
 impl RxCountWriter {
@@ -32,7 +36,8 @@ impl RxCountWriter {
             .expect("RxDataWriter::new: No RxDataWriter config found");
         let output_file = output_path.join(&config.output_filename);
         Self {
-            to_output: DataOutput::new(&output_file, Self::schema()),
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
             time_step: Vec::new(),
             agent_id: Vec::new(),
             attempted_in_agent_count: Vec::new(),
@@ -41,6 +46,9 @@ impl RxCountWriter {
             feasible_in_agent_count: Vec::new(),
             feasible_in_data_size: Vec::new(),
             feasible_in_data_count: Vec::new(),
+            expired_agent_count: Vec::new(),
+            expired_data_size: Vec::new(),
+            expired_data_count: Vec::new(),
             success_rate: Vec::new(),
         }
     }
@@ -57,6 +65,9 @@ impl RxCountWriter {
             Field::new("feasible_in_agent_count", DataType::UInt32, false);
         let feasible_in_data_size = Field::new("feasible_in_data_size", DataType::UInt64, false);
         let feasible_in_data_count = Field::new("feasible_in_data_count", DataType::UInt32, false);
+        let expired_agent_count = Field::new("expired_agent_count", DataType::UInt32, false);
+        let expired_data_size = Field::new("expired_data_size", DataType::UInt64, false);
+        let expired_data_count = Field::new("expired_data_count", DataType::UInt32, false);
         let success_rate = Field::new("success_rate", DataType::Float32, false);
         Schema::new(vec![
             time_ms,
@@ -67,10 +78,17 @@ impl RxCountWriter {
             feasible_in_agent_count,
             feasible_in_data_size,
             feasible_in_data_count,
+            expired_agent_count,
+            expired_data_size,
+            expired_data_count,
             success_rate,
         ])
     }
 
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
     pub fn add_data(
         &mut self,
         time_step: TimeMS,
@@ -91,7 +109,16 @@ impl RxCountWriter {
             .push(in_data_stats.feasible.data_size.as_u64());
         self.feasible_in_data_count
             .push(in_data_stats.feasible.data_count);
+        self.expired_agent_count
+            .push(in_data_stats.expired.agent_count);
+        self.expired_data_size
+            .push(in_data_stats.expired.data_size.as_u64());
+        self.expired_data_count
+            .push(in_data_stats.expired.data_count);
         self.success_rate.push(in_data_stats.get_success_rate());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
     }
 
     pub fn write_to_file(&mut self) {
@@ -143,6 +170,24 @@ impl RxCountWriter {
                             &mut self.feasible_in_data_count,
                         ))) as ArrayRef,
                     ),
+                    (
+                        "expired_agent_count",
+                        Arc::new(UInt32Array::from(std::mem::take(
+                            &mut self.expired_agent_count,
+                        ))) as ArrayRef,
+                    ),
+                    (
+                        "expired_data_size",
+                        Arc::new(UInt64Array::from(std::mem::take(
+                            &mut self.expired_data_size,
+                        ))) as ArrayRef,
+                    ),
+                    (
+                        "expired_data_count",
+                        Arc::new(UInt32Array::from(std::mem::take(
+                            &mut self.expired_data_count,
+                        ))) as ArrayRef,
+                    ),
                     (
                         "success_rate",
                         Arc::new(Float32Array::from(std::mem::take(&mut self.success_rate)))
@@ -158,7 +203,10 @@ impl RxCountWriter {
         }
     }
 
-    pub(crate) fn close_files(self) {
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
         match self.to_output {
             DataOutput::Parquet(to_output) => to_output.close(),
         }
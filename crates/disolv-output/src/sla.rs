@@ -0,0 +1,147 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::sla::SlaRecord;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the compliance with the service level agreements in every output interval in which
+/// they covered transfers, including the violations.
+#[derive(Debug)]
+pub(crate) struct SlaWriter {
+    time_step: Vec<u64>,
+    sla_id: Vec<u32>,
+    attempted: Vec<u64>,
+    delivered: Vec<u64>,
+    on_time: Vec<u64>,
+    worst_latency: Vec<u64>,
+    delivery_ratio: Vec<f64>,
+    violated: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl SlaWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::SlaCompliance)
+            .expect("SlaWriter::new: No SlaWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            sla_id: Vec::new(),
+            attempted: Vec::new(),
+            delivered: Vec::new(),
+            on_time: Vec::new(),
+            worst_latency: Vec::new(),
+            delivery_ratio: Vec::new(),
+            violated: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let sla_id = Field::new("sla_id", DataType::UInt32, false);
+        let attempted = Field::new("attempted", DataType::UInt64, false);
+        let delivered = Field::new("delivered", DataType::UInt64, false);
+        let on_time = Field::new("on_time", DataType::UInt64, false);
+        let worst_latency = Field::new("worst_latency", DataType::UInt64, false);
+        let delivery_ratio = Field::new("delivery_ratio", DataType::Float64, false);
+        let violated = Field::new("violated", DataType::UInt32, false);
+        Schema::new(vec![
+            time_ms,
+            sla_id,
+            attempted,
+            delivered,
+            on_time,
+            worst_latency,
+            delivery_ratio,
+            violated,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, record: &SlaRecord) {
+        self.time_step.push(time_step.as_u64());
+        self.sla_id.push(record.sla_id);
+        self.attempted.push(record.attempted);
+        self.delivered.push(record.delivered);
+        self.on_time.push(record.on_time);
+        self.worst_latency.push(record.worst_latency);
+        self.delivery_ratio.push(record.delivery_ratio);
+        self.violated.push(record.violated as u32);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "sla_id",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.sla_id))) as ArrayRef,
+                    ),
+                    (
+                        "attempted",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.attempted)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "delivered",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.delivered)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "on_time",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.on_time))) as ArrayRef,
+                    ),
+                    (
+                        "worst_latency",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.worst_latency)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "delivery_ratio",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.delivery_ratio)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "violated",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.violated))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::state::{MachineState, StateChange};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the state changes of the state machines of the agents. The machine column tells the
+/// kinds of state machines apart.
+#[derive(Debug)]
+pub(crate) struct StateWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    machine: Vec<u32>,
+    from_state: Vec<u32>,
+    to_state: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl StateWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StateChanges)
+            .expect("StateWriter::new: No StateWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            machine: Vec::new(),
+            from_state: Vec::new(),
+            to_state: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let machine = Field::new("machine", DataType::UInt32, false);
+        let from_state = Field::new("from_state", DataType::UInt32, false);
+        let to_state = Field::new("to_state", DataType::UInt32, false);
+        Schema::new(vec![time_ms, agent_id, machine, from_state, to_state])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data<S: MachineState>(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        change: &StateChange<S>,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.machine.push(S::MACHINE);
+        self.from_state.push(change.from.as_int());
+        self.to_state.push(change.to.as_int());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "machine",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.machine))) as ArrayRef,
+                    ),
+                    (
+                        "from_state",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.from_state)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "to_state",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.to_state))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the streaming interval chosen in every streaming step along with the average
+/// displacement of the agents it was chosen for. The displacement is null when no agent was
+/// seen in both streaming steps.
+#[derive(Debug)]
+pub(crate) struct StreamingWriter {
+    time_step: Vec<u64>,
+    interval: Vec<u64>,
+    displacement: Vec<Option<f64>>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl StreamingWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::StreamingIntervals)
+            .expect("StreamingWriter::new: No StreamingWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            interval: Vec::new(),
+            displacement: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let interval = Field::new("interval", DataType::UInt64, false);
+        let displacement = Field::new("displacement", DataType::Float64, true);
+        Schema::new(vec![time_ms, interval, displacement])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, interval: TimeMS, displacement: Option<f64>) {
+        self.time_step.push(time_step.as_u64());
+        self.interval.push(interval.as_u64());
+        self.displacement.push(displacement);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "interval",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.interval))) as ArrayRef,
+                    ),
+                    (
+                        "displacement",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.displacement)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{open_sink, FlushPolicy};
+use disolv_core::bucket::TimeMS;
+use disolv_models::net::message::{DPayload, TxMetrics};
+use disolv_models::net::radio::DLink;
+use prost::Message;
+use std::fmt::{Debug, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Bindings of the trace schema in `proto/trace.proto`, for the tools reading the traces.
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/disolv.trace.rs"));
+}
+
+/// Estimated size of an encoded transfer event without data blobs.
+const EVENT_BYTES: usize = 64;
+
+/// Writes the payload transfers as length-delimited protobuf messages of the trace schema, so
+/// that other tools and simulators can read them without the output tables.
+pub(crate) struct TraceWriter {
+    events: Vec<proto::TransferEvent>,
+    sink: Box<dyn Write + Send>,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl Debug for TraceWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceWriter")
+            .field("events", &self.events.len())
+            .field("flush_policy", &self.flush_policy)
+            .finish()
+    }
+}
+
+impl TraceWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::PayloadTrace)
+            .expect("TraceWriter::new: No PayloadTrace config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            events: Vec::new(),
+            sink: open_sink(output_settings, &output_file),
+            flush_policy: FlushPolicy::with_row_bytes(config, EVENT_BYTES),
+        }
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        link: &DLink,
+        payload: &DPayload,
+        tx_metrics: TxMetrics,
+    ) {
+        let data_blobs = payload
+            .metadata
+            .data_blobs
+            .iter()
+            .map(|blob| proto::DataBlob {
+                data_type: blob.data_type.as_int() as i32,
+                data_size: blob.data_size.as_u64(),
+                raw_size: blob.uncompressed_size().as_u64(),
+                created_at: blob.created_at.as_u64(),
+            })
+            .collect();
+        self.events.push(proto::TransferEvent {
+            time_step: time_step.as_u64(),
+            payload_id: payload.metadata.id.to_string(),
+            source_id: payload.agent_state.device_info.id.as_u64(),
+            target_id: link.target.as_u64(),
+            distance: link.properties.distance.unwrap_or(-1.0),
+            tx_order: tx_metrics.tx_order,
+            tx_status: tx_metrics.tx_status.as_int() as i32,
+            tx_fail_reason: tx_metrics.tx_fail_reason.as_int() as i32,
+            payload_size: tx_metrics.payload_size.as_u64(),
+            latency: tx_metrics.latency.as_u64(),
+            data_blobs,
+        });
+        if self.flush_policy.is_full(self.events.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        let mut content = Vec::new();
+        for event in self.events.drain(..) {
+            event
+                .encode_length_delimited(&mut content)
+                .expect("Failed to encode the transfer event");
+        }
+        self.sink
+            .write_all(&content)
+            .expect("Failed to write the trace to file");
+    }
+
+    pub(crate) fn close_files(mut self) {
+        self.write_to_file();
+        self.sink.flush().expect("Failed to close the trace file");
+    }
+}
@@ -1,5 +1,5 @@
 use crate::result::{OutputSettings, OutputType};
-use crate::writer::DataOutput;
+use crate::writer::{DataOutput, FlushPolicy};
 use arrow::array::{ArrayRef, Float32Array, RecordBatch, UInt32Array, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use disolv_core::bucket::TimeMS;
@@ -22,6 +22,7 @@ pub(crate) struct TxDataWriter {
     tx_fail_reason: Vec<u32>,
     latency: Vec<u64>,
     to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
 }
 
 impl TxDataWriter {
@@ -34,7 +35,8 @@ impl TxDataWriter {
             .expect("TxDataWriter::new: No TxDataWriter config found");
         let output_file = output_path.join(&config.output_filename);
         Self {
-            to_output: DataOutput::new(&output_file, Self::schema()),
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
             time_step: Vec::new(),
             agent_id: Vec::new(),
             selected_agent: Vec::new(),
@@ -76,6 +78,10 @@ impl TxDataWriter {
         ])
     }
 
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
     pub fn add_data(
         &mut self,
         time_step: TimeMS,
@@ -95,6 +101,9 @@ impl TxDataWriter {
         self.payload_size.push(tx_metrics.payload_size.as_u64());
         self.tx_fail_reason.push(tx_metrics.tx_fail_reason.as_int());
         self.latency.push(tx_metrics.latency.as_u64());
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
     }
 
     pub fn write_to_file(&mut self) {
@@ -163,7 +172,10 @@ impl TxDataWriter {
         }
     }
 
-    pub(crate) fn close_files(self) {
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
         match self.to_output {
             DataOutput::Parquet(to_output) => to_output.close(),
         }
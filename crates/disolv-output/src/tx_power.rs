@@ -0,0 +1,140 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::tx_power::TxPowerChoice;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the transmission power picked by the agents for every transmission, with the range
+/// at that power and whether the target was in it. The distance is empty when the link does
+/// not know it.
+#[derive(Debug)]
+pub(crate) struct TxPowerWriter {
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    target_id: Vec<u64>,
+    distance: Vec<Option<f64>>,
+    tx_power: Vec<f64>,
+    range: Vec<f64>,
+    reached: Vec<u32>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl TxPowerWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::TxPower)
+            .expect("TxPowerWriter::new: No TxPowerWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            target_id: Vec::new(),
+            distance: Vec::new(),
+            tx_power: Vec::new(),
+            range: Vec::new(),
+            reached: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let agent_id = Field::new("agent_id", DataType::UInt64, false);
+        let target_id = Field::new("target_id", DataType::UInt64, false);
+        let distance = Field::new("distance", DataType::Float64, true);
+        let tx_power = Field::new("tx_power", DataType::Float64, false);
+        let range = Field::new("range", DataType::Float64, false);
+        let reached = Field::new("reached", DataType::UInt32, false);
+        Schema::new(vec![
+            time_ms, agent_id, target_id, distance, tx_power, range, reached,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        target_id: AgentId,
+        distance: Option<f32>,
+        choice: &TxPowerChoice,
+    ) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.target_id.push(target_id.as_u64());
+        self.distance.push(distance.map(|distance| distance as f64));
+        self.tx_power.push(choice.power as f64);
+        self.range.push(choice.range as f64);
+        self.reached.push(choice.reached as u32);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "agent_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                    ),
+                    (
+                        "target_id",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.target_id)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "distance",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.distance)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "tx_power",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.tx_power)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "range",
+                        Arc::new(Float64Array::from(std::mem::take(&mut self.range))) as ArrayRef,
+                    ),
+                    (
+                        "reached",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.reached))) as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
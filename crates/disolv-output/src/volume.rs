@@ -0,0 +1,129 @@
+use crate::result::{OutputSettings, OutputType};
+use crate::writer::{DataOutput, FlushPolicy};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::volume::DataVolume;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Writes the volume of the data of each type sent in an output interval, before and after the
+/// data was compressed.
+#[derive(Debug)]
+pub(crate) struct VolumeWriter {
+    time_step: Vec<u64>,
+    data_type: Vec<u32>,
+    blobs: Vec<u64>,
+    raw_bytes: Vec<u64>,
+    sent_bytes: Vec<u64>,
+    delivered_bytes: Vec<u64>,
+    to_output: DataOutput,
+    pub(crate) flush_policy: FlushPolicy,
+}
+
+impl VolumeWriter {
+    pub fn new(output_settings: &OutputSettings) -> Self {
+        let output_path = PathBuf::from(&output_settings.output_path);
+        let config = output_settings
+            .file_out_config
+            .iter()
+            .find(|&file_out_config| file_out_config.output_type == OutputType::DataVolume)
+            .expect("VolumeWriter::new: No VolumeWriter config found");
+        let output_file = output_path.join(&config.output_filename);
+        Self {
+            to_output: DataOutput::new(output_settings, &output_file, Self::schema()),
+            flush_policy: FlushPolicy::new(config, &Self::schema()),
+            time_step: Vec::new(),
+            data_type: Vec::new(),
+            blobs: Vec::new(),
+            raw_bytes: Vec::new(),
+            sent_bytes: Vec::new(),
+            delivered_bytes: Vec::new(),
+        }
+    }
+
+    fn schema() -> Schema {
+        let time_ms = Field::new("time_step", DataType::UInt64, false);
+        let data_type = Field::new("data_type", DataType::UInt32, false);
+        let blobs = Field::new("blobs", DataType::UInt64, false);
+        let raw_bytes = Field::new("raw_bytes", DataType::UInt64, false);
+        let sent_bytes = Field::new("sent_bytes", DataType::UInt64, false);
+        let delivered_bytes = Field::new("delivered_bytes", DataType::UInt64, false);
+        Schema::new(vec![
+            time_ms,
+            data_type,
+            blobs,
+            raw_bytes,
+            sent_bytes,
+            delivered_bytes,
+        ])
+    }
+
+    pub(crate) fn buffered_rows(&self) -> usize {
+        self.time_step.len()
+    }
+
+    pub fn add_data(&mut self, time_step: TimeMS, volume: &DataVolume) {
+        self.time_step.push(time_step.as_u64());
+        self.data_type.push(volume.data_type.as_int());
+        self.blobs.push(volume.blobs);
+        self.raw_bytes.push(volume.raw_bytes);
+        self.sent_bytes.push(volume.sent_bytes);
+        self.delivered_bytes.push(volume.delivered_bytes);
+        if self.flush_policy.is_full(self.time_step.len()) {
+            self.write_to_file();
+        }
+    }
+
+    pub fn write_to_file(&mut self) {
+        match &mut self.to_output {
+            DataOutput::Parquet(to_output) => {
+                let record_batch = RecordBatch::try_from_iter(vec![
+                    (
+                        "time_step",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "data_type",
+                        Arc::new(UInt32Array::from(std::mem::take(&mut self.data_type)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "blobs",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.blobs))) as ArrayRef,
+                    ),
+                    (
+                        "raw_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.raw_bytes)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "sent_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.sent_bytes)))
+                            as ArrayRef,
+                    ),
+                    (
+                        "delivered_bytes",
+                        Arc::new(UInt64Array::from(std::mem::take(&mut self.delivered_bytes)))
+                            as ArrayRef,
+                    ),
+                ])
+                .expect("Failed to convert results to record batch");
+                to_output
+                    .writer
+                    .write(&record_batch)
+                    .expect("Failed to write record batches to file");
+            }
+        }
+    }
+
+    pub(crate) fn close_files(mut self) {
+        if self.flush_policy.has_interval() {
+            self.write_to_file();
+        }
+        match self.to_output {
+            DataOutput::Parquet(to_output) => to_output.close(),
+        }
+    }
+}
@@ -1,9 +1,51 @@
+use crate::result::{FileOutConfig, OutputSettings};
+use arrow::array::RecordBatch;
 use arrow::datatypes::{Schema, SchemaRef};
+use bytes::Bytes;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
 use parquet::basic::Compression;
+use parquet::file::metadata::KeyValue;
 use parquet::file::properties::WriterProperties;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Parameters of the run that wrote an output, added as key-value metadata to every parquet
+/// file so that the outputs of a sweep can be told apart after they are merged. The end of the
+/// warm-up is added when the rows of the warm-up are left out of the outputs.
+#[derive(Clone, Debug, Default)]
+pub struct RunMetadata {
+    pub scenario_id: String,
+    pub seed: u64,
+    pub config_hash: String,
+    pub warm_up: Option<TimeMS>,
+}
+
+impl RunMetadata {
+    pub const SCENARIO_ID: &'static str = "disolv.scenario_id";
+    pub const SEED: &'static str = "disolv.seed";
+    pub const CONFIG_HASH: &'static str = "disolv.config_hash";
+    pub const WARM_UP: &'static str = "disolv.warm_up_ms";
+
+    fn key_values(&self) -> Vec<KeyValue> {
+        let mut key_values = vec![
+            KeyValue::new(Self::SCENARIO_ID.to_string(), self.scenario_id.clone()),
+            KeyValue::new(Self::SEED.to_string(), self.seed.to_string()),
+            KeyValue::new(Self::CONFIG_HASH.to_string(), self.config_hash.clone()),
+        ];
+        if let Some(warm_up) = self.warm_up {
+            key_values.push(KeyValue::new(
+                Self::WARM_UP.to_string(),
+                warm_up.to_string(),
+            ));
+        }
+        key_values
+    }
+}
 
 #[derive(Debug)]
 pub(crate) enum DataOutput {
@@ -11,7 +53,18 @@ pub(crate) enum DataOutput {
 }
 
 impl DataOutput {
-    pub fn new(file_name: &PathBuf, schema: Schema) -> Self {
+    pub fn new(output_settings: &OutputSettings, file_name: &PathBuf, schema: Schema) -> Self {
+        if let Some(ref memory) = output_settings.memory {
+            let table_name = file_name
+                .file_name()
+                .and_then(|name| name.to_str())
+                .expect("Invalid output file name");
+            return DataOutput::Parquet(WriterParquet::with_sink(
+                Box::new(memory.buffer(table_name)),
+                schema,
+                output_settings.run_metadata.as_ref(),
+            ));
+        }
         if file_name.exists() {
             match std::fs::remove_file(file_name) {
                 Ok(_) => {}
@@ -20,7 +73,11 @@ impl DataOutput {
         }
         match file_name.extension() {
             Some(ext) => match ext.to_str() {
-                Some("parquet") => DataOutput::Parquet(WriterParquet::new(file_name, schema)),
+                Some("parquet") => DataOutput::Parquet(WriterParquet::new(
+                    file_name,
+                    schema,
+                    output_settings.run_metadata.as_ref(),
+                )),
                 _ => panic!("Invalid file extension"),
             },
             None => panic!("Invalid file extension"),
@@ -28,21 +85,49 @@ impl DataOutput {
     }
 }
 
+/// Opens the file of an output that is written as raw bytes, or its buffer when the tables are
+/// kept in memory.
+pub(crate) fn open_sink(
+    output_settings: &OutputSettings,
+    file_name: &Path,
+) -> Box<dyn Write + Send> {
+    if let Some(ref memory) = output_settings.memory {
+        let table_name = file_name
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect("Invalid output file name");
+        return Box::new(memory.buffer(table_name));
+    }
+    match File::create(file_name) {
+        Ok(file) => Box::new(BufWriter::new(file)),
+        Err(e) => panic!("Failed to create output file {:?}: {}", file_name, e),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct WriterParquet {
-    pub(crate) writer: ArrowWriter<File>,
+    pub(crate) writer: ArrowWriter<Box<dyn Write + Send>>,
 }
 
 impl WriterParquet {
-    fn new(file_name: &PathBuf, schema: Schema) -> Self {
-        let props = WriterProperties::builder()
-            .set_compression(Compression::SNAPPY)
-            .build();
+    fn new(file_name: &PathBuf, schema: Schema, run_metadata: Option<&RunMetadata>) -> Self {
         let output_file = match File::create(file_name) {
             Ok(file) => file,
             Err(_) => panic!("Failed to create links file to write"),
         };
-        let writer = match ArrowWriter::try_new(output_file, SchemaRef::from(schema), Some(props)) {
+        Self::with_sink(Box::new(output_file), schema, run_metadata)
+    }
+
+    fn with_sink(
+        sink: Box<dyn Write + Send>,
+        schema: Schema,
+        run_metadata: Option<&RunMetadata>,
+    ) -> Self {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_key_value_metadata(run_metadata.map(|metadata| metadata.key_values()))
+            .build();
+        let writer = match ArrowWriter::try_new(sink, SchemaRef::from(schema), Some(props)) {
             Ok(writer) => writer,
             Err(_) => panic!("Failed to create links file writer"),
         };
@@ -53,3 +138,212 @@ impl WriterParquet {
         self.writer.close().expect("Failed to close output file");
     }
 }
+
+/// Output tables kept in memory instead of being written to the output path, e.g. to run
+/// scenarios in tests. Tables are stored as parquet files under their output file names and
+/// are complete once the result writer is closed.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTables {
+    tables: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemoryTables {
+    pub fn table_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Reads the record batches of the table with the given output file name.
+    pub fn read(&self, table_name: &str) -> Option<Vec<RecordBatch>> {
+        let content = self
+            .tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .get(table_name)?
+            .clone();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(content))
+            .unwrap_or_else(|e| panic!("Table {} is not complete: {}", table_name, e))
+            .build()
+            .expect("Failed to read the table");
+        Some(
+            reader
+                .map(|batch| batch.expect("Failed to read record batch"))
+                .collect(),
+        )
+    }
+
+    /// Key-value metadata of the table with the given output file name.
+    pub fn metadata(&self, table_name: &str) -> Option<Vec<(String, String)>> {
+        let content = self
+            .tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .get(table_name)?
+            .clone();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(content))
+            .unwrap_or_else(|e| panic!("Table {} is not complete: {}", table_name, e));
+        let key_values = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .map(|key_values| {
+                key_values
+                    .iter()
+                    .map(|kv| (kv.key.clone(), kv.value.clone().unwrap_or_default()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(key_values)
+    }
+
+    /// Reads the content of an output that is not a parquet table, e.g. a trace.
+    pub fn bytes(&self, table_name: &str) -> Option<Vec<u8>> {
+        self.tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .get(table_name)
+            .cloned()
+    }
+
+    fn buffer(&self, table_name: &str) -> TableBuffer {
+        self.tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .insert(table_name.to_string(), Vec::new());
+        TableBuffer {
+            table_name: table_name.to_string(),
+            tables: Arc::clone(&self.tables),
+        }
+    }
+}
+
+struct TableBuffer {
+    table_name: String,
+    tables: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl Write for TableBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tables
+            .lock()
+            .expect("Memory tables are poisoned")
+            .entry(self.table_name.clone())
+            .or_default()
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decides when the rows buffered by a writer are written to the file. Rows are written when
+/// the buffer exceeds the row count or the estimated byte size thresholds, and when the output
+/// interval of the table is due. Tables without an interval of their own are written at the
+/// output interval of the simulation.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FlushPolicy {
+    interval: Option<TimeMS>,
+    next_output: TimeMS,
+    max_rows: Option<usize>,
+    max_bytes: Option<usize>,
+    row_bytes: usize,
+}
+
+impl FlushPolicy {
+    pub(crate) fn new(config: &FileOutConfig, schema: &Schema) -> Self {
+        let row_bytes = schema
+            .fields()
+            .iter()
+            .map(|field| field.data_type().primitive_width().unwrap_or(8))
+            .sum();
+        Self::with_row_bytes(config, row_bytes)
+    }
+
+    /// Flush policy of an output that is not a table, with the estimated size of a row.
+    pub(crate) fn with_row_bytes(config: &FileOutConfig, row_bytes: usize) -> Self {
+        Self {
+            interval: config.output_interval,
+            next_output: config.output_interval.unwrap_or_default(),
+            max_rows: config.max_rows,
+            max_bytes: config.max_bytes,
+            row_bytes,
+        }
+    }
+
+    pub(crate) fn has_interval(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// Estimated size of the rows in memory.
+    pub(crate) fn bytes_of(&self, rows: usize) -> usize {
+        rows * self.row_bytes
+    }
+
+    pub(crate) fn is_full(&self, rows: usize) -> bool {
+        if let Some(max_rows) = self.max_rows {
+            if rows >= max_rows {
+                return true;
+            }
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            if rows * self.row_bytes >= max_bytes {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn is_due(&mut self, step: TimeMS) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return false,
+        };
+        if step < self.next_output {
+            return false;
+        }
+        self.next_output = step + interval;
+        true
+    }
+}
+
+/// Decides at which steps the rows of a table are recorded. Tables with an `interval` record
+/// their rows in the steps at multiples of the interval and skip them in the other steps.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Cadence {
+    interval: TimeMS,
+    next_sample: TimeMS,
+    due: bool,
+}
+
+impl Cadence {
+    pub(crate) fn new(interval: TimeMS) -> Self {
+        if interval == TimeMS::default() {
+            panic!("Interval of a table must be larger than zero");
+        }
+        Self {
+            interval,
+            next_sample: TimeMS::default(),
+            due: true,
+        }
+    }
+
+    pub(crate) fn start_step(&mut self, step: TimeMS) {
+        self.due = step >= self.next_sample;
+        while self.next_sample <= step {
+            self.next_sample += self.interval;
+        }
+    }
+
+    pub(crate) fn is_due(&self) -> bool {
+        self.due
+    }
+}
@@ -0,0 +1,34 @@
+use crate::placement::PlacementMethod;
+use disolv_core::bucket::TimeMS;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Configuration of the placement of infrastructure, e.g. RSUs or edge servers.
+///
+/// The `count` sites are chosen among the candidates in the `candidates_file` to serve the
+/// agents in the `trace_file`. Both files use the position schema of the simulator. Positions in
+/// the trace are taken every `sample_interval` and gathered in cells of `cell_size` meters
+/// before the placement. A site covers the positions within `range` meters of it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Config {
+    pub candidates_file: String,
+    pub trace_file: String,
+    pub output_file: String,
+    pub count: usize,
+    pub range: f64,
+    pub method: PlacementMethod,
+    pub sample_interval: Option<TimeMS>,
+    pub cell_size: Option<f64>,
+    pub iterations: Option<u32>,
+}
+
+pub fn read_config(file_path: &Path) -> Config {
+    let input_toml = match std::fs::read_to_string(file_path) {
+        Ok(parsed_string) => parsed_string,
+        Err(e) => panic!("Failed to read {}: {}", file_path.display(), e),
+    };
+    match toml::from_str(&input_toml) {
+        Ok(config) => config,
+        Err(e) => panic!("Invalid placement configuration: {}", e),
+    }
+}
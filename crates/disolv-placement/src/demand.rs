@@ -0,0 +1,96 @@
+use arrow::array::RecordBatch;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_input::batch::{read_f64_column, read_u64_column};
+use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, TIME_STEP};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+/// A site where the infrastructure can be placed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub agent_id: AgentId,
+    pub pos: [f64; 2],
+}
+
+/// Positions of the agents gathered in a cell. The weight is the number of positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Demand {
+    pub pos: [f64; 2],
+    pub weight: f64,
+}
+
+/// Reads the candidate sites. The time step of the positions is ignored when it is present.
+pub fn read_candidates(file_path: &Path) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for record_batch in read_batches(file_path) {
+        let agent_ids = read_u64_column(AGENT_ID, &record_batch);
+        let x_positions = read_f64_column(COORD_X, &record_batch);
+        let y_positions = read_f64_column(COORD_Y, &record_batch);
+        for row in 0..record_batch.num_rows() {
+            candidates.push(Candidate {
+                agent_id: AgentId::from(agent_ids[row]),
+                pos: [x_positions[row], y_positions[row]],
+            });
+        }
+    }
+    candidates
+}
+
+/// Reads the positions in the trace at every sample interval and gathers them in cells. The
+/// demand of a cell is placed at the mean of its positions.
+pub fn read_demand(
+    file_path: &Path,
+    sample_interval: Option<TimeMS>,
+    cell_size: f64,
+) -> Vec<Demand> {
+    let mut cells: HashMap<(i64, i64), ([f64; 2], f64)> = HashMap::new();
+    for record_batch in read_batches(file_path) {
+        let time_steps = read_u64_column(TIME_STEP, &record_batch);
+        let x_positions = read_f64_column(COORD_X, &record_batch);
+        let y_positions = read_f64_column(COORD_Y, &record_batch);
+        for row in 0..record_batch.num_rows() {
+            if let Some(interval) = sample_interval {
+                if !time_steps[row].is_multiple_of(interval.as_u64()) {
+                    continue;
+                }
+            }
+            let (x, y) = (x_positions[row], y_positions[row]);
+            let cell = (
+                (x / cell_size).floor() as i64,
+                (y / cell_size).floor() as i64,
+            );
+            let (sum, weight) = cells.entry(cell).or_insert(([0.0, 0.0], 0.0));
+            sum[0] += x;
+            sum[1] += y;
+            *weight += 1.0;
+        }
+    }
+    let mut demand: Vec<((i64, i64), Demand)> = cells
+        .into_iter()
+        .map(|(cell, (sum, weight))| {
+            let pos = [sum[0] / weight, sum[1] / weight];
+            (cell, Demand { pos, weight })
+        })
+        .collect();
+    demand.sort_by_key(|(cell, _)| *cell);
+    demand.into_iter().map(|(_, demand)| demand).collect()
+}
+
+fn read_batches(file_path: &Path) -> impl Iterator<Item = RecordBatch> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Error reading file {}: {}", file_path.display(), e),
+    };
+    let reader = match ParquetRecordBatchReaderBuilder::try_new(file) {
+        Ok(builder) => builder.build(),
+        Err(e) => panic!("Error building parquet reader: {}", e),
+    };
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(e) => panic!("Error building reader: {}", e),
+    };
+    reader.map(|batch| batch.unwrap_or_else(|e| panic!("Error reading record batch: {}", e)))
+}
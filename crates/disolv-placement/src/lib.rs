@@ -0,0 +1,4 @@
+pub mod config;
+pub mod demand;
+pub mod placement;
+pub mod writer;
@@ -0,0 +1,53 @@
+use clap::Parser;
+use disolv_placement::config::read_config;
+use disolv_placement::demand::{read_candidates, read_demand};
+use disolv_placement::placement::place;
+use disolv_placement::writer::write_sites;
+use std::path::PathBuf;
+
+const CELL_SIZE: f64 = 10.0;
+const ITERATIONS: u32 = 20;
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 'c', long, value_name = "Placement Configuration File")]
+    config: String,
+}
+
+fn main() {
+    let config_file = PathBuf::from(CliArgs::parse().config);
+    let start = std::time::Instant::now();
+    let config = read_config(&config_file);
+    let config_path = config_file.parent().map(PathBuf::from).unwrap_or_default();
+
+    let candidates = read_candidates(&config_path.join(&config.candidates_file));
+    let demand = read_demand(
+        &config_path.join(&config.trace_file),
+        config.sample_interval,
+        config.cell_size.unwrap_or(CELL_SIZE),
+    );
+    println!(
+        "Placing {} sites among {} candidates for {} demand cells.",
+        config.count,
+        candidates.len(),
+        demand.len()
+    );
+    let placement = place(
+        &candidates,
+        &demand,
+        config.count,
+        config.range,
+        config.method,
+        config.iterations.unwrap_or(ITERATIONS),
+    );
+    write_sites(&config_path.join(&config.output_file), &placement.sites);
+
+    let elapsed = start.elapsed();
+    println!(
+        "Placement finished in {} ms with {:.1}% coverage and a mean distance of {:.1} m.",
+        elapsed.as_millis(),
+        placement.coverage * 100.0,
+        placement.mean_distance
+    );
+}
@@ -0,0 +1,272 @@
+use crate::demand::{Candidate, Demand};
+use serde::Deserialize;
+
+/// Heuristic used to choose the sites.
+///
+/// * `Greedy` adds the site that covers the most demand not yet covered, until the requested
+///   number of sites is chosen.
+/// * `KMedian` starts from the greedy sites and swaps sites while that shortens the mean
+///   distance of the demand to its nearest site.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementMethod {
+    Greedy,
+    KMedian,
+}
+
+/// Chosen sites, in the order they were chosen, with the share of the demand within range of
+/// a site and the mean distance of the demand to its nearest site.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    pub sites: Vec<Candidate>,
+    pub coverage: f64,
+    pub mean_distance: f64,
+}
+
+/// Distances between the candidates and the demand.
+struct Distances {
+    distances: Vec<Vec<f64>>,
+    weights: Vec<f64>,
+}
+
+impl Distances {
+    fn new(candidates: &[Candidate], demand: &[Demand]) -> Self {
+        let distances = candidates
+            .iter()
+            .map(|candidate| {
+                demand
+                    .iter()
+                    .map(|demand| {
+                        let dx = candidate.pos[0] - demand.pos[0];
+                        let dy = candidate.pos[1] - demand.pos[1];
+                        (dx * dx + dy * dy).sqrt()
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            distances,
+            weights: demand.iter().map(|demand| demand.weight).collect(),
+        }
+    }
+
+    /// Distance of every demand to the nearest of the sites.
+    fn nearest(&self, sites: &[usize]) -> Vec<f64> {
+        (0..self.weights.len())
+            .map(|demand| {
+                sites
+                    .iter()
+                    .map(|site| self.distances[*site][demand])
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect()
+    }
+
+    fn cost(&self, sites: &[usize]) -> f64 {
+        self.nearest(sites)
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(distance, weight)| distance * weight)
+            .sum()
+    }
+}
+
+pub fn place(
+    candidates: &[Candidate],
+    demand: &[Demand],
+    count: usize,
+    range: f64,
+    method: PlacementMethod,
+    iterations: u32,
+) -> Placement {
+    if count > candidates.len() {
+        panic!(
+            "{} sites are requested but only {} candidates are given.",
+            count,
+            candidates.len()
+        );
+    }
+    let distances = Distances::new(candidates, demand);
+    let mut sites = greedy(&distances, count, range);
+    if method == PlacementMethod::KMedian {
+        swap_sites(&distances, &mut sites, candidates.len(), iterations);
+    }
+
+    let nearest = distances.nearest(&sites);
+    let total: f64 = distances.weights.iter().sum();
+    let covered: f64 = nearest
+        .iter()
+        .zip(distances.weights.iter())
+        .filter(|(distance, _)| **distance <= range)
+        .map(|(_, weight)| weight)
+        .sum();
+    let (coverage, mean_distance) = match total > 0.0 {
+        true => (covered / total, distances.cost(&sites) / total),
+        false => (0.0, 0.0),
+    };
+    Placement {
+        sites: sites.iter().map(|site| candidates[*site]).collect(),
+        coverage,
+        mean_distance,
+    }
+}
+
+/// Adds the site covering the most uncovered demand until there are enough sites. When no site
+/// covers more demand, the site that shortens the distances the most is added instead.
+fn greedy(distances: &Distances, count: usize, range: f64) -> Vec<usize> {
+    let candidate_count = distances.distances.len();
+    let mut sites: Vec<usize> = Vec::with_capacity(count);
+    let mut covered = vec![false; distances.weights.len()];
+    while sites.len() < count {
+        let gain_of = |candidate: usize| -> f64 {
+            distances.distances[candidate]
+                .iter()
+                .zip(distances.weights.iter())
+                .zip(covered.iter())
+                .filter(|((distance, _), covered)| **distance <= range && !**covered)
+                .map(|((_, weight), _)| weight)
+                .sum()
+        };
+        let open = (0..candidate_count).filter(|candidate| !sites.contains(candidate));
+        let (best, gain) = open.map(|candidate| (candidate, gain_of(candidate))).fold(
+            (usize::MAX, 0.0),
+            |best, next| match next.1 > best.1 {
+                true => next,
+                false => best,
+            },
+        );
+        let best = match gain > 0.0 {
+            true => best,
+            false => {
+                (0..candidate_count)
+                    .filter(|candidate| !sites.contains(candidate))
+                    .map(|candidate| {
+                        let mut with = sites.clone();
+                        with.push(candidate);
+                        (candidate, distances.cost(&with))
+                    })
+                    .fold((usize::MAX, f64::INFINITY), |best, next| {
+                        match next.1 < best.1 {
+                            true => next,
+                            false => best,
+                        }
+                    })
+                    .0
+            }
+        };
+        distances.distances[best]
+            .iter()
+            .zip(covered.iter_mut())
+            .filter(|(distance, _)| **distance <= range)
+            .for_each(|(_, covered)| *covered = true);
+        sites.push(best);
+    }
+    sites
+}
+
+/// Swaps a site with a candidate whenever that lowers the total distance of the demand to its
+/// nearest site, for at most the given number of passes over the sites.
+fn swap_sites(distances: &Distances, sites: &mut [usize], candidate_count: usize, passes: u32) {
+    let mut cost = distances.cost(sites);
+    for _ in 0..passes {
+        let mut improved = false;
+        for idx in 0..sites.len() {
+            for candidate in 0..candidate_count {
+                if sites.contains(&candidate) {
+                    continue;
+                }
+                let previous = sites[idx];
+                sites[idx] = candidate;
+                let new_cost = distances.cost(sites);
+                if new_cost < cost {
+                    cost = new_cost;
+                    improved = true;
+                } else {
+                    sites[idx] = previous;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use disolv_core::agent::AgentId;
+
+    fn candidates(positions: &[[f64; 2]]) -> Vec<Candidate> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(idx, pos)| Candidate {
+                agent_id: AgentId::from(idx as u64 + 100),
+                pos: *pos,
+            })
+            .collect()
+    }
+
+    /// Two clusters of demand, the heavier one at x = 0 and the other at x = 1000.
+    fn two_clusters() -> Vec<Demand> {
+        let mut demand = Vec::new();
+        for offset in 0..5 {
+            demand.push(Demand {
+                pos: [offset as f64 * 10.0, 0.0],
+                weight: 3.0,
+            });
+            demand.push(Demand {
+                pos: [1000.0 + offset as f64 * 10.0, 0.0],
+                weight: 1.0,
+            });
+        }
+        demand
+    }
+
+    #[test]
+    fn test_greedy_covers_the_clusters() {
+        let candidates = candidates(&[[500.0, 0.0], [20.0, 0.0], [1020.0, 0.0], [0.0, 0.0]]);
+        let placement = place(
+            &candidates,
+            &two_clusters(),
+            2,
+            50.0,
+            PlacementMethod::Greedy,
+            0,
+        );
+        let chosen: Vec<u64> = placement
+            .sites
+            .iter()
+            .map(|site| site.agent_id.as_u64())
+            .collect();
+        assert_eq!(chosen, vec![101, 102]);
+        assert_eq!(placement.coverage, 1.0);
+        assert!((placement.mean_distance - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_k_median_moves_sites_closer() {
+        // With a range covering the first candidate, greedy picks it although it is far from
+        // the demand, and the swaps move the sites to the clusters.
+        let candidates = candidates(&[[500.0, 0.0], [20.0, 0.0], [1020.0, 0.0]]);
+        let greedy = place(
+            &candidates,
+            &two_clusters(),
+            1,
+            600.0,
+            PlacementMethod::Greedy,
+            0,
+        );
+        assert_eq!(greedy.sites[0].agent_id.as_u64(), 100);
+        let k_median = place(
+            &candidates,
+            &two_clusters(),
+            1,
+            600.0,
+            PlacementMethod::KMedian,
+            10,
+        );
+        assert_eq!(k_median.sites[0].agent_id.as_u64(), 101);
+        assert!(k_median.mean_distance < greedy.mean_distance);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::demand::Candidate;
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, TIME_STEP};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Writes the sites as the positions of stationary agents, which the simulator and the link
+/// producer read from the first time step.
+pub fn write_sites(output_file: &Path, sites: &[Candidate]) {
+    let schema = Schema::new(vec![
+        Field::new(TIME_STEP, DataType::UInt64, false),
+        Field::new(AGENT_ID, DataType::UInt64, false),
+        Field::new(COORD_X, DataType::Float64, false),
+        Field::new(COORD_Y, DataType::Float64, false),
+    ]);
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = match File::create(output_file) {
+        Ok(file) => file,
+        Err(e) => panic!("Failed to create file {}: {}", output_file.display(), e),
+    };
+    let mut writer = match ArrowWriter::try_new(file, SchemaRef::from(schema), Some(props)) {
+        Ok(writer) => writer,
+        Err(e) => panic!("Failed to create parquet writer: {}", e),
+    };
+    let record_batch = RecordBatch::try_from_iter(vec![
+        (
+            TIME_STEP,
+            Arc::new(UInt64Array::from(vec![0; sites.len()])) as ArrayRef,
+        ),
+        (
+            AGENT_ID,
+            Arc::new(UInt64Array::from_iter_values(
+                sites.iter().map(|site| site.agent_id.as_u64()),
+            )) as ArrayRef,
+        ),
+        (
+            COORD_X,
+            Arc::new(Float64Array::from_iter_values(
+                sites.iter().map(|site| site.pos[0]),
+            )) as ArrayRef,
+        ),
+        (
+            COORD_Y,
+            Arc::new(Float64Array::from_iter_values(
+                sites.iter().map(|site| site.pos[1]),
+            )) as ArrayRef,
+        ),
+    ])
+    .expect("Failed to convert sites to record batch");
+    writer
+        .write(&record_batch)
+        .expect("Failed to write sites to file");
+    writer.close().expect("Failed to close sites file");
+}
@@ -0,0 +1,75 @@
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::dist::DistParams;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Configuration of a power schedule generated from descriptions of groups of agents.
+///
+/// The schedule covers `duration` and all the on and off times are multiples of `step_size`,
+/// the step size of the simulation. Random patterns are drawn with the `seed`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Config {
+    pub output_file: String,
+    pub duration: TimeMS,
+    pub step_size: TimeMS,
+    pub seed: Option<u64>,
+    pub groups: Vec<AgentGroup>,
+}
+
+/// How the agents of a group are switched on and off.
+/// * `AlwaysOn`: on for the entire duration.
+/// * `DutyCycle`: on for `on_time` at the start of every `period`. With `stagger`, each agent
+///   starts at a random offset within the period.
+/// * `Random`: alternately off and on for durations in milliseconds drawn from `off_duration`
+///   and `on_duration`, starting off.
+/// * `Trace`: on while the agent appears in the `trace_file`. Absences longer than `max_gap`
+///   switch the agent off, shorter ones are bridged.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    AlwaysOn,
+    DutyCycle,
+    Random,
+    Trace,
+}
+
+/// A group of agents with the same power pattern. The agents are either listed in `agent_ids`
+/// or numbered from `first_id` on for `count` agents. Agents of a `Trace` group default to all
+/// the agents in the trace.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AgentGroup {
+    pub pattern: Pattern,
+    pub agent_ids: Option<Vec<AgentId>>,
+    pub first_id: Option<u64>,
+    pub count: Option<u64>,
+    pub period: Option<TimeMS>,
+    pub on_time: Option<TimeMS>,
+    pub stagger: Option<bool>,
+    pub on_duration: Option<DistParams>,
+    pub off_duration: Option<DistParams>,
+    pub trace_file: Option<String>,
+    pub max_gap: Option<TimeMS>,
+}
+
+impl AgentGroup {
+    /// Agents of the group when they are given in the configuration.
+    pub fn agents(&self) -> Option<Vec<AgentId>> {
+        if let Some(ref agent_ids) = self.agent_ids {
+            return Some(agent_ids.clone());
+        }
+        let first_id = self.first_id?;
+        let count = self.count.unwrap_or(1);
+        Some((first_id..first_id + count).map(AgentId::from).collect())
+    }
+}
+
+pub fn read_config(file_path: &Path) -> Config {
+    let input_toml = match std::fs::read_to_string(file_path) {
+        Ok(parsed_string) => parsed_string,
+        Err(e) => panic!("Failed to read {}: {}", file_path.display(), e),
+    };
+    match toml::from_str(&input_toml) {
+        Ok(config) => config,
+        Err(e) => panic!("Invalid power schedule configuration: {}", e),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod config;
+pub mod schedule;
+pub mod trace;
+pub mod writer;
@@ -0,0 +1,37 @@
+use clap::Parser;
+use disolv_power::config::read_config;
+use disolv_power::schedule::generate;
+use disolv_power::writer::write_periods;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 'c', long, value_name = "Power Schedule Configuration File")]
+    config: String,
+}
+
+fn main() {
+    let config_file = PathBuf::from(CliArgs::parse().config);
+    let start = std::time::Instant::now();
+    let config = read_config(&config_file);
+    let config_path = config_file.parent().map(PathBuf::from).unwrap_or_default();
+
+    println!(
+        "Generating the power schedule of {} groups for {} ms.",
+        config.groups.len(),
+        config.duration
+    );
+    let periods = generate(&config, &config_path);
+    write_periods(&config_path.join(&config.output_file), &periods);
+
+    let agents: HashSet<_> = periods.iter().map(|period| period.agent_id).collect();
+    let elapsed = start.elapsed();
+    println!(
+        "Power schedule with {} periods of {} agents written in {} ms.",
+        periods.len(),
+        agents.len(),
+        elapsed.as_millis()
+    );
+}
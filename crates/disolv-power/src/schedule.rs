@@ -0,0 +1,286 @@
+use crate::config::{AgentGroup, Config, Pattern};
+use crate::trace::read_appearances;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_models::dist::{DistParams, RngSampler};
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+use std::path::Path;
+
+/// A period in which an agent is on. The agent is switched off at `off`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    pub agent_id: AgentId,
+    pub on: TimeMS,
+    pub off: TimeMS,
+}
+
+/// Generates the periods of all the groups in the configuration, sorted by agent and time.
+/// Files of the configuration are relative to the `config_path`.
+pub fn generate(config: &Config, config_path: &Path) -> Vec<Period> {
+    if config.step_size == TimeMS::default() {
+        panic!("Step size of the power schedule must be larger than zero");
+    }
+    let mut rng = Pcg64Mcg::new(config.seed.unwrap_or_default() as u128);
+    let mut periods = Vec::new();
+    for group in config.groups.iter() {
+        periods.extend(generate_group(config, group, config_path, &mut rng));
+    }
+    periods.sort_by_key(|period| (period.agent_id, period.on));
+    periods
+}
+
+fn generate_group(
+    config: &Config,
+    group: &AgentGroup,
+    config_path: &Path,
+    rng: &mut Pcg64Mcg,
+) -> Vec<Period> {
+    if group.pattern == Pattern::Trace {
+        let trace_file = group
+            .trace_file
+            .as_ref()
+            .expect("Trace pattern needs a trace_file");
+        let mut appearances = read_appearances(&config_path.join(trace_file));
+        if let Some(agent_ids) = group.agents() {
+            appearances.retain(|agent_id, _| agent_ids.contains(agent_id));
+        }
+        let max_gap = group.max_gap.unwrap_or(config.step_size);
+        return trace_periods(&appearances, max_gap, config.step_size, config.duration);
+    }
+
+    let agent_ids = group
+        .agents()
+        .expect("Group needs either agent_ids or first_id and count");
+    match group.pattern {
+        Pattern::AlwaysOn => agent_ids
+            .into_iter()
+            .map(|agent_id| Period {
+                agent_id,
+                on: TimeMS::default(),
+                off: config.duration,
+            })
+            .collect(),
+        Pattern::DutyCycle => {
+            let period = group.period.expect("Duty cycle needs a period");
+            let on_time = group.on_time.expect("Duty cycle needs an on_time");
+            if on_time > period || round(period, config.step_size) == TimeMS::default() {
+                panic!("Duty cycle needs an on_time within a period of at least one step");
+            }
+            agent_ids
+                .into_iter()
+                .flat_map(|agent_id| {
+                    let offset = match group.stagger.unwrap_or(false) {
+                        true => TimeMS::from(rng.gen_range(0..period.as_u64())),
+                        false => TimeMS::default(),
+                    };
+                    duty_cycle(agent_id, period, on_time, offset, config)
+                })
+                .collect()
+        }
+        Pattern::Random => {
+            let mut on_sampler = sampler(group.on_duration.as_ref(), config, config_path);
+            let mut off_sampler = sampler(group.off_duration.as_ref(), config, config_path);
+            agent_ids
+                .into_iter()
+                .flat_map(|agent_id| {
+                    random_periods(agent_id, &mut on_sampler, &mut off_sampler, config)
+                })
+                .collect()
+        }
+        Pattern::Trace => unreachable!("Trace pattern is handled above"),
+    }
+}
+
+fn sampler(params: Option<&DistParams>, config: &Config, config_path: &Path) -> RngSampler {
+    let mut params = params
+        .cloned()
+        .expect("Random pattern needs on_duration and off_duration")
+        .with_base_path(config_path);
+    params.seed = params.seed.or(config.seed);
+    RngSampler::new(params)
+}
+
+/// Rounds the time down to a multiple of the step size.
+fn round(time: TimeMS, step_size: TimeMS) -> TimeMS {
+    TimeMS::from(time.as_u64() - time.as_u64() % step_size.as_u64())
+}
+
+fn push_period(
+    periods: &mut Vec<Period>,
+    agent_id: AgentId,
+    on: TimeMS,
+    off: TimeMS,
+    config: &Config,
+) {
+    let on = round(on, config.step_size);
+    let off = round(off.min(config.duration), config.step_size);
+    if on < off {
+        periods.push(Period { agent_id, on, off });
+    }
+}
+
+/// Periods of an agent that is on for `on_time` at the start of every `period` after the
+/// `offset`.
+pub fn duty_cycle(
+    agent_id: AgentId,
+    period: TimeMS,
+    on_time: TimeMS,
+    offset: TimeMS,
+    config: &Config,
+) -> Vec<Period> {
+    let mut periods = Vec::new();
+    let mut start = offset;
+    while start < config.duration {
+        push_period(&mut periods, agent_id, start, start + on_time, config);
+        start += period;
+    }
+    periods
+}
+
+/// Periods of an agent that is alternately off and on for the sampled durations. Samples
+/// shorter than a step are extended to one step so that every period advances the time.
+pub fn random_periods(
+    agent_id: AgentId,
+    on_sampler: &mut RngSampler,
+    off_sampler: &mut RngSampler,
+    config: &Config,
+) -> Vec<Period> {
+    let sample = |sampler: &mut RngSampler| {
+        TimeMS::from((sampler.sample().max(0.0) as u64).max(config.step_size.as_u64()))
+    };
+    let mut periods = Vec::new();
+    let mut time = TimeMS::default();
+    while time < config.duration {
+        let on = time + sample(off_sampler);
+        let off = on + sample(on_sampler);
+        push_period(&mut periods, agent_id, on, off, config);
+        time = off;
+    }
+    periods
+}
+
+/// Periods in which the agents appear in a trace. An agent is switched on at its first
+/// appearance and off one step after its last one, unless it is absent for longer than the
+/// `max_gap` in between.
+pub fn trace_periods(
+    appearances: &HashMap<AgentId, Vec<TimeMS>>,
+    max_gap: TimeMS,
+    step_size: TimeMS,
+    duration: TimeMS,
+) -> Vec<Period> {
+    let mut periods = Vec::new();
+    for (agent_id, time_steps) in appearances.iter() {
+        let mut time_steps = time_steps.iter().copied().filter(|time| *time < duration);
+        let Some(first) = time_steps.next() else {
+            continue;
+        };
+        let (mut on, mut last) = (first, first);
+        for time in time_steps {
+            if time.as_u64() - last.as_u64() > max_gap.as_u64() {
+                periods.push(Period {
+                    agent_id: *agent_id,
+                    on,
+                    off: last + step_size,
+                });
+                on = time;
+            }
+            last = time;
+        }
+        periods.push(Period {
+            agent_id: *agent_id,
+            on,
+            off: (last + step_size).min(duration),
+        });
+    }
+    periods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(duration: u64) -> Config {
+        Config {
+            output_file: "power.parquet".to_string(),
+            duration: TimeMS::from(duration),
+            step_size: TimeMS::from(100),
+            seed: Some(7),
+            groups: Vec::new(),
+        }
+    }
+
+    fn uniform(min: f32, max: f32) -> RngSampler {
+        RngSampler::new(DistParams {
+            dist_name: "uniform".to_string(),
+            seed: Some(3),
+            mean: None,
+            std_dev: None,
+            location: None,
+            scale: None,
+            shape: None,
+            rate: None,
+            alpha: None,
+            beta: None,
+            min: Some(min),
+            max: Some(max),
+            cdf_file: None,
+        })
+    }
+
+    #[test]
+    fn test_duty_cycle() {
+        let agent_id = AgentId::from(1);
+        let periods = duty_cycle(
+            agent_id,
+            TimeMS::from(1000),
+            TimeMS::from(300),
+            TimeMS::from(250),
+            &config(2500),
+        );
+        let times: Vec<(u64, u64)> = periods
+            .iter()
+            .map(|period| (period.on.as_u64(), period.off.as_u64()))
+            .collect();
+        assert_eq!(times, vec![(200, 500), (1200, 1500), (2200, 2500)]);
+    }
+
+    #[test]
+    fn test_random_periods_alternate() {
+        let config = config(60000);
+        let mut on_sampler = uniform(500.0, 1500.0);
+        let mut off_sampler = uniform(2000.0, 4000.0);
+        let periods = random_periods(AgentId::from(2), &mut on_sampler, &mut off_sampler, &config);
+        assert!(periods.len() > 10);
+        for pair in periods.windows(2) {
+            assert!(pair[1].on.as_u64() - pair[0].off.as_u64() >= 1900);
+        }
+        for period in periods.iter() {
+            assert_eq!(period.on.as_u64() % 100, 0);
+            assert!(period.off <= config.duration);
+            assert!(period.off.as_u64() - period.on.as_u64() <= 1600);
+        }
+    }
+
+    #[test]
+    fn test_trace_periods_split_on_gaps() {
+        let mut appearances = HashMap::new();
+        let times = [100, 200, 300, 700, 800, 900, 1000]
+            .into_iter()
+            .map(TimeMS::from)
+            .collect();
+        appearances.insert(AgentId::from(3), times);
+        let periods = trace_periods(
+            &appearances,
+            TimeMS::from(200),
+            TimeMS::from(100),
+            TimeMS::from(1000),
+        );
+        let times: Vec<(u64, u64)> = periods
+            .iter()
+            .map(|period| (period.on.as_u64(), period.off.as_u64()))
+            .collect();
+        assert_eq!(times, vec![(100, 400), (700, 1000)]);
+    }
+}
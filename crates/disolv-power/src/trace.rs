@@ -0,0 +1,45 @@
+use arrow::array::RecordBatch;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_input::batch::read_u64_column;
+use disolv_input::columns::{AGENT_ID, TIME_STEP};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+/// Reads the time steps at which every agent appears in a position trace, in order.
+pub fn read_appearances(file_path: &Path) -> HashMap<AgentId, Vec<TimeMS>> {
+    let mut appearances: HashMap<AgentId, Vec<TimeMS>> = HashMap::new();
+    for record_batch in read_batches(file_path) {
+        let time_steps = read_u64_column(TIME_STEP, &record_batch);
+        let agent_ids = read_u64_column(AGENT_ID, &record_batch);
+        for row in 0..record_batch.num_rows() {
+            appearances
+                .entry(AgentId::from(agent_ids[row]))
+                .or_default()
+                .push(TimeMS::from(time_steps[row]));
+        }
+    }
+    appearances.values_mut().for_each(|time_steps| {
+        time_steps.sort();
+        time_steps.dedup();
+    });
+    appearances
+}
+
+fn read_batches(file_path: &Path) -> impl Iterator<Item = RecordBatch> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Error reading file {}: {}", file_path.display(), e),
+    };
+    let reader = match ParquetRecordBatchReaderBuilder::try_new(file) {
+        Ok(builder) => builder.build(),
+        Err(e) => panic!("Error building parquet reader: {}", e),
+    };
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(e) => panic!("Error building reader: {}", e),
+    };
+    reader.map(|batch| batch.unwrap_or_else(|e| panic!("Error reading record batch: {}", e)))
+}
@@ -0,0 +1,55 @@
+use crate::schedule::Period;
+use arrow::array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use disolv_input::columns::{AGENT_ID, OFF_TIMES, ON_TIMES};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Writes the periods in the power schedule schema of the simulator, one row per period.
+pub fn write_periods(output_file: &Path, periods: &[Period]) {
+    let schema = Schema::new(vec![
+        Field::new(AGENT_ID, DataType::UInt64, false),
+        Field::new(ON_TIMES, DataType::UInt64, false),
+        Field::new(OFF_TIMES, DataType::UInt64, false),
+    ]);
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = match File::create(output_file) {
+        Ok(file) => file,
+        Err(e) => panic!("Failed to create file {}: {}", output_file.display(), e),
+    };
+    let mut writer = match ArrowWriter::try_new(file, SchemaRef::from(schema), Some(props)) {
+        Ok(writer) => writer,
+        Err(e) => panic!("Failed to create parquet writer: {}", e),
+    };
+    let record_batch = RecordBatch::try_from_iter(vec![
+        (
+            AGENT_ID,
+            Arc::new(UInt64Array::from_iter_values(
+                periods.iter().map(|period| period.agent_id.as_u64()),
+            )) as ArrayRef,
+        ),
+        (
+            ON_TIMES,
+            Arc::new(UInt64Array::from_iter_values(
+                periods.iter().map(|period| period.on.as_u64()),
+            )) as ArrayRef,
+        ),
+        (
+            OFF_TIMES,
+            Arc::new(UInt64Array::from_iter_values(
+                periods.iter().map(|period| period.off.as_u64()),
+            )) as ArrayRef,
+        ),
+    ])
+    .expect("Failed to convert power schedule to record batch");
+    writer
+        .write(&record_batch)
+        .expect("Failed to write power schedule to file");
+    writer.close().expect("Failed to close power schedule file");
+}
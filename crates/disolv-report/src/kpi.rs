@@ -0,0 +1,107 @@
+use arrow_array::RecordBatch;
+use disolv_input::batch::{read_f64_column, read_u32_column, read_u64_column};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+const TX_DATA_FILE: &str = "tx_data.parquet";
+const PERCEPTION_FILE: &str = "perception.parquet";
+const CACHE_FILE: &str = "cache.parquet";
+
+/// KPIs of a single run in the order they are reported.
+pub(crate) type RunKpis = Vec<(String, f64)>;
+
+/// Derives the KPIs of a run from the output files in the run directory. Output tables that
+/// were not enabled in the run are skipped.
+pub(crate) fn read_run_kpis(run_dir: &Path) -> RunKpis {
+    let mut kpis = RunKpis::new();
+    let tx_file = run_dir.join(TX_DATA_FILE);
+    if tx_file.exists() {
+        kpis.extend(tx_kpis(&read_batches(&tx_file)));
+    }
+    let perception_file = run_dir.join(PERCEPTION_FILE);
+    if perception_file.exists() {
+        kpis.extend(perception_kpis(&read_batches(&perception_file)));
+    }
+    let cache_file = run_dir.join(CACHE_FILE);
+    if cache_file.exists() {
+        kpis.extend(cache_kpis(&read_batches(&cache_file)));
+    }
+    if kpis.is_empty() {
+        panic!("No output files found in {}", run_dir.display());
+    }
+    kpis
+}
+
+fn tx_kpis(batches: &[RecordBatch]) -> RunKpis {
+    let (mut attempted, mut succeeded) = (0u64, 0u64);
+    let (mut total_latency, mut delivered_bytes) = (0u64, 0u64);
+    for batch in batches.iter() {
+        let tx_status = read_u32_column("tx_status", batch);
+        let latency = read_u64_column("latency", batch);
+        let payload_size = read_u64_column("payload_size", batch);
+        for (idx, status) in tx_status.iter().enumerate() {
+            attempted += 1;
+            if *status == 0 {
+                succeeded += 1;
+                total_latency += latency[idx];
+                delivered_bytes += payload_size[idx];
+            }
+        }
+    }
+    let delivery_ratio = match attempted {
+        0 => 0.0,
+        _ => succeeded as f64 / attempted as f64,
+    };
+    let mean_latency = match succeeded {
+        0 => 0.0,
+        _ => total_latency as f64 / succeeded as f64,
+    };
+    vec![
+        ("delivery_ratio".to_string(), delivery_ratio),
+        ("mean_latency".to_string(), mean_latency),
+        ("delivered_bytes".to_string(), delivered_bytes as f64),
+    ]
+}
+
+fn perception_kpis(batches: &[RecordBatch]) -> RunKpis {
+    let redundancy: Vec<f64> = batches
+        .iter()
+        .flat_map(|batch| read_f64_column("redundancy", batch))
+        .collect();
+    let mean_redundancy = match redundancy.len() {
+        0 => 0.0,
+        steps => redundancy.iter().sum::<f64>() / steps as f64,
+    };
+    vec![("mean_redundancy".to_string(), mean_redundancy)]
+}
+
+fn cache_kpis(batches: &[RecordBatch]) -> RunKpis {
+    let (mut requests, mut hits) = (0u64, 0u64);
+    for batch in batches.iter() {
+        requests += read_u64_column("requests", batch).iter().sum::<u64>();
+        hits += read_u64_column("hits", batch).iter().sum::<u64>();
+    }
+    let hit_ratio = match requests {
+        0 => 0.0,
+        _ => hits as f64 / requests as f64,
+    };
+    vec![("cache_hit_ratio".to_string(), hit_ratio)]
+}
+
+fn read_batches(file_path: &Path) -> Vec<RecordBatch> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Error reading {}: {}", file_path.display(), e),
+    };
+    let reader = match ParquetRecordBatchReaderBuilder::try_new(file) {
+        Ok(builder) => builder.build(),
+        Err(e) => panic!("Error building parquet reader: {}", e),
+    };
+    match reader {
+        Ok(reader) => reader
+            .map(|batch| batch.unwrap_or_else(|e| panic!("Error reading record batch: {}", e)))
+            .collect(),
+        Err(e) => panic!("Error building reader: {}", e),
+    }
+}
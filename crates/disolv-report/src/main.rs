@@ -0,0 +1,58 @@
+mod kpi;
+mod report;
+mod stats;
+
+use crate::kpi::{read_run_kpis, RunKpis};
+use crate::report::{compare, write_csv, write_markdown, write_svg};
+use clap::Parser;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 'r', long, num_args = 1.., required = true, value_name = "Run Directories")]
+    runs: Vec<PathBuf>,
+    #[arg(short = 'b', long, num_args = 1.., value_name = "Baseline Run Directories")]
+    baseline: Vec<PathBuf>,
+    #[arg(short = 'o', long, value_name = "Report Directory")]
+    output: PathBuf,
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 0.95,
+        value_name = "Confidence Level"
+    )]
+    confidence: f64,
+    #[arg(long, default_value_t = false)]
+    svg: bool,
+}
+
+fn main() {
+    let args = CliArgs::parse();
+    if !(0.0..1.0).contains(&args.confidence) {
+        panic!("Confidence level must be between 0 and 1.");
+    }
+    fs::create_dir_all(&args.output)
+        .unwrap_or_else(|e| panic!("Failed to create the report directory: {}", e));
+
+    let runs: Vec<RunKpis> = args.runs.iter().map(|run| read_run_kpis(run)).collect();
+    let baseline: Vec<RunKpis> = args.baseline.iter().map(|run| read_run_kpis(run)).collect();
+    let comparisons = compare(&runs, &baseline, args.confidence);
+
+    write_csv(&args.output.join("comparison.csv"), &comparisons);
+    write_markdown(
+        &args.output.join("comparison.md"),
+        &comparisons,
+        args.confidence,
+    );
+    if args.svg {
+        write_svg(&args.output, &comparisons);
+    }
+    println!(
+        "Compared {} runs against {} baseline runs. Report written to {}.",
+        runs.len(),
+        baseline.len(),
+        args.output.display()
+    );
+}
@@ -0,0 +1,187 @@
+use crate::kpi::RunKpis;
+use crate::stats::{summarize, welch_t_test, Summary, TTest};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+const SVG_WIDTH: f64 = 320.0;
+const SVG_HEIGHT: f64 = 240.0;
+const SVG_MARGIN: f64 = 40.0;
+
+/// Comparison of a KPI across the runs and, when given, the baseline runs.
+#[derive(Clone, Debug)]
+pub(crate) struct KpiComparison {
+    pub(crate) name: String,
+    pub(crate) summary: Summary,
+    pub(crate) baseline: Option<Summary>,
+    pub(crate) t_test: Option<TTest>,
+}
+
+pub(crate) fn compare(
+    runs: &[RunKpis],
+    baseline: &[RunKpis],
+    confidence: f64,
+) -> Vec<KpiComparison> {
+    let names: Vec<String> = runs[0].iter().map(|(name, _)| name.to_owned()).collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let values = values_of(&name, runs);
+            let baseline_values = values_of(&name, baseline);
+            let baseline_summary = match baseline_values.is_empty() {
+                true => None,
+                false => Some(summarize(&baseline_values, confidence)),
+            };
+            KpiComparison {
+                summary: summarize(&values, confidence),
+                baseline: baseline_summary,
+                t_test: welch_t_test(&values, &baseline_values),
+                name,
+            }
+        })
+        .collect()
+}
+
+fn values_of(name: &str, runs: &[RunKpis]) -> Vec<f64> {
+    runs.iter()
+        .filter_map(|kpis| kpis.iter().find(|(kpi, _)| kpi == name))
+        .map(|(_, value)| *value)
+        .collect()
+}
+
+pub(crate) fn write_csv(output_file: &Path, comparisons: &[KpiComparison]) {
+    let mut content = String::from(
+        "kpi,runs,mean,std_dev,ci_low,ci_high,baseline_mean,baseline_ci_low,baseline_ci_high,t_value,p_value\n",
+    );
+    for comparison in comparisons.iter() {
+        let summary = comparison.summary;
+        let _ = write!(
+            content,
+            "{},{},{},{},{},{}",
+            comparison.name,
+            summary.runs,
+            summary.mean,
+            summary.std_dev,
+            summary.ci_low,
+            summary.ci_high
+        );
+        match comparison.baseline {
+            Some(base) => {
+                let _ = write!(content, ",{},{},{}", base.mean, base.ci_low, base.ci_high);
+            }
+            None => content.push_str(",,,"),
+        }
+        match comparison.t_test {
+            Some(t_test) => {
+                let _ = writeln!(content, ",{},{}", t_test.t_value, t_test.p_value);
+            }
+            None => content.push_str(",,\n"),
+        }
+    }
+    write_file(output_file, content);
+}
+
+pub(crate) fn write_markdown(output_file: &Path, comparisons: &[KpiComparison], confidence: f64) {
+    let ci_label = format!("{:.0}% CI", confidence * 100.0);
+    let mut content = format!(
+        "| KPI | Runs | Mean | {ci} | Baseline mean | Baseline {ci} | p-value | Significant |\n",
+        ci = ci_label
+    );
+    content.push_str("|---|---|---|---|---|---|---|---|\n");
+    for comparison in comparisons.iter() {
+        let summary = comparison.summary;
+        let (base_mean, base_ci) = match comparison.baseline {
+            Some(base) => (
+                format!("{:.4}", base.mean),
+                format!("[{:.4}, {:.4}]", base.ci_low, base.ci_high),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let (p_value, significant) = match comparison.t_test {
+            Some(t_test) => (
+                format!("{:.4}", t_test.p_value),
+                (t_test.p_value < SIGNIFICANCE_LEVEL).to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let _ = writeln!(
+            content,
+            "| {} | {} | {:.4} | [{:.4}, {:.4}] | {} | {} | {} | {} |",
+            comparison.name,
+            summary.runs,
+            summary.mean,
+            summary.ci_low,
+            summary.ci_high,
+            base_mean,
+            base_ci,
+            p_value,
+            significant
+        );
+    }
+    write_file(output_file, content);
+}
+
+/// Writes a bar chart with the confidence intervals for each KPI.
+pub(crate) fn write_svg(output_dir: &Path, comparisons: &[KpiComparison]) {
+    for comparison in comparisons.iter() {
+        let mut bars = vec![("runs", comparison.summary)];
+        if let Some(base) = comparison.baseline {
+            bars.push(("baseline", base));
+        }
+        let max_value = bars
+            .iter()
+            .map(|(_, summary)| summary.ci_high.max(summary.mean))
+            .fold(0.0, f64::max);
+        let scale = match max_value > 0.0 {
+            true => (SVG_HEIGHT - 2.0 * SVG_MARGIN) / max_value,
+            false => 0.0,
+        };
+        let base_y = SVG_HEIGHT - SVG_MARGIN;
+        let bar_width = (SVG_WIDTH - 2.0 * SVG_MARGIN) / (2 * bars.len()) as f64;
+
+        let mut content = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\">\n"
+        );
+        let _ = writeln!(
+            content,
+            "<text x=\"{}\" y=\"20\" text-anchor=\"middle\">{}</text>",
+            SVG_WIDTH / 2.0,
+            comparison.name
+        );
+        for (idx, (label, summary)) in bars.iter().enumerate() {
+            let x = SVG_MARGIN + bar_width * (2 * idx) as f64 + bar_width / 2.0;
+            let center = x + bar_width / 2.0;
+            let height = summary.mean.max(0.0) * scale;
+            let (ci_top, ci_bottom) = (
+                base_y - summary.ci_high.max(0.0) * scale,
+                base_y - summary.ci_low.max(0.0) * scale,
+            );
+            let _ = writeln!(
+                content,
+                "<rect x=\"{x:.1}\" y=\"{:.1}\" width=\"{bar_width:.1}\" height=\"{height:.1}\" fill=\"steelblue\"/>",
+                base_y - height
+            );
+            let _ = writeln!(
+                content,
+                "<line x1=\"{center:.1}\" y1=\"{ci_top:.1}\" x2=\"{center:.1}\" y2=\"{ci_bottom:.1}\" stroke=\"black\"/>"
+            );
+            let _ = writeln!(
+                content,
+                "<text x=\"{center:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{label} ({:.3})</text>",
+                base_y + 20.0,
+                summary.mean
+            );
+        }
+        content.push_str("</svg>\n");
+        write_file(
+            &output_dir.join(format!("{}.svg", comparison.name)),
+            content,
+        );
+    }
+}
+
+fn write_file(output_file: &Path, content: String) {
+    fs::write(output_file, content)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", output_file.display(), e));
+}
@@ -0,0 +1,209 @@
+/// Summary of a KPI across the runs. The confidence interval of the mean uses the Student's t
+/// distribution, as the number of runs is usually small.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Summary {
+    pub(crate) runs: usize,
+    pub(crate) mean: f64,
+    pub(crate) std_dev: f64,
+    pub(crate) ci_low: f64,
+    pub(crate) ci_high: f64,
+}
+
+/// Result of a Welch's t-test comparing the means of two sets of runs.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TTest {
+    pub(crate) t_value: f64,
+    pub(crate) p_value: f64,
+}
+
+pub(crate) fn summarize(values: &[f64], confidence: f64) -> Summary {
+    let runs = values.len();
+    let mean = mean(values);
+    let std_dev = variance(values, mean).sqrt();
+    let margin = match runs {
+        0 | 1 => 0.0,
+        _ => {
+            let t_crit = t_quantile(0.5 + confidence / 2.0, (runs - 1) as f64);
+            t_crit * std_dev / (runs as f64).sqrt()
+        }
+    };
+    Summary {
+        runs,
+        mean,
+        std_dev,
+        ci_low: mean - margin,
+        ci_high: mean + margin,
+    }
+}
+
+/// Two-sided Welch's t-test. Returns None when either set has fewer than two runs or when both
+/// sets have no variance.
+pub(crate) fn welch_t_test(values: &[f64], baseline: &[f64]) -> Option<TTest> {
+    if values.len() < 2 || baseline.len() < 2 {
+        return None;
+    }
+    let (n_a, n_b) = (values.len() as f64, baseline.len() as f64);
+    let (mean_a, mean_b) = (mean(values), mean(baseline));
+    let var_a = variance(values, mean_a) / n_a;
+    let var_b = variance(baseline, mean_b) / n_b;
+    if var_a + var_b == 0.0 {
+        return None;
+    }
+    let t_value = (mean_a - mean_b) / (var_a + var_b).sqrt();
+    let dof = (var_a + var_b).powi(2) / (var_a.powi(2) / (n_a - 1.0) + var_b.powi(2) / (n_b - 1.0));
+    let p_value = incomplete_beta(dof / (dof + t_value * t_value), dof / 2.0, 0.5);
+    Some(TTest { t_value, p_value })
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance with Bessel's correction.
+fn variance(values: &[f64], mean: f64) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (values.len() - 1) as f64
+}
+
+fn t_cdf(t_value: f64, dof: f64) -> f64 {
+    let tail = 0.5 * incomplete_beta(dof / (dof + t_value * t_value), dof / 2.0, 0.5);
+    if t_value >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Quantile of the Student's t distribution found by bisection of the CDF.
+fn t_quantile(probability: f64, dof: f64) -> f64 {
+    let (mut low, mut high) = (-1e3, 1e3);
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if t_cdf(mid, dof) < probability {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    // Lanczos approximation with g = 7.
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (idx, coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + idx as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function evaluated with the modified Lentz method.
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-30;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut fraction = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + even * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1.0 + even / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1.0 / d;
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + odd * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1.0 + odd / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1.0 / d;
+        let delta = d * c;
+        fraction *= delta;
+        if (delta - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_t_quantile() {
+        // Reference values from the t-tables.
+        assert!((t_quantile(0.975, 4.0) - 2.776).abs() < 1e-3);
+        assert!((t_quantile(0.975, 29.0) - 2.045).abs() < 1e-3);
+        assert!((t_quantile(0.5, 10.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.95);
+        assert_eq!(summary.runs, 5);
+        assert!((summary.mean - 3.0).abs() < 1e-9);
+        assert!((summary.std_dev - 2.5f64.sqrt()).abs() < 1e-9);
+        let margin = 2.776 * 2.5f64.sqrt() / 5f64.sqrt();
+        assert!((summary.ci_high - 3.0 - margin).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        let values = [27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6];
+        let baseline = [27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2];
+        let t_test = welch_t_test(&values, &baseline).expect("t-test should be defined");
+        assert!((t_test.t_value + 2.036).abs() < 1e-3);
+        assert!((t_test.p_value - 0.0593).abs() < 1e-3);
+        assert!(welch_t_test(&[1.0], &baseline).is_none());
+    }
+}
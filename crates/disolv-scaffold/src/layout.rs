@@ -0,0 +1,218 @@
+use clap::ValueEnum;
+
+/// Templates of the scenarios that can be generated.
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub(crate) enum Template {
+    /// Vehicles driving on a grid of streets with RSUs at the junctions.
+    Urban,
+    /// Vehicles driving on a multi-lane highway with RSUs along the road.
+    Highway,
+}
+
+/// Trajectories flown by the UAVs of a scenario.
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+pub(crate) enum Flight {
+    /// Each UAV orbits above one of the RSUs.
+    #[default]
+    Circular,
+    /// UAVs fly a closed tour over all the RSUs, one after the other.
+    Waypoint,
+}
+
+/// Geometry of a template. Vehicles are placed on the streets of the layout and RSUs are
+/// placed at fixed positions. Link radii are in meters.
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    pub(crate) cell_size: f64,
+    pub(crate) rsu_positions: Vec<[f64; 2]>,
+    pub(crate) rsu_radius: f64,
+    pub(crate) v2v_radius: f64,
+}
+
+const URBAN_SIZE: f64 = 1000.0;
+const URBAN_BLOCK: f64 = 200.0;
+const HIGHWAY_LENGTH: f64 = 5000.0;
+const HIGHWAY_LANE_WIDTH: f64 = 10.0;
+const HIGHWAY_LANES: u32 = 4;
+const HIGHWAY_RSU_SPACING: f64 = 500.0;
+const UAV_ALTITUDE: f64 = 100.0;
+const UAV_SPEED: f64 = 15.0;
+
+impl Template {
+    pub(crate) fn layout(&self) -> Layout {
+        match self {
+            Template::Urban => {
+                let mut rsu_positions = Vec::new();
+                let junctions = (URBAN_SIZE / (2.0 * URBAN_BLOCK)).ceil() as u32;
+                for x_idx in 0..junctions {
+                    for y_idx in 0..junctions {
+                        rsu_positions.push([
+                            URBAN_BLOCK / 2.0 + 2.0 * URBAN_BLOCK * x_idx as f64,
+                            URBAN_BLOCK / 2.0 + 2.0 * URBAN_BLOCK * y_idx as f64,
+                        ]);
+                    }
+                }
+                Layout {
+                    width: URBAN_SIZE,
+                    height: URBAN_SIZE,
+                    cell_size: URBAN_BLOCK,
+                    rsu_positions,
+                    rsu_radius: 300.0,
+                    v2v_radius: 100.0,
+                }
+            }
+            Template::Highway => {
+                let rsu_count = (HIGHWAY_LENGTH / HIGHWAY_RSU_SPACING) as u32;
+                let rsu_positions = (0..rsu_count)
+                    .map(|idx| {
+                        [
+                            HIGHWAY_RSU_SPACING / 2.0 + HIGHWAY_RSU_SPACING * idx as f64,
+                            HIGHWAY_LANE_WIDTH * HIGHWAY_LANES as f64 / 2.0,
+                        ]
+                    })
+                    .collect();
+                Layout {
+                    width: HIGHWAY_LENGTH,
+                    height: HIGHWAY_LANE_WIDTH * HIGHWAY_LANES as f64,
+                    cell_size: HIGHWAY_RSU_SPACING,
+                    rsu_positions,
+                    rsu_radius: 400.0,
+                    v2v_radius: 200.0,
+                }
+            }
+        }
+    }
+
+    /// Position of a vehicle at the given time in seconds. Vehicles are spread over the streets
+    /// or lanes with different start offsets and speeds, and wrap around at the field edges.
+    pub(crate) fn vehicle_position(&self, vehicle_idx: u32, time_secs: f64) -> [f64; 2] {
+        match self {
+            Template::Urban => {
+                let streets = (URBAN_SIZE / URBAN_BLOCK) as u32;
+                let street = vehicle_idx % streets;
+                let street_pos = URBAN_BLOCK / 2.0 + URBAN_BLOCK * street as f64;
+                let speed = 8.0 + (vehicle_idx % 5) as f64;
+                let start = (vehicle_idx as f64 * 137.0) % URBAN_SIZE;
+                let mut travelled = (start + speed * time_secs) % URBAN_SIZE;
+                if (vehicle_idx / streets) % 2 == 1 {
+                    travelled = URBAN_SIZE - travelled;
+                }
+                if vehicle_idx.is_multiple_of(2) {
+                    [travelled, street_pos]
+                } else {
+                    [street_pos, travelled]
+                }
+            }
+            Template::Highway => {
+                let lane = vehicle_idx % HIGHWAY_LANES;
+                let lane_pos = HIGHWAY_LANE_WIDTH / 2.0 + HIGHWAY_LANE_WIDTH * lane as f64;
+                let speed = 25.0 + 2.5 * (vehicle_idx % 4) as f64;
+                let start = (vehicle_idx as f64 * 311.0) % HIGHWAY_LENGTH;
+                let travelled = (start + speed * time_secs) % HIGHWAY_LENGTH;
+                if lane < HIGHWAY_LANES / 2 {
+                    [travelled, lane_pos]
+                } else {
+                    [HIGHWAY_LENGTH - travelled, lane_pos]
+                }
+            }
+        }
+    }
+}
+
+impl Layout {
+    /// Position of a UAV at the given time in seconds, including its altitude. UAVs are spread
+    /// over the RSUs so that they do not share a trajectory.
+    pub(crate) fn uav_position(&self, flight: Flight, uav_idx: u32, time_secs: f64) -> [f64; 3] {
+        let rsu_count = self.rsu_positions.len();
+        match flight {
+            Flight::Circular => {
+                let centre = self.rsu_positions[uav_idx as usize % rsu_count];
+                let radius = self.cell_size / 2.0;
+                let angle = UAV_SPEED * time_secs / radius
+                    + std::f64::consts::TAU * (uav_idx as usize / rsu_count) as f64 / 4.0;
+                [
+                    centre[0] + radius * angle.cos(),
+                    centre[1] + radius * angle.sin(),
+                    UAV_ALTITUDE,
+                ]
+            }
+            Flight::Waypoint => {
+                let legs: Vec<([f64; 2], [f64; 2], f64)> = (0..rsu_count)
+                    .map(|idx| {
+                        let from = self.rsu_positions[idx];
+                        let to = self.rsu_positions[(idx + 1) % rsu_count];
+                        (from, to, (to[0] - from[0]).hypot(to[1] - from[1]))
+                    })
+                    .collect();
+                let tour_length: f64 = legs.iter().map(|leg| leg.2).sum();
+                if tour_length == 0.0 {
+                    let waypoint = self.rsu_positions[0];
+                    return [waypoint[0], waypoint[1], UAV_ALTITUDE];
+                }
+                let start = tour_length * uav_idx as f64 / rsu_count as f64;
+                let mut travelled = (start + UAV_SPEED * time_secs) % tour_length;
+                for (from, to, length) in legs.iter() {
+                    if travelled < *length {
+                        let share = travelled / length;
+                        return [
+                            from[0] + (to[0] - from[0]) * share,
+                            from[1] + (to[1] - from[1]) * share,
+                            UAV_ALTITUDE,
+                        ];
+                    }
+                    travelled -= length;
+                }
+                let waypoint = self.rsu_positions[0];
+                [waypoint[0], waypoint[1], UAV_ALTITUDE]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ground_distance(a: [f64; 3], b: [f64; 2]) -> f64 {
+        (a[0] - b[0]).hypot(a[1] - b[1])
+    }
+
+    #[test]
+    fn test_circular_flight_orbits_an_rsu_at_altitude() {
+        let layout = Template::Urban.layout();
+        for time_secs in [0.0, 7.5, 42.0] {
+            let position = layout.uav_position(Flight::Circular, 1, time_secs);
+            let centre = layout.rsu_positions[1];
+            assert!((ground_distance(position, centre) - layout.cell_size / 2.0).abs() < 1e-9);
+            assert_eq!(position[2], UAV_ALTITUDE);
+        }
+        assert_ne!(
+            layout.uav_position(Flight::Circular, 0, 0.0),
+            layout.uav_position(Flight::Circular, 0, 5.0)
+        );
+    }
+
+    #[test]
+    fn test_waypoint_flight_passes_over_the_rsus() {
+        let layout = Template::Highway.layout();
+        let first = layout.uav_position(Flight::Waypoint, 0, 0.0);
+        assert!(ground_distance(first, layout.rsu_positions[0]) < 1e-9);
+        let leg_time = HIGHWAY_RSU_SPACING / UAV_SPEED;
+        let second = layout.uav_position(Flight::Waypoint, 0, leg_time);
+        assert!(ground_distance(second, layout.rsu_positions[1]) < 1e-6);
+        let halfway = layout.uav_position(Flight::Waypoint, 0, leg_time / 2.0);
+        assert!((halfway[0] - HIGHWAY_RSU_SPACING).abs() < 1e-6);
+        assert_eq!(halfway[2], UAV_ALTITUDE);
+    }
+
+    #[test]
+    fn test_waypoint_flight_closes_the_tour() {
+        let layout = Template::Highway.layout();
+        let last = *layout.rsu_positions.last().expect("highway has RSUs");
+        let tour_length = 2.0 * (last[0] - layout.rsu_positions[0][0]);
+        let end = layout.uav_position(Flight::Waypoint, 0, tour_length / UAV_SPEED);
+        assert!(ground_distance(end, layout.rsu_positions[0]) < 1e-6);
+    }
+}
@@ -0,0 +1,49 @@
+mod layout;
+mod scenario;
+mod traces;
+
+use crate::layout::{Flight, Template};
+use crate::scenario::Scenario;
+use clap::Parser;
+use disolv_core::bucket::TimeMS;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct CliArgs {
+    #[arg(short = 't', long, value_enum, value_name = "Scenario Template")]
+    template: Template,
+    #[arg(short = 'o', long, value_name = "Scenario Directory")]
+    output: String,
+    #[arg(short = 'n', long, default_value_t = 50, value_name = "Vehicle Count")]
+    vehicles: u32,
+    #[arg(short = 'u', long, default_value_t = 0, value_name = "UAV Count")]
+    uavs: u32,
+    #[arg(short = 'f', long, value_enum, default_value_t = Flight::Circular, value_name = "UAV Flight")]
+    flight: Flight,
+    #[arg(short = 'd', long, default_value_t = 60000, value_name = "Duration")]
+    duration: u64,
+    #[arg(short = 'l', long, value_name = "Load Profile")]
+    load_profile: Option<PathBuf>,
+}
+
+fn main() {
+    let args = CliArgs::parse();
+    let start = std::time::Instant::now();
+    let scenario = Scenario::new(
+        args.template,
+        &PathBuf::from(args.output),
+        args.vehicles,
+        args.uavs,
+        args.flight,
+        TimeMS::from(args.duration),
+        args.load_profile,
+    );
+    scenario.generate();
+    let elapsed = start.elapsed();
+    println!("Scenario generated in {} ms.", elapsed.as_millis());
+    println!(
+        "Run it with: disolv -c {}",
+        scenario.config_file().display()
+    );
+}
@@ -0,0 +1,425 @@
+use crate::layout::{Flight, Layout, Template};
+use crate::traces::{write_power_schedule, PositionWriter};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_links::builder::LinkBuilder;
+use disolv_links::config::read_config;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const STEP_SIZE: u64 = 100;
+const STREAMING_INTERVAL: u64 = 10000;
+
+const BASE_CONFIG_FILE: &str = "config.toml";
+const LINK_CONFIG_FILE: &str = "links.toml";
+const VEHICLE_POSITIONS: &str = "positions/vehicle_positions.parquet";
+const RSU_POSITIONS: &str = "positions/rsu_positions.parquet";
+const UAV_POSITIONS: &str = "positions/uav_positions.parquet";
+const VEHICLE_POWER: &str = "power/vehicle_power.parquet";
+const RSU_POWER: &str = "power/rsu_power.parquet";
+const UAV_POWER: &str = "power/uav_power.parquet";
+const VEHICLE_RSU_LINKS: &str = "vehicle_rsu";
+const VEHICLE_V2V_LINKS: &str = "vehicle_vehicle";
+const UAV_RSU_LINKS: &str = "uav_rsu";
+const LOAD_PROFILE_FILE: &str = "load_profile.toml";
+
+/// A scenario generated from a template. All the input files are placed in the scenario
+/// directory, with the configuration file at its root. A load profile is copied next to the
+/// configuration, and the simulation uses it for the data generation rates and activations.
+/// UAVs, if any, fly above the RSUs and stream aerial images to them.
+pub(crate) struct Scenario {
+    template: Template,
+    layout: Layout,
+    scenario_path: PathBuf,
+    vehicle_count: u32,
+    uav_count: u32,
+    flight: Flight,
+    duration: TimeMS,
+    load_profile: Option<PathBuf>,
+}
+
+impl Scenario {
+    pub(crate) fn new(
+        template: Template,
+        scenario_path: &Path,
+        vehicle_count: u32,
+        uav_count: u32,
+        flight: Flight,
+        duration: TimeMS,
+        load_profile: Option<PathBuf>,
+    ) -> Self {
+        fs::create_dir_all(scenario_path)
+            .unwrap_or_else(|e| panic!("Failed to create the scenario directory: {}", e));
+        let scenario_path = scenario_path
+            .canonicalize()
+            .expect("Failed to resolve the scenario directory");
+        Self {
+            template,
+            layout: template.layout(),
+            scenario_path,
+            vehicle_count,
+            uav_count,
+            flight,
+            duration,
+            load_profile,
+        }
+    }
+
+    pub(crate) fn config_file(&self) -> PathBuf {
+        self.scenario_path.join(BASE_CONFIG_FILE)
+    }
+
+    pub(crate) fn generate(&self) {
+        for dir in ["positions", "power", "links", "output", "log"] {
+            fs::create_dir_all(self.scenario_path.join(dir))
+                .unwrap_or_else(|e| panic!("Failed to create the {} directory: {}", dir, e));
+        }
+        let vehicle_ids = self.vehicle_ids();
+        let rsu_ids = self.rsu_ids();
+        self.write_vehicle_positions(&vehicle_ids);
+        self.write_rsu_positions(&rsu_ids);
+        write_power_schedule(
+            &self.scenario_path.join(VEHICLE_POWER),
+            &vehicle_ids,
+            self.duration,
+        );
+        if let Some(ref profile_file) = self.load_profile {
+            fs::copy(profile_file, self.scenario_path.join(LOAD_PROFILE_FILE))
+                .unwrap_or_else(|e| panic!("Failed to copy the load profile: {}", e));
+        }
+        write_power_schedule(&self.scenario_path.join(RSU_POWER), &rsu_ids, self.duration);
+        if self.uav_count > 0 {
+            let uav_ids = self.uav_ids();
+            self.write_uav_positions(&uav_ids);
+            write_power_schedule(&self.scenario_path.join(UAV_POWER), &uav_ids, self.duration);
+        }
+        self.write_file(LINK_CONFIG_FILE, self.link_config());
+        self.write_file(BASE_CONFIG_FILE, self.base_config());
+        self.build_links();
+    }
+
+    fn vehicle_ids(&self) -> Vec<AgentId> {
+        (1..=self.vehicle_count as u64).map(AgentId::from).collect()
+    }
+
+    fn rsu_ids(&self) -> Vec<AgentId> {
+        let first_id = self.vehicle_count as u64 + 1;
+        (0..self.layout.rsu_positions.len() as u64)
+            .map(|idx| AgentId::from(first_id + idx))
+            .collect()
+    }
+
+    fn uav_ids(&self) -> Vec<AgentId> {
+        let first_id = self.vehicle_count as u64 + self.layout.rsu_positions.len() as u64 + 1;
+        (0..self.uav_count as u64)
+            .map(|idx| AgentId::from(first_id + idx))
+            .collect()
+    }
+
+    fn write_vehicle_positions(&self, vehicle_ids: &[AgentId]) {
+        let mut writer = PositionWriter::new(&self.scenario_path.join(VEHICLE_POSITIONS));
+        let mut now = TimeMS::default();
+        while now < self.duration {
+            let time_secs = now.as_u64() as f64 / 1000.0;
+            for (idx, vehicle_id) in vehicle_ids.iter().enumerate() {
+                let position = self.template.vehicle_position(idx as u32, time_secs);
+                writer.add_position(now, *vehicle_id, position);
+            }
+            now = TimeMS::from(now.as_u64() + STEP_SIZE);
+            if now.as_u64() % STREAMING_INTERVAL == 0 {
+                writer.flush();
+            }
+        }
+        writer.close();
+    }
+
+    fn write_uav_positions(&self, uav_ids: &[AgentId]) {
+        let mut writer = PositionWriter::aerial(&self.scenario_path.join(UAV_POSITIONS));
+        let mut now = TimeMS::default();
+        while now < self.duration {
+            let time_secs = now.as_u64() as f64 / 1000.0;
+            for (idx, uav_id) in uav_ids.iter().enumerate() {
+                let position = self.layout.uav_position(self.flight, idx as u32, time_secs);
+                writer.add_aerial_position(now, *uav_id, position);
+            }
+            now = TimeMS::from(now.as_u64() + STEP_SIZE);
+            if now.as_u64() % STREAMING_INTERVAL == 0 {
+                writer.flush();
+            }
+        }
+        writer.close();
+    }
+
+    fn write_rsu_positions(&self, rsu_ids: &[AgentId]) {
+        let mut writer = PositionWriter::new(&self.scenario_path.join(RSU_POSITIONS));
+        rsu_ids
+            .iter()
+            .zip(self.layout.rsu_positions.iter())
+            .for_each(|(rsu_id, position)| {
+                writer.add_position(TimeMS::default(), *rsu_id, *position)
+            });
+        writer.close();
+    }
+
+    fn write_file(&self, file_name: &str, content: String) {
+        fs::write(self.scenario_path.join(file_name), content)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", file_name, e));
+    }
+
+    fn build_links(&self) {
+        let link_config_file = self.scenario_path.join(LINK_CONFIG_FILE);
+        let mut builder = LinkBuilder::new(read_config(&link_config_file), link_config_file);
+        builder.initialize();
+        let mut now = builder.start;
+        while now < builder.end {
+            builder.build_links_at(now);
+            now += builder.step_size;
+        }
+        builder.complete();
+    }
+
+    fn link_config(&self) -> String {
+        let path = self.scenario_path.display();
+        format!(
+            r#"[log_settings]
+log_path = "{path}/log"
+log_level = "warn"
+log_file_name = "links.log"
+log_overwrite = true
+
+[settings]
+threads = 1
+start = 0
+end = {end}
+step_size = {STEP_SIZE}
+output_type = "parquet"
+output_path = "{path}/links/"
+
+[[position_files]]
+device = "Vehicle"
+trace_type = "Mobile"
+position_file = "{path}/{VEHICLE_POSITIONS}"
+
+[[position_files]]
+device = "RSU"
+trace_type = "Constant"
+position_file = "{path}/{RSU_POSITIONS}"
+
+[[link_settings]]
+source = "Vehicle"
+target = "RSU"
+link_radius = {rsu_radius:.1}
+link_model = "circular"
+link_type = "Dynamic"
+links_file = "{VEHICLE_RSU_LINKS}"
+
+[[link_settings]]
+source = "Vehicle"
+target = "Vehicle"
+link_radius = {v2v_radius:.1}
+link_model = "circular"
+link_type = "Dynamic"
+links_file = "{VEHICLE_V2V_LINKS}"
+{uav_links}"#,
+            end = self.duration.as_u64(),
+            rsu_radius = self.layout.rsu_radius,
+            v2v_radius = self.layout.v2v_radius,
+            uav_links = self.uav_link_config(),
+        )
+    }
+
+    /// Links of the UAVs to the RSUs. The link producer measures the slant range, so the
+    /// altitude of the UAVs counts towards the RSU radius.
+    fn uav_link_config(&self) -> String {
+        if self.uav_count == 0 {
+            return String::new();
+        }
+        let path = self.scenario_path.display();
+        format!(
+            r#"
+[[position_files]]
+device = "UAV"
+trace_type = "Mobile"
+position_file = "{path}/{UAV_POSITIONS}"
+
+[[link_settings]]
+source = "UAV"
+target = "RSU"
+link_radius = {rsu_radius:.1}
+link_model = "circular"
+link_type = "Dynamic"
+links_file = "{UAV_RSU_LINKS}"
+"#,
+            rsu_radius = self.layout.rsu_radius,
+        )
+    }
+
+    fn base_config(&self) -> String {
+        let path = self.scenario_path.display();
+        format!(
+            r#"[simulation_settings]
+scenario = "{scenario:?}"
+duration = {duration}
+step_size = {STEP_SIZE}
+streaming_interval = {STREAMING_INTERVAL}
+seed = 42
+{load_profile}
+[field_settings]
+width = {width:.1}
+height = {height:.1}
+cell_size = {cell_size:.1}
+
+[log_settings]
+log_path = "log"
+log_level = "info"
+log_file_name = "disolv.log"
+log_overwrite = true
+
+[output_settings]
+output_interval = {STREAMING_INTERVAL}
+output_path = "{path}/output"
+file_out_config = [
+    {{ output_type = "TxData", output_filename = "tx_data.parquet", max_rows = 500000 }},
+    {{ output_type = "RxCounts", output_filename = "rx_counts.parquet", output_interval = 1000 }},
+    {{ output_type = "Perception", output_filename = "perception.parquet" }},
+]
+
+[[network_settings.slice]]
+id = 0
+name = "v2x"
+latency = {{ variant = "constant", constraint = 100, constant_term = 10 }}
+bandwidth = {{ variant = "constant" }}
+
+[[agents]]
+agent_type = "Vehicle"
+power_file = "{VEHICLE_POWER}"
+mobility = {{ mobility_type = "Mobile", is_streaming = true, trace_file = "{VEHICLE_POSITIONS}" }}
+linker = [
+    {{ target_type = "RSU", links_file = "links/{VEHICLE_RSU_LINKS}.parquet", range = {rsu_radius:.1}, is_streaming = true }},
+    {{ target_type = "Vehicle", links_file = "links/{VEHICLE_V2V_LINKS}.parquet", range = {v2v_radius:.1}, is_streaming = true }},
+]
+
+[[agents.class]]
+agent_share = 1.0
+agent_class = "Vehicle5G"
+agent_order = 0
+composer = {{ name = "basic", source_settings = [
+    {{ data_type = "CAM", agent_class = "RSU5G", data_size = 300, source_step = {STEP_SIZE} }},
+    {{ data_type = "Image", agent_class = "RSU5G", data_size = 50000, source_step = 1000 }},
+] }}
+selector = [{{ target_class = "RSU5G", name = "nearest", link_count = 1 }}]
+replier = {{ name = "stats" }}
+energy = {{ name = "proportional", factor = 1, static_power = 0 }}
+storage = {{ variant = "constant", limit = 1000000000 }}
+sensor = {{ name = "perception", target_class = "RSU5G", sensing_range = {sensing_range:.1}, field_of_view = 120.0, header_size = 100, object_size = 35 }}
+actions = [
+    {{ target = "RSU5G", data_type = "CAM", action_type = "Consume" }},
+    {{ target = "RSU5G", data_type = "Image", action_type = "Consume" }},
+    {{ target = "RSU5G", data_type = "CPM", action_type = "Consume" }},
+]
+
+[[agents]]
+agent_type = "RSU"
+power_file = "{RSU_POWER}"
+mobility = {{ mobility_type = "Stationery", is_streaming = false, trace_file = "{RSU_POSITIONS}" }}
+
+[[agents.class]]
+agent_share = 1.0
+agent_class = "RSU5G"
+agent_order = 1
+composer = {{ name = "basic", source_settings = [] }}
+selector = []
+replier = {{ name = "stats" }}
+energy = {{ name = "proportional", factor = 1, static_power = 0 }}
+storage = {{ variant = "constant", limit = 1000000000 }}
+{uav_agents}"#,
+            scenario = self.template,
+            duration = self.duration.as_u64(),
+            width = self.layout.width,
+            height = self.layout.height,
+            cell_size = self.layout.cell_size,
+            rsu_radius = self.layout.rsu_radius,
+            v2v_radius = self.layout.v2v_radius,
+            sensing_range = self.layout.v2v_radius,
+            load_profile = match self.load_profile {
+                Some(_) => format!("load_profile = \"{LOAD_PROFILE_FILE}\"\n"),
+                None => String::new(),
+            },
+            uav_agents = self.uav_agent_config(),
+        )
+    }
+
+    fn uav_agent_config(&self) -> String {
+        if self.uav_count == 0 {
+            return String::new();
+        }
+        format!(
+            r#"
+[[agents]]
+agent_type = "UAV"
+power_file = "{UAV_POWER}"
+mobility = {{ mobility_type = "Mobile", is_streaming = true, trace_file = "{UAV_POSITIONS}" }}
+linker = [
+    {{ target_type = "RSU", links_file = "links/{UAV_RSU_LINKS}.parquet", range = {rsu_radius:.1}, is_streaming = true }},
+]
+
+[[agents.class]]
+agent_share = 1.0
+agent_class = "UAV5G"
+agent_order = 0
+composer = {{ name = "basic", source_settings = [
+    {{ data_type = "Image", agent_class = "RSU5G", data_size = 50000, source_step = 1000 }},
+] }}
+selector = [{{ target_class = "RSU5G", name = "nearest", link_count = 1 }}]
+replier = {{ name = "stats" }}
+energy = {{ name = "proportional", factor = 1, static_power = 0 }}
+storage = {{ variant = "constant", limit = 1000000000 }}
+actions = [
+    {{ target = "RSU5G", data_type = "Image", action_type = "Consume" }},
+]
+"#,
+            rsu_radius = self.layout.rsu_radius,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use disolv::builder::SimulationBuilder;
+    use disolv_core::runner::run_for_kpis;
+
+    // The link producer sets up the global logger, so a test process generates one scenario.
+    #[test]
+    fn test_generated_skeleton_runs_in_the_simulator() {
+        let scenario_path = std::env::temp_dir().join("disolv_scaffold_highway");
+        let _ = fs::remove_dir_all(&scenario_path);
+        let scenario = Scenario::new(
+            Template::Highway,
+            &scenario_path,
+            10,
+            2,
+            Flight::Waypoint,
+            TimeMS::from(2000),
+            None,
+        );
+        scenario.generate();
+        assert!(scenario_path.join(UAV_POSITIONS).exists());
+        assert!(scenario_path
+            .join(format!("links/{UAV_RSU_LINKS}.parquet"))
+            .exists());
+
+        let config_file = scenario.config_file().display().to_string();
+        let scheduler = SimulationBuilder::new(&config_file)
+            .with_fast_mode()
+            .without_logging()
+            .build_with_map();
+        let kpis = run_for_kpis(scheduler);
+        let tx_attempted = kpis
+            .iter()
+            .find(|(name, _)| name == "tx_attempted")
+            .map(|(_, value)| *value)
+            .expect("transmissions are counted");
+        assert!(tx_attempted > 0.0);
+    }
+}
@@ -0,0 +1,163 @@
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_input::columns::{AGENT_ID, COORD_X, COORD_Y, COORD_Z, OFF_TIMES, ON_TIMES, TIME_STEP};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Writes positions in the format read by the simulator and the link producer. Each call to
+/// `flush` closes a row group, which should be done only at the end of a time step to keep the
+/// positions of a time step in a single row group. Aerial writers add the altitude of the
+/// agents in the `COORD_Z` column.
+pub(crate) struct PositionWriter {
+    writer: ArrowWriter<File>,
+    time_step: Vec<u64>,
+    agent_id: Vec<u64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    z: Option<Vec<f64>>,
+}
+
+impl PositionWriter {
+    pub(crate) fn new(output_file: &Path) -> Self {
+        Self::with_altitude(output_file, false)
+    }
+
+    pub(crate) fn aerial(output_file: &Path) -> Self {
+        Self::with_altitude(output_file, true)
+    }
+
+    fn with_altitude(output_file: &Path, altitude: bool) -> Self {
+        let mut fields = vec![
+            Field::new(TIME_STEP, DataType::UInt64, false),
+            Field::new(AGENT_ID, DataType::UInt64, false),
+            Field::new(COORD_X, DataType::Float64, false),
+            Field::new(COORD_Y, DataType::Float64, false),
+        ];
+        if altitude {
+            fields.push(Field::new(COORD_Z, DataType::Float64, false));
+        }
+        Self {
+            writer: create_writer(output_file, Schema::new(fields)),
+            time_step: Vec::new(),
+            agent_id: Vec::new(),
+            x: Vec::new(),
+            y: Vec::new(),
+            z: altitude.then(Vec::new),
+        }
+    }
+
+    pub(crate) fn add_position(&mut self, time_step: TimeMS, agent_id: AgentId, pos: [f64; 2]) {
+        self.time_step.push(time_step.as_u64());
+        self.agent_id.push(agent_id.as_u64());
+        self.x.push(pos[0]);
+        self.y.push(pos[1]);
+    }
+
+    pub(crate) fn add_aerial_position(
+        &mut self,
+        time_step: TimeMS,
+        agent_id: AgentId,
+        pos: [f64; 3],
+    ) {
+        self.add_position(time_step, agent_id, [pos[0], pos[1]]);
+        self.z
+            .as_mut()
+            .expect("Altitude can only be added to an aerial position writer")
+            .push(pos[2]);
+    }
+
+    pub(crate) fn flush(&mut self) {
+        if self.time_step.is_empty() {
+            return;
+        }
+        let altitude = self.z.as_mut().map(|z| {
+            (
+                COORD_Z,
+                Arc::new(Float64Array::from(std::mem::take(z))) as ArrayRef,
+            )
+        });
+        let record_batch = RecordBatch::try_from_iter(
+            vec![
+                (
+                    TIME_STEP,
+                    Arc::new(UInt64Array::from(std::mem::take(&mut self.time_step))) as ArrayRef,
+                ),
+                (
+                    AGENT_ID,
+                    Arc::new(UInt64Array::from(std::mem::take(&mut self.agent_id))) as ArrayRef,
+                ),
+                (
+                    COORD_X,
+                    Arc::new(Float64Array::from(std::mem::take(&mut self.x))) as ArrayRef,
+                ),
+                (
+                    COORD_Y,
+                    Arc::new(Float64Array::from(std::mem::take(&mut self.y))) as ArrayRef,
+                ),
+            ]
+            .into_iter()
+            .chain(altitude),
+        )
+        .expect("Failed to convert positions to record batch");
+        self.writer
+            .write(&record_batch)
+            .expect("Failed to write positions to file");
+        self.writer.flush().expect("Failed to flush positions");
+    }
+
+    pub(crate) fn close(mut self) {
+        self.flush();
+        self.writer.close().expect("Failed to close positions file");
+    }
+}
+
+/// Writes a power schedule in which all the given agents are on for the entire duration.
+pub(crate) fn write_power_schedule(output_file: &Path, agent_ids: &[AgentId], end: TimeMS) {
+    let schema = Schema::new(vec![
+        Field::new(AGENT_ID, DataType::UInt64, false),
+        Field::new(ON_TIMES, DataType::UInt64, false),
+        Field::new(OFF_TIMES, DataType::UInt64, false),
+    ]);
+    let mut writer = create_writer(output_file, schema);
+    let record_batch = RecordBatch::try_from_iter(vec![
+        (
+            AGENT_ID,
+            Arc::new(UInt64Array::from(
+                agent_ids.iter().map(|id| id.as_u64()).collect::<Vec<u64>>(),
+            )) as ArrayRef,
+        ),
+        (
+            ON_TIMES,
+            Arc::new(UInt64Array::from(vec![0; agent_ids.len()])) as ArrayRef,
+        ),
+        (
+            OFF_TIMES,
+            Arc::new(UInt64Array::from(vec![end.as_u64(); agent_ids.len()])) as ArrayRef,
+        ),
+    ])
+    .expect("Failed to convert power schedule to record batch");
+    writer
+        .write(&record_batch)
+        .expect("Failed to write power schedule to file");
+    writer.close().expect("Failed to close power schedule file");
+}
+
+fn create_writer(output_file: &Path, schema: Schema) -> ArrowWriter<File> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let output_file = match File::create(output_file) {
+        Ok(file) => file,
+        Err(e) => panic!("Failed to create file {}: {}", output_file.display(), e),
+    };
+    match ArrowWriter::try_new(output_file, SchemaRef::from(schema), Some(props)) {
+        Ok(writer) => writer,
+        Err(e) => panic!("Failed to create parquet writer: {}", e),
+    }
+}
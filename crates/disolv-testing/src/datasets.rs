@@ -0,0 +1,126 @@
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_input::links::LinkMap;
+use disolv_input::mobility::TraceMap;
+use disolv_models::device::mobility::{MapState, Point2D};
+use disolv_models::net::radio::{DLink, LinkProperties};
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Positions of three vehicles driving along the road at y = 100 m at 20, 30 and 40 m/s,
+/// sampled every second for 10 s. Columns are `time_step,agent_id,x,y`.
+pub const TOY_TRACE: &str = include_str!("../data/toy_trace.csv");
+
+/// Links of the vehicles of the toy trace to an RSU with id 100 at (500, 100), within 300 m,
+/// sampled every second. Columns are `time_step,agent_id,target_id,distance`.
+pub const TOY_LINKS: &str = include_str!("../data/toy_links.csv");
+
+/// Values of the rows of an embedded dataset, without its header.
+fn rows(csv: &str) -> impl Iterator<Item = Vec<&str>> {
+    csv.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split(',').map(|value| value.trim()).collect())
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid value {} in the dataset", value))
+}
+
+/// Reads a trace in the format of the toy trace.
+pub fn read_trace(csv: &str) -> TraceMap {
+    let mut trace = TraceMap::new();
+    for row in rows(csv) {
+        let map_state = MapState::builder()
+            .pos(Point2D::builder().x(parse(row[2])).y(parse(row[3])).build())
+            .build();
+        trace
+            .entry(TimeMS::from(parse::<u64>(row[0])))
+            .or_default()
+            .insert(AgentId::from(parse::<u64>(row[1])), map_state);
+    }
+    trace
+}
+
+/// Reads links in the format of the toy links.
+pub fn read_links(csv: &str) -> LinkMap {
+    let mut links = LinkMap::new();
+    for row in rows(csv) {
+        let link = DLink::builder()
+            .target(AgentId::from(parse::<u64>(row[2])))
+            .properties(LinkProperties {
+                distance: Some(parse(row[3])),
+                ..Default::default()
+            })
+            .build();
+        links
+            .entry(TimeMS::from(parse::<u64>(row[0])))
+            .or_default()
+            .entry(AgentId::from(parse::<u64>(row[1])))
+            .or_default()
+            .push(link);
+    }
+    links
+}
+
+/// Writes a trace in the format of the toy trace as the parquet file read by the mapper.
+pub fn write_trace_file(csv: &str, trace_file: &Path) {
+    let rows: Vec<Vec<&str>> = rows(csv).collect();
+    write_parquet(
+        trace_file,
+        vec![
+            ("time_step", u64_column(&rows, 0)),
+            ("agent_id", u64_column(&rows, 1)),
+            ("x", f64_column(&rows, 2)),
+            ("y", f64_column(&rows, 3)),
+        ],
+    );
+}
+
+/// Writes links in the format of the toy links as the parquet file read by the linker.
+pub fn write_links_file(csv: &str, links_file: &Path) {
+    let rows: Vec<Vec<&str>> = rows(csv).collect();
+    write_parquet(
+        links_file,
+        vec![
+            ("time_step", u64_column(&rows, 0)),
+            ("agent_id", u64_column(&rows, 1)),
+            ("target_id", u64_column(&rows, 2)),
+            ("distance", f64_column(&rows, 3)),
+        ],
+    );
+}
+
+fn u64_column(rows: &[Vec<&str>], index: usize) -> ArrayRef {
+    Arc::new(UInt64Array::from(
+        rows.iter()
+            .map(|row| parse::<u64>(row[index]))
+            .collect::<Vec<u64>>(),
+    ))
+}
+
+fn f64_column(rows: &[Vec<&str>], index: usize) -> ArrayRef {
+    Arc::new(Float64Array::from(
+        rows.iter()
+            .map(|row| parse::<f64>(row[index]))
+            .collect::<Vec<f64>>(),
+    ))
+}
+
+fn write_parquet(file: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let record_batch =
+        RecordBatch::try_from_iter(columns).expect("Failed to convert the dataset to a batch");
+    let output =
+        File::create(file).unwrap_or_else(|e| panic!("Failed to create {}: {}", file.display(), e));
+    let mut writer = ArrowWriter::try_new(output, record_batch.schema(), None)
+        .expect("Failed to create the dataset writer");
+    writer
+        .write(&record_batch)
+        .expect("Failed to write the dataset");
+    writer.close().expect("Failed to close the dataset file");
+}
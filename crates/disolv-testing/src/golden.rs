@@ -0,0 +1,314 @@
+use arrow::array::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use disolv_output::writer::MemoryTables;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that makes the checks write the golden files instead of comparing
+/// against them. Used to create the golden files and to update them after intended changes.
+pub const BLESS_VAR: &str = "DISOLV_BLESS";
+
+/// Path of the golden file with the name in the golden directory of the tests.
+pub fn golden_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(name)
+}
+
+/// Allowed difference between a value and its golden value. Values match when the difference is
+/// within the absolute tolerance or within the relative tolerance of the golden value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Tolerance {
+    pub fn exact() -> Self {
+        Self::default()
+    }
+
+    fn accepts(&self, value: f64, golden: f64) -> bool {
+        let difference = (value - golden).abs();
+        difference <= self.absolute || difference <= self.relative * golden.abs()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    Number(f64),
+    Text(String),
+}
+
+impl Cell {
+    fn parse(value: &str) -> Self {
+        match value.parse::<f64>() {
+            Ok(number) => Cell::Number(number),
+            Err(_) => Cell::Text(value.to_string()),
+        }
+    }
+
+    fn matches(&self, golden: &Cell, tolerance: &Tolerance) -> bool {
+        match (self, golden) {
+            (Cell::Number(value), Cell::Number(golden)) => tolerance.accepts(*value, *golden),
+            _ => self == golden,
+        }
+    }
+
+    fn order(&self, other: &Cell) -> Ordering {
+        match (self, other) {
+            (Cell::Number(a), Cell::Number(b)) => a.total_cmp(b),
+            (Cell::Text(a), Cell::Text(b)) => a.cmp(b),
+            (Cell::Number(_), Cell::Text(_)) => Ordering::Less,
+            (Cell::Text(_), Cell::Number(_)) => Ordering::Greater,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Cell::Number(number) => number.to_string(),
+            Cell::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Rows of the selected columns of a table, sorted by the key columns so that the comparison
+/// does not depend on the order in which the agents wrote their rows.
+#[derive(Debug, PartialEq)]
+struct Rows {
+    columns: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Rows {
+    fn from_batches(batches: &[RecordBatch], columns: &[String]) -> Self {
+        let options = FormatOptions::default();
+        let mut rows = Vec::new();
+        for batch in batches.iter() {
+            let formatters: Vec<ArrayFormatter> = columns
+                .iter()
+                .map(|column| {
+                    let array = batch
+                        .column_by_name(column)
+                        .unwrap_or_else(|| panic!("Column {} is not in the table", column));
+                    ArrayFormatter::try_new(array.as_ref(), &options)
+                        .expect("Failed to format column")
+                })
+                .collect();
+            for row in 0..batch.num_rows() {
+                rows.push(
+                    formatters
+                        .iter()
+                        .map(|formatter| Cell::parse(&formatter.value(row).to_string()))
+                        .collect(),
+                );
+            }
+        }
+        Self {
+            columns: columns.to_vec(),
+            rows,
+        }
+    }
+
+    fn from_csv(content: &str) -> Self {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let columns = lines
+            .next()
+            .map(|header| header.split(',').map(|column| column.to_string()).collect())
+            .unwrap_or_default();
+        let rows = lines
+            .map(|line| line.split(',').map(Cell::parse).collect())
+            .collect();
+        Self { columns, rows }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut content = self.columns.join(",");
+        content.push('\n');
+        for row in self.rows.iter() {
+            let cells: Vec<String> = row.iter().map(Cell::to_text).collect();
+            content.push_str(&cells.join(","));
+            content.push('\n');
+        }
+        content
+    }
+
+    fn sort(&mut self, keys: &[String]) {
+        let key_idx: Vec<usize> = keys
+            .iter()
+            .map(|key| {
+                self.columns
+                    .iter()
+                    .position(|column| column == key)
+                    .unwrap_or_else(|| panic!("Key {} is not a selected column", key))
+            })
+            .collect();
+        let all_idx: Vec<usize> = (0..self.columns.len()).collect();
+        self.rows.sort_by(|a, b| {
+            key_idx
+                .iter()
+                .chain(all_idx.iter())
+                .map(|idx| a[*idx].order(&b[*idx]))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+/// Check of the selected columns of an output table against a golden file in CSV format.
+#[derive(Clone, Debug)]
+pub struct TableCheck {
+    table_name: String,
+    columns: Vec<String>,
+    keys: Vec<String>,
+    tolerance: Tolerance,
+}
+
+impl TableCheck {
+    /// Check of the table with the given output file name.
+    pub fn new(table_name: &str) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+            columns: Vec::new(),
+            keys: Vec::new(),
+            tolerance: Tolerance::exact(),
+        }
+    }
+
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Columns by which the rows are sorted before they are compared.
+    pub fn keys(mut self, keys: &[&str]) -> Self {
+        self.keys = keys.iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Compares the table against the golden file and returns the differences. The golden file
+    /// is written instead when the bless variable is set.
+    pub fn compare(&self, tables: &MemoryTables, golden_file: &Path) -> Vec<String> {
+        let batches = tables
+            .read(&self.table_name)
+            .unwrap_or_else(|| panic!("Table {} is not in the output", self.table_name));
+        let mut actual = Rows::from_batches(&batches, &self.columns);
+        actual.sort(&self.keys);
+
+        if std::env::var_os(BLESS_VAR).is_some() {
+            std::fs::write(golden_file, actual.to_csv()).unwrap_or_else(|e| {
+                panic!("Failed to write {}: {}", golden_file.display(), e);
+            });
+            return Vec::new();
+        }
+        let content = std::fs::read_to_string(golden_file).unwrap_or_else(|e| {
+            panic!(
+                "Failed to read {}: {}. Set {} to create it.",
+                golden_file.display(),
+                e,
+                BLESS_VAR
+            );
+        });
+        let mut golden = Rows::from_csv(&content);
+        golden.sort(&self.keys);
+        self.differences(&actual, &golden)
+    }
+
+    /// Panics with the differences when the table does not match the golden file.
+    pub fn assert_matches(&self, tables: &MemoryTables, golden_file: &Path) {
+        let differences = self.compare(tables, golden_file);
+        if !differences.is_empty() {
+            panic!(
+                "Table {} does not match {}:\n{}",
+                self.table_name,
+                golden_file.display(),
+                differences.join("\n")
+            );
+        }
+    }
+
+    fn differences(&self, actual: &Rows, golden: &Rows) -> Vec<String> {
+        if actual.columns != golden.columns {
+            return vec![format!(
+                "columns {:?} differ from golden columns {:?}",
+                actual.columns, golden.columns
+            )];
+        }
+        let mut differences = Vec::new();
+        if actual.rows.len() != golden.rows.len() {
+            differences.push(format!(
+                "{} rows differ from {} golden rows",
+                actual.rows.len(),
+                golden.rows.len()
+            ));
+        }
+        for (idx, (row, golden_row)) in actual.rows.iter().zip(golden.rows.iter()).enumerate() {
+            for (column, (cell, golden_cell)) in row.iter().zip(golden_row.iter()).enumerate() {
+                if !cell.matches(golden_cell, &self.tolerance) {
+                    differences.push(format!(
+                        "row {}, column {}: {} differs from golden {}",
+                        idx,
+                        actual.columns[column],
+                        cell.to_text(),
+                        golden_cell.to_text()
+                    ));
+                }
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check() -> TableCheck {
+        TableCheck::new("table.parquet")
+            .columns(&["agent_id", "value"])
+            .keys(&["agent_id"])
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let tolerance = Tolerance {
+            absolute: 0.1,
+            relative: 0.01,
+        };
+        assert!(tolerance.accepts(1.05, 1.0));
+        assert!(tolerance.accepts(1005.0, 1000.0));
+        assert!(!tolerance.accepts(1.2, 1.0));
+        assert!(Tolerance::exact().accepts(2.0, 2.0));
+        assert!(!Tolerance::exact().accepts(2.0001, 2.0));
+    }
+
+    #[test]
+    fn test_sorted_comparison() {
+        let mut actual = Rows::from_csv("agent_id,value\n2,3.0\n1,5.0\n");
+        let mut golden = Rows::from_csv("agent_id,value\n1,5.0\n2,3.0\n");
+        actual.sort(&check().keys);
+        golden.sort(&check().keys);
+        assert!(check().differences(&actual, &golden).is_empty());
+    }
+
+    #[test]
+    fn test_differences() {
+        let actual = Rows::from_csv("agent_id,value\n1,5.5\n2,3.0\n");
+        let golden = Rows::from_csv("agent_id,value\n1,5.0\n2,3.0\n");
+        assert_eq!(check().differences(&actual, &golden).len(), 1);
+        let relaxed = check().tolerance(Tolerance {
+            absolute: 0.0,
+            relative: 0.2,
+        });
+        assert!(relaxed.differences(&actual, &golden).is_empty());
+
+        let shorter = Rows::from_csv("agent_id,value\n1,5.0\n");
+        assert_eq!(check().differences(&shorter, &golden).len(), 1);
+    }
+}
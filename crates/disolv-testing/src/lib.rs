@@ -0,0 +1,3 @@
+pub mod datasets;
+pub mod golden;
+pub mod scenario;
@@ -0,0 +1,225 @@
+use disolv::base::BaseConfig;
+use disolv::builder::{ScenarioInputs, SimulationBuilder};
+use disolv::population::PopulationMode;
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_core::hashbrown::HashMap;
+use disolv_core::runner::{run_for_kpis, run_headless};
+use disolv_input::links::LinkMap;
+use disolv_input::mobility::TraceMap;
+use disolv_models::bucket::digest::DigestMode;
+use disolv_models::device::mobility::{MapState, Point2D};
+use disolv_models::device::types::DeviceType;
+use disolv_models::net::radio::{DLink, LinkProperties};
+use disolv_output::writer::MemoryTables;
+use std::path::Path;
+
+/// Replaces the text in the scenario configuration. Panics when the configuration does not
+/// contain the text, so that a test does not silently run the unchanged scenario.
+pub fn patch(config: &str, from: &str, to: &str) -> String {
+    assert!(
+        config.contains(from),
+        "Scenario configuration does not contain {:?}",
+        from
+    );
+    config.replace(from, to)
+}
+
+/// Value of the KPI with the name among the KPIs reported by a run.
+pub fn kpi(kpis: &[(String, f64)], name: &str) -> f64 {
+    kpis.iter()
+        .find(|(kpi, _)| kpi == name)
+        .map(|(_, value)| *value)
+        .unwrap_or_else(|| panic!("KPI {} not reported", name))
+}
+
+/// A miniature scenario that is run fully in memory. The configuration is given as a TOML
+/// string and the input files named in it are replaced by the power schedules, positions and
+/// links added to the scenario.
+pub struct MiniScenario {
+    config: BaseConfig,
+    inputs: ScenarioInputs,
+    digest: Option<DigestMode>,
+    population: Option<PopulationMode>,
+}
+
+impl MiniScenario {
+    pub fn from_toml(config: &str) -> Self {
+        let config = BaseConfig::from_toml(config)
+            .unwrap_or_else(|e| panic!("Invalid scenario configuration: {}", e));
+        Self {
+            config,
+            inputs: ScenarioInputs::default(),
+            digest: None,
+            population: None,
+        }
+    }
+
+    pub fn duration(&self) -> TimeMS {
+        self.config.simulation_settings.duration
+    }
+
+    pub fn step_size(&self) -> TimeMS {
+        self.config.simulation_settings.step_size
+    }
+
+    /// Sets the interval at which the simulation writes its output.
+    pub fn set_output_interval(&mut self, interval: u64) {
+        self.config.output_settings.output_interval = TimeMS::from(interval);
+    }
+
+    /// Records the digest of the run, or verifies the run against a recorded digest.
+    pub fn set_digest(&mut self, mode: DigestMode) {
+        self.digest = Some(mode);
+    }
+
+    /// Writes the population built in the run, or builds the population in the file.
+    pub fn set_population(&mut self, mode: PopulationMode) {
+        self.population = Some(mode);
+    }
+
+    /// Adds an agent that is powered on at `on` and off at `off`.
+    pub fn add_agent(&mut self, device_type: DeviceType, agent_id: u64, on: u64, off: u64) {
+        self.inputs
+            .power_schedules
+            .entry(device_type)
+            .or_default()
+            .insert(
+                AgentId::from(agent_id),
+                (vec![TimeMS::from(on)], vec![TimeMS::from(off)]),
+            );
+    }
+
+    /// Places the agent at the given position for the whole simulation.
+    pub fn place(&mut self, device_type: DeviceType, agent_id: u64, x: f64, y: f64) {
+        self.move_along(device_type, agent_id, |_| {
+            Point2D::builder().x(x).y(y).build()
+        });
+    }
+
+    /// Moves the agent to the position given by `path` at every step of the simulation.
+    pub fn move_along<F>(&mut self, device_type: DeviceType, agent_id: u64, path: F)
+    where
+        F: Fn(TimeMS) -> Point2D,
+    {
+        let steps = self.steps();
+        let trace = self.inputs.traces.entry(device_type).or_default();
+        for step in steps {
+            let map_state = MapState::builder().pos(path(step)).build();
+            trace
+                .entry(step)
+                .or_default()
+                .insert(AgentId::from(agent_id), map_state);
+        }
+    }
+
+    /// Moves the agents of the type along a sampled trace, e.g. one of the embedded datasets.
+    /// The agents keep the positions of the latest sample until the next one.
+    pub fn follow_trace(&mut self, device_type: DeviceType, trace: &TraceMap) {
+        let steps = self.steps();
+        let positions = self.inputs.traces.entry(device_type).or_default();
+        for step in steps {
+            if let Some(sample) = Self::latest(trace, step) {
+                positions.entry(step).or_default().extend(sample.clone());
+            }
+        }
+    }
+
+    /// Links the agents of the source type to the agents of the target type with sampled links,
+    /// e.g. one of the embedded datasets. The links of the latest sample hold until the next one.
+    pub fn link_with(&mut self, source_type: DeviceType, target_type: DeviceType, links: &LinkMap) {
+        let mut held = HashMap::new();
+        for step in self.steps() {
+            if let Some(sample) = Self::latest(links, step) {
+                held.insert(step, sample.clone());
+            }
+        }
+        self.inputs.links.insert((source_type, target_type), held);
+    }
+
+    /// Links every agent of the source type to the agents of the target type that are within
+    /// the range at each step. Positions must be added before the links.
+    pub fn connect_within(&mut self, source_type: DeviceType, target_type: DeviceType, range: f64) {
+        let empty = HashMap::new();
+        let sources = self.inputs.traces.get(&source_type).unwrap_or(&empty);
+        let targets = self.inputs.traces.get(&target_type).unwrap_or(&empty);
+        let mut links = HashMap::new();
+        for step in self.steps() {
+            let (Some(sources), Some(targets)) = (sources.get(&step), targets.get(&step)) else {
+                continue;
+            };
+            let mut step_links: HashMap<AgentId, Vec<DLink>> = HashMap::new();
+            for (source_id, source) in sources.iter() {
+                let mut in_range: Vec<(AgentId, f64)> = targets
+                    .iter()
+                    .filter(|(target_id, _)| *target_id != source_id)
+                    .map(|(target_id, target)| (*target_id, source.slant_range(target)))
+                    .filter(|(_, distance)| *distance <= range)
+                    .collect();
+                in_range.sort_by_key(|(target_id, _)| *target_id);
+                let source_links = in_range
+                    .into_iter()
+                    .map(|(target_id, distance)| {
+                        DLink::builder()
+                            .target(target_id)
+                            .properties(LinkProperties {
+                                distance: Some(distance as f32),
+                                ..Default::default()
+                            })
+                            .build()
+                    })
+                    .collect();
+                step_links.insert(*source_id, source_links);
+            }
+            links.insert(step, step_links);
+        }
+        self.inputs.links.insert((source_type, target_type), links);
+    }
+
+    /// Runs the scenario to the end and returns the output tables.
+    pub fn run(mut self) -> MemoryTables {
+        let tables = MemoryTables::default();
+        self.config.output_settings.memory = Some(tables.clone());
+        run_headless(self.builder().build_with_map());
+        tables
+    }
+
+    /// Runs the scenario to the end and returns the KPIs at the end of the run, in the fast mode
+    /// when `fast` is set.
+    pub fn run_for_kpis(mut self, fast: bool) -> Vec<(String, f64)> {
+        self.config.output_settings.memory = Some(MemoryTables::default());
+        let mut builder = match fast {
+            true => self.builder().with_fast_mode(),
+            false => self.builder(),
+        };
+        run_for_kpis(builder.build_with_map())
+    }
+
+    fn builder(self) -> SimulationBuilder {
+        let mut builder = SimulationBuilder::with_config(self.config, Path::new("."), "memory")
+            .with_inputs(self.inputs)
+            .without_logging();
+        if let Some(mode) = self.digest {
+            builder = builder.with_digest(mode);
+        }
+        if let Some(mode) = self.population {
+            builder = builder.with_population(mode);
+        }
+        builder
+    }
+
+    fn latest<T>(samples: &HashMap<TimeMS, T>, step: TimeMS) -> Option<&T> {
+        samples
+            .iter()
+            .filter(|(time, _)| **time <= step)
+            .max_by_key(|(time, _)| **time)
+            .map(|(_, sample)| sample)
+    }
+
+    fn steps(&self) -> Vec<TimeMS> {
+        (0..=self.duration().as_u64())
+            .step_by(self.step_size().as_u64() as usize)
+            .map(TimeMS::from)
+            .collect()
+    }
+}
@@ -0,0 +1,60 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A building between the lane at y = 90 and the first RSU blocks the links of the vehicles in
+/// that lane until they pass the RSU. A storm from 8 s makes every remaining link lossy and
+/// blocks the links longer than 45 m.
+const ATTENUATION: &str = r#"
+[network_settings.attenuation]
+loss_per_db = 2.0
+max_attenuation = 45.0
+obstacles = [
+    { vertices = [[150.0, 92.0], [250.0, 92.0], [250.0, 98.0], [150.0, 98.0]], loss = 50.0 },
+]
+weather = [{ start = 8000, loss_per_km = 1000.0 }]
+"#;
+
+/// Four vehicles driving along a highway past two RSUs, with one vehicle leaving half way.
+fn highway_with_attenuation() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "[network_settings.age_of_information]",
+        &format!("{}\n[network_settings.age_of_information]", ATTENUATION),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    scenario.place(DeviceType::RSU, 101, 750.0, 100.0);
+    for vehicle in 0..4u64 {
+        let off = if vehicle == 3 { end / 2 } else { end };
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, off);
+        let speed = 20.0 + 10.0 * vehicle as f64;
+        let lane = 90.0 + 10.0 * (vehicle % 2) as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = speed * step.as_u64() as f64 / 1000.0;
+            Point2D::builder().x(x).y(lane).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_obstacle_and_weather_attenuation() {
+    let tables = highway_with_attenuation().run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "tx_status",
+            "tx_fail_reason",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("attenuation_tx_data.csv"));
+}
@@ -0,0 +1,51 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::scenario::{kpi, patch, MiniScenario};
+
+/// The RSU is half busy with the background load for the first half second and idle after.
+const TRACE: &str = "time_step,agent_id,utilization\n0,100,0.5\n500,100,0.0\n";
+
+/// Four vehicles sending to an RSU for a second, which serves four payloads in a step and queues
+/// at most four more.
+fn shared_edge(trace_name: &str) -> MiniScenario {
+    let trace_file = std::env::temp_dir().join(trace_name);
+    std::fs::write(&trace_file, TRACE).expect("utilization trace is written");
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "agent_class = \"RSU5G\"\nagent_order = 1",
+        &format!(
+            "agent_class = \"RSU5G\"\nagent_order = 1\nprocessor = {{ name = \"fifo\", \
+                 service_rate = 4, queue_length = 4, utilization_trace = \"{}\" }}",
+            trace_file.display()
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..4u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(
+            DeviceType::Vehicle,
+            vehicle,
+            50.0 + 10.0 * vehicle as f64,
+            90.0,
+        );
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// Half of the service rate is withheld in the first five steps. The two payloads the RSU cannot
+/// serve in a step fill its queue, which drops two payloads in every step until the load is gone.
+#[test]
+fn test_background_load_slows_the_server() {
+    let kpis = shared_edge("disolv_background_load.csv").run_for_kpis(true);
+    assert_eq!(kpi(&kpis, "tx_attempted"), 36.0);
+    assert_eq!(kpi(&kpis, "background_slots_withheld"), 10.0);
+    assert_eq!(kpi(&kpis, "background_queue_overflows"), 8.0);
+}
@@ -0,0 +1,65 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::scenario::{kpi, patch, MiniScenario};
+
+/// Four vehicles sending 300 bytes to an RSU in every step but the first over a slice with the
+/// capacity in bytes per second, so that 10800 bytes are offered to the slice in a second.
+fn loaded_slice(capacity: u64, tolerance: f64) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "seed = 42",
+        &format!("seed = 42\nbaseline = {{ tolerance = {} }}", tolerance),
+    );
+    let config = patch(
+        &config,
+        "bandwidth = { variant = \"constant\" }",
+        &format!(
+            "bandwidth = {{ variant = \"constant\" }}\ncapacity = {}",
+            capacity
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..4u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(
+            DeviceType::Vehicle,
+            vehicle,
+            50.0 + 10.0 * vehicle as f64,
+            90.0,
+        );
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// An underloaded slice carries all the offered bytes, as expected.
+#[test]
+fn test_underloaded_slice_matches_baseline() {
+    let kpis = loaded_slice(20000, 0.01).run_for_kpis(true);
+    assert!((kpi(&kpis, "slice_utilization_expected") - 0.54).abs() < 1e-9);
+    assert!((kpi(&kpis, "slice_utilization") - 0.54).abs() < 1e-9);
+    assert_eq!(kpi(&kpis, "slice_delivery_ratio"), 1.0);
+    assert_eq!(kpi(&kpis, "baseline_mismatches"), 0.0);
+}
+
+/// An overloaded slice only fits three whole payloads in a step and leaves the first step
+/// unused, while the fluid model expects it to be used fully.
+#[test]
+fn test_overloaded_slice_is_flagged() {
+    let kpis = loaded_slice(10000, 0.1).run_for_kpis(true);
+    assert_eq!(kpi(&kpis, "slice_utilization_expected"), 1.0);
+    assert!((kpi(&kpis, "slice_utilization") - 0.81).abs() < 1e-9);
+    assert!((kpi(&kpis, "slice_delivery_ratio_expected") - 1.0 / 1.08).abs() < 1e-9);
+    assert!((kpi(&kpis, "slice_delivery_ratio") - 0.75).abs() < 1e-9);
+    assert_eq!(kpi(&kpis, "baseline_mismatches"), 2.0);
+
+    let kpis = loaded_slice(10000, 0.25).run_for_kpis(true);
+    assert_eq!(kpi(&kpis, "baseline_mismatches"), 0.0);
+}
@@ -0,0 +1,93 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const RSU_BROADCAST: &str = r#"composer = { name = "basic", source_settings = [
+    { data_type = "CPM", agent_class = "Vehicle5G", data_size = 500, source_step = 100 },
+] }
+actions = [
+    { target = "Vehicle5G", data_type = "CPM", action_type = "Consume" },
+]
+broadcast = { target_class = "Vehicle5G", coverage = 300.0, reliable_range = 100.0 }"#;
+
+/// An RSU broadcasting to four parked vehicles for a second. The first two vehicles are within
+/// the reliable range, the third one is close to the edge of the coverage and the last one is
+/// out of it.
+fn broadcasting_highway() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "mobility = { mobility_type = \"Stationery\", is_streaming = false, trace_file = \"memory\" }",
+        "mobility = { mobility_type = \"Stationery\", is_streaming = false, trace_file = \"memory\" }\nlinker = [\n    { target_type = \"Vehicle\", links_file = \"memory\", range = 400.0, is_streaming = true },\n]",
+    );
+    let config = patch(
+        &config,
+        "composer = { name = \"basic\", source_settings = [] }",
+        RSU_BROADCAST,
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"BroadcastReception\", output_filename = \"broadcast.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for (id, x) in [(0, 150.0), (1, 180.0), (2, 370.0), (3, 450.0)] {
+        scenario.add_agent(DeviceType::Vehicle, id, 0, end);
+        scenario.move_along(DeviceType::Vehicle, id, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 400.0);
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_broadcast_reception() {
+    let tables = broadcasting_highway().run();
+    TableCheck::new("broadcast.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "target_class",
+            "tx_status",
+            "covered",
+            "received",
+            "reception_ratio",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("broadcast_reception.csv"));
+}
+
+#[test]
+fn test_broadcast_is_sent_once() {
+    let tables = broadcasting_highway().run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "selected_agent"])
+        .keys(&["time_step", "agent_id", "selected_agent"])
+        .assert_matches(&tables, &golden_file("broadcast_tx_data.csv"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid broadcast reliable range 400")]
+fn test_reliable_range_beyond_coverage_is_rejected() {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "composer = { name = \"basic\", source_settings = [] }",
+        "composer = { name = \"basic\", source_settings = [] }\nbroadcast = { target_class = \"Vehicle5G\", coverage = 300.0, reliable_range = 400.0 }",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    scenario.add_agent(DeviceType::RSU, 100, 0, 1000);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, 1000);
+    scenario.run();
+}
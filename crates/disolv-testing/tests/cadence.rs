@@ -0,0 +1,56 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{kpi, patch, MiniScenario};
+
+/// Three vehicles next to an RSU for a second, with the cadences of the vehicles and the RSU.
+fn paced_highway(vehicle_cadence: &str, rsu_cadence: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "agent_order = 0\n",
+        &format!("agent_order = 0\ncadence = {}\n", vehicle_cadence),
+    );
+    let config = patch(
+        &config,
+        "agent_order = 1\n",
+        &format!("agent_order = 1\ncadence = {}\n", rsu_cadence),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..3 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(DeviceType::Vehicle, vehicle, 50.0 * vehicle as f64, 90.0);
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_rsu_acts_every_fifth_step() {
+    let kpis = paced_highway(
+        "{ policy = \"probabilistic\", probability = 1.0 }",
+        "{ policy = \"every\", interval = 5, offset = 1 }",
+    )
+    .run_for_kpis(true);
+    assert_eq!(kpi(&kpis, "agent_steps_acted"), 3.0 * 10.0 + 2.0);
+    assert_eq!(kpi(&kpis, "agent_steps_skipped"), 8.0);
+}
+
+#[test]
+fn test_vehicles_take_turns() {
+    let tables = paced_highway(
+        "{ policy = \"round_robin\", interval = 3 }",
+        "{ policy = \"every\" }",
+    )
+    .run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "selected_agent"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("cadence_round_robin_tx_data.csv"));
+}
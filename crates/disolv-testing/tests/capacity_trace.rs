@@ -0,0 +1,71 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// The slice carries three payloads per step until the trace doubles its capacity at half a
+/// second.
+const TRACE: &str = "time_step,slice_id,capacity\n0,0,10000\n500,0,20000\n";
+
+/// Four vehicles next to an RSU sharing a slice whose capacity follows the trace in the file.
+fn traced_contention(trace_name: &str, interpolate: bool) -> MiniScenario {
+    let trace_file = std::env::temp_dir().join(trace_name);
+    std::fs::write(&trace_file, TRACE).expect("trace is written");
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\ncapacity = 10000",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"NetStat\", output_filename = \"net_stats.parquet\" },",
+    );
+    let config = format!(
+        "{}\n[network_settings.capacity_trace]\ntrace_file = \"{}\"\ninterpolate = {}\n",
+        config,
+        trace_file.display(),
+        interpolate
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..4u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let x = 50.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(90.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn capacity_check() -> TableCheck {
+    TableCheck::new("net_stats.parquet")
+        .columns(&["time_step", "slice_id", "capacity"])
+        .keys(&["time_step", "slice_id"])
+}
+
+#[test]
+fn test_capacity_steps_between_entries() {
+    let tables = traced_contention("disolv_capacity_step.csv", false).run();
+    capacity_check().assert_matches(&tables, &golden_file("capacity_trace_step.csv"));
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "tx_status", "latency"])
+        .keys(&["time_step", "tx_status", "latency"])
+        .assert_matches(&tables, &golden_file("capacity_trace_tx_data.csv"));
+}
+
+#[test]
+fn test_capacity_interpolates_between_entries() {
+    let tables = traced_contention("disolv_capacity_interpolated.csv", true).run();
+    capacity_check().assert_matches(&tables, &golden_file("capacity_trace_interpolated.csv"));
+}
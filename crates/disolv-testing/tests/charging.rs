@@ -0,0 +1,70 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Three electric vehicles whose batteries run low at the same time next to an RSU that acts
+/// as a charging station with a single slot, so that the vehicles take turns to charge.
+fn single_slot_station() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 3000",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"StationOccupancy\", output_filename = \"occupancy.parquet\" },\n    { output_type = \"ChargingSessions\", output_filename = \"sessions.parquet\" },",
+    );
+    let config = patch(
+        &config,
+        "    { target = \"RSU5G\", data_type = \"CAM\", action_type = \"Consume\" },\n]",
+        "    { target = \"RSU5G\", data_type = \"CAM\", action_type = \"Consume\" },\n    { target = \"RSU5G\", data_type = \"Reservation\", action_type = \"Consume\" },\n]\nbattery = { capacity = 1000, initial_level = 0.5, drive_drain = 20, seek_below = 0.4 }",
+    );
+    let config = patch(
+        &config,
+        "agent_order = 1\n",
+        "agent_order = 1\nstation = { slots = 1, charge_rate = 100 }\n",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..3 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(DeviceType::Vehicle, vehicle, 50.0 * vehicle as f64, 90.0);
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_station_grants_its_slot_in_turns() {
+    let tables = single_slot_station().run();
+    TableCheck::new("occupancy.parquet")
+        .columns(&[
+            "time_step",
+            "station_id",
+            "slots",
+            "occupied",
+            "granted",
+            "denied",
+        ])
+        .keys(&["time_step", "station_id"])
+        .assert_matches(&tables, &golden_file("charging_occupancy.csv"));
+}
+
+#[test]
+fn test_sessions_are_written_when_vehicles_leave() {
+    let tables = single_slot_station().run();
+    TableCheck::new("sessions.parquet")
+        .columns(&[
+            "time_step",
+            "vehicle_id",
+            "station_id",
+            "start",
+            "duration",
+            "energy",
+        ])
+        .keys(&["time_step", "vehicle_id"])
+        .assert_matches(&tables, &golden_file("charging_sessions.csv"));
+}
@@ -0,0 +1,67 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Four vehicles next to an RSU sharing a slice that carries three of their payloads per step.
+fn contention(sub_steps: u32) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "bandwidth = { variant = \"constant\" }",
+        &format!(
+            "bandwidth = {{ variant = \"constant\" }}\ncapacity = 10000\nsub_steps = {}",
+            sub_steps
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..4u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let x = 50.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(90.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// Transfers of the agents with the same order may be served in any order, so only the
+/// latencies of the transfers in each step are compared.
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "tx_status", "latency"])
+        .keys(&["time_step", "tx_status", "latency"])
+}
+
+#[test]
+fn test_contention_in_one_step() {
+    let tables = contention(1).run();
+    check().assert_matches(&tables, &golden_file("contention_one_step.csv"));
+}
+
+#[test]
+fn test_contention_in_sub_steps() {
+    let tables = contention(10).run();
+    check().assert_matches(&tables, &golden_file("contention_sub_steps.csv"));
+}
+
+/// One of the four vehicles is not served in every step. Which one depends on the order of the
+/// transfers, so only the totals of the class are compared.
+#[test]
+fn test_contention_starvation() {
+    let tables = contention(1).run();
+    TableCheck::new("fairness.parquet")
+        .columns(&[
+            "time_step",
+            "device_class",
+            "agent_count",
+            "delivered_bytes",
+            "starved_steps",
+        ])
+        .keys(&["time_step", "device_class"])
+        .assert_matches(&tables, &golden_file("contention_starvation.csv"));
+}
@@ -0,0 +1,56 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{kpi, patch, MiniScenario};
+
+/// The first 200 m of the highway are covered, the next 200 m are a gap.
+const GRID: &str = "1,0\n";
+
+/// An RSU and two vehicles for a second. The first vehicle stays next to the RSU, while the
+/// second drives into the gap at half a second.
+fn rural_highway(grid_name: &str) -> MiniScenario {
+    let coverage_file = std::env::temp_dir().join(grid_name);
+    std::fs::write(&coverage_file, GRID).expect("coverage grid is written");
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = format!(
+        "{}\n[network_settings.coverage]\ncoverage_file = \"{}\"\ncell_size = 200.0\n",
+        config,
+        coverage_file.display()
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.place(DeviceType::Vehicle, 0, 50.0, 90.0);
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 1, |time_step: TimeMS| {
+        Point2D::builder()
+            .x(0.4 * time_step.as_u64() as f64)
+            .y(90.0)
+            .build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// The second vehicle stops sending to the RSU once it is in the gap.
+#[test]
+fn test_gap_cuts_off_the_infrastructure() {
+    let tables = rural_highway("disolv_coverage_tx.csv").run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "tx_status"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("coverage_tx_data.csv"));
+}
+
+#[test]
+fn test_gap_links_are_counted() {
+    let kpis = rural_highway("disolv_coverage_kpis.csv").run_for_kpis(true);
+    assert_eq!(kpi(&kpis, "links_in_coverage_gap"), 5.0);
+}
@@ -0,0 +1,101 @@
+use arrow::array::{Array, UInt64Array};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_input::links::LinkReader;
+use disolv_input::mobility::MapReader;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::datasets::{
+    read_links, read_trace, write_links_file, write_trace_file, TOY_LINKS, TOY_TRACE,
+};
+use disolv_testing::scenario::MiniScenario;
+use std::path::PathBuf;
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("disolv_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_mapper_reads_the_toy_trace() {
+    let trace_file = temp_file("toy_trace.parquet");
+    write_trace_file(TOY_TRACE, &trace_file);
+    let read = MapReader::builder()
+        .is_streaming(false)
+        .file_path(trace_file.clone())
+        .streaming_step(TimeMS::from(10000))
+        .build()
+        .fetch_traffic_data(TimeMS::default());
+    std::fs::remove_file(&trace_file).expect("Failed to remove the trace file");
+
+    let expected = read_trace(TOY_TRACE);
+    assert_eq!(read.len(), 11);
+    for (time_step, positions) in expected.iter() {
+        let read_positions = read.get(time_step).expect("Sample is not read");
+        assert_eq!(read_positions.len(), 3);
+        for (agent_id, map_state) in positions.iter() {
+            let read_state = read_positions.get(agent_id).expect("Agent is not read");
+            assert_eq!(read_state.pos.x, map_state.pos.x);
+            assert_eq!(read_state.pos.y, map_state.pos.y);
+        }
+    }
+}
+
+#[test]
+fn test_linker_reads_the_toy_links() {
+    let links_file = temp_file("toy_links.parquet");
+    write_links_file(TOY_LINKS, &links_file);
+    let read = LinkReader::builder()
+        .is_streaming(false)
+        .file_path(links_file.clone())
+        .streaming_step(TimeMS::from(10000))
+        .build()
+        .fetch_links_data(TimeMS::default());
+    std::fs::remove_file(&links_file).expect("Failed to remove the links file");
+
+    let expected = read_links(TOY_LINKS);
+    assert_eq!(read.len(), expected.len());
+    for (time_step, links) in expected.iter() {
+        let read_links = read.get(time_step).expect("Sample is not read");
+        for (agent_id, agent_links) in links.iter() {
+            let read_agent_links = read_links.get(agent_id).expect("Agent is not read");
+            assert_eq!(read_agent_links.len(), agent_links.len());
+            assert_eq!(read_agent_links[0].target, AgentId::from(100));
+            assert_eq!(
+                read_agent_links[0].properties.distance,
+                agent_links[0].properties.distance
+            );
+        }
+    }
+}
+
+#[test]
+fn test_toy_scenario_sends_in_range() {
+    let mut scenario = MiniScenario::from_toml(include_str!("scenarios/highway.toml"));
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 500.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+    }
+    scenario.follow_trace(DeviceType::Vehicle, &read_trace(TOY_TRACE));
+    scenario.link_with(DeviceType::Vehicle, DeviceType::RSU, &read_links(TOY_LINKS));
+    let tables = scenario.run();
+
+    let time_steps: Vec<u64> = tables
+        .read("tx_data.parquet")
+        .expect("Transfers are not written")
+        .iter()
+        .flat_map(|batch| {
+            let values = batch
+                .column_by_name("time_step")
+                .expect("Column is missing")
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .expect("Column is not u64")
+                .clone();
+            (0..values.len()).map(move |row| values.value(row))
+        })
+        .collect();
+    // The fastest vehicle comes within 300 m of the RSU at 3 s.
+    assert!(!time_steps.is_empty());
+    assert!(time_steps.iter().all(|time_step| *time_step >= 3000));
+}
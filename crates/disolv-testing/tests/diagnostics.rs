@@ -0,0 +1,128 @@
+use arrow::array::{Array, UInt32Array, UInt64Array};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::writer::MemoryTables;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Memory sampled every second. The data lake is cleaned when it holds more than 2 payloads
+/// and the output buffers are only watched.
+const DIAGNOSTICS: &str = r#"
+[simulation_settings.diagnostics]
+interval = 1000
+caps = [
+    { subsystem = "DataLake", max_entries = 2, action = "Clean" },
+    { subsystem = "OutputBuffers", max_entries = 100 },
+]
+"#;
+
+const LAKE: &str = r#"
+[network_settings.lake]
+ttl = 10000
+"#;
+
+/// Three vehicles sending to an RSU that receives before them, so that their payloads wait in
+/// the data lake until the next step.
+fn waiting_payloads() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "[network_settings.age_of_information]",
+        &format!("{}\n[network_settings.age_of_information]", LAKE),
+    );
+    let config = patch(
+        &config,
+        "agent_class = \"Vehicle5G\"\nagent_order = 0",
+        "agent_class = \"Vehicle5G\"\nagent_order = 2",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"Memory\", output_filename = \"memory.parquet\" },",
+    );
+    let config = format!("{}\n{}", DIAGNOSTICS, config);
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let x = 200.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn rows(tables: &MemoryTables) -> Vec<(u64, u32, u64, u32)> {
+    let batches = tables
+        .read("memory.parquet")
+        .expect("Memory is not written");
+    let mut rows = Vec::new();
+    for batch in batches.iter() {
+        let column = |name: &str| batch.column_by_name(name).expect("Column is missing");
+        let time_step = column("time_step")
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .expect("Column is not u64")
+            .clone();
+        let subsystem = column("subsystem")
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .expect("Column is not u32")
+            .clone();
+        let entries = column("entries")
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .expect("Column is not u64")
+            .clone();
+        let over_cap = column("over_cap")
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .expect("Column is not u32")
+            .clone();
+        for row in 0..batch.num_rows() {
+            rows.push((
+                time_step.value(row),
+                subsystem.value(row),
+                entries.value(row),
+                over_cap.value(row),
+            ));
+        }
+    }
+    rows
+}
+
+#[test]
+fn test_memory_is_sampled() {
+    let tables = waiting_payloads().run();
+    // Byte estimates depend on the layout of the types, so only the entries are compared.
+    TableCheck::new("memory.parquet")
+        .columns(&["time_step", "subsystem", "entries", "over_cap"])
+        .keys(&["time_step", "subsystem"])
+        .assert_matches(&tables, &golden_file("memory.csv"));
+}
+
+#[test]
+fn test_data_lake_is_cleaned_over_cap() {
+    let rows = rows(&waiting_payloads().run());
+    let entries_of = |time_step: u64, subsystem: u32| {
+        rows.iter()
+            .find(|row| row.0 == time_step && row.1 == subsystem)
+            .map(|row| (row.2, row.3))
+            .expect("Subsystem is not sampled")
+    };
+    let mut cleaned = 0;
+    for time_step in (1000..10000).step_by(1000) {
+        let (lake, over_cap) = entries_of(time_step, 0);
+        let (expired, _) = entries_of(time_step, 1);
+        if over_cap == 1 {
+            // The payloads cleaned from the lake are handed back to their senders as expired.
+            assert_eq!(expired, lake);
+            cleaned += 1;
+        }
+    }
+    assert!(cleaned > 0);
+}
@@ -0,0 +1,62 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::bucket::digest::DigestMode;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::scenario::MiniScenario;
+use std::path::PathBuf;
+
+/// Two vehicles relaying their CAMs through an RSU. The second vehicle drives out of the range
+/// of the RSU at the given time.
+fn relay(leaves_at: u64) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(include_str!("scenarios/relay.toml"));
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.place(DeviceType::Vehicle, 0, 50.0, 90.0);
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 1, move |step: TimeMS| {
+        let x = match step.as_u64() < leaves_at {
+            true => 150.0,
+            false => 900.0,
+        };
+        Point2D::builder().x(x).y(90.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario
+}
+
+fn digest_file(name: &str) -> PathBuf {
+    let digest_file = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&digest_file);
+    digest_file
+}
+
+fn recorded(name: &str) -> PathBuf {
+    let digest_file = digest_file(name);
+    let mut scenario = relay(1000);
+    scenario.set_digest(DigestMode::Record(digest_file.clone()));
+    scenario.run();
+    digest_file
+}
+
+#[test]
+fn test_same_run_matches_the_recorded_digest() {
+    let digest_file = recorded("disolv_digest_same.csv");
+    let content = std::fs::read_to_string(&digest_file).expect("digest is recorded");
+    assert_eq!(content.lines().count(), 11);
+
+    let mut scenario = relay(1000);
+    scenario.set_digest(DigestMode::Verify(digest_file));
+    scenario.run();
+}
+
+#[test]
+#[should_panic(expected = "Digest mismatch at step 500")]
+fn test_diverged_run_fails_at_the_first_different_step() {
+    let digest_file = recorded("disolv_digest_diverged.csv");
+    let mut scenario = relay(500);
+    scenario.set_digest(DigestMode::Verify(digest_file));
+    scenario.run();
+}
@@ -0,0 +1,73 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::writer::MemoryTables;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::MiniScenario;
+use std::net::TcpListener;
+use std::thread;
+
+/// The highway split into four regions of 250 m. Every vehicle starts in a region of its own,
+/// and the second and third vehicles are handed over to the next region while driving.
+fn highway_region(region: u32, addresses: &[String]) -> MiniScenario {
+    let config = format!(
+        "{}\n[distributed]\nregion = {}\naddresses = {:?}\nconnect_timeout = 10\n",
+        include_str!("scenarios/highway.toml"),
+        region,
+        addresses
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    scenario.place(DeviceType::RSU, 101, 750.0, 100.0);
+    for vehicle in 0..4u64 {
+        let off = if vehicle == 3 { end / 2 } else { end };
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, off);
+        let start = 50.0 + 250.0 * vehicle as f64;
+        let speed = 20.0 + 5.0 * vehicle as f64;
+        let lane = 90.0 + 10.0 * (vehicle % 2) as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = start + speed * step.as_u64() as f64 / 1000.0;
+            Point2D::builder().x(x).y(lane).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn free_address() -> String {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to find a free port")
+        .to_string()
+}
+
+fn run_regions(regions: u32) -> Vec<MemoryTables> {
+    let addresses: Vec<String> = (0..regions).map(|_| free_address()).collect();
+    let handles: Vec<_> = (0..regions)
+        .map(|region| {
+            let scenario = highway_region(region, &addresses);
+            thread::spawn(move || scenario.run())
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|handle| handle.join().expect("Region failed"))
+        .collect()
+}
+
+#[test]
+fn test_regions_split_the_transfers() {
+    let tables = run_regions(4);
+    for (region, tables) in tables.iter().enumerate() {
+        TableCheck::new("tx_data.parquet")
+            .columns(&["time_step", "agent_id", "selected_agent", "tx_status"])
+            .keys(&["time_step", "agent_id"])
+            .assert_matches(
+                tables,
+                &golden_file(&format!("distributed_region_{}_tx_data.csv", region)),
+            );
+    }
+}
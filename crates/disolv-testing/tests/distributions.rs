@@ -0,0 +1,255 @@
+use disolv_core::metrics::{Feasibility, Measurable};
+use disolv_models::dist::{DistParams, EmpiricalCdf, RngSampler, SeedRegistry};
+use disolv_models::net::latency::{Jitter, LatencyConfig, LatencyType};
+use disolv_models::net::message::{PayloadInfo, TxMetrics};
+use disolv_models::net::metrics::Latency;
+
+const SAMPLES: usize = 100_000;
+
+fn params(dist_name: &str) -> DistParams {
+    DistParams {
+        dist_name: dist_name.to_string(),
+        seed: Some(7),
+        mean: None,
+        std_dev: None,
+        location: None,
+        scale: None,
+        shape: None,
+        rate: None,
+        alpha: None,
+        beta: None,
+        min: None,
+        max: None,
+        cdf_file: None,
+    }
+}
+
+/// Mean and variance of the samples drawn with the parameters.
+fn moments(params: DistParams) -> (f64, f64, Vec<f32>) {
+    let mut sampler = RngSampler::new(params);
+    let samples: Vec<f32> = (0..SAMPLES).map(|_| sampler.sample()).collect();
+    let mean = samples.iter().map(|x| *x as f64).sum::<f64>() / SAMPLES as f64;
+    let variance = samples
+        .iter()
+        .map(|x| (*x as f64 - mean).powi(2))
+        .sum::<f64>()
+        / SAMPLES as f64;
+    (mean, variance, samples)
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{} is not within {} of {}",
+        actual,
+        tolerance,
+        expected
+    );
+}
+
+#[test]
+fn test_beta_moments() {
+    let mut beta = params("beta");
+    beta.alpha = Some(2.0);
+    beta.beta = Some(5.0);
+    beta.location = Some(10.0);
+    beta.scale = Some(7.0);
+    let (mean, variance, samples) = moments(beta);
+    // Beta(2, 5) has a mean of 2/7 and a variance of 10/392, stretched by 7 and moved by 10.
+    assert_close(mean, 12.0, 0.02);
+    assert_close(variance, 49.0 * 10.0 / 392.0, 0.02);
+    assert!(samples.iter().all(|x| (10.0..=17.0).contains(x)));
+}
+
+#[test]
+fn test_pareto_moments() {
+    let mut pareto = params("pareto");
+    pareto.scale = Some(2.0);
+    pareto.shape = Some(5.0);
+    let (mean, variance, samples) = moments(pareto);
+    // Mean is shape * scale / (shape - 1) and variance scale^2 * shape / ((shape - 1)^2 (shape - 2)).
+    assert_close(mean, 2.5, 0.01);
+    assert_close(variance, 4.0 * 5.0 / (16.0 * 3.0), 0.03);
+    assert!(samples.iter().all(|x| *x >= 2.0));
+}
+
+#[test]
+fn test_truncated_normal_moments() {
+    let mut truncated = params("truncated_normal");
+    truncated.mean = Some(20.0);
+    truncated.std_dev = Some(4.0);
+    truncated.min = Some(16.0);
+    truncated.max = Some(28.0);
+    let (mean, variance, samples) = moments(truncated);
+    // Standard normal truncated to [-1, 2], scaled by the standard deviation.
+    let pdf = |x: f64| (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    let mass = 0.977250 - 0.158655;
+    let shift = (pdf(-1.0) - pdf(2.0)) / mass;
+    let spread = 1.0 + (-pdf(-1.0) - 2.0 * pdf(2.0)) / mass - shift * shift;
+    assert_close(mean, 20.0 + 4.0 * shift, 0.03);
+    assert_close(variance, 16.0 * spread, 0.1);
+    assert!(samples.iter().all(|x| (16.0..=28.0).contains(x)));
+}
+
+#[test]
+fn test_empirical_moments() {
+    let cdf_file = std::env::temp_dir().join("disolv_uniform_cdf.csv");
+    std::fs::write(&cdf_file, "value,probability\n0,0\n10,1\n").expect("write the CDF file");
+    let mut empirical = params("empirical");
+    empirical.cdf_file = Some(cdf_file.to_string_lossy().to_string());
+    let (mean, variance, _) = moments(empirical);
+    assert_close(mean, 5.0, 0.05);
+    assert_close(variance, 100.0 / 12.0, 0.1);
+}
+
+#[test]
+fn test_weibull_moments() {
+    let mut weibull = params("weibull");
+    weibull.scale = Some(3.0);
+    weibull.shape = Some(2.0);
+    let (mean, variance, samples) = moments(weibull);
+    // With shape 2, the mean is scale * sqrt(pi) / 2 and the variance scale^2 * (1 - pi / 4).
+    let pi = std::f64::consts::PI;
+    assert_close(mean, 3.0 * pi.sqrt() / 2.0, 0.02);
+    assert_close(variance, 9.0 * (1.0 - pi / 4.0), 0.03);
+    assert!(samples.iter().all(|x| *x >= 0.0));
+}
+
+#[test]
+fn test_empirical_quantiles() {
+    let cdf_file = std::env::temp_dir().join("disolv_skewed_cdf.csv");
+    std::fs::write(&cdf_file, "value,probability\n0,0\n10,0.5\n100,1\n")
+        .expect("write the CDF file");
+    let mut empirical = params("empirical");
+    empirical.cdf_file = Some(cdf_file.to_string_lossy().to_string());
+    let (_, _, mut samples) = moments(empirical);
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let quantile = |q: f64| samples[(q * SAMPLES as f64) as usize] as f64;
+    // Half of the samples are spread over 0-10 and the other half over 10-100.
+    assert_close(quantile(0.25), 5.0, 0.2);
+    assert_close(quantile(0.5), 10.0, 1.0);
+    assert_close(quantile(0.75), 55.0, 1.0);
+    assert!(samples.iter().all(|x| (0.0..=100.0).contains(x)));
+}
+
+#[test]
+fn test_malformed_cdf_files_are_rejected() {
+    let malformed = [
+        ("disolv_unsorted_cdf.csv", "10,0.2\n5,0.6\n20,1\n"),
+        ("disolv_decreasing_cdf.csv", "0,0.5\n10,0.2\n"),
+        ("disolv_empty_cdf.csv", "value,probability\n"),
+    ];
+    for (name, content) in malformed {
+        let cdf_file = std::env::temp_dir().join(name);
+        std::fs::write(&cdf_file, content).expect("write the CDF file");
+        assert!(
+            EmpiricalCdf::read(&cdf_file).is_err(),
+            "{} is accepted",
+            name
+        );
+    }
+    let missing = std::env::temp_dir().join("disolv_missing_cdf.csv");
+    assert!(EmpiricalCdf::read(&missing).is_err());
+}
+
+fn latency_config(variant: &str) -> LatencyConfig {
+    LatencyConfig {
+        variant: variant.to_string(),
+        constraint: Latency::new(100),
+        constant_term: None,
+        min_latency: None,
+        max_latency: None,
+        factor: None,
+        dist_params: None,
+        jitter: None,
+        max_jitter: None,
+    }
+}
+
+fn latency_of(feasibility: Feasibility<Latency>) -> (bool, u64) {
+    match feasibility {
+        Feasibility::Feasible(latency) => (true, latency.as_u64()),
+        Feasibility::Infeasible(latency) => (false, latency.as_u64()),
+    }
+}
+
+#[test]
+fn test_random_latency_is_sampled_within_bounds() {
+    let mut normal = params("normal");
+    normal.mean = Some(20.0);
+    normal.std_dev = Some(5.0);
+    let mut config = latency_config("random");
+    config.min_latency = Some(Latency::new(10));
+    config.max_latency = Some(Latency::new(25));
+    config.dist_params = Some(normal);
+    let mut latency = LatencyType::with_settings(&config);
+
+    let latencies: Vec<u64> = (0..SAMPLES)
+        .map(|_| latency.measure(&TxMetrics::default(), &PayloadInfo::default()))
+        .map(|feasibility| latency_of(feasibility).1)
+        .collect();
+    let mean = latencies.iter().sum::<u64>() as f64 / SAMPLES as f64;
+    assert!(latencies.iter().all(|x| (10..=25).contains(x)));
+    assert!(latencies.contains(&10) && latencies.contains(&25));
+    // Clamping cuts more of the upper tail than of the lower one.
+    assert_close(mean, 19.63, 0.1);
+}
+
+#[test]
+fn test_jitter_is_bounded_and_checked_against_constraint() {
+    let mut uniform = params("uniform");
+    uniform.min = Some(-20.0);
+    uniform.max = Some(20.0);
+    let mut config = latency_config("constant");
+    config.jitter = Some(uniform);
+    config.max_jitter = Some(Latency::new(5));
+    let mut jitter = Jitter::with_settings(&config).expect("jitter is configured");
+
+    let jittered: Vec<(bool, u64)> = (0..SAMPLES)
+        .map(|_| latency_of(jitter.apply(Feasibility::Feasible(Latency::new(50)))))
+        .collect();
+    assert!(jittered
+        .iter()
+        .all(|(feasible, x)| *feasible && (45..=55).contains(x)));
+    let mean = jittered.iter().map(|(_, x)| *x).sum::<u64>() as f64 / SAMPLES as f64;
+    assert_close(mean, 50.0, 0.1);
+
+    let near_constraint: Vec<(bool, u64)> = (0..1000)
+        .map(|_| latency_of(jitter.apply(Feasibility::Feasible(Latency::new(98)))))
+        .collect();
+    assert!(near_constraint.iter().any(|(feasible, _)| !feasible));
+    assert!(near_constraint
+        .iter()
+        .all(|(feasible, x)| *feasible == (*x <= 100)));
+    assert_eq!(
+        latency_of(jitter.apply(Feasibility::Infeasible(Latency::new(150)))),
+        (false, 150)
+    );
+    assert!(Jitter::with_settings(&latency_config("constant")).is_none());
+}
+
+#[test]
+fn test_seed_streams() {
+    let seeds = SeedRegistry::new(42);
+    assert_eq!(
+        seeds.seed_for("latency"),
+        SeedRegistry::new(42).seed_for("latency")
+    );
+    assert_ne!(seeds.seed_for("latency"), seeds.seed_for("jitter"));
+    assert_ne!(
+        seeds.seed_for("latency"),
+        SeedRegistry::new(43).seed_for("latency")
+    );
+
+    let mut unseeded = params("uniform");
+    unseeded.seed = None;
+    unseeded.min = Some(0.0);
+    unseeded.max = Some(1.0);
+    let seeded = unseeded
+        .clone()
+        .with_default_seed(seeds.seed_for("latency"));
+    assert_eq!(seeded.seed, Some(seeds.seed_for("latency")));
+    let mut explicit = unseeded.clone();
+    explicit.seed = Some(3);
+    assert_eq!(explicit.with_default_seed(11).seed, Some(3));
+}
@@ -0,0 +1,73 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Two vehicles sending their CAMs to two RSUs that both forward them to the vehicles, so that
+/// every vehicle receives two copies of the CAM of the other vehicle and of its own.
+fn flooded_relay(vehicle_filter: &str, rsu_filter: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/relay.toml"),
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"Duplicates\", output_filename = \"duplicates.parquet\" },",
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]",
+        &format!(
+            "selector = [{{ target_class = \"RSU5G\", name = \"all\" }}]\n{}",
+            vehicle_filter
+        ),
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"Vehicle5G\", name = \"all\" }]",
+        &format!(
+            "selector = [{{ target_class = \"Vehicle5G\", name = \"all\" }}]\n{}",
+            rsu_filter
+        ),
+    );
+    let config = format!("{}\n[network_settings.lake]\nttl = 200\n", config);
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.place(DeviceType::RSU, 101, 200.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.place(DeviceType::Vehicle, 0, 50.0, 90.0);
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.place(DeviceType::Vehicle, 1, 250.0, 90.0);
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("duplicates.parquet")
+        .columns(&[
+            "time_step",
+            "device_class",
+            "received",
+            "duplicates",
+            "rebroadcast",
+            "withheld",
+            "overhead",
+        ])
+        .keys(&["time_step", "device_class"])
+}
+
+#[test]
+fn test_copies_over_both_relays_are_suppressed() {
+    let tables = flooded_relay("duplicates = {}", "").run();
+    check().assert_matches(&tables, &golden_file("duplicates_suppressed.csv"));
+}
+
+#[test]
+fn test_relays_rebroadcast_with_the_probability() {
+    let tables = flooded_relay(
+        "duplicates = {}",
+        "duplicates = { rebroadcast_probability = 0.5 }",
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("duplicates_rebroadcast.csv"));
+}
@@ -0,0 +1,54 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const SCENARIO: &str = include_str!("scenarios/duty_cycle.toml");
+
+/// Vehicles with a duty-cycled radio driving past an RSU that sends them payloads every step.
+fn duty_cycle(config: &str) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let speed = 10.0 + 5.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = speed * step.as_u64() as f64 / 1000.0;
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("duty_cycle.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "awake",
+            "addressed",
+            "reached",
+            "buffered",
+            "dropped",
+            "energy",
+        ])
+        .keys(&["time_step", "agent_id"])
+}
+
+#[test]
+fn test_buffered_duty_cycle() {
+    let tables = duty_cycle(SCENARIO).run();
+    check().assert_matches(&tables, &golden_file("duty_cycle_buffered.csv"));
+}
+
+#[test]
+fn test_unbuffered_duty_cycle() {
+    let config = patch(SCENARIO, "buffering = true", "buffering = false");
+    let tables = duty_cycle(&config).run();
+    check().assert_matches(&tables, &golden_file("duty_cycle_unbuffered.csv"));
+}
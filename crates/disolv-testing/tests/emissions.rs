@@ -0,0 +1,69 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const PASSENGER_CAR: &str = "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]\nemissions = { mass = 1500.0, drag_area = 0.7, rolling_resistance = 0.01, idle_fuel = 0.2, fuel_per_kj = 0.08, co2_per_ml = 2.3 }";
+
+/// Two vehicles for half a second, one cruising at 20 m/s and one accelerating from standstill
+/// at 2 m/s², without velocities in the trace.
+fn driving_highway(mass: f64) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 500",
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]",
+        &patch(
+            PASSENGER_CAR,
+            "mass = 1500.0",
+            &format!("mass = {:.1}", mass),
+        ),
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"Emissions\", output_filename = \"emissions.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |step: TimeMS| {
+        let seconds = step.as_u64() as f64 / 1000.0;
+        Point2D::builder().x(20.0 * seconds).y(100.0).build()
+    });
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 1, |step: TimeMS| {
+        let seconds = step.as_u64() as f64 / 1000.0;
+        Point2D::builder().x(seconds * seconds).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_emissions_follow_speed_and_acceleration() {
+    let tables = driving_highway(1500.0).run();
+    TableCheck::new("emissions.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "speed",
+            "acceleration",
+            "fuel",
+            "co2",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("emissions.csv"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid vehicle mass 0")]
+fn test_massless_vehicle_is_rejected() {
+    driving_highway(0.0).run();
+}
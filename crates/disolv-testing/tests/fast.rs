@@ -0,0 +1,28 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::scenario::MiniScenario;
+
+/// Two vehicles driving past an RSU, one of them out of range for the second half of the run.
+fn highway() -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(include_str!("scenarios/highway.toml"));
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 500.0, 100.0);
+    for vehicle in 0..2 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = step.as_u64() as f64 * 0.05 * (vehicle + 1) as f64;
+            Point2D::builder().x(x).y(90.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_fast_mode_keeps_the_kpis() {
+    let kpis = highway().run_for_kpis(false);
+    assert!(kpis.iter().any(|(_, value)| *value > 0.0));
+    assert_eq!(highway().run_for_kpis(true), kpis);
+}
@@ -0,0 +1,73 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// An outage of the RSU from 2 s to 4 s, the capacity of the slice cut to nothing from 6 s to
+/// 7 s and a blackout of half a second over the last vehicle at a random time.
+const FAULTS: &str = r#"
+[[simulation_settings.faults]]
+fault_type = "Outage"
+start = 2000
+duration = 2000
+agent_ids = [100]
+
+[[simulation_settings.faults]]
+fault_type = "SliceDegradation"
+start = 6000
+duration = 1000
+slice_id = 0
+capacity_factor = 0.0
+
+[[simulation_settings.faults]]
+fault_type = "Blackout"
+duration = 500
+area = [215.0, 0.0, 225.0, 200.0]
+"#;
+
+/// Three parked vehicles sending to an RSU over a slice with a capacity.
+fn faulty_highway() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\ncapacity = 1000000\nsub_steps = 1",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"FaultEvents\", output_filename = \"faults.parquet\" },",
+    );
+    let config = format!("{}\n{}", FAULTS, config);
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let x = 200.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_fault_events() {
+    let tables = faulty_highway().run();
+    TableCheck::new("faults.parquet")
+        .columns(&["time_step", "fault_id", "fault_type", "active"])
+        .keys(&["time_step", "fault_id"])
+        .assert_matches(&tables, &golden_file("fault_events.csv"));
+}
+
+#[test]
+fn test_faults_fail_transfers() {
+    let tables = faulty_highway().run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "tx_status", "tx_fail_reason"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("fault_tx_data.csv"));
+}
@@ -0,0 +1,66 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A vehicle next to an RSU sending a payload of 10000 bytes every second on a slice with an
+/// MTU of 1500 bytes, so that every payload is sent in seven fragments.
+fn fragmented_highway(fragmentation: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 3000",
+    );
+    let config = patch(
+        &config,
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\nmtu = 1500",
+    );
+    let config = patch(
+        &config,
+        "data_size = 300, source_step = 100",
+        "data_size = 10000, source_step = 1000",
+    );
+    let config = patch(
+        &config,
+        "[network_settings.age_of_information]",
+        &format!(
+            "[network_settings.fragmentation]\n{}\n\n[network_settings.age_of_information]",
+            fragmentation
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.place(DeviceType::Vehicle, 0, 110.0, 100.0);
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "payload_size",
+            "tx_status",
+            "tx_fail_reason",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+}
+
+/// Payloads are delivered once the last of their fragments arrives, two fragments per step.
+#[test]
+fn test_fragments_are_reassembled() {
+    let tables = fragmented_highway("reassembly_timeout = 1000\nfragments_per_step = 2").run();
+    check().assert_matches(&tables, &golden_file("fragments_tx_data.csv"));
+}
+
+/// Payloads whose fragments take longer than the timeout are dropped by the receiver.
+#[test]
+fn test_slow_reassembly_times_out() {
+    let tables = fragmented_highway("reassembly_timeout = 300\nfragments_per_step = 1").run();
+    check().assert_matches(&tables, &golden_file("fragments_timeout_tx_data.csv"));
+}
@@ -0,0 +1,214 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck, Tolerance};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Four vehicles driving along a highway past two RSUs, with one vehicle leaving half way.
+fn highway() -> MiniScenario {
+    highway_from(include_str!("scenarios/highway.toml"))
+}
+
+fn highway_from(config: &str) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    scenario.place(DeviceType::RSU, 101, 750.0, 100.0);
+    for vehicle in 0..4u64 {
+        let off = if vehicle == 3 { end / 2 } else { end };
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, off);
+        let speed = 20.0 + 10.0 * vehicle as f64;
+        let lane = 90.0 + 10.0 * (vehicle % 2) as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = speed * step.as_u64() as f64 / 1000.0;
+            Point2D::builder().x(x).y(lane).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_highway_tx_data() {
+    let tables = highway().run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "distance",
+            "tx_status",
+            "payload_size",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(Tolerance {
+            absolute: 0.01,
+            relative: 1e-6,
+        })
+        .assert_matches(&tables, &golden_file("highway_tx_data.csv"));
+}
+
+#[test]
+fn test_highway_rx_counts_and_lifecycle() {
+    let tables = highway().run();
+    TableCheck::new("rx_counts.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "attempted_in_agent_count",
+            "attempted_in_data_size",
+            "feasible_in_data_count",
+            "success_rate",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(Tolerance {
+            absolute: 1e-4,
+            relative: 0.0,
+        })
+        .assert_matches(&tables, &golden_file("highway_rx_counts.csv"));
+    TableCheck::new("lifecycle.parquet")
+        .columns(&[
+            "agent_id",
+            "first_activation",
+            "last_deactivation",
+            "active_duration",
+            "power_cycles",
+            "reason",
+        ])
+        .keys(&["agent_id"])
+        .assert_matches(&tables, &golden_file("highway_lifecycle.csv"));
+}
+
+#[test]
+fn test_highway_sampled_rx_counts() {
+    // Receive counts are recorded every second while the transfers are still recorded in
+    // every step.
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "output_filename = \"rx_counts.parquet\",",
+        "output_filename = \"rx_counts.parquet\", sample_interval = 1000,",
+    );
+    let tables = highway_from(&config).run();
+    TableCheck::new("rx_counts.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "attempted_in_agent_count",
+            "attempted_in_data_size",
+            "feasible_in_data_count",
+            "success_rate",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(Tolerance {
+            absolute: 1e-4,
+            relative: 0.0,
+        })
+        .assert_matches(&tables, &golden_file("highway_rx_counts_sampled.csv"));
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "distance",
+            "tx_status",
+            "payload_size",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(Tolerance {
+            absolute: 0.01,
+            relative: 1e-6,
+        })
+        .assert_matches(&tables, &golden_file("highway_tx_data.csv"));
+}
+
+#[test]
+fn test_highway_age_of_information() {
+    let tables = highway().run();
+    TableCheck::new("aoi.parquet")
+        .columns(&[
+            "time_step",
+            "source_id",
+            "target_id",
+            "age",
+            "peak_age",
+            "updates",
+        ])
+        .keys(&["time_step", "source_id", "target_id"])
+        .assert_matches(&tables, &golden_file("highway_aoi.csv"));
+}
+
+#[test]
+fn test_highway_fairness() {
+    let mut scenario = highway();
+    scenario.set_output_interval(2000);
+    let tables = scenario.run();
+    let tolerance = Tolerance {
+        absolute: 1e-6,
+        relative: 0.0,
+    };
+    TableCheck::new("fairness.parquet")
+        .columns(&[
+            "time_step",
+            "device_class",
+            "agent_count",
+            "delivered_bytes",
+            "jain_index",
+            "starved_agents",
+            "starved_steps",
+        ])
+        .keys(&["time_step", "device_class"])
+        .tolerance(tolerance)
+        .assert_matches(&tables, &golden_file("highway_fairness.csv"));
+    TableCheck::new("agent_fairness.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "attempted_bytes",
+            "delivered_bytes",
+            "starved_steps",
+            "source_count",
+            "source_fairness",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(tolerance)
+        .assert_matches(&tables, &golden_file("highway_agent_fairness.csv"));
+}
+
+#[test]
+fn test_highway_power_state_changes() {
+    let tables = highway().run();
+    TableCheck::new("state_changes.parquet")
+        .columns(&["time_step", "agent_id", "machine", "from_state", "to_state"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("highway_state_changes.csv"));
+}
+
+#[test]
+fn test_highway_keeps_invariants() {
+    // Any violation stops the run, and checking the invariants leaves the results unchanged.
+    let config = format!(
+        "{}\n[simulation_settings.validation]\nfail_on_violation = true\n",
+        include_str!("scenarios/highway.toml")
+    );
+    let tables = highway_from(&config).run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "distance",
+            "tx_status",
+            "payload_size",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .tolerance(Tolerance {
+            absolute: 0.01,
+            relative: 1e-6,
+        })
+        .assert_matches(&tables, &golden_file("highway_tx_data.csv"));
+}
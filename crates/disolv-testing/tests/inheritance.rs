@@ -0,0 +1,141 @@
+use disolv::base::BaseConfig;
+use disolv_testing::scenario::patch;
+
+const SCENARIO: &str = include_str!("scenarios/highway.toml");
+
+const TEMPLATES: &str = r#"
+[[class_templates]]
+name = "base"
+agent_share = 1.0
+replier = { name = "stats" }
+energy = { name = "proportional", factor = 1, static_power = 0 }
+storage = { variant = "constant", limit = 1000000000 }
+
+[[class_templates]]
+name = "silent"
+inherits = "base"
+composer = { name = "basic", source_settings = [] }
+selector = []
+"#;
+
+const VEHICLE_CLASS: &str = r#"[[agents.class]]
+agent_share = 1.0
+agent_class = "Vehicle5G"
+agent_order = 0
+composer = { name = "basic", source_settings = [
+    { data_type = "CAM", agent_class = "RSU5G", data_size = 300, source_step = 100 },
+] }
+selector = [{ target_class = "RSU5G", name = "nearest", link_count = 1 }]
+replier = { name = "stats" }
+energy = { name = "proportional", factor = 1, static_power = 0 }
+storage = { variant = "constant", limit = 1000000000 }
+"#;
+
+const RSU_CLASS: &str = r#"[[agents.class]]
+agent_share = 1.0
+agent_class = "RSU5G"
+agent_order = 1
+composer = { name = "basic", source_settings = [] }
+selector = []
+replier = { name = "stats" }
+energy = { name = "proportional", factor = 1, static_power = 0 }
+storage = { variant = "constant", limit = 1000000000 }"#;
+
+/// The highway scenario with the shared settings of the classes moved to templates.
+fn inherited(vehicle_base: &str, rsu_base: &str) -> String {
+    let vehicle_class = format!(
+        r#"[[agents.class]]
+name = "vehicle"
+inherits = "{}"
+agent_class = "Vehicle5G"
+agent_order = 0
+composer = {{ name = "basic", source_settings = [
+    {{ data_type = "CAM", agent_class = "RSU5G", data_size = 300, source_step = 100 }},
+] }}
+selector = [{{ target_class = "RSU5G", name = "nearest", link_count = 1 }}]
+"#,
+        vehicle_base
+    );
+    let rsu_class = format!(
+        r#"[[agents.class]]
+inherits = "{}"
+agent_class = "RSU5G"
+agent_order = 1"#,
+        rsu_base
+    );
+    let config = patch(SCENARIO, VEHICLE_CLASS, &vehicle_class);
+    let config = patch(&config, RSU_CLASS, &rsu_class);
+    format!("{}{}", TEMPLATES, config)
+}
+
+fn parse(config: &str) -> Result<String, String> {
+    BaseConfig::from_toml(config)
+        .map(|config| format!("{:?}", config))
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_inherited_classes_match_flat_classes() {
+    let config = inherited("base", "silent");
+    assert_ne!(config, format!("{}{}", TEMPLATES, SCENARIO));
+    assert_eq!(parse(&config), parse(SCENARIO));
+}
+
+#[test]
+fn test_class_can_inherit_from_named_class() {
+    let config = patch(
+        &inherited("base", "vehicle"),
+        "agent_class = \"RSU5G\"\nagent_order = 1",
+        "agent_class = \"RSU5G\"\nagent_order = 1\ncomposer = { name = \"basic\", source_settings = [] }\nselector = []",
+    );
+    let flat = patch(SCENARIO, RSU_CLASS, &format!(
+            "{}\nactions = [{{ target = \"RSU5G\", data_type = \"CAM\", action_type = \"Consume\" }}]",
+            RSU_CLASS
+        ));
+    assert_eq!(parse(&config), parse(&flat));
+}
+
+#[test]
+fn test_unknown_base_is_reported() {
+    let error = parse(&inherited("base", "station")).unwrap_err();
+    assert_eq!(
+        error,
+        "Class 0 of agents 1: Base class station is not defined"
+    );
+}
+
+#[test]
+fn test_cyclic_inheritance_is_reported() {
+    let config = patch(
+        &inherited("base", "silent"),
+        "name = \"base\"\n",
+        "name = \"base\"\ninherits = \"silent\"\n",
+    );
+    let error = parse(&config).unwrap_err();
+    assert_eq!(
+        error,
+        "Class vehicle: Cyclic inheritance base -> silent -> base"
+    );
+}
+
+#[test]
+fn test_duplicate_names_are_reported() {
+    let config = patch(
+        &inherited("base", "silent"),
+        "name = \"vehicle\"",
+        "name = \"silent\"",
+    );
+    let error = parse(&config).unwrap_err();
+    assert_eq!(error, "Class silent is defined more than once");
+}
+
+#[test]
+fn test_missing_settings_are_reported() {
+    let config = patch(&inherited("base", "silent"), "\nselector = []\n", "\n");
+    let error = parse(&config).unwrap_err();
+    assert!(
+        error.starts_with("Class 0 of agents 1: missing field `selector`"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,53 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Path loss exponent of 3 with a noise floor 100 dB below the power received at 1 m. Transfers
+/// below -10 dB SINR fail.
+const INTERFERENCE: &str = r#"
+[network_settings.interference]
+path_loss_exponent = 3.0
+noise = -100.0
+min_sinr = -10.0
+"#;
+
+/// Six vehicles parked in the cell of an RSU at 10 m to 60 m from it, switched on one per
+/// second. Every vehicle that joins interferes with the transfers of the others, and the
+/// vehicles far from the RSU are drowned out by the near ones.
+fn parked_vehicles() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "[network_settings.age_of_information]",
+        &format!("{}\n[network_settings.age_of_information]", INTERFERENCE),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 500.0, 100.0);
+    for vehicle in 0..6u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, vehicle * 1000, end);
+        let x = 490.0 - 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_interference_in_a_crowded_cell() {
+    let tables = parked_vehicles().run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "tx_status",
+            "tx_fail_reason",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("interference_tx_data.csv"));
+}
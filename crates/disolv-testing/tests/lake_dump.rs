@@ -0,0 +1,72 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::dump::decode_lake_dump;
+use disolv_output::dump::proto::LakeDump;
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const LAKE: &str = r#"
+[network_settings.lake]
+ttl = 10000
+"#;
+
+/// Three vehicles sending to an RSU that receives before them, so that the payloads of the last
+/// step are still waiting in the data lake when the simulation ends.
+fn waiting_payloads() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "[network_settings.age_of_information]",
+        &format!("{}\n[network_settings.age_of_information]", LAKE),
+    );
+    let config = patch(
+        &config,
+        "agent_class = \"Vehicle5G\"\nagent_order = 0",
+        "agent_class = \"Vehicle5G\"\nagent_order = 2",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"LakeDump\", output_filename = \"lake.pb\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let x = 200.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn read_dump(scenario: MiniScenario) -> LakeDump {
+    let tables = scenario.run();
+    let content = tables.bytes("lake.pb").expect("Lake dump is not written");
+    decode_lake_dump(&content).expect("Failed to decode the lake dump")
+}
+
+#[test]
+fn test_dump_holds_the_waiting_payloads() {
+    let dump = read_dump(waiting_payloads());
+    let summary = dump.summary.expect("Lake dump has no summary");
+    assert_eq!(summary.waiting, 3);
+    assert_eq!(summary.sl_waiting, 0);
+    assert_eq!(summary.targets, 1);
+    assert_eq!(summary.sources, 3);
+    assert!(summary.taken > 0);
+
+    assert_eq!(dump.waiting.len(), 3);
+    assert!(dump.waiting.iter().all(|payload| payload.target_id == 100
+        && payload.expires_at > dump.time_step
+        && !payload.data_blobs.is_empty()));
+    let waiting_bytes: u64 = dump
+        .waiting
+        .iter()
+        .map(|payload| payload.payload_size)
+        .sum();
+    assert_eq!(summary.waiting_bytes, waiting_bytes);
+}
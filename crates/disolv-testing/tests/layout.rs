@@ -0,0 +1,52 @@
+use disolv_output::layout::{RunLayout, LATEST_LINK};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn layout(run_name: &str, latest_link: bool) -> RunLayout {
+    RunLayout {
+        run_name: run_name.to_string(),
+        tags: vec!["dense traffic".to_string(), "v2".to_string()],
+        latest_link,
+    }
+}
+
+fn fresh_output_path(name: &str) -> PathBuf {
+    let output_path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&output_path);
+    output_path
+}
+
+#[test]
+fn test_run_name_fills_the_template() {
+    let started = UNIX_EPOCH + Duration::from_secs(1714568701);
+    let name =
+        layout("{scenario}_s{seed}_{timestamp}_{tags}", false).run_name("Highway/A9", 42, started);
+    assert_eq!(name, "Highway_A9_s42_20240501T130501_dense_traffic_v2");
+}
+
+#[test]
+#[should_panic(expected = "Invalid run name placeholder date")]
+fn test_unknown_placeholder_is_rejected() {
+    layout("{scenario}_{date}", false).run_name("Highway", 42, SystemTime::now());
+}
+
+#[test]
+fn test_runs_do_not_overwrite_each_other() {
+    let output_path = fresh_output_path("disolv_layout_runs");
+    let layout = layout("{scenario}_s{seed}", true);
+    let name = layout.run_name("Highway", 42, SystemTime::now());
+
+    let first = layout.create_run_directory(&output_path, &name, false);
+    let second = layout.create_run_directory(&output_path, &name, false);
+    assert_eq!(first, output_path.join("Highway_s42"));
+    assert_eq!(second, output_path.join("Highway_s42_1"));
+    assert!(second.is_dir());
+    let latest = std::fs::read_link(output_path.join(LATEST_LINK)).expect("latest is a link");
+    assert_eq!(latest, PathBuf::from("Highway_s42_1"));
+
+    let shared = layout.create_run_directory(&output_path, &name, true);
+    assert_eq!(shared, first);
+    let latest = std::fs::read_link(output_path.join(LATEST_LINK)).expect("latest is a link");
+    assert_eq!(latest, PathBuf::from("Highway_s42"));
+    std::fs::remove_dir_all(&output_path).expect("outputs are removed");
+}
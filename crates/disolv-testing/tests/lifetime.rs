@@ -0,0 +1,65 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A parked vehicle in range of a far RSU for the whole second and of a near RSU that drops out
+/// of range every third step, selecting one of them with the lifetime selector.
+fn flickering_highway(lifetime_weight: f32) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]",
+        &format!(
+            "selector = [{{ target_class = \"RSU5G\", name = \"lifetime\", link_count = 1, lifetime_weight = {:.1} }}]",
+            lifetime_weight
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.move_along(DeviceType::RSU, 101, |step: TimeMS| {
+        let x = match (step.as_u64() / 100) % 3 {
+            2 => 900.0,
+            _ => 100.0,
+        };
+        Point2D::builder().x(x).y(100.0).build()
+    });
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(50.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "selected_agent"])
+        .keys(&["time_step", "agent_id"])
+}
+
+#[test]
+fn test_long_lived_link_is_preferred() {
+    let tables = flickering_highway(1.0).run();
+    check().assert_matches(&tables, &golden_file("lifetime_tx_data.csv"));
+}
+
+#[test]
+fn test_distance_only_weight_selects_nearest() {
+    let tables = flickering_highway(0.0).run();
+    check().assert_matches(&tables, &golden_file("lifetime_distance_tx_data.csv"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid lifetime weight 1.5")]
+fn test_weight_beyond_one_is_rejected() {
+    flickering_highway(1.5).run();
+}
@@ -0,0 +1,41 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Two vehicles next to an RSU with the metrics sampled every two seconds. The second vehicle
+/// leaves after five seconds, so the transfers of the slice drop while the counter keeps growing.
+fn sampled_highway() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "output_interval = 10000",
+        "output_interval = 2000",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"Metrics\", output_filename = \"metrics.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for (id, off) in [(0, end), (1, 5000)] {
+        scenario.add_agent(DeviceType::Vehicle, id, 0, off);
+        scenario.move_along(DeviceType::Vehicle, id, move |_: TimeMS| {
+            Point2D::builder().x(110.0 + id as f64).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_metrics_are_sampled() {
+    let tables = sampled_highway().run();
+    TableCheck::new("metrics.parquet")
+        .columns(&["time_step", "metric", "kind", "value"])
+        .keys(&["time_step", "metric"])
+        .assert_matches(&tables, &golden_file("metrics.csv"));
+}
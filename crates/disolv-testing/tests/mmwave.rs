@@ -0,0 +1,52 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A vehicle next to an RSU over a mmWave link whose beam follows the given settings.
+fn beam_link(mmwave: &str) -> MiniScenario {
+    let config = format!(
+        "{}\n[network_settings.mmwave]\n{}\n",
+        patch(
+            include_str!("scenarios/highway.toml"),
+            "duration = 10000",
+            "duration = 1000"
+        ),
+        mmwave
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(110.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "tx_status", "tx_fail_reason"])
+        .keys(&["time_step"])
+}
+
+#[test]
+fn test_blocked_beam_is_realigned_before_use() {
+    let tables = beam_link(
+        "los_to_nlos = 1.0\nnlos_to_los = 1.0\nrealignment = 200\naligned_capacity = 1000000",
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("mmwave_realignment.csv"));
+}
+
+#[test]
+fn test_blocked_beam_falls_back_to_nlos_capacity() {
+    let tables = beam_link(
+        "los_to_nlos = 1.0\nnlos_to_los = 0.0\naligned_capacity = 1000000\nnlos_capacity = 1000",
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("mmwave_nlos.csv"));
+}
@@ -0,0 +1,92 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// The second RSU belongs to another operator than the vehicles and the first RSU.
+const VISITED_RSU: &str = r#"
+[[agents.class]]
+agent_share = 0.5
+agent_class = "RSU5G"
+agent_order = 1
+operator = 2
+composer = { name = "basic", source_settings = [] }
+selector = [{ target_class = "Vehicle5G", name = "all" }]
+replier = { name = "stats" }
+energy = { name = "proportional", factor = 1, static_power = 0 }
+storage = { variant = "constant", limit = 1000000000 }
+actions = [
+    { target = "Vehicle5G", data_type = "CAM", action_type = "Consume" },
+]
+"#;
+
+/// Two RSUs of two operators and two vehicles of the first operator. The first vehicle is
+/// nearer to the RSU of the other operator but also covered by the RSU of its own, while the
+/// second vehicle is only covered by the RSU of the other operator.
+fn shared_relay(roaming: Option<u64>) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/relay.toml"),
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"OperatorStats\", output_filename = \"operator_stats.parquet\" },",
+    );
+    let config = patch(
+        &config,
+        "agent_class = \"Vehicle5G\"\nagent_order = 0\n",
+        "agent_class = \"Vehicle5G\"\nagent_order = 0\noperator = 1\n",
+    );
+    let mut config = patch(
+        &config,
+        "agent_share = 1.0\nagent_class = \"RSU5G\"\nagent_order = 1\n",
+        "agent_share = 0.5\nagent_class = \"RSU5G\"\nagent_order = 1\noperator = 1\n",
+    );
+    config.push_str(VISITED_RSU);
+    if let Some(extra_latency) = roaming {
+        config.push_str(&format!(
+            "\n[[network_settings.roaming]]\nhome = 1\nvisited = 2\nextra_latency = {}\n",
+            extra_latency
+        ));
+    }
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::RSU, 101, 0, end);
+    scenario.place(DeviceType::RSU, 101, 300.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.place(DeviceType::Vehicle, 0, 260.0, 90.0);
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.place(DeviceType::Vehicle, 1, 550.0, 90.0);
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("operator_stats.parquet")
+        .columns(&[
+            "time_step",
+            "operator_id",
+            "tx_attempted",
+            "tx_succeeded",
+            "visiting",
+            "denied",
+        ])
+        .keys(&["time_step", "operator_id"])
+}
+
+#[test]
+fn test_agents_without_roaming_stay_on_their_operator() {
+    let tables = shared_relay(None).run();
+    check().assert_matches(&tables, &golden_file("operators_home.csv"));
+}
+
+#[test]
+fn test_agents_roam_only_without_coverage_of_their_operator() {
+    let tables = shared_relay(Some(20)).run();
+    check().assert_matches(&tables, &golden_file("operators_roaming.csv"));
+}
+
+#[test]
+fn test_roaming_penalty_breaks_the_latency_constraint() {
+    let tables = shared_relay(Some(95)).run();
+    check().assert_matches(&tables, &golden_file("operators_penalty.csv"));
+}
@@ -0,0 +1,80 @@
+use arrow::array::{Array, UInt64Array};
+use disolv_models::device::types::DeviceType;
+use disolv_output::writer::MemoryTables;
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Three vehicles of order 0 sending to an RSU of order 1, with the execution order audited.
+fn audited(tie_break: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "seed = 42\n",
+        &format!(
+            "seed = 42\nordering = {{ tie_break = \"{}\", audit = true }}\n",
+            tie_break
+        ),
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"ExecutionOrder\", output_filename = \"order.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(
+            DeviceType::Vehicle,
+            vehicle,
+            200.0 + 10.0 * vehicle as f64,
+            100.0,
+        );
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// Agent ids in the order they ran, per step.
+fn steps(tables: &MemoryTables) -> Vec<Vec<u64>> {
+    let batches = tables
+        .read("order.parquet")
+        .expect("Execution order is not written");
+    let mut steps: Vec<(u64, Vec<u64>)> = Vec::new();
+    for batch in batches.iter() {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .expect("Column is missing")
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .expect("Column is not u64")
+                .clone()
+        };
+        let (time_steps, agent_ids) = (column("time_step"), column("agent_id"));
+        for row in 0..batch.num_rows() {
+            match steps.last_mut() {
+                Some((time_step, agents)) if *time_step == time_steps.value(row) => {
+                    agents.push(agent_ids.value(row))
+                }
+                _ => steps.push((time_steps.value(row), vec![agent_ids.value(row)])),
+            }
+        }
+    }
+    steps.into_iter().map(|(_, agents)| agents).collect()
+}
+
+#[test]
+fn test_ties_are_broken_by_reverse_id() {
+    let steps = steps(&audited("ReverseId").run());
+    assert!(!steps.is_empty());
+    assert!(steps.iter().all(|agents| *agents == vec![2, 1, 0, 100]));
+}
+
+#[test]
+fn test_random_ties_are_reproducible() {
+    let first = steps(&audited("Random").run());
+    let again = steps(&audited("Random").run());
+    assert_eq!(first, again);
+    assert!(first.iter().all(|agents| agents.last() == Some(&100)));
+}
@@ -0,0 +1,101 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const SCENARIO: &str = include_str!("scenarios/relay.toml");
+
+/// Two vehicles next to an RSU that forwards their CAMs to both of them, with the given
+/// pipelines of the RSU.
+fn relay(pipelines: &str) -> MiniScenario {
+    relay_with(&format!("{}pipelines = {}\n", SCENARIO, pipelines))
+}
+
+fn relay_with(config: &str) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..2u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(
+            DeviceType::Vehicle,
+            vehicle,
+            50.0 + 100.0 * vehicle as f64,
+            90.0,
+        );
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "data_count",
+            "payload_size",
+        ])
+        .keys(&["time_step", "agent_id", "selected_agent"])
+}
+
+#[test]
+fn test_forward_compressed_and_delayed() {
+    let tables = relay(
+        r#"[
+    { data_type = "CAM", stage = "Forward", steps = [
+        { name = "compress", ratio = 0.5 },
+        { name = "delay", delay = 300 },
+    ] },
+]"#,
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("pipeline_forward.csv"));
+}
+
+#[test]
+fn test_receive_filtered_by_size() {
+    let tables = relay(
+        r#"[
+    { data_type = "CAM", stage = "Receive", steps = [{ name = "filter", max_size = 200 }] },
+]"#,
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("pipeline_receive.csv"));
+}
+
+/// The vehicles compress their CAMs to half their size and the RSU aggregates the CAMs of both
+/// vehicles into one blob for every target.
+#[test]
+fn test_forward_aggregated_with_volumes() {
+    let config = patch(
+        &format!(
+            "{}pipelines = {}\n",
+            SCENARIO,
+            r#"[{ data_type = "CAM", stage = "Forward", steps = [{ name = "aggregate", ratio = 0.25 }] }]"#
+        ),
+        "data_size = 300, source_step = 100 }",
+        "data_size = 300, source_step = 100, compression = 0.5 }",
+    );
+    let config = patch(
+        &config,
+        r#"{ output_type = "TxData", output_filename = "tx_data.parquet" },"#,
+        r#"{ output_type = "TxData", output_filename = "tx_data.parquet" },
+    { output_type = "DataVolume", output_filename = "data_volume.parquet" },"#,
+    );
+    let tables = relay_with(&config).run();
+    check().assert_matches(&tables, &golden_file("pipeline_aggregate.csv"));
+    TableCheck::new("data_volume.parquet")
+        .columns(&[
+            "time_step",
+            "data_type",
+            "blobs",
+            "raw_bytes",
+            "sent_bytes",
+            "delivered_bytes",
+        ])
+        .keys(&["time_step", "data_type"])
+        .assert_matches(&tables, &golden_file("pipeline_volumes.csv"));
+}
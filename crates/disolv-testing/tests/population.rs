@@ -0,0 +1,103 @@
+use arrow::array::RecordBatch;
+use disolv::population::{Population, PopulationMode};
+use disolv_core::agent::AgentId;
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::{DeviceClass, DeviceType};
+use disolv_output::writer::MemoryTables;
+use disolv_testing::scenario::{patch, MiniScenario};
+use std::path::PathBuf;
+
+/// An RSU broadcasting to four parked vehicles, some of them close to the edge of the coverage
+/// where the reception depends on the draws of the broadcaster.
+fn broadcasting_highway(seed: u64, vehicle_share: f32) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(&config, "seed = 42", &format!("seed = {}", seed));
+    let config = patch(
+        &config,
+        "agent_share = 1.0\nagent_class = \"Vehicle5G\"",
+        &format!(
+            "agent_share = {}\nagent_class = \"Vehicle5G\"",
+            vehicle_share
+        ),
+    );
+    let config = patch(
+        &config,
+        "mobility = { mobility_type = \"Stationery\", is_streaming = false, trace_file = \"memory\" }",
+        "mobility = { mobility_type = \"Stationery\", is_streaming = false, trace_file = \"memory\" }\nlinker = [\n    { target_type = \"Vehicle\", links_file = \"memory\", range = 400.0, is_streaming = true },\n]",
+    );
+    let config = patch(
+        &config,
+        "composer = { name = \"basic\", source_settings = [] }",
+        "composer = { name = \"basic\", source_settings = [\n    { data_type = \"CPM\", agent_class = \"Vehicle5G\", data_size = 500, source_step = 100 },\n] }\nactions = [\n    { target = \"Vehicle5G\", data_type = \"CPM\", action_type = \"Consume\" },\n]\nbroadcast = { target_class = \"Vehicle5G\", coverage = 400.0, reliable_range = 50.0 }",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"BroadcastReception\", output_filename = \"broadcast.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for (id, x) in [(0, 200.0), (1, 250.0), (2, 300.0), (3, 350.0)] {
+        scenario.add_agent(DeviceType::Vehicle, id, 0, end);
+        scenario.move_along(DeviceType::Vehicle, id, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 400.0);
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn population_file(name: &str) -> PathBuf {
+    let population_file = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&population_file);
+    population_file
+}
+
+fn receptions(tables: &MemoryTables) -> Vec<RecordBatch> {
+    tables.read("broadcast.parquet").expect("table is written")
+}
+
+#[test]
+fn test_population_is_exported() {
+    let population_file = population_file("disolv_population_exported.toml");
+    let mut scenario = broadcasting_highway(42, 1.0);
+    scenario.set_population(PopulationMode::Export(population_file.clone()));
+    scenario.run();
+
+    let population = Population::read(&population_file);
+    assert_eq!(population.len(), 5);
+    let rsu = population
+        .record_of(AgentId::from(100))
+        .expect("RSU is exported");
+    assert_eq!(rsu.agent_class, DeviceClass::RSU5G);
+    assert!(population
+        .seed_of(AgentId::from(100), "broadcast_100")
+        .is_some());
+    assert!(population
+        .seed_of(AgentId::from(0), "broadcast_0")
+        .is_none());
+}
+
+#[test]
+fn test_imported_population_keeps_classes_and_draws() {
+    let population_file = population_file("disolv_population_imported.toml");
+    let mut scenario = broadcasting_highway(42, 1.0);
+    scenario.set_population(PopulationMode::Export(population_file.clone()));
+    let exported = receptions(&scenario.run());
+
+    let reseeded = receptions(&broadcasting_highway(7, 1.0).run());
+    assert_ne!(reseeded, exported);
+
+    // The shares of the classes are ignored for the agents in the population.
+    let mut scenario = broadcasting_highway(7, 0.5);
+    scenario.set_population(PopulationMode::Import(population_file));
+    assert_eq!(receptions(&scenario.run()), exported);
+}
@@ -0,0 +1,66 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Two vehicles driving along the highway for a second with the positioning error, next to an
+/// RSU that knows its position.
+fn noisy_highway(positioning: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"PerceivedPos\", output_filename = \"perceived_pos.parquet\" },",
+    );
+    let config = patch(
+        &config,
+        "agent_order = 0\n",
+        &format!("agent_order = 0\npositioning = {}\n", positioning),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    for vehicle in 0..2 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |now: TimeMS| {
+            Point2D::builder()
+                .x(50.0 * vehicle as f64 + now.as_u64() as f64 / 100.0)
+                .y(90.0)
+                .build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("perceived_pos.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "x",
+            "y",
+            "perceived_x",
+            "perceived_y",
+            "error",
+        ])
+        .keys(&["time_step", "agent_id"])
+}
+
+#[test]
+fn test_bias_shifts_the_perceived_position() {
+    let tables = noisy_highway("{ std_dev = 0.0, bias_x = 3.0, bias_y = -4.0 }").run();
+    check().assert_matches(&tables, &golden_file("positioning_bias.csv"));
+}
+
+#[test]
+fn test_correlated_noise_drifts() {
+    let tables = noisy_highway("{ std_dev = 2.0, correlation = 0.9 }").run();
+    check().assert_matches(&tables, &golden_file("positioning_markov.csv"));
+}
@@ -0,0 +1,77 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A parked vehicle sending to an RSU for two seconds over a slice with a capacity. The watch
+/// file is read at the output interval of a second, starting with the first step.
+fn reloading_highway(watch_file: &str, reloaded: &str) -> MiniScenario {
+    let watch_file = std::env::temp_dir().join(watch_file);
+    std::fs::write(&watch_file, reloaded).expect("watch file is written");
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        &format!("duration = 2000\nwatch_file = {:?}", watch_file),
+    );
+    let config = patch(
+        &config,
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\ncapacity = 1000000\nsub_steps = 1",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    scenario.set_output_interval(1000);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(110.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_composer_rate_is_reloaded() {
+    let reloaded = r#"
+[[composer]]
+agent_class = "Vehicle5G"
+data_type = "CAM"
+source_step = 500
+"#;
+    let tables = reloading_highway("disolv_reload_composer.toml", reloaded).run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "data_count", "payload_size"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("reload_composer_tx_data.csv"));
+}
+
+#[test]
+fn test_slice_capacity_is_reloaded() {
+    let reloaded = r#"
+[[slice]]
+id = 0
+capacity = 1000
+"#;
+    let tables = reloading_highway("disolv_reload_slice.toml", reloaded).run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "tx_status", "tx_fail_reason"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("reload_slice_tx_data.csv"));
+}
+
+#[test]
+fn test_invalid_watch_file_is_ignored() {
+    let reloaded = r#"
+[[composer]]
+agent_class = "Vehicle5G"
+data_type = "CAM"
+data_size = 500
+"#;
+    let tables = reloading_highway("disolv_reload_invalid.toml", reloaded).run();
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "data_count", "payload_size"])
+        .keys(&["time_step", "agent_id"])
+        .assert_matches(&tables, &golden_file("reload_invalid_tx_data.csv"));
+}
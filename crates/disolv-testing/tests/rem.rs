@@ -0,0 +1,108 @@
+use arrow::array::{Array, Float64Array, UInt32Array, UInt64Array};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::writer::MemoryTables;
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// Interference of the crowded cell with a radio environment map in cells of 100 m, written
+/// every second.
+const INTERFERENCE: &str = r#"
+[network_settings.interference]
+path_loss_exponent = 3.0
+noise = -100.0
+min_sinr = -10.0
+
+[network_settings.radio_map]
+cell_size = 100.0
+"#;
+
+/// Six vehicles parked in the cell of an RSU at 10 m to 60 m from it, switched on one per
+/// second, so that the far vehicles are drowned out by the near ones.
+fn parked_vehicles() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "[network_settings.age_of_information]",
+        &format!("{}\n[network_settings.age_of_information]", INTERFERENCE),
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"RadioMap\", output_filename = \"rem.parquet\" },",
+    );
+    let config = patch(&config, "output_interval = 10000", "output_interval = 1000");
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 500.0, 100.0);
+    for vehicle in 0..6u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, vehicle * 1000, end);
+        let x = 490.0 - 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |_: TimeMS| {
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// Cells of the map with their samples, mean and 95th percentile SINR and outage.
+fn cells(tables: &MemoryTables) -> Vec<(u32, u32, u64, f64, f64, f64)> {
+    let batches = tables
+        .read("rem.parquet")
+        .expect("Radio environment map is not written");
+    let mut cells = Vec::new();
+    for batch in batches.iter() {
+        let column = |name: &str| batch.column_by_name(name).expect("Column is missing");
+        let u32s = |name: &str| {
+            column(name)
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .expect("Column is not u32")
+                .clone()
+        };
+        let f64s = |name: &str| {
+            column(name)
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .expect("Column is not f64")
+                .clone()
+        };
+        let samples = column("samples")
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .expect("Column is not u64")
+            .clone();
+        let (cell_x, cell_y) = (u32s("cell_x"), u32s("cell_y"));
+        let (mean, p95, outage) = (f64s("mean_sinr"), f64s("p95_sinr"), f64s("outage"));
+        for row in 0..batch.num_rows() {
+            cells.push((
+                cell_x.value(row),
+                cell_y.value(row),
+                samples.value(row),
+                mean.value(row),
+                p95.value(row),
+                outage.value(row),
+            ));
+        }
+    }
+    cells
+}
+
+#[test]
+fn test_map_covers_the_crowded_cell() {
+    let cells = cells(&parked_vehicles().run());
+    assert!(!cells.is_empty());
+    // The vehicles are between 440 m and 490 m on the road at 100 m.
+    assert!(cells
+        .iter()
+        .all(|(cell_x, cell_y, samples, ..)| *cell_x == 4 && *cell_y == 1 && *samples > 0));
+    assert!(cells
+        .iter()
+        .all(|(.., outage)| (0.0..=1.0).contains(outage)));
+    // Once the near vehicles join, the far ones fall below the outage SINR.
+    assert!(cells.iter().any(|(.., outage)| *outage > 0.0));
+    let (.., first_mean, _, _) = cells.first().expect("No cell is written");
+    let (.., last_mean, _, _) = cells.last().expect("No cell is written");
+    assert!(last_mean < first_mean);
+}
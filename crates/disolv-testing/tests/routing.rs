@@ -0,0 +1,90 @@
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::MiniScenario;
+
+const SCENARIO: &str = include_str!("scenarios/relay.toml");
+
+/// The RSU drops the CAMs it would forward to the vehicles until the controller duplicates them
+/// from half a second on.
+const CONTROLLER: &str = r#"
+[[agents]]
+agent_type = "Controller"
+power_file = "memory"
+mobility = { mobility_type = "Stationery", is_streaming = false, trace_file = "memory" }
+linker = [
+    { target_type = "RSU", links_file = "memory", range = 500.0, is_streaming = true },
+]
+
+[[agents.class]]
+agent_share = 1.0
+agent_class = "Controller"
+agent_order = 0
+composer = { name = "basic", source_settings = [
+    { data_type = "Control", agent_class = "RSU5G", data_size = 100, source_step = 200 },
+] }
+selector = [{ target_class = "RSU5G", name = "all" }]
+replier = { name = "stats" }
+energy = { name = "proportional", factor = 1, static_power = 0 }
+storage = { variant = "constant", limit = 1000000000 }
+actions = [
+    { target = "RSU5G", data_type = "Control", action_type = "Consume" },
+]
+controller = { target_class = "RSU5G", rules = [
+    { data_type = "CAM", source_class = "Vehicle5G", target_class = "Vehicle5G", action = "drop" },
+], updates = [
+    { at = 500, rules = [{ data_type = "CAM", action = "duplicate" }] },
+] }
+"#;
+
+/// Two vehicles next to an RSU that forwards their CAMs to both of them, and a controller at
+/// the given distance from the RSU programming the forwarding of the RSU.
+fn controlled_relay(controller_distance: f64) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(&format!("{}{}", SCENARIO, CONTROLLER));
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Controller, 200, 0, end);
+    scenario.place(
+        DeviceType::Controller,
+        200,
+        100.0 + controller_distance,
+        100.0,
+    );
+    for vehicle in 0..2u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        scenario.place(
+            DeviceType::Vehicle,
+            vehicle,
+            50.0 + 100.0 * vehicle as f64,
+            90.0,
+        );
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario.connect_within(DeviceType::RSU, DeviceType::Vehicle, 300.0);
+    scenario.connect_within(DeviceType::Controller, DeviceType::RSU, 500.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "selected_agent",
+            "data_count",
+            "payload_size",
+        ])
+        .keys(&["time_step", "agent_id", "selected_agent"])
+}
+
+#[test]
+fn test_rules_follow_the_control_messages() {
+    let tables = controlled_relay(200.0).run();
+    check().assert_matches(&tables, &golden_file("routing_rules.csv"));
+}
+
+#[test]
+fn test_unreached_agent_keeps_forwarding() {
+    let tables = controlled_relay(800.0).run();
+    check().assert_matches(&tables, &golden_file("routing_unreached.csv"));
+}
@@ -0,0 +1,97 @@
+use arrow::array::{Array, UInt64Array};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::writer::RunMetadata;
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const SCENARIO: &str = include_str!("scenarios/highway.toml");
+
+fn highway(config: &str) -> MiniScenario {
+    let mut scenario = MiniScenario::from_toml(config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(110.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+/// Run metadata of every output table of the scenario, which must be the same for all of them.
+fn run_metadata(config: &str) -> Vec<(String, String)> {
+    let tables = highway(config).run();
+    let mut run_metadata: Option<Vec<(String, String)>> = None;
+    for table_name in tables.table_names() {
+        let mut metadata: Vec<(String, String)> = tables
+            .metadata(&table_name)
+            .expect("table is written")
+            .into_iter()
+            .filter(|(key, _)| key.starts_with("disolv."))
+            .collect();
+        metadata.sort();
+        match run_metadata {
+            Some(ref expected) => assert_eq!(&metadata, expected, "{}", table_name),
+            None => run_metadata = Some(metadata),
+        }
+    }
+    run_metadata.expect("scenario writes tables")
+}
+
+fn value_of<'a>(metadata: &'a [(String, String)], key: &str) -> &'a str {
+    metadata
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_else(|| panic!("{} is missing", key))
+}
+
+#[test]
+fn test_outputs_carry_run_metadata() {
+    let metadata = run_metadata(SCENARIO);
+    assert_eq!(metadata.len(), 3);
+    assert_eq!(value_of(&metadata, RunMetadata::SCENARIO_ID), "Highway");
+    assert_eq!(value_of(&metadata, RunMetadata::SEED), "42");
+    assert_eq!(value_of(&metadata, RunMetadata::CONFIG_HASH).len(), 16);
+}
+
+#[test]
+fn test_config_hash_follows_the_configuration() {
+    let original = run_metadata(SCENARIO);
+    let reformatted = run_metadata(&format!("# Same scenario\n{}", SCENARIO));
+    let reseeded = run_metadata(&patch(SCENARIO, "seed = 42", "seed = 7"));
+    let hash =
+        |metadata: &[(String, String)]| value_of(metadata, RunMetadata::CONFIG_HASH).to_string();
+    assert_eq!(hash(&original), hash(&reformatted));
+    assert_ne!(hash(&original), hash(&reseeded));
+    assert_eq!(value_of(&reseeded, RunMetadata::SEED), "7");
+}
+
+#[test]
+fn test_warm_up_is_left_out_of_outputs() {
+    let config = patch(
+        SCENARIO,
+        "output_interval = 10000\n",
+        "output_interval = 10000\nwarm_up = 2000\n",
+    );
+    let metadata = run_metadata(&config);
+    assert_eq!(value_of(&metadata, RunMetadata::WARM_UP), "2000");
+
+    let tables = highway(&config).run();
+    let batches = tables.read("tx_data.parquet").expect("tx data is written");
+    let mut time_steps = Vec::new();
+    for batch in batches.iter() {
+        let column = batch
+            .column_by_name("time_step")
+            .expect("Column is missing")
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .expect("Column is not u64")
+            .clone();
+        time_steps.extend((0..column.len()).map(|row| column.value(row)));
+    }
+    assert!(!time_steps.is_empty());
+    assert_eq!(time_steps.iter().min(), Some(&2000));
+}
@@ -0,0 +1,69 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const SESSIONS: &str = "[network_settings.sessions]\ntimeout = 2000\n\n";
+
+/// Two vehicles sending a payload of five steps of the slice capacity every second to an RSU.
+/// The first vehicle stays next to the RSU and the second one drives out of its range.
+fn sessions(sessions: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\ncapacity = 50000\nsub_steps = 10",
+    );
+    let config = patch(
+        &config,
+        "data_size = 300, source_step = 100",
+        "data_size = 25000, source_step = 1000",
+    );
+    let config = patch(
+        &config,
+        "[network_settings.age_of_information]",
+        &format!("{}[network_settings.age_of_information]", sessions),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(110.0).y(100.0).build()
+    });
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 1, |step: TimeMS| {
+        let x = 290.0 + 100.0 * step.as_u64() as f64 / 1000.0;
+        Point2D::builder().x(x).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "tx_status",
+            "tx_fail_reason",
+            "latency",
+        ])
+        .keys(&["time_step", "agent_id"])
+}
+
+/// Payloads larger than the slice carries in a step are not sent without sessions.
+#[test]
+fn test_large_payloads_fail_without_sessions() {
+    let tables = sessions("").run();
+    check().assert_matches(&tables, &golden_file("sessions_disabled_tx_data.csv"));
+}
+
+/// Payloads are delivered when their session completes, and the session of the vehicle that
+/// leaves the range of the RSU is aborted.
+#[test]
+fn test_sessions_span_steps() {
+    let tables = sessions(SESSIONS).run();
+    check().assert_matches(&tables, &golden_file("sessions_tx_data.csv"));
+}
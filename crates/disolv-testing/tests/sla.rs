@@ -0,0 +1,81 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const SLAS: &str = r#"[[simulation_settings.slas]]
+name = "cam_latency"
+data_type = "CAM"
+max_latency = 20
+min_delivery_ratio = 0.9
+
+[[simulation_settings.slas]]
+name = "vehicle_latency"
+agent_class = "Vehicle5G"
+max_latency = 25
+min_delivery_ratio = 0.6
+
+"#;
+
+/// Two vehicles sending CAMs to an RSU over a slice that fits one of them in a sub-step, with
+/// the SLAs evaluated every two seconds. The first vehicle stays next to the RSU and the second
+/// one drives out of its range, after which the CAMs of the first one are on time.
+fn monitored_highway() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "output_interval = 10000",
+        "output_interval = 2000",
+    );
+    let config = patch(
+        &config,
+        "bandwidth = { variant = \"constant\" }",
+        "bandwidth = { variant = \"constant\" }\ncapacity = 30000\nsub_steps = 10",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"SlaCompliance\", output_filename = \"slas.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&format!("{}{}", SLAS, config));
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(110.0).y(100.0).build()
+    });
+    scenario.add_agent(DeviceType::Vehicle, 1, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 1, |step: TimeMS| {
+        let x = 290.0 + 40.0 * step.as_u64() as f64 / 1000.0;
+        Point2D::builder().x(x).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_sla_compliance() {
+    let tables = monitored_highway().run();
+    TableCheck::new("slas.parquet")
+        .columns(&[
+            "time_step",
+            "sla_id",
+            "attempted",
+            "delivered",
+            "on_time",
+            "worst_latency",
+            "delivery_ratio",
+            "violated",
+        ])
+        .keys(&["time_step", "sla_id"])
+        .assert_matches(&tables, &golden_file("sla_compliance.csv"));
+}
+
+#[test]
+#[should_panic(expected = "SLA loose needs a max_latency or a min_delivery_ratio within 0 and 1")]
+fn test_sla_without_bounds_is_rejected() {
+    let config = include_str!("scenarios/highway.toml");
+    let sla = "[[simulation_settings.slas]]\nname = \"loose\"\ndata_type = \"CAM\"\n\n";
+    MiniScenario::from_toml(&format!("{}{}", sla, config)).run();
+}
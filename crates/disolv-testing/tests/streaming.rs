@@ -0,0 +1,45 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+const ADAPTIVE: &str = "adaptive_streaming = { min_interval = 500, max_interval = 4000, target_displacement = 10.0 }\n";
+
+/// A vehicle that stays next to an RSU for four seconds and then drives away at 40 m/s, with
+/// the streaming interval adapted to the average displacement of both agents.
+fn adaptive_highway() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "streaming_interval = 10000\n",
+        &format!("streaming_interval = 1000\n{}", ADAPTIVE),
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"StreamingIntervals\", output_filename = \"streaming.parquet\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 100.0, 100.0);
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |step: TimeMS| {
+        let driving = step.as_u64().saturating_sub(4000) as f64 / 1000.0;
+        Point2D::builder()
+            .x(110.0 + 40.0 * driving)
+            .y(100.0)
+            .build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+#[test]
+fn test_streaming_interval_follows_mobility() {
+    let tables = adaptive_highway().run();
+    TableCheck::new("streaming.parquet")
+        .columns(&["time_step", "interval", "displacement"])
+        .keys(&["time_step"])
+        .assert_matches(&tables, &golden_file("streaming_intervals.csv"));
+}
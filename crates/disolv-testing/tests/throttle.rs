@@ -0,0 +1,53 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A vehicle in range of three RSUs for a second, sending to all of them but throttled to at
+/// most two targets per step with the policy.
+fn throttled_highway(policy: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]",
+        &format!(
+            "selector = [{{ target_class = \"RSU5G\", name = \"all\" }}]\nthrottle = {{ max_targets = 2, policy = \"{}\" }}",
+            policy
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    for (rsu_id, x) in [(100, 100.0), (101, 200.0), (102, 300.0)] {
+        scenario.add_agent(DeviceType::RSU, rsu_id, 0, end);
+        scenario.place(DeviceType::RSU, rsu_id, x, 100.0);
+    }
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(120.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_data.parquet")
+        .columns(&["time_step", "agent_id", "selected_agent"])
+        .keys(&["time_step", "agent_id", "selected_agent"])
+}
+
+#[test]
+fn test_nearest_targets_are_kept() {
+    let tables = throttled_highway("nearest").run();
+    check().assert_matches(&tables, &golden_file("throttle_nearest_tx_data.csv"));
+}
+
+#[test]
+fn test_targets_take_turns() {
+    let tables = throttled_highway("round_robin").run();
+    check().assert_matches(&tables, &golden_file("throttle_round_robin_tx_data.csv"));
+}
@@ -0,0 +1,100 @@
+use arrow::array::{Array, UInt32Array, UInt64Array};
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_output::trace::proto::{TransferEvent, TxStatus};
+use disolv_output::writer::MemoryTables;
+use disolv_testing::scenario::{patch, MiniScenario};
+use prost::Message;
+
+/// The highway scenario with the payload transfers also written as a protobuf trace.
+fn highway_with_trace() -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"PayloadTrace\", output_filename = \"trace.pb\" },",
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    scenario.add_agent(DeviceType::RSU, 100, 0, end);
+    scenario.place(DeviceType::RSU, 100, 250.0, 100.0);
+    for vehicle in 0..3u64 {
+        scenario.add_agent(DeviceType::Vehicle, vehicle, 0, end);
+        let speed = 20.0 + 10.0 * vehicle as f64;
+        scenario.move_along(DeviceType::Vehicle, vehicle, move |step: TimeMS| {
+            let x = speed * step.as_u64() as f64 / 1000.0;
+            Point2D::builder().x(x).y(100.0).build()
+        });
+    }
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn read_trace(tables: &MemoryTables) -> Vec<TransferEvent> {
+    let content = tables.bytes("trace.pb").expect("Trace is not written");
+    let mut content = content.as_slice();
+    let mut events = Vec::new();
+    while !content.is_empty() {
+        events.push(
+            TransferEvent::decode_length_delimited(&mut content)
+                .expect("Failed to decode the transfer event"),
+        );
+    }
+    events
+}
+
+fn u64_column(tables: &MemoryTables, column: &str) -> Vec<u64> {
+    tables
+        .read("tx_data.parquet")
+        .expect("Transfers are not written")
+        .iter()
+        .flat_map(|batch| {
+            let values = batch
+                .column_by_name(column)
+                .expect("Column is missing")
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .expect("Column is not u64")
+                .clone();
+            (0..values.len()).map(move |row| values.value(row))
+        })
+        .collect()
+}
+
+fn u32_column(tables: &MemoryTables, column: &str) -> Vec<u32> {
+    tables
+        .read("tx_data.parquet")
+        .expect("Transfers are not written")
+        .iter()
+        .flat_map(|batch| {
+            let values = batch
+                .column_by_name(column)
+                .expect("Column is missing")
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .expect("Column is not u32")
+                .clone();
+            (0..values.len()).map(move |row| values.value(row))
+        })
+        .collect()
+}
+
+#[test]
+fn test_trace_matches_transfers() {
+    let tables = highway_with_trace().run();
+    let events = read_trace(&tables);
+    assert!(!events.is_empty());
+    assert!(events
+        .iter()
+        .all(|event| event.target_id == 100 && !event.data_blobs.is_empty()));
+    assert!(events.iter().any(|event| event.tx_status() == TxStatus::Ok));
+
+    let time_steps: Vec<u64> = events.iter().map(|event| event.time_step).collect();
+    let sources: Vec<u64> = events.iter().map(|event| event.source_id).collect();
+    let sizes: Vec<u64> = events.iter().map(|event| event.payload_size).collect();
+    let statuses: Vec<u32> = events.iter().map(|event| event.tx_status as u32).collect();
+    assert_eq!(time_steps, u64_column(&tables, "time_step"));
+    assert_eq!(sources, u64_column(&tables, "agent_id"));
+    assert_eq!(sizes, u64_column(&tables, "payload_size"));
+    assert_eq!(statuses, u32_column(&tables, "tx_status"));
+}
@@ -0,0 +1,72 @@
+use disolv_core::bucket::TimeMS;
+use disolv_models::device::mobility::Point2D;
+use disolv_models::device::types::DeviceType;
+use disolv_testing::golden::{golden_file, TableCheck};
+use disolv_testing::scenario::{patch, MiniScenario};
+
+/// A vehicle sending to three RSUs at 20, 80 and 180 m for a second, picking the transmission
+/// power of every transfer with the power control settings.
+fn powered_highway(tx_power: &str) -> MiniScenario {
+    let config = patch(
+        include_str!("scenarios/highway.toml"),
+        "duration = 10000",
+        "duration = 1000",
+    );
+    let config = patch(
+        &config,
+        "file_out_config = [",
+        "file_out_config = [\n    { output_type = \"TxPower\", output_filename = \"tx_power.parquet\" },",
+    );
+    let config = patch(
+        &config,
+        "selector = [{ target_class = \"RSU5G\", name = \"nearest\", link_count = 1 }]",
+        &format!(
+            "selector = [{{ target_class = \"RSU5G\", name = \"all\" }}]\ntx_power = {}",
+            tx_power
+        ),
+    );
+    let mut scenario = MiniScenario::from_toml(&config);
+    let end = scenario.duration().as_u64();
+    for (rsu_id, x) in [(100, 120.0), (101, 180.0), (102, 280.0)] {
+        scenario.add_agent(DeviceType::RSU, rsu_id, 0, end);
+        scenario.place(DeviceType::RSU, rsu_id, x, 100.0);
+    }
+    scenario.add_agent(DeviceType::Vehicle, 0, 0, end);
+    scenario.move_along(DeviceType::Vehicle, 0, |_: TimeMS| {
+        Point2D::builder().x(100.0).y(100.0).build()
+    });
+    scenario.connect_within(DeviceType::Vehicle, DeviceType::RSU, 300.0);
+    scenario
+}
+
+fn check() -> TableCheck {
+    TableCheck::new("tx_power.parquet")
+        .columns(&[
+            "time_step",
+            "agent_id",
+            "target_id",
+            "distance",
+            "tx_power",
+            "range",
+            "reached",
+        ])
+        .keys(&["time_step", "agent_id", "target_id"])
+}
+
+#[test]
+fn test_power_follows_the_distance() {
+    let tables = powered_highway(
+        "{ policy = \"distance\", max_power = 23.0, min_power = 0.0, max_range = 300.0, margin = 3.0 }",
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("tx_power_distance.csv"));
+}
+
+#[test]
+fn test_low_fixed_power_misses_far_targets() {
+    let tables = powered_highway(
+        "{ policy = \"fixed\", max_power = 23.0, min_power = 0.0, max_range = 300.0, power = 5.0 }",
+    )
+    .run();
+    check().assert_matches(&tables, &golden_file("tx_power_fixed.csv"));
+}
@@ -1,14 +1,52 @@
+use crate::classes::resolve_classes;
 use disolv_core::agent::AgentOrder;
+use disolv_core::boundary::BoundarySettings;
 use disolv_core::bucket::TimeMS;
+use disolv_core::group::GroupId;
+use disolv_core::heatmap::HeatmapKind;
+use disolv_core::ordering::OrderingSettings;
+use disolv_core::streaming::AdaptiveStreamingSettings;
+use disolv_device::baseline::BaselineSettings;
+use disolv_device::diagnostics::DiagnosticsSettings;
 use disolv_device::linker::LinkerSettings;
 use disolv_device::space::{FieldSettings, MobilitySettings};
+use disolv_device::validate::ValidationSettings;
+use disolv_models::bucket::age::AgeSettings;
+use disolv_models::bucket::fault::FaultSettings;
+use disolv_models::bucket::lake::LakeSettings;
+use disolv_models::bucket::sla::SlaSettings;
+use disolv_models::device::actions::PipelineSettings;
+use disolv_models::device::broadcast::BroadcastSettings;
+use disolv_models::device::cache::CacheSettings;
+use disolv_models::device::cadence::CadenceSettings;
+use disolv_models::device::charging::{BatterySettings, StationSettings};
 use disolv_models::device::compose::ComposerSettings;
+use disolv_models::device::duplicates::DuplicateSettings;
+use disolv_models::device::duty::DutyCycleSettings;
 use disolv_models::device::energy::EnergySettings;
 use disolv_models::device::hardware::StorageSettings;
+use disolv_models::device::mobility::emissions::EmissionSettings;
+use disolv_models::device::positioning::PositioningSettings;
+use disolv_models::device::predict::PredictorSettings;
+use disolv_models::device::queue::ProcessorSettings;
 use disolv_models::device::reply::ReplierSettings;
+use disolv_models::device::routing::ControllerSettings;
 use disolv_models::device::select::SelectorSettings;
+use disolv_models::device::sensor::SensorSettings;
+use disolv_models::device::throttle::ThrottleSettings;
+use disolv_models::device::tx_power::TxPowerSettings;
 use disolv_models::device::types::{DeviceClass, DeviceType};
+use disolv_models::net::attenuation::AttenuationSettings;
+use disolv_models::net::capacity::CapacityTraceSettings;
+use disolv_models::net::coverage::CoverageSettings;
+use disolv_models::net::fragment::FragmentSettings;
+use disolv_models::net::interference::InterferenceSettings;
+use disolv_models::net::mmwave::MmWaveSettings;
+use disolv_models::net::network::BackhaulSettings;
+use disolv_models::net::operator::{OperatorId, RoamingSettings};
 use disolv_models::net::radio::ActionSettings;
+use disolv_models::net::rem::RemSettings;
+use disolv_models::net::session::SessionSettings;
 use disolv_models::net::slice::SliceSettings;
 use disolv_output::result::OutputSettings;
 use serde::Deserialize;
@@ -23,6 +61,16 @@ pub struct BaseConfig {
     pub log_settings: LogSettings,
     pub output_settings: OutputSettings,
     pub agents: Vec<AgentSettings>,
+    pub distributed: Option<BoundarySettings>,
+}
+
+impl BaseConfig {
+    /// Parses the configuration after resolving the inheritance of the agent classes.
+    pub fn from_toml(config: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config: toml::Table = toml::from_str(config)?;
+        resolve_classes(&mut config)?;
+        Ok(config.try_into()?)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -31,15 +79,35 @@ pub struct SimSettings {
     pub duration: TimeMS,
     pub step_size: TimeMS,
     pub streaming_interval: TimeMS,
+    pub adaptive_streaming: Option<AdaptiveStreamingSettings>,
+    pub ordering: Option<OrderingSettings>,
     pub seed: u64,
+    pub episode_file: Option<String>,
+    pub load_profile: Option<String>,
+    pub heatmap: Option<HeatmapKind>,
+    pub mobility_prediction: Option<PredictorSettings>,
+    pub validation: Option<ValidationSettings>,
+    pub baseline: Option<BaselineSettings>,
+    pub diagnostics: Option<DiagnosticsSettings>,
+    pub faults: Option<Vec<FaultSettings>>,
+    pub slas: Option<Vec<SlaSettings>>,
+    pub watch_file: Option<String>,
 }
 
+/// Settings of the logging. The `subscriber` selects where the logs and the spans of the
+/// scheduler stages go: `file` (the default) writes plain log lines to the log file, `pretty`
+/// writes multi-line events to the log file and `json` writes one JSON object per event to the
+/// log file. The console is left to the terminal UI. With `span_events`, the `pretty` and
+/// `json` subscribers also log the time spent in every span when it closes. The log file of the
+/// previous run is cleared when `log_overwrite` is set and appended to otherwise.
 #[derive(Deserialize, Debug, Clone)]
 pub struct LogSettings {
     pub log_path: String,
     pub log_level: String,
     pub log_file_name: String,
     pub log_overwrite: bool,
+    pub subscriber: Option<String>,
+    pub span_events: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -54,6 +122,18 @@ pub struct AgentSettings {
 #[derive(Deserialize, Debug, Clone)]
 pub struct NetworkSettings {
     pub slice: Vec<SliceSettings>,
+    pub lake: Option<LakeSettings>,
+    pub backhaul: Option<BackhaulSettings>,
+    pub age_of_information: Option<AgeSettings>,
+    pub attenuation: Option<AttenuationSettings>,
+    pub interference: Option<InterferenceSettings>,
+    pub sessions: Option<SessionSettings>,
+    pub fragmentation: Option<FragmentSettings>,
+    pub roaming: Option<Vec<RoamingSettings>>,
+    pub capacity_trace: Option<CapacityTraceSettings>,
+    pub mmwave: Option<MmWaveSettings>,
+    pub coverage: Option<CoverageSettings>,
+    pub radio_map: Option<RemSettings>,
 }
 
 #[serde_with::skip_serializing_none]
@@ -67,7 +147,24 @@ pub struct AgentClassSettings {
     pub replier: ReplierSettings,
     pub energy: EnergySettings,
     pub storage: StorageSettings,
+    pub processor: Option<ProcessorSettings>,
     pub actions: Option<Vec<ActionSettings>>,
+    pub sensor: Option<SensorSettings>,
+    pub cache: Option<CacheSettings>,
+    pub groups: Option<Vec<GroupId>>,
+    pub duty_cycle: Option<DutyCycleSettings>,
+    pub pipelines: Option<Vec<PipelineSettings>>,
+    pub throttle: Option<ThrottleSettings>,
+    pub broadcast: Option<BroadcastSettings>,
+    pub emissions: Option<EmissionSettings>,
+    pub controller: Option<ControllerSettings>,
+    pub operator: Option<OperatorId>,
+    pub duplicates: Option<DuplicateSettings>,
+    pub battery: Option<BatterySettings>,
+    pub station: Option<StationSettings>,
+    pub tx_power: Option<TxPowerSettings>,
+    pub positioning: Option<PositioningSettings>,
+    pub cadence: Option<CadenceSettings>,
 }
 
 pub struct BaseConfigReader {
@@ -82,7 +179,6 @@ impl BaseConfigReader {
 
     pub fn parse(&self) -> Result<BaseConfig, Box<dyn std::error::Error>> {
         let parsing_result = std::fs::read_to_string(&self.file_path)?;
-        let config: BaseConfig = toml::from_str(&parsing_result)?;
-        Ok(config)
+        BaseConfig::from_toml(&parsing_result)
     }
 }
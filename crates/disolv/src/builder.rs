@@ -1,53 +1,130 @@
 use crate::base::{AgentClassSettings, AgentSettings, BaseConfig, BaseConfigReader};
 use crate::logger;
+use crate::population::{Population, PopulationMode};
 use disolv_core::agent::{AgentId, AgentImpl};
+use disolv_core::boundary::RegionId;
 use disolv_core::bucket::TimeMS;
 use disolv_core::core::Core;
+use disolv_core::episode::Episodes;
+use disolv_core::group::Groups;
 use disolv_core::hashbrown::HashMap;
 use disolv_core::map_scheduler::MapScheduler;
 use disolv_core::metrics::Resource;
 use disolv_core::metrics::{Consumable, Measurable};
 use disolv_core::model::Model;
+use disolv_core::ordering::AgentOrdering;
 use disolv_core::scheduler::DefaultScheduler;
+use disolv_core::streaming::StreamingController;
 use disolv_core::ui::SimUIMetadata;
-use disolv_device::bucket::{BucketModels, DeviceBucket};
+use disolv_device::baseline::Baseline;
+use disolv_device::bucket::{
+    BackgroundCounts, BucketModels, CadenceCounts, DeviceBucket, HeatmapRecorder, ThrottleCounts,
+    TxPowerCounts,
+};
 use disolv_device::device::{Device, DeviceModel};
+use disolv_device::diagnostics::MemoryMonitor;
+use disolv_device::episode::DeviceEpisode;
 use disolv_device::linker::{Linker, LinkerSettings};
+use disolv_device::region::RegionExchange;
+use disolv_device::reload::SettingsWatcher;
 use disolv_device::space::{Mapper, Space};
-use disolv_input::links::LinkReader;
+use disolv_device::validate::Validator;
+use disolv_input::capacity::read_capacity_trace;
+use disolv_input::coverage::read_coverage_grid;
+use disolv_input::links::{LinkMap, LinkReader};
+use disolv_input::mobility::TraceMap;
 use disolv_input::power::{read_power_schedule, PowerTimes};
+use disolv_input::utilization::{read_utilization_trace, UtilizationEntries};
+use disolv_models::bucket::charging::ChargingStations;
+use disolv_models::bucket::digest::{DigestMode, RunDigest};
+use disolv_models::bucket::fault::FaultInjector;
 use disolv_models::bucket::flow::FlowRegister;
 use disolv_models::bucket::lake::DataLake;
+use disolv_models::bucket::lifetime::LinkLifetimes;
+use disolv_models::bucket::sla::SlaMonitor;
+use disolv_models::device::actions::Pipelines;
 use disolv_models::device::actor::Actor;
+use disolv_models::device::broadcast::Broadcaster;
+use disolv_models::device::cache::ContentCache;
+use disolv_models::device::cadence::Cadence;
+use disolv_models::device::charging::Battery;
 use disolv_models::device::compose::Composer;
+use disolv_models::device::duplicates::{ClassDuplicates, DuplicateFilter};
+use disolv_models::device::duty::DutyCycle;
 use disolv_models::device::energy::EnergyType;
 use disolv_models::device::hardware::StorageType;
+use disolv_models::device::mobility::emissions::EmissionModel;
+use disolv_models::device::positioning::PositionError;
 use disolv_models::device::power::PowerManager;
+use disolv_models::device::predict::MobilityPredictor;
+use disolv_models::device::queue::Processor;
 use disolv_models::device::reply::Replier;
+use disolv_models::device::routing::{ControllerSettings, RuleBook, RuleTable};
 use disolv_models::device::select::Selector;
+use disolv_models::device::sensor::Sensor;
+use disolv_models::device::throttle::Throttle;
+use disolv_models::device::tx_power::TxPowerControl;
 use disolv_models::device::types::{DeviceClass, DeviceInfo, DeviceType};
+use disolv_models::dist::SeedRegistry;
+use disolv_models::net::attenuation::Attenuation;
 use disolv_models::net::bandwidth::BandwidthType;
-use disolv_models::net::latency::LatencyType;
-use disolv_models::net::network::Network;
-use disolv_models::net::slice::{RadioMetrics, RadioResources, Slice, SliceSettings};
+use disolv_models::net::capacity::CapacityTrace;
+use disolv_models::net::coverage::CoverageMap;
+use disolv_models::net::fragment::Fragments;
+use disolv_models::net::interference::Interference;
+use disolv_models::net::latency::{Jitter, LatencyType};
+use disolv_models::net::mmwave::MmWave;
+use disolv_models::net::network::{Backhaul, Network};
+use disolv_models::net::operator::{OperatorCounts, Operators};
+use disolv_models::net::rem::RadioMap;
+use disolv_models::net::session::Sessions;
+use disolv_models::net::slice::{RadioMetrics, RadioResources, Slice, SliceSettings, SubSteps};
+use disolv_models::profile::LoadProfile;
 use disolv_output::result::ResultWriter;
+use disolv_output::writer::{MemoryTables, RunMetadata};
 use indexmap::IndexMap;
-use log::info;
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub type DCore = Core<Device, DeviceBucket>;
 pub type DScheduler = DefaultScheduler<Device, DeviceBucket>;
 pub type MScheduler = MapScheduler<Device, DeviceBucket>;
 pub type DAgentImpl = AgentImpl<Device, DeviceBucket>;
 
+#[derive(Deserialize, Debug, Clone)]
+struct EpisodeFile {
+    episodes: Vec<DeviceEpisode>,
+}
+
+/// Inputs of a scenario given in memory instead of the input files of the configuration. Power
+/// schedules and traces are given per agent type and links per source and target agent types.
+#[derive(Clone, Debug, Default)]
+pub struct ScenarioInputs {
+    pub power_schedules: HashMap<DeviceType, HashMap<AgentId, PowerTimes>>,
+    pub traces: HashMap<DeviceType, TraceMap>,
+    pub links: HashMap<(DeviceType, DeviceType), LinkMap>,
+}
+
 pub struct SimulationBuilder {
     base_config: BaseConfig,
     config_path: PathBuf,
     metadata: SimUIMetadata,
+    groups: Groups,
+    inputs: Option<ScenarioInputs>,
+    logging: bool,
+    digest: Option<DigestMode>,
+    population: Option<PopulationMode>,
+    imported: Option<Population>,
+    exported: Population,
+    agent_seeds: BTreeMap<String, u64>,
+    utilization_traces: HashMap<String, UtilizationEntries>,
 }
 
 impl SimulationBuilder {
-    pub(crate) fn new(base_config_file: &str) -> Self {
+    pub fn new(base_config_file: &str) -> Self {
         if !Path::new(base_config_file).exists() {
             panic!("Configuration file is not found.");
         }
@@ -60,20 +137,162 @@ impl SimulationBuilder {
 
         let config_reader = BaseConfigReader::new(base_config_file);
         match config_reader.parse() {
-            Ok(base_config) => {
-                let metadata = Self::build_metadata(&base_config, base_config_file);
-                Self {
-                    base_config,
-                    config_path,
-                    metadata,
-                }
-            }
+            Ok(base_config) => Self::with_config(base_config, &config_path, base_config_file),
             Err(e) => {
                 panic!("Error while parsing the base configuration file: {}", e);
             }
         }
     }
 
+    /// Builder for a configuration that is already parsed. Input files are looked up relative
+    /// to `config_path` unless the inputs are given in memory.
+    pub fn with_config(mut base_config: BaseConfig, config_path: &Path, input_file: &str) -> Self {
+        let metadata = Self::build_metadata(&base_config, input_file);
+        base_config.output_settings.run_metadata = Some(Self::build_run_metadata(&base_config));
+        Self {
+            base_config,
+            config_path: config_path.to_path_buf(),
+            metadata,
+            groups: Groups::default(),
+            inputs: None,
+            logging: true,
+            digest: None,
+            population: None,
+            imported: None,
+            exported: Population::default(),
+            agent_seeds: BTreeMap::new(),
+            utilization_traces: HashMap::new(),
+        }
+    }
+
+    /// Uses the given power schedules, traces and links instead of reading the input files.
+    pub fn with_inputs(mut self, inputs: ScenarioInputs) -> Self {
+        self.inputs = Some(inputs);
+        self
+    }
+
+    /// Simulates the given region of the map in the distributed mode, overriding the region in
+    /// the configuration so that all the processes can share one configuration file.
+    pub fn with_region(mut self, region: RegionId) -> Self {
+        match self.base_config.distributed {
+            Some(ref mut settings) => settings.region = region,
+            None => panic!(
+                "Region {} is given without the distributed settings.",
+                region
+            ),
+        }
+        self
+    }
+
+    /// Records the digest of the run to a file, or verifies the run against a recorded digest.
+    pub fn with_digest(mut self, mode: DigestMode) -> Self {
+        self.digest = Some(mode);
+        self
+    }
+
+    /// Keeps only the KPIs of the run, e.g. for quick calibration runs. No output table is
+    /// written, the run metadata stays in memory and the heatmap of the user interface is not
+    /// recorded. The simulation itself is not changed.
+    pub fn with_fast_mode(mut self) -> Self {
+        let output_settings = &mut self.base_config.output_settings;
+        output_settings.file_out_config.clear();
+        output_settings.memory = Some(MemoryTables::default());
+        self.base_config.simulation_settings.heatmap = None;
+        self
+    }
+
+    /// Writes the built population to a file, or builds the population in the file.
+    pub fn with_population(mut self, mode: PopulationMode) -> Self {
+        if let PopulationMode::Import(ref population_file) = mode {
+            self.imported = Some(Population::read(population_file));
+        }
+        self.population = Some(mode);
+        self
+    }
+
+    /// Skips setting up the logger, e.g. when the logger is set up by the caller.
+    pub fn without_logging(mut self) -> Self {
+        self.logging = false;
+        self
+    }
+
+    /// Moves the outputs to a directory of their own for the run when the output settings have
+    /// a run layout. All the regions of a distributed run share the run directory.
+    fn place_outputs_of_run(&mut self) {
+        let output_settings = &self.base_config.output_settings;
+        let layout = match output_settings.run_layout {
+            Some(ref layout) if output_settings.memory.is_none() => layout,
+            _ => return,
+        };
+        let run_name = layout.run_name(
+            &self.base_config.simulation_settings.scenario,
+            self.base_config.simulation_settings.seed,
+            SystemTime::now(),
+        );
+        let run_dir = layout.create_run_directory(
+            Path::new(&output_settings.output_path),
+            &run_name,
+            self.base_config.distributed.is_some(),
+        );
+        let output_path = run_dir.to_string_lossy().to_string();
+        self.base_config.output_settings.output_path = output_path.clone();
+        self.metadata.output_path = output_path;
+    }
+
+    /// Every region writes its log file to a directory of its own. The log path is split before
+    /// the logger starts, so that the logs of placing the outputs are kept.
+    fn split_logs_by_region(&mut self) {
+        let region = match self.base_config.distributed {
+            Some(ref settings) => settings.region,
+            None => return,
+        };
+        let log_path = PathBuf::from(&self.base_config.log_settings.log_path)
+            .join(format!("region_{}", region))
+            .to_string_lossy()
+            .to_string();
+        self.base_config.log_settings.log_path = log_path.clone();
+        self.metadata.log_path = log_path;
+    }
+
+    /// Every region writes its output files to a directory of its own.
+    fn split_outputs_by_region(&mut self) {
+        let region = match self.base_config.distributed {
+            Some(ref settings) => settings.region,
+            None => return,
+        };
+        let output_path = PathBuf::from(&self.base_config.output_settings.output_path)
+            .join(format!("region_{}", region))
+            .to_string_lossy()
+            .to_string();
+        if self.base_config.output_settings.memory.is_none() {
+            if let Err(e) = std::fs::create_dir_all(&output_path) {
+                panic!(
+                    "Failed to create the output directory {}: {}",
+                    output_path, e
+                );
+            }
+        }
+        self.base_config.output_settings.output_path = output_path.clone();
+        self.metadata.output_path = output_path;
+    }
+
+    fn initiate_logger(&self) {
+        if self.logging {
+            logger::initiate_logger(&self.config_path, &self.base_config.log_settings);
+        }
+    }
+
+    /// Parameters of the run written to the outputs. The configuration hash is taken over the
+    /// parsed configuration, so it ignores the formatting and the comments of the file.
+    fn build_run_metadata(base_config: &BaseConfig) -> RunMetadata {
+        RunMetadata {
+            scenario_id: base_config.simulation_settings.scenario.clone(),
+            seed: base_config.simulation_settings.seed,
+            config_hash: config_hash(&format!("{:?}", base_config)),
+            warm_up: base_config.output_settings.warm_up,
+        }
+    }
+
     fn build_metadata(base_config: &BaseConfig, base_config_file: &str) -> SimUIMetadata {
         SimUIMetadata {
             scenario: base_config.simulation_settings.scenario.clone(),
@@ -83,25 +302,71 @@ impl SimulationBuilder {
         }
     }
 
-    pub(crate) fn build(&mut self) -> DScheduler {
-        logger::initiate_logger(&self.config_path, &self.base_config.log_settings);
+    pub fn build(&mut self) -> DScheduler {
+        self.split_logs_by_region();
+        self.initiate_logger();
+        self.place_outputs_of_run();
+        self.split_outputs_by_region();
 
         info!("Building devices and device pools...");
-        let device_bucket = self.build_device_bucket();
+        let mut device_bucket = self.build_device_bucket();
         let agent_map = self.build_agents();
+        device_bucket.groups = std::mem::take(&mut self.groups);
+        device_bucket.validator = self.build_validator(&agent_map);
+        device_bucket.baseline = self.build_baseline();
         self.build_scheduler(agent_map, device_bucket)
     }
 
-    pub(crate) fn build_with_map(&mut self) -> MScheduler {
-        logger::initiate_logger(&self.config_path, &self.base_config.log_settings);
+    pub fn build_with_map(&mut self) -> MScheduler {
+        self.split_logs_by_region();
+        self.initiate_logger();
+        self.place_outputs_of_run();
+        self.split_outputs_by_region();
 
         info!("Building devices and device pools...");
-        let device_bucket = self.build_device_bucket();
+        let mut device_bucket = self.build_device_bucket();
         let agent_map = self.build_agents();
+        device_bucket.groups = std::mem::take(&mut self.groups);
+        device_bucket.validator = self.build_validator(&agent_map);
+        device_bucket.baseline = self.build_baseline();
         self.build_map_scheduler(agent_map, device_bucket)
     }
 
+    fn build_validator(&self, agent_map: &HashMap<AgentId, DAgentImpl>) -> Option<Validator> {
+        let settings = self.base_config.simulation_settings.validation?;
+        info!("Checking the invariants of the simulation in every step");
+        Some(Validator::new(
+            &settings,
+            agent_map.keys().copied().collect(),
+        ))
+    }
+
+    /// The baseline only applies to a network of a single slice with a capacity.
+    fn build_baseline(&self) -> Option<Baseline> {
+        let settings = self.base_config.simulation_settings.baseline?;
+        let network_settings = &self.base_config.network_settings;
+        let is_simple = network_settings.slice.len() == 1
+            && network_settings.slice[0].capacity.is_some()
+            && network_settings.backhaul.is_none();
+        if !is_simple {
+            warn!("The baseline needs a single slice with a capacity and no backhaul, skipping");
+            return None;
+        }
+        info!("Comparing the slice with its analytical baseline");
+        Some(Baseline::new(
+            &settings,
+            self.base_config.simulation_settings.step_size,
+        ))
+    }
+
     fn read_power_schedules(&self, device_type: DeviceType) -> HashMap<AgentId, PowerTimes> {
+        if let Some(ref inputs) = self.inputs {
+            return inputs
+                .power_schedules
+                .get(&device_type)
+                .cloned()
+                .unwrap_or_else(|| panic!("No power schedules given for {}", device_type));
+        }
         let device_settings: &AgentSettings = self
             .base_config
             .agents
@@ -121,6 +386,7 @@ impl SimulationBuilder {
         power_schedules.iter().for_each(|(device_id, _)| {
             device_ids.push(*device_id);
         });
+        device_ids.sort();
         device_ids
     }
 
@@ -137,14 +403,20 @@ impl SimulationBuilder {
                 device_setting.agent_type
             );
 
+            if self.imported.is_some() {
+                self.build_imported_agents(device_setting, power_schedules, &mut device_map);
+                continue;
+            }
             for class_settings in device_setting.class.iter() {
                 let class_count = (class_settings.agent_share * device_count as f32) as usize;
                 let mut device_count = 0;
 
+                // The agents built for the previous classes of the type are skipped.
                 for device_id in device_ids.iter() {
-                    let device_schedule = power_schedules
-                        .remove(device_id)
-                        .unwrap_or_else(|| panic!("Invalid device id"));
+                    let device_schedule = match power_schedules.remove(device_id) {
+                        Some(device_schedule) => device_schedule,
+                        None => continue,
+                    };
                     let device = self.build_device(
                         *device_id,
                         &device_setting.agent_type,
@@ -163,9 +435,60 @@ impl SimulationBuilder {
                 }
             }
         }
+        if let Some(PopulationMode::Export(ref population_file)) = self.population {
+            self.exported.write(population_file);
+        }
         device_map
     }
 
+    /// Builds the agents of the type with the classes and the seeds of the imported population
+    /// instead of the shares of the classes.
+    fn build_imported_agents(
+        &mut self,
+        device_setting: &AgentSettings,
+        power_schedules: HashMap<AgentId, PowerTimes>,
+        device_map: &mut HashMap<AgentId, DAgentImpl>,
+    ) {
+        let mut power_schedules: Vec<(AgentId, PowerTimes)> = power_schedules.into_iter().collect();
+        power_schedules.sort_by_key(|(device_id, _)| *device_id);
+        for (device_id, device_schedule) in power_schedules.into_iter() {
+            let record = self
+                .imported
+                .as_ref()
+                .and_then(|population| population.record_of(device_id))
+                .cloned()
+                .unwrap_or_else(|| panic!("Agent {} is not in the population.", device_id));
+            if record.agent_type != device_setting.agent_type {
+                panic!(
+                    "Agent {} is a {} in the population, not a {}.",
+                    device_id, record.agent_type, device_setting.agent_type
+                );
+            }
+            let class_settings = device_setting
+                .class
+                .iter()
+                .find(|class_settings| class_settings.agent_class == record.agent_class)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Class {} of agent {} is not configured.",
+                        record.agent_class, device_id
+                    )
+                });
+            let mut device = self.build_device(
+                device_id,
+                &device_setting.agent_type,
+                class_settings,
+                device_schedule,
+            );
+            device.device_info = record.device_info();
+            let agent_impl = AgentImpl::builder()
+                .agent_id(device_id)
+                .agent(device)
+                .build();
+            device_map.insert(device_id, agent_impl);
+        }
+    }
+
     fn build_device(
         &mut self,
         device_id: AgentId,
@@ -182,29 +505,150 @@ impl SimulationBuilder {
             .build();
 
         let mut selector_vec = Vec::new();
+        let mut target_groups = Vec::new();
         class_settings.selector.iter().for_each(|settings| {
             let selector = Selector::with_settings(settings);
             selector_vec.push((settings.target_class, selector));
+            if let Some(group_id) = settings.target_group {
+                target_groups.push((settings.target_class, group_id));
+            }
         });
 
+        if let Some(ref groups) = class_settings.groups {
+            groups
+                .iter()
+                .for_each(|group_id| self.groups.join(*group_id, device_id));
+        }
+
+        let mut processor = match class_settings.processor {
+            Some(ref settings) => Processor::with_settings(settings),
+            None => Processor::default(),
+        };
+        if let Some(background) = self.background_of(class_settings, device_id) {
+            processor.set_background(background);
+        }
+
+        if class_settings.station.is_some() && !device_type.is_infrastructure() {
+            panic!(
+                "Only infrastructure agents can act as charging stations, not {}.",
+                device_type
+            );
+        }
+
+        // Only the composers that draw content use a random stream.
+        let content_seed = match class_settings
+            .composer
+            .source_settings
+            .iter()
+            .any(|data_source| data_source.content.is_some())
+        {
+            true => self.agent_seed(device_id, &format!("content_{}", device_id)),
+            false => 0,
+        };
         let device_model = DeviceModel::builder()
             .power(power_manager)
             .flow(FlowRegister::default())
             .sl_flow(FlowRegister::default())
-            .composer(Composer::with_settings(&class_settings.composer))
+            .composer(Composer::new(&class_settings.composer, content_seed))
             .selector(selector_vec)
             .actor(Actor::new(&class_settings.actions.clone()))
             .replier(Replier::with_settings(&class_settings.replier))
             .energy(EnergyType::with_settings(&class_settings.energy))
             .storage(StorageType::with_settings(&class_settings.storage))
+            .processor(processor)
+            .sensor(class_settings.sensor.as_ref().map(Sensor::with_settings))
+            .cache(
+                class_settings
+                    .cache
+                    .as_ref()
+                    .map(ContentCache::with_settings),
+            )
+            .target_groups(target_groups)
+            .duty_cycle(class_settings.duty_cycle.as_ref().map(DutyCycle::new))
+            .pipelines(Pipelines::new(&class_settings.pipelines))
+            .throttle(
+                class_settings
+                    .throttle
+                    .as_ref()
+                    .map(|settings| Throttle::new(settings, device_id.as_u64())),
+            )
+            .broadcaster(class_settings.broadcast.as_ref().map(|settings| {
+                let stream = format!("broadcast_{}", device_id);
+                Broadcaster::new(settings, self.agent_seed(device_id, &stream))
+            }))
+            .emissions(class_settings.emissions.as_ref().map(EmissionModel::new))
+            .routing(
+                self.is_controlled(&class_settings.agent_class)
+                    .then(RuleTable::default),
+            )
+            .duplicates(class_settings.duplicates.as_ref().map(|settings| {
+                let stream = format!("duplicates_{}", device_id);
+                DuplicateFilter::new(settings, self.agent_seed(device_id, &stream))
+            }))
+            .battery(class_settings.battery.as_ref().map(Battery::new))
+            .station(class_settings.station)
+            .tx_power(class_settings.tx_power.as_ref().map(TxPowerControl::new))
+            .positioning(class_settings.positioning.as_ref().map(|settings| {
+                let stream = format!("positioning_{}", device_id);
+                PositionError::new(settings, self.agent_seed(device_id, &stream))
+            }))
+            .cadence(class_settings.cadence.as_ref().map(|settings| {
+                let stream = format!("cadence_{}", device_id);
+                Cadence::new(
+                    settings,
+                    self.step_size(),
+                    self.agent_seed(device_id, &stream),
+                )
+            }))
             .build();
 
+        let agent_seeds = std::mem::take(&mut self.agent_seeds);
+        if let Some(PopulationMode::Export(_)) = self.population {
+            self.exported.add(&device_info, agent_seeds);
+        }
+
         Device::builder()
             .device_info(device_info)
             .models(device_model)
             .build()
     }
 
+    /// Seed of a random stream of the agent, which is taken from the imported population when
+    /// the agent has one for the stream.
+    fn agent_seed(&mut self, device_id: AgentId, stream: &str) -> u64 {
+        let seed = self
+            .imported
+            .as_ref()
+            .and_then(|population| population.seed_of(device_id, stream))
+            .unwrap_or_else(|| self.seeds().seed_for(stream));
+        self.agent_seeds.insert(stream.to_string(), seed);
+        seed
+    }
+
+    /// Busy fractions of the processor of the agent in the utilization trace of its class. Each
+    /// trace is read once and shared by the classes that use it.
+    fn background_of(
+        &mut self,
+        class_settings: &AgentClassSettings,
+        device_id: AgentId,
+    ) -> Option<Vec<(TimeMS, f64)>> {
+        let trace = class_settings
+            .processor
+            .as_ref()?
+            .utilization_trace
+            .as_ref()?;
+        if !self.utilization_traces.contains_key(trace) {
+            let trace_file = self.config_path.join(trace);
+            if !trace_file.exists() {
+                panic!("Utilization trace {} is not found.", trace_file.display());
+            }
+            info!("Reading the utilization trace {}", trace_file.display());
+            self.utilization_traces
+                .insert(trace.clone(), read_utilization_trace(&trace_file));
+        }
+        self.utilization_traces.get(trace)?.get(&device_id).cloned()
+    }
+
     fn build_device_info(
         device_id: AgentId,
         device_type: &DeviceType,
@@ -215,6 +659,7 @@ impl SimulationBuilder {
             .device_type(device_type.to_owned())
             .device_class(class_settings.agent_class)
             .agent_order(class_settings.agent_order)
+            .operator(class_settings.operator)
             .build()
     }
 
@@ -230,6 +675,7 @@ impl SimulationBuilder {
             .agents(agent_map)
             .core(DCore::new(device_bucket))
             .streaming_interval(self.streaming_interval())
+            .streaming_controller(self.build_streaming_controller())
             .output_interval(self.output_interval())
             .build()
     }
@@ -248,18 +694,236 @@ impl SimulationBuilder {
             .inactive_agents(agent_map)
             .core(DCore::new(device_bucket))
             .streaming_interval(self.streaming_interval())
+            .streaming_controller(self.build_streaming_controller())
             .output_interval(self.output_interval())
+            .ordering(self.build_ordering())
             .build()
     }
 
+    fn build_ordering(&self) -> AgentOrdering {
+        let settings = self
+            .base_config
+            .simulation_settings
+            .ordering
+            .unwrap_or_default();
+        AgentOrdering::new(&settings, self.seeds().seed_for("ordering"))
+    }
+
+    fn build_streaming_controller(&self) -> Option<StreamingController> {
+        let settings = self
+            .base_config
+            .simulation_settings
+            .adaptive_streaming
+            .as_ref()?;
+        info!(
+            "Adapting the streaming interval between {} and {}",
+            settings.min_interval, settings.max_interval
+        );
+        Some(StreamingController::new(
+            settings,
+            self.streaming_interval(),
+            self.step_size(),
+        ))
+    }
+
     fn build_device_bucket(&mut self) -> DeviceBucket {
         info!("Building device bucket...");
+        let models = self.build_bucket_models();
+        let faults = self.build_faults(&models.network);
+        let operator_counts = models
+            .network
+            .operators
+            .as_ref()
+            .map(|_| OperatorCounts::default());
         DeviceBucket::builder()
-            .models(self.build_bucket_models())
+            .models(models)
+            .faults(faults)
+            .sessions(self.build_sessions())
+            .fragments(self.build_fragments())
+            .sla_monitor(self.build_sla_monitor())
+            .throttle_counts(self.build_throttle_counts())
+            .background_counts(self.build_background_counts())
+            .tx_power_counts(self.build_tx_power_counts())
+            .cadence_counts(self.build_cadence_counts())
+            .link_lifetimes(self.build_link_lifetimes())
+            .rule_book(self.build_rule_book())
+            .digest(self.digest.clone().map(RunDigest::new))
+            .operator_counts(operator_counts)
+            .duplicates(self.build_class_duplicates())
+            .charging(self.build_charging())
             .class_to_type(self.read_class_to_type_map())
+            .load_profile(self.build_load_profile())
+            .heatmap(self.build_heatmap())
+            .predictor(self.build_predictor())
+            .memory_monitor(self.build_memory_monitor())
+            .watcher(self.build_watcher())
             .build()
     }
 
+    fn build_watcher(&self) -> Option<SettingsWatcher> {
+        self.base_config
+            .simulation_settings
+            .watch_file
+            .as_ref()
+            .map(|file_name| SettingsWatcher::new(&self.config_path.join(file_name)))
+    }
+
+    fn build_faults(&self, network: &Network) -> Option<FaultInjector> {
+        let faults = self.base_config.simulation_settings.faults.as_ref()?;
+        info!("Injecting {} faults", faults.len());
+        Some(FaultInjector::new(
+            faults,
+            self.duration(),
+            self.sim_seed(),
+            network,
+        ))
+    }
+
+    fn build_throttle_counts(&self) -> Option<ThrottleCounts> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .any(|class_settings| class_settings.throttle.is_some())
+            .then(ThrottleCounts::default)
+    }
+
+    fn build_background_counts(&self) -> Option<BackgroundCounts> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .filter_map(|class_settings| class_settings.processor.as_ref())
+            .any(|settings| settings.utilization_trace.is_some())
+            .then(BackgroundCounts::default)
+    }
+
+    fn build_cadence_counts(&self) -> Option<CadenceCounts> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .any(|class_settings| class_settings.cadence.is_some())
+            .then(CadenceCounts::default)
+    }
+
+    fn build_tx_power_counts(&self) -> Option<TxPowerCounts> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .any(|class_settings| class_settings.tx_power.is_some())
+            .then(TxPowerCounts::default)
+    }
+
+    fn build_class_duplicates(&self) -> Option<ClassDuplicates> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .any(|class_settings| class_settings.duplicates.is_some())
+            .then(ClassDuplicates::default)
+    }
+
+    fn build_charging(&self) -> Option<ChargingStations> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .any(|class_settings| {
+                class_settings.battery.is_some() || class_settings.station.is_some()
+            })
+            .then(ChargingStations::default)
+    }
+
+    fn build_link_lifetimes(&self) -> Option<LinkLifetimes> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .flat_map(|class_settings| class_settings.selector.iter())
+            .any(|settings| settings.name.to_lowercase() == "lifetime")
+            .then(|| LinkLifetimes::new(self.step_size()))
+    }
+
+    fn controllers(&self) -> Vec<&ControllerSettings> {
+        self.base_config
+            .agents
+            .iter()
+            .flat_map(|agent_settings| agent_settings.class.iter())
+            .filter_map(|class_settings| class_settings.controller.as_ref())
+            .collect()
+    }
+
+    fn is_controlled(&self, device_class: &DeviceClass) -> bool {
+        self.controllers()
+            .iter()
+            .any(|controller| controller.target_class == *device_class)
+    }
+
+    fn build_rule_book(&self) -> Option<RuleBook> {
+        let controllers = self.controllers();
+        if controllers.is_empty() {
+            return None;
+        }
+        info!("Routing with {} controller classes", controllers.len());
+        Some(RuleBook::new(&controllers))
+    }
+
+    fn build_sla_monitor(&self) -> Option<SlaMonitor> {
+        let slas = self.base_config.simulation_settings.slas.as_ref()?;
+        info!("Monitoring {} SLAs", slas.len());
+        Some(SlaMonitor::new(slas))
+    }
+
+    fn build_sessions(&self) -> Option<Sessions> {
+        let settings = self.base_config.network_settings.sessions.as_ref()?;
+        info!("Sending large payloads in transfer sessions");
+        Some(Sessions::new(settings, self.step_size()))
+    }
+
+    fn build_fragments(&self) -> Option<Fragments> {
+        let settings = self.base_config.network_settings.fragmentation.as_ref()?;
+        info!("Sending the payloads larger than the MTU of their slice in fragments");
+        Some(Fragments::new(settings))
+    }
+
+    fn build_memory_monitor(&self) -> Option<MemoryMonitor> {
+        self.base_config
+            .simulation_settings
+            .diagnostics
+            .as_ref()
+            .map(MemoryMonitor::new)
+    }
+
+    fn build_predictor(&self) -> Option<MobilityPredictor> {
+        self.base_config
+            .simulation_settings
+            .mobility_prediction
+            .as_ref()
+            .map(|settings| MobilityPredictor::new(settings, self.step_size()))
+    }
+
+    fn build_heatmap(&self) -> Option<HeatmapRecorder> {
+        let field_settings = &self.base_config.field_settings;
+        self.base_config
+            .simulation_settings
+            .heatmap
+            .map(|kind| HeatmapRecorder::new(kind, field_settings.width, field_settings.height))
+    }
+
+    fn build_load_profile(&self) -> Option<LoadProfile> {
+        let profile_file = match self.base_config.simulation_settings.load_profile {
+            Some(ref file_name) => self.config_path.join(file_name),
+            None => return None,
+        };
+        if !profile_file.exists() {
+            panic!("Load profile {} is not found.", profile_file.display());
+        }
+        info!("Reading the load profile {}", profile_file.display());
+        Some(LoadProfile::read(&profile_file))
+    }
+
     fn build_bucket_models(&mut self) -> BucketModels {
         BucketModels::builder()
             .result_writer(ResultWriter::new(&self.base_config.output_settings))
@@ -267,14 +931,73 @@ impl SimulationBuilder {
             .space(self.build_space())
             .mapper_holder(self.build_mapper_vec())
             .linker_holder(self.build_linker_vec())
-            .data_lake(DataLake::default())
+            .data_lake(
+                DataLake::new(self.base_config.network_settings.lake)
+                    .with_age_tracking(
+                        self.base_config
+                            .network_settings
+                            .age_of_information
+                            .as_ref(),
+                    )
+                    .with_reassembly(self.base_config.network_settings.fragmentation.as_ref()),
+            )
+            .episodes(self.build_episodes())
+            .region(self.build_region())
             .build()
     }
 
+    fn build_region(&self) -> Option<RegionExchange> {
+        self.base_config.distributed.as_ref().map(|settings| {
+            info!("Connecting region {} to the other regions", settings.region);
+            RegionExchange::new(settings, self.base_config.field_settings.width)
+        })
+    }
+
+    fn build_episodes(&self) -> Episodes<DeviceEpisode> {
+        let episode_file = match self.base_config.simulation_settings.episode_file {
+            Some(ref file_name) => self.config_path.join(file_name),
+            None => return Episodes::default(),
+        };
+        if !episode_file.exists() {
+            panic!("Episode file {} is not found.", episode_file.display());
+        }
+        let episode_toml = match std::fs::read_to_string(&episode_file) {
+            Ok(content) => content,
+            Err(e) => panic!("Error while reading the episode file: {}", e),
+        };
+        let mut episode_config: EpisodeFile = match toml::from_str(&episode_toml) {
+            Ok(config) => config,
+            Err(e) => panic!("Error while parsing the episode file: {}", e),
+        };
+        episode_config
+            .episodes
+            .iter_mut()
+            .flat_map(|episode| episode.slices.iter_mut().flatten())
+            .for_each(|slice_episode| {
+                slice_episode.latency = slice_episode
+                    .latency
+                    .take()
+                    .map(|latency| latency.with_base_path(&self.config_path));
+            });
+        info!("Read {} episodes", episode_config.episodes.len());
+        Episodes::new(
+            episode_config
+                .episodes
+                .into_iter()
+                .map(|episode| (episode.start, episode))
+                .collect(),
+        )
+    }
+
     fn build_mapper_vec(&self) -> Vec<(DeviceType, Mapper)> {
         let mut mapper_vec: Vec<(DeviceType, Mapper)> = Vec::new();
         for device_setting in self.base_config.agents.iter() {
             let device_type = device_setting.agent_type;
+            if let Some(ref inputs) = self.inputs {
+                let trace = inputs.traces.get(&device_type).cloned();
+                mapper_vec.push((device_type, Mapper::with_trace(trace.unwrap_or_default())));
+                continue;
+            }
             let mapper = Mapper::builder(&self.config_path)
                 .streaming_step(self.streaming_interval())
                 .field_settings(self.base_config.field_settings.clone())
@@ -300,6 +1023,18 @@ impl SimulationBuilder {
     }
 
     fn build_linker(&self, source_type: &DeviceType, link_config: &LinkerSettings) -> Linker {
+        if let Some(ref inputs) = self.inputs {
+            let links = inputs
+                .links
+                .get(&(*source_type, link_config.target_type))
+                .cloned();
+            return Linker::builder()
+                .source_type(source_type.to_owned())
+                .target_type(link_config.target_type.to_owned())
+                .is_static(!link_config.is_streaming)
+                .links(links.unwrap_or_default())
+                .build();
+        }
         let links_file = self.config_path.join(&link_config.links_file);
         if !links_file.exists() {
             panic!("Link file {} is not found.", links_file.display());
@@ -310,7 +1045,7 @@ impl SimulationBuilder {
             .streaming_step(self.streaming_interval())
             .build();
         Linker::builder()
-            .reader(link_reader)
+            .reader(Some(link_reader))
             .source_type(source_type.to_owned())
             .target_type(link_config.target_type.to_owned())
             .is_static(!link_config.is_streaming)
@@ -340,16 +1075,131 @@ impl SimulationBuilder {
     fn build_network(&self) -> Network {
         let mut slices: Vec<Slice> = Vec::new();
         for slice_setting in self.base_config.network_settings.slice.iter() {
-            let slice = Slice::builder()
-                .id(slice_setting.id)
-                .name(slice_setting.name.clone())
-                .step_size(self.step_size())
-                .resources(self.build_network_resources(slice_setting))
-                .metrics(self.build_network_metrics(slice_setting))
-                .build();
-            slices.push(slice);
+            slices.push(self.build_slice(slice_setting));
+        }
+        let backhaul = self
+            .base_config
+            .network_settings
+            .backhaul
+            .as_ref()
+            .map(|settings| Backhaul::new(settings, self.build_slice(&settings.slice)));
+        let attenuation = self
+            .base_config
+            .network_settings
+            .attenuation
+            .as_ref()
+            .map(Attenuation::new);
+        let interference = self
+            .base_config
+            .network_settings
+            .interference
+            .as_ref()
+            .map(Interference::new);
+        Network::builder()
+            .slices(slices)
+            .backhaul(backhaul)
+            .attenuation(attenuation)
+            .interference(interference)
+            .operators(self.build_operators())
+            .capacity_trace(self.build_capacity_trace())
+            .coverage(self.build_coverage())
+            .mmwave(self.build_mmwave())
+            .radio_map(self.build_radio_map())
+            .build()
+    }
+
+    /// The radio environment map samples the SINR given by the interference model.
+    fn build_radio_map(&self) -> Option<RadioMap> {
+        let network_settings = &self.base_config.network_settings;
+        let settings = network_settings.radio_map.as_ref()?;
+        let interference = match network_settings.interference {
+            Some(ref interference) => interference,
+            None => {
+                warn!("The radio environment map needs the interference settings, skipping it");
+                return None;
+            }
+        };
+        Some(RadioMap::new(settings, interference.min_sinr))
+    }
+
+    fn build_mmwave(&self) -> Option<MmWave> {
+        let settings = self.base_config.network_settings.mmwave.as_ref()?;
+        Some(MmWave::new(settings, self.seeds().seed_for("mmwave")))
+    }
+
+    fn build_capacity_trace(&self) -> Option<CapacityTrace> {
+        let settings = self.base_config.network_settings.capacity_trace.as_ref()?;
+        let trace_file = self.config_path.join(&settings.trace_file);
+        if !trace_file.exists() {
+            panic!("Capacity trace {} is not found.", trace_file.display());
         }
-        Network::builder().slices(slices).build()
+        info!("Reading the capacity trace {}", trace_file.display());
+        Some(CapacityTrace::new(
+            read_capacity_trace(&trace_file),
+            settings.interpolate.unwrap_or(false),
+        ))
+    }
+
+    fn build_coverage(&self) -> Option<CoverageMap> {
+        let settings = self.base_config.network_settings.coverage.as_ref()?;
+        let coverage_file = self.config_path.join(&settings.coverage_file);
+        if !coverage_file.exists() {
+            panic!("Coverage file {} is not found.", coverage_file.display());
+        }
+        info!("Reading the coverage grid {}", coverage_file.display());
+        Some(CoverageMap::new(
+            settings,
+            read_coverage_grid(&coverage_file),
+        ))
+    }
+
+    /// Operators are only tracked when the agents or the slices belong to operators, or the
+    /// operators have roaming agreements.
+    fn build_operators(&self) -> Option<Operators> {
+        let network_settings = &self.base_config.network_settings;
+        let has_operators = network_settings.roaming.is_some()
+            || network_settings
+                .slice
+                .iter()
+                .any(|slice_settings| slice_settings.operator.is_some())
+            || self
+                .base_config
+                .agents
+                .iter()
+                .flat_map(|agent_settings| agent_settings.class.iter())
+                .any(|class_settings| class_settings.operator.is_some());
+        if !has_operators {
+            return None;
+        }
+        let roaming = network_settings.roaming.clone().unwrap_or_default();
+        info!(
+            "Sharing the network with {} roaming agreements",
+            roaming.len()
+        );
+        Some(Operators::new(&roaming))
+    }
+
+    fn build_slice(&self, slice_setting: &SliceSettings) -> Slice {
+        Slice::builder()
+            .id(slice_setting.id)
+            .name(slice_setting.name.clone())
+            .step_size(self.step_size())
+            .loss_rng(
+                self.seeds()
+                    .seed_for(&format!("slice_{}_loss", slice_setting.id)),
+            )
+            .resources(self.build_network_resources(slice_setting))
+            .metrics(self.build_network_metrics(slice_setting))
+            .sub_steps(slice_setting.capacity.map(|capacity| {
+                SubSteps::new(
+                    capacity,
+                    slice_setting.sub_steps.unwrap_or(1),
+                    self.step_size(),
+                )
+            }))
+            .operator(slice_setting.operator)
+            .mtu(slice_setting.mtu)
+            .build()
     }
 
     fn build_network_resources(&self, slice_settings: &SliceSettings) -> RadioResources {
@@ -361,8 +1211,14 @@ impl SimulationBuilder {
     }
 
     fn build_network_metrics(&self, slice_settings: &SliceSettings) -> RadioMetrics {
+        let latency_config = slice_settings
+            .latency
+            .clone()
+            .with_base_path(&self.config_path)
+            .with_default_seeds(&self.seeds(), slice_settings.id);
         RadioMetrics::builder()
-            .latency_type(LatencyType::with_settings(&slice_settings.latency))
+            .latency_type(LatencyType::with_settings(&latency_config))
+            .jitter(Jitter::with_settings(&latency_config))
             .build()
     }
 
@@ -378,6 +1234,10 @@ impl SimulationBuilder {
         self.base_config.simulation_settings.step_size
     }
 
+    fn seeds(&self) -> SeedRegistry {
+        SeedRegistry::new(self.base_config.simulation_settings.seed)
+    }
+
     fn sim_seed(&self) -> u128 {
         u128::from(self.base_config.simulation_settings.seed)
     }
@@ -386,7 +1246,15 @@ impl SimulationBuilder {
         self.base_config.output_settings.output_interval
     }
 
-    pub(crate) fn metadata(&self) -> SimUIMetadata {
+    pub fn metadata(&self) -> SimUIMetadata {
         self.metadata.clone()
     }
 }
+
+/// FNV-1a hash of the configuration, written in hexadecimal.
+fn config_hash(config: &str) -> String {
+    let hash = config.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,123 @@
+use crate::base::AgentClassSettings;
+use serde::Deserialize;
+use toml::{Table, Value};
+
+/// Key of the class templates, which are only used as bases of other classes.
+pub const CLASS_TEMPLATES: &str = "class_templates";
+const NAME: &str = "name";
+const INHERITS: &str = "inherits";
+
+/// Resolves the inheritance of the agent classes in a parsed configuration.
+///
+/// Bases are the `[[class_templates]]` and the classes of the agents that have a `name`. A class
+/// with `inherits = "<name>"` takes all the settings of the base and replaces the ones it sets
+/// itself, so a class that sets `selector` replaces the entire selector list of its base. Bases
+/// can inherit from other bases. The templates are removed from the configuration and every
+/// class is checked for missing settings once its bases are applied.
+pub fn resolve_classes(config: &mut Table) -> Result<(), String> {
+    let templates = match config.remove(CLASS_TEMPLATES) {
+        Some(Value::Array(templates)) => templates,
+        Some(_) => return Err(format!("{} must be an array of tables", CLASS_TEMPLATES)),
+        None => Vec::new(),
+    };
+    let mut bases: Vec<(String, Table)> = Vec::new();
+    for template in templates.into_iter() {
+        let Value::Table(template) = template else {
+            return Err(format!("{} must be an array of tables", CLASS_TEMPLATES));
+        };
+        let Some(name) = class_name(&template)? else {
+            return Err(format!("Every entry of {} needs a name", CLASS_TEMPLATES));
+        };
+        add_base(&mut bases, name, template)?;
+    }
+    for (_, _, class) in labelled_classes(config) {
+        if let Some(name) = class_name(class)? {
+            add_base(&mut bases, name, class.clone())?;
+        }
+    }
+
+    for (agent_idx, class_idx, class) in labelled_classes(config) {
+        let label = match class_name(class)? {
+            Some(name) => format!("Class {}", name),
+            None => format!("Class {} of agents {}", class_idx, agent_idx),
+        };
+        if class.contains_key(INHERITS) {
+            let mut chain = Vec::new();
+            *class = resolve(class, &bases, &mut chain).map_err(|e| format!("{}: {}", label, e))?;
+        }
+        class.remove(NAME);
+        class.remove(INHERITS);
+        AgentClassSettings::deserialize(Value::Table(class.clone()))
+            .map_err(|e| format!("{}: {}", label, e.message()))?;
+    }
+    Ok(())
+}
+
+fn class_name(class: &Table) -> Result<Option<String>, String> {
+    match class.get(NAME) {
+        Some(Value::String(name)) => Ok(Some(name.clone())),
+        Some(_) => Err("Class names must be strings".to_string()),
+        None => Ok(None),
+    }
+}
+
+fn add_base(bases: &mut Vec<(String, Table)>, name: String, class: Table) -> Result<(), String> {
+    if bases.iter().any(|(base_name, _)| *base_name == name) {
+        return Err(format!("Class {} is defined more than once", name));
+    }
+    bases.push((name, class));
+    Ok(())
+}
+
+/// Classes of all the agents with the positions of the agents and of the class.
+fn labelled_classes(config: &mut Table) -> impl Iterator<Item = (usize, usize, &mut Table)> {
+    config
+        .get_mut("agents")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(agent_idx, agent)| {
+            let classes = agent.get_mut("class")?.as_array_mut()?;
+            Some(
+                classes
+                    .iter_mut()
+                    .enumerate()
+                    .filter_map(move |(class_idx, class)| {
+                        class
+                            .as_table_mut()
+                            .map(|class| (agent_idx, class_idx, class))
+                    }),
+            )
+        })
+        .flatten()
+}
+
+/// Settings of the class with the settings of its bases applied, nearest base last.
+fn resolve(
+    class: &Table,
+    bases: &[(String, Table)],
+    chain: &mut Vec<String>,
+) -> Result<Table, String> {
+    let Some(inherits) = class.get(INHERITS) else {
+        return Ok(class.clone());
+    };
+    let Value::String(base_name) = inherits else {
+        return Err(format!("{} must be the name of a class", INHERITS));
+    };
+    if chain.contains(base_name) {
+        chain.push(base_name.clone());
+        return Err(format!("Cyclic inheritance {}", chain.join(" -> ")));
+    }
+    chain.push(base_name.clone());
+    let Some((_, base)) = bases.iter().find(|(name, _)| name == base_name) else {
+        return Err(format!("Base class {} is not defined", base_name));
+    };
+    let mut resolved = resolve(base, bases, chain)?;
+    resolved.remove(NAME);
+    resolved.remove(INHERITS);
+    for (key, value) in class.iter() {
+        resolved.insert(key.clone(), value.clone());
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,5 @@
+pub mod base;
+pub mod builder;
+pub mod classes;
+mod logger;
+pub mod population;
@@ -5,7 +5,11 @@ use log4rs::config::runtime::ConfigErrors;
 use log4rs::config::{Appender, Config, Root};
 use log4rs::encode::pattern::PatternEncoder;
 use std::fs;
+use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_log::AsTrace;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 pub fn setup_logging(log_level: &str, log_file_path: PathBuf) -> Result<Config, ConfigErrors> {
     let log_level = get_logging_level(log_level);
@@ -43,12 +47,32 @@ pub fn initiate_logger(config_path: &Path, log_settings: &LogSettings) {
     }
 
     let log_file_path = log_path.join(log_settings.log_file_name);
-    if log_file_path.exists() {
+    if log_settings.log_overwrite && log_file_path.exists() {
         fs::remove_file(&log_file_path)
             .unwrap_or_else(|_| panic!("Error while clearing the log file"));
     }
 
-    let logger_config = match setup_logging(&log_level, log_file_path) {
+    let subscriber = log_settings
+        .subscriber
+        .unwrap_or_else(|| "file".to_string())
+        .to_lowercase();
+    let span_events = match log_settings.span_events.unwrap_or_default() {
+        true => FmtSpan::CLOSE,
+        false => FmtSpan::NONE,
+    };
+    match subscriber.as_str() {
+        "file" => initiate_file_logger(&log_level, log_file_path),
+        "pretty" => initiate_pretty_logger(&log_level, log_file_path, span_events),
+        "json" => initiate_json_logger(&log_level, log_file_path, span_events),
+        _ => panic!(
+            "Unsupported log subscriber {}, use file, pretty or json.",
+            subscriber
+        ),
+    }
+}
+
+fn initiate_file_logger(log_level: &str, log_file_path: PathBuf) {
+    let logger_config = match setup_logging(log_level, log_file_path) {
         Ok(logger_config) => logger_config,
         Err(e) => {
             panic!("Error while configuring the logger: {}", e);
@@ -62,3 +86,49 @@ pub fn initiate_logger(config_path: &Path, log_settings: &LogSettings) {
         }
     };
 }
+
+/// Writes the logs and the spans to the log file in a human readable form. The log calls of all
+/// the crates are forwarded to the subscriber as events.
+fn initiate_pretty_logger(log_level: &str, log_file_path: PathBuf, span_events: FmtSpan) {
+    match tracing_subscriber::fmt()
+        .pretty()
+        .with_ansi(false)
+        .with_max_level(get_logging_level(log_level).as_trace())
+        .with_span_events(span_events)
+        .with_writer(Mutex::new(open_log_file(log_file_path)))
+        .try_init()
+    {
+        Ok(_) => {}
+        Err(e) => {
+            panic!("Error while initializing the pretty subscriber: {}", e);
+        }
+    };
+}
+
+/// Writes the logs and the spans to the log file as one JSON object per line, with the spans
+/// the event happened in.
+fn initiate_json_logger(log_level: &str, log_file_path: PathBuf, span_events: FmtSpan) {
+    match tracing_subscriber::fmt()
+        .json()
+        .with_ansi(false)
+        .with_max_level(get_logging_level(log_level).as_trace())
+        .with_span_events(span_events)
+        .with_writer(Mutex::new(open_log_file(log_file_path)))
+        .try_init()
+    {
+        Ok(_) => {}
+        Err(e) => {
+            panic!("Error while initializing the JSON subscriber: {}", e);
+        }
+    };
+}
+
+/// Opens the log file to append to, as the file of the previous run is already cleared when the
+/// logs must not be appended.
+fn open_log_file(log_file_path: PathBuf) -> File {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file_path)
+        .expect("Error while opening the log file")
+}
@@ -1,28 +1,81 @@
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-mod base;
-mod builder;
-mod logger;
-
 use clap::Parser;
-use disolv_core::runner::run_simulation;
+use disolv_control::start_control_server;
+use disolv_core::control::Controller;
+use disolv_core::runner::{run_controlled_simulation, run_for_kpis, run_simulation};
+use disolv_core::scheduler::Scheduler;
+use disolv_models::bucket::digest::DigestMode;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 
-use builder::SimulationBuilder;
+use disolv::builder::SimulationBuilder;
+use disolv::population::PopulationMode;
 
 #[derive(Parser, Debug)]
 #[command(author, version, long_about = None)]
 struct CliArgs {
     #[arg(short = 'c', long, value_name = "CONFIG_FILE")]
     config: String,
+    #[arg(short = 'p', long, value_name = "CONTROL_PORT")]
+    control_port: Option<u16>,
+    #[arg(long, value_name = "CONTROL_ADDRESS", default_value = "127.0.0.1")]
+    control_address: IpAddr,
+    #[arg(short = 'r', long, value_name = "REGION")]
+    region: Option<u32>,
+    #[arg(long, value_name = "DIGEST_FILE", conflicts_with = "verify_digest")]
+    record_digest: Option<PathBuf>,
+    #[arg(long, value_name = "DIGEST_FILE")]
+    verify_digest: Option<PathBuf>,
+    #[arg(long, conflicts_with = "control_port")]
+    fast: bool,
+    #[arg(
+        long,
+        value_name = "POPULATION_FILE",
+        conflicts_with = "import_population"
+    )]
+    export_population: Option<PathBuf>,
+    #[arg(long, value_name = "POPULATION_FILE")]
+    import_population: Option<PathBuf>,
 }
 
 fn main() {
     let args = CliArgs::parse();
     let start = std::time::Instant::now();
     let mut builder = SimulationBuilder::new(&args.config);
+    if let Some(region) = args.region {
+        builder = builder.with_region(region);
+    }
+    if let Some(digest_file) = args.record_digest {
+        builder = builder.with_digest(DigestMode::Record(digest_file));
+    }
+    if let Some(digest_file) = args.verify_digest {
+        builder = builder.with_digest(DigestMode::Verify(digest_file));
+    }
+    if let Some(population_file) = args.export_population {
+        builder = builder.with_population(PopulationMode::Export(population_file));
+    }
+    if let Some(population_file) = args.import_population {
+        builder = builder.with_population(PopulationMode::Import(population_file));
+    }
+    if args.fast {
+        builder = builder.with_fast_mode();
+    }
     let scheduler = builder.build_with_map();
-    run_simulation(scheduler, builder.metadata());
+    match args.control_port {
+        None if args.fast => {
+            for (name, value) in run_for_kpis(scheduler) {
+                println!("{} = {}", name, value);
+            }
+        }
+        Some(port) => {
+            let (controller, handle) = Controller::new(scheduler.duration().as_u64());
+            start_control_server(handle, SocketAddr::new(args.control_address, port));
+            run_controlled_simulation(scheduler, builder.metadata(), controller);
+        }
+        None => run_simulation(scheduler, builder.metadata()),
+    }
     let elapsed = start.elapsed();
     println!("Simulation finished in {} ms.", elapsed.as_millis());
 }
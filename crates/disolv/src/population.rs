@@ -0,0 +1,141 @@
+use disolv_core::agent::{AgentId, AgentOrder};
+use disolv_core::hashbrown::HashMap;
+use disolv_models::device::types::{DeviceClass, DeviceInfo, DeviceType};
+use disolv_models::net::operator::OperatorId;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Whether the population built in the run is written to the file, or the population in the
+/// file is built instead of assigning the classes by their shares.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PopulationMode {
+    Export(PathBuf),
+    Import(PathBuf),
+}
+
+/// An agent of the population with the seeds of its random streams. The seeds are written in
+/// hexadecimal since TOML integers cannot hold all of them.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentRecord {
+    pub agent_id: AgentId,
+    pub agent_type: DeviceType,
+    pub agent_class: DeviceClass,
+    pub agent_order: AgentOrder,
+    pub operator: Option<OperatorId>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub seeds: BTreeMap<String, String>,
+}
+
+impl AgentRecord {
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo::builder()
+            .id(self.agent_id)
+            .device_type(self.agent_type)
+            .device_class(self.agent_class)
+            .agent_order(self.agent_order)
+            .operator(self.operator)
+            .build()
+    }
+}
+
+/// Agents built in a run. Later runs that import the population keep the classes of the
+/// agents and their random draws when they only change e.g. the network settings or the seed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Population {
+    agents: Vec<AgentRecord>,
+    #[serde(skip)]
+    index: HashMap<AgentId, usize>,
+}
+
+impl Population {
+    pub fn read(population_file: &Path) -> Self {
+        let content = match std::fs::read_to_string(population_file) {
+            Ok(content) => content,
+            Err(e) => panic!(
+                "Failed to read the population file {}: {}",
+                population_file.display(),
+                e
+            ),
+        };
+        let mut population: Population = match toml::from_str(&content) {
+            Ok(population) => population,
+            Err(e) => panic!(
+                "Invalid population file {}: {}",
+                population_file.display(),
+                e
+            ),
+        };
+        population.reindex();
+        info!(
+            "Read {} agents from the population file {}",
+            population.agents.len(),
+            population_file.display()
+        );
+        population
+    }
+
+    pub fn write(&mut self, population_file: &Path) {
+        self.agents.sort_by_key(|record| record.agent_id);
+        self.reindex();
+        let content = toml::to_string(&*self).expect("Failed to serialize the population");
+        if let Err(e) = std::fs::write(population_file, content) {
+            panic!(
+                "Failed to write the population file {}: {}",
+                population_file.display(),
+                e
+            );
+        }
+        info!(
+            "Wrote {} agents to the population file {}",
+            self.agents.len(),
+            population_file.display()
+        );
+    }
+
+    pub fn add(&mut self, device_info: &DeviceInfo, seeds: BTreeMap<String, u64>) {
+        self.index.insert(device_info.id, self.agents.len());
+        self.agents.push(AgentRecord {
+            agent_id: device_info.id,
+            agent_type: device_info.device_type,
+            agent_class: device_info.device_class,
+            agent_order: device_info.agent_order,
+            operator: device_info.operator,
+            seeds: seeds
+                .into_iter()
+                .map(|(stream, seed)| (stream, format!("{:016x}", seed)))
+                .collect(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    pub fn record_of(&self, agent_id: AgentId) -> Option<&AgentRecord> {
+        self.index.get(&agent_id).map(|idx| &self.agents[*idx])
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .agents
+            .iter()
+            .enumerate()
+            .map(|(idx, record)| (record.agent_id, idx))
+            .collect();
+    }
+
+    pub fn seed_of(&self, agent_id: AgentId, stream: &str) -> Option<u64> {
+        let seed = self.record_of(agent_id)?.seeds.get(stream)?;
+        match u64::from_str_radix(seed, 16) {
+            Ok(seed) => Some(seed),
+            Err(_) => panic!("Invalid seed {} of agent {}.", seed, agent_id),
+        }
+    }
+}